@@ -0,0 +1,122 @@
+#ifndef HERGMES_H
+#define HERGMES_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum HergmesStatus {
+  HERGMES_STATUS_OK = 0,
+  HERGMES_STATUS_NULL_POINTER = 1,
+  HERGMES_STATUS_INVALID_UTF8 = 2,
+  HERGMES_STATUS_BUFFER_TOO_SMALL = 3,
+  /**
+   * Unknown network or address type passed by the caller.
+   */
+  HERGMES_STATUS_INVALID_ARGUMENT = 4,
+  HERGMES_STATUS_INVALID_BASE58 = 10,
+  HERGMES_STATUS_TOO_SHORT = 11,
+  HERGMES_STATUS_CHECKSUM_MISMATCH = 12,
+  HERGMES_STATUS_UNKNOWN_NETWORK = 13,
+  HERGMES_STATUS_UNKNOWN_TYPE = 14,
+  HERGMES_STATUS_INVALID_CONTENT = 15,
+} HergmesStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Checks that `address` is a well-formed Ergo address with a valid checksum.
+ *
+ * # Safety
+ *
+ * `address` must be a NUL-terminated string.
+ */
+enum HergmesStatus hergmes_address_validate(const char *address);
+
+/**
+ * Decodes `address` into its network prefix (0x00 mainnet, 0x10 testnet), type (1 P2PK,
+ * 2 P2SH, 3 P2S) and content: the public key, script hash or ErgoTree.
+ *
+ * # Safety
+ *
+ * `address` must be a NUL-terminated string, `network`, `address_type` and `content_len`
+ * valid pointers, and `content` valid for `content_cap` bytes.
+ */
+enum HergmesStatus hergmes_address_decode(const char *address,
+                                          uint8_t *network,
+                                          uint8_t *address_type,
+                                          uint8_t *content,
+                                          size_t content_cap,
+                                          size_t *content_len);
+
+/**
+ * Encodes an address from its parts into `out` as a NUL-terminated string. `out_len` receives
+ * the length without the terminator.
+ *
+ * # Safety
+ *
+ * `content` must be valid for `content_len` bytes, `out` for `out_cap` bytes and `out_len` a
+ * valid pointer.
+ */
+enum HergmesStatus hergmes_address_encode(uint8_t network,
+                                          uint8_t address_type,
+                                          const uint8_t *content,
+                                          size_t content_len,
+                                          char *out,
+                                          size_t out_cap,
+                                          size_t *out_len);
+
+/**
+ * Encodes the address of an ErgoTree, P2PK for `ProveDlog` trees and P2S otherwise, into
+ * `out` as a NUL-terminated string.
+ *
+ * # Safety
+ *
+ * `tree` must be valid for `tree_len` bytes, `out` for `out_cap` bytes and `out_len` a valid
+ * pointer.
+ */
+enum HergmesStatus hergmes_address_from_ergo_tree(uint8_t network,
+                                                  const uint8_t *tree,
+                                                  size_t tree_len,
+                                                  char *out,
+                                                  size_t out_cap,
+                                                  size_t *out_len);
+
+/**
+ * Base58-encodes `bytes` into `out` as a NUL-terminated string.
+ *
+ * # Safety
+ *
+ * `bytes` must be valid for `len` bytes, `out` for `out_cap` bytes and `out_len` a valid
+ * pointer.
+ */
+enum HergmesStatus hergmes_base58_encode(const uint8_t *bytes,
+                                         size_t len,
+                                         char *out,
+                                         size_t out_cap,
+                                         size_t *out_len);
+
+/**
+ * Decodes a base58 string into `out`.
+ *
+ * # Safety
+ *
+ * `encoded` must be a NUL-terminated string, `out` valid for `out_cap` bytes and `out_len` a
+ * valid pointer.
+ */
+enum HergmesStatus hergmes_base58_decode(const char *encoded,
+                                         uint8_t *out,
+                                         size_t out_cap,
+                                         size_t *out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HERGMES_H */
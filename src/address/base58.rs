@@ -0,0 +1,158 @@
+//! Base58 with the Bitcoin alphabet, as used by Ergo addresses and extended keys.
+
+use super::AddressError;
+
+pub(super) const ALPHABET: &[u8; 58] =
+    b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+const INVALID: u8 = 0xff;
+
+static DECODE_MAP: [u8; 128] = {
+    let mut map = [INVALID; 128];
+    let mut i = 0;
+    while i < ALPHABET.len() {
+        map[ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    map
+};
+
+/// Whether `c` is a character of the alphabet.
+pub(super) fn is_base58(c: u8) -> bool {
+    DECODE_MAP.get(c as usize).is_some_and(|d| *d != INVALID)
+}
+
+/// Digits that fit in this stack buffer are encoded without a heap allocation; it covers every
+/// P2PK and P2SH address and payloads of up to 92 bytes.
+const STACK_DIGITS: usize = 128;
+
+/// Digits decoded with one pass over the bytes decoded so far: 58^5 fits in a `u32`.
+const CHUNK_DIGITS: u32 = 5;
+
+/// Upper bound of the encoded length of `len` bytes, to presize output buffers.
+pub const fn max_encoded_len(len: usize) -> usize {
+    len * 138 / 100 + 1
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    encode_into(&[bytes], &mut encoded);
+    encoded
+}
+
+/// Appends the encoding of the concatenation of `parts` to `out`, without copying the parts
+/// into one buffer first.
+pub fn encode_into(parts: &[&[u8]], out: &mut String) {
+    let len: usize = parts.iter().map(|p| p.len()).sum();
+    let mut bytes = parts.iter().flat_map(|p| p.iter().copied()).peekable();
+    let mut zeros = 0;
+    while bytes.next_if_eq(&0).is_some() {
+        zeros += 1;
+    }
+
+    let mut stack = [0u8; STACK_DIGITS];
+    let mut heap = Vec::new();
+    let buf = match max_encoded_len(len) {
+        n if n <= STACK_DIGITS => &mut stack[..n],
+        n => {
+            heap.resize(n, 0);
+            &mut heap[..]
+        }
+    };
+
+    // Little-endian base58 digits
+    let mut n = 0;
+    for byte in bytes {
+        let mut carry = byte as u32;
+        for digit in buf[..n].iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            buf[n] = (carry % 58) as u8;
+            n += 1;
+            carry /= 58;
+        }
+    }
+
+    out.reserve(zeros + n);
+    out.extend(std::iter::repeat_n('1', zeros));
+    out.extend(buf[..n].iter().rev().map(|d| ALPHABET[*d as usize] as char));
+}
+
+pub fn decode(s: &str) -> Result<Vec<u8>, AddressError> {
+    let mut decoded = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+    decode_into(s, &mut decoded)?;
+    Ok(decoded)
+}
+
+/// Appends the decoded bytes of `s` to `out`. On error `out` is left as it was.
+pub fn decode_into(s: &str, out: &mut Vec<u8>) -> Result<(), AddressError> {
+    decode_with(s, out, |c| {
+        DECODE_MAP
+            .get(c as usize)
+            .copied()
+            .filter(|v| *v != INVALID)
+    })
+}
+
+/// Decodes `s` without checking its characters, for strings already known to be base58, e.g.
+/// addresses read back from storage.
+///
+/// # Safety
+///
+/// Every character of `s` must be in the base58 alphabet.
+pub unsafe fn decode_unsafe(s: &str) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+    // SAFETY: characters of the alphabet are ASCII, so they index into `DECODE_MAP`.
+    let _ =
+        decode_with(s, &mut decoded, |c| Some(unsafe { *DECODE_MAP.get_unchecked(c as usize) }));
+    decoded
+}
+
+fn decode_with(
+    s: &str,
+    out: &mut Vec<u8>,
+    digit: impl Fn(u8) -> Option<u8>,
+) -> Result<(), AddressError> {
+    let start = out.len();
+    let zeros = s.bytes().take_while(|c| *c == b'1').count();
+    out.resize(start + zeros, 0);
+
+    // Little-endian bytes, after the leading zeros
+    let digits = start + zeros;
+    let (mut chunk, mut scale) = (0u64, 1u64);
+    for c in s.bytes().skip(zeros) {
+        let Some(value) = digit(c) else {
+            out.truncate(start);
+            return Err(AddressError::InvalidBase58);
+        };
+        chunk = chunk * 58 + value as u64;
+        scale *= 58;
+        if scale == 58u64.pow(CHUNK_DIGITS) {
+            mul_add(out, digits, scale, chunk);
+            (chunk, scale) = (0, 1);
+        }
+    }
+    if scale > 1 {
+        mul_add(out, digits, scale, chunk);
+    }
+
+    out[digits..].reverse();
+    Ok(())
+}
+
+/// Multiplies the little-endian number in `out[from..]` by `scale` and adds `chunk`.
+fn mul_add(out: &mut Vec<u8>, from: usize, scale: u64, chunk: u64) {
+    let mut carry = chunk;
+    for byte in out[from..].iter_mut() {
+        carry += *byte as u64 * scale;
+        *byte = carry as u8;
+        carry >>= 8;
+    }
+    while carry > 0 {
+        out.push(carry as u8);
+        carry >>= 8;
+    }
+}
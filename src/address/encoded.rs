@@ -0,0 +1,103 @@
+use std::{cmp::Ordering, fmt, hash, ops::Deref, str::FromStr};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{AddressError, ErgoAddress};
+
+/// An address that keeps its base58 form once encoded, for addresses displayed or serialized
+/// over and over, e.g. in snapshots and API responses. Encoding is deferred to the first use.
+#[derive(Debug, Clone)]
+pub struct EncodedAddress {
+    address: ErgoAddress,
+    encoded: OnceCell<Box<str>>,
+}
+
+impl EncodedAddress {
+    pub fn address(&self) -> &ErgoAddress {
+        &self.address
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.encoded.get_or_init(|| self.address.encode().into())
+    }
+
+    pub fn into_inner(self) -> ErgoAddress {
+        self.address
+    }
+}
+
+impl From<ErgoAddress> for EncodedAddress {
+    fn from(address: ErgoAddress) -> Self {
+        Self { address, encoded: OnceCell::new() }
+    }
+}
+
+impl Deref for EncodedAddress {
+    type Target = ErgoAddress;
+
+    fn deref(&self) -> &ErgoAddress {
+        &self.address
+    }
+}
+
+impl PartialEq for EncodedAddress {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl Eq for EncodedAddress {}
+
+impl PartialEq<ErgoAddress> for EncodedAddress {
+    fn eq(&self, other: &ErgoAddress) -> bool {
+        self.address == *other
+    }
+}
+
+impl PartialOrd for EncodedAddress {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EncodedAddress {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.address.cmp(&other.address)
+    }
+}
+
+impl hash::Hash for EncodedAddress {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+    }
+}
+
+impl fmt::Display for EncodedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EncodedAddress {
+    type Err = AddressError;
+
+    /// Keeps `s` as the encoded form, since base58 has a single encoding per payload.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let address = s.parse()?;
+        Ok(Self { address, encoded: OnceCell::with_value(s.into()) })
+    }
+}
+
+impl Serialize for EncodedAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for EncodedAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,254 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    hash::{Blake2b256Hasher, P2SH_HASH_LEN, p2sh_hash},
+    types::HexBytes,
+};
+
+pub mod base58;
+mod encoded;
+#[cfg(feature = "qr")]
+pub mod qr;
+mod set;
+pub mod typo;
+
+pub use encoded::EncodedAddress;
+pub use set::AddressSet;
+
+const CHECKSUM_LEN: usize = 4;
+const P2PK_TREE_PREFIX: [u8; 3] = [0x00, 0x08, 0xcd];
+const PUBLIC_KEY_LEN: usize = 33;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AddressError {
+    #[error("Address is not valid base58.")]
+    InvalidBase58,
+
+    #[error("Address is too short.")]
+    TooShort,
+
+    #[error("Address checksum mismatch.")]
+    ChecksumMismatch,
+
+    #[error("Unknown network prefix {0:#04x}.")]
+    UnknownNetwork(u8),
+
+    #[error("Unknown address type {0}.")]
+    UnknownType(u8),
+
+    #[error("Invalid {0:?} address content.")]
+    InvalidContent(AddressType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum NetworkPrefix {
+    Mainnet = 0x00,
+    Testnet = 0x10,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum AddressType {
+    P2PK = 1,
+    P2SH = 2,
+    P2S = 3,
+}
+
+/// An Ergo address: network and type prefix, type-specific content and a blake2b256 checksum.
+///
+/// Addresses are ordered by network, then type, then content bytes. For the fixed-length
+/// content of P2PK and P2SH addresses of one network this is the order of their encoded forms,
+/// but not for P2S addresses whose content differs in length.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErgoAddress {
+    network: NetworkPrefix,
+    kind: AddressType,
+    content: Vec<u8>,
+}
+
+impl ErgoAddress {
+    /// Pay-to-public-key address of a compressed secp256k1 public key.
+    pub fn p2pk(network: NetworkPrefix, public_key: &[u8; PUBLIC_KEY_LEN]) -> Self {
+        Self { network, kind: AddressType::P2PK, content: public_key.to_vec() }
+    }
+
+    /// Address from its parts, checking the content length expected by its type.
+    pub fn new(
+        network: NetworkPrefix,
+        kind: AddressType,
+        content: Vec<u8>,
+    ) -> Result<Self, AddressError> {
+        let valid = match kind {
+            AddressType::P2PK => content.len() == PUBLIC_KEY_LEN,
+            AddressType::P2SH => content.len() == P2SH_HASH_LEN,
+            AddressType::P2S => !content.is_empty(),
+        };
+        if !valid {
+            return Err(AddressError::InvalidContent(kind));
+        }
+        Ok(Self { network, kind, content })
+    }
+
+    /// Pay-to-script-hash address of an ErgoTree, carrying only the hash of the script.
+    pub fn p2sh(network: NetworkPrefix, ergo_tree: &[u8]) -> Self {
+        Self { network, kind: AddressType::P2SH, content: p2sh_hash(ergo_tree).to_vec() }
+    }
+
+    /// Address of an ErgoTree: P2PK for `ProveDlog` trees, P2S otherwise.
+    pub fn from_ergo_tree(network: NetworkPrefix, tree: &[u8]) -> Self {
+        match tree.strip_prefix(&P2PK_TREE_PREFIX[..]) {
+            Some(public_key) if public_key.len() == PUBLIC_KEY_LEN => {
+                Self { network, kind: AddressType::P2PK, content: public_key.to_vec() }
+            }
+            _ => Self { network, kind: AddressType::P2S, content: tree.to_vec() },
+        }
+    }
+
+    pub fn network(&self) -> NetworkPrefix {
+        self.network
+    }
+
+    pub fn kind(&self) -> AddressType {
+        self.kind
+    }
+
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// The ErgoTree guarding boxes sent to this address. `None` for P2SH addresses, which only
+    /// carry a hash of the script.
+    pub fn ergo_tree(&self) -> Option<HexBytes> {
+        match self.kind {
+            AddressType::P2PK => Some(HexBytes([&P2PK_TREE_PREFIX[..], &self.content].concat())),
+            AddressType::P2S => Some(HexBytes(self.content.clone())),
+            AddressType::P2SH => None,
+        }
+    }
+
+    /// The canonical form of the address: a P2S address of a `ProveDlog` tree becomes the
+    /// P2PK address of its key, as [`from_ergo_tree`](Self::from_ergo_tree) would decode it.
+    /// Re-encoding the result gives the canonical string.
+    pub fn normalize(&self) -> Self {
+        let (kind, content) = self.canonical();
+        Self { network: self.network, kind, content: content.to_vec() }
+    }
+
+    /// Whether both addresses guard boxes with the same script, whatever their network and
+    /// encoding.
+    pub fn eq_ignoring_network(&self, other: &ErgoAddress) -> bool {
+        self.canonical() == other.canonical()
+    }
+
+    fn canonical(&self) -> (AddressType, &[u8]) {
+        match self.kind {
+            AddressType::P2S => match self.content.strip_prefix(&P2PK_TREE_PREFIX[..]) {
+                Some(public_key) if public_key.len() == PUBLIC_KEY_LEN => {
+                    (AddressType::P2PK, public_key)
+                }
+                _ => (AddressType::P2S, &self.content),
+            },
+            kind => (kind, &self.content),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut encoded = String::with_capacity(self.encoded_len());
+        self.encode_into(&mut encoded);
+        encoded
+    }
+
+    /// Appends the encoded address to `out`, so callers rendering many addresses can reuse one
+    /// buffer.
+    pub fn encode_into(&self, out: &mut String) {
+        let head = self.network as u8 + self.kind as u8;
+        let checksum = checksum(&mut Blake2b256Hasher::new(), head, &self.content);
+        base58::encode_into(&[&[head], &self.content, &checksum], out);
+    }
+
+    /// Upper bound of the encoded address length.
+    pub fn encoded_len(&self) -> usize {
+        base58::max_encoded_len(1 + self.content.len() + CHECKSUM_LEN)
+    }
+}
+
+/// Checksum of `head || body`, hashed incrementally so the two never need to be copied into
+/// one buffer.
+fn checksum(hasher: &mut Blake2b256Hasher, head: u8, body: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = hasher.update(&[head]).update(body).finalize_reset();
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+impl fmt::Display for ErgoAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for ErgoAddress {
+    type Err = AddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::decode_with(&mut Blake2b256Hasher::new(), s)
+    }
+}
+
+impl ErgoAddress {
+    /// Decodes addresses in bulk, reusing one checksum hasher per worker. With the `rayon`
+    /// feature the work is spread over the rayon thread pool.
+    pub fn decode_batch<S: AsRef<str> + Sync>(encoded: &[S]) -> Vec<Result<Self, AddressError>> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+
+            encoded
+                .par_iter()
+                .map_init(Blake2b256Hasher::new, |hasher, s| Self::decode_with(hasher, s.as_ref()))
+                .collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let mut hasher = Blake2b256Hasher::new();
+            encoded
+                .iter()
+                .map(|s| Self::decode_with(&mut hasher, s.as_ref()))
+                .collect()
+        }
+    }
+
+    fn decode_with(hasher: &mut Blake2b256Hasher, s: &str) -> Result<Self, AddressError> {
+        if !s.bytes().all(base58::is_base58) {
+            return Err(AddressError::InvalidBase58);
+        }
+        // SAFETY: every character was checked to be in the alphabet.
+        let mut bytes = unsafe { base58::decode_unsafe(s) };
+        if bytes.len() <= 1 + CHECKSUM_LEN {
+            return Err(AddressError::TooShort);
+        }
+
+        let body_end = bytes.len() - CHECKSUM_LEN;
+        if checksum(hasher, bytes[0], &bytes[1..body_end]) != bytes[body_end..] {
+            return Err(AddressError::ChecksumMismatch);
+        }
+
+        let prefix = bytes[0];
+        let network = match prefix & 0xf0 {
+            0x00 => NetworkPrefix::Mainnet,
+            0x10 => NetworkPrefix::Testnet,
+            _ => return Err(AddressError::UnknownNetwork(prefix & 0xf0)),
+        };
+        let kind = match prefix & 0x0f {
+            1 => AddressType::P2PK,
+            2 => AddressType::P2SH,
+            3 => AddressType::P2S,
+            other => return Err(AddressError::UnknownType(other)),
+        };
+
+        // The decoded buffer becomes the content, without reallocating.
+        bytes.truncate(body_end);
+        bytes.remove(0);
+        Self::new(network, kind, bytes)
+    }
+}
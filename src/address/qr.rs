@@ -0,0 +1,81 @@
+use std::{fmt, io::Cursor};
+
+use image::{ImageFormat, Luma};
+use qrcode::{
+    EcLevel, QrCode,
+    render::{svg, unicode::Dense1x2},
+};
+
+use super::ErgoAddress;
+
+/// Smallest side of rendered images, in pixels.
+const MIN_SIZE: u32 = 256;
+
+#[derive(Debug, thiserror::Error)]
+pub enum QrError {
+    /// The content doesn't fit a QR code, e.g. the address of a large P2S script.
+    #[error("Cannot encode QR code: {0}")]
+    Encode(#[from] qrcode::types::QrError),
+
+    #[error("Cannot render PNG: {0}")]
+    Png(#[from] image::ImageError),
+}
+
+/// A QR code of an address or payment URI, for wallets to scan.
+#[derive(Clone)]
+pub struct AddressQr {
+    code: QrCode,
+}
+
+impl fmt::Debug for AddressQr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AddressQr")
+            .field("width", &self.width())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AddressQr {
+    pub fn new(address: &ErgoAddress) -> Result<Self, QrError> {
+        Self::from_text(&address.encode())
+    }
+
+    /// QR code of arbitrary text, e.g. a payment URI.
+    pub fn from_text(text: &str) -> Result<Self, QrError> {
+        Ok(Self { code: QrCode::with_error_correction_level(text, EcLevel::M)? })
+    }
+
+    /// Modules per side, without the quiet zone.
+    pub fn width(&self) -> usize {
+        self.code.width()
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.code
+            .render::<svg::Color<'_>>()
+            .min_dimensions(MIN_SIZE, MIN_SIZE)
+            .build()
+    }
+
+    /// PNG image, black on white.
+    pub fn to_png(&self) -> Result<Vec<u8>, QrError> {
+        let image = self
+            .code
+            .render::<Luma<u8>>()
+            .min_dimensions(MIN_SIZE, MIN_SIZE)
+            .build();
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png)?;
+        Ok(png.into_inner())
+    }
+
+    /// Unicode half blocks, two modules per character, light on dark so that it scans on
+    /// dark terminals.
+    pub fn to_terminal(&self) -> String {
+        self.code
+            .render::<Dense1x2>()
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark)
+            .build()
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::{BTreeSet, HashSet};
+
+use super::{AddressType, ErgoAddress};
+use crate::{
+    hash::{P2SH_HASH_LEN, blake2b256},
+    types::ergo::UTxO,
+};
+
+/// Addresses matched against box trees by blake2b256, so a membership test hashes the tree
+/// once and never compares trees, whatever the number of addresses. Since P2SH addresses
+/// carry a prefix of the same hash, they are matched by the same lookup.
+///
+/// Trees carry no network, so neither do the keys: an address and its counterpart on the
+/// other network are the same member.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressSet {
+    trees: HashSet<[u8; 32]>,
+    script_hashes: HashSet<[u8; P2SH_HASH_LEN]>,
+    /// In canonical form, for listing.
+    addresses: BTreeSet<ErgoAddress>,
+}
+
+impl AddressSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `address`, returning whether it wasn't a member yet.
+    pub fn insert(&mut self, address: &ErgoAddress) -> bool {
+        let address = address.normalize();
+        let added = match address.ergo_tree() {
+            Some(tree) => self.trees.insert(blake2b256(&tree.0)),
+            None => self.script_hashes.insert(script_hash(address.content())),
+        };
+        if added {
+            self.addresses.insert(address);
+        }
+        added
+    }
+
+    /// Whether `address` or its counterpart on the other network was inserted. A P2SH address
+    /// and the P2S address of its script are different members.
+    pub fn contains(&self, address: &ErgoAddress) -> bool {
+        match address.kind() {
+            AddressType::P2SH => self.script_hashes.contains(&script_hash(address.content())),
+            _ => address
+                .ergo_tree()
+                .is_some_and(|tree| self.trees.contains(&blake2b256(&tree.0))),
+        }
+    }
+
+    /// Whether boxes guarded by `tree` are sent to a member, including P2SH members by the
+    /// hash of the tree.
+    pub fn contains_tree(&self, tree: &[u8]) -> bool {
+        let hash = blake2b256(tree);
+        self.trees.contains(&hash)
+            || (!self.script_hashes.is_empty()
+                && self
+                    .script_hashes
+                    .contains(&script_hash(&hash[..P2SH_HASH_LEN])))
+    }
+
+    /// Whether `utxo` is sent to a member.
+    pub fn matches(&self, utxo: &UTxO) -> bool {
+        self.contains_tree(&utxo.ergo_tree.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Members in canonical form and address order.
+    pub fn iter(&self) -> impl Iterator<Item = &ErgoAddress> {
+        self.addresses.iter()
+    }
+}
+
+fn script_hash(content: &[u8]) -> [u8; P2SH_HASH_LEN] {
+    let mut hash = [0; P2SH_HASH_LEN];
+    hash.copy_from_slice(content);
+    hash
+}
+
+impl<'a> Extend<&'a ErgoAddress> for AddressSet {
+    fn extend<I: IntoIterator<Item = &'a ErgoAddress>>(&mut self, addresses: I) {
+        for address in addresses {
+            self.insert(address);
+        }
+    }
+}
+
+impl Extend<ErgoAddress> for AddressSet {
+    fn extend<I: IntoIterator<Item = ErgoAddress>>(&mut self, addresses: I) {
+        for address in addresses {
+            self.insert(&address);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a ErgoAddress> for AddressSet {
+    fn from_iter<I: IntoIterator<Item = &'a ErgoAddress>>(addresses: I) -> Self {
+        let mut set = Self::new();
+        set.extend(addresses);
+        set
+    }
+}
+
+impl FromIterator<ErgoAddress> for AddressSet {
+    fn from_iter<I: IntoIterator<Item = ErgoAddress>>(addresses: I) -> Self {
+        let mut set = Self::new();
+        set.extend(addresses);
+        set
+    }
+}
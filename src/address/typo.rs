@@ -0,0 +1,116 @@
+//! Detection of single-character typos in addresses. The 4-byte checksum makes a random edit
+//! pass with a chance of 1 in 2^32, so an edit that does pass almost certainly undoes the
+//! typo. Suggestions are only reported, never applied: funds must go where the user wrote.
+
+use std::fmt;
+
+use super::{AddressError, ErgoAddress, base58};
+use crate::hash::Blake2b256Hasher;
+
+/// Longest input searched for typos. Covers P2PK and P2SH addresses and small scripts; every
+/// candidate is decoded, so searching long P2S addresses would be quadratic in their length.
+pub const MAX_TYPO_SEARCH_LEN: usize = 128;
+
+/// How the suggested address differs from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypoFix {
+    /// The character at `position` was `found` instead of `expected`.
+    Substitution { found: char, expected: char },
+    /// The characters at `position` and the next one were swapped.
+    Transposition,
+}
+
+/// A valid address one typo away from an invalid input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypoSuggestion {
+    pub address: ErgoAddress,
+    /// Index of the edited character, from 0.
+    pub position: usize,
+    pub fix: TypoFix,
+}
+
+impl fmt::Display for TypoSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Did you mean {}? ", self.address)?;
+        let position = self.position + 1;
+        match self.fix {
+            TypoFix::Substitution { found, expected } => {
+                write!(f, "(character {position} is `{found}`, expected `{expected}`)")
+            }
+            TypoFix::Transposition => {
+                write!(f, "(characters {position} and {} are swapped)", position + 1)
+            }
+        }
+    }
+}
+
+/// The valid address `input` most likely meant, when it fails its checksum or has a single
+/// character outside the base58 alphabet, and exactly one substitution or transposition of
+/// adjacent characters makes it valid. `None` for valid, ambiguous or too long inputs.
+pub fn suggest(input: &str) -> Option<TypoSuggestion> {
+    if !input.is_ascii() || input.len() > MAX_TYPO_SEARCH_LEN {
+        return None;
+    }
+    let invalid: Vec<usize> = input
+        .bytes()
+        .enumerate()
+        .filter(|(_, c)| !base58::is_base58(*c))
+        .map(|(i, _)| i)
+        .collect();
+    let mut hasher = Blake2b256Hasher::new();
+    match (ErgoAddress::decode_with(&mut hasher, input), invalid.as_slice()) {
+        (Err(AddressError::ChecksumMismatch), []) => {}
+        (Err(AddressError::InvalidBase58), [_]) => {}
+        _ => return None,
+    }
+
+    let mut candidate = input.as_bytes().to_vec();
+    let mut found = None;
+    let mut try_candidate = |candidate: &[u8], position: usize, fix: TypoFix| {
+        // Only ASCII bytes were swapped or replaced.
+        let candidate = std::str::from_utf8(candidate).expect("ASCII");
+        if let Ok(address) = ErgoAddress::decode_with(&mut hasher, candidate) {
+            let suggestion = TypoSuggestion { address, position, fix };
+            // A second valid edit makes the input ambiguous.
+            found = match found {
+                None => Some(Some(suggestion)),
+                Some(_) => Some(None),
+            };
+        }
+    };
+
+    let positions: Vec<usize> = match invalid.as_slice() {
+        [position] => vec![*position],
+        _ => (0..candidate.len()).collect(),
+    };
+    for position in positions {
+        let original = candidate[position];
+        for &expected in base58::ALPHABET.iter().filter(|c| **c != original) {
+            candidate[position] = expected;
+            let fix = TypoFix::Substitution { found: original as char, expected: expected as char };
+            try_candidate(&candidate, position, fix);
+        }
+        candidate[position] = original;
+    }
+    if invalid.is_empty() {
+        for position in 0..candidate.len().saturating_sub(1) {
+            if candidate[position] == candidate[position + 1] {
+                continue;
+            }
+            candidate.swap(position, position + 1);
+            try_candidate(&candidate, position, TypoFix::Transposition);
+            candidate.swap(position, position + 1);
+        }
+    }
+    found.flatten()
+}
+
+/// `error` for `input`, with a [suggestion](suggest) if there is one, for messages shown to
+/// whoever typed the address. A search decodes hundreds of candidates, so this is meant for the
+/// CLI; the server reports the plain error rather than spend that work on every bad request.
+pub fn describe_error(input: &str, error: &AddressError) -> String {
+    match suggest(input) {
+        Some(suggestion) => format!("{error} {suggestion}"),
+        None => error.to_string(),
+    }
+}
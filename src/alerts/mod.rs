@@ -0,0 +1,366 @@
+//! Threshold rules over the mempool, rolling aggregates, address balances and oracle pools,
+//! dispatched through a [`Notifier`] when they start or stop breaching.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::info;
+
+pub use notifier::{LogNotifier, Notifier, NotifyError, WebhookNotifier};
+
+use crate::{
+    address::{AddressError, ErgoAddress},
+    analytics::rolling::RollingReport,
+    chain::register::RegisterId,
+    clients::node::{BoxQuery, NodeClient, NodeError},
+    shutdown::ShutdownSignal,
+    tokens::TokenRegistry,
+    trace::ErrorLog,
+    types::{HashDigest, TimestampMillis},
+    watcher::MempoolSnapshot,
+};
+
+mod notifier;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum AlertError {
+    #[error("Invalid alert rules: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid address `{0}` in alert rule: {1}")]
+    Address(String, AddressError),
+}
+
+/// A named threshold, e.g. `{ "name": "busy", "kind": "mempoolSize", "max": 5000 }`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Condition {
+    /// More than `max` unconfirmed transactions.
+    MempoolSize { max: usize },
+    /// The 90th percentile fee of the rolling window above `max` nanoERG.
+    FeeP90 { max: u64 },
+    /// The confirmed balance of `address` moving by more than `max` nanoERG between two checks.
+    BalanceChange { address: String, max: u64 },
+    /// The confirmed balance of `token_id` held by `address` moving by more than `max`, in the
+    /// token's smallest unit, between two checks.
+    TokenBalanceChange { address: String, token_id: HashDigest, max: u64 },
+    /// The rate in R4 of the oracle pool box holding `pool_nft` moving by more than
+    /// `max_percent` between two checks.
+    OraclePriceDeviation { pool_nft: HashDigest, max_percent: f64 },
+}
+
+impl Condition {
+    fn threshold(&self) -> f64 {
+        match self {
+            Condition::MempoolSize { max } => *max as f64,
+            Condition::FeeP90 { max }
+            | Condition::BalanceChange { max, .. }
+            | Condition::TokenBalanceChange { max, .. } => *max as f64,
+            Condition::OraclePriceDeviation { max_percent, .. } => *max_percent,
+        }
+    }
+
+    fn metric(&self) -> String {
+        match self {
+            Condition::MempoolSize { .. } => "mempool size".to_string(),
+            Condition::FeeP90 { .. } => "p90 fee (nanoERG)".to_string(),
+            Condition::BalanceChange { address, .. } => {
+                format!("balance change of {address} (nanoERG)")
+            }
+            Condition::TokenBalanceChange { address, .. } => {
+                format!("token balance change of {address}")
+            }
+            Condition::OraclePriceDeviation { pool_nft, .. } => {
+                format!("rate deviation of oracle pool {pool_nft} (%)")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// A rule starting or stopping to breach its threshold.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub rule: String,
+    pub state: AlertState,
+    pub value: f64,
+    pub threshold: f64,
+    pub message: String,
+    pub at: TimestampMillis,
+}
+
+/// Values the rules are evaluated against, gathered by [`AlertEngine::observe`].
+#[derive(Debug, Clone, Default)]
+pub struct Observations {
+    pub at: TimestampMillis,
+    pub mempool_size: usize,
+    /// `None` without rolling aggregates or transactions in the window.
+    pub fee_p90: Option<u64>,
+    /// Confirmed nanoERG balances.
+    pub balances: HashMap<ErgoAddress, u64>,
+    /// Confirmed token balances, by address and token id.
+    pub token_balances: HashMap<(ErgoAddress, HashDigest), u64>,
+    /// Rates in R4 of oracle pool boxes, by pool NFT.
+    pub oracle_rates: HashMap<HashDigest, i64>,
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    address: Option<ErgoAddress>,
+    /// Last balance or oracle rate seen, for the rules comparing two checks.
+    previous: Option<f64>,
+    firing: bool,
+}
+
+/// Evaluates rules and reports their transitions: an alert fires once when a rule starts
+/// breaching and resolves once when it stops. Rules without a value, e.g. a fee percentile
+/// without transactions or the first check of a change, keep their state.
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<(AlertRule, RuleState)>,
+    /// Names and decimals of the tokens in alert messages.
+    tokens: Arc<TokenRegistry>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Result<Self, AlertError> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let mut state = RuleState::default();
+                if let Condition::BalanceChange { address, .. }
+                | Condition::TokenBalanceChange { address, .. } = &rule.condition
+                {
+                    let parsed = address
+                        .parse()
+                        .map_err(|e| AlertError::Address(address.clone(), e))?;
+                    state.address = Some(parsed);
+                }
+                Ok((rule, state))
+            })
+            .collect::<Result<_, AlertError>>()?;
+        Ok(Self { rules, tokens: Arc::default() })
+    }
+
+    /// Formats token amounts in alert messages with the metadata of `tokens`.
+    pub fn with_tokens(mut self, tokens: Arc<TokenRegistry>) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Parses a JSON array of [`AlertRule`]s.
+    pub fn from_json(json: &str) -> Result<Self, AlertError> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Whether a rule reads the rolling aggregates.
+    pub fn needs_rolling(&self) -> bool {
+        self.rules()
+            .any(|rule| matches!(rule.condition, Condition::FeeP90 { .. }))
+    }
+
+    /// Rules currently breaching.
+    pub fn firing(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules
+            .iter()
+            .filter(|(_, state)| state.firing)
+            .map(|(rule, _)| rule)
+    }
+
+    /// Gathers the values of the rules, fetching balances and oracle pool boxes from the node.
+    pub async fn observe(
+        &self,
+        node: &NodeClient,
+        mempool: &MempoolSnapshot,
+        rolling: Option<&RollingReport>,
+    ) -> Result<Observations, NodeError> {
+        let mut observations = Observations {
+            at: TimestampMillis::now(),
+            mempool_size: mempool.transactions.len(),
+            fee_p90: rolling.and_then(|r| r.p90_fee),
+            ..Default::default()
+        };
+        for (rule, state) in &self.rules {
+            match (&rule.condition, &state.address) {
+                (Condition::BalanceChange { .. }, Some(address))
+                    if !observations.balances.contains_key(address) =>
+                {
+                    let balance = node.get_balance(address).await?;
+                    observations
+                        .balances
+                        .insert(address.clone(), balance.confirmed.nano_ergs);
+                }
+                (Condition::TokenBalanceChange { token_id, .. }, Some(address))
+                    if !observations
+                        .token_balances
+                        .contains_key(&(address.clone(), token_id.clone())) =>
+                {
+                    let balance = node.get_balance(address).await?;
+                    self.tokens.remember_balances(&balance.confirmed.tokens);
+                    let amount = balance
+                        .confirmed
+                        .tokens
+                        .iter()
+                        .filter(|t| &t.token_id == token_id)
+                        .fold(0u64, |sum, t| sum.saturating_add(t.amount));
+                    observations
+                        .token_balances
+                        .insert((address.clone(), token_id.clone()), amount);
+                }
+                (Condition::OraclePriceDeviation { pool_nft, .. }, _)
+                    if !observations.oracle_rates.contains_key(pool_nft) =>
+                {
+                    let pool = node
+                        .get_unspent_boxes_by_token_id(pool_nft, &BoxQuery::new().limit(1))
+                        .await?;
+                    let rate = pool
+                        .items
+                        .first()
+                        .and_then(|b| b.utxo.register::<i64>(RegisterId::R4).ok());
+                    if let Some(rate) = rate {
+                        observations.oracle_rates.insert(pool_nft.clone(), rate);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(observations)
+    }
+
+    /// Evaluates every rule, returning the alerts of the ones that changed state.
+    pub fn evaluate(&mut self, observations: &Observations) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (rule, state) in &mut self.rules {
+            let value = match &rule.condition {
+                Condition::MempoolSize { .. } => Some(observations.mempool_size as f64),
+                Condition::FeeP90 { .. } => observations.fee_p90.map(|fee| fee as f64),
+                Condition::BalanceChange { .. } => state
+                    .address
+                    .as_ref()
+                    .and_then(|a| observations.balances.get(a))
+                    .and_then(|balance| {
+                        state.change(*balance as f64, |now, before| Some(now - before))
+                    }),
+                Condition::TokenBalanceChange { token_id, .. } => state
+                    .address
+                    .as_ref()
+                    .and_then(|a| {
+                        observations
+                            .token_balances
+                            .get(&(a.clone(), token_id.clone()))
+                    })
+                    .and_then(|balance| {
+                        state.change(*balance as f64, |now, before| Some(now - before))
+                    }),
+                Condition::OraclePriceDeviation { pool_nft, .. } => {
+                    observations.oracle_rates.get(pool_nft).and_then(|rate| {
+                        state.change(*rate as f64, |now, before| {
+                            (before != 0.0).then(|| (now - before) / before * 100.0)
+                        })
+                    })
+                }
+            };
+            let Some(value) = value else { continue };
+
+            let threshold = rule.condition.threshold();
+            let breaching = value > threshold;
+            if breaching == state.firing {
+                continue;
+            }
+            state.firing = breaching;
+            let (alert_state, relation) = if breaching {
+                (AlertState::Firing, "above")
+            } else {
+                (AlertState::Resolved, "back within")
+            };
+            let (shown, shown_threshold) = match &rule.condition {
+                Condition::TokenBalanceChange { token_id, max, .. } => (
+                    self.tokens.format_token_amount(token_id, value as u64),
+                    self.tokens.format_token_amount(token_id, *max),
+                ),
+                _ => (value.to_string(), threshold.to_string()),
+            };
+            alerts.push(Alert {
+                rule: rule.name.clone(),
+                state: alert_state,
+                value,
+                threshold,
+                message: format!(
+                    "{}: {} is {shown}, {relation} {shown_threshold}",
+                    rule.name,
+                    rule.condition.metric()
+                ),
+                at: observations.at,
+            });
+        }
+        alerts
+    }
+}
+
+impl RuleState {
+    /// Absolute change from the previous value, which `current` replaces.
+    fn change(&mut self, current: f64, diff: impl Fn(f64, f64) -> Option<f64>) -> Option<f64> {
+        let previous = self.previous.replace(current)?;
+        diff(current, previous).map(f64::abs)
+    }
+}
+
+/// Evaluates the rules against the latest mempool snapshot and rolling report, sending the
+/// alerts of every transition to `notifier`. Stops once `shutdown` is requested, after
+/// sending the alerts of the evaluation under way.
+pub fn spawn_alerts(
+    node: NodeClient,
+    mempool: Arc<ArcSwap<MempoolSnapshot>>,
+    rolling: Option<Arc<ArcSwap<RollingReport>>>,
+    mut engine: AlertEngine,
+    notifier: Arc<dyn Notifier>,
+    shutdown: ShutdownSignal,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!(rules = engine.rules.len(), "Starting alert rules...");
+        let mut errors = ErrorLog::new("alert rules");
+        let mut delivery = ErrorLog::new("alert notifier");
+        loop {
+            let snapshot = mempool.load_full();
+            let report = rolling.as_ref().map(|r| r.load_full());
+            match engine.observe(&node, &snapshot, report.as_deref()).await {
+                Ok(observations) => {
+                    errors.success();
+                    for alert in engine.evaluate(&observations) {
+                        match notifier.notify(&alert).await {
+                            Ok(()) => delivery.success(),
+                            Err(e) => delivery.failure(&e),
+                        }
+                    }
+                }
+                Err(e) => errors.failure(&e),
+            }
+            tokio::select! {
+                () = sleep(DEFAULT_POLL_INTERVAL) => {}
+                () = shutdown.requested() => return,
+            }
+        }
+    })
+}
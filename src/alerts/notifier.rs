@@ -0,0 +1,70 @@
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use super::{Alert, AlertState};
+use crate::clients::node::{HttpRequest, HttpTransport, NodeError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error(transparent)]
+    Transport(#[from] NodeError),
+
+    #[error("Webhook responded with status {0}")]
+    Status(u16),
+}
+
+/// Delivers alerts to operators. Implement this to route them to a chat, pager or mail gateway.
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    async fn notify(&self, alert: &Alert) -> Result<(), NotifyError>;
+}
+
+/// Logs alerts as warnings and their resolutions as info.
+#[derive(Debug, Clone, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<(), NotifyError> {
+        match alert.state {
+            AlertState::Firing => {
+                warn!(rule = alert.rule, value = alert.value, "{}", alert.message)
+            }
+            AlertState::Resolved => {
+                info!(rule = alert.rule, value = alert.value, "{}", alert.message)
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Posts alerts as JSON to a webhook. Any non-2xx response is a failure.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    transport: Arc<dyn HttpTransport>,
+    path: String,
+}
+
+impl WebhookNotifier {
+    /// Posts to `path`, relative to the transport's base URL.
+    pub fn new(transport: impl HttpTransport + 'static, path: &str) -> Self {
+        Self { transport: Arc::new(transport), path: path.trim_start_matches('/').to_string() }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<(), NotifyError> {
+        let body = serde_json::to_vec(alert).map_err(NodeError::from)?;
+        let resp = self
+            .transport
+            .send(HttpRequest::post(&self.path, body))
+            .await?;
+        if !(200..300).contains(&resp.status) {
+            return Err(NotifyError::Status(resp.status));
+        }
+        Ok(())
+    }
+}
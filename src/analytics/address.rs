@@ -0,0 +1,85 @@
+//! Summaries of an address for explorer-like views, resolved through the node's indexer.
+
+use serde::Serialize;
+
+use crate::{
+    address::{EncodedAddress, ErgoAddress},
+    clients::node::{BoxQuery, NodeClient, NodeError, TokenBalance},
+    types::{HashDigest, Height, TimestampMillis, ergo::IndexedTransaction},
+};
+
+/// Balance, boxes and activity of an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressInfo {
+    pub address: EncodedAddress,
+    /// Confirmed balance in nanoERG.
+    pub balance: u64,
+    /// Balance change of mempool transactions, in nanoERG.
+    pub unconfirmed_balance: u64,
+    pub tokens: Vec<TokenBalance>,
+    /// Boxes ever owned.
+    pub box_count: u64,
+    pub unspent_box_count: u64,
+    pub transaction_count: u64,
+    /// `None` for addresses without confirmed transactions.
+    pub first_activity: Option<Activity>,
+    pub last_activity: Option<Activity>,
+}
+
+/// A confirmed transaction involving an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    pub transaction_id: HashDigest,
+    pub height: Height,
+    /// Block timestamp, 0 when the indexer doesn't report it.
+    pub timestamp: TimestampMillis,
+}
+
+impl From<&IndexedTransaction> for Activity {
+    fn from(tx: &IndexedTransaction) -> Self {
+        Self { transaction_id: tx.id.clone(), height: tx.inclusion_height, timestamp: tx.timestamp }
+    }
+}
+
+/// Resolves the [`AddressInfo`] of `address`. The balance, box counts and one end of the
+/// transaction history are requested concurrently, then the other end of the history.
+///
+/// The ends are told apart by inclusion height, so this doesn't depend on the order in which
+/// the indexer pages transactions.
+#[tracing::instrument(skip(node))]
+pub async fn resolve_address_info(
+    node: &NodeClient,
+    address: &ErgoAddress,
+) -> Result<AddressInfo, NodeError> {
+    let count = BoxQuery::new().limit(1);
+    let (balance, boxes, unspent, transactions) = tokio::try_join!(
+        node.get_balance(address),
+        node.get_boxes_by_address(address, &count),
+        node.get_unspent_boxes_by_address(address, &count),
+        node.get_transactions_by_address(address, 0, 1),
+    )?;
+
+    let mut ends: Vec<Activity> = transactions.items.iter().map(Activity::from).collect();
+    if transactions.total > 1 {
+        let other = node
+            .get_transactions_by_address(address, transactions.total - 1, 1)
+            .await?;
+        ends.extend(other.items.iter().map(Activity::from));
+    }
+    let first_activity = ends.iter().min_by_key(|a| a.height).cloned();
+    let last_activity = ends.iter().max_by_key(|a| a.height).cloned();
+
+    Ok(AddressInfo {
+        address: address.clone().into(),
+        balance: balance.confirmed.nano_ergs,
+        unconfirmed_balance: balance.unconfirmed.nano_ergs,
+        tokens: balance.confirmed.tokens,
+        box_count: boxes.total,
+        unspent_box_count: unspent.total,
+        transaction_count: transactions.total,
+        first_activity,
+        last_activity,
+    })
+}
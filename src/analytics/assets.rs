@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::types::{HashDigest, ergo::UTxO};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AssetError {
+    #[error("Amount of token {0} exceeds the 64-bit range of box amounts")]
+    Overflow(HashDigest),
+}
+
+/// Token totals of `boxes`. Amounts are summed as `u128`, which can't overflow: that would take
+/// more than 2^64 boxes holding the maximal amount.
+pub fn aggregate_assets<'a>(
+    boxes: impl IntoIterator<Item = &'a UTxO>,
+) -> HashMap<HashDigest, u128> {
+    let mut totals = HashMap::new();
+    for utxo in boxes {
+        for token in &utxo.tokens {
+            *totals.entry(token.id.clone()).or_default() += u128::from(token.amount);
+        }
+    }
+    totals
+}
+
+/// [`aggregate_assets`] of many boxes. With the `rayon` feature, chunks are summed on the rayon
+/// thread pool and then merged.
+pub fn aggregate_assets_batch(boxes: &[UTxO]) -> HashMap<HashDigest, u128> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        boxes
+            .par_chunks(1024)
+            .map(aggregate_assets)
+            .reduce(HashMap::new, |mut totals, partial| {
+                merge_assets(&mut totals, partial);
+                totals
+            })
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        aggregate_assets(boxes)
+    }
+}
+
+/// Adds the totals of `partial` to `totals`, e.g. to combine the sums of concurrent workers.
+pub fn merge_assets(totals: &mut HashMap<HashDigest, u128>, partial: HashMap<HashDigest, u128>) {
+    for (id, amount) in partial {
+        *totals.entry(id).or_default() += amount;
+    }
+}
+
+/// Net token change from `inputs` to `outputs`: positive for minted or received tokens,
+/// negative for burned or sent ones. Tokens that net out are left out.
+pub fn asset_deltas<'a>(
+    inputs: impl IntoIterator<Item = &'a UTxO>,
+    outputs: impl IntoIterator<Item = &'a UTxO>,
+) -> HashMap<HashDigest, i128> {
+    let mut deltas: HashMap<HashDigest, i128> = HashMap::new();
+    for (id, amount) in aggregate_assets(outputs) {
+        *deltas.entry(id).or_default() += amount as i128;
+    }
+    for (id, amount) in aggregate_assets(inputs) {
+        *deltas.entry(id).or_default() -= amount as i128;
+    }
+    deltas.retain(|_, delta| *delta != 0);
+    deltas
+}
+
+/// Narrows totals to the `u64` amounts boxes can hold, failing on the first that doesn't fit.
+pub fn checked_u64(
+    totals: HashMap<HashDigest, u128>,
+) -> Result<HashMap<HashDigest, u64>, AssetError> {
+    totals
+        .into_iter()
+        .map(|(id, amount)| match u64::try_from(amount) {
+            Ok(amount) => Ok((id, amount)),
+            Err(_) => Err(AssetError::Overflow(id)),
+        })
+        .collect()
+}
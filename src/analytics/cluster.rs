@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    labels::{Label, LabelSet},
+    types::ergo::{IndexedTransaction, UTxO},
+};
+
+/// Identifier of an address cluster. Ids are only stable until the next observed transaction,
+/// since merging two clusters keeps just one of their ids.
+pub type ClusterId = usize;
+
+/// Groups addresses by the common-input-ownership heuristic: all P2PK addresses spent together
+/// in one transaction are assumed to belong to the same owner.
+///
+/// Script addresses are never clustered, as contracts (pools, order books) are spent by
+/// unrelated parties.
+#[derive(Debug)]
+pub struct AddressClusters {
+    network: NetworkPrefix,
+    index: HashMap<ErgoAddress, usize>,
+    addresses: Vec<ErgoAddress>,
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl AddressClusters {
+    pub fn new(network: NetworkPrefix) -> Self {
+        Self {
+            network,
+            index: HashMap::new(),
+            addresses: Vec::new(),
+            parent: Vec::new(),
+            size: Vec::new(),
+        }
+    }
+
+    pub fn observe(&mut self, tx: &IndexedTransaction) {
+        self.observe_inputs(&tx.inputs);
+    }
+
+    pub fn observe_inputs(&mut self, inputs: &[UTxO]) {
+        let network = self.network;
+        let members: Vec<usize> = inputs
+            .iter()
+            .map(|i| ErgoAddress::from_ergo_tree(network, &i.ergo_tree.0))
+            .filter(|a| a.kind() == AddressType::P2PK)
+            .map(|a| self.insert(a))
+            .collect();
+
+        for pair in members.windows(2) {
+            self.union(pair[0], pair[1]);
+        }
+    }
+
+    pub fn cluster_id(&self, address: &ErgoAddress) -> Option<ClusterId> {
+        self.index.get(address).map(|i| self.find(*i))
+    }
+
+    /// Whether both addresses are known and belong to the same cluster.
+    pub fn same_owner(&self, a: &ErgoAddress, b: &ErgoAddress) -> bool {
+        matches!((self.cluster_id(a), self.cluster_id(b)), (Some(a), Some(b)) if a == b)
+    }
+
+    /// All clusters, keyed by cluster id.
+    pub fn clusters(&self) -> HashMap<ClusterId, Vec<&ErgoAddress>> {
+        let mut clusters: HashMap<ClusterId, Vec<&ErgoAddress>> = HashMap::new();
+        for (i, address) in self.addresses.iter().enumerate() {
+            clusters.entry(self.find(i)).or_default().push(address);
+        }
+        clusters
+    }
+
+    /// Labels of the known addresses in each cluster, for clusters with at least one.
+    pub fn labels<'a>(&self, labels: &'a LabelSet) -> HashMap<ClusterId, Vec<&'a Label>> {
+        let mut labelled: HashMap<ClusterId, Vec<&'a Label>> = HashMap::new();
+        for (i, address) in self.addresses.iter().enumerate() {
+            if let Some(label) = labels.get(address) {
+                labelled.entry(self.find(i)).or_default().push(label);
+            }
+        }
+        labelled
+    }
+
+    /// Number of known addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    fn insert(&mut self, address: ErgoAddress) -> usize {
+        if let Some(i) = self.index.get(&address) {
+            return *i;
+        }
+
+        let i = self.addresses.len();
+        self.index.insert(address.clone(), i);
+        self.addresses.push(address);
+        self.parent.push(i);
+        self.size.push(1);
+        i
+    }
+
+    fn find(&self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
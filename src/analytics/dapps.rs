@@ -0,0 +1,202 @@
+//! Transaction counts and volume by dApp, attributing transactions through the contract
+//! template registry.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Write,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::{
+    analytics::rolling::TokenVolume,
+    clients::node::NodeClient,
+    templates::TemplateRegistry,
+    trace::ErrorLog,
+    types::{
+        HashDigest, Height,
+        ergo::{Block, UTxO},
+    },
+    watcher::BlockFollower,
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// dApps rendered by [`DappCounters::to_prometheus`], busiest first.
+const PROMETHEUS_DAPPS: usize = 50;
+
+/// Transactions and output value of one dApp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DappVolume {
+    pub dapp: String,
+    pub transactions: u64,
+    /// Sum of the output values of its transactions, in nanoERG.
+    pub nano_ergs: u64,
+    /// Most transactions first.
+    pub tokens: Vec<TokenVolume>,
+}
+
+/// Transactions and the value of their outputs: nanoERG, and amount and transaction count by
+/// token.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Volume {
+    pub(crate) transactions: u64,
+    pub(crate) nano_ergs: u64,
+    pub(crate) tokens: BTreeMap<HashDigest, (u64, u64)>,
+}
+
+impl Volume {
+    pub(crate) fn add(&mut self, outputs: &[UTxO]) {
+        self.transactions += 1;
+        let mut tokens: HashSet<&HashDigest> = HashSet::new();
+        for output in outputs {
+            self.nano_ergs = self.nano_ergs.saturating_add(output.value);
+            for token in &output.tokens {
+                let (amount, transactions) = self.tokens.entry(token.id.clone()).or_default();
+                *amount = amount.saturating_add(token.amount);
+                if tokens.insert(&token.id) {
+                    *transactions += 1;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: &Volume) {
+        self.transactions += other.transactions;
+        self.nano_ergs = self.nano_ergs.saturating_add(other.nano_ergs);
+        for (id, (amount, count)) in &other.tokens {
+            let total = self.tokens.entry(id.clone()).or_default();
+            total.0 = total.0.saturating_add(*amount);
+            total.1 += count;
+        }
+    }
+
+    /// Token volumes, most transactions first.
+    pub(crate) fn token_volumes(&self) -> Vec<TokenVolume> {
+        let mut tokens: Vec<TokenVolume> = self
+            .tokens
+            .iter()
+            .map(|(id, (amount, transactions))| TokenVolume {
+                token_id: id.clone(),
+                amount: *amount,
+                transactions: *transactions,
+            })
+            .collect();
+        tokens.sort_by_key(|t| Reverse(t.transactions));
+        tokens
+    }
+}
+
+/// Volumes by dApp, most transactions first, ties by name.
+pub(crate) fn dapp_volumes(dapps: &BTreeMap<String, Volume>) -> Vec<DappVolume> {
+    let mut volumes: Vec<DappVolume> = dapps
+        .iter()
+        .map(|(dapp, volume)| DappVolume {
+            dapp: dapp.clone(),
+            transactions: volume.transactions,
+            nano_ergs: volume.nano_ergs,
+            tokens: volume.token_volumes(),
+        })
+        .collect();
+    volumes.sort_by_key(|v| Reverse(v.transactions));
+    volumes
+}
+
+/// Running totals by dApp of the confirmed transactions of the blocks applied.
+///
+/// Block transactions list their inputs by id only, so a transaction is attributed to the
+/// dApps of its outputs' contracts; one only spending a dApp's boxes is missed.
+#[derive(Debug, Clone)]
+pub struct DappCounters {
+    templates: Arc<TemplateRegistry>,
+    dapps: BTreeMap<String, Volume>,
+    height: Option<Height>,
+}
+
+impl DappCounters {
+    pub fn new(templates: Arc<TemplateRegistry>) -> Self {
+        Self { templates, dapps: BTreeMap::new(), height: None }
+    }
+
+    pub fn apply_block(&mut self, block: &Block) {
+        for tx in &block.transactions.transactions {
+            let dapps: BTreeSet<&str> = tx
+                .outputs
+                .iter()
+                .filter_map(|output| self.templates.classify_box(output))
+                .map(|t| t.dapp.as_str())
+                .collect();
+            for dapp in dapps {
+                self.dapps
+                    .entry(dapp.to_owned())
+                    .or_default()
+                    .add(&tx.outputs);
+            }
+        }
+        self.height = Some(block.header.height);
+    }
+
+    /// Height of the last block applied.
+    pub fn height(&self) -> Option<Height> {
+        self.height
+    }
+
+    pub fn report(&self) -> Vec<DappVolume> {
+        dapp_volumes(&self.dapps)
+    }
+
+    /// Renders the counters of the busiest dApps in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let report = self.report();
+        let dapps = &report[..report.len().min(PROMETHEUS_DAPPS)];
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, value: fn(&DappVolume) -> u64| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            for dapp in dapps {
+                let _ = writeln!(out, "{name}{{dapp=\"{}\"}} {}", dapp.dapp, value(dapp));
+            }
+        };
+        counter(
+            "hergmes_dapp_confirmed_transactions_total",
+            "Confirmed transactions creating boxes of the dApp's contracts.",
+            |d| d.transactions,
+        );
+        counter(
+            "hergmes_dapp_confirmed_erg_volume_total",
+            "nanoERG in the outputs of the dApp's confirmed transactions.",
+            |d| d.nano_ergs,
+        );
+        out
+    }
+}
+
+/// Keeps the dApp counters up to date with new blocks in the background.
+pub fn spawn_dapp_counters(node: NodeClient, counters: DappCounters) -> Arc<RwLock<DappCounters>> {
+    let counters = Arc::new(RwLock::new(counters));
+    let cloned_counters = counters.clone();
+
+    tokio::spawn(async move {
+        info!("Starting dApp counters...");
+        let mut blocks = BlockFollower::new();
+        let mut errors = ErrorLog::new("dApp counters");
+        loop {
+            let polled = blocks.poll(&node, |block| {
+                cloned_counters.write().unwrap().apply_block(block);
+            });
+            match polled.await {
+                Ok(_) => errors.success(),
+                Err(e) => errors.failure(&e),
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    counters
+}
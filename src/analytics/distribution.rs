@@ -0,0 +1,191 @@
+use std::{collections::HashMap, future::Future};
+
+use serde::Serialize;
+
+use crate::{
+    clients::node::{BoxQuery, ItemsResponse, NodeClient, NodeError},
+    types::{
+        HashDigest, HexBytes,
+        ergo::{IndexedBox, UTxO},
+    },
+};
+
+const PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Asset {
+    Erg,
+    Token(HashDigest),
+}
+
+impl Asset {
+    /// Amount of the asset held by a box, in nanoErgs or raw token units.
+    pub fn amount(&self, utxo: &UTxO) -> u64 {
+        match self {
+            Asset::Erg => utxo.value,
+            Asset::Token(id) => utxo
+                .tokens
+                .iter()
+                .filter(|t| &t.id == id)
+                .fold(0, |sum, t| sum.saturating_add(t.amount)),
+        }
+    }
+}
+
+/// Holders whose balance is within `[min, max)`; the last bucket has no upper bound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bucket {
+    pub min: u64,
+    pub max: Option<u64>,
+    pub holders: usize,
+    pub total: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub asset: Asset,
+    pub holders: usize,
+    pub total: u128,
+    /// Largest holders by ErgoTree, descending.
+    pub top: Vec<(HexBytes, u64)>,
+    /// 0 when every holder has the same balance, approaching 1 when one holder has it all.
+    pub gini: f64,
+    /// Holder counts by order of magnitude of their balance.
+    pub histogram: Vec<Bucket>,
+}
+
+/// Folds boxes into per-holder balances, so only one entry per holder is kept in memory
+/// while boxes are streamed in.
+#[derive(Debug, Clone)]
+pub struct DistributionBuilder {
+    asset: Asset,
+    balances: HashMap<HexBytes, u64>,
+}
+
+impl DistributionBuilder {
+    pub fn new(asset: Asset) -> Self {
+        Self { asset, balances: HashMap::new() }
+    }
+
+    pub fn add(&mut self, utxo: &UTxO) {
+        let amount = self.asset.amount(utxo);
+        if amount > 0 {
+            let balance = self.balances.entry(utxo.ergo_tree.clone()).or_default();
+            *balance = balance.saturating_add(amount);
+        }
+    }
+
+    pub fn build(self, top_n: usize) -> Distribution {
+        let mut balances: Vec<(HexBytes, u64)> = self.balances.into_iter().collect();
+        balances.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.0.cmp(&a.0.0)));
+
+        let amounts: Vec<u64> = balances.iter().map(|(_, amount)| *amount).collect();
+        let total: u128 = amounts.iter().map(|a| *a as u128).sum();
+
+        Distribution {
+            asset: self.asset,
+            holders: amounts.len(),
+            total,
+            gini: gini(&amounts, total),
+            histogram: histogram(&amounts),
+            top: balances.into_iter().rev().take(top_n).collect(),
+        }
+    }
+}
+
+/// Gini coefficient of balances sorted ascending.
+fn gini(sorted: &[u64], total: u128) -> f64 {
+    if sorted.is_empty() || total == 0 {
+        return 0.0;
+    }
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted
+        .iter()
+        .zip(1..)
+        .map(|(amount, i)| i as f64 * *amount as f64)
+        .sum();
+    2.0 * weighted / (n * total as f64) - (n + 1.0) / n
+}
+
+/// Power-of-ten buckets of balances sorted ascending, from 1 up to the largest balance.
+fn histogram(sorted: &[u64]) -> Vec<Bucket> {
+    let Some(largest) = sorted.last() else {
+        return Vec::new();
+    };
+
+    let magnitudes = largest.checked_ilog10().unwrap_or(0);
+    (0..=magnitudes)
+        .map(|m| {
+            let min = 10u64.pow(m);
+            let max = 10u64.checked_pow(m + 1).filter(|_| m < magnitudes);
+            let start = sorted.partition_point(|a| *a < min);
+            let end = max.map_or(sorted.len(), |max| sorted.partition_point(|a| *a < max));
+            let in_bucket = &sorted[start..end];
+            Bucket {
+                min,
+                max,
+                holders: in_bucket.len(),
+                total: in_bucket.iter().map(|a| *a as u128).sum(),
+            }
+        })
+        .collect()
+}
+
+/// Distribution of a token over the current unspent boxes holding it, streamed page by page
+/// from the node's blockchain indexer.
+#[tracing::instrument(skip(node))]
+pub async fn token_distribution(
+    node: &NodeClient,
+    token_id: &HashDigest,
+    top_n: usize,
+) -> Result<Distribution, NodeError> {
+    let builder = DistributionBuilder::new(Asset::Token(token_id.clone()));
+    let fetch =
+        |query: BoxQuery| async move { node.get_unspent_boxes_by_token_id(token_id, &query).await };
+    Ok(stream_unspent(builder, fetch).await?.build(top_n))
+}
+
+/// Distribution of ERG over the current unspent boxes guarded by any instance of the contract
+/// of `template_hash`, e.g. the ERG locked per pool or vault of a dApp. The indexer has no
+/// listing of every unspent box, so a chain-wide rich list is out of reach.
+#[tracing::instrument(skip(node))]
+pub async fn erg_distribution(
+    node: &NodeClient,
+    template_hash: &HashDigest,
+    top_n: usize,
+) -> Result<Distribution, NodeError> {
+    let builder = DistributionBuilder::new(Asset::Erg);
+    let fetch = |query: BoxQuery| async move {
+        node.get_unspent_boxes_by_template_hash(template_hash, &query)
+            .await
+    };
+    Ok(stream_unspent(builder, fetch).await?.build(top_n))
+}
+
+/// Adds every page of unspent boxes returned by `fetch` to `builder`.
+async fn stream_unspent<F, Fut>(
+    mut builder: DistributionBuilder,
+    fetch: F,
+) -> Result<DistributionBuilder, NodeError>
+where
+    F: Fn(BoxQuery) -> Fut,
+    Fut: Future<Output = Result<ItemsResponse<IndexedBox>, NodeError>>,
+{
+    let mut offset = 0;
+    loop {
+        let page = fetch(BoxQuery::new().offset(offset).limit(PAGE_SIZE)).await?;
+        if page.items.is_empty() {
+            break;
+        }
+        offset += page.items.len() as u64;
+        page.items.iter().for_each(|b| builder.add(&b.utxo));
+
+        if offset >= page.total {
+            break;
+        }
+    }
+    Ok(builder)
+}
@@ -0,0 +1,315 @@
+//! What-if analysis of the fee market: the mempool, with hypothetical transactions injected,
+//! is packed into the next blocks as a miner would, answering questions like "what fee do I
+//! need if someone dumps 500 transactions right now".
+//!
+//! Blocks are only bounded by size, as the cost of a transaction can't be known without
+//! evaluating its scripts.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{
+    chain::fee,
+    types::{HashDigest, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+/// Size assumed for quoted and injected transactions by default: a payment with a couple of
+/// inputs, in bytes.
+pub const DEFAULT_TX_SIZE: usize = 300;
+
+/// A mempool transaction as seen by block assembly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransaction {
+    pub id: HashDigest,
+    /// Serialized size, in bytes.
+    pub size: usize,
+    /// Miner fee, in nanoERG.
+    pub fee: u64,
+    /// Mempool transactions whose outputs it spends, which must be included first.
+    pub parents: Vec<HashDigest>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    size: usize,
+    fee: u64,
+    /// Indexes of the parents in the market.
+    parents: Vec<usize>,
+    injected: bool,
+}
+
+impl Entry {
+    /// Whether this pays strictly more per byte than `other`.
+    fn outbids(&self, other: &Entry) -> bool {
+        self.fee as u128 * other.size as u128 > other.fee as u128 * self.size as u128
+    }
+}
+
+/// Packs a block of at most `max_size` bytes from the entries not yet `included`, best fee
+/// per byte first. A transaction is only eligible once its parents are included, in an
+/// earlier block or earlier in this one. Returns the indexes of the packed entries, in
+/// inclusion order, and marks them as included.
+fn assemble(
+    entries: &[Entry],
+    order: &[usize],
+    included: &mut [bool],
+    max_size: u64,
+) -> Vec<usize> {
+    let (mut block, mut size) = (Vec::new(), 0u64);
+    // Children may outbid their parents, so passes are repeated until nothing is added.
+    loop {
+        let packed = block.len();
+        for &i in order {
+            let entry = &entries[i];
+            if included[i]
+                || size + entry.size as u64 > max_size
+                || entry.parents.iter().any(|p| !included[*p])
+            {
+                continue;
+            }
+            included[i] = true;
+            size += entry.size as u64;
+            block.push(i);
+        }
+        if block.len() == packed {
+            return block;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedBlock {
+    pub transactions: usize,
+    /// How many of `transactions` are injected ones.
+    pub injected: usize,
+    pub bytes: u64,
+    pub fees: u64,
+    /// Lowest fee per byte of the block, in nanoERG; `None` for an empty block.
+    pub min_fee_per_byte: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    pub blocks: Vec<SimulatedBlock>,
+    /// Transactions left in the mempool after the simulated blocks.
+    pub remaining: usize,
+    pub remaining_injected: usize,
+}
+
+/// The mempool with hypothetical transactions, to be packed into blocks.
+#[derive(Debug, Clone, Default)]
+pub struct FeeMarket {
+    entries: Vec<Entry>,
+}
+
+impl FeeMarket {
+    /// A market of `transactions`. Parents outside of `transactions` are taken as confirmed.
+    pub fn new(transactions: Vec<PendingTransaction>) -> Self {
+        let index: HashMap<HashDigest, usize> = transactions
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| (tx.id.clone(), i))
+            .collect();
+        let entries = transactions
+            .into_iter()
+            .map(|tx| Entry {
+                size: tx.size,
+                fee: tx.fee,
+                parents: tx
+                    .parents
+                    .iter()
+                    .filter_map(|p| index.get(p).copied())
+                    .collect(),
+                injected: false,
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// The transactions of `snapshot`. Those the serializer rejected have no size and are
+    /// left out, along with the transactions spending their outputs, which can't be
+    /// included before them.
+    pub fn from_snapshot(snapshot: &MempoolSnapshot) -> Self {
+        let creators: HashMap<&HashDigest, &HashDigest> = snapshot
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.outputs.iter().map(move |o| (&o.id, &tx.id)))
+            .collect();
+        let parents = |tx: &UnconfirmedTransaction| -> Vec<HashDigest> {
+            let mut parents: Vec<HashDigest> = tx
+                .inputs
+                .iter()
+                .filter_map(|i| creators.get(&i.utxo.id).map(|id| (*id).clone()))
+                .collect();
+            parents.sort();
+            parents.dedup();
+            parents
+        };
+
+        let mut unserialized: HashSet<&HashDigest> = snapshot
+            .transactions
+            .iter()
+            .map(|tx| &tx.id)
+            .filter(|id| snapshot.size_of(id).is_none())
+            .collect();
+        // Spreads to the descendants of unserialized transactions, whatever the snapshot order.
+        loop {
+            let blocked: Vec<&HashDigest> = snapshot
+                .transactions
+                .iter()
+                .filter(|tx| !unserialized.contains(&tx.id))
+                .filter(|tx| parents(tx).iter().any(|p| unserialized.contains(p)))
+                .map(|tx| &tx.id)
+                .collect();
+            if blocked.is_empty() {
+                break;
+            }
+            unserialized.extend(blocked);
+        }
+
+        let transactions = snapshot
+            .transactions
+            .iter()
+            .filter(|tx| !unserialized.contains(&tx.id))
+            .map(|tx| PendingTransaction {
+                id: tx.id.clone(),
+                size: snapshot.size_of(&tx.id).unwrap_or_default(),
+                fee: fee::outputs_fee(&tx.outputs).unwrap_or_default(),
+                parents: parents(tx),
+            })
+            .collect();
+        Self::new(transactions)
+    }
+
+    /// Adds `count` independent hypothetical transactions of `size` bytes, each paying `fee`.
+    pub fn inject(&mut self, count: usize, size: usize, fee: u64) {
+        let entry = Entry { size, fee, parents: Vec::new(), injected: true };
+        self.entries.extend(std::iter::repeat_n(entry, count));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entry indexes by fee per byte, best first; ties keep the market order, so
+    /// transactions added later lose them.
+    fn order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.entries[*a], &self.entries[*b]);
+            (b.fee as u128 * a.size as u128).cmp(&(a.fee as u128 * b.size as u128))
+        });
+        order
+    }
+
+    /// Packs the next `blocks` blocks of at most `max_block_size` bytes, as a miner
+    /// maximizing fees would, with no new transactions arriving in between.
+    pub fn simulate(&self, blocks: usize, max_block_size: u64) -> Simulation {
+        let order = self.order();
+        let mut included = vec![false; self.entries.len()];
+        let blocks: Vec<SimulatedBlock> = (0..blocks)
+            .map(|_| {
+                let packed = assemble(&self.entries, &order, &mut included, max_block_size);
+                let entries = packed.iter().map(|i| &self.entries[*i]);
+                SimulatedBlock {
+                    transactions: packed.len(),
+                    injected: entries.clone().filter(|e| e.injected).count(),
+                    bytes: entries.clone().map(|e| e.size as u64).sum(),
+                    fees: entries
+                        .clone()
+                        .fold(0, |total, e| total.saturating_add(e.fee)),
+                    min_fee_per_byte: entries
+                        .clone()
+                        .reduce(|min, e| if min.outbids(e) { e } else { min })
+                        .map(|e| e.fee as f64 / e.size as f64),
+                }
+            })
+            .collect();
+
+        let remaining = self.entries.iter().zip(&included).filter(|(_, i)| !**i);
+        Simulation {
+            blocks,
+            remaining: remaining.clone().count(),
+            remaining_injected: remaining.filter(|(e, _)| e.injected).count(),
+        }
+    }
+
+    /// Lowest fee of a new independent transaction of `size` bytes for it to be included
+    /// within the next `blocks` blocks; `None` if it doesn't fit in a block.
+    pub fn required_fee(&self, size: usize, blocks: usize, max_block_size: u64) -> Option<u64> {
+        if size as u64 > max_block_size || blocks == 0 {
+            return None;
+        }
+        let included = |fee: u64| {
+            let mut market = self.clone();
+            market.inject(1, size, fee);
+            let order = market.order();
+            let mut included = vec![false; market.entries.len()];
+            for _ in 0..blocks {
+                assemble(&market.entries, &order, &mut included, max_block_size);
+            }
+            included[market.entries.len() - 1]
+        };
+
+        // Outbidding every transaction gets the first place of the next block.
+        let mut high = self
+            .entries
+            .iter()
+            .map(|e| (e.fee as u128 * size as u128).div_ceil(e.size.max(1) as u128) + 1)
+            .max()
+            .unwrap_or(0)
+            .min(u64::MAX as u128) as u64;
+        let mut low = 0;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if included(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Some(low)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeQuote {
+    pub within_blocks: usize,
+    /// In nanoERG; `None` if the transaction doesn't fit in a block.
+    pub fee: Option<u64>,
+    pub fee_per_byte: Option<f64>,
+}
+
+/// Outcome of injecting hypothetical transactions into the mempool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIf {
+    pub simulation: Simulation,
+    /// Fees a new transaction needs to be included within 1 to `blocks` blocks.
+    pub quotes: Vec<FeeQuote>,
+}
+
+/// Simulates the next `blocks` blocks after injecting `market`'s hypothetical
+/// transactions, and quotes the fees a new transaction of `tx_size` bytes would need.
+pub fn what_if(market: &FeeMarket, blocks: usize, max_block_size: u64, tx_size: usize) -> WhatIf {
+    let quotes = (1..=blocks)
+        .map(|within_blocks| {
+            let fee = market.required_fee(tx_size, within_blocks, max_block_size);
+            FeeQuote {
+                within_blocks,
+                fee,
+                fee_per_byte: fee.map(|fee| fee as f64 / tx_size as f64),
+            }
+        })
+        .collect();
+    WhatIf { simulation: market.simulate(blocks, max_block_size), quotes }
+}
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::{
+    clients::node::{NodeClient, NodeError},
+    labels::{Label, LabelSet},
+    types::{
+        HashDigest, Height,
+        ergo::{IndexedTransaction, UTxO},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FlowDirection {
+    /// Follow spending transactions towards the current holders.
+    Forward,
+    /// Follow creating transactions towards the origins.
+    Backward,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowBox {
+    #[serde(flatten)]
+    pub utxo: UTxO,
+    /// Number of transactions between this box and the traced box.
+    pub depth: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Label>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowTransaction {
+    pub inclusion_height: Height,
+    pub inputs: Vec<HashDigest>,
+    pub outputs: Vec<HashDigest>,
+}
+
+/// Boxes and the transactions linking them. Edges are implied by each transaction's input and
+/// output ids; only boxes within the traced depth are included in `boxes`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowGraph {
+    pub root: HashDigest,
+    pub direction: FlowDirection,
+    pub boxes: BTreeMap<HashDigest, FlowBox>,
+    pub transactions: BTreeMap<HashDigest, FlowTransaction>,
+}
+
+impl FlowGraph {
+    /// Labels every box guarded by a known address.
+    pub fn annotate(&mut self, labels: &LabelSet) {
+        for flow_box in self.boxes.values_mut() {
+            flow_box.label = labels.get_by_tree(&flow_box.utxo.ergo_tree.0).cloned();
+        }
+    }
+}
+
+/// Traces funds from `box_id` across at most `max_depth` transactions using the node's
+/// blockchain indexer. The graph grows with the fan-out of every transaction, so keep
+/// `max_depth` small for busy boxes.
+#[tracing::instrument(skip(node))]
+pub async fn trace_funds(
+    node: &NodeClient,
+    box_id: &HashDigest,
+    direction: FlowDirection,
+    max_depth: u32,
+) -> Result<FlowGraph, NodeError> {
+    let root = node.get_indexed_box(box_id).await?;
+    let mut graph = FlowGraph {
+        root: box_id.clone(),
+        direction,
+        boxes: BTreeMap::new(),
+        transactions: BTreeMap::new(),
+    };
+    let root_spent = root.spent_transaction_id;
+    graph
+        .boxes
+        .insert(box_id.clone(), FlowBox { utxo: root.utxo, depth: 0, label: None });
+
+    let mut queue: VecDeque<(HashDigest, u32)> = VecDeque::from([(box_id.clone(), 0)]);
+    let mut visited: HashSet<HashDigest> = HashSet::new();
+
+    while let Some((id, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+
+        let tx_id = match direction {
+            FlowDirection::Forward if depth == 0 => root_spent.clone(),
+            FlowDirection::Forward => node.get_indexed_box(&id).await?.spent_transaction_id,
+            FlowDirection::Backward => Some(graph.boxes[&id].utxo.transaction_id.clone()),
+        };
+        let Some(tx_id) = tx_id else { continue };
+        if !visited.insert(tx_id.clone()) {
+            continue;
+        }
+
+        let tx = node.get_indexed_transaction(&tx_id).await?;
+        let next = match direction {
+            FlowDirection::Forward => &tx.outputs,
+            FlowDirection::Backward => &tx.inputs,
+        };
+        for utxo in next {
+            if !graph.boxes.contains_key(&utxo.id) {
+                queue.push_back((utxo.id.clone(), depth + 1));
+                let flow_box = FlowBox { utxo: utxo.clone(), depth: depth + 1, label: None };
+                graph.boxes.insert(utxo.id.clone(), flow_box);
+            }
+        }
+        graph.transactions.insert(tx_id, flow_transaction(&tx));
+    }
+
+    Ok(graph)
+}
+
+fn flow_transaction(tx: &IndexedTransaction) -> FlowTransaction {
+    FlowTransaction {
+        inclusion_height: tx.inclusion_height,
+        inputs: tx.inputs.iter().map(|i| i.id.clone()).collect(),
+        outputs: tx.outputs.iter().map(|o| o.id.clone()).collect(),
+    }
+}
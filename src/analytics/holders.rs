@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{
+    address::{ErgoAddress, NetworkPrefix},
+    chain::value,
+    clients::node::{BoxQuery, NodeClient, NodeError},
+    tokens::TokenRegistry,
+    types::{HashDigest, Height, HexBytes, ergo::IndexedBox},
+};
+
+const PAGE_SIZE: u32 = 100;
+
+/// Balances of a token by holder, at a given height or at the indexed tip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHolders {
+    pub token_id: HashDigest,
+    pub height: Option<Height>,
+    /// Balance by holder ErgoTree.
+    pub balances: HashMap<HexBytes, u64>,
+}
+
+impl TokenHolders {
+    pub fn new(token_id: HashDigest, height: Option<Height>) -> Self {
+        Self { token_id, height, balances: HashMap::new() }
+    }
+
+    /// Credits the box's amount of the token to its owner.
+    pub fn add(&mut self, indexed: &IndexedBox) {
+        let utxo = &indexed.utxo;
+        let amount: u64 = utxo
+            .tokens
+            .iter()
+            .filter(|t| t.id == self.token_id)
+            .fold(0u64, |sum, t| sum.saturating_add(t.amount));
+        if amount > 0 {
+            let balance = self.balances.entry(utxo.ergo_tree.clone()).or_default();
+            *balance = balance.saturating_add(amount);
+        }
+    }
+
+    /// Sum of all balances, which can exceed a single box amount for inconsistent indexes.
+    pub fn total_supply(&self) -> u128 {
+        value::total(self.balances.values().copied())
+    }
+
+    /// Holders ordered by descending balance, ties by ErgoTree.
+    pub fn ranked(&self, network: NetworkPrefix) -> Vec<(ErgoAddress, u64)> {
+        let mut ranked: Vec<_> = self.balances.iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.0.cmp(&b.0.0)));
+        ranked
+            .into_iter()
+            .map(|(tree, amount)| (ErgoAddress::from_ergo_tree(network, &tree.0), *amount))
+            .collect()
+    }
+
+    /// Writes `address,amount,balance` rows, largest holders first. The balance is the amount
+    /// in whole units of the token, with the decimals known to `tokens`.
+    pub fn write_csv(
+        &self,
+        network: NetworkPrefix,
+        tokens: &TokenRegistry,
+        mut out: impl Write,
+    ) -> io::Result<()> {
+        writeln!(out, "address,amount,balance")?;
+        let mut encoded = String::new();
+        for (address, amount) in self.ranked(network) {
+            encoded.clear();
+            address.encode_into(&mut encoded);
+            let balance = tokens.format_units(&self.token_id, amount);
+            writeln!(out, "{encoded},{amount},{balance}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes the holders of `token_id` from the node's blockchain indexer.
+///
+/// Without `at_height` only unspent boxes are read. With it, every box that ever held the
+/// token is visited, and the inclusion height of each spending transaction is fetched to
+/// tell whether the box was still unspent at that height.
+#[tracing::instrument(skip(node))]
+pub async fn token_holders(
+    node: &NodeClient,
+    token_id: &HashDigest,
+    at_height: Option<Height>,
+) -> Result<TokenHolders, NodeError> {
+    let mut holders = TokenHolders::new(token_id.clone(), at_height);
+    let mut spending_heights: HashMap<HashDigest, Height> = HashMap::new();
+
+    let mut offset = 0;
+    loop {
+        let query = BoxQuery::new().offset(offset).limit(PAGE_SIZE);
+        let page = match at_height {
+            None => node.get_unspent_boxes_by_token_id(token_id, &query).await?,
+            Some(_) => node.get_boxes_by_token_id(token_id, &query).await?,
+        };
+        if page.items.is_empty() {
+            break;
+        }
+        offset += page.items.len() as u64;
+
+        for indexed in &page.items {
+            if let Some(height) = at_height {
+                if indexed.inclusion_height > height {
+                    continue;
+                }
+                if let Some(spent_by) = &indexed.spent_transaction_id {
+                    let spent_at = match spending_heights.get(spent_by) {
+                        Some(h) => *h,
+                        None => {
+                            let h = node
+                                .get_indexed_transaction(spent_by)
+                                .await?
+                                .inclusion_height;
+                            spending_heights.insert(spent_by.clone(), h);
+                            h
+                        }
+                    };
+                    if spent_at <= height {
+                        continue;
+                    }
+                }
+            }
+            holders.add(indexed);
+        }
+
+        if offset >= page.total {
+            break;
+        }
+    }
+
+    Ok(holders)
+}
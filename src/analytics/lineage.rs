@@ -0,0 +1,222 @@
+//! State transitions of protocols that keep their state in a single box lineage, such as an
+//! oracle pool box or a bank box: each transaction spending the state box recreates it with
+//! updated registers.
+
+use futures_util::{Stream, stream};
+
+use crate::{
+    chain::register::Constant,
+    clients::node::{NodeClient, NodeError},
+    types::{
+        HashDigest, HexBytes,
+        ergo::{IndexedTransaction, NonMandatoryRegisters, UTxO, UnconfirmedTransaction},
+    },
+    watcher::MempoolSnapshot,
+};
+
+/// The output of a transaction spending `state` that carries the state on.
+///
+/// A state box is identified by its first token, by convention a singleton NFT, so the
+/// successor is the output holding it. A state box without tokens is followed by the output
+/// with the same ErgoTree. Either way the first match wins.
+pub fn successor<'a>(state: &UTxO, outputs: &'a [UTxO]) -> Option<&'a UTxO> {
+    match state.tokens.first() {
+        Some(nft) => outputs
+            .iter()
+            .find(|output| output.tokens.iter().any(|t| t.id == nft.id)),
+        None => outputs
+            .iter()
+            .find(|output| output.ergo_tree == state.ergo_tree),
+    }
+}
+
+/// Follows the lineage of `box_id` through the blockchain indexer, returning the box and up
+/// to `max_steps` of its successors, oldest first. The lineage ends at an unspent box or at a
+/// transaction that doesn't recreate the state.
+#[tracing::instrument(skip(node))]
+pub async fn follow_lineage(
+    node: &NodeClient,
+    box_id: &HashDigest,
+    max_steps: usize,
+) -> Result<Vec<UTxO>, NodeError> {
+    let mut current = node.get_indexed_box(box_id).await?;
+    let mut lineage = Vec::new();
+    for _ in 0..max_steps {
+        let Some(spent_by) = &current.spent_transaction_id else {
+            break;
+        };
+        let tx = node.get_indexed_transaction(spent_by).await?;
+        let Some(next) = successor(&current.utxo, &tx.outputs) else {
+            break;
+        };
+        let next = node.get_indexed_box(&next.id).await?;
+        lineage.push(std::mem::replace(&mut current, next).utxo);
+    }
+    lineage.push(current.utxo);
+    Ok(lineage)
+}
+
+/// A transaction spending a box of a lineage.
+#[derive(Debug, Clone)]
+pub enum SpendingTransaction {
+    Confirmed(IndexedTransaction),
+    Unconfirmed(UnconfirmedTransaction),
+}
+
+impl SpendingTransaction {
+    pub fn id(&self) -> &HashDigest {
+        match self {
+            Self::Confirmed(tx) => &tx.id,
+            Self::Unconfirmed(tx) => &tx.id,
+        }
+    }
+
+    pub fn outputs(&self) -> &[UTxO] {
+        match self {
+            Self::Confirmed(tx) => &tx.outputs,
+            Self::Unconfirmed(tx) => &tx.outputs,
+        }
+    }
+}
+
+/// A box of a lineage, with its spender when the indexer knows it.
+struct Cursor {
+    utxo: UTxO,
+    spent_by: Option<HashDigest>,
+}
+
+/// Streams the lineage of `box_id` at its contract: each box with the transaction spending it,
+/// oldest first, the next box being the first output of that transaction with the same
+/// ErgoTree. Confirmed spends are read from the blockchain indexer; past the last of them,
+/// the lineage continues through the transactions of `mempool`, when given.
+///
+/// The stream ends at an unspent box, which is not yielded, at a transaction that doesn't
+/// recreate the contract, or after the first error.
+pub fn lineage<'a>(
+    node: &'a NodeClient,
+    box_id: &HashDigest,
+    mempool: Option<&'a MempoolSnapshot>,
+) -> impl Stream<Item = Result<(UTxO, SpendingTransaction), NodeError>> + 'a {
+    let start = box_id.clone();
+    stream::unfold(Some(Err(start)), move |state| async move {
+        let cursor = match state? {
+            Ok(cursor) => cursor,
+            Err(box_id) => match node.get_indexed_box(&box_id).await {
+                Ok(indexed) => {
+                    Cursor { utxo: indexed.utxo, spent_by: indexed.spent_transaction_id }
+                }
+                Err(e) => return Some((Err(e), None)),
+            },
+        };
+
+        let tx = match &cursor.spent_by {
+            Some(tx_id) => match node.get_indexed_transaction(tx_id).await {
+                Ok(tx) => SpendingTransaction::Confirmed(tx),
+                Err(e) => return Some((Err(e), None)),
+            },
+            None => SpendingTransaction::Unconfirmed(
+                mempool?
+                    .transactions
+                    .iter()
+                    .find(|tx| {
+                        tx.inputs
+                            .iter()
+                            .any(|input| input.utxo.id == cursor.utxo.id)
+                    })?
+                    .clone(),
+            ),
+        };
+
+        let next = tx
+            .outputs()
+            .iter()
+            .find(|output| output.ergo_tree == cursor.utxo.ergo_tree)
+            .cloned();
+        let next = match (next, &tx) {
+            (None, _) => None,
+            (Some(next), SpendingTransaction::Confirmed(_)) => Some(Err(next.id)),
+            (Some(utxo), SpendingTransaction::Unconfirmed(_)) => {
+                Some(Ok(Cursor { utxo, spent_by: None }))
+            }
+        };
+        Some((Ok((cursor.utxo, tx)), next))
+    })
+}
+
+/// A register value, decoded when its type is supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterValue {
+    Decoded(Constant),
+    Raw(HexBytes),
+}
+
+impl RegisterValue {
+    fn new(bytes: &HexBytes) -> Self {
+        match Constant::decode(&bytes.0) {
+            Ok(constant) => Self::Decoded(constant),
+            Err(_) => Self::Raw(bytes.clone()),
+        }
+    }
+
+    /// The value of an `Int` or `Long` register.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Decoded(Constant::Int(v)) => Some((*v).into()),
+            Self::Decoded(Constant::Long(v)) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// How a register differs between two versions of a box. Registers are numbered from 4 to 9.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterChange {
+    Added { register: u8, value: RegisterValue },
+    Removed { register: u8, value: RegisterValue },
+    Changed { register: u8, from: RegisterValue, to: RegisterValue },
+}
+
+impl RegisterChange {
+    pub fn register(&self) -> u8 {
+        match self {
+            Self::Added { register, .. }
+            | Self::Removed { register, .. }
+            | Self::Changed { register, .. } => *register,
+        }
+    }
+
+    /// `to - from` for a numeric register that changed, saturating.
+    pub fn delta(&self) -> Option<i64> {
+        match self {
+            Self::Changed { from, to, .. } => Some(to.as_i64()?.saturating_sub(from.as_i64()?)),
+            _ => None,
+        }
+    }
+}
+
+/// The registers that differ from `prev` to `next`, in register order. Values are compared
+/// by their serialized bytes.
+pub fn diff_registers(
+    prev: &NonMandatoryRegisters,
+    next: &NonMandatoryRegisters,
+) -> Vec<RegisterChange> {
+    prev.as_array()
+        .into_iter()
+        .zip(next.as_array())
+        .zip(4u8..)
+        .filter_map(|((from, to), register)| match (from, to) {
+            (None, Some(to)) => {
+                Some(RegisterChange::Added { register, value: RegisterValue::new(to) })
+            }
+            (Some(from), None) => {
+                Some(RegisterChange::Removed { register, value: RegisterValue::new(from) })
+            }
+            (Some(from), Some(to)) if from != to => Some(RegisterChange::Changed {
+                register,
+                from: RegisterValue::new(from),
+                to: RegisterValue::new(to),
+            }),
+            _ => None,
+        })
+        .collect()
+}
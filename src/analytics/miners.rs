@@ -0,0 +1,192 @@
+//! Attribution of blocks to miners, from the reward outputs of each block.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::time::sleep;
+use tracing::{debug, info};
+
+use crate::{
+    address::{EncodedAddress, ErgoAddress, NetworkPrefix},
+    chain::fee,
+    clients::node::NodeClient,
+    trace::ErrorLog,
+    types::{HashDigest, Height, ergo::Block},
+    watcher::BlockFollower,
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Blocks kept by [`MinerTracker`] by default, about a day of blocks.
+pub const DEFAULT_MINER_WINDOW: usize = 720;
+
+/// Who mined a block and what it earned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockReward {
+    pub header_id: HashDigest,
+    pub height: Height,
+    /// P2PK address of the key the rewards are paid to.
+    pub miner: EncodedAddress,
+    /// Emission paid to the miner by the block's first transaction, in nanoERG.
+    pub reward: u64,
+    /// Miner fees paid by the block's transactions, in nanoERG.
+    pub fees: u64,
+}
+
+impl BlockReward {
+    /// Attributes `block` to the key of its first output guarded by the miner reward script,
+    /// or to the key of its PoW solution for blocks without one.
+    pub fn of(network: NetworkPrefix, block: &Block) -> Self {
+        let transactions = &block.transactions.transactions;
+        let key = transactions
+            .iter()
+            .flat_map(|tx| &tx.outputs)
+            .find_map(|o| fee::miner_reward_key(&o.ergo_tree.0))
+            .unwrap_or(block.header.pow_solution.pk.0);
+
+        let reward = transactions.first().map_or(0, |tx| {
+            tx.outputs
+                .iter()
+                .filter(|o| fee::miner_reward_key(&o.ergo_tree.0) == Some(key))
+                .fold(0u64, |total, o| total.saturating_add(o.value))
+        });
+        let fees = transactions
+            .iter()
+            .map(|tx| fee::outputs_fee(&tx.outputs).unwrap_or(u64::MAX))
+            .fold(0u64, u64::saturating_add);
+
+        Self {
+            header_id: block.header.id.clone(),
+            height: block.header.height,
+            miner: ErgoAddress::p2pk(network, &key).into(),
+            reward,
+            fees,
+        }
+    }
+}
+
+/// Blocks, rewards and fees of one miner within the window of a [`MinerStats`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerShare {
+    pub miner: EncodedAddress,
+    pub blocks: u32,
+    pub rewards: u64,
+    pub fees: u64,
+    /// Fraction of the window's blocks.
+    pub share: f64,
+}
+
+/// Per-miner totals over the last blocks, most blocks first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinerStats {
+    pub blocks: u32,
+    /// Height range of the window, `None` before any block.
+    pub from_height: Option<Height>,
+    pub to_height: Option<Height>,
+    pub miners: Vec<MinerShare>,
+}
+
+/// Keeps the [`BlockReward`] of the last `window` blocks.
+#[derive(Debug, Clone)]
+pub struct MinerTracker {
+    network: NetworkPrefix,
+    window: usize,
+    blocks: VecDeque<BlockReward>,
+}
+
+impl MinerTracker {
+    pub fn new(network: NetworkPrefix) -> Self {
+        Self { network, window: DEFAULT_MINER_WINDOW, blocks: VecDeque::new() }
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Attributes `block` and drops the blocks falling out of the window.
+    pub fn apply_block(&mut self, block: &Block) -> &BlockReward {
+        self.record(BlockReward::of(self.network, block))
+    }
+
+    pub fn record(&mut self, reward: BlockReward) -> &BlockReward {
+        self.blocks.push_back(reward);
+        while self.blocks.len() > self.window {
+            self.blocks.pop_front();
+        }
+        self.blocks.back().expect("a block was just recorded")
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &BlockReward> {
+        self.blocks.iter()
+    }
+
+    pub fn report(&self) -> MinerStats {
+        let mut miners: HashMap<&ErgoAddress, MinerShare> = HashMap::new();
+        for block in &self.blocks {
+            let share = miners
+                .entry(block.miner.address())
+                .or_insert_with(|| MinerShare {
+                    miner: block.miner.clone(),
+                    blocks: 0,
+                    rewards: 0,
+                    fees: 0,
+                    share: 0.0,
+                });
+            share.blocks += 1;
+            share.rewards = share.rewards.saturating_add(block.reward);
+            share.fees = share.fees.saturating_add(block.fees);
+        }
+
+        let blocks = self.blocks.len() as u32;
+        let mut miners: Vec<MinerShare> = miners.into_values().collect();
+        for miner in &mut miners {
+            miner.share = miner.blocks as f64 / blocks as f64;
+        }
+        miners.sort_by(|a, b| {
+            b.blocks
+                .cmp(&a.blocks)
+                .then_with(|| a.miner.as_str().cmp(b.miner.as_str()))
+        });
+
+        MinerStats {
+            blocks,
+            from_height: self.blocks.iter().map(|b| b.height).min(),
+            to_height: self.blocks.iter().map(|b| b.height).max(),
+            miners,
+        }
+    }
+}
+
+/// Keeps the miner tracker up to date with new blocks in the background.
+pub fn spawn_miner_tracker(node: NodeClient, tracker: MinerTracker) -> Arc<RwLock<MinerTracker>> {
+    let tracker = Arc::new(RwLock::new(tracker));
+    let cloned_tracker = tracker.clone();
+
+    tokio::spawn(async move {
+        info!("Starting miner tracker...");
+        let mut blocks = BlockFollower::new();
+        let mut errors = ErrorLog::new("miner tracker");
+        loop {
+            let polled = blocks.poll(&node, |block| {
+                let mut tracker = cloned_tracker.write().unwrap();
+                let reward = tracker.apply_block(block);
+                debug!(height = %reward.height, miner = %reward.miner, "Block attributed.");
+            });
+            match polled.await {
+                Ok(_) => errors.success(),
+                Err(e) => errors.failure(&e),
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    tracker
+}
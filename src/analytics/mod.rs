@@ -0,0 +1,17 @@
+pub mod address;
+pub mod assets;
+pub mod cluster;
+pub mod dapps;
+pub mod distribution;
+pub mod feemarket;
+pub mod flow;
+pub mod holders;
+pub mod lineage;
+pub mod miners;
+pub mod orderbook;
+pub mod portfolio;
+pub mod proxy;
+pub mod rolling;
+pub mod sniping;
+pub mod treemap;
+pub mod voting;
@@ -0,0 +1,271 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::{
+    address::{EncodedAddress, ErgoAddress, NetworkPrefix},
+    chain::{ergo_tree, register::RegisterId},
+    clients::node::{BoxQuery, NodeClient, NodeError},
+    trace::ErrorLog,
+    types::{
+        HashDigest, Height,
+        ergo::{Block, UTxO},
+    },
+    watcher::BlockFollower,
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Unspent boxes fetched per request while backfilling.
+const PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderKind {
+    /// Sells the token to whoever pays `price`.
+    FixedPrice,
+    /// `price` is the current highest bid, or the starting price before any bid.
+    Auction,
+}
+
+/// How a sale contract stores the seller in its registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SellerEncoding {
+    /// `SigmaProp` constant of the seller's public key.
+    ProveDlog,
+    /// `Coll[Byte]` of the seller's ErgoTree.
+    ErgoTree,
+}
+
+/// Register layout of a sale contract, matched by ErgoTree template hash.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaleContract {
+    pub name: String,
+    pub kind: OrderKind,
+    pub template_hash: HashDigest,
+    /// Register (4 to 9) holding the price in nanoErgs as a `Long`.
+    pub price_register: u8,
+    /// Register (4 to 9) holding the seller.
+    pub seller_register: u8,
+    pub seller_encoding: SellerEncoding,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub box_id: HashDigest,
+    pub contract: String,
+    pub kind: OrderKind,
+    pub seller: EncodedAddress,
+    pub token_id: HashDigest,
+    pub token_amount: u64,
+    pub price: u64,
+    pub creation_height: Height,
+}
+
+/// Open sale and auction orders, keyed by the box holding the token for sale.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    network: NetworkPrefix,
+    contracts: HashMap<HashDigest, SaleContract>,
+    orders: BTreeMap<HashDigest, Order>,
+}
+
+impl OrderBook {
+    pub fn new(network: NetworkPrefix) -> Self {
+        Self { network, contracts: HashMap::new(), orders: BTreeMap::new() }
+    }
+
+    pub fn register(&mut self, contract: SaleContract) {
+        self.contracts
+            .insert(contract.template_hash.clone(), contract);
+    }
+
+    /// Reads the order held by `utxo`, if it is guarded by a registered sale contract and its
+    /// registers decode. The first token of the box is the one for sale.
+    pub fn decode(&self, utxo: &UTxO) -> Option<Order> {
+        let hash = ergo_tree::template_hash(&utxo.ergo_tree.0).ok()?;
+        let contract = self.contracts.get(&hash)?;
+        let price = utxo
+            .register::<i64>(RegisterId::new(contract.price_register)?)
+            .ok()?;
+        let seller = RegisterId::new(contract.seller_register)?;
+        let seller = match contract.seller_encoding {
+            SellerEncoding::ProveDlog => {
+                ErgoAddress::p2pk(self.network, &utxo.register(seller).ok()?)
+            }
+            SellerEncoding::ErgoTree => {
+                ErgoAddress::from_ergo_tree(self.network, &utxo.register::<Vec<u8>>(seller).ok()?)
+            }
+        };
+        let token = utxo.tokens.first()?;
+
+        Some(Order {
+            box_id: utxo.id.clone(),
+            contract: contract.name.clone(),
+            kind: contract.kind,
+            seller: seller.into(),
+            token_id: token.id.clone(),
+            token_amount: token.amount,
+            price: u64::try_from(price).ok()?,
+            creation_height: utxo.creation_height,
+        })
+    }
+
+    /// Adds the orders among `boxes`, e.g. from an initial unspent box query.
+    pub fn insert_boxes(&mut self, boxes: &[UTxO]) {
+        for utxo in boxes {
+            if let Some(order) = self.decode(utxo) {
+                self.orders.insert(order.box_id.clone(), order);
+            }
+        }
+    }
+
+    /// Closes orders spent by the block's transactions and opens the ones they create,
+    /// including auction boxes recreated with a new bid.
+    pub fn apply_block(&mut self, block: &Block) {
+        for tx in &block.transactions.transactions {
+            for input in &tx.inputs {
+                self.orders.remove(&input.id);
+            }
+            self.insert_boxes(&tx.outputs);
+        }
+    }
+
+    /// Template hashes of the registered contracts.
+    pub fn template_hashes(&self) -> impl Iterator<Item = &HashDigest> {
+        self.contracts.keys()
+    }
+
+    pub fn get(&self, box_id: &HashDigest) -> Option<&Order> {
+        self.orders.get(box_id)
+    }
+
+    /// Open orders for a token, cheapest first.
+    pub fn orders_for_token(&self, token_id: &HashDigest) -> Vec<&Order> {
+        let mut orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|o| &o.token_id == token_id)
+            .collect();
+        orders.sort_by_key(|o| (o.price, o.creation_height));
+        orders
+    }
+
+    pub fn orders_by_seller(&self, seller: &ErgoAddress) -> Vec<&Order> {
+        self.orders
+            .values()
+            .filter(|o| o.seller == *seller)
+            .collect()
+    }
+
+    /// Cheapest fixed-price order for a token.
+    pub fn best_offer(&self, token_id: &HashDigest) -> Option<&Order> {
+        self.orders_for_token(token_id)
+            .into_iter()
+            .find(|o| o.kind == OrderKind::FixedPrice)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Order> {
+        self.orders.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+/// Unspent boxes guarded by the contract of `template_hash`, e.g. to backfill a book with the
+/// orders opened before it started following blocks.
+pub async fn fetch_unspent(
+    node: &NodeClient,
+    template_hash: &HashDigest,
+) -> Result<Vec<UTxO>, NodeError> {
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+    loop {
+        let query = BoxQuery::new().offset(offset).limit(PAGE_SIZE);
+        let page = node
+            .get_unspent_boxes_by_template_hash(template_hash, &query)
+            .await?;
+        if page.items.is_empty() {
+            break;
+        }
+        offset += page.items.len() as u64;
+        boxes.extend(page.items.into_iter().map(|indexed| indexed.utxo));
+        if offset >= page.total {
+            break;
+        }
+    }
+    Ok(boxes)
+}
+
+/// Backfills the order book with the unspent boxes of its contracts, then keeps it up to date
+/// with new blocks in the background.
+///
+/// Orders only reflect confirmed blocks: mempool transactions are ignored, so an order stays
+/// open until the transaction filling or cancelling it is included.
+pub fn spawn_order_book(node: NodeClient, book: OrderBook) -> Arc<RwLock<OrderBook>> {
+    let book = Arc::new(RwLock::new(book));
+    let cloned_book = book.clone();
+
+    tokio::spawn(async move {
+        info!("Starting order book tracker...");
+        let mut errors = ErrorLog::new("order book tracker");
+        let mut blocks = loop {
+            match backfill(&node, &cloned_book).await {
+                Ok(blocks) => break blocks,
+                Err(e) => errors.failure(&e),
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        };
+        errors.success();
+        let orders = cloned_book.read().unwrap().len();
+        info!(orders, "Order book backfilled.");
+
+        loop {
+            let mut applied = 0;
+            let polled = blocks.poll(&node, |block| {
+                cloned_book.write().unwrap().apply_block(block);
+                applied += 1;
+            });
+            match polled.await {
+                Ok(_) => errors.success(),
+                Err(e) => errors.failure(&e),
+            }
+            if applied > 0 {
+                let orders = cloned_book.read().unwrap().len();
+                info!(blocks = applied, orders, "Order book updated.");
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    book
+}
+
+/// Loads the unspent boxes of every contract into `book`. The returned follower starts at
+/// the tip seen before loading, so blocks spending boxes meanwhile still close their orders.
+async fn backfill(node: &NodeClient, book: &RwLock<OrderBook>) -> Result<BlockFollower, NodeError> {
+    let blocks = match node.get_last_n_headers(1).await?.pop() {
+        Some(tip) => BlockFollower::after(tip.height),
+        None => BlockFollower::new(),
+    };
+    let hashes: Vec<HashDigest> = book.read().unwrap().template_hashes().cloned().collect();
+    for hash in hashes {
+        let boxes = fetch_unspent(node, &hash).await?;
+        book.write().unwrap().insert_boxes(&boxes);
+    }
+    Ok(blocks)
+}
@@ -0,0 +1,215 @@
+//! Valuation of the holdings of an address in ERG and USD: tokens are priced by the reserves
+//! of ERG/token AMM pools, and ERG by the ERG/USD oracle pool.
+
+use std::{cmp::Reverse, collections::HashMap, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::{
+    address::ErgoAddress,
+    chain::register::RegisterId,
+    clients::node::{Balance, BoxQuery, NodeClient, NodeError},
+    tokens::TokenRegistry,
+    trace::ErrorLog,
+    types::{HashDigest, TimestampMillis, ergo::UTxO},
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Pool boxes prices are read from, each found by the NFT it holds.
+#[derive(Debug, Clone, Default)]
+pub struct PriceFeeds {
+    /// NFT of the ERG/USD oracle pool, whose box holds nanoERG per USD in R4.
+    pub erg_usd_oracle: Option<HashDigest>,
+    /// NFTs of ERG/token AMM pools, see [`AmmPool`].
+    pub amm_pools: Vec<HashDigest>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prices {
+    pub nano_ergs_per_usd: Option<u64>,
+    /// nanoERG per smallest unit of each token.
+    pub tokens: HashMap<HashDigest, f64>,
+    pub updated: TimestampMillis,
+}
+
+impl Prices {
+    /// `nano_ergs` in USD, with an oracle rate.
+    pub fn usd(&self, nano_ergs: u64) -> Option<f64> {
+        self.nano_ergs_per_usd
+            .map(|rate| nano_ergs as f64 / rate as f64)
+    }
+}
+
+/// An ERG/token AMM pool box: it holds the pool NFT, the LP token and the pooled token, in
+/// this order, and its value is the pooled ERG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmmPool {
+    pub nft: HashDigest,
+    pub nano_ergs: u64,
+    pub token_id: HashDigest,
+    pub token_amount: u64,
+}
+
+impl AmmPool {
+    pub fn decode(utxo: &UTxO) -> Option<Self> {
+        let [nft, _lp, token] = &utxo.tokens[..] else {
+            return None;
+        };
+        (token.amount > 0).then(|| Self {
+            nft: nft.id.clone(),
+            nano_ergs: utxo.value,
+            token_id: token.id.clone(),
+            token_amount: token.amount,
+        })
+    }
+
+    /// nanoERG per smallest unit of the pooled token, at the pool's reserves.
+    pub fn price(&self) -> f64 {
+        self.nano_ergs as f64 / self.token_amount as f64
+    }
+}
+
+/// nanoERG per USD in R4 of an oracle pool box.
+pub fn oracle_rate(utxo: &UTxO) -> Option<u64> {
+    let rate = utxo.register::<i64>(RegisterId::R4).ok()?;
+    u64::try_from(rate).ok().filter(|rate| *rate > 0)
+}
+
+async fn pool_box(node: &NodeClient, nft: &HashDigest) -> Result<Option<UTxO>, NodeError> {
+    let page = node
+        .get_unspent_boxes_by_token_id(nft, &BoxQuery::new().limit(1))
+        .await?;
+    Ok(page.items.into_iter().next().map(|b| b.utxo))
+}
+
+/// Reads the current prices of `feeds`. Pools whose box is missing or doesn't decode are
+/// left out.
+pub async fn fetch_prices(node: &NodeClient, feeds: &PriceFeeds) -> Result<Prices, NodeError> {
+    let mut prices = Prices { updated: TimestampMillis::now(), ..Default::default() };
+    if let Some(nft) = &feeds.erg_usd_oracle {
+        prices.nano_ergs_per_usd = pool_box(node, nft).await?.as_ref().and_then(oracle_rate);
+    }
+    for nft in &feeds.amm_pools {
+        if let Some(pool) = pool_box(node, nft)
+            .await?
+            .as_ref()
+            .and_then(AmmPool::decode)
+        {
+            prices.tokens.insert(pool.token_id.clone(), pool.price());
+        }
+    }
+    Ok(prices)
+}
+
+/// Keeps the prices of `feeds` up to date in the background.
+pub fn spawn_prices(node: NodeClient, feeds: PriceFeeds) -> Arc<ArcSwap<Prices>> {
+    let prices = Arc::new(ArcSwap::from_pointee(Prices::default()));
+    let cloned_prices = prices.clone();
+
+    tokio::spawn(async move {
+        info!(pools = feeds.amm_pools.len(), "Starting price feeds...");
+        let mut errors = ErrorLog::new("price feeds");
+        loop {
+            match fetch_prices(&node, &feeds).await {
+                Ok(fetched) => {
+                    errors.success();
+                    cloned_prices.store(Arc::new(fetched));
+                }
+                Err(e) => errors.failure(&e),
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    prices
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetValuation {
+    pub token_id: HashDigest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// In the token's smallest unit.
+    pub amount: u64,
+    /// The amount in whole units with the token's name, e.g. `12.50 SigUSD`.
+    pub formatted: String,
+    /// `None` for tokens without a price.
+    pub nano_ergs: Option<u64>,
+    pub usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Valuation {
+    pub address: String,
+    pub nano_ergs: u64,
+    /// Most valuable first, unpriced tokens last.
+    pub assets: Vec<AssetValuation>,
+    /// ERG and priced tokens, in nanoERG.
+    pub total_nano_ergs: u64,
+    pub total_usd: Option<f64>,
+    pub prices_updated: TimestampMillis,
+}
+
+/// Values a confirmed `balance` of `address` at `prices`.
+pub fn valuate(
+    address: &ErgoAddress,
+    balance: &Balance,
+    tokens: &TokenRegistry,
+    prices: &Prices,
+) -> Valuation {
+    let mut assets: Vec<AssetValuation> = balance
+        .tokens
+        .iter()
+        .map(|token| {
+            let nano_ergs = prices
+                .tokens
+                .get(&token.token_id)
+                .map(|price| (token.amount as f64 * price) as u64);
+            AssetValuation {
+                token_id: token.token_id.clone(),
+                name: tokens.get(&token.token_id).and_then(|m| m.name),
+                amount: token.amount,
+                formatted: tokens.format_token_amount(&token.token_id, token.amount),
+                nano_ergs,
+                usd: nano_ergs.and_then(|v| prices.usd(v)),
+            }
+        })
+        .collect();
+    assets.sort_by(|a, b| {
+        Reverse(a.nano_ergs)
+            .cmp(&Reverse(b.nano_ergs))
+            .then_with(|| a.token_id.cmp(&b.token_id))
+    });
+
+    let total_nano_ergs = assets
+        .iter()
+        .filter_map(|a| a.nano_ergs)
+        .fold(balance.nano_ergs, u64::saturating_add);
+    Valuation {
+        address: address.to_string(),
+        nano_ergs: balance.nano_ergs,
+        assets,
+        total_nano_ergs,
+        total_usd: prices.usd(total_nano_ergs),
+        prices_updated: prices.updated,
+    }
+}
+
+/// Values the confirmed holdings of `address`, caching the token metadata of its balance.
+pub async fn value(
+    node: &NodeClient,
+    tokens: &TokenRegistry,
+    prices: &Prices,
+    address: &ErgoAddress,
+) -> Result<Valuation, NodeError> {
+    let balance = node.get_balance(address).await?.confirmed;
+    tokens.remember_balances(&balance.tokens);
+    Ok(valuate(address, &balance, tokens, prices))
+}
@@ -0,0 +1,211 @@
+//! Proxy contracts of ErgoPay flows: a wallet pays an order into a P2S box that a service
+//! executes later, or that the buyer can take back through the contract's refund path.
+//!
+//! A contract is described by one instance of it, whose other instances only differ in the
+//! segregated constant holding the buyer's refund key.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address::{EncodedAddress, ErgoAddress, NetworkPrefix},
+    chain::{
+        ergo_tree::{self, ErgoTreeParts},
+        register::{self, Constant},
+    },
+    codec::CodecError,
+    types::{
+        HashDigest, Height, HexBytes,
+        ergo::{IndexedTransaction, UTxO},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProxyError {
+    #[error("Invalid ErgoTree for proxy contract `{0}`: {1}")]
+    ErgoTree(String, CodecError),
+
+    #[error("Constant {index} of proxy contract `{name}` is not a public key")]
+    RefundKey { name: String, index: usize },
+}
+
+/// A proxy contract and where its instances keep the buyer's refund key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyContract {
+    pub name: String,
+    /// Any instance of the contract.
+    pub ergo_tree: HexBytes,
+    /// Segregated constant holding the buyer's `SigmaProp` public key.
+    pub refund_key_constant: usize,
+}
+
+impl ProxyContract {
+    /// Checks that the tree parses and holds a public key at the refund constant.
+    pub fn validate(&self) -> Result<HashDigest, ProxyError> {
+        let parts = self.parts()?;
+        self.read_refund_key(&parts)?;
+        Ok(parts.template_hash())
+    }
+
+    /// The instance of the contract refunding to `refund_key`.
+    pub fn build(&self, refund_key: &[u8; 33]) -> Result<HexBytes, ProxyError> {
+        let key = Constant::ProveDlog(*refund_key).encode();
+        self.parts()?
+            .with_constant(self.refund_key_constant, &key.0)
+            .map(HexBytes)
+            .map_err(|e| ProxyError::ErgoTree(self.name.clone(), e))
+    }
+
+    /// Address to pay an order into, refundable to `refund_key`.
+    pub fn address(
+        &self,
+        network: NetworkPrefix,
+        refund_key: &[u8; 33],
+    ) -> Result<ErgoAddress, ProxyError> {
+        Ok(ErgoAddress::from_ergo_tree(network, &self.build(refund_key)?.0))
+    }
+
+    /// Refund key of an instance of this contract.
+    pub fn refund_key(&self, ergo_tree: &[u8]) -> Option<[u8; 33]> {
+        let parts = ErgoTreeParts::parse(ergo_tree).ok()?;
+        self.read_refund_key(&parts).ok()
+    }
+
+    fn parts(&self) -> Result<ErgoTreeParts<'_>, ProxyError> {
+        ErgoTreeParts::parse(&self.ergo_tree.0)
+            .map_err(|e| ProxyError::ErgoTree(self.name.clone(), e))
+    }
+
+    fn read_refund_key(&self, parts: &ErgoTreeParts) -> Result<[u8; 33], ProxyError> {
+        let invalid =
+            || ProxyError::RefundKey { name: self.name.clone(), index: self.refund_key_constant };
+        let constants = parts.constant_list().map_err(|_| invalid())?;
+        let constant = constants
+            .get(self.refund_key_constant)
+            .ok_or_else(invalid)?;
+        register::decode_prove_dlog(constant).map_err(|_| invalid())
+    }
+}
+
+/// An order paid into a proxy contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyOrder {
+    pub box_id: HashDigest,
+    pub contract: String,
+    /// P2PK address of the refund key.
+    pub buyer: EncodedAddress,
+    pub value: u64,
+    pub tokens: Vec<(HashDigest, u64)>,
+    pub creation_height: Height,
+}
+
+/// How an order left its proxy box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyOutcome {
+    /// Spent by the service, together with boxes of its own.
+    Executed,
+    /// Taken back by the buyer: only the buyer's boxes are spent alongside it, and an output
+    /// pays the buyer.
+    Refunded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ProxyEvent {
+    Received { order: ProxyOrder },
+    Closed { order: ProxyOrder, outcome: ProxyOutcome, transaction_id: HashDigest },
+}
+
+/// Recognizes proxy boxes by template hash and follows their orders until they are closed.
+#[derive(Debug, Clone)]
+pub struct ProxyTracker {
+    network: NetworkPrefix,
+    contracts: HashMap<HashDigest, ProxyContract>,
+    open: HashMap<HashDigest, ProxyOrder>,
+}
+
+impl ProxyTracker {
+    pub fn new(network: NetworkPrefix) -> Self {
+        Self { network, contracts: HashMap::new(), open: HashMap::new() }
+    }
+
+    pub fn register(&mut self, contract: ProxyContract) -> Result<(), ProxyError> {
+        let hash = contract.validate()?;
+        self.contracts.insert(hash, contract);
+        Ok(())
+    }
+
+    /// The order held by `utxo`, if it is guarded by a registered proxy contract.
+    pub fn recognize(&self, utxo: &UTxO) -> Option<ProxyOrder> {
+        let hash = ergo_tree::template_hash(&utxo.ergo_tree.0).ok()?;
+        let contract = self.contracts.get(&hash)?;
+        let refund_key = contract.refund_key(&utxo.ergo_tree.0)?;
+        Some(ProxyOrder {
+            box_id: utxo.id.clone(),
+            contract: contract.name.clone(),
+            buyer: ErgoAddress::p2pk(self.network, &refund_key).into(),
+            value: utxo.value,
+            tokens: utxo
+                .tokens
+                .iter()
+                .map(|t| (t.id.clone(), t.amount))
+                .collect(),
+            creation_height: utxo.creation_height,
+        })
+    }
+
+    /// Closes the orders spent by a transaction and opens the ones it pays.
+    pub fn apply(&mut self, tx: &IndexedTransaction) -> Vec<ProxyEvent> {
+        let mut events = Vec::new();
+        for input in &tx.inputs {
+            if let Some(order) = self.open.remove(&input.id) {
+                let outcome = outcome(&order, &tx.inputs, &tx.outputs);
+                events.push(ProxyEvent::Closed { order, outcome, transaction_id: tx.id.clone() });
+            }
+        }
+        for output in &tx.outputs {
+            if let Some(order) = self.recognize(output) {
+                self.open.insert(order.box_id.clone(), order.clone());
+                events.push(ProxyEvent::Received { order });
+            }
+        }
+        events
+    }
+
+    pub fn get(&self, box_id: &HashDigest) -> Option<&ProxyOrder> {
+        self.open.get(box_id)
+    }
+
+    /// Open orders of a buyer, oldest first.
+    pub fn orders_by_buyer(&self, buyer: &ErgoAddress) -> Vec<&ProxyOrder> {
+        let mut orders: Vec<&ProxyOrder> =
+            self.open.values().filter(|o| o.buyer == *buyer).collect();
+        orders.sort_by_key(|o| (o.creation_height, &o.box_id));
+        orders
+    }
+
+    pub fn len(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+}
+
+/// Whether the spend of `order` by a transaction is the buyer's refund. Executions spend boxes
+/// of the service (e.g. a pool or a bank box) that the buyer couldn't sign for.
+pub fn outcome(order: &ProxyOrder, inputs: &[UTxO], outputs: &[UTxO]) -> ProxyOutcome {
+    let Some(buyer_tree) = order.buyer.ergo_tree() else {
+        return ProxyOutcome::Executed;
+    };
+    let only_buyer_inputs = inputs
+        .iter()
+        .all(|i| i.id == order.box_id || i.ergo_tree == buyer_tree);
+    let pays_buyer = outputs.iter().any(|o| o.ergo_tree == buyer_tree);
+    if only_buyer_inputs && pays_buyer { ProxyOutcome::Refunded } else { ProxyOutcome::Executed }
+}
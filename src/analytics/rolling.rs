@@ -0,0 +1,356 @@
+//! Time-windowed aggregates of the transactions entering the mempool: rate, fees and volume
+//! by token and by dApp, kept in a ring of fixed-width buckets.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    analytics::dapps::{self, DappVolume, Volume},
+    chain::fee,
+    clock::{self, Clock},
+    templates::{self, TemplateRegistry},
+    types::{HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Window of [`RollingWindow`] aggregates by default.
+pub const DEFAULT_ROLLING_WINDOW: Duration = Duration::from_secs(600);
+
+/// Buckets a window is split into by default.
+pub const DEFAULT_ROLLING_BUCKETS: usize = 60;
+
+/// Tokens and dApps with the most transactions rendered by [`RollingReport::to_prometheus`].
+const PROMETHEUS_TOKENS: usize = 20;
+const PROMETHEUS_DAPPS: usize = 20;
+
+/// Amount of one token moved by the transactions of the window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenVolume {
+    pub token_id: HashDigest,
+    /// Sum of the token's amounts in transaction outputs.
+    pub amount: u64,
+    pub transactions: u64,
+}
+
+/// Aggregates over the window ending at `at`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollingReport {
+    pub at: TimestampMillis,
+    pub window_secs: u64,
+    /// Transactions that entered the mempool during the window.
+    pub transactions: u64,
+    pub transactions_per_sec: f64,
+    /// Miner fees in nanoERG, `None` without transactions. Transactions whose fee outputs
+    /// overflow are left out.
+    pub average_fee: Option<f64>,
+    pub median_fee: Option<u64>,
+    pub p90_fee: Option<u64>,
+    pub p99_fee: Option<u64>,
+    /// Sum of the output values, in nanoERG.
+    pub nano_ergs: u64,
+    /// Most transactions first.
+    pub tokens: Vec<TokenVolume>,
+    /// Transactions attributed to each dApp whose contracts they spend or create, most
+    /// transactions first. A transaction touching several dApps counts for each.
+    pub dapps: Vec<DappVolume>,
+}
+
+impl RollingReport {
+    /// Renders the rate, fees and busiest tokens in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+
+        gauge(
+            "hergmes_rolling_transactions_per_second",
+            "Transactions entering the mempool per second over the rolling window.",
+            &[(String::new(), self.transactions_per_sec)],
+        );
+        let fees: Vec<_> =
+            [("0.5", self.median_fee), ("0.9", self.p90_fee), ("0.99", self.p99_fee)]
+                .into_iter()
+                .filter_map(|(q, fee)| Some((format!("{{quantile=\"{q}\"}}"), fee? as f64)))
+                .collect();
+        gauge(
+            "hergmes_rolling_fee",
+            "Miner fee quantiles of the transactions of the rolling window, in nanoERG.",
+            &fees,
+        );
+        if let Some(average) = self.average_fee {
+            gauge(
+                "hergmes_rolling_average_fee",
+                "Average miner fee of the transactions of the rolling window, in nanoERG.",
+                &[(String::new(), average)],
+            );
+        }
+        gauge(
+            "hergmes_rolling_erg_volume",
+            "nanoERG in the outputs of the transactions of the rolling window.",
+            &[(String::new(), self.nano_ergs as f64)],
+        );
+        let tokens: Vec<_> = self
+            .tokens
+            .iter()
+            .take(PROMETHEUS_TOKENS)
+            .map(|t| (format!("{{token_id=\"{}\"}}", t.token_id), t.amount as f64))
+            .collect();
+        gauge(
+            "hergmes_rolling_token_volume",
+            "Token amount in the outputs of the transactions of the rolling window.",
+            &tokens,
+        );
+        let dapps: Vec<_> = self.dapps.iter().take(PROMETHEUS_DAPPS).collect();
+        let per_dapp = |value: &dyn Fn(&DappVolume) -> f64| -> Vec<(String, f64)> {
+            dapps
+                .iter()
+                .map(|d| (format!("{{dapp=\"{}\"}}", d.dapp), value(d)))
+                .collect()
+        };
+        let window_secs = self.window_secs.max(1) as f64;
+        gauge(
+            "hergmes_rolling_dapp_transactions_per_second",
+            "Transactions of the dApp entering the mempool per second over the rolling window.",
+            &per_dapp(&|d| d.transactions as f64 / window_secs),
+        );
+        gauge(
+            "hergmes_rolling_dapp_erg_volume",
+            "nanoERG in the outputs of the dApp's transactions of the rolling window.",
+            &per_dapp(&|d| d.nano_ergs as f64),
+        );
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Bucket {
+    start: TimestampMillis,
+    fees: Vec<u64>,
+    volume: Volume,
+    dapps: BTreeMap<String, Volume>,
+}
+
+impl Bucket {
+    fn new(start: TimestampMillis) -> Self {
+        Self { start, fees: Vec::new(), volume: Volume::default(), dapps: BTreeMap::new() }
+    }
+}
+
+/// Aggregates of the transactions recorded over the last `window`.
+///
+/// The window is split into buckets stored in a ring: recording in a new bucket evicts the
+/// ones that left the window, so memory stays bounded by the bucket count. Reports cover the
+/// buckets overlapping the window, so the oldest one may reach up to a bucket width further.
+#[derive(Debug, Clone)]
+pub struct RollingWindow {
+    window: Duration,
+    buckets: usize,
+    ring: VecDeque<Bucket>,
+    templates: Arc<TemplateRegistry>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for RollingWindow {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROLLING_WINDOW)
+    }
+}
+
+impl RollingWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window: window.max(Duration::from_millis(1)),
+            buckets: DEFAULT_ROLLING_BUCKETS,
+            ring: VecDeque::new(),
+            templates: Arc::new(templates::BUILTIN.clone()),
+            clock: clock::system(),
+        }
+    }
+
+    /// Reports as of the time of `clock` in [`spawn_rolling`] instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Attributes transactions to dApps with `templates` instead of the built-in ones.
+    pub fn with_templates(mut self, templates: Arc<TemplateRegistry>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    pub fn with_buckets(mut self, buckets: usize) -> Self {
+        self.buckets = buckets.clamp(1, self.window.as_millis() as usize);
+        self
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    fn bucket_width(&self) -> u64 {
+        self.window.as_millis() as u64 / self.buckets as u64
+    }
+
+    /// Records a transaction entering the mempool at `at`. Transactions older than the window
+    /// of the latest one recorded are dropped.
+    pub fn record(&mut self, at: TimestampMillis, tx: &UnconfirmedTransaction) {
+        let width = self.bucket_width();
+        let start = TimestampMillis(at.0 - at.0 % width);
+        if self
+            .ring
+            .back()
+            .is_some_and(|latest| self.expired(start, latest.start))
+        {
+            return;
+        }
+
+        let i = self.ring.partition_point(|b| b.start < start);
+        if self.ring.get(i).is_none_or(|b| b.start != start) {
+            self.ring.insert(i, Bucket::new(start));
+        }
+        let bucket = &mut self.ring[i];
+        if let Ok(fee) = fee::outputs_fee(&tx.outputs) {
+            bucket.fees.push(fee);
+        }
+        bucket.volume.add(&tx.outputs);
+        for dapp in self.templates.classify_transaction(tx) {
+            bucket
+                .dapps
+                .entry(dapp.to_owned())
+                .or_default()
+                .add(&tx.outputs);
+        }
+
+        self.evict();
+    }
+
+    /// Whether the bucket starting at `start` is out of every window ending after `latest`.
+    fn expired(&self, start: TimestampMillis, latest: TimestampMillis) -> bool {
+        start.0 + self.bucket_width() + self.window.as_millis() as u64 <= latest.0
+    }
+
+    fn evict(&mut self) {
+        let Some(latest) = self.ring.back().map(|b| b.start) else { return };
+        while self
+            .ring
+            .front()
+            .is_some_and(|b| self.expired(b.start, latest))
+        {
+            self.ring.pop_front();
+        }
+    }
+
+    /// Records the transactions of `snapshot` missing from `previous` as entering the mempool
+    /// at the snapshot's update time.
+    pub fn apply_snapshot(&mut self, previous: &MempoolSnapshot, snapshot: &MempoolSnapshot) {
+        let known: HashSet<&HashDigest> = previous.transactions.iter().map(|tx| &tx.id).collect();
+        for tx in snapshot
+            .transactions
+            .iter()
+            .filter(|tx| !known.contains(&tx.id))
+        {
+            self.record(snapshot.last_update, tx);
+        }
+    }
+
+    fn buckets_at(&self, now: TimestampMillis) -> impl Iterator<Item = &Bucket> {
+        let from = now.saturating_sub(self.window);
+        self.ring
+            .iter()
+            .filter(move |b| b.start.0 + self.bucket_width() > from.0 && b.start <= now)
+    }
+
+    /// Miner fee at quantile `q` (between 0 and 1) of the transactions of the window ending
+    /// at `now`, by nearest rank.
+    pub fn fee_percentile(&self, now: TimestampMillis, q: f64) -> Option<u64> {
+        let mut fees: Vec<u64> = self
+            .buckets_at(now)
+            .flat_map(|b| b.fees.iter().copied())
+            .collect();
+        fees.sort_unstable();
+        percentile(&fees, q)
+    }
+
+    pub fn report(&self, now: TimestampMillis) -> RollingReport {
+        let (mut fees, mut volume) = (Vec::new(), Volume::default());
+        let mut dapps: BTreeMap<String, Volume> = BTreeMap::new();
+        for bucket in self.buckets_at(now) {
+            fees.extend_from_slice(&bucket.fees);
+            volume.merge(&bucket.volume);
+            for (dapp, dapp_volume) in &bucket.dapps {
+                dapps.entry(dapp.clone()).or_default().merge(dapp_volume);
+            }
+        }
+        fees.sort_unstable();
+        let transactions = volume.transactions;
+
+        RollingReport {
+            at: now,
+            window_secs: self.window.as_secs(),
+            transactions,
+            transactions_per_sec: transactions as f64 / self.window.as_secs_f64(),
+            average_fee: (!fees.is_empty())
+                .then(|| fees.iter().map(|f| *f as f64).sum::<f64>() / fees.len() as f64),
+            median_fee: percentile(&fees, 0.5),
+            p90_fee: percentile(&fees, 0.9),
+            p99_fee: percentile(&fees, 0.99),
+            nano_ergs: volume.nano_ergs,
+            tokens: volume.token_volumes(),
+            dapps: dapps::dapp_volumes(&dapps),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[u64], q: f64) -> Option<u64> {
+    let rank = (q.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.max(1) - 1).copied()
+}
+
+/// Keeps the rolling report up to date from the mempool snapshots. Transactions already in
+/// the first snapshot seen are not recorded, as their entry time is unknown.
+pub fn spawn_rolling(
+    mempool: Arc<ArcSwap<MempoolSnapshot>>,
+    mut window: RollingWindow,
+) -> Arc<ArcSwap<RollingReport>> {
+    let report = Arc::new(ArcSwap::from_pointee(window.report(window.clock.timestamp())));
+    let cloned_report = report.clone();
+
+    tokio::spawn(async move {
+        info!(window = ?window.window(), "Starting rolling window aggregates...");
+        let mut previous: Option<Arc<MempoolSnapshot>> = None;
+        loop {
+            let snapshot = mempool.load_full();
+            if snapshot.last_update > TimestampMillis(0) {
+                if let Some(previous) = &previous
+                    && snapshot.last_update > previous.last_update
+                {
+                    window.apply_snapshot(previous, &snapshot);
+                }
+                previous = Some(snapshot);
+            }
+            cloned_report.store(Arc::new(window.report(window.clock.timestamp())));
+            window.clock.sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    report
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use crate::{
+    chain::{fee, value::ValueError},
+    types::{
+        HashDigest, HexBytes,
+        ergo::{BoxCandidate, UTxO, UnconfirmedTransaction},
+    },
+};
+
+/// A transaction about to be submitted, with its inputs resolved to boxes.
+#[derive(Debug, Clone)]
+pub struct PlannedTransaction {
+    pub inputs: Vec<UTxO>,
+    pub outputs: Vec<BoxCandidate>,
+}
+
+impl PlannedTransaction {
+    pub fn fee(&self) -> Result<u64, ValueError> {
+        fee::candidates_fee(&self.outputs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The mempool transaction spends some of the planned inputs.
+    DoubleSpend { box_ids: Vec<HashDigest> },
+    /// The mempool transaction spends other boxes guarded by a contract the planned
+    /// transaction also interacts with (e.g. the same pool or order book).
+    SharedContract { ergo_trees: Vec<HexBytes> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub transaction_id: HashDigest,
+    pub kind: ConflictKind,
+    pub fee: u64,
+    /// Whether the mempool transaction pays at least the planned fee, so miners would
+    /// prefer it.
+    pub outbids: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    None,
+    /// Contention on a shared contract, but the planned transaction pays more.
+    Low,
+    /// Contention on a shared contract with an equal or better-paying transaction.
+    Medium,
+    /// Some planned input is already being spent.
+    High,
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskReport {
+    pub planned_fee: u64,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl RiskReport {
+    pub fn level(&self) -> RiskLevel {
+        self.conflicts
+            .iter()
+            .map(|c| match (&c.kind, c.outbids) {
+                (ConflictKind::DoubleSpend { .. }, _) => RiskLevel::High,
+                (ConflictKind::SharedContract { .. }, true) => RiskLevel::Medium,
+                (ConflictKind::SharedContract { .. }, false) => RiskLevel::Low,
+            })
+            .max()
+            .unwrap_or(RiskLevel::None)
+    }
+
+    /// The highest fee paid by a conflicting transaction, i.e. the fee to beat.
+    pub fn highest_competing_fee(&self) -> Option<u64> {
+        self.conflicts.iter().map(|c| c.fee).max()
+    }
+}
+
+/// Checks `mempool` for transactions that conflict with or may front-run `planned`.
+///
+/// Fees are compared as absolute amounts; P2PK inputs don't count as shared contracts.
+pub fn analyze(
+    planned: &PlannedTransaction,
+    mempool: &[UnconfirmedTransaction],
+) -> Result<RiskReport, ValueError> {
+    let planned_fee = planned.fee()?;
+    let box_ids: HashSet<&HashDigest> = planned.inputs.iter().map(|i| &i.id).collect();
+    let contracts: HashSet<&HexBytes> = planned
+        .inputs
+        .iter()
+        .map(|i| &i.ergo_tree)
+        .filter(|tree| !is_p2pk(tree))
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for tx in mempool {
+        let fee = fee::outputs_fee(&tx.outputs)?;
+        let outbids = fee >= planned_fee;
+
+        let spent: Vec<HashDigest> = tx
+            .inputs
+            .iter()
+            .filter(|i| box_ids.contains(&i.utxo.id))
+            .map(|i| i.utxo.id.clone())
+            .collect();
+        if !spent.is_empty() {
+            let kind = ConflictKind::DoubleSpend { box_ids: spent };
+            conflicts.push(Conflict { transaction_id: tx.id.clone(), kind, fee, outbids });
+            continue;
+        }
+
+        let mut shared: Vec<HexBytes> = Vec::new();
+        for input in &tx.inputs {
+            if contracts.contains(&input.utxo.ergo_tree) && !shared.contains(&input.utxo.ergo_tree)
+            {
+                shared.push(input.utxo.ergo_tree.clone());
+            }
+        }
+        if !shared.is_empty() {
+            let kind = ConflictKind::SharedContract { ergo_trees: shared };
+            conflicts.push(Conflict { transaction_id: tx.id.clone(), kind, fee, outbids });
+        }
+    }
+
+    Ok(RiskReport { planned_fee, conflicts })
+}
+
+/// `ProveDlog` trees: header, constant type, `SigmaProp` constant, then a 33-byte group element.
+fn is_p2pk(tree: &HexBytes) -> bool {
+    tree.0.len() == 36 && tree.0.starts_with(&[0x00, 0x08, 0xcd])
+}
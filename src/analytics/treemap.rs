@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    labels::{LabelCategory, LabelSet},
+    templates::TemplateRegistry,
+    types::ergo::UTxO,
+    watcher::MempoolSnapshot,
+};
+
+/// Group of boxes whose contract is neither a known template nor labelled.
+pub const UNLABELLED: &str = "Unlabelled";
+
+/// A node of a value hierarchy, in the `{ name, value, children }` shape treemap and
+/// flamegraph renderers take. `value` is in nanoErgs and includes the children.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TreemapNode {
+    pub name: String,
+    pub value: u128,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreemapNode>,
+}
+
+/// Groups the value of boxes by contract, two levels deep: by dApp then contract template,
+/// by label category then label for labelled addresses, and by address type under
+/// [`UNLABELLED`] for the rest.
+#[derive(Debug, Clone)]
+pub struct TreemapBuilder<'a> {
+    templates: &'a TemplateRegistry,
+    labels: &'a LabelSet,
+    groups: BTreeMap<String, BTreeMap<String, u128>>,
+}
+
+impl<'a> TreemapBuilder<'a> {
+    pub fn new(templates: &'a TemplateRegistry, labels: &'a LabelSet) -> Self {
+        Self { templates, labels, groups: BTreeMap::new() }
+    }
+
+    pub fn add(&mut self, utxo: &UTxO) {
+        let tree = &utxo.ergo_tree.0;
+        let (group, leaf) = if let Some(template) = self.templates.classify_tree(tree) {
+            (template.dapp.clone(), template.name.clone())
+        } else if let Some(label) = self.labels.get_by_tree(tree) {
+            (category_name(label.category).to_owned(), label.name.clone())
+        } else {
+            let kind = match ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, tree).kind() {
+                AddressType::P2PK => "P2PK",
+                AddressType::P2SH => "P2SH",
+                AddressType::P2S => "P2S",
+            };
+            (UNLABELLED.to_owned(), kind.to_owned())
+        };
+        *self
+            .groups
+            .entry(group)
+            .or_default()
+            .entry(leaf)
+            .or_default() += utxo.value as u128;
+    }
+
+    /// Adds the outputs of every transaction of the snapshot, i.e. where the pending value
+    /// goes.
+    pub fn add_mempool(&mut self, snapshot: &MempoolSnapshot) {
+        for output in snapshot.transactions.iter().flat_map(|tx| &tx.outputs) {
+            self.add(output);
+        }
+    }
+
+    /// The hierarchy under a root named `name`, largest values first at every level, ties by
+    /// name.
+    pub fn build(self, name: &str) -> TreemapNode {
+        let children: Vec<TreemapNode> = self
+            .groups
+            .into_iter()
+            .map(|(group, leaves)| {
+                let children: Vec<TreemapNode> = leaves
+                    .into_iter()
+                    .map(|(name, value)| TreemapNode { name, value, children: Vec::new() })
+                    .collect();
+                node(group, children)
+            })
+            .collect();
+        node(name.to_owned(), children)
+    }
+}
+
+fn node(name: String, mut children: Vec<TreemapNode>) -> TreemapNode {
+    sort(&mut children);
+    let value = children.iter().map(|c| c.value).sum();
+    TreemapNode { name, value, children }
+}
+
+fn sort(nodes: &mut [TreemapNode]) {
+    nodes.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.name.cmp(&b.name)));
+}
+
+fn category_name(category: LabelCategory) -> &'static str {
+    match category {
+        LabelCategory::Exchange => "Exchanges",
+        LabelCategory::Mixer => "Mixers",
+        LabelCategory::Bridge => "Bridges",
+        LabelCategory::Dapp => "dApps",
+        LabelCategory::Miner => "Miners",
+        LabelCategory::Protocol => "Protocols",
+        LabelCategory::Other => "Other",
+    }
+}
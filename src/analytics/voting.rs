@@ -0,0 +1,422 @@
+//! Status of ongoing parameter and soft-fork votes, tallied from the votes of block headers.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, VecDeque},
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::{
+    address::NetworkPrefix,
+    chain::extension::{self, ParameterId, Parameters, VOTING_EPOCH_LENGTH, Vote},
+    clients::node::{NodeClient, NodeError},
+    trace::ErrorLog,
+    types::{Height, ergo::BlockHeader},
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Epochs kept by [`VotingTracker`] by default, the current one included.
+pub const DEFAULT_VOTING_EPOCHS: usize = 4;
+
+/// Headers requested at once while catching up.
+const CHAIN_SLICE_LENGTH: u32 = 1024;
+
+/// Voting rules of a network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VotingSettings {
+    pub epoch_length: u32,
+    /// Epochs over which soft-fork votes are collected.
+    pub soft_fork_epochs: u32,
+    /// Epochs between an approved soft-fork and its activation.
+    pub activation_epochs: u32,
+}
+
+impl VotingSettings {
+    pub const MAINNET: Self =
+        Self { epoch_length: VOTING_EPOCH_LENGTH, soft_fork_epochs: 32, activation_epochs: 32 };
+
+    pub const TESTNET: Self =
+        Self { epoch_length: 128, soft_fork_epochs: 32, activation_epochs: 32 };
+
+    pub fn for_network(network: NetworkPrefix) -> Self {
+        match network {
+            NetworkPrefix::Mainnet => Self::MAINNET,
+            NetworkPrefix::Testnet => Self::TESTNET,
+        }
+    }
+
+    /// Votes a parameter change needs within its epoch: more than half of the blocks.
+    pub fn change_threshold(&self) -> u32 {
+        self.epoch_length / 2 + 1
+    }
+
+    /// Votes a soft-fork needs over its voting epochs: more than 90% of the blocks.
+    pub fn soft_fork_threshold(&self) -> u32 {
+        (self.epoch_length as u64 * self.soft_fork_epochs as u64 * 9 / 10) as u32 + 1
+    }
+
+    pub fn epoch_start(&self, height: Height) -> Height {
+        Height(height.0 - height.0 % self.epoch_length)
+    }
+}
+
+impl Default for VotingSettings {
+    fn default() -> Self {
+        Self::MAINNET
+    }
+}
+
+/// Votes for changing one parameter in one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterVote {
+    /// Name of the parameter, or its id for parameters this crate doesn't know about.
+    pub parameter: String,
+    pub increase: bool,
+    pub votes: u32,
+    pub threshold: u32,
+    /// `votes / threshold`, at least 1 once approved.
+    pub progress: f64,
+    /// Votes at the end of the epoch if its remaining blocks vote like the ones so far.
+    pub projected_votes: u32,
+    pub approved: bool,
+    /// Start of the next epoch, when an approved change takes effect.
+    pub activation_height: Height,
+}
+
+/// Votes of the blocks of one epoch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochVotes {
+    pub start_height: Height,
+    /// Blocks tallied so far.
+    pub blocks: u32,
+    pub soft_fork_votes: u32,
+    /// Most voted first.
+    pub changes: Vec<ParameterVote>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SoftForkPhase {
+    /// Voted for in the current epoch; collecting starts with the next one.
+    Proposed,
+    Voting,
+    /// Approved and waiting for its activation height.
+    Activating,
+    /// Voting ended without enough votes.
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftForkStatus {
+    pub phase: SoftForkPhase,
+    pub starting_height: Height,
+    /// Votes collected since `starting_height`, the current epoch included.
+    pub votes: u32,
+    pub threshold: u32,
+    pub progress: f64,
+    pub voting_end_height: Height,
+    /// Activation height if the soft-fork is, or gets, approved.
+    pub activation_height: Height,
+}
+
+/// Voting status at the last tallied height.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VotingReport {
+    pub height: Option<Height>,
+    /// Oldest first, the current epoch last.
+    pub epochs: Vec<EpochVotes>,
+    pub soft_fork: Option<SoftForkStatus>,
+}
+
+impl VotingReport {
+    pub fn current_epoch(&self) -> Option<&EpochVotes> {
+        self.epochs.last()
+    }
+
+    /// Renders the current epoch and soft-fork in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+
+        let Some(epoch) = self.current_epoch() else {
+            return out;
+        };
+        gauge(
+            "hergmes_voting_epoch_blocks",
+            "Blocks of the current voting epoch tallied so far.",
+            &[(String::new(), epoch.blocks as f64)],
+        );
+        let labels = |v: &ParameterVote| {
+            let direction = if v.increase { "increase" } else { "decrease" };
+            format!("{{parameter=\"{}\",direction=\"{direction}\"}}", v.parameter)
+        };
+        let votes: Vec<_> = epoch
+            .changes
+            .iter()
+            .map(|v| (labels(v), v.votes as f64))
+            .collect();
+        gauge(
+            "hergmes_voting_parameter_votes",
+            "Votes for a parameter change in the current epoch.",
+            &votes,
+        );
+        let progress: Vec<_> = epoch
+            .changes
+            .iter()
+            .map(|v| (labels(v), v.progress))
+            .collect();
+        gauge(
+            "hergmes_voting_parameter_progress",
+            "Votes for a parameter change relative to the approval threshold.",
+            &progress,
+        );
+        if let Some(soft_fork) = &self.soft_fork {
+            gauge(
+                "hergmes_voting_soft_fork_votes",
+                "Votes collected by the ongoing soft-fork.",
+                &[(String::new(), soft_fork.votes as f64)],
+            );
+            gauge(
+                "hergmes_voting_soft_fork_progress",
+                "Votes of the ongoing soft-fork relative to the approval threshold.",
+                &[(String::new(), soft_fork.progress)],
+            );
+            gauge(
+                "hergmes_voting_soft_fork_activation_height",
+                "Activation height of the ongoing soft-fork if approved.",
+                &[(String::new(), soft_fork.activation_height.0 as f64)],
+            );
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Tally {
+    start: Height,
+    blocks: u32,
+    votes: BTreeMap<Vote, u32>,
+}
+
+/// Tallies the votes of the headers of the last epochs.
+///
+/// Headers are applied by height and reorganizations are not followed, as in
+/// [`BlockFollower`](crate::watcher::BlockFollower).
+#[derive(Debug, Clone)]
+pub struct VotingTracker {
+    settings: VotingSettings,
+    epochs: usize,
+    height: Option<Height>,
+    tallies: VecDeque<Tally>,
+    /// Parameters declared at the start of the current epoch, with that height.
+    declared: Option<(Height, Parameters)>,
+}
+
+impl VotingTracker {
+    pub fn new(settings: VotingSettings) -> Self {
+        Self {
+            settings,
+            epochs: DEFAULT_VOTING_EPOCHS,
+            height: None,
+            tallies: VecDeque::new(),
+            declared: None,
+        }
+    }
+
+    pub fn with_epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs.max(1);
+        self
+    }
+
+    /// Height of the last tallied header.
+    pub fn height(&self) -> Option<Height> {
+        self.height
+    }
+
+    /// Tallies the votes of `header`. Headers at or below the last tallied height are ignored.
+    pub fn apply_header(&mut self, header: &BlockHeader) {
+        if self.height.is_some_and(|h| header.height <= h) {
+            return;
+        }
+        self.height = Some(header.height);
+
+        let start = self.settings.epoch_start(header.height);
+        if self.tallies.back().is_none_or(|t| t.start != start) {
+            self.tallies
+                .push_back(Tally { start, blocks: 0, votes: BTreeMap::new() });
+            while self.tallies.len() > self.epochs {
+                self.tallies.pop_front();
+            }
+        }
+        let tally = self.tallies.back_mut().expect("an epoch was just started");
+        tally.blocks += 1;
+        for vote in extension::parse_votes(&header.votes.0) {
+            *tally.votes.entry(vote).or_default() += 1;
+        }
+    }
+
+    /// Sets the parameters declared by the epoch-start block at `epoch_height`, which carry the
+    /// state of an ongoing soft-fork.
+    pub fn set_declared(&mut self, epoch_height: Height, parameters: Parameters) {
+        self.declared = Some((epoch_height, parameters));
+    }
+
+    pub fn report(&self) -> VotingReport {
+        let epochs = self.tallies.iter().map(|t| self.epoch_votes(t)).collect();
+        VotingReport { height: self.height, epochs, soft_fork: self.soft_fork() }
+    }
+
+    fn epoch_votes(&self, tally: &Tally) -> EpochVotes {
+        let threshold = self.settings.change_threshold();
+        let mut changes: Vec<ParameterVote> = tally
+            .votes
+            .iter()
+            .filter_map(|(vote, votes)| {
+                let (parameter, increase) = match vote {
+                    Vote::Increase(id) => (id.name().to_string(), true),
+                    Vote::Decrease(id) => (id.name().to_string(), false),
+                    Vote::Unknown(id) => (id.unsigned_abs().to_string(), *id > 0),
+                    Vote::SoftFork => return None,
+                };
+                let projected =
+                    *votes as u64 * self.settings.epoch_length as u64 / tally.blocks.max(1) as u64;
+                Some(ParameterVote {
+                    parameter,
+                    increase,
+                    votes: *votes,
+                    threshold,
+                    progress: *votes as f64 / threshold as f64,
+                    projected_votes: projected as u32,
+                    approved: *votes >= threshold,
+                    activation_height: tally.start + self.settings.epoch_length,
+                })
+            })
+            .collect();
+        changes.sort_by_key(|v| Reverse(v.votes));
+
+        EpochVotes {
+            start_height: tally.start,
+            blocks: tally.blocks,
+            soft_fork_votes: tally.votes.get(&Vote::SoftFork).copied().unwrap_or(0),
+            changes,
+        }
+    }
+
+    fn soft_fork(&self) -> Option<SoftForkStatus> {
+        let height = self.height?;
+        let current = self.tallies.back()?;
+        let current_votes = current.votes.get(&Vote::SoftFork).copied().unwrap_or(0);
+        let declared = self
+            .declared
+            .as_ref()
+            .filter(|(epoch, _)| *epoch == current.start)
+            .map(|(_, parameters)| parameters);
+        let started = declared.and_then(|p| p.get(ParameterId::SoftForkStartingHeight));
+
+        let length = self.settings.epoch_length;
+        let (phase, starting_height, votes) = match started {
+            Some(start) => {
+                let start = Height(start.max(0) as u32);
+                let collected = declared
+                    .and_then(|p| p.get(ParameterId::SoftForkVotesCollected))
+                    .unwrap_or(0)
+                    .max(0) as u32;
+                let voting_end = start + self.settings.soft_fork_epochs * length;
+                if height < voting_end {
+                    (SoftForkPhase::Voting, start, collected + current_votes)
+                } else if collected >= self.settings.soft_fork_threshold() {
+                    (SoftForkPhase::Activating, start, collected)
+                } else {
+                    (SoftForkPhase::Rejected, start, collected)
+                }
+            }
+            None if current_votes > 0 => (SoftForkPhase::Proposed, current.start + length, 0),
+            None => return None,
+        };
+
+        let threshold = self.settings.soft_fork_threshold();
+        let voting_end_height = starting_height + self.settings.soft_fork_epochs * length;
+        Some(SoftForkStatus {
+            phase,
+            starting_height,
+            votes,
+            threshold,
+            progress: votes as f64 / threshold as f64,
+            voting_end_height,
+            activation_height: voting_end_height + self.settings.activation_epochs * length,
+        })
+    }
+
+    /// Tallies the headers up to the current tip, starting with the first tracked epoch when
+    /// far behind, and fetches the parameters of a new epoch.
+    pub async fn poll(&mut self, node: &NodeClient) -> Result<(), NodeError> {
+        let Some(tip) = node.get_last_n_headers(1).await?.pop() else {
+            return Ok(());
+        };
+        let first_epoch = self
+            .settings
+            .epoch_start(tip.height)
+            .saturating_sub((self.epochs as u32 - 1) * self.settings.epoch_length);
+        let mut from = self
+            .height
+            .map_or(first_epoch, |h| h.next().max(first_epoch));
+
+        while from <= tip.height {
+            let to = (from + CHAIN_SLICE_LENGTH - 1).min(tip.height);
+            let mut headers = node.get_chain_slice(from, to).await?;
+            headers.sort_by_key(|h| h.height);
+            headers.iter().for_each(|h| self.apply_header(h));
+            from = to.next();
+        }
+
+        let epoch = self.settings.epoch_start(tip.height);
+        if self.declared.as_ref().is_none_or(|(e, _)| *e != epoch) {
+            let ids = node.get_header_ids_at_height(epoch).await?;
+            let id = ids
+                .first()
+                .ok_or(NodeError::NotFound(format!("block at height {epoch}")))?;
+            let parameters = node.get_block_extension(id).await?.parameters()?;
+            self.set_declared(epoch, parameters);
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the voting report up to date in the background.
+pub fn spawn_voting(node: NodeClient, mut tracker: VotingTracker) -> Arc<ArcSwap<VotingReport>> {
+    let report = Arc::new(ArcSwap::from_pointee(tracker.report()));
+    let cloned_report = report.clone();
+
+    tokio::spawn(async move {
+        info!("Starting voting tracker...");
+        let mut errors = ErrorLog::new("voting tracker");
+        loop {
+            match tracker.poll(&node).await {
+                Ok(()) => errors.success(),
+                Err(e) => errors.failure(&e),
+            }
+            cloned_report.store(Arc::new(tracker.report()));
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    report
+}
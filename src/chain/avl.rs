@@ -0,0 +1,213 @@
+use crate::{
+    codec::CodecError,
+    hash::blake2b256,
+    types::{Digest, HashDigest, ergo::BlockHeader},
+};
+
+const KEY_LENGTH: usize = 32;
+const LABEL_LENGTH: usize = 32;
+
+const LEAF: u8 = 2;
+const LABEL: u8 = 3;
+const END_OF_TREE: u8 = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AvlError {
+    #[error("Malformed AVL proof: {0}")]
+    Malformed(#[from] CodecError),
+
+    #[error("AVL proof does not match the expected state root.")]
+    RootMismatch,
+
+    #[error("Box {0} does not hash to its key in the proof.")]
+    BoxIdMismatch(HashDigest),
+}
+
+enum Node {
+    Label([u8; LABEL_LENGTH]),
+    Leaf { key: [u8; KEY_LENGTH], value: Vec<u8>, next_key: [u8; KEY_LENGTH] },
+    Internal { balance: u8, left: Box<Node>, right: Box<Node> },
+}
+
+impl Node {
+    fn label(&self) -> [u8; LABEL_LENGTH] {
+        match self {
+            Node::Label(label) => *label,
+            Node::Leaf { key, value, next_key } => {
+                blake2b256(&[&[0u8][..], key, value, next_key].concat())
+            }
+            Node::Internal { balance, left, right } => {
+                blake2b256(&[&[1u8, *balance][..], &left.label(), &right.label()].concat())
+            }
+        }
+    }
+}
+
+/// Verifies a batch lookup proof against `state_root` and returns the value found for each key,
+/// in order (`None` proves the key is absent).
+///
+/// Proofs use the scrypto `BatchAVLProver` format: a post-order packaged tree followed by
+/// direction bits for each lookup, as returned by the node's `/utxo/getBoxesBinaryProof`.
+pub fn verify_lookups(
+    state_root: &Digest<33>,
+    proof: &[u8],
+    keys: &[HashDigest],
+) -> Result<Vec<Option<Vec<u8>>>, AvlError> {
+    let (root, tree_len) = reconstruct(proof)?;
+    if root.label()[..] != state_root.0[..LABEL_LENGTH] {
+        return Err(AvlError::RootMismatch);
+    }
+
+    let mut directions = Directions { proof, index: tree_len * 8 };
+    keys.iter()
+        .map(|key| lookup(&root, &key.0, &mut directions))
+        .collect()
+}
+
+/// Verifies box existence proofs against a header's state root, additionally checking that each
+/// found value is the serialized box matching its id.
+pub fn verify_boxes(
+    header: &BlockHeader,
+    proof: &[u8],
+    box_ids: &[HashDigest],
+) -> Result<Vec<Option<Vec<u8>>>, AvlError> {
+    let values = verify_lookups(&header.state_root, proof, box_ids)?;
+    for (id, value) in box_ids.iter().zip(&values) {
+        if let Some(bytes) = value
+            && blake2b256(bytes) != id.0
+        {
+            return Err(AvlError::BoxIdMismatch(id.clone()));
+        }
+    }
+    Ok(values)
+}
+
+/// Verifies a dump of a whole UTXO set against `state_root` and returns its boxes as
+/// `(id, serialized box)` in key order.
+///
+/// The dump is the full tree in the packaged format of [`verify_lookups`] proofs, without
+/// pruned subtrees or lookup directions. Each value must hash to its key.
+pub fn verify_dump(
+    state_root: &Digest<33>,
+    dump: &[u8],
+) -> Result<Vec<(HashDigest, Vec<u8>)>, AvlError> {
+    let (root, tree_len) = reconstruct(dump)?;
+    if tree_len != dump.len() {
+        return Err(CodecError("trailing bytes after UTXO set dump").into());
+    }
+    if root.label()[..] != state_root.0[..LABEL_LENGTH] {
+        return Err(AvlError::RootMismatch);
+    }
+
+    let mut boxes = Vec::new();
+    collect_leaves(root, &mut boxes)?;
+    Ok(boxes)
+}
+
+fn collect_leaves(node: Node, out: &mut Vec<(HashDigest, Vec<u8>)>) -> Result<(), AvlError> {
+    match node {
+        Node::Label(_) => Err(CodecError("UTXO set dump contains a pruned subtree").into()),
+        Node::Internal { left, right, .. } => {
+            collect_leaves(*left, out)?;
+            collect_leaves(*right, out)
+        }
+        // The tree always holds a sentinel leaf with the smallest key and an empty value.
+        Node::Leaf { key, .. } if key == [0; KEY_LENGTH] => Ok(()),
+        Node::Leaf { key, value, .. } => {
+            if blake2b256(&value) != key {
+                return Err(AvlError::BoxIdMismatch(Digest(key)));
+            }
+            out.push((Digest(key), value));
+            Ok(())
+        }
+    }
+}
+
+/// Rebuilds the packaged tree, returning its root and the byte length including the end marker.
+fn reconstruct(proof: &[u8]) -> Result<(Node, usize), CodecError> {
+    let mut stack: Vec<Node> = Vec::new();
+    let mut previous_next_key: Option<[u8; KEY_LENGTH]> = None;
+    let mut i = 0;
+
+    loop {
+        let marker = *take(proof, &mut i, 1)?.first().unwrap();
+        match marker {
+            END_OF_TREE => break,
+            LABEL => {
+                let label = take(proof, &mut i, LABEL_LENGTH)?.try_into().unwrap();
+                stack.push(Node::Label(label));
+                previous_next_key = None;
+            }
+            LEAF => {
+                let key = match previous_next_key {
+                    Some(key) => key,
+                    None => take(proof, &mut i, KEY_LENGTH)?.try_into().unwrap(),
+                };
+                let next_key: [u8; KEY_LENGTH] =
+                    take(proof, &mut i, KEY_LENGTH)?.try_into().unwrap();
+                let len = u32::from_be_bytes(take(proof, &mut i, 4)?.try_into().unwrap()) as usize;
+                let value = take(proof, &mut i, len)?.to_vec();
+                stack.push(Node::Leaf { key, value, next_key });
+                previous_next_key = Some(next_key);
+            }
+            balance => {
+                let right = stack.pop().ok_or(CodecError("missing right child"))?;
+                let left = stack.pop().ok_or(CodecError("missing left child"))?;
+                stack.push(Node::Internal { balance, left: left.into(), right: right.into() });
+            }
+        }
+    }
+
+    match (stack.pop(), stack.is_empty()) {
+        (Some(root), true) => Ok((root, i)),
+        _ => Err(CodecError("packaged tree must have exactly one root")),
+    }
+}
+
+fn take<'a>(proof: &'a [u8], i: &mut usize, n: usize) -> Result<&'a [u8], CodecError> {
+    let bytes = proof
+        .get(*i..*i + n)
+        .ok_or(CodecError("unexpected end of proof"))?;
+    *i += n;
+    Ok(bytes)
+}
+
+struct Directions<'a> {
+    proof: &'a [u8],
+    index: usize,
+}
+
+impl Directions<'_> {
+    fn next_is_left(&mut self) -> Result<bool, CodecError> {
+        let byte = self
+            .proof
+            .get(self.index >> 3)
+            .ok_or(CodecError("missing direction bits"))?;
+        let left = byte & (1 << (self.index & 7)) != 0;
+        self.index += 1;
+        Ok(left)
+    }
+}
+
+fn lookup(
+    node: &Node,
+    key: &[u8; KEY_LENGTH],
+    directions: &mut Directions,
+) -> Result<Option<Vec<u8>>, AvlError> {
+    match node {
+        Node::Label(_) => Err(CodecError("lookup path reaches a pruned subtree").into()),
+        Node::Internal { left, right, .. } => {
+            let next = if directions.next_is_left()? { left } else { right };
+            lookup(next, key, directions)
+        }
+        Node::Leaf { key: leaf_key, value, next_key } => {
+            if key == leaf_key {
+                Ok(Some(value.clone()))
+            } else if key > leaf_key && key < next_key {
+                Ok(None)
+            } else {
+                Err(CodecError("lookup key is outside the proven leaf range").into())
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    chain::{extension::Extension, header, transaction},
+    codec::{CodecError, Reader},
+    hash::blake2b256,
+    types::{
+        Digest, HashDigest, HexBytes,
+        ergo::{Block, BlockTransaction, BlockTransactions, MinimalInput},
+    },
+};
+
+/// Modifier type ids of the block sections, as used by the P2P layer and in section ids.
+pub const HEADER_TYPE_ID: u8 = 101;
+pub const BLOCK_TRANSACTIONS_TYPE_ID: u8 = 102;
+pub const AD_PROOFS_TYPE_ID: u8 = 104;
+pub const EXTENSION_TYPE_ID: u8 = 108;
+
+/// Transaction counts above this value are a block version marker, followed by the count.
+const MAX_TRANSACTIONS_IN_BLOCK: u32 = 10_000_000;
+
+/// Authenticated UTXO set proofs of a block.
+#[derive(Debug, Clone)]
+pub struct AdProofs {
+    pub header_id: HashDigest,
+    pub proof_bytes: HexBytes,
+}
+
+/// Assembles a block from its serialized sections, e.g. as received over P2P, checking that
+/// they belong to the header. Parsing binary sections skips the JSON decoding of outputs and
+/// registers, which dominates backfills.
+pub fn parse(
+    header: &[u8],
+    transactions: &[u8],
+    extension: Option<&[u8]>,
+) -> Result<Block, CodecError> {
+    let mut header = header::parse(header)?;
+    let transactions = parse_transactions(transactions)?;
+    if transactions.header_id != header.id {
+        return Err(CodecError("transactions belong to another header"));
+    }
+    let extension = extension.map(parse_extension).transpose()?;
+    if let Some(extension) = &extension {
+        if extension.header_id != header.id {
+            return Err(CodecError("extension belongs to another header"));
+        }
+        if extension.digest != header.extension_root {
+            return Err(CodecError("extension digest mismatch"));
+        }
+    }
+
+    header.transactions_id =
+        Some(section_id(BLOCK_TRANSACTIONS_TYPE_ID, &header.id, &header.transactions_root));
+    header.extension_id = Some(section_id(EXTENSION_TYPE_ID, &header.id, &header.extension_root));
+    header.ad_proofs_id = Some(section_id(AD_PROOFS_TYPE_ID, &header.id, &header.ad_proofs_root));
+    Ok(Block { header, transactions, extension })
+}
+
+/// Parses a block transactions section. Output box ids are computed from the parsed outputs.
+pub fn parse_transactions(bytes: &[u8]) -> Result<BlockTransactions, CodecError> {
+    let mut r = Reader::new(bytes);
+    let header_id = Digest(r.get_array()?);
+    let mut count = r.get_uint()?;
+    if count > MAX_TRANSACTIONS_IN_BLOCK {
+        // Block version marker
+        count = r.get_uint()?;
+    }
+
+    let mut transactions = Vec::with_capacity(count.min(4096) as usize);
+    for _ in 0..count {
+        let tx = transaction::read_transaction(&mut r)?;
+        let id = tx
+            .id
+            .clone()
+            .ok_or(CodecError("parsed transaction has no id"))?;
+        let outputs = transaction::into_boxes(&id, tx.outputs)?;
+        let inputs = tx
+            .inputs
+            .into_iter()
+            .map(|input| MinimalInput { id: input.box_id })
+            .collect();
+        transactions.push(BlockTransaction { id, inputs, outputs });
+    }
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after block transactions"));
+    }
+
+    Ok(BlockTransactions { header_id, transactions })
+}
+
+/// Parses an extension section, computing its digest.
+pub fn parse_extension(bytes: &[u8]) -> Result<Extension, CodecError> {
+    let mut r = Reader::new(bytes);
+    let header_id = Digest(r.get_array()?);
+    let count = r.get_uint()?;
+    let fields = (0..count)
+        .map(|_| {
+            let key = HexBytes(r.get_bytes(2)?.to_vec());
+            let len = r.get_u8()? as usize;
+            Ok((key, HexBytes(r.get_bytes(len)?.to_vec())))
+        })
+        .collect::<Result<Vec<_>, CodecError>>()?;
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after extension"));
+    }
+
+    let digest = extension_digest(&fields);
+    Ok(Extension { header_id, digest, fields })
+}
+
+pub fn parse_ad_proofs(bytes: &[u8]) -> Result<AdProofs, CodecError> {
+    let mut r = Reader::new(bytes);
+    let header_id = Digest(r.get_array()?);
+    let len = r.get_uint()? as usize;
+    let proof_bytes = HexBytes(r.get_bytes(len)?.to_vec());
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after AD proofs"));
+    }
+    Ok(AdProofs { header_id, proof_bytes })
+}
+
+/// Id of a block section: blake2b256 of its type id, header id and digest.
+pub fn section_id(type_id: u8, header_id: &HashDigest, digest: &HashDigest) -> HashDigest {
+    Digest(blake2b256(&[&[type_id][..], &header_id.0, &digest.0].concat()))
+}
+
+/// Merkle root of the extension fields, each leaf being `key length || key || value`.
+pub fn extension_digest(fields: &[(HexBytes, HexBytes)]) -> HashDigest {
+    const LEAF_PREFIX: u8 = 0;
+    const INTERNAL_PREFIX: u8 = 1;
+
+    let mut level: Vec<[u8; 32]> = fields
+        .iter()
+        .map(|(key, value)| {
+            let leaf = [&[LEAF_PREFIX, key.0.len() as u8][..], &key.0, &value.0].concat();
+            blake2b256(&leaf)
+        })
+        .collect();
+    if level.is_empty() {
+        return Digest(blake2b256(&[]));
+    }
+
+    // Leaves are always hashed into at least one internal node.
+    loop {
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                // A missing right child contributes no bytes.
+                let right = pair.get(1).map_or(&[][..], |r| &r[..]);
+                blake2b256(&[&[INTERNAL_PREFIX][..], &pair[0], right].concat())
+            })
+            .collect();
+        if let [root] = level[..] {
+            return Digest(root);
+        }
+    }
+}
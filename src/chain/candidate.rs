@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    address::ErgoAddress,
+    chain::ergo_box,
+    codec::CodecError,
+    params::NetworkParameters,
+    types::{
+        HashDigest, Height, HexBytes,
+        ergo::{BoxCandidate, NonMandatoryRegisters, Token},
+    },
+};
+
+/// Most distinct tokens a box may hold.
+pub const MAX_TOKENS: usize = 122;
+
+/// Largest serialized box accepted by the protocol, in bytes.
+pub const MAX_BOX_SIZE: usize = 4096;
+
+/// Bytes the creating transaction id and a one-byte output index add to a candidate once it
+/// becomes a box.
+const BOX_REFERENCE_LEN: usize = 33;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CandidateError {
+    #[error("Box candidate has no ErgoTree.")]
+    MissingErgoTree,
+
+    #[error("P2SH addresses only carry a script hash and can't guard a box.")]
+    P2shAddress,
+
+    #[error("Box value {value} is below the minimum of {min} for its size.")]
+    ValueTooLow { value: u64, min: u64 },
+
+    #[error("Box holds {0} tokens, more than the maximum of {MAX_TOKENS}.")]
+    TooManyTokens(usize),
+
+    #[error("Token {0} has a zero amount.")]
+    ZeroTokenAmount(HashDigest),
+
+    #[error("Token {0} amount overflows.")]
+    TokenAmountOverflow(HashDigest),
+
+    #[error("R{0} is not a non-mandatory register (R4 to R9).")]
+    InvalidRegister(u8),
+
+    #[error("R{0} is set but R{1} is not; registers must be densely packed from R4.")]
+    RegisterGap(u8, u8),
+
+    #[error("Box of {0} bytes exceeds the maximum of {MAX_BOX_SIZE}.")]
+    TooLarge(usize),
+
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+}
+
+/// Builds a [`BoxCandidate`], checking it against the protocol rules the node enforces on
+/// outputs.
+///
+/// The minimum value is checked against [`NetworkParameters::default`] unless
+/// [`parameters`](Self::parameters) provides the current ones.
+#[derive(Debug, Clone)]
+pub struct BoxCandidateBuilder {
+    value: u64,
+    creation_height: Height,
+    ergo_tree: Option<HexBytes>,
+    p2sh: bool,
+    tokens: Vec<Token>,
+    registers: BTreeMap<u8, HexBytes>,
+    parameters: NetworkParameters,
+}
+
+impl BoxCandidateBuilder {
+    pub fn new(value: u64, creation_height: Height) -> Self {
+        Self {
+            value,
+            creation_height,
+            ergo_tree: None,
+            p2sh: false,
+            tokens: Vec::new(),
+            registers: BTreeMap::new(),
+            parameters: NetworkParameters::default(),
+        }
+    }
+
+    pub fn value(mut self, value: u64) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn creation_height(mut self, creation_height: Height) -> Self {
+        self.creation_height = creation_height;
+        self
+    }
+
+    pub fn ergo_tree(mut self, ergo_tree: HexBytes) -> Self {
+        self.ergo_tree = Some(ergo_tree);
+        self.p2sh = false;
+        self
+    }
+
+    /// Guards the box with the ErgoTree of `address`. P2SH addresses are rejected at
+    /// [`build`](Self::build).
+    pub fn address(mut self, address: &ErgoAddress) -> Self {
+        self.ergo_tree = address.ergo_tree();
+        self.p2sh = self.ergo_tree.is_none();
+        self
+    }
+
+    /// Adds a token; amounts of the same token are summed.
+    pub fn token(mut self, id: HashDigest, amount: u64) -> Self {
+        self.tokens.push(Token { id, amount });
+        self
+    }
+
+    /// Sets register `R{id}` to a serialized constant.
+    pub fn register(mut self, id: u8, value: HexBytes) -> Self {
+        self.registers.insert(id, value);
+        self
+    }
+
+    pub fn parameters(mut self, parameters: &NetworkParameters) -> Self {
+        self.parameters = parameters.clone();
+        self
+    }
+
+    pub fn build(self) -> Result<BoxCandidate, CandidateError> {
+        let ergo_tree = match self.ergo_tree {
+            Some(ergo_tree) => ergo_tree,
+            None if self.p2sh => return Err(CandidateError::P2shAddress),
+            None => return Err(CandidateError::MissingErgoTree),
+        };
+
+        let mut tokens: Vec<Token> = Vec::with_capacity(self.tokens.len());
+        for token in self.tokens {
+            if token.amount == 0 {
+                return Err(CandidateError::ZeroTokenAmount(token.id));
+            }
+            match tokens.iter_mut().find(|t| t.id == token.id) {
+                Some(existing) => {
+                    existing.amount = existing
+                        .amount
+                        .checked_add(token.amount)
+                        .ok_or(CandidateError::TokenAmountOverflow(token.id))?;
+                }
+                None => tokens.push(token),
+            }
+        }
+        if tokens.len() > MAX_TOKENS {
+            return Err(CandidateError::TooManyTokens(tokens.len()));
+        }
+
+        let candidate = BoxCandidate {
+            ergo_tree,
+            creation_height: self.creation_height,
+            value: self.value,
+            tokens,
+            registers: registers(self.registers)?,
+        };
+
+        let size = ergo_box::serialize_candidate(&candidate)?.len() + BOX_REFERENCE_LEN;
+        if size > MAX_BOX_SIZE {
+            return Err(CandidateError::TooLarge(size));
+        }
+        let min = self.parameters.min_box_value(size);
+        if candidate.value < min {
+            return Err(CandidateError::ValueTooLow { value: candidate.value, min });
+        }
+
+        Ok(candidate)
+    }
+}
+
+fn registers(registers: BTreeMap<u8, HexBytes>) -> Result<NonMandatoryRegisters, CandidateError> {
+    let mut slots: [Option<HexBytes>; 6] = Default::default();
+    for (id, value) in registers {
+        let slot = id
+            .checked_sub(4)
+            .and_then(|i| slots.get_mut(i as usize))
+            .ok_or(CandidateError::InvalidRegister(id))?;
+        *slot = Some(value);
+    }
+    if let Some(gap) = slots.iter().position(Option::is_none)
+        && let Some(set) = slots[gap..].iter().rposition(Option::is_some)
+    {
+        return Err(CandidateError::RegisterGap(gap as u8 + set as u8 + 4, gap as u8 + 4));
+    }
+
+    let [r4, r5, r6, r7, r8, r9] = slots;
+    Ok(NonMandatoryRegisters { r4, r5, r6, r7, r8, r9 })
+}
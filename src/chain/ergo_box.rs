@@ -0,0 +1,157 @@
+use crate::{
+    chain::ergo_tree::{read_ergo_tree, skip_constant},
+    codec::{CodecError, Reader, Writer},
+    hash::blake2b256,
+    types::{
+        Digest, HashDigest, Height, HexBytes,
+        ergo::{BoxCandidate, NonMandatoryRegisters, Token, UTxO},
+    },
+};
+
+/// Serializes a box as stored in the UTXO set: the candidate fields followed by the id of the
+/// creating transaction and the output index.
+pub fn serialize(utxo: &UTxO) -> Result<Vec<u8>, CodecError> {
+    let mut w = Writer::new();
+    write_candidate(
+        &mut w,
+        utxo.value,
+        &utxo.ergo_tree,
+        utxo.creation_height,
+        &utxo.tokens,
+        &utxo.registers,
+        None,
+    )?;
+    w.put_bytes(&utxo.transaction_id.0)
+        .put_uint(utxo.index as u32);
+    Ok(w.into_bytes())
+}
+
+/// Serializes an output candidate, without transaction id and index.
+pub fn serialize_candidate(candidate: &BoxCandidate) -> Result<Vec<u8>, CodecError> {
+    let mut w = Writer::new();
+    write_candidate(
+        &mut w,
+        candidate.value,
+        &candidate.ergo_tree,
+        candidate.creation_height,
+        &candidate.tokens,
+        &candidate.registers,
+        None,
+    )?;
+    Ok(w.into_bytes())
+}
+
+/// Parses a box serialized by [`serialize`], computing its id.
+pub fn parse(bytes: &[u8]) -> Result<UTxO, CodecError> {
+    let mut r = Reader::new(bytes);
+    let candidate = read_candidate(&mut r, None)?;
+    let transaction_id = Digest(r.get_array()?);
+    let index = u16::try_from(r.get_uint()?).map_err(|_| CodecError("output index exceeds u16"))?;
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after box"));
+    }
+
+    Ok(UTxO {
+        id: Digest(blake2b256(bytes)),
+        ergo_tree: candidate.ergo_tree,
+        creation_height: candidate.creation_height,
+        value: candidate.value,
+        tokens: candidate.tokens,
+        registers: candidate.registers,
+        index,
+        transaction_id,
+    })
+}
+
+/// Computes the box id (blake2b256 of the serialized box).
+pub fn compute_id(utxo: &UTxO) -> Result<HashDigest, CodecError> {
+    Ok(Digest(blake2b256(&serialize(utxo)?)))
+}
+
+/// Writes the candidate fields. Inside transactions, tokens are written as indexes into the
+/// transaction's distinct `token_ids` instead of full ids.
+pub(crate) fn write_candidate(
+    w: &mut Writer,
+    value: u64,
+    ergo_tree: &HexBytes,
+    creation_height: Height,
+    tokens: &[Token],
+    registers: &NonMandatoryRegisters,
+    token_ids: Option<&[HashDigest]>,
+) -> Result<(), CodecError> {
+    let token_count = u8::try_from(tokens.len()).map_err(|_| CodecError("too many tokens"))?;
+
+    let registers = registers.as_array();
+    let register_count = registers.iter().take_while(|r| r.is_some()).count();
+    if registers[register_count..].iter().any(Option::is_some) {
+        return Err(CodecError("registers must be densely packed from R4"));
+    }
+
+    w.put_ulong(value)
+        .put_bytes(&ergo_tree.0)
+        .put_uint(creation_height.0)
+        .put_u8(token_count);
+    for token in tokens {
+        match token_ids {
+            Some(ids) => {
+                let index = ids
+                    .iter()
+                    .position(|id| *id == token.id)
+                    .ok_or(CodecError("token missing from transaction token ids"))?;
+                w.put_uint(index as u32);
+            }
+            None => {
+                w.put_bytes(&token.id.0);
+            }
+        }
+        w.put_ulong(token.amount);
+    }
+
+    w.put_u8(register_count as u8);
+    for register in registers.into_iter().flatten() {
+        w.put_bytes(&register.0);
+    }
+    Ok(())
+}
+
+/// Reads the fields written by [`write_candidate`], resolving token indexes against
+/// `token_ids` inside transactions.
+pub(crate) fn read_candidate(
+    r: &mut Reader,
+    token_ids: Option<&[HashDigest]>,
+) -> Result<BoxCandidate, CodecError> {
+    let value = r.get_ulong()?;
+    let ergo_tree = HexBytes(read_ergo_tree(r)?.to_vec());
+    let creation_height = Height(r.get_uint()?);
+
+    let tokens = (0..r.get_u8()?)
+        .map(|_| {
+            let id = match token_ids {
+                Some(ids) => ids
+                    .get(r.get_uint()? as usize)
+                    .cloned()
+                    .ok_or(CodecError("token index out of range"))?,
+                None => Digest(r.get_array()?),
+            };
+            Ok(Token { id, amount: r.get_ulong()? })
+        })
+        .collect::<Result<_, CodecError>>()?;
+
+    let mut registers: [Option<HexBytes>; 6] = Default::default();
+    let count = r.get_u8()? as usize;
+    if count > registers.len() {
+        return Err(CodecError("too many registers"));
+    }
+    for register in &mut registers[..count] {
+        *register = Some(HexBytes(r.get_consumed(skip_constant)?.to_vec()));
+    }
+    let [r4, r5, r6, r7, r8, r9] = registers;
+
+    Ok(BoxCandidate {
+        ergo_tree,
+        creation_height,
+        value,
+        tokens,
+        registers: NonMandatoryRegisters { r4, r5, r6, r7, r8, r9 },
+    })
+}
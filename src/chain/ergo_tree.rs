@@ -0,0 +1,534 @@
+use crate::{
+    chain::fee::FEE_ERGO_TREE,
+    codec::{CodecError, Reader, Writer},
+    hash::blake2b256,
+    types::{Digest, HashDigest},
+};
+
+const CONSTANT_SEGREGATION_FLAG: u8 = 0x10;
+const SIZE_FLAG: u8 = 0x08;
+const VERSION_MASK: u8 = 0x07;
+
+/// Nesting limit for constant types and values, matching the reference implementation.
+const MAX_DEPTH: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErgoTreeHeader {
+    pub version: u8,
+    pub constant_segregation: bool,
+    pub has_size: bool,
+}
+
+impl From<u8> for ErgoTreeHeader {
+    fn from(byte: u8) -> Self {
+        Self {
+            version: byte & VERSION_MASK,
+            constant_segregation: byte & CONSTANT_SEGREGATION_FLAG != 0,
+            has_size: byte & SIZE_FLAG != 0,
+        }
+    }
+}
+
+impl From<ErgoTreeHeader> for u8 {
+    fn from(header: ErgoTreeHeader) -> Self {
+        let mut byte = header.version & VERSION_MASK;
+        if header.constant_segregation {
+            byte |= CONSTANT_SEGREGATION_FLAG;
+        }
+        if header.has_size {
+            byte |= SIZE_FLAG;
+        }
+        byte
+    }
+}
+
+/// An ErgoTree split into header, segregated constants and body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErgoTreeParts<'a> {
+    pub header: ErgoTreeHeader,
+    /// Serialized segregated constants, each `(type, value)`, after their count.
+    pub constants: &'a [u8],
+    pub constants_count: u32,
+    /// Root expression with constants replaced by placeholders. Shared by every instance of a
+    /// contract when constants are segregated; for other trees it contains the constants
+    /// inline.
+    pub template: &'a [u8],
+}
+
+impl<'a> ErgoTreeParts<'a> {
+    pub fn parse(tree: &'a [u8]) -> Result<Self, CodecError> {
+        let mut r = Reader::new(tree);
+        let header = ErgoTreeHeader::from(r.get_u8()?);
+        if header.has_size {
+            let size = r.get_uint()? as usize;
+            if size != r.remaining() {
+                return Err(CodecError("ErgoTree size mismatch"));
+            }
+        }
+
+        let mut constants_count = 0;
+        let constants_start = tree.len() - r.remaining();
+        if header.constant_segregation {
+            constants_count = r.get_uint()?;
+            for _ in 0..constants_count {
+                let tpe = read_type(&mut r, 0)?;
+                skip_value(&mut r, &tpe, 0)?;
+            }
+        }
+        let template_start = tree.len() - r.remaining();
+
+        Ok(Self {
+            header,
+            constants: &tree[constants_start..template_start],
+            constants_count,
+            template: &tree[template_start..],
+        })
+    }
+
+    /// blake2b256 of the template, identifying the contract independently of its constants.
+    pub fn template_hash(&self) -> HashDigest {
+        Digest(blake2b256(self.template))
+    }
+
+    /// Each segregated constant, serialized as `(type, value)`.
+    pub fn constant_list(&self) -> Result<Vec<&'a [u8]>, CodecError> {
+        let mut r = Reader::new(self.constants);
+        if self.header.constant_segregation {
+            r.get_uint()?;
+        }
+        (0..self.constants_count)
+            .map(|_| r.get_consumed(skip_constant))
+            .collect()
+    }
+
+    /// The tree with segregated constant `index` replaced by the serialized `constant`: another
+    /// instance of the same contract, sharing its template.
+    pub fn with_constant(&self, index: usize, constant: &[u8]) -> Result<Vec<u8>, CodecError> {
+        let mut constants = self.constant_list()?;
+        *constants
+            .get_mut(index)
+            .ok_or(CodecError("constant index out of range"))? = constant;
+
+        let mut body = Writer::new();
+        body.put_uint(self.constants_count);
+        for constant in constants {
+            body.put_bytes(constant);
+        }
+        body.put_bytes(self.template);
+        let body = body.into_bytes();
+
+        let mut w = Writer::new();
+        w.put_u8(self.header.into());
+        if self.header.has_size {
+            w.put_uint(body.len() as u32);
+        }
+        w.put_bytes(&body);
+        Ok(w.into_bytes())
+    }
+}
+
+/// Template hash of a serialized ErgoTree.
+pub fn template_hash(tree: &[u8]) -> Result<HashDigest, CodecError> {
+    Ok(ErgoTreeParts::parse(tree)?.template_hash())
+}
+
+/// Reads a serialized ErgoTree embedded in a box, without a length prefix.
+///
+/// Trees without the size flag, common among contracts deployed before v5, are only delimited
+/// by walking their body expression.
+pub(crate) fn read_ergo_tree<'a>(r: &mut Reader<'a>) -> Result<&'a [u8], CodecError> {
+    if r.rest().starts_with(&FEE_ERGO_TREE) {
+        return r.get_bytes(FEE_ERGO_TREE.len());
+    }
+
+    r.get_consumed(|r| {
+        let header = ErgoTreeHeader::from(r.get_u8()?);
+        if header.has_size {
+            let size = r.get_uint()? as usize;
+            r.get_bytes(size)?;
+            return Ok(());
+        }
+        if header.constant_segregation {
+            for _ in 0..r.get_uint()? {
+                skip_constant(r)?;
+            }
+        }
+        skip_expr(r, 0)
+    })
+}
+
+/// Nesting limit for expressions, matching the reference implementation.
+const MAX_TREE_DEPTH: u8 = 110;
+
+const TAGGED_VARIABLE: u8 = 113;
+const VAL_USE: u8 = 114;
+const CONSTANT_PLACEHOLDER: u8 = 115;
+const SUBST_CONSTANTS: u8 = 116;
+const DOWNCAST: u8 = 125;
+const UPCAST: u8 = 126;
+const CONCRETE_COLL: u8 = 131;
+const CONCRETE_COLL_BOOLEAN: u8 = 133;
+const TUPLE_EXPR: u8 = 134;
+const SELECT_FIELD: u8 = 140;
+const IF: u8 = 149;
+const FOLD: u8 = 176;
+const BY_INDEX: u8 = 178;
+const SLICE: u8 = 180;
+const CREATE_AVL_TREE: u8 = 182;
+const TREE_LOOKUP: u8 = 183;
+const EXTRACT_REGISTER_AS: u8 = 198;
+const DESERIALIZE_CONTEXT: u8 = 212;
+const DESERIALIZE_REGISTER: u8 = 213;
+const VAL_DEF: u8 = 214;
+const FUN_DEF: u8 = 215;
+const BLOCK_VALUE: u8 = 216;
+const FUNC_VALUE: u8 = 217;
+const FUNC_APPLY: u8 = 218;
+const PROPERTY_CALL: u8 = 219;
+const METHOD_CALL: u8 = 220;
+const GET_VAR: u8 = 227;
+const SIGMA_AND: u8 = 234;
+const SIGMA_OR: u8 = 235;
+
+/// Skips a serialized expression, identified by its opcode.
+fn skip_expr(r: &mut Reader, depth: u8) -> Result<(), CodecError> {
+    if depth > MAX_TREE_DEPTH {
+        return Err(CodecError("ErgoTree expression nested too deeply"));
+    }
+
+    match r.rest().first() {
+        Some(code) if *code < LAST_CONSTANT_CODE => return skip_constant(r),
+        _ => {}
+    }
+    let depth = depth + 1;
+    let code = r.get_u8()?;
+    match code {
+        // True, False, Unit, GroupGenerator, Height, Inputs, Outputs, LastBlockUtxoRootHash,
+        // Self, MinerPubkey, TrivialPropFalse/True, Global and Context.
+        127..=130 | 163..=167 | 172 | 210 | 211 | 221 | 254 => {}
+        // Conversions, tuple selects, And/Or, SizeOf, box extractors, hashes, ProveDlog,
+        // sigma prop conversions, Option accessors, ModQ, DecodePoint, negations and XorOf.
+        122..=124
+        | 135..=139
+        | 150
+        | 151
+        | 177
+        | 193..=197
+        | 199
+        | 203..=205
+        | 207..=209
+        | 228
+        | 230
+        | 231
+        | 238..=241
+        | 255 => skip_expr(r, depth)?,
+        // AtLeast, arithmetic, group operations, Min/Max, collection transformers,
+        // OptionGetOrElse, modular and bitwise arithmetic.
+        152..=162 | 173..=175 | 179 | 181 | 184 | 229 | 232 | 233 | 242 | 243 | 245..=248 => {
+            skip_exprs(r, 2, depth)?
+        }
+        // Comparisons and binary boolean operations, with two boolean constants packed as bits.
+        143..=148 | 236 | 237 | 244 => match r.rest().first() {
+            Some(&CONCRETE_COLL_BOOLEAN) => {
+                r.get_bytes(2)?;
+            }
+            _ => skip_exprs(r, 2, depth)?,
+        },
+        SUBST_CONSTANTS | IF | FOLD | SLICE | TREE_LOOKUP => skip_exprs(r, 3, depth)?,
+        CREATE_AVL_TREE | PROVE_DH_TUPLE => skip_exprs(r, 4, depth)?,
+        TAGGED_VARIABLE | GET_VAR => {
+            r.get_u8()?;
+            read_type(r, 0)?;
+        }
+        VAL_USE | CONSTANT_PLACEHOLDER => {
+            r.get_uint()?;
+        }
+        DOWNCAST | UPCAST => {
+            skip_expr(r, depth)?;
+            read_type(r, 0)?;
+        }
+        CONCRETE_COLL => {
+            let len = r.get_uint()?;
+            read_type(r, 0)?;
+            skip_exprs(r, len, depth)?;
+        }
+        CONCRETE_COLL_BOOLEAN => {
+            let len = r.get_uint()? as usize;
+            r.get_bytes(len.div_ceil(8))?;
+        }
+        TUPLE_EXPR => {
+            let len = r.get_u8()?;
+            skip_exprs(r, len.into(), depth)?;
+        }
+        SELECT_FIELD => {
+            skip_expr(r, depth)?;
+            r.get_u8()?;
+        }
+        BY_INDEX => {
+            skip_exprs(r, 2, depth)?;
+            r.get_option(|r| skip_expr(r, depth))?;
+        }
+        EXTRACT_REGISTER_AS => {
+            skip_expr(r, depth)?;
+            r.get_u8()?;
+            read_type(r, 0)?;
+        }
+        DESERIALIZE_CONTEXT => {
+            read_type(r, 0)?;
+            r.get_u8()?;
+        }
+        DESERIALIZE_REGISTER => {
+            r.get_u8()?;
+            read_type(r, 0)?;
+            r.get_option(|r| skip_expr(r, depth))?;
+        }
+        VAL_DEF => {
+            r.get_uint()?;
+            skip_expr(r, depth)?;
+        }
+        FUN_DEF => {
+            r.get_uint()?;
+            let type_args = r.get_u8()?;
+            read_types(r, type_args, 0)?;
+            skip_expr(r, depth)?;
+        }
+        BLOCK_VALUE => {
+            let len = r.get_uint()?;
+            skip_exprs(r, len + 1, depth)?;
+        }
+        FUNC_VALUE => {
+            for _ in 0..r.get_uint()? {
+                r.get_uint()?;
+                read_type(r, 0)?;
+            }
+            skip_expr(r, depth)?;
+        }
+        FUNC_APPLY => {
+            skip_expr(r, depth)?;
+            let len = r.get_uint()?;
+            skip_exprs(r, len, depth)?;
+        }
+        PROPERTY_CALL | METHOD_CALL => {
+            r.get_bytes(2)?;
+            skip_expr(r, depth)?;
+            if code == METHOD_CALL {
+                let len = r.get_uint()?;
+                skip_exprs(r, len, depth)?;
+            }
+        }
+        SIGMA_AND | SIGMA_OR => {
+            let len = r.get_uint()?;
+            skip_exprs(r, len, depth)?;
+        }
+        _ => return Err(CodecError("unsupported ErgoTree opcode")),
+    }
+    Ok(())
+}
+
+fn skip_exprs(r: &mut Reader, n: u32, depth: u8) -> Result<(), CodecError> {
+    (0..n).try_for_each(|_| skip_expr(r, depth))
+}
+
+/// Skips a serialized constant: its type followed by its value.
+pub(crate) fn skip_constant(r: &mut Reader) -> Result<(), CodecError> {
+    let tpe = read_type(r, 0)?;
+    skip_value(r, &tpe, 0)
+}
+
+/// Opcodes below this one are constant type codes.
+const LAST_CONSTANT_CODE: u8 = 112;
+
+/// Constant types needed to walk serialized values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SType {
+    Boolean,
+    Byte,
+    Short,
+    Int,
+    Long,
+    BigInt,
+    GroupElement,
+    SigmaProp,
+    Unit,
+    AvlTree,
+    Coll(Box<SType>),
+    Option(Box<SType>),
+    Tuple(Vec<SType>),
+    /// Types only found in expressions, e.g. the argument of a lambda over boxes.
+    NonConstant,
+}
+
+const PRIM_RANGE: u8 = 12;
+const COLL: u8 = PRIM_RANGE;
+const NESTED_COLL: u8 = 2 * PRIM_RANGE;
+const OPTION: u8 = 3 * PRIM_RANGE;
+const OPTION_COLL: u8 = 4 * PRIM_RANGE;
+const PAIR1: u8 = 5 * PRIM_RANGE;
+const PAIR2: u8 = 6 * PRIM_RANGE;
+const PAIR_SYMMETRIC: u8 = 7 * PRIM_RANGE;
+const TUPLE: u8 = 8 * PRIM_RANGE;
+const ANY: u8 = 97;
+const UNIT: u8 = 98;
+const BOX: u8 = 99;
+const AVL_TREE: u8 = 100;
+const CONTEXT: u8 = 101;
+const TYPE_VAR: u8 = 103;
+const GLOBAL: u8 = 106;
+
+fn read_type(r: &mut Reader, depth: u8) -> Result<SType, CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError("ErgoTree constant type nested too deeply"));
+    }
+
+    let code = r.get_u8()?;
+    let (constr, prim) = (code / PRIM_RANGE * PRIM_RANGE, code % PRIM_RANGE);
+    let elem = |r: &mut Reader| match prim {
+        0 => read_type(r, depth + 1),
+        id => prim_type(id),
+    };
+
+    Ok(match code {
+        1..COLL => prim_type(code)?,
+        COLL..TUPLE => match constr {
+            COLL => SType::Coll(Box::new(elem(r)?)),
+            NESTED_COLL => SType::Coll(Box::new(SType::Coll(Box::new(elem(r)?)))),
+            OPTION => SType::Option(Box::new(elem(r)?)),
+            OPTION_COLL => SType::Option(Box::new(SType::Coll(Box::new(elem(r)?)))),
+            PAIR1 => {
+                let first = elem(r)?;
+                SType::Tuple(vec![first, read_type(r, depth + 1)?])
+            }
+            // Pair2 and PairSymmetric without a primitive encode triples and quadruples.
+            PAIR2 if prim == 0 => SType::Tuple(read_types(r, 3, depth)?),
+            PAIR2 => {
+                let first = read_type(r, depth + 1)?;
+                SType::Tuple(vec![first, prim_type(prim)?])
+            }
+            PAIR_SYMMETRIC if prim == 0 => SType::Tuple(read_types(r, 4, depth)?),
+            _ => SType::Tuple(vec![prim_type(prim)?, prim_type(prim)?]),
+        },
+        TUPLE => {
+            let len = r.get_u8()?;
+            SType::Tuple(read_types(r, len, depth)?)
+        }
+        UNIT => SType::Unit,
+        AVL_TREE => SType::AvlTree,
+        TYPE_VAR => {
+            r.get_short_string()?;
+            SType::NonConstant
+        }
+        // Context, String, Header, PreHeader and Global.
+        ANY | BOX | CONTEXT..=GLOBAL => SType::NonConstant,
+        _ => return Err(CodecError("unsupported ErgoTree constant type")),
+    })
+}
+
+fn read_types(r: &mut Reader, n: u8, depth: u8) -> Result<Vec<SType>, CodecError> {
+    (0..n).map(|_| read_type(r, depth + 1)).collect()
+}
+
+fn prim_type(id: u8) -> Result<SType, CodecError> {
+    Ok(match id {
+        1 => SType::Boolean,
+        2 => SType::Byte,
+        3 => SType::Short,
+        4 => SType::Int,
+        5 => SType::Long,
+        6 => SType::BigInt,
+        7 => SType::GroupElement,
+        8 => SType::SigmaProp,
+        _ => return Err(CodecError("unsupported ErgoTree primitive type")),
+    })
+}
+
+const GROUP_ELEMENT_LEN: usize = 33;
+
+fn skip_value(r: &mut Reader, tpe: &SType, depth: u8) -> Result<(), CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError("ErgoTree constant value nested too deeply"));
+    }
+
+    match tpe {
+        SType::Boolean | SType::Byte => {
+            r.get_u8()?;
+        }
+        SType::Short | SType::Int | SType::Long => {
+            r.get_ulong()?;
+        }
+        SType::BigInt => {
+            let len = r.get_uint()? as usize;
+            r.get_bytes(len)?;
+        }
+        SType::GroupElement => {
+            r.get_bytes(GROUP_ELEMENT_LEN)?;
+        }
+        SType::SigmaProp => skip_sigma_boolean(r, depth)?,
+        SType::Unit => {}
+        SType::AvlTree => {
+            r.get_bytes(GROUP_ELEMENT_LEN)?;
+            r.get_u8()?;
+            r.get_uint()?;
+            r.get_option(|r| r.get_uint())?;
+        }
+        SType::Coll(elem) => {
+            let len = r.get_uint()? as usize;
+            match **elem {
+                SType::Boolean => {
+                    r.get_bytes(len.div_ceil(8))?;
+                }
+                SType::Byte => {
+                    r.get_bytes(len)?;
+                }
+                _ => {
+                    for _ in 0..len {
+                        skip_value(r, elem, depth + 1)?;
+                    }
+                }
+            }
+        }
+        SType::Option(elem) => {
+            r.get_option(|r| skip_value(r, elem, depth + 1))?;
+        }
+        SType::Tuple(items) => {
+            for item in items {
+                skip_value(r, item, depth + 1)?;
+            }
+        }
+        SType::NonConstant => return Err(CodecError("unsupported ErgoTree constant type")),
+    }
+    Ok(())
+}
+
+const TRIVIAL_TRUE: u8 = 0x7f;
+const TRIVIAL_FALSE: u8 = 0x80;
+const CAND: u8 = 0x96;
+const COR: u8 = 0x97;
+const CTHRESHOLD: u8 = 0x98;
+const PROVE_DLOG: u8 = 0xcd;
+const PROVE_DH_TUPLE: u8 = 0xce;
+
+fn skip_sigma_boolean(r: &mut Reader, depth: u8) -> Result<(), CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError("sigma proposition nested too deeply"));
+    }
+
+    match r.get_u8()? {
+        TRIVIAL_TRUE | TRIVIAL_FALSE => {}
+        PROVE_DLOG => {
+            r.get_bytes(GROUP_ELEMENT_LEN)?;
+        }
+        PROVE_DH_TUPLE => {
+            r.get_bytes(4 * GROUP_ELEMENT_LEN)?;
+        }
+        code @ (CAND | COR | CTHRESHOLD) => {
+            if code == CTHRESHOLD {
+                r.get_uint()?;
+            }
+            for _ in 0..r.get_uint()? {
+                skip_sigma_boolean(r, depth + 1)?;
+            }
+        }
+        _ => return Err(CodecError("unknown sigma proposition")),
+    }
+    Ok(())
+}
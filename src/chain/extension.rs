@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    codec::CodecError,
+    types::{Digest, HashDigest, Height, HexBytes},
+};
+
+/// Key prefix of system parameter fields: `[0x00, parameter id]`.
+pub const PARAMETERS_PREFIX: u8 = 0x00;
+/// Key prefix of packed interlink fields: `[0x01, index]`.
+pub const INTERLINKS_PREFIX: u8 = 0x01;
+/// Parameters are written to the extension of the first block of each voting epoch.
+pub const VOTING_EPOCH_LENGTH: u32 = 1024;
+
+/// Block extension section as returned by the node.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Extension {
+    pub header_id: HashDigest,
+    pub digest: HashDigest,
+    pub fields: Vec<(HexBytes, HexBytes)>,
+}
+
+/// Adjustable consensus parameters, identified by the same ids used for voting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum ParameterId {
+    StorageFeeFactor = 1,
+    MinValuePerByte = 2,
+    MaxBlockSize = 3,
+    MaxBlockCost = 4,
+    TokenAccessCost = 5,
+    InputCost = 6,
+    DataInputCost = 7,
+    OutputCost = 8,
+    SubblocksPerBlock = 9,
+    SoftFork = 120,
+    SoftForkVotesCollected = 121,
+    SoftForkStartingHeight = 122,
+    BlockVersion = 123,
+}
+
+impl ParameterId {
+    pub fn from_id(id: u8) -> Option<Self> {
+        use ParameterId::*;
+        [
+            StorageFeeFactor,
+            MinValuePerByte,
+            MaxBlockSize,
+            MaxBlockCost,
+            TokenAccessCost,
+            InputCost,
+            DataInputCost,
+            OutputCost,
+            SubblocksPerBlock,
+            SoftFork,
+            SoftForkVotesCollected,
+            SoftForkStartingHeight,
+            BlockVersion,
+        ]
+        .into_iter()
+        .find(|p| *p as u8 == id)
+    }
+
+    /// Name of the parameter in the node's `/info` parameters.
+    pub fn name(&self) -> &'static str {
+        use ParameterId::*;
+        match self {
+            StorageFeeFactor => "storageFeeFactor",
+            MinValuePerByte => "minValuePerByte",
+            MaxBlockSize => "maxBlockSize",
+            MaxBlockCost => "maxBlockCost",
+            TokenAccessCost => "tokenAccessCost",
+            InputCost => "inputCost",
+            DataInputCost => "dataInputCost",
+            OutputCost => "outputCost",
+            SubblocksPerBlock => "subblocksPerBlock",
+            SoftFork => "softFork",
+            SoftForkVotesCollected => "softForkVotesCollected",
+            SoftForkStartingHeight => "softForkStartingHeight",
+            BlockVersion => "blockVersion",
+        }
+    }
+}
+
+/// System parameters declared in an epoch-start extension.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parameters {
+    values: BTreeMap<ParameterId, i32>,
+}
+
+impl Parameters {
+    pub fn get(&self, id: ParameterId) -> Option<i32> {
+        self.values.get(&id).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ParameterId, i32)> + '_ {
+        self.values.iter().map(|(k, v)| (*k, *v))
+    }
+
+    pub fn storage_fee_factor(&self) -> Option<i32> {
+        self.get(ParameterId::StorageFeeFactor)
+    }
+
+    pub fn min_value_per_byte(&self) -> Option<i32> {
+        self.get(ParameterId::MinValuePerByte)
+    }
+
+    pub fn max_block_size(&self) -> Option<i32> {
+        self.get(ParameterId::MaxBlockSize)
+    }
+
+    pub fn max_block_cost(&self) -> Option<i32> {
+        self.get(ParameterId::MaxBlockCost)
+    }
+
+    pub fn block_version(&self) -> Option<i32> {
+        self.get(ParameterId::BlockVersion)
+    }
+}
+
+/// A single vote from a header's `votes` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Vote {
+    Increase(ParameterId),
+    Decrease(ParameterId),
+    SoftFork,
+    /// A vote for a parameter id this crate doesn't know about.
+    Unknown(i8),
+}
+
+impl Extension {
+    /// Parses the system parameter fields. Unknown parameter ids and non-parameter values
+    /// (e.g. validation rule updates) are skipped.
+    pub fn parameters(&self) -> Result<Parameters, CodecError> {
+        let mut values = BTreeMap::new();
+        for (key, value) in &self.fields {
+            let [PARAMETERS_PREFIX, id] = key.0[..] else { continue };
+            let Some(id) = ParameterId::from_id(id) else { continue };
+            let bytes = value
+                .0
+                .as_slice()
+                .try_into()
+                .map_err(|_| CodecError("parameter must be 4 bytes"))?;
+            values.insert(id, i32::from_be_bytes(bytes));
+        }
+        Ok(Parameters { values })
+    }
+
+    /// Unpacks the interlink vector: each field stores a repetition count followed by an id.
+    pub fn interlinks(&self) -> Result<Vec<HashDigest>, CodecError> {
+        let mut packed: Vec<(u8, &HexBytes)> = self
+            .fields
+            .iter()
+            .filter_map(|(key, value)| match key.0[..] {
+                [INTERLINKS_PREFIX, index] => Some((index, value)),
+                _ => None,
+            })
+            .collect();
+        packed.sort_by_key(|(index, _)| *index);
+
+        let mut interlinks = Vec::new();
+        for (_, value) in packed {
+            let (qty, id) = value
+                .0
+                .split_first()
+                .ok_or(CodecError("empty interlink field"))?;
+            let id: [u8; 32] = id
+                .try_into()
+                .map_err(|_| CodecError("interlink must be 33 bytes"))?;
+            interlinks.extend(std::iter::repeat_n(Digest(id), *qty as usize));
+        }
+        Ok(interlinks)
+    }
+}
+
+/// Parses a header's three vote slots, skipping empty (zero) slots.
+pub fn parse_votes(votes: &[u8]) -> Vec<Vote> {
+    votes
+        .iter()
+        .filter(|v| **v != 0)
+        .map(|v| {
+            let v = *v as i8;
+            match ParameterId::from_id(v.unsigned_abs()) {
+                Some(ParameterId::SoftFork) => Vote::SoftFork,
+                Some(id) if v > 0 => Vote::Increase(id),
+                Some(id) => Vote::Decrease(id),
+                None => Vote::Unknown(v),
+            }
+        })
+        .collect()
+}
+
+/// Height of the epoch-start block whose extension declares the parameters in force at `height`.
+pub fn epoch_start(height: Height) -> Height {
+    Height(height.0 - height.0 % VOTING_EPOCH_LENGTH)
+}
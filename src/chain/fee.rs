@@ -0,0 +1,74 @@
+use once_cell::sync::Lazy;
+
+use crate::{
+    chain::{
+        ergo_tree::{self, ErgoTreeParts},
+        register, transaction,
+        value::{self, ValueError},
+    },
+    codec::CodecError,
+    types::{
+        HashDigest,
+        ergo::{BoxCandidate, SignedTransaction, UTxO},
+    },
+};
+
+/// The standard miner fee contract (mainnet, 720 blocks reward delay).
+pub static FEE_ERGO_TREE: Lazy<Vec<u8>> = Lazy::new(|| {
+    hex::decode(
+        "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304",
+    )
+    .unwrap()
+});
+
+/// Constants of the miner reward script before the miner's key: the 720 blocks reward delay,
+/// and the type of the key.
+const MINER_REWARD_PREFIX: [u8; 7] = [0x10, 0x02, 0x04, 0xa0, 0x0b, 0x08, 0xcd];
+const MINER_REWARD_BODY: [u8; 14] =
+    [0xea, 0x02, 0xd1, 0x92, 0xa3, 0x9a, 0x8c, 0xc7, 0xa7, 0x01, 0x73, 0x00, 0x73, 0x01];
+
+static MINER_REWARD_TEMPLATE: Lazy<HashDigest> =
+    Lazy::new(|| ergo_tree::template_hash(&miner_reward_tree(&[0x02; 33])).unwrap());
+
+/// The script guarding block rewards and collected fees of a miner, spendable with the
+/// miner's key once the reward delay has passed.
+pub fn miner_reward_tree(miner_key: &[u8; 33]) -> Vec<u8> {
+    [&MINER_REWARD_PREFIX[..], miner_key, &MINER_REWARD_BODY].concat()
+}
+
+/// Key of the miner paid by a box guarded by the miner reward script, whatever its delay.
+pub fn miner_reward_key(ergo_tree: &[u8]) -> Option<[u8; 33]> {
+    let parts = ErgoTreeParts::parse(ergo_tree).ok()?;
+    if parts.template_hash() != *MINER_REWARD_TEMPLATE {
+        return None;
+    }
+    let constants = parts.constant_list().ok()?;
+    register::decode_prove_dlog(constants.get(1)?).ok()
+}
+
+/// Sum of the outputs paying the miner fee contract.
+pub fn outputs_fee(outputs: &[UTxO]) -> Result<u64, ValueError> {
+    value::checked_total(
+        outputs
+            .iter()
+            .filter(|o| o.ergo_tree.0 == *FEE_ERGO_TREE)
+            .map(|o| o.value),
+    )
+}
+
+/// Sum of the candidates paying the miner fee contract.
+pub fn candidates_fee(candidates: &[BoxCandidate]) -> Result<u64, ValueError> {
+    value::checked_total(
+        candidates
+            .iter()
+            .filter(|c| c.ergo_tree.0 == *FEE_ERGO_TREE)
+            .map(|c| c.value),
+    )
+}
+
+/// Miner fee per byte of the serialized transaction, in nanoERG.
+pub fn fee_per_byte(tx: &SignedTransaction) -> Result<f64, CodecError> {
+    let size = transaction::serialize(tx)?.len();
+    let fee = candidates_fee(&tx.outputs).map_err(|_| CodecError("miner fee overflows"))?;
+    Ok(fee as f64 / size as f64)
+}
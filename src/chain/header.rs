@@ -0,0 +1,127 @@
+use crate::{
+    codec::{CodecError, Reader, Writer},
+    hash::blake2b256,
+    types::{
+        Digest, HashDigest, Height, HexBytes, TimestampMillis,
+        ergo::{BlockHeader, PowSolution},
+    },
+};
+
+/// Header version that introduced Autolykos v2 and the `unparsedBytes` field.
+pub const AUTOLYKOS_V2_VERSION: u8 = 2;
+
+/// Serializes the header fields covered by the proof-of-work message.
+pub fn serialize_without_pow(header: &BlockHeader) -> Result<Vec<u8>, CodecError> {
+    let votes: [u8; 3] = header
+        .votes
+        .0
+        .as_slice()
+        .try_into()
+        .map_err(|_| CodecError("votes must be 3 bytes"))?;
+
+    let mut w = Writer::new();
+    w.put_u8(header.version)
+        .put_bytes(&header.parent_id.0)
+        .put_bytes(&header.ad_proofs_root.0)
+        .put_bytes(&header.transactions_root.0)
+        .put_bytes(&header.state_root.0)
+        .put_ulong(header.timestamp.0)
+        .put_bytes(&header.extension_root.0)
+        .put_bytes(&header.n_bits.to_be_bytes())
+        .put_uint(header.height.0)
+        .put_bytes(&votes);
+
+    if header.version > 1 {
+        let len = u8::try_from(header.unparsed_bytes.0.len())
+            .map_err(|_| CodecError("unparsed bytes too long"))?;
+        w.put_u8(len).put_bytes(&header.unparsed_bytes.0);
+    }
+
+    Ok(w.into_bytes())
+}
+
+/// Serializes the full header, including the proof-of-work solution.
+///
+/// Only Autolykos v2 headers are supported: the v1 solution distance `d` is a 256-bit integer
+/// that doesn't survive JSON number parsing.
+pub fn serialize(header: &BlockHeader) -> Result<Vec<u8>, CodecError> {
+    if header.version < AUTOLYKOS_V2_VERSION {
+        return Err(CodecError("Autolykos v1 headers are not supported"));
+    }
+
+    let mut bytes = serialize_without_pow(header)?;
+    bytes.extend_from_slice(&header.pow_solution.pk.0);
+    bytes.extend_from_slice(&header.pow_solution.n.0);
+    Ok(bytes)
+}
+
+/// `w` of Autolykos v2 solutions, which only carry `pk` and `n`: the secp256k1 generator.
+const V2_SOLUTION_W: [u8; 33] = [
+    0x02, 0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87, 0x0b,
+    0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17,
+    0x98,
+];
+
+/// Parses a header serialized by [`serialize`], computing its id. Section ids and the
+/// other fields only present in the node's JSON are left empty.
+pub fn parse(bytes: &[u8]) -> Result<BlockHeader, CodecError> {
+    let mut r = Reader::new(bytes);
+    let header = read_header(&mut r)?;
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after header"));
+    }
+    Ok(header)
+}
+
+fn read_header(r: &mut Reader) -> Result<BlockHeader, CodecError> {
+    let start = r.rest();
+    let version = r.get_u8()?;
+    if version < AUTOLYKOS_V2_VERSION {
+        return Err(CodecError("Autolykos v1 headers are not supported"));
+    }
+
+    let parent_id = Digest(r.get_array()?);
+    let ad_proofs_root = Digest(r.get_array()?);
+    let transactions_root = Digest(r.get_array()?);
+    let state_root = Digest(r.get_array()?);
+    let timestamp = TimestampMillis(r.get_ulong()?);
+    let extension_root = Digest(r.get_array()?);
+    let n_bits = u32::from_be_bytes(r.get_array()?);
+    let height = Height(r.get_uint()?);
+    let votes = HexBytes(r.get_bytes(3)?.to_vec());
+    let len = r.get_u8()? as usize;
+    let unparsed_bytes = HexBytes(r.get_bytes(len)?.to_vec());
+    let pow_solution = PowSolution {
+        pk: Digest(r.get_array()?),
+        w: Digest(V2_SOLUTION_W),
+        n: Digest(r.get_array()?),
+        d: 0.into(),
+    };
+    let size = start.len() - r.remaining();
+
+    Ok(BlockHeader {
+        id: Digest(blake2b256(&start[..size])),
+        parent_id,
+        height,
+        version,
+        ad_proofs_root,
+        transactions_root,
+        state_root,
+        timestamp,
+        extension_root,
+        n_bits,
+        votes,
+        unparsed_bytes,
+        pow_solution,
+        extension_id: None,
+        ad_proofs_id: None,
+        transactions_id: None,
+        difficulty: None,
+        size: Some(size as u32),
+    })
+}
+
+/// Computes the header id (blake2b256 of the serialized header).
+pub fn compute_id(header: &BlockHeader) -> Result<HashDigest, CodecError> {
+    Ok(Digest(blake2b256(&serialize(header)?)))
+}
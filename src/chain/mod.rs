@@ -0,0 +1,17 @@
+pub mod avl;
+pub mod block;
+pub mod candidate;
+pub mod ergo_box;
+pub mod ergo_tree;
+pub mod extension;
+pub mod fee;
+pub mod header;
+pub mod nipopow;
+pub mod pow;
+pub mod reduced;
+pub mod register;
+#[cfg(feature = "proofs")]
+pub mod schnorr;
+pub mod transaction;
+pub mod value;
+pub mod vectors;
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+use super::{header, pow};
+use crate::{
+    codec::CodecError,
+    types::{HashDigest, ergo::BlockHeader},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum NipopowError {
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+
+    #[error("Header id mismatch: claimed {claimed}, computed {computed}.")]
+    IdMismatch { claimed: HashDigest, computed: HashDigest },
+
+    #[error("Header {0} has an invalid proof-of-work.")]
+    InvalidPow(HashDigest),
+
+    #[error("Header {to} does not link to {from}.")]
+    BrokenConnection { from: HashDigest, to: HashDigest },
+
+    #[error("Header heights are not strictly increasing at {0}.")]
+    InvalidHeights(HashDigest),
+
+    #[error("Expected a suffix of {expected} headers, got {actual}.")]
+    InvalidSuffixLength { expected: u32, actual: usize },
+}
+
+/// A header together with its interlink vector.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoPowHeader {
+    pub header: BlockHeader,
+    pub interlinks: Vec<HashDigest>,
+    /// Batch merkle proof of the interlinks against the header's extension root.
+    #[serde(default)]
+    pub interlinks_proof: serde_json::Value,
+}
+
+/// A NiPoPoW proof as returned by the node's `/nipopow/proof` endpoints.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NipopowProof {
+    /// Security parameter: minimum superchain length.
+    pub m: u32,
+    /// Security parameter: suffix length.
+    pub k: u32,
+    pub prefix: Vec<PoPowHeader>,
+    pub suffix_head: PoPowHeader,
+    pub suffix_tail: Vec<BlockHeader>,
+    #[serde(default)]
+    pub continuous: bool,
+}
+
+impl NipopowProof {
+    /// All headers in chain order: prefix, suffix head, suffix tail.
+    pub fn headers_chain(&self) -> impl Iterator<Item = &BlockHeader> {
+        self.prefix
+            .iter()
+            .map(|h| &h.header)
+            .chain(std::iter::once(&self.suffix_head.header))
+            .chain(self.suffix_tail.iter())
+    }
+
+    /// The header the proof attests to.
+    pub fn tip(&self) -> &BlockHeader {
+        self.suffix_tail.last().unwrap_or(&self.suffix_head.header)
+    }
+
+    /// Verifies the proof without trusting the node that produced it.
+    ///
+    /// Checks the suffix length, recomputes every header id, validates each header's PoW and
+    /// checks that the chain is connected (by parent id or interlinks) with increasing heights.
+    /// Interlink merkle proofs against the extension root are not checked.
+    pub fn verify(&self) -> Result<(), NipopowError> {
+        let suffix_len = self.suffix_tail.len() + 1;
+        if suffix_len != self.k as usize {
+            return Err(NipopowError::InvalidSuffixLength { expected: self.k, actual: suffix_len });
+        }
+
+        for header in self.headers_chain() {
+            let computed = header::compute_id(header)?;
+            if computed != header.id {
+                return Err(NipopowError::IdMismatch { claimed: header.id.clone(), computed });
+            }
+            if !pow::check_pow(header)? {
+                return Err(NipopowError::InvalidPow(header.id.clone()));
+            }
+        }
+
+        let prefix = self.prefix.iter().chain(std::iter::once(&self.suffix_head));
+        for (prev, next) in prefix.clone().zip(prefix.skip(1)) {
+            let linked = next.header.parent_id == prev.header.id
+                || next.interlinks.contains(&prev.header.id);
+            check_link(&prev.header, &next.header, linked)?;
+        }
+
+        let suffix = std::iter::once(&self.suffix_head.header).chain(self.suffix_tail.iter());
+        for (prev, next) in suffix.clone().zip(suffix.skip(1)) {
+            check_link(prev, next, next.parent_id == prev.id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compares two valid proofs by the quality of their chains after the lowest common
+    /// ancestor. Returns `false` if the proofs share no header.
+    pub fn is_better_than(&self, other: &NipopowProof) -> Result<bool, NipopowError> {
+        let Some(ancestor) = self
+            .headers_chain()
+            .filter(|h| other.contains(&h.id))
+            .last()
+        else {
+            return Ok(false);
+        };
+
+        let ours = self.headers_chain().filter(|h| h.height > ancestor.height);
+        let theirs = other.headers_chain().filter(|h| h.height > ancestor.height);
+        Ok(best_arg(ours, self.m)? > best_arg(theirs, self.m)?)
+    }
+
+    fn contains(&self, id: &HashDigest) -> bool {
+        self.headers_chain().any(|h| &h.id == id)
+    }
+}
+
+/// Best superchain score of `chain`: the maximum of `2^level * count` over every level with at
+/// least `m` headers (level 0 always counts).
+pub fn best_arg<'a>(
+    chain: impl Iterator<Item = &'a BlockHeader>,
+    m: u32,
+) -> Result<u128, NipopowError> {
+    let levels = chain.map(pow::max_level).collect::<Result<Vec<_>, _>>()?;
+
+    let mut best = levels.len() as u128;
+    for level in 1.. {
+        let count = levels.iter().filter(|l| **l >= level).count();
+        if count < m as usize || count == 0 {
+            break;
+        }
+        let weight = 1u128.checked_shl(level).unwrap_or(u128::MAX);
+        best = best.max(weight.saturating_mul(count as u128));
+    }
+
+    Ok(best)
+}
+
+fn check_link(prev: &BlockHeader, next: &BlockHeader, linked: bool) -> Result<(), NipopowError> {
+    if !linked {
+        return Err(NipopowError::BrokenConnection { from: prev.id.clone(), to: next.id.clone() });
+    }
+    if next.height <= prev.height {
+        return Err(NipopowError::InvalidHeights(next.id.clone()));
+    }
+    Ok(())
+}
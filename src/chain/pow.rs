@@ -0,0 +1,120 @@
+use num_bigint::BigUint;
+use once_cell::sync::Lazy;
+
+use super::header::{self, AUTOLYKOS_V2_VERSION};
+use crate::{
+    codec::CodecError,
+    hash::blake2b256,
+    types::{Height, ergo::BlockHeader},
+};
+
+/// Number of elements summed per Autolykos solution.
+const K: usize = 32;
+const N_BASE: u32 = 1 << 26;
+const N_INCREASE_START: u32 = 600 * 1024;
+const N_INCREASE_PERIOD: u32 = 50 * 1024;
+const N_INCREASE_MAX_HEIGHT: u32 = 4_198_400;
+
+/// Order of the secp256k1 group.
+static Q: Lazy<BigUint> = Lazy::new(|| {
+    BigUint::parse_bytes(b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141", 16)
+        .unwrap()
+});
+
+/// Big-endian `0..1024` as 8-byte longs, appended to every element hash.
+static M: Lazy<Vec<u8>> = Lazy::new(|| (0u64..1024).flat_map(u64::to_be_bytes).collect());
+
+/// Decodes a compact ("nBits") difficulty representation. Negative values decode to zero.
+pub fn decode_compact_bits(n_bits: u32) -> BigUint {
+    let size = n_bits >> 24;
+    let mantissa = BigUint::from(n_bits & 0x007f_ffff);
+    if n_bits & 0x0080_0000 != 0 {
+        return BigUint::ZERO;
+    }
+
+    if size <= 3 { mantissa >> (8 * (3 - size)) } else { mantissa << (8 * (size - 3)) }
+}
+
+/// Autolykos v2 table size at `height`: grows by 5% every 50k blocks until a fixed height.
+pub fn calc_n(height: Height) -> u32 {
+    let height = height.0.min(N_INCREASE_MAX_HEIGHT);
+    if height < N_INCREASE_START {
+        return N_BASE;
+    }
+
+    let iterations = (height - N_INCREASE_START) / N_INCREASE_PERIOD + 1;
+    (0..iterations).fold(N_BASE, |n, _| n / 100 * 105)
+}
+
+/// Target the PoW hit must stay below: `q / difficulty`.
+pub fn target(header: &BlockHeader) -> BigUint {
+    let difficulty = decode_compact_bits(header.n_bits);
+    if difficulty == BigUint::ZERO { BigUint::ZERO } else { &*Q / difficulty }
+}
+
+/// Computes the Autolykos v2 PoW hit of a header.
+pub fn pow_hit(header: &BlockHeader) -> Result<BigUint, CodecError> {
+    if header.version < AUTOLYKOS_V2_VERSION {
+        return Err(CodecError("Autolykos v1 headers are not supported"));
+    }
+
+    let msg = blake2b256(&header::serialize_without_pow(header)?);
+    let nonce = &header.pow_solution.n.0;
+    let h = header.height.0.to_be_bytes();
+    let n = calc_n(header.height);
+
+    let prei8 = BigUint::from_bytes_be(&blake2b256(&[&msg[..], nonce].concat())[24..]);
+    let i = u32::try_from(prei8 % n).unwrap().to_be_bytes();
+    let f = blake2b256(&[&i[..], &h, &M].concat());
+    let seed = [&f[1..], &msg, nonce].concat();
+
+    let sum = gen_indexes(&seed, n)
+        .into_iter()
+        .fold(BigUint::ZERO, |acc, idx| {
+            let element = blake2b256(&[&idx.to_be_bytes()[..], &h, &M].concat());
+            acc + BigUint::from_bytes_be(&element[1..])
+        });
+
+    let sum_bytes = sum.to_bytes_be();
+    let mut padded = [0u8; 32];
+    padded[32 - sum_bytes.len()..].copy_from_slice(&sum_bytes);
+
+    Ok(BigUint::from_bytes_be(&blake2b256(&padded)))
+}
+
+/// Whether the header's PoW hit is below its difficulty target.
+pub fn check_pow(header: &BlockHeader) -> Result<bool, CodecError> {
+    Ok(pow_hit(header)? < target(header))
+}
+
+/// NiPoPoW superblock level: `floor(log2(target) - log2(hit))`. The genesis block has
+/// unbounded level.
+pub fn max_level(header: &BlockHeader) -> Result<u32, CodecError> {
+    if header.height == 1 {
+        return Ok(u32::MAX);
+    }
+
+    let hit = pow_hit(header)?;
+    let level = log2(&target(header)) - log2(&hit);
+    Ok(level.max(0.0) as u32)
+}
+
+fn gen_indexes(seed: &[u8], n: u32) -> Vec<u32> {
+    let hash = blake2b256(seed);
+    let extended = [&hash[..], &hash[..3]].concat();
+    (0..K)
+        .map(|i| u32::from_be_bytes(extended[i..i + 4].try_into().unwrap()) % n)
+        .collect()
+}
+
+fn log2(value: &BigUint) -> f64 {
+    let bits = value.bits();
+    if bits <= 53 {
+        return (value.iter_u64_digits().next().unwrap_or(0) as f64).log2();
+    }
+
+    // Keep the 53 most significant bits so the mantissa fits an f64 exactly.
+    let shift = bits - 53;
+    let top = (value >> shift).iter_u64_digits().next().unwrap_or(0) as f64;
+    top.log2() + shift as f64
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chain::transaction,
+    codec::{CodecError, Writer},
+    types::{HexBytes, ergo::UnsignedTransaction},
+};
+
+/// An input script already reduced to the sigma proposition the signer has to prove.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReducedInput {
+    /// Serialized `SigmaBoolean`.
+    #[serde(rename = "sigmaProp")]
+    pub sigma_prop: HexBytes,
+    pub cost: u64,
+}
+
+/// A transaction with every input reduced in the context of the current blockchain state
+/// (EIP-19), so a cold wallet only needs a sigma prover to sign it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReducedTransaction {
+    pub unsigned_tx: UnsignedTransaction,
+    /// One entry per input, in input order.
+    pub reduced_inputs: Vec<ReducedInput>,
+    pub cost: u32,
+}
+
+impl ReducedTransaction {
+    /// Serializes the transaction in the EIP-19 binary format.
+    pub fn serialize(&self) -> Result<Vec<u8>, CodecError> {
+        if self.reduced_inputs.len() != self.unsigned_tx.inputs.len() {
+            return Err(CodecError("one reduced input is required per input"));
+        }
+
+        let message = transaction::bytes_to_sign(&self.unsigned_tx)?;
+        let len = u32::try_from(message.len()).map_err(|_| CodecError("transaction too large"))?;
+
+        let mut w = Writer::new();
+        w.put_uint(len).put_bytes(&message);
+        for input in &self.reduced_inputs {
+            w.put_bytes(&input.sigma_prop.0).put_ulong(input.cost);
+        }
+        w.put_uint(self.cost);
+        Ok(w.into_bytes())
+    }
+}
@@ -0,0 +1,343 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{
+    codec::{CodecError, Reader, Writer},
+    types::{Digest, HashDigest, HexBytes},
+};
+
+const BOOLEAN: u8 = 0x01;
+const INT: u8 = 0x04;
+const LONG: u8 = 0x05;
+const SIGMA_PROP: u8 = 0x08;
+const COLL_BYTE: u8 = 0x0e;
+const COLL_COLL_BYTE: u8 = 0x1a;
+const COLL_LONG: u8 = 0x11;
+const PROVE_DLOG: u8 = 0xcd;
+
+/// A serialized constant of one of the types found in registers and context extensions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constant {
+    Boolean(bool),
+    Int(i32),
+    Long(i64),
+    CollByte(Vec<u8>),
+    CollCollByte(Vec<Vec<u8>>),
+    CollLong(Vec<i64>),
+    /// Public key of a `ProveDlog` sigma proposition.
+    ProveDlog([u8; 33]),
+}
+
+impl Constant {
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        match bytes.first() {
+            Some(&BOOLEAN) => decode(bytes, BOOLEAN, |r| match r.get_u8()? {
+                0 => Ok(Self::Boolean(false)),
+                1 => Ok(Self::Boolean(true)),
+                _ => Err(CodecError("invalid boolean constant")),
+            }),
+            Some(&INT) => decode_int(bytes).map(Self::Int),
+            Some(&LONG) => decode_long(bytes).map(Self::Long),
+            Some(&COLL_BYTE) => decode_coll_byte(bytes).map(Self::CollByte),
+            Some(&COLL_COLL_BYTE) => decode_coll_coll_byte(bytes).map(Self::CollCollByte),
+            Some(&COLL_LONG) => decode_coll_long(bytes).map(Self::CollLong),
+            Some(&SIGMA_PROP) => decode_prove_dlog(bytes).map(Self::ProveDlog),
+            Some(_) => Err(CodecError("unsupported constant type")),
+            None => Err(CodecError("unexpected end of buffer")),
+        }
+    }
+
+    /// The ErgoScript name of the constant's type.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Boolean(_) => bool::TYPE_NAME,
+            Self::Int(_) => i32::TYPE_NAME,
+            Self::Long(_) => i64::TYPE_NAME,
+            Self::CollByte(_) => Vec::<u8>::TYPE_NAME,
+            Self::CollCollByte(_) => Vec::<Vec<u8>>::TYPE_NAME,
+            Self::CollLong(_) => Vec::<i64>::TYPE_NAME,
+            Self::ProveDlog(_) => <[u8; 33]>::TYPE_NAME,
+        }
+    }
+
+    pub fn encode(&self) -> HexBytes {
+        let mut w = Writer::new();
+        match self {
+            Self::Boolean(v) => w.put_u8(BOOLEAN).put_u8(*v as u8),
+            Self::Int(v) => w.put_u8(INT).put_int(*v),
+            Self::Long(v) => put_long(w.put_u8(LONG), *v),
+            Self::CollByte(bytes) => put_coll_byte(w.put_u8(COLL_BYTE), bytes),
+            Self::CollCollByte(items) => {
+                w.put_u8(COLL_COLL_BYTE).put_uint(items.len() as u32);
+                for item in items {
+                    put_coll_byte(&mut w, item);
+                }
+                &mut w
+            }
+            Self::CollLong(items) => {
+                w.put_u8(COLL_LONG).put_uint(items.len() as u32);
+                for item in items {
+                    put_long(&mut w, *item);
+                }
+                &mut w
+            }
+            Self::ProveDlog(public_key) => w
+                .put_u8(SIGMA_PROP)
+                .put_u8(PROVE_DLOG)
+                .put_bytes(public_key),
+        };
+        HexBytes(w.into_bytes())
+    }
+}
+
+impl From<bool> for Constant {
+    fn from(v: bool) -> Self {
+        Self::Boolean(v)
+    }
+}
+
+impl From<i32> for Constant {
+    fn from(v: i32) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<i64> for Constant {
+    fn from(v: i64) -> Self {
+        Self::Long(v)
+    }
+}
+
+impl From<Vec<u8>> for Constant {
+    fn from(v: Vec<u8>) -> Self {
+        Self::CollByte(v)
+    }
+}
+
+impl From<Vec<Vec<u8>>> for Constant {
+    fn from(v: Vec<Vec<u8>>) -> Self {
+        Self::CollCollByte(v)
+    }
+}
+
+impl From<Vec<i64>> for Constant {
+    fn from(v: Vec<i64>) -> Self {
+        Self::CollLong(v)
+    }
+}
+
+/// One of the non-mandatory registers of a box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegisterId {
+    R4,
+    R5,
+    R6,
+    R7,
+    R8,
+    R9,
+}
+
+impl RegisterId {
+    pub const ALL: [Self; 6] = [Self::R4, Self::R5, Self::R6, Self::R7, Self::R8, Self::R9];
+
+    /// Register `n`, from 4 to 9.
+    pub fn new(n: u8) -> Option<Self> {
+        Self::ALL.get(n.checked_sub(4)? as usize).copied()
+    }
+
+    pub fn number(self) -> u8 {
+        self as u8 + 4
+    }
+}
+
+impl Display for RegisterId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "R{}", self.number())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RegisterError {
+    #[error("Register {0} is not set")]
+    Missing(RegisterId),
+
+    #[error("Register {register} holds a value of type {found}, expected {expected}")]
+    WrongType { register: RegisterId, expected: &'static str, found: &'static str },
+
+    #[error("Invalid {expected} in register {register}: {error}")]
+    Invalid { register: RegisterId, expected: &'static str, error: CodecError },
+}
+
+/// A value decoded from a serialized constant, see [`decode_register`].
+pub trait SigmaDecode: Sized {
+    /// The ErgoScript name of the type, for errors.
+    const TYPE_NAME: &'static str;
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError>;
+}
+
+/// Decodes the value of `register`, telling apart a register that isn't set, one holding
+/// another type and one that doesn't decode.
+pub fn decode_register<T: SigmaDecode>(
+    register: RegisterId,
+    value: Option<&HexBytes>,
+) -> Result<T, RegisterError> {
+    let bytes = &value.ok_or(RegisterError::Missing(register))?.0;
+    T::sigma_decode(bytes).map_err(|error| match Constant::decode(bytes) {
+        Ok(constant) if constant.type_name() != T::TYPE_NAME => RegisterError::WrongType {
+            register,
+            expected: T::TYPE_NAME,
+            found: constant.type_name(),
+        },
+        _ => RegisterError::Invalid { register, expected: T::TYPE_NAME, error },
+    })
+}
+
+impl SigmaDecode for Constant {
+    const TYPE_NAME: &'static str = "Any";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        Self::decode(bytes)
+    }
+}
+
+impl SigmaDecode for bool {
+    const TYPE_NAME: &'static str = "Boolean";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        match Constant::decode(bytes)? {
+            Constant::Boolean(v) => Ok(v),
+            _ => Err(CodecError("unexpected register type")),
+        }
+    }
+}
+
+impl SigmaDecode for i32 {
+    const TYPE_NAME: &'static str = "Int";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_int(bytes)
+    }
+}
+
+impl SigmaDecode for i64 {
+    const TYPE_NAME: &'static str = "Long";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_long(bytes)
+    }
+}
+
+impl SigmaDecode for Vec<u8> {
+    const TYPE_NAME: &'static str = "Coll[Byte]";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_coll_byte(bytes)
+    }
+}
+
+/// A `Coll[Byte]` of 32 bytes, e.g. a token or box id.
+impl SigmaDecode for HashDigest {
+    const TYPE_NAME: &'static str = "Coll[Byte]";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let bytes = decode_coll_byte(bytes)?;
+        Ok(Digest(
+            bytes
+                .try_into()
+                .map_err(|_| CodecError("expected 32 bytes"))?,
+        ))
+    }
+}
+
+impl SigmaDecode for Vec<Vec<u8>> {
+    const TYPE_NAME: &'static str = "Coll[Coll[Byte]]";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_coll_coll_byte(bytes)
+    }
+}
+
+impl SigmaDecode for Vec<i64> {
+    const TYPE_NAME: &'static str = "Coll[Long]";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_coll_long(bytes)
+    }
+}
+
+/// The public key of a `ProveDlog` sigma proposition.
+impl SigmaDecode for [u8; 33] {
+    const TYPE_NAME: &'static str = "SigmaProp";
+
+    fn sigma_decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        decode_prove_dlog(bytes)
+    }
+}
+
+pub fn decode_int(bytes: &[u8]) -> Result<i32, CodecError> {
+    decode(bytes, INT, |r| r.get_int())
+}
+
+pub fn decode_long(bytes: &[u8]) -> Result<i64, CodecError> {
+    decode(bytes, LONG, get_long)
+}
+
+pub fn decode_coll_byte(bytes: &[u8]) -> Result<Vec<u8>, CodecError> {
+    decode(bytes, COLL_BYTE, get_coll_byte)
+}
+
+pub fn decode_coll_coll_byte(bytes: &[u8]) -> Result<Vec<Vec<u8>>, CodecError> {
+    decode(bytes, COLL_COLL_BYTE, |r| {
+        let len = r.get_uint()?;
+        (0..len).map(|_| get_coll_byte(r)).collect()
+    })
+}
+
+pub fn decode_coll_long(bytes: &[u8]) -> Result<Vec<i64>, CodecError> {
+    decode(bytes, COLL_LONG, |r| {
+        let len = r.get_uint()?;
+        (0..len).map(|_| get_long(r)).collect()
+    })
+}
+
+/// Public key of a `ProveDlog` sigma proposition constant.
+pub fn decode_prove_dlog(bytes: &[u8]) -> Result<[u8; 33], CodecError> {
+    decode(bytes, SIGMA_PROP, |r| match r.get_u8()? {
+        PROVE_DLOG => r.get_array(),
+        _ => Err(CodecError("sigma proposition is not a ProveDlog")),
+    })
+}
+
+fn decode<T>(
+    bytes: &[u8],
+    tpe: u8,
+    read: impl FnOnce(&mut Reader) -> Result<T, CodecError>,
+) -> Result<T, CodecError> {
+    let mut r = Reader::new(bytes);
+    if r.get_u8()? != tpe {
+        return Err(CodecError("unexpected register type"));
+    }
+    let value = read(&mut r)?;
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after register value"));
+    }
+    Ok(value)
+}
+
+fn get_long(r: &mut Reader) -> Result<i64, CodecError> {
+    let v = r.get_ulong()?;
+    Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+}
+
+fn get_coll_byte(r: &mut Reader) -> Result<Vec<u8>, CodecError> {
+    let len = r.get_uint()? as usize;
+    Ok(r.get_bytes(len)?.to_vec())
+}
+
+fn put_long(w: &mut Writer, v: i64) -> &mut Writer {
+    w.put_ulong(((v << 1) ^ (v >> 63)) as u64)
+}
+
+fn put_coll_byte<'w>(w: &'w mut Writer, bytes: &[u8]) -> &'w mut Writer {
+    w.put_uint(bytes.len() as u32).put_bytes(bytes)
+}
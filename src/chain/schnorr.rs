@@ -0,0 +1,107 @@
+//! Verification of the Schnorr proofs spending P2PK boxes, i.e. sigma proofs of a single
+//! `ProveDlog`.
+//!
+//! A proof is the challenge `e` (24 bytes) followed by the response `z` (32 bytes). The
+//! commitment `a = g^z * h^-e` is recomputed from them and the public key `h`, and the proof
+//! holds if hashing it with the proposition and the message gives back `e` (Fiat-Shamir).
+
+use k256::{
+    AffinePoint, ProjectivePoint, PublicKey, Scalar,
+    elliptic_curve::{PrimeField, sec1::ToEncodedPoint},
+};
+
+use crate::{
+    chain::transaction,
+    codec::CodecError,
+    hash::blake2b256,
+    types::ergo::{TransactionInput, UnconfirmedTransaction},
+};
+
+pub const CHALLENGE_LEN: usize = 24;
+pub const RESPONSE_LEN: usize = 32;
+pub const PROOF_LEN: usize = CHALLENGE_LEN + RESPONSE_LEN;
+const PUBLIC_KEY_LEN: usize = 33;
+
+const P2PK_TREE_PREFIX: [u8; 3] = [0x00, 0x08, 0xcd];
+/// Prefix of a leaf of the Fiat-Shamir tree.
+const LEAF_PREFIX: u8 = 1;
+
+/// Outcome of checking the proof of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofCheck {
+    Valid,
+    Invalid,
+    /// The input is not a P2PK box, so its proof needs a full script interpreter.
+    Unsupported,
+}
+
+/// The public key of a P2PK tree.
+pub fn p2pk_public_key(tree: &[u8]) -> Option<&[u8; PUBLIC_KEY_LEN]> {
+    tree.strip_prefix(&P2PK_TREE_PREFIX[..])?.try_into().ok()
+}
+
+/// The Fiat-Shamir challenge of a `ProveDlog` proof with `commitment` over `message`.
+pub fn challenge(
+    public_key: &[u8; PUBLIC_KEY_LEN],
+    commitment: &[u8; PUBLIC_KEY_LEN],
+    message: &[u8],
+) -> [u8; CHALLENGE_LEN] {
+    // The proposition as a constant-segregated tree: header, one constant, placeholder 0.
+    let mut proposition = vec![0x10, 0x01, 0x08, 0xcd];
+    proposition.extend_from_slice(public_key);
+    proposition.extend_from_slice(&[0x73, 0x00]);
+
+    let mut bytes = vec![LEAF_PREFIX];
+    bytes.extend_from_slice(&(proposition.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&proposition);
+    bytes.extend_from_slice(&(commitment.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(commitment);
+    bytes.extend_from_slice(message);
+    blake2b256(&bytes)[..CHALLENGE_LEN].try_into().unwrap()
+}
+
+/// Whether `proof` proves knowledge of the secret key of `public_key` over `message`.
+pub fn verify(public_key: &[u8; PUBLIC_KEY_LEN], proof: &[u8], message: &[u8]) -> bool {
+    let Ok(proof) = <&[u8; PROOF_LEN]>::try_from(proof) else {
+        return false;
+    };
+    let Ok(key) = PublicKey::from_sec1_bytes(public_key) else {
+        return false;
+    };
+    let (e, z) = proof.split_at(CHALLENGE_LEN);
+    let mut e_repr = [0; 32];
+    e_repr[32 - CHALLENGE_LEN..].copy_from_slice(e);
+    let e: Option<Scalar> = Scalar::from_repr(e_repr.into()).into();
+    let z: Option<Scalar> = Scalar::from_repr(<[u8; 32]>::try_from(z).unwrap().into()).into();
+    let (Some(e), Some(z)) = (e, z) else {
+        return false;
+    };
+
+    let commitment = ProjectivePoint::GENERATOR * z - key.to_projective() * e;
+    if commitment == ProjectivePoint::IDENTITY {
+        return false;
+    }
+    let encoded = AffinePoint::from(commitment).to_encoded_point(true);
+    let commitment = encoded.as_bytes().try_into().unwrap();
+    challenge(public_key, commitment, message)[..] == proof[..CHALLENGE_LEN]
+}
+
+/// Checks the proof of one input against the message signed by its transaction, see
+/// [`transaction::unconfirmed_bytes_to_sign`].
+pub fn check_input(input: &TransactionInput, message: &[u8]) -> ProofCheck {
+    match p2pk_public_key(&input.utxo.ergo_tree.0) {
+        Some(key) if verify(key, &input.spending_proof.proof_bytes.0, message) => ProofCheck::Valid,
+        Some(_) => ProofCheck::Invalid,
+        None => ProofCheck::Unsupported,
+    }
+}
+
+/// Checks the proofs of all inputs of a transaction, in input order.
+pub fn check_transaction(tx: &UnconfirmedTransaction) -> Result<Vec<ProofCheck>, CodecError> {
+    let message = transaction::unconfirmed_bytes_to_sign(tx)?;
+    Ok(tx
+        .inputs
+        .iter()
+        .map(|input| check_input(input, &message))
+        .collect())
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use crate::{
+    chain::{ergo_box, ergo_tree::skip_constant},
+    codec::{CodecError, Reader, Writer},
+    hash::blake2b256,
+    types::{
+        Digest, HashDigest, Height, HexBytes,
+        ergo::{
+            BoxCandidate, MinimalInput, NonMandatoryRegisters, SignedInput, SignedTransaction,
+            SpendingProof, Token, UTxO, UnconfirmedTransaction, UnsignedTransaction,
+        },
+    },
+};
+
+/// An input as serialized: box id, proof (empty in the message to sign) and context extension.
+struct InputBytes<'a> {
+    box_id: &'a HashDigest,
+    proof: &'a [u8],
+    extension: Vec<(u8, &'a [u8])>,
+}
+
+/// An output as serialized, from either a candidate or a box.
+struct OutputBytes<'a> {
+    value: u64,
+    ergo_tree: &'a HexBytes,
+    creation_height: Height,
+    tokens: &'a [Token],
+    registers: &'a NonMandatoryRegisters,
+}
+
+impl<'a> From<&'a BoxCandidate> for OutputBytes<'a> {
+    fn from(c: &'a BoxCandidate) -> Self {
+        Self {
+            value: c.value,
+            ergo_tree: &c.ergo_tree,
+            creation_height: c.creation_height,
+            tokens: &c.tokens,
+            registers: &c.registers,
+        }
+    }
+}
+
+impl<'a> From<&'a UTxO> for OutputBytes<'a> {
+    fn from(u: &'a UTxO) -> Self {
+        Self {
+            value: u.value,
+            ergo_tree: &u.ergo_tree,
+            creation_height: u.creation_height,
+            tokens: &u.tokens,
+            registers: &u.registers,
+        }
+    }
+}
+
+/// Serializes the message signed by every input: the transaction with empty proofs.
+pub fn bytes_to_sign(tx: &UnsignedTransaction) -> Result<Vec<u8>, CodecError> {
+    let inputs = tx
+        .inputs
+        .iter()
+        .map(|input| InputBytes {
+            box_id: &input.box_id,
+            proof: &[],
+            extension: input
+                .extension
+                .iter()
+                .map(|(id, value)| (*id, &value.0[..]))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    write_transaction(&inputs, &tx.data_inputs, &outputs(&tx.outputs))
+}
+
+/// Serializes a signed transaction in the binary format of the P2P layer and the node's
+/// `transactions/bytes` endpoints.
+pub fn serialize(tx: &SignedTransaction) -> Result<Vec<u8>, CodecError> {
+    write_transaction(&signed_inputs(tx, false)?, &tx.data_inputs, &outputs(&tx.outputs))
+}
+
+/// Size of a mempool transaction in the binary format, which miners and fee rates go by.
+pub fn unconfirmed_size(tx: &UnconfirmedTransaction) -> Result<usize, CodecError> {
+    let inputs = tx
+        .inputs
+        .iter()
+        .map(|input| input_bytes(&input.utxo.id, &input.spending_proof, false))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+    Ok(write_transaction(&inputs, &tx.data_inputs, &outputs(&tx.outputs))?.len())
+}
+
+/// Serializes the message signed by every input of a mempool transaction.
+pub fn unconfirmed_bytes_to_sign(tx: &UnconfirmedTransaction) -> Result<Vec<u8>, CodecError> {
+    let inputs = tx
+        .inputs
+        .iter()
+        .map(|input| input_bytes(&input.utxo.id, &input.spending_proof, true))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+    write_transaction(&inputs, &tx.data_inputs, &outputs(&tx.outputs))
+}
+
+/// Transaction id: blake2b256 of the transaction serialized without proofs.
+pub fn transaction_id(tx: &SignedTransaction) -> Result<HashDigest, CodecError> {
+    let inputs = signed_inputs(tx, true)?;
+    let message = write_transaction(&inputs, &tx.data_inputs, &outputs(&tx.outputs))?;
+    Ok(Digest(blake2b256(&message)))
+}
+
+/// Parses a transaction serialized by [`serialize`], computing its id.
+pub fn parse(bytes: &[u8]) -> Result<SignedTransaction, CodecError> {
+    let mut r = Reader::new(bytes);
+    let tx = read_transaction(&mut r)?;
+    if r.remaining() != 0 {
+        return Err(CodecError("trailing bytes after transaction"));
+    }
+    Ok(tx)
+}
+
+/// Reads one transaction from a sequence, e.g. the transactions section of a block.
+pub(crate) fn read_transaction(r: &mut Reader) -> Result<SignedTransaction, CodecError> {
+    let inputs = (0..get_count(r)?)
+        .map(|_| {
+            let box_id = Digest(r.get_array()?);
+            let proof_len = get_count(r)? as usize;
+            let proof_bytes = HexBytes(r.get_bytes(proof_len)?.to_vec());
+            let extension = (0..r.get_u8()?)
+                .map(|_| {
+                    let id = r.get_u8()?;
+                    let value = r.get_consumed(skip_constant)?;
+                    Ok((id.to_string(), HexBytes(value.to_vec())))
+                })
+                .collect::<Result<HashMap<_, _>, CodecError>>()?;
+            Ok(SignedInput { box_id, spending_proof: SpendingProof { proof_bytes, extension } })
+        })
+        .collect::<Result<Vec<_>, CodecError>>()?;
+
+    let data_inputs = (0..get_count(r)?)
+        .map(|_| Ok(MinimalInput { id: Digest(r.get_array()?) }))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+
+    let token_ids = (0..r.get_uint()?)
+        .map(|_| Ok(Digest(r.get_array()?)))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+
+    let outputs = (0..get_count(r)?)
+        .map(|_| ergo_box::read_candidate(r, Some(&token_ids)))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+
+    let mut tx = SignedTransaction { id: None, inputs, data_inputs, outputs };
+    tx.id = Some(transaction_id(&tx)?);
+    Ok(tx)
+}
+
+/// Output boxes of a transaction with their ids computed locally, so they can be spent before
+/// the transaction is confirmed or even seen by the node.
+pub fn output_boxes(tx: &SignedTransaction) -> Result<Vec<UTxO>, CodecError> {
+    let id = match &tx.id {
+        Some(id) => id.clone(),
+        None => transaction_id(tx)?,
+    };
+    into_boxes(&id, tx.outputs.clone())
+}
+
+/// Turns the candidates created by transaction `id` into boxes.
+pub(crate) fn into_boxes(
+    id: &HashDigest,
+    candidates: Vec<BoxCandidate>,
+) -> Result<Vec<UTxO>, CodecError> {
+    candidates
+        .into_iter()
+        .enumerate()
+        .map(|(index, candidate)| {
+            let index =
+                u16::try_from(index).map_err(|_| CodecError("too many transaction outputs"))?;
+            let mut utxo = UTxO {
+                id: Digest([0; 32]),
+                ergo_tree: candidate.ergo_tree,
+                creation_height: candidate.creation_height,
+                value: candidate.value,
+                tokens: candidate.tokens,
+                registers: candidate.registers,
+                index,
+                transaction_id: id.clone(),
+            };
+            utxo.id = ergo_box::compute_id(&utxo)?;
+            Ok(utxo)
+        })
+        .collect()
+}
+
+/// Token ids in order of first appearance among the outputs.
+pub fn distinct_token_ids(outputs: &[BoxCandidate]) -> Vec<HashDigest> {
+    distinct_ids(outputs.iter().flat_map(|o| &o.tokens))
+}
+
+fn distinct_ids<'a>(tokens: impl Iterator<Item = &'a Token>) -> Vec<HashDigest> {
+    let mut ids: Vec<HashDigest> = Vec::new();
+    for token in tokens {
+        if !ids.contains(&token.id) {
+            ids.push(token.id.clone());
+        }
+    }
+    ids
+}
+
+/// Inputs of a signed transaction, with context extension variables in id order.
+fn signed_inputs(
+    tx: &SignedTransaction,
+    without_proofs: bool,
+) -> Result<Vec<InputBytes<'_>>, CodecError> {
+    tx.inputs
+        .iter()
+        .map(|input| input_bytes(&input.box_id, &input.spending_proof, without_proofs))
+        .collect()
+}
+
+fn input_bytes<'a>(
+    box_id: &'a HashDigest,
+    proof: &'a SpendingProof,
+    without_proof: bool,
+) -> Result<InputBytes<'a>, CodecError> {
+    let mut extension = proof
+        .extension
+        .iter()
+        .map(|(id, value)| {
+            let id = id
+                .parse()
+                .map_err(|_| CodecError("invalid context extension variable id"))?;
+            Ok((id, &value.0[..]))
+        })
+        .collect::<Result<Vec<_>, CodecError>>()?;
+    extension.sort_unstable_by_key(|(id, _)| *id);
+
+    let proof_bytes = if without_proof { &[][..] } else { &proof.proof_bytes.0[..] };
+    Ok(InputBytes { box_id, proof: proof_bytes, extension })
+}
+
+fn outputs<'a, T>(outputs: &'a [T]) -> Vec<OutputBytes<'a>>
+where
+    &'a T: Into<OutputBytes<'a>>,
+{
+    outputs.iter().map(Into::into).collect()
+}
+
+fn write_transaction(
+    inputs: &[InputBytes],
+    data_inputs: &[MinimalInput],
+    outputs: &[OutputBytes],
+) -> Result<Vec<u8>, CodecError> {
+    let mut w = Writer::new();
+
+    put_count(&mut w, inputs.len())?;
+    for input in inputs {
+        w.put_bytes(&input.box_id.0);
+        put_count(&mut w, input.proof.len())?;
+        w.put_bytes(input.proof);
+
+        let count = u8::try_from(input.extension.len())
+            .map_err(|_| CodecError("too many context extension variables"))?;
+        w.put_u8(count);
+        for (id, value) in &input.extension {
+            w.put_u8(*id).put_bytes(value);
+        }
+    }
+
+    put_count(&mut w, data_inputs.len())?;
+    for input in data_inputs {
+        w.put_bytes(&input.id.0);
+    }
+
+    let token_ids = distinct_ids(outputs.iter().flat_map(|o| o.tokens));
+    w.put_uint(token_ids.len() as u32);
+    for id in &token_ids {
+        w.put_bytes(&id.0);
+    }
+
+    put_count(&mut w, outputs.len())?;
+    for output in outputs {
+        ergo_box::write_candidate(
+            &mut w,
+            output.value,
+            output.ergo_tree,
+            output.creation_height,
+            output.tokens,
+            output.registers,
+            Some(&token_ids),
+        )?;
+    }
+
+    Ok(w.into_bytes())
+}
+
+fn put_count(w: &mut Writer, count: usize) -> Result<(), CodecError> {
+    let count = u16::try_from(count).map_err(|_| CodecError("too many transaction items"))?;
+    w.put_uint(count as u32);
+    Ok(())
+}
+
+fn get_count(r: &mut Reader) -> Result<u16, CodecError> {
+    u16::try_from(r.get_uint()?).map_err(|_| CodecError("transaction item count exceeds u16"))
+}
@@ -0,0 +1,53 @@
+//! Checked arithmetic on nanoERG values and token amounts.
+//!
+//! Box values are `u64`, but their sums aren't: a whole mempool, or a malformed transaction,
+//! can exceed the range and `u64` addition silently wraps in release builds. Sums and
+//! differences are computed in 128 bits, which no realistic number of `u64` terms can overflow,
+//! and narrowed back to 64 bits with an explicit error.
+
+use crate::types::ergo::UTxO;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ValueError {
+    #[error("Value {0} exceeds the 64-bit range")]
+    Overflow(u128),
+
+    #[error("Value change {0} exceeds the signed 64-bit range")]
+    OutOfRange(i128),
+}
+
+/// Sum of `values`.
+pub fn total(values: impl IntoIterator<Item = u64>) -> u128 {
+    values.into_iter().map(u128::from).sum()
+}
+
+/// nanoERG held by `boxes`.
+pub fn total_value<'a>(boxes: impl IntoIterator<Item = &'a UTxO>) -> u128 {
+    total(boxes.into_iter().map(|utxo| utxo.value))
+}
+
+/// nanoERG moved from `inputs` to `outputs`, negative when the outputs hold less.
+pub fn value_delta<'a>(
+    inputs: impl IntoIterator<Item = &'a UTxO>,
+    outputs: impl IntoIterator<Item = &'a UTxO>,
+) -> i128 {
+    signed(total_value(outputs)) - signed(total_value(inputs))
+}
+
+/// Sum of `values`, failing if it doesn't fit a box value.
+pub fn checked_total(values: impl IntoIterator<Item = u64>) -> Result<u64, ValueError> {
+    to_u64(total(values))
+}
+
+pub fn to_u64(value: u128) -> Result<u64, ValueError> {
+    u64::try_from(value).map_err(|_| ValueError::Overflow(value))
+}
+
+pub fn to_i64(value: i128) -> Result<i64, ValueError> {
+    i64::try_from(value).map_err(|_| ValueError::OutOfRange(value))
+}
+
+/// Sums of `u64` stay far below `i128::MAX`, so the conversion saturates only in theory.
+fn signed(value: u128) -> i128 {
+    i128::try_from(value).unwrap_or(i128::MAX)
+}
@@ -0,0 +1,240 @@
+//! Test vectors of the address, ErgoTree and transaction codecs, as JSON shared with reference
+//! implementations such as sigma-rust and ergo-ts.
+//!
+//! A vector file lists inputs along with the outputs every implementation must agree on:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "addresses": [{ "address": "9f…", "network": 0, "kind": 1, "content": "02…", "ergoTree": "0008cd02…" }],
+//!   "trees": [{ "ergoTree": "0008cd02…", "templateHash": "…", "mainnetAddress": "9f…" }],
+//!   "transactions": [{ "id": "…", "bytes": "…", "transaction": { … }, "outputIds": ["…"] }]
+//! }
+//! ```
+//!
+//! `network` and `kind` are the address prefix bytes, e.g. `0x10` for testnet and `3` for P2S.
+
+use std::{collections::HashSet, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    chain::{ergo_tree, transaction},
+    codec::CodecError,
+    types::{HashDigest, HexBytes, ergo::SignedTransaction},
+};
+
+/// Version of the vector file format written by this crate.
+pub const VECTORS_VERSION: u32 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VectorError {
+    #[error("Failed to read test vectors: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid test vectors: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unsupported test vector version {0}")]
+    UnsupportedVersion(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressVector {
+    pub address: String,
+    pub network: u8,
+    pub kind: u8,
+    pub content: HexBytes,
+    /// `None` for P2SH addresses.
+    pub ergo_tree: Option<HexBytes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeVector {
+    pub ergo_tree: HexBytes,
+    pub template_hash: HashDigest,
+    pub mainnet_address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionVector {
+    pub id: HashDigest,
+    /// Serialized as on the P2P layer.
+    pub bytes: HexBytes,
+    pub transaction: SignedTransaction,
+    pub output_ids: Vec<HashDigest>,
+}
+
+/// A vector this crate's codecs disagree with, e.g. `transactions[2]: id mismatch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorMismatch {
+    pub vector: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestVectors {
+    pub version: u32,
+    #[serde(default)]
+    pub addresses: Vec<AddressVector>,
+    #[serde(default)]
+    pub trees: Vec<TreeVector>,
+    #[serde(default)]
+    pub transactions: Vec<TransactionVector>,
+}
+
+impl Default for TestVectors {
+    fn default() -> Self {
+        Self {
+            version: VECTORS_VERSION,
+            addresses: Vec::new(),
+            trees: Vec::new(),
+            transactions: Vec::new(),
+        }
+    }
+}
+
+impl TestVectors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, VectorError> {
+        let vectors: Self = serde_json::from_str(json)?;
+        if vectors.version != VECTORS_VERSION {
+            return Err(VectorError::UnsupportedVersion(vectors.version));
+        }
+        Ok(vectors)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, VectorError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Pretty-printed JSON, ending with a newline.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("vectors serialize to JSON");
+        json.push('\n');
+        json
+    }
+
+    /// Records the encoding of `address`.
+    pub fn add_address(&mut self, address: &ErgoAddress) {
+        self.addresses.push(AddressVector {
+            address: address.encode(),
+            network: address.network() as u8,
+            kind: address.kind() as u8,
+            content: HexBytes(address.content().to_vec()),
+            ergo_tree: address.ergo_tree(),
+        });
+    }
+
+    /// Records the template hash and mainnet address of `tree`.
+    pub fn add_tree(&mut self, tree: &[u8]) -> Result<(), CodecError> {
+        self.trees.push(TreeVector {
+            ergo_tree: HexBytes(tree.to_vec()),
+            template_hash: ergo_tree::template_hash(tree)?,
+            mainnet_address: ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, tree).encode(),
+        });
+        Ok(())
+    }
+
+    /// Records the serialization and ids of `tx`, along with the trees and addresses of its
+    /// outputs not recorded yet.
+    pub fn add_transaction(&mut self, tx: &SignedTransaction) -> Result<(), CodecError> {
+        let outputs = transaction::output_boxes(tx)?;
+        self.transactions.push(TransactionVector {
+            id: transaction::transaction_id(tx)?,
+            bytes: HexBytes(transaction::serialize(tx)?),
+            transaction: tx.clone(),
+            output_ids: outputs.iter().map(|o| o.id.clone()).collect(),
+        });
+
+        let mut known: HashSet<HexBytes> = self.trees.iter().map(|t| t.ergo_tree.clone()).collect();
+        for output in &tx.outputs {
+            if known.insert(output.ergo_tree.clone()) {
+                self.add_tree(&output.ergo_tree.0)?;
+                let address =
+                    ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &output.ergo_tree.0);
+                self.add_address(&address);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every vector against this crate's codecs.
+    pub fn verify(&self) -> Vec<VectorMismatch> {
+        let mut mismatches = Vec::new();
+        let mut check = |vector: String, failure: Option<String>| {
+            if let Some(reason) = failure {
+                mismatches.push(VectorMismatch { vector, reason });
+            }
+        };
+
+        for (i, v) in self.addresses.iter().enumerate() {
+            check(format!("addresses[{i}]"), verify_address(v).err());
+        }
+        for (i, v) in self.trees.iter().enumerate() {
+            check(format!("trees[{i}]"), verify_tree(v).err());
+        }
+        for (i, v) in self.transactions.iter().enumerate() {
+            check(format!("transactions[{i}]"), verify_transaction(v).err());
+        }
+        mismatches
+    }
+}
+
+fn ensure(ok: bool, reason: &str) -> Result<(), String> {
+    if ok { Ok(()) } else { Err(reason.to_string()) }
+}
+
+fn verify_address(v: &AddressVector) -> Result<(), String> {
+    let decoded: ErgoAddress = v.address.parse().map_err(|e| format!("decoding: {e}"))?;
+    ensure(decoded.network() as u8 == v.network, "network mismatch")?;
+    ensure(decoded.kind() as u8 == v.kind, "kind mismatch")?;
+    ensure(decoded.content() == &v.content.0[..], "content mismatch")?;
+    ensure(decoded.ergo_tree() == v.ergo_tree, "ErgoTree mismatch")?;
+
+    let network = match v.network {
+        0x00 => NetworkPrefix::Mainnet,
+        0x10 => NetworkPrefix::Testnet,
+        other => return Err(format!("unknown network {other:#04x}")),
+    };
+    let kind = match v.kind {
+        1 => AddressType::P2PK,
+        2 => AddressType::P2SH,
+        3 => AddressType::P2S,
+        other => return Err(format!("unknown kind {other}")),
+    };
+    let encoded = ErgoAddress::new(network, kind, v.content.0.clone())
+        .map_err(|e| format!("encoding: {e}"))?
+        .encode();
+    ensure(encoded == v.address, "encoding mismatch")
+}
+
+fn verify_tree(v: &TreeVector) -> Result<(), String> {
+    let hash = ergo_tree::template_hash(&v.ergo_tree.0).map_err(|e| format!("parsing: {e}"))?;
+    ensure(hash == v.template_hash, "template hash mismatch")?;
+    let address = ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &v.ergo_tree.0);
+    ensure(address.encode() == v.mainnet_address, "address mismatch")
+}
+
+fn verify_transaction(v: &TransactionVector) -> Result<(), String> {
+    let bytes = transaction::serialize(&v.transaction).map_err(|e| format!("serializing: {e}"))?;
+    ensure(bytes == v.bytes.0, "serialization mismatch")?;
+
+    let parsed = transaction::parse(&v.bytes.0).map_err(|e| format!("parsing: {e}"))?;
+    let reserialized = transaction::serialize(&parsed).map_err(|e| format!("serializing: {e}"))?;
+    ensure(reserialized == v.bytes.0, "parsed transaction serializes differently")?;
+
+    let id = transaction::transaction_id(&v.transaction).map_err(|e| format!("id: {e}"))?;
+    ensure(id == v.id, "id mismatch")?;
+    let outputs = transaction::output_boxes(&v.transaction).map_err(|e| format!("outputs: {e}"))?;
+    let output_ids: Vec<_> = outputs.into_iter().map(|o| o.id).collect();
+    ensure(output_ids == v.output_ids, "output id mismatch")
+}
@@ -0,0 +1,170 @@
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{Certificate, Identity, Proxy};
+
+#[cfg(feature = "unix-socket")]
+use super::UnixSocketTransport;
+use super::{
+    CacheConfig, DEFAULT_SLOW_REQUEST_THRESHOLD, IndexPolicy, NodeClient, NodeError,
+    ReqwestTransport, ResponseLimits, SchemaMode, SseTransport,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+#[cfg(feature = "unix-socket")]
+const UNIX_SCHEME: &str = "unix://";
+
+/// Builds a [`NodeClient`] together with its underlying HTTP client.
+///
+/// TLS and proxy settings are applied to the `reqwest` client at [`build`](Self::build) time,
+/// so callers don't need to construct it themselves. A `unix://` base URL (e.g.
+/// `unix:///run/ergo/node.sock`) selects the `UnixSocketTransport` instead (requires the
+/// `unix-socket` feature), in which case TLS,
+/// proxy and timeout settings don't apply.
+#[derive(Debug)]
+pub struct NodeClientBuilder {
+    base_url: String,
+    timeout: Duration,
+    schema_mode: SchemaMode,
+    root_certificates: Vec<Vec<u8>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    proxy: Option<String>,
+    cache: Option<CacheConfig>,
+    index_policy: IndexPolicy,
+    response_limits: ResponseLimits,
+    slow_request_threshold: Duration,
+    push_url: Option<String>,
+}
+
+impl NodeClientBuilder {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            schema_mode: SchemaMode::default(),
+            root_certificates: Vec::new(),
+            identity: None,
+            proxy: None,
+            cache: None,
+            index_policy: IndexPolicy::default(),
+            response_limits: ResponseLimits::default(),
+            slow_request_threshold: DEFAULT_SLOW_REQUEST_THRESHOLD,
+            push_url: None,
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn schema_mode(mut self, schema_mode: SchemaMode) -> Self {
+        self.schema_mode = schema_mode;
+        self
+    }
+
+    /// Trusts an additional PEM-encoded CA certificate, e.g. for a node behind a self-signed proxy.
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Authenticates with a PEM-encoded client certificate chain and PKCS#8 private key.
+    pub fn client_identity_pem(
+        mut self,
+        cert: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.identity = Some((cert.into(), key.into()));
+        self
+    }
+
+    /// Routes all requests through a proxy. Accepts `http://`, `https://`, `socks5://` and
+    /// `socks5h://` URLs; use `socks5h` to resolve hostnames through the proxy (e.g. Tor).
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Enables the response cache for immutable data, see [`NodeClient::with_cache`].
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    pub fn index_policy(mut self, index_policy: IndexPolicy) -> Self {
+        self.index_policy = index_policy;
+        self
+    }
+
+    pub fn response_limits(mut self, limits: ResponseLimits) -> Self {
+        self.response_limits = limits;
+        self
+    }
+
+    pub fn slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = threshold;
+        self
+    }
+
+    /// Subscribes to server-sent mempool and block events at `url`, see
+    /// [`NodeClient::with_push`]. The subscription uses the TLS and proxy settings, and the
+    /// timeout only for connecting.
+    pub fn push_url(mut self, url: &str) -> Self {
+        self.push_url = Some(url.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<NodeClient, NodeError> {
+        #[cfg(feature = "unix-socket")]
+        if let Some(socket_path) = self.base_url.strip_prefix(UNIX_SCHEME) {
+            let transport = UnixSocketTransport::new(socket_path);
+            return self.configure(NodeClient::with_transport(transport));
+        }
+
+        let http = self.http_client(reqwest::Client::builder().timeout(self.timeout))?;
+        let transport = ReqwestTransport::new(http, &self.base_url);
+        self.configure(NodeClient::with_transport(transport))
+    }
+
+    fn http_client(&self, mut http: reqwest::ClientBuilder) -> Result<reqwest::Client, NodeError> {
+        for pem in &self.root_certificates {
+            http = http.add_root_certificate(Certificate::from_pem(pem)?);
+        }
+
+        if let Some((cert, key)) = &self.identity {
+            http = http.identity(Identity::from_pkcs8_pem(cert, key)?);
+        }
+
+        if let Some(url) = &self.proxy {
+            http = http.proxy(Proxy::all(url)?);
+        }
+
+        Ok(http.build()?)
+    }
+
+    /// Builds the client and detects the node's capabilities, see
+    /// [`NodeClient::detect_capabilities`].
+    pub async fn connect(self) -> Result<NodeClient, NodeError> {
+        let client = self.build()?;
+        client.detect_capabilities().await?;
+        Ok(client)
+    }
+
+    fn configure(&self, client: NodeClient) -> Result<NodeClient, NodeError> {
+        let mut client = client
+            .with_schema_mode(self.schema_mode)
+            .with_index_policy(self.index_policy)
+            .with_response_limits(self.response_limits)
+            .with_slow_request_threshold(self.slow_request_threshold);
+        if let Some(config) = self.cache {
+            client = client.with_cache(config);
+        }
+        if let Some(url) = &self.push_url {
+            // Without an overall timeout, which would end the subscription.
+            let http =
+                self.http_client(reqwest::Client::builder().connect_timeout(self.timeout))?;
+            client = client.with_push(Arc::new(SseTransport::new(http, url)));
+        }
+        Ok(client)
+    }
+}
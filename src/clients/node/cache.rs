@@ -0,0 +1,73 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use bytes::Bytes;
+use tokio::time::Instant;
+
+const DEFAULT_TTL: Duration = Duration::from_secs(600);
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Bounds of the [`NodeClient`](super::NodeClient) response cache.
+///
+/// Entries expire after `ttl` even though the data is immutable, so blocks and transactions
+/// orphaned by a reorg are eventually refetched.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheConfig {
+    pub ttl: Duration,
+    /// Maximum number of cached responses.
+    pub capacity: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { ttl: DEFAULT_TTL, capacity: DEFAULT_CAPACITY }
+    }
+}
+
+/// Raw response bodies keyed by request path and query. Times are read from the client's
+/// [`Clock`](crate::clock::Clock).
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<String, (Instant, Bytes)>>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self { config, entries: Mutex::new(HashMap::new()) }
+    }
+
+    pub(crate) fn get(&self, key: &str, now: Instant) -> Option<Bytes> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((inserted, body)) if now.duration_since(*inserted) < self.config.ttl => {
+                Some(body.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, key: String, body: Bytes, now: Instant) {
+        if self.config.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.config.capacity && !entries.contains_key(&key) {
+            entries.retain(|_, (inserted, _)| now.duration_since(*inserted) < self.config.ttl);
+        }
+        if entries.len() >= self.config.capacity && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (inserted, _))| *inserted)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (now, body));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{fmt, str::FromStr};
+
+use serde::Deserialize;
+
+/// Optional node APIs that requests may depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// The blockchain indexer (`extraIndex`) behind the `blockchain/*` endpoints.
+    ExtraIndex,
+    /// The wallet behind the `wallet/*` endpoints.
+    Wallet,
+}
+
+impl Capability {
+    /// The capability serving requests to `path`, if any.
+    pub fn for_path(path: &str) -> Option<Self> {
+        if path.starts_with("blockchain/") {
+            Some(Capability::ExtraIndex)
+        } else if path.starts_with("wallet/") {
+            Some(Capability::Wallet)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::ExtraIndex => "the blockchain indexer (extraIndex)",
+            Capability::Wallet => "the wallet API",
+        })
+    }
+}
+
+/// Release of the node software, from the `appVersion` of `/info`. Pre-release suffixes such as
+/// `-RC1` are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl NodeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+}
+
+impl FromStr for NodeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let release = s
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default();
+        let mut parts = release.split('.').map(str::parse::<u32>);
+        match (parts.next(), parts.next(), parts.next().unwrap_or(Ok(0)), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Ok(patch), None) => Ok(Self { major, minor, patch }),
+            _ => Err(format!("Invalid node version `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Version and optional APIs of a node, as detected by
+/// [`NodeClient::detect_capabilities`](super::NodeClient::detect_capabilities).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeCapabilities {
+    /// `None` for nodes not reporting a parsable `appVersion`.
+    pub version: Option<NodeVersion>,
+    pub extra_index: bool,
+    pub wallet: bool,
+}
+
+impl NodeCapabilities {
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::ExtraIndex => self.extra_index,
+            Capability::Wallet => self.wallet,
+        }
+    }
+}
+
+/// The fields of `/info` describing the node software.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct InfoCapabilities {
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// Not reported by older nodes, in which case the indexer is probed.
+    #[serde(default)]
+    pub is_explorer: Option<bool>,
+}
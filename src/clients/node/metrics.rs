@@ -0,0 +1,93 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+pub const LATENCY_BUCKETS: [f64; 11] =
+    [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Latencies of the requests to one endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// Requests at or below each of [`LATENCY_BUCKETS`], not cumulative.
+    pub buckets: [u64; LATENCY_BUCKETS.len()],
+    pub count: u64,
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[i] += 1;
+        }
+        self.count += 1;
+        self.sum += elapsed;
+    }
+}
+
+/// Request latencies of a [`NodeClient`](super::NodeClient) by endpoint, shared between its
+/// clones. Endpoints are request paths with ids and heights replaced by `{id}`, so that e.g.
+/// every box lookup shares a histogram.
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    histograms: Mutex<BTreeMap<String, LatencyHistogram>>,
+}
+
+impl RequestMetrics {
+    pub(crate) fn observe(&self, endpoint: &str, elapsed: Duration) {
+        let mut histograms = self.histograms.lock().unwrap();
+        match histograms.get_mut(endpoint) {
+            Some(histogram) => histogram.observe(elapsed),
+            None => histograms
+                .entry(endpoint.to_string())
+                .or_default()
+                .observe(elapsed),
+        }
+    }
+
+    pub fn histogram(&self, endpoint: &str) -> Option<LatencyHistogram> {
+        self.histograms.lock().unwrap().get(endpoint).cloned()
+    }
+
+    /// Renders the histograms in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        const NAME: &str = "hergmes_node_request_duration_seconds";
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP {NAME} Latency of node API requests by endpoint.");
+        let _ = writeln!(out, "# TYPE {NAME} histogram");
+        for (endpoint, histogram) in self.histograms.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{NAME}_bucket{{endpoint=\"{endpoint}\",le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{NAME}_bucket{{endpoint=\"{endpoint}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "{NAME}_sum{{endpoint=\"{endpoint}\"}} {}",
+                histogram.sum.as_secs_f64()
+            );
+            let _ = writeln!(out, "{NAME}_count{{endpoint=\"{endpoint}\"}} {}", histogram.count);
+        }
+        out
+    }
+}
+
+/// Endpoint of a request path: segments that are hex ids or numbers become `{id}`.
+pub(crate) fn endpoint(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            let is_id = segment.len() == 64 && segment.bytes().all(|b| b.is_ascii_hexdigit());
+            let is_number = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+            if is_id || is_number { "{id}" } else { segment }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,1183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+#[cfg(feature = "reqwest")]
+pub use builder::NodeClientBuilder;
+use bytes::Bytes;
+pub use cache::CacheConfig;
+use cache::ResponseCache;
+use capabilities::InfoCapabilities;
+pub use capabilities::{Capability, NodeCapabilities, NodeVersion};
+use futures_util::{Stream, stream};
+pub use metrics::{LATENCY_BUCKETS, LatencyHistogram, RequestMetrics};
+#[cfg(feature = "reqwest")]
+pub use push::SseTransport;
+pub use push::{
+    PUSH_FALLBACK_INTERVAL, PUSH_RECONNECT_DELAY, PushEvent, PushStream, PushTopic, PushTransport,
+    SseDecoder, SseMessage, Wakeup,
+};
+pub use query::{BoxQuery, DEFAULT_BOX_QUERY_LIMIT, SortOrder};
+use serde::{
+    self, Deserialize, Serialize,
+    de::{DeserializeOwned, IgnoredAny},
+};
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{Instant, sleep},
+};
+use tracing::{debug, info, warn};
+pub use transport::*;
+
+use crate::{
+    address::ErgoAddress,
+    chain::{
+        extension::{self, Extension, Parameters},
+        nipopow::NipopowProof,
+    },
+    clock::{self, Clock},
+    codec::CodecError,
+    hash::blake2b256,
+    types::{
+        Digest, HashDigest, Height, HexBytes, TimestampMillis,
+        ergo::{
+            Block, BlockHeader, IndexedBox, IndexedTransaction, MinimalInput, SignedTransaction,
+            SpendingProof, TokenInfo, TransactionInput, UTxO, UnconfirmedTransaction,
+        },
+    },
+};
+
+#[cfg(feature = "reqwest")]
+mod builder;
+mod cache;
+mod capabilities;
+mod metrics;
+mod push;
+mod query;
+mod transport;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NodeError {
+    #[cfg(feature = "reqwest")]
+    #[error(transparent)]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("The node is not fully indexed.")]
+    NotIndexed(IndexedHeightResponse),
+
+    #[error("The node doesn't run the blockchain indexer.")]
+    IndexUnavailable,
+
+    #[error("The node doesn't provide {capability}.")]
+    Unsupported { capability: Capability, version: Option<NodeVersion> },
+
+    #[error("Failed to decode node response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("Node response contains unknown fields: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid node data: {0}")]
+    InvalidData(#[from] CodecError),
+
+    #[error("Node response exceeds {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+
+    #[error("Transport error: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl NodeError {
+    pub fn transport(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        NodeError::Transport(Box::new(err))
+    }
+
+    /// Whether the request failed before reaching a response, so retrying it may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            #[cfg(feature = "reqwest")]
+            NodeError::HttpError(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            NodeError::Transport(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Requests in flight at once in [`NodeClient::get_boxes_by_ids`].
+pub const BULK_FETCH_CONCURRENCY: usize = 8;
+/// Attempts per box in [`NodeClient::get_boxes_by_ids`], including the first one.
+pub const BULK_FETCH_ATTEMPTS: u32 = 3;
+const BULK_FETCH_BACKOFF: Duration = Duration::from_millis(200);
+/// Requests slower than this are logged by default, see
+/// [`NodeClient::with_slow_request_threshold`].
+pub const DEFAULT_SLOW_REQUEST_THRESHOLD: Duration = Duration::from_secs(2);
+/// Time given by default to requests sent to the old node to complete in
+/// [`NodeClient::swap_node`].
+pub const DEFAULT_SWAP_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+const SWAP_DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Transactions requested per page of the mempool.
+pub const MEMPOOL_PAGE_SIZE: u32 = 1000;
+/// Passes over a mempool of several pages. The node pages by offset, so a transaction leaving
+/// the mempool moves later ones to pages already fetched; passes are repeated until two count
+/// as many transactions, and merged. A transaction can still be missed if the mempool changes
+/// on every pass, and one that left during the passes is kept until the next poll.
+pub const MAX_MEMPOOL_PASSES: usize = 3;
+
+/// Largest accepted response bodies, protecting memory against a misconfigured or malicious
+/// endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseLimits {
+    /// Applies to every request but the mempool fetch.
+    pub default: usize,
+    /// Applies to the full mempool fetch, whose size grows with the mempool.
+    pub mempool: usize,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self { default: 32 * 1024 * 1024, mempool: 256 * 1024 * 1024 }
+    }
+}
+
+/// Controls how strictly node responses are matched against the expected schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaMode {
+    /// Unknown fields are ignored (and logged) and optional fields fall back to defaults.
+    #[default]
+    Lenient,
+    /// Any unknown field fails deserialization, surfacing node schema drift early.
+    Strict,
+}
+
+impl SchemaMode {
+    /// Deserializes a JSON payload according to this mode.
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, NodeError> {
+        let mut unknown = Vec::new();
+        let de = &mut serde_json::Deserializer::from_slice(bytes);
+        let value = serde_ignored::deserialize(de, |path| unknown.push(path.to_string()))?;
+
+        if !unknown.is_empty() {
+            match self {
+                SchemaMode::Strict => return Err(NodeError::UnknownFields(unknown)),
+                SchemaMode::Lenient => {
+                    debug!(?unknown, "Ignoring unknown fields in node response.")
+                }
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// What to do when the node's blockchain indexer (`extraIndex`) is missing or lagging, e.g.
+/// on pruned nodes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IndexPolicy {
+    /// [`NodeClient::check_node_index_status`] fails unless the node is fully indexed.
+    #[default]
+    Require,
+    /// The check only logs a warning. Once the indexer is known to be missing, `blockchain/*`
+    /// calls fail with [`NodeError::IndexUnavailable`] without reaching the node, and
+    /// [`NodeClient::get_box`] looks up unspent boxes in the UTXO set instead.
+    Degrade,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedHeightResponse {
+    pub indexed_height: Height,
+    pub full_height: Height,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InfoResponse {
+    #[serde(rename = "lastMemPoolUpdateTime", default)]
+    pub last_mempool_update: TimestampMillis,
+    #[serde(rename = "fullHeight", default)]
+    pub full_height: Option<Height>,
+    #[serde(default)]
+    pub parameters: Option<InfoParameters>,
+}
+
+/// Consensus parameters of the current voting epoch, as reported by `/info`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoParameters {
+    /// Height of the epoch-start block that declared these parameters.
+    pub height: Height,
+    pub storage_fee_factor: i32,
+    pub min_value_per_byte: i32,
+    pub max_block_size: i32,
+    pub max_block_cost: i32,
+    pub token_access_cost: i32,
+    pub input_cost: i32,
+    pub data_input_cost: i32,
+    pub output_cost: i32,
+    pub block_version: i32,
+}
+
+/// A page of indexer results.
+#[derive(Debug, Deserialize)]
+pub struct ItemsResponse<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+}
+
+/// Confirmed and unconfirmed balances of an address, from `/blockchain/balance`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BalanceResponse {
+    pub confirmed: Balance,
+    /// Balance change of mempool transactions.
+    #[serde(default)]
+    pub unconfirmed: Balance,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balance {
+    pub nano_ergs: u64,
+    #[serde(default)]
+    pub tokens: Vec<TokenBalance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalance {
+    pub token_id: HashDigest,
+    pub amount: u64,
+    #[serde(default)]
+    pub decimals: Option<u32>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Error body returned by the node API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    pub error: u16,
+    pub reason: String,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// Outcome of validating a transaction with `/transactions/check`.
+#[derive(Debug, Clone)]
+pub enum TransactionCheck {
+    Valid {
+        id: HashDigest,
+        /// Execution cost, when the node reports it.
+        cost: Option<u64>,
+    },
+    Invalid(ApiError),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CheckResponse {
+    Id(HashDigest),
+    WithCost { id: HashDigest, cost: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeClient {
+    /// Swapped by [`swap_node`](Self::swap_node); shared between clones.
+    transport: Arc<ArcSwap<Arc<dyn HttpTransport>>>,
+    schema_mode: SchemaMode,
+    cache: Option<Arc<ResponseCache>>,
+    /// Expires cached responses.
+    clock: Arc<dyn Clock>,
+    /// Wakes the watchers up on pushed events, see [`wakeup`](Self::wakeup).
+    push: Option<Arc<dyn PushTransport>>,
+    index_policy: IndexPolicy,
+    limits: ResponseLimits,
+    metrics: Arc<RequestMetrics>,
+    slow_request_threshold: Duration,
+    /// Set once `blockchain/indexedHeight` returned 404; shared between clones.
+    index_unavailable: Arc<AtomicBool>,
+    /// Set by [`detect_capabilities`](Self::detect_capabilities); shared between clones.
+    capabilities: Arc<ArcSwapOption<NodeCapabilities>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct MempoolTransactionResponse {
+    pub id: HashDigest,
+    pub inputs: Vec<MempoolTransactionInput>,
+    #[serde(rename = "dataInputs", default)]
+    pub data_inputs: Vec<MinimalInput>,
+    pub outputs: Vec<UTxO>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MempoolTransactionInput {
+    #[serde(flatten)]
+    pub utxo: Option<UTxO>,
+    #[serde(rename = "spendingProof")]
+    pub spending_proof: SpendingProof,
+}
+
+impl From<MempoolTransactionResponse> for UnconfirmedTransaction {
+    fn from(mempool_input: MempoolTransactionResponse) -> Self {
+        UnconfirmedTransaction {
+            id: mempool_input.id,
+            data_inputs: mempool_input.data_inputs,
+            outputs: mempool_input.outputs,
+            inputs: mempool_input
+                .inputs
+                .into_iter()
+                .map(|input| TransactionInput {
+                    utxo: input.utxo.expect("UTxO should be present"),
+                    spending_proof: input.spending_proof,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Outcome of [`NodeClient::get_mempool_snapshot_if_changed`].
+#[derive(Debug)]
+pub enum MempoolPoll {
+    /// The response body is byte-identical to the previous poll.
+    Unchanged,
+    Changed {
+        /// blake2b256 of the response body, to pass to the next poll.
+        body_hash: HashDigest,
+        transactions: Vec<UnconfirmedTransaction>,
+    },
+}
+
+impl NodeClient {
+    #[cfg(feature = "reqwest")]
+    pub fn new(http_client: reqwest::Client, base_url: &str) -> Self {
+        Self::with_transport(ReqwestTransport::new(http_client, base_url))
+    }
+
+    pub fn with_transport(transport: impl HttpTransport + 'static) -> Self {
+        Self {
+            transport: Arc::new(ArcSwap::from_pointee(Arc::new(transport))),
+            schema_mode: SchemaMode::default(),
+            cache: None,
+            clock: clock::system(),
+            push: None,
+            index_policy: IndexPolicy::default(),
+            limits: ResponseLimits::default(),
+            metrics: Arc::new(RequestMetrics::default()),
+            slow_request_threshold: DEFAULT_SLOW_REQUEST_THRESHOLD,
+            index_unavailable: Arc::new(AtomicBool::new(false)),
+            capabilities: Arc::new(ArcSwapOption::empty()),
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub fn builder(base_url: &str) -> NodeClientBuilder {
+        NodeClientBuilder::new(base_url)
+    }
+
+    pub fn with_schema_mode(mut self, schema_mode: SchemaMode) -> Self {
+        self.schema_mode = schema_mode;
+        self
+    }
+
+    pub fn schema_mode(&self) -> SchemaMode {
+        self.schema_mode
+    }
+
+    pub fn with_index_policy(mut self, index_policy: IndexPolicy) -> Self {
+        self.index_policy = index_policy;
+        self
+    }
+
+    pub fn index_policy(&self) -> IndexPolicy {
+        self.index_policy
+    }
+
+    pub fn with_response_limits(mut self, limits: ResponseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn response_limits(&self) -> ResponseLimits {
+        self.limits
+    }
+
+    /// Logs a warning for requests taking longer than `threshold`.
+    pub fn with_slow_request_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_request_threshold = threshold;
+        self
+    }
+
+    /// Request latencies by endpoint, shared between clones of the client.
+    pub fn metrics(&self) -> &RequestMetrics {
+        &self.metrics
+    }
+
+    /// Whether the node is known to run without the blockchain indexer, as detected by
+    /// [`get_indexed_height`](Self::get_indexed_height).
+    pub fn index_unavailable(&self) -> bool {
+        self.index_unavailable.load(Ordering::Relaxed)
+    }
+
+    /// Capabilities of the node, once [detected](Self::detect_capabilities). Until then, no
+    /// request is refused upfront.
+    pub fn capabilities(&self) -> Option<Arc<NodeCapabilities>> {
+        self.capabilities.load_full()
+    }
+
+    /// Caches responses for immutable data: blocks, indexed transactions and token info.
+    /// Clones of the client share the cache.
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(Arc::new(ResponseCache::new(config)));
+        self
+    }
+
+    /// Reads the time cached responses expire at, and paces [`wakeup`](Self::wakeup)s, from
+    /// `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The clock set with [`with_clock`](Self::with_clock).
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Subscribes the watchers to mempool and block events pushed by `push`, polling on
+    /// them instead of on a timer. See [`wakeup`](Self::wakeup).
+    pub fn with_push(mut self, push: Arc<dyn PushTransport>) -> Self {
+        self.push = Some(push);
+        self
+    }
+
+    /// Paces the polls of a watcher of `topic`: every `poll_interval` without a push
+    /// transport, or while its subscription is down; on pushed events otherwise, at least
+    /// every [`PUSH_FALLBACK_INTERVAL`].
+    pub fn wakeup(&self, topic: PushTopic, poll_interval: Duration) -> Wakeup {
+        Wakeup::new(self.push.clone(), self.clock.clone(), topic, poll_interval)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_indexed_height(&self) -> Result<IndexedHeightResponse, NodeError> {
+        let resp = self
+            .send(HttpRequest::get("blockchain/indexedHeight"))
+            .await?;
+        if resp.status == 404 {
+            self.index_unavailable.store(true, Ordering::Relaxed);
+            return Err(NodeError::IndexUnavailable);
+        }
+
+        self.index_unavailable.store(false, Ordering::Relaxed);
+        self.schema_mode
+            .decode(&resp.body)
+            .inspect_err(|e| warn!(%e, "Failed to decode node response."))
+    }
+
+    /// Fetches the whole mempool, page by page. Transactions moving between pages while they
+    /// are fetched are only reported once, and may be reported after leaving the mempool; see
+    /// [`MAX_MEMPOOL_PASSES`].
+    #[tracing::instrument(skip(self))]
+    pub async fn get_mempool_snapshot(&self) -> Result<Vec<UnconfirmedTransaction>, NodeError> {
+        let pages = self.fetch_mempool_pages().await?;
+        self.decode_mempool(&pages)
+    }
+
+    /// Fetches the mempool, skipping deserialization when the response pages hash to
+    /// `previous`, the `body_hash` of an earlier poll.
+    ///
+    /// The node sends no `ETag` or `Last-Modified` for this endpoint, and its
+    /// `lastMemPoolUpdateTime` also moves when transactions are only revalidated, so the body
+    /// itself is compared.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_mempool_snapshot_if_changed(
+        &self,
+        previous: Option<&HashDigest>,
+    ) -> Result<MempoolPoll, NodeError> {
+        let pages = self.fetch_mempool_pages().await?;
+        let body_hash = Digest(blake2b256(&pages.concat()));
+        if previous == Some(&body_hash) {
+            debug!(%body_hash, "Mempool response unchanged, skipping decode.");
+            return Ok(MempoolPoll::Unchanged);
+        }
+
+        let transactions = self.decode_mempool(&pages)?;
+        Ok(MempoolPoll::Changed { body_hash, transactions })
+    }
+
+    /// Streams the mempool one page of [`MEMPOOL_PAGE_SIZE`] transactions at a time, ending
+    /// after the first short page or error. This is a single pass, which skips a transaction
+    /// whenever one on an earlier page leaves the mempool meanwhile.
+    pub fn mempool_pages(
+        &self,
+    ) -> impl Stream<Item = Result<Vec<UnconfirmedTransaction>, NodeError>> + '_ {
+        stream::unfold(Some(0), move |offset| async move {
+            let offset = offset?;
+            let page = match self.fetch_mempool_page(offset).await {
+                Ok(body) => self.decode_mempool_page(&body),
+                Err(e) => return Some((Err(e), None)),
+            };
+            match page {
+                Ok((count, transactions)) => {
+                    let next = (count == MEMPOOL_PAGE_SIZE as usize)
+                        .then_some(offset + MEMPOOL_PAGE_SIZE as u64);
+                    Some((Ok(transactions), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Raw mempool pages of one or more passes, see [`MAX_MEMPOOL_PASSES`].
+    async fn fetch_mempool_pages(&self) -> Result<Vec<Bytes>, NodeError> {
+        let (mut pages, mut count) = self.fetch_mempool_pass().await?;
+        if pages.len() > 1 {
+            for _ in 1..MAX_MEMPOOL_PASSES {
+                let (pass, pass_count) = self.fetch_mempool_pass().await?;
+                pages.extend(pass);
+                if pass_count == count {
+                    break;
+                }
+                count = pass_count;
+            }
+        }
+        Ok(pages)
+    }
+
+    /// Raw mempool pages until a short one, together bounded by [`ResponseLimits::mempool`],
+    /// and the number of transactions they hold.
+    async fn fetch_mempool_pass(&self) -> Result<(Vec<Bytes>, usize), NodeError> {
+        let limit = self.limits.mempool;
+        let (mut pages, mut total, mut transactions) = (Vec::new(), 0, 0);
+        loop {
+            let offset = pages.len() as u64 * MEMPOOL_PAGE_SIZE as u64;
+            let body = self.fetch_mempool_page(offset).await?;
+            total += body.len();
+            if total > limit {
+                return Err(NodeError::ResponseTooLarge { limit });
+            }
+
+            let count = serde_json::from_slice::<Vec<IgnoredAny>>(&body)?.len();
+            transactions += count;
+            pages.push(body);
+            if count < MEMPOOL_PAGE_SIZE as usize {
+                return Ok((pages, transactions));
+            }
+        }
+    }
+
+    async fn fetch_mempool_page(&self, offset: u64) -> Result<Bytes, NodeError> {
+        let request = HttpRequest::get("transactions/unconfirmed")
+            .query("offset", offset)
+            .query("limit", MEMPOOL_PAGE_SIZE)
+            .max_body_size(self.limits.mempool);
+        Ok(self.send(request).await?.body)
+    }
+
+    fn decode_mempool(&self, pages: &[Bytes]) -> Result<Vec<UnconfirmedTransaction>, NodeError> {
+        let mut seen = HashSet::new();
+        let mut transactions = Vec::new();
+        for page in pages {
+            let (_, valid) = self.decode_mempool_page(page)?;
+            transactions.extend(valid.into_iter().filter(|tx| seen.insert(tx.id.clone())));
+        }
+        Ok(transactions)
+    }
+
+    /// Decodes a page of the mempool, returning its size before filtering and its valid
+    /// transactions.
+    fn decode_mempool_page(
+        &self,
+        body: &[u8],
+    ) -> Result<(usize, Vec<UnconfirmedTransaction>), NodeError> {
+        let resp: Vec<MempoolTransactionResponse> = self
+            .schema_mode
+            .decode(body)
+            .inspect_err(|e| warn!(%e, "Failed to decode node response."))?;
+        let count = resp.len();
+
+        // Filter out invalid transactions (those with missing UTxOs in inputs)
+        // https://github.com/ergoplatform/ergo/issues/2248#issuecomment-3463844934
+        let valid = resp
+            .into_iter()
+            .filter(|utx| utx.inputs.iter().all(|i| i.utxo.is_some()))
+            .map(|utx| utx.into())
+            .collect::<Vec<UnconfirmedTransaction>>();
+
+        Ok((count, valid))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_info(&self) -> Result<InfoResponse, NodeError> {
+        let response: InfoResponse = self.request(HttpRequest::get("info")).await?;
+        debug!(?response, "Node info fetched.");
+
+        Ok(response)
+    }
+
+    /// Detects the node version and its optional APIs from `/info`, probing the endpoints
+    /// `/info` doesn't report on. Requests needing a missing capability then fail upfront with
+    /// [`NodeError::Unsupported`], or [`NodeError::IndexUnavailable`] for the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn detect_capabilities(&self) -> Result<Arc<NodeCapabilities>, NodeError> {
+        let resp = self.send(HttpRequest::get("info")).await?;
+        // Only a few fields are read, so `/info` is not matched against the schema mode.
+        let info: InfoCapabilities = serde_json::from_slice(&resp.body)?;
+        let extra_index = match info.is_explorer {
+            Some(enabled) => enabled,
+            None => self.probe("blockchain/indexedHeight").await?,
+        };
+        let capabilities = Arc::new(NodeCapabilities {
+            version: info.app_version.and_then(|v| v.parse().ok()),
+            extra_index,
+            wallet: self.probe("wallet/status").await?,
+        });
+
+        self.index_unavailable
+            .store(!extra_index, Ordering::Relaxed);
+        self.capabilities.store(Some(capabilities.clone()));
+        info!(?capabilities, "Node capabilities detected.");
+        Ok(capabilities)
+    }
+
+    /// Points this client and all its clones at the node of `other`, e.g. one built with
+    /// [`NodeClient::builder`] for a new URL, without restarting the tasks using them.
+    ///
+    /// The capabilities of the new node are detected first, and the current node is kept if
+    /// that fails. Requests already sent to the old node are then given up to `drain_timeout`
+    /// to complete. Settings, metrics and the response cache of this client are kept, so the
+    /// new node must be on the same network.
+    #[tracing::instrument(skip_all)]
+    pub async fn swap_node(
+        &self,
+        other: &NodeClient,
+        drain_timeout: Duration,
+    ) -> Result<Arc<NodeCapabilities>, NodeError> {
+        let mut candidate = self.clone();
+        candidate.transport = Arc::new(ArcSwap::new(other.transport.load_full()));
+        candidate.capabilities = Arc::new(ArcSwapOption::empty());
+        candidate.index_unavailable = Arc::new(AtomicBool::new(false));
+        let capabilities = candidate.detect_capabilities().await?;
+
+        let old = self.transport.swap(candidate.transport.load_full());
+        self.index_unavailable
+            .store(!capabilities.extra_index, Ordering::Relaxed);
+        self.capabilities.store(Some(capabilities.clone()));
+        info!("Switched to the new node, draining requests to the old one...");
+
+        let started = Instant::now();
+        while Arc::strong_count(&old) > 1 {
+            if started.elapsed() >= drain_timeout {
+                warn!(in_flight = Arc::strong_count(&old) - 1, "Old node not drained in time.");
+                break;
+            }
+            sleep(SWAP_DRAIN_POLL_INTERVAL).await;
+        }
+        Ok(capabilities)
+    }
+
+    /// Whether the node serves `path`, i.e. doesn't answer 404.
+    async fn probe(&self, path: &str) -> Result<bool, NodeError> {
+        Ok(self.send(HttpRequest::get(path)).await?.status != 404)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_last_mempool_update_timestamp(&self) -> Result<TimestampMillis, NodeError> {
+        let info = self.get_info().await?;
+        Ok(info.last_mempool_update)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn check_node_index_status(&self) -> Result<(), NodeError> {
+        info!("Checking node index status...");
+        let degrade = self.index_policy == IndexPolicy::Degrade;
+        let index_status = match self.get_indexed_height().await {
+            Err(NodeError::IndexUnavailable) if degrade => {
+                warn!("Node runs without the blockchain indexer, indexed queries are disabled.");
+                return Ok(());
+            }
+            result => result?,
+        };
+
+        if index_status.indexed_height != index_status.full_height {
+            if degrade {
+                warn!(?index_status, "Node is not fully indexed, indexed queries may be stale.");
+                return Ok(());
+            }
+            return Err(NodeError::NotIndexed(index_status));
+        }
+
+        debug!(?index_status, "Node is fully indexed.");
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unconfirmed_transaction_ids(&self) -> Result<Vec<HashDigest>, NodeError> {
+        let resp = self
+            .request(HttpRequest::get("transactions/unconfirmed/transactionIds"))
+            .await?;
+        Ok(resp)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unconfirmed_transactions_by_ids(
+        &self,
+        tx_ids: &[HashDigest],
+    ) -> Result<Vec<UnconfirmedTransaction>, NodeError> {
+        let body = serde_json::to_vec(tx_ids)?;
+        let request = HttpRequest::post("transactions/unconfirmed/byTransactionIds", body);
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_last_n_headers(&self, n: u32) -> Result<Vec<BlockHeader>, NodeError> {
+        let resp = self
+            .request(HttpRequest::get(&format!("blocks/lastHeaders/{n}")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches the headers of the best chain from `from_height` to `to_height`, lowest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_chain_slice(
+        &self,
+        from_height: Height,
+        to_height: Height,
+    ) -> Result<Vec<BlockHeader>, NodeError> {
+        let request = HttpRequest::get("blocks/chainSlice")
+            .query("fromHeight", from_height)
+            .query("toHeight", to_height);
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_block(&self, header_id: &str) -> Result<Block, NodeError> {
+        let resp = self
+            .request_immutable(HttpRequest::get(&format!("blocks/{header_id}")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Transactions of a block with their proofs, which [`get_block`](Self::get_block) drops.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_block_signed_transactions(
+        &self,
+        header_id: &HashDigest,
+    ) -> Result<Vec<SignedTransaction>, NodeError> {
+        #[derive(Deserialize)]
+        struct SignedBlockTransactions {
+            transactions: Vec<SignedTransaction>,
+        }
+
+        let resp: SignedBlockTransactions = self
+            .request_immutable(HttpRequest::get(&format!("blocks/{header_id}/transactions")))
+            .await?;
+        Ok(resp.transactions)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_block_extension(
+        &self,
+        header_id: &HashDigest,
+    ) -> Result<Extension, NodeError> {
+        let resp = self
+            .request(HttpRequest::get(&format!("blocks/{header_id}/extension")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Header ids at `height`, main chain first.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_header_ids_at_height(
+        &self,
+        height: Height,
+    ) -> Result<Vec<HashDigest>, NodeError> {
+        let resp = self
+            .request(HttpRequest::get(&format!("blocks/at/{height}")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches the system parameters in force at `height` from its voting epoch's first block.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_epoch_parameters(&self, height: Height) -> Result<Parameters, NodeError> {
+        let epoch_start = extension::epoch_start(height);
+        let ids = self.get_header_ids_at_height(epoch_start).await?;
+        let id = ids
+            .first()
+            .ok_or(NodeError::NotFound(format!("block at height {epoch_start}")))?;
+        let parameters = self.get_block_extension(id).await?.parameters()?;
+        Ok(parameters)
+    }
+
+    /// Fetches a NiPoPoW proof for the current tip, or for `header_id` if given.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_nipopow_proof(
+        &self,
+        m: u32,
+        k: u32,
+        header_id: Option<&HashDigest>,
+    ) -> Result<NipopowProof, NodeError> {
+        let path = match header_id {
+            Some(id) => format!("nipopow/proof/{m}/{k}/{id}"),
+            None => format!("nipopow/proof/{m}/{k}"),
+        };
+        let resp = self.request(HttpRequest::get(&path)).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a batch AVL+ proof of the given boxes against the node's current UTxO state.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_boxes_binary_proof(
+        &self,
+        box_ids: &[HashDigest],
+    ) -> Result<HexBytes, NodeError> {
+        let body = serde_json::to_vec(box_ids)?;
+        let resp = self
+            .request(HttpRequest::post("utxo/getBoxesBinaryProof", body))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of confirmed transactions involving `address` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_transactions_by_address(
+        &self,
+        address: &ErgoAddress,
+        offset: u64,
+        limit: u32,
+    ) -> Result<ItemsResponse<IndexedTransaction>, NodeError> {
+        let body = address.to_string().into_bytes();
+        let request = HttpRequest::post("blockchain/transaction/byAddress", body)
+            .query("offset", offset)
+            .query("limit", limit);
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// Fetches the balance of `address` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_balance(&self, address: &ErgoAddress) -> Result<BalanceResponse, NodeError> {
+        let body = address.to_string().into_bytes();
+        let resp = self
+            .request(HttpRequest::post("blockchain/balance", body))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of boxes ever owned by `address` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_boxes_by_address(
+        &self,
+        address: &ErgoAddress,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let body = address.to_string().into_bytes();
+        let request = query.page(HttpRequest::post("blockchain/box/byAddress", body));
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of unspent boxes owned by `address` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unspent_boxes_by_address(
+        &self,
+        address: &ErgoAddress,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let body = address.to_string().into_bytes();
+        let request = query.apply(HttpRequest::post("blockchain/box/unspent/byAddress", body));
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of unspent boxes guarded by `ergo_tree` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unspent_boxes_by_ergo_tree(
+        &self,
+        ergo_tree: &HexBytes,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let body = hex::encode(&ergo_tree.0).into_bytes();
+        let request = query.apply(HttpRequest::post("blockchain/box/unspent/byErgoTree", body));
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of unspent boxes guarded by any instance of the contract of
+    /// `template_hash` from the indexer, see [`ergo_tree::template_hash`](crate::chain::ergo_tree::template_hash).
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unspent_boxes_by_template_hash(
+        &self,
+        template_hash: &HashDigest,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let path = format!("blockchain/box/unspent/byTemplateHash/{template_hash}");
+        let resp = self.request(query.apply(HttpRequest::get(&path))).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of boxes ever holding `token_id` from the indexer, oldest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_boxes_by_token_id(
+        &self,
+        token_id: &HashDigest,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let request = query.page(HttpRequest::get(&format!("blockchain/box/byTokenId/{token_id}")));
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// Fetches a page of unspent boxes holding `token_id` from the indexer.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_unspent_boxes_by_token_id(
+        &self,
+        token_id: &HashDigest,
+        query: &BoxQuery,
+    ) -> Result<ItemsResponse<IndexedBox>, NodeError> {
+        let request =
+            query.apply(HttpRequest::get(&format!("blockchain/box/unspent/byTokenId/{token_id}")));
+        let resp = self.request(request).await?;
+        Ok(resp)
+    }
+
+    /// The node's encoding of the address of `tree`, in the node's network.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_address_of_ergo_tree(&self, tree: &HexBytes) -> Result<String, NodeError> {
+        #[derive(Deserialize)]
+        struct AddressResponse {
+            address: String,
+        }
+
+        let resp: AddressResponse = self
+            .request_immutable(HttpRequest::get(&format!("utils/ergoTreeToAddress/{tree}")))
+            .await?;
+        Ok(resp.address)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_token(&self, token_id: &HashDigest) -> Result<TokenInfo, NodeError> {
+        let resp = self
+            .request_immutable(HttpRequest::get(&format!("blockchain/token/byId/{token_id}")))
+            .await?;
+        Ok(resp)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_indexed_box(&self, box_id: &HashDigest) -> Result<IndexedBox, NodeError> {
+        let resp = self
+            .request(HttpRequest::get(&format!("blockchain/box/byId/{box_id}")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Fetches a box from the indexer or, under [`IndexPolicy::Degrade`] without an indexer,
+    /// from the node's UTXO set, in which case only unspent boxes are found.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_box(&self, box_id: &HashDigest) -> Result<UTxO, NodeError> {
+        if self.index_policy == IndexPolicy::Degrade && self.index_unavailable() {
+            let resp = self
+                .send(HttpRequest::get(&format!("utxo/byId/{box_id}")))
+                .await?;
+            if resp.status == 404 {
+                return Err(NodeError::NotFound(format!("unspent box {box_id}")));
+            }
+            return self
+                .schema_mode
+                .decode(&resp.body)
+                .inspect_err(|e| warn!(%e, "Failed to decode node response."));
+        }
+
+        Ok(self.get_indexed_box(box_id).await?.utxo)
+    }
+
+    /// Fetches many boxes from the blockchain indexer, with at most [`BULK_FETCH_CONCURRENCY`]
+    /// requests in flight. Lookups failing with a transient error are retried, with
+    /// exponential backoff, up to [`BULK_FETCH_ATTEMPTS`] times; other errors are final.
+    #[tracing::instrument(skip_all, fields(count = ids.len()))]
+    pub async fn get_boxes_by_ids(
+        &self,
+        ids: &[HashDigest],
+    ) -> HashMap<HashDigest, Result<IndexedBox, NodeError>> {
+        let mut results = HashMap::with_capacity(ids.len());
+        let mut pending = ids.to_vec();
+        pending.sort_unstable();
+        pending.dedup();
+
+        for attempt in 1..=BULK_FETCH_ATTEMPTS {
+            if attempt > 1 {
+                debug!(retrying = pending.len(), attempt, "Retrying failed box lookups.");
+                sleep(BULK_FETCH_BACKOFF * 2u32.pow(attempt - 2)).await;
+            }
+
+            let permits = Arc::new(Semaphore::new(BULK_FETCH_CONCURRENCY));
+            let mut tasks = JoinSet::new();
+            for id in pending.drain(..) {
+                let (node, permits) = (self.clone(), permits.clone());
+                tasks.spawn(async move {
+                    let _permit = permits.acquire_owned().await;
+                    let result = node.get_indexed_box(&id).await;
+                    (id, result)
+                });
+            }
+
+            while let Some(joined) = tasks.join_next().await {
+                let (id, result) =
+                    joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+                match result {
+                    Err(e) if e.is_transient() && attempt < BULK_FETCH_ATTEMPTS => pending.push(id),
+                    result => {
+                        results.insert(id, result);
+                    }
+                }
+            }
+            if pending.is_empty() {
+                break;
+            }
+        }
+
+        results
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_indexed_transaction(
+        &self,
+        tx_id: &HashDigest,
+    ) -> Result<IndexedTransaction, NodeError> {
+        let resp = self
+            .request_immutable(HttpRequest::get(&format!("blockchain/transaction/byId/{tx_id}")))
+            .await?;
+        Ok(resp)
+    }
+
+    /// Validates a transaction against the node's current state without broadcasting it.
+    #[tracing::instrument(skip_all)]
+    pub async fn check_transaction(
+        &self,
+        tx: &SignedTransaction,
+    ) -> Result<TransactionCheck, NodeError> {
+        let body = serde_json::to_vec(tx)?;
+        let resp = self
+            .send(HttpRequest::post("transactions/check", body))
+            .await?;
+
+        if resp.status != 200 {
+            let error: ApiError = serde_json::from_slice(&resp.body)?;
+            debug!(?error, "Transaction rejected by node.");
+            return Ok(TransactionCheck::Invalid(error));
+        }
+
+        let check = match serde_json::from_slice(&resp.body)? {
+            CheckResponse::Id(id) => TransactionCheck::Valid { id, cost: None },
+            CheckResponse::WithCost { id, cost } => {
+                TransactionCheck::Valid { id, cost: Some(cost) }
+            }
+        };
+        Ok(check)
+    }
+
+    /// Like [`request`](Self::request), going through the response cache if enabled.
+    async fn request_immutable<T: DeserializeOwned>(
+        &self,
+        request: HttpRequest,
+    ) -> Result<T, NodeError> {
+        let Some(cache) = &self.cache else {
+            return self.request(request).await;
+        };
+        self.ensure_supported(&request)?;
+
+        let (key, path) = (request.path_and_query(), request.path.clone());
+        let resp = match cache.get(&key, self.clock.now()) {
+            Some(body) => HttpResponse { status: 200, body },
+            None => {
+                let resp = self.send(request).await?;
+                if resp.status == 200 {
+                    cache.insert(key, resp.body.clone(), self.clock.now());
+                }
+                resp
+            }
+        };
+        self.decode(&path, resp)
+    }
+
+    /// Fails requests upfront once the capability serving them is known to be missing.
+    fn ensure_supported(&self, request: &HttpRequest) -> Result<(), NodeError> {
+        match Capability::for_path(&request.path) {
+            Some(Capability::ExtraIndex) if self.index_unavailable() => {
+                Err(NodeError::IndexUnavailable)
+            }
+            Some(capability) => self.require(capability),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with [`NodeError::Unsupported`] if the node is known to lack `capability`.
+    pub fn require(&self, capability: Capability) -> Result<(), NodeError> {
+        match self.capabilities.load().as_deref() {
+            Some(capabilities) if !capabilities.supports(capability) => {
+                Err(NodeError::Unsupported { capability, version: capabilities.version })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Decodes a response body. A 404 that isn't the expected payload, e.g. the HTML page of a
+    /// disabled API, is reported as the missing capability or resource rather than a decoding
+    /// failure.
+    fn decode<T: DeserializeOwned>(&self, path: &str, resp: HttpResponse) -> Result<T, NodeError> {
+        let decoded = self.schema_mode.decode(&resp.body);
+        if resp.status == 404 && decoded.is_err() {
+            return Err(match Capability::for_path(path) {
+                Some(Capability::ExtraIndex) => NodeError::IndexUnavailable,
+                Some(capability) => NodeError::Unsupported {
+                    capability,
+                    version: self.capabilities().and_then(|c| c.version),
+                },
+                None => NodeError::NotFound(path.to_string()),
+            });
+        }
+        decoded.inspect_err(|e| warn!(%e, "Failed to decode node response."))
+    }
+
+    /// Sends a request, limiting its response to [`ResponseLimits::default`] unless it sets a
+    /// limit of its own. The limit is checked again here for transports that don't enforce it.
+    async fn send(&self, mut request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let limit = *request.max_body_size.get_or_insert(self.limits.default);
+        let endpoint = metrics::endpoint(&request.path);
+        let started = Instant::now();
+        // Holding the transport marks the request in flight for `swap_node` to drain.
+        let transport = self.transport.load_full();
+        let resp = transport.send(request).await;
+        drop(transport);
+
+        let elapsed = started.elapsed();
+        self.metrics.observe(&endpoint, elapsed);
+        if elapsed > self.slow_request_threshold {
+            warn!(%endpoint, elapsed_ms = elapsed.as_millis() as u64, "Slow node request.");
+        }
+
+        let resp = resp?;
+        if resp.body.len() > limit {
+            return Err(NodeError::ResponseTooLarge { limit });
+        }
+        Ok(resp)
+    }
+
+    async fn request<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T, NodeError> {
+        self.ensure_supported(&request)?;
+        let path = request.path.clone();
+        let resp = self.send(request).await?;
+        self.decode(&path, resp)
+    }
+}
@@ -0,0 +1,271 @@
+//! Push subscriptions to node forks and sidecars announcing mempool and block changes, so
+//! watchers can re-read the node as soon as something changes instead of on a timer.
+//!
+//! Pushed events only wake the watchers up: the node's REST API stays the source of truth,
+//! and watchers fall back to polling whenever the subscription is down.
+
+use std::{fmt::Debug, pin::Pin, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use tokio::time::Instant;
+use tracing::info;
+
+use super::NodeError;
+use crate::{clock::Clock, trace::ErrorLog, types::Height};
+
+/// How often watchers still poll while subscribed, in case an event was missed.
+pub const PUSH_FALLBACK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Delay before resubscribing after a subscription failed or ended.
+pub const PUSH_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// What a pushed event is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushTopic {
+    Mempool,
+    Blocks,
+}
+
+/// A change announced by a push API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushEvent {
+    MempoolChanged,
+    NewBlock { height: Option<Height> },
+}
+
+#[derive(Deserialize)]
+struct BlockData {
+    height: Option<Height>,
+}
+
+impl PushEvent {
+    pub fn topic(&self) -> PushTopic {
+        match self {
+            PushEvent::MempoolChanged => PushTopic::Mempool,
+            PushEvent::NewBlock { .. } => PushTopic::Blocks,
+        }
+    }
+
+    /// The event of a server-sent `mempool` or `block` message, the latter optionally with a
+    /// `{"height": ...}` payload. Other messages are ignored.
+    pub fn from_sse(message: &SseMessage) -> Option<Self> {
+        match message.event.as_str() {
+            "mempool" => Some(PushEvent::MempoolChanged),
+            "block" => {
+                let data = serde_json::from_str::<BlockData>(&message.data).ok();
+                Some(PushEvent::NewBlock { height: data.and_then(|d| d.height) })
+            }
+            _ => None,
+        }
+    }
+}
+
+pub type PushStream = Pin<Box<dyn Stream<Item = Result<PushEvent, NodeError>> + Send>>;
+
+/// A push API of the node or a sidecar, e.g. server-sent events or a websocket.
+#[async_trait]
+pub trait PushTransport: Debug + Send + Sync {
+    /// Opens a subscription. The stream ends or fails when the connection drops.
+    async fn subscribe(&self) -> Result<PushStream, NodeError>;
+}
+
+/// A message of a `text/event-stream` body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseMessage {
+    /// `message` unless the server named the event.
+    pub event: String,
+    pub data: String,
+}
+
+/// Splits a `text/event-stream` body into messages as its chunks arrive.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    line: Vec<u8>,
+    event: Option<String>,
+    data: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages completed by `chunk`.
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<SseMessage> {
+        let mut messages = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let mut line = std::mem::take(&mut self.line);
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            messages.extend(self.line_done(&String::from_utf8_lossy(&line)));
+        }
+        messages
+    }
+
+    fn line_done(&mut self, line: &str) -> Option<SseMessage> {
+        if line.is_empty() {
+            let event = self.event.take();
+            let data = self.data.take()?;
+            return Some(SseMessage {
+                event: event.unwrap_or_else(|| "message".to_string()),
+                data,
+            });
+        }
+        // Lines starting with a colon are comments, e.g. keep-alives.
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Server-sent events from `url`, read with a `reqwest` client.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct SseTransport {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "reqwest")]
+impl SseTransport {
+    /// `client` should have no overall request timeout, which would end the subscription.
+    pub fn new(client: reqwest::Client, url: &str) -> Self {
+        Self { client, url: url.to_string() }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl PushTransport for SseTransport {
+    async fn subscribe(&self) -> Result<PushStream, NodeError> {
+        let resp = self
+            .client
+            .get(&self.url)
+            .header(http::header::ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let events = futures_util::stream::unfold(
+            (resp, SseDecoder::new()),
+            |(mut resp, mut decoder)| async move {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => {
+                        let events: Vec<Result<PushEvent, NodeError>> = decoder
+                            .decode(&chunk)
+                            .iter()
+                            .filter_map(PushEvent::from_sse)
+                            .map(Ok)
+                            .collect();
+                        Some((events, (resp, decoder)))
+                    }
+                    Ok(None) => None,
+                    Err(e) => Some((vec![Err(e.into())], (resp, decoder))),
+                }
+            },
+        );
+        Ok(Box::pin(events.flat_map(futures_util::stream::iter)))
+    }
+}
+
+enum Woken {
+    Pushed,
+    Timeout,
+    Failed(NodeError),
+    Ended,
+}
+
+/// Paces a watcher's polls: on pushed events of its topic while subscribed, every
+/// `interval` otherwise. See [`NodeClient::wakeup`](super::NodeClient::wakeup).
+pub struct Wakeup {
+    push: Option<Arc<dyn PushTransport>>,
+    clock: Arc<dyn Clock>,
+    topic: PushTopic,
+    interval: Duration,
+    stream: Option<PushStream>,
+    errors: ErrorLog,
+    reconnect_at: Option<Instant>,
+}
+
+impl Wakeup {
+    pub(super) fn new(
+        push: Option<Arc<dyn PushTransport>>,
+        clock: Arc<dyn Clock>,
+        topic: PushTopic,
+        interval: Duration,
+    ) -> Self {
+        let errors = ErrorLog::new(format!("{topic:?} push subscription").to_lowercase());
+        Self { push, clock, topic, interval, stream: None, errors, reconnect_at: None }
+    }
+
+    /// Whether polls are currently paced by pushed events.
+    pub fn is_subscribed(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Waits until the watcher should poll again.
+    pub async fn wait(&mut self) {
+        self.subscribe().await;
+        let Some(stream) = &mut self.stream else {
+            return self.clock.sleep(self.interval).await;
+        };
+
+        let mut fallback = self.clock.sleep(PUSH_FALLBACK_INTERVAL.max(self.interval));
+        let woken = loop {
+            tokio::select! {
+                () = &mut fallback => break Woken::Timeout,
+                next = stream.next() => match next {
+                    Some(Ok(event)) if event.topic() == self.topic => break Woken::Pushed,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => break Woken::Failed(e),
+                    None => break Woken::Ended,
+                },
+            }
+        };
+        match woken {
+            Woken::Pushed | Woken::Timeout => {}
+            // Polls right away, as events may have been missed.
+            Woken::Failed(e) => self.disconnect(&e),
+            Woken::Ended => self.disconnect(&"Push stream ended"),
+        }
+    }
+
+    async fn subscribe(&mut self) {
+        let Some(push) = &self.push else { return };
+        if self.stream.is_some() || self.reconnect_at.is_some_and(|at| self.clock.now() < at) {
+            return;
+        }
+        match push.subscribe().await {
+            Ok(stream) => {
+                info!(topic = ?self.topic, "Subscribed to pushed node events.");
+                self.errors.success();
+                self.stream = Some(stream);
+            }
+            Err(e) => self.disconnect(&e),
+        }
+    }
+
+    fn disconnect(&mut self, error: &impl std::fmt::Display) {
+        self.errors.failure(error);
+        self.stream = None;
+        self.reconnect_at = Some(self.clock.now() + PUSH_RECONNECT_DELAY);
+    }
+}
@@ -0,0 +1,95 @@
+use super::HttpRequest;
+
+/// Boxes per page when a [`BoxQuery`] sets no limit.
+pub const DEFAULT_BOX_QUERY_LIMIT: u32 = 100;
+
+/// Order of indexer box queries by global index, i.e. by inclusion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    /// Newest boxes first, the indexer's default.
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// Paging and filters of the indexer box queries.
+///
+/// The indexer only honours `sort` and the mempool flags on unspent box queries; queries over
+/// all boxes ever created only page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoxQuery {
+    pub sort: SortOrder,
+    /// Adds outputs of mempool transactions.
+    pub include_unconfirmed: bool,
+    /// Removes boxes spent by mempool transactions.
+    pub exclude_mempool_spent: bool,
+    pub offset: u64,
+    pub limit: u32,
+}
+
+impl Default for BoxQuery {
+    fn default() -> Self {
+        Self {
+            sort: SortOrder::default(),
+            include_unconfirmed: false,
+            exclude_mempool_spent: false,
+            offset: 0,
+            limit: DEFAULT_BOX_QUERY_LIMIT,
+        }
+    }
+}
+
+impl BoxQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn include_unconfirmed(mut self, include_unconfirmed: bool) -> Self {
+        self.include_unconfirmed = include_unconfirmed;
+        self
+    }
+
+    pub fn exclude_mempool_spent(mut self, exclude_mempool_spent: bool) -> Self {
+        self.exclude_mempool_spent = exclude_mempool_spent;
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Adds the paging parameters to `request`.
+    pub(crate) fn page(&self, request: HttpRequest) -> HttpRequest {
+        request
+            .query("offset", self.offset)
+            .query("limit", self.limit)
+    }
+
+    /// Adds the paging, sort and mempool parameters to `request`.
+    pub(crate) fn apply(&self, request: HttpRequest) -> HttpRequest {
+        self.page(request)
+            .query("sortDirection", self.sort.as_str())
+            .query("includeUnconfirmed", self.include_unconfirmed)
+            .query("excludeMempoolSpent", self.exclude_mempool_spent)
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::Method;
+pub use replay::ReplayTransport;
+#[cfg(feature = "reqwest")]
+pub use reqwest_transport::ReqwestTransport;
+#[cfg(feature = "unix-socket")]
+pub use unix_socket::UnixSocketTransport;
+
+use super::NodeError;
+
+mod replay;
+#[cfg(feature = "reqwest")]
+mod reqwest_transport;
+#[cfg(feature = "unix-socket")]
+mod unix_socket;
+
+/// A transport-agnostic request against the node REST API.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    /// Path relative to the node API root, without a leading slash.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    /// JSON-encoded request body.
+    pub body: Option<Vec<u8>>,
+    /// Largest accepted response body. Transports should stop reading and fail with
+    /// [`NodeError::ResponseTooLarge`] once it is exceeded rather than buffer the whole body.
+    pub max_body_size: Option<usize>,
+}
+
+impl HttpRequest {
+    pub fn get(path: &str) -> Self {
+        Self {
+            method: Method::GET,
+            path: path.to_string(),
+            query: Vec::new(),
+            body: None,
+            max_body_size: None,
+        }
+    }
+
+    pub fn post(path: &str, body: Vec<u8>) -> Self {
+        Self {
+            method: Method::POST,
+            path: path.to_string(),
+            query: Vec::new(),
+            body: Some(body),
+            max_body_size: None,
+        }
+    }
+
+    pub fn query(mut self, key: &str, value: impl ToString) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn max_body_size(mut self, limit: usize) -> Self {
+        self.max_body_size = Some(limit);
+        self
+    }
+
+    /// Path with the URL-encoded query string appended, if any.
+    pub fn path_and_query(&self) -> String {
+        if self.query.is_empty() {
+            return self.path.clone();
+        }
+
+        let query = self
+            .query
+            .iter()
+            .map(|(k, v)| format!("{}={}", urlencode(k), urlencode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", self.path, query)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Bytes,
+}
+
+/// Sends requests to the node. Implement this to plug in a different HTTP stack (hyper, surf, a
+/// browser `fetch` binding on wasm) or a test double.
+#[async_trait]
+pub trait HttpTransport: Debug + Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError>;
+}
+
+fn urlencode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
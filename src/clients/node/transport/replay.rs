@@ -0,0 +1,66 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+
+use super::{HttpRequest, HttpResponse, HttpTransport};
+use crate::clients::node::NodeError;
+
+/// Serves recorded node responses from a directory, to run the pipeline without a node.
+///
+/// A request for `blocks/lastHeaders/10` is answered with `<dir>/blocks/lastHeaders/10.json`,
+/// whatever its query. Numbered recordings `<path>.1.json`, `<path>.2.json`, … are served in
+/// turn on later requests, the last one repeating, e.g. to replay a changing mempool. Missing
+/// recordings answer 404.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    dir: PathBuf,
+    /// Index of the recording last served per path.
+    served: Mutex<HashMap<String, usize>>,
+}
+
+impl ReplayTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), served: Mutex::default() }
+    }
+
+    fn recording(&self, path: &str, index: usize) -> PathBuf {
+        match index {
+            0 => self.dir.join(format!("{path}.json")),
+            n => self.dir.join(format!("{path}.{n}.json")),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReplayTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let not_found = || HttpResponse { status: 404, body: r#"{"error": 404}"#.into() };
+        if Path::new(&request.path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Ok(not_found());
+        }
+
+        let file = {
+            let mut served = self.served.lock().unwrap();
+            let index = match served.get(&request.path) {
+                Some(&last) if self.recording(&request.path, last + 1).is_file() => last + 1,
+                Some(&last) => last,
+                None => 0,
+            };
+            served.insert(request.path.clone(), index);
+            self.recording(&request.path, index)
+        };
+
+        match std::fs::read(&file) {
+            Ok(body) => Ok(HttpResponse { status: 200, body: body.into() }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(not_found()),
+            Err(e) => Err(NodeError::transport(e)),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use bytes::BytesMut;
+use http::header;
+
+use super::{HttpRequest, HttpResponse, HttpTransport};
+use crate::clients::node::NodeError;
+
+/// Default transport backed by a `reqwest` client.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client, base_url: &str) -> Self {
+        Self { client, base_url: base_url.trim_end_matches('/').to_string() }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let url = format!("{}/{}", self.base_url, request.path);
+        let mut builder = self
+            .client
+            .request(request.method, &url)
+            .query(&request.query);
+        if let Some(body) = request.body {
+            builder = builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+
+        let mut resp = builder.send().await?;
+        let status = resp.status().as_u16();
+        let Some(limit) = request.max_body_size else {
+            return Ok(HttpResponse { status, body: resp.bytes().await? });
+        };
+
+        if resp.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(NodeError::ResponseTooLarge { limit });
+        }
+        let mut body = BytesMut::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(NodeError::ResponseTooLarge { limit });
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(HttpResponse { status, body: body.freeze() })
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::header;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper_util::rt::TokioIo;
+use tokio::net::UnixStream;
+
+use super::{HttpRequest, HttpResponse, HttpTransport};
+use crate::clients::node::NodeError;
+
+/// Transport for a node API exposed over a unix domain socket, e.g. behind a local reverse proxy.
+///
+/// Opens a fresh HTTP/1.1 connection per request; connection setup over a local socket is cheap.
+#[derive(Debug, Clone)]
+pub struct UnixSocketTransport {
+    socket_path: PathBuf,
+}
+
+impl UnixSocketTransport {
+    pub fn new(socket_path: impl Into<PathBuf>) -> Self {
+        Self { socket_path: socket_path.into() }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for UnixSocketTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(NodeError::transport)?;
+        let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(NodeError::transport)?;
+        tokio::spawn(conn);
+
+        let limit = request.max_body_size;
+        let req = hyper::Request::builder()
+            .method(request.method.clone())
+            .uri(format!("/{}", request.path_and_query()))
+            .header(header::HOST, "localhost")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(request.body.unwrap_or_default())))
+            .map_err(NodeError::transport)?;
+
+        let resp = sender
+            .send_request(req)
+            .await
+            .map_err(NodeError::transport)?;
+        let status = resp.status().as_u16();
+        let body = match limit {
+            Some(limit) => Limited::new(resp.into_body(), limit)
+                .collect()
+                .await
+                .map_err(|e| match e.downcast::<LengthLimitError>() {
+                    Ok(_) => NodeError::ResponseTooLarge { limit },
+                    Err(e) => NodeError::Transport(e),
+                })?
+                .to_bytes(),
+            None => resp
+                .into_body()
+                .collect()
+                .await
+                .map_err(NodeError::transport)?
+                .to_bytes(),
+        };
+
+        Ok(HttpResponse { status, body })
+    }
+}
@@ -0,0 +1,90 @@
+//! Time as seen by the watchers, backoffs, rolling windows and TTL caches, so that
+//! time-dependent behavior can be driven by a [`ManualClock`] in tests instead of sleeps.
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::types::TimestampMillis;
+
+/// A source of monotonic and wall-clock time.
+pub trait Clock: Debug + Send + Sync {
+    /// Monotonic time, for timeouts and expiries.
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, for timestamps compared with the node's.
+    fn timestamp(&self) -> TimestampMillis;
+
+    /// Waits for `duration` to pass on this clock.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// The system clock, sleeping on the tokio timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn timestamp(&self) -> TimestampMillis {
+        TimestampMillis::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The clock used when none is configured.
+pub fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// A clock that only moves when told to. Sleeping advances it by the duration slept and
+/// returns at once. Clones share the time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    origin: Instant,
+    start: TimestampMillis,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// A clock reading `start` as its wall-clock time.
+    pub fn new(start: TimestampMillis) -> Self {
+        Self { origin: Instant::now(), start, elapsed: Arc::default() }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// Time advanced since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn timestamp(&self) -> TimestampMillis {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.advance(duration);
+        // Still yields, so that a loop sleeping on this clock lets other tasks run.
+        Box::pin(tokio::task::yield_now())
+    }
+}
@@ -0,0 +1,153 @@
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0}")]
+pub struct CodecError(pub &'static str);
+
+/// Reader for the Scorex serialization primitives used by Ergo's binary formats.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn get_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.get_bytes(1)?[0])
+    }
+
+    pub fn get_bytes(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if self.remaining() < n {
+            return Err(CodecError("unexpected end of buffer"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    /// Bytes not read yet.
+    pub fn rest(&self) -> &'a [u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// Runs `read` and returns the bytes it consumed, for values kept in serialized form.
+    pub fn get_consumed(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<(), CodecError>,
+    ) -> Result<&'a [u8], CodecError> {
+        let start = self.pos;
+        read(self)?;
+        Ok(&self.buf[start..self.pos])
+    }
+
+    pub fn get_array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        Ok(self.get_bytes(N)?.try_into().unwrap())
+    }
+
+    /// Unsigned VLQ (LEB128) integer.
+    pub fn get_ulong(&mut self) -> Result<u64, CodecError> {
+        let mut result = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.get_u8()?;
+            result |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(CodecError("VLQ integer overflow"))
+    }
+
+    pub fn get_uint(&mut self) -> Result<u32, CodecError> {
+        u32::try_from(self.get_ulong()?).map_err(|_| CodecError("VLQ value exceeds u32"))
+    }
+
+    /// ZigZag-encoded signed VLQ integer.
+    pub fn get_int(&mut self) -> Result<i32, CodecError> {
+        let v = self.get_uint()?;
+        Ok(((v >> 1) as i32) ^ -((v & 1) as i32))
+    }
+
+    /// UTF-8 string prefixed by a single length byte.
+    pub fn get_short_string(&mut self) -> Result<String, CodecError> {
+        let len = self.get_u8()? as usize;
+        let bytes = self.get_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CodecError("invalid UTF-8 string"))
+    }
+
+    pub fn get_option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, CodecError>,
+    ) -> Result<Option<T>, CodecError> {
+        match self.get_u8()? {
+            0 => Ok(None),
+            _ => read(self).map(Some),
+        }
+    }
+}
+
+/// Writer counterpart of [`Reader`].
+#[derive(Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn put_u8(&mut self, v: u8) -> &mut Self {
+        self.buf.push(v);
+        self
+    }
+
+    pub fn put_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn put_ulong(&mut self, mut v: u64) -> &mut Self {
+        loop {
+            let b = (v & 0x7f) as u8;
+            v >>= 7;
+            if v == 0 {
+                self.buf.push(b);
+                return self;
+            }
+            self.buf.push(b | 0x80);
+        }
+    }
+
+    pub fn put_uint(&mut self, v: u32) -> &mut Self {
+        self.put_ulong(v as u64)
+    }
+
+    pub fn put_int(&mut self, v: i32) -> &mut Self {
+        self.put_uint(((v << 1) ^ (v >> 31)) as u32)
+    }
+
+    pub fn put_short_string(&mut self, s: &str) -> &mut Self {
+        let bytes = &s.as_bytes()[..s.len().min(u8::MAX as usize)];
+        self.put_u8(bytes.len() as u8).put_bytes(bytes)
+    }
+
+    pub fn put_option<T>(&mut self, v: Option<T>, write: impl FnOnce(&mut Self, T)) -> &mut Self {
+        match v {
+            None => self.put_u8(0),
+            Some(v) => {
+                self.put_u8(1);
+                write(self, v);
+                self
+            }
+        }
+    }
+}
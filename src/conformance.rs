@@ -0,0 +1,167 @@
+//! Differential checks of local computations against a reference node: transaction and box
+//! ids, addresses of ErgoTrees and miner fees of confirmed blocks.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    address::ErgoAddress,
+    chain::{ergo_box, fee, transaction, value::ValueError},
+    clients::node::{NodeClient, NodeError},
+    types::{HashDigest, Height, HexBytes},
+};
+
+/// A local computation disagreeing with the reference node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// What was checked, e.g. a transaction or box id.
+    pub subject: String,
+    pub check: &'static str,
+    pub local: String,
+    pub reference: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub blocks: usize,
+    pub transactions: usize,
+    pub boxes: usize,
+    pub addresses: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl ConformanceReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    fn compare(
+        &mut self,
+        subject: impl fmt::Display,
+        check: &'static str,
+        local: impl fmt::Display,
+        reference: impl fmt::Display,
+    ) {
+        let (local, reference) = (local.to_string(), reference.to_string());
+        if local != reference {
+            let subject = subject.to_string();
+            self.mismatches
+                .push(Mismatch { subject, check, local, reference });
+        }
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Checked {} blocks, {} transactions, {} boxes and {} addresses: {} mismatches.",
+            self.blocks,
+            self.transactions,
+            self.boxes,
+            self.addresses,
+            self.mismatches.len()
+        )?;
+        for m in &self.mismatches {
+            writeln!(f, "{} {}: local {}, node {}", m.check, m.subject, m.local, m.reference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the checks on blocks of the reference node, accumulating the results in a report.
+#[derive(Debug)]
+pub struct ConformanceChecker<'a> {
+    node: &'a NodeClient,
+    report: ConformanceReport,
+    checked_trees: HashSet<HexBytes>,
+}
+
+impl<'a> ConformanceChecker<'a> {
+    pub fn new(node: &'a NodeClient) -> Self {
+        Self { node, report: ConformanceReport::default(), checked_trees: HashSet::new() }
+    }
+
+    /// Checks the main chain block at `height`.
+    pub async fn check_height(&mut self, height: Height) -> Result<(), NodeError> {
+        let ids = self.node.get_header_ids_at_height(height).await?;
+        match ids.first() {
+            Some(id) => self.check_block(id).await,
+            None => Err(NodeError::NotFound(format!("block at height {height}"))),
+        }
+    }
+
+    pub async fn check_block(&mut self, header_id: &HashDigest) -> Result<(), NodeError> {
+        let block = self.node.get_block(&header_id.to_string()).await?;
+        let signed = self.node.get_block_signed_transactions(header_id).await?;
+        let report = &mut self.report;
+        report.blocks += 1;
+        report.compare(
+            header_id,
+            "transaction count",
+            signed.len(),
+            block.transactions.transactions.len(),
+        );
+
+        let mut trees = Vec::new();
+        for (tx, reference) in signed.iter().zip(&block.transactions.transactions) {
+            report.transactions += 1;
+            let id = transaction::transaction_id(tx)?;
+            report.compare(&reference.id, "transaction id", &id, &reference.id);
+            report.compare(
+                &reference.id,
+                "miner fee",
+                show_fee(fee::candidates_fee(&tx.outputs)),
+                show_fee(fee::outputs_fee(&reference.outputs)),
+            );
+
+            let outputs = transaction::into_boxes(&id, tx.outputs.clone())?;
+            for (output, reference) in outputs.iter().zip(&reference.outputs) {
+                report.boxes += 1;
+                report.compare(&reference.id, "box id", &output.id, &reference.id);
+                let from_node_box = ergo_box::compute_id(reference)?;
+                report.compare(&reference.id, "box id of node box", from_node_box, &reference.id);
+                if self.checked_trees.insert(reference.ergo_tree.clone()) {
+                    trees.push(reference.ergo_tree.clone());
+                }
+            }
+        }
+
+        for tree in trees {
+            self.check_address(&tree).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks the address encoded from `tree`, in the network of the node.
+    pub async fn check_address(&mut self, tree: &HexBytes) -> Result<(), NodeError> {
+        let reference = self.node.get_address_of_ergo_tree(tree).await?;
+        let network = match reference.parse::<ErgoAddress>() {
+            Ok(address) => address.network(),
+            Err(e) => {
+                let local = format!("invalid node address: {e}");
+                self.report.compare(tree, "address", local, &reference);
+                return Ok(());
+            }
+        };
+        self.report.addresses += 1;
+        let local = ErgoAddress::from_ergo_tree(network, &tree.0).encode();
+        self.report.compare(tree, "address", local, reference);
+        Ok(())
+    }
+
+    pub fn report(&self) -> &ConformanceReport {
+        &self.report
+    }
+
+    pub fn into_report(self) -> ConformanceReport {
+        self.report
+    }
+}
+
+/// Overflowing fees are reported as mismatches rather than aborting the check.
+fn show_fee(fee: Result<u64, ValueError>) -> String {
+    match fee {
+        Ok(fee) => fee.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
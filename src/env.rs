@@ -1,8 +1,95 @@
 use once_cell::sync::Lazy;
 use std::env;
 
-pub static ERGO_NODE_URL: Lazy<String> = Lazy::new(|| get_var("ERGO_NODE_URL"));
+use crate::error::{AppError, Context};
 
-fn get_var(key: &str) -> String {
-    env::var(key).unwrap_or_else(|_| panic!("Environment variable `{key}` must be set"))
+pub static ERGO_NODE_PROXY: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_NODE_PROXY"));
+pub static ERGO_NODE_PUSH_URL: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_NODE_PUSH_URL"));
+pub static ERGO_NODE_CA_CERT: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_NODE_CA_CERT"));
+pub static ERGO_NODE_INDEX_POLICY: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_NODE_INDEX_POLICY"));
+pub static ERGO_NETWORK: Lazy<Option<String>> = Lazy::new(|| get_optional_var("ERGO_NETWORK"));
+pub static ERGO_LABELS_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_LABELS_FILE"));
+pub static ERGO_TEMPLATES_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_TEMPLATES_FILE"));
+pub static ERGO_TOKENS_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_TOKENS_FILE"));
+pub static ERGO_SALE_CONTRACTS_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_SALE_CONTRACTS_FILE"));
+pub static ERGO_ALERT_RULES_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_ALERT_RULES_FILE"));
+pub static ERGO_ALERT_WEBHOOK_URL: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_ALERT_WEBHOOK_URL"));
+pub static ERGO_USD_ORACLE_POOL_NFT: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("ERGO_USD_ORACLE_POOL_NFT"));
+pub static ERGO_AMM_POOL_NFTS: Lazy<Vec<String>> = Lazy::new(|| get_list_var("ERGO_AMM_POOL_NFTS"));
+pub static SERVER_LISTEN_ADDR: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("SERVER_LISTEN_ADDR"));
+pub static SERVER_TENANTS_FILE: Lazy<Option<String>> =
+    Lazy::new(|| get_optional_var("SERVER_TENANTS_FILE"));
+pub static ERGO_P2P_PEERS: Lazy<Vec<String>> = Lazy::new(|| get_list_var("ERGO_P2P_PEERS"));
+pub static ERGO_MIRROR_NODE_URLS: Lazy<Vec<String>> =
+    Lazy::new(|| get_list_var("ERGO_MIRROR_NODE_URLS"));
+
+// Variables that can be malformed are read on use, so that errors exit with a configuration
+// failure rather than a panic.
+
+pub fn ergo_node_url() -> Result<String, AppError> {
+    get_var("ERGO_NODE_URL")
+}
+
+pub fn ergo_miner_window() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("ERGO_MINER_WINDOW")
+}
+
+pub fn ergo_voting_epochs() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("ERGO_VOTING_EPOCHS")
+}
+
+pub fn ergo_rolling_window_secs() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("ERGO_ROLLING_WINDOW_SECS")
+}
+
+pub fn server_worker_threads() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("SERVER_WORKER_THREADS")
+}
+
+pub fn runtime_worker_threads() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("RUNTIME_WORKER_THREADS")
+}
+
+pub fn runtime_max_blocking_threads() -> Result<Option<usize>, AppError> {
+    get_optional_number_var("RUNTIME_MAX_BLOCKING_THREADS")
+}
+
+fn get_var(key: &str) -> Result<String, AppError> {
+    env::var(key).config_context(format!("Environment variable `{key}` must be set"))
+}
+
+fn get_optional_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|v| !v.is_empty())
+}
+
+fn get_optional_number_var(key: &str) -> Result<Option<usize>, AppError> {
+    get_optional_var(key)
+        .map(|v| {
+            v.parse()
+                .config_context(format!("Environment variable `{key}` must be a number"))
+        })
+        .transpose()
+}
+
+fn get_list_var(key: &str) -> Vec<String> {
+    get_optional_var(key)
+        .map(|v| {
+            v.split(',')
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
 }
@@ -1,7 +1,117 @@
+use std::{borrow::Cow, process::ExitCode};
+
 use crate::clients::node::NodeError;
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Process exit codes, following `sysexits.h` so that supervisors can tell failures apart.
+pub mod exit_code {
+    pub const USAGE: u8 = 64;
+    pub const NODE_UNAVAILABLE: u8 = 69;
+    pub const SOFTWARE: u8 = 70;
+    pub const SERVER: u8 = 71;
+    pub const STORAGE: u8 = 74;
+    pub const NODE_PROTOCOL: u8 = 76;
+    pub const CONFIG: u8 = 78;
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error(transparent)]
     NodeError(#[from] NodeError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("Task `{task}` failed too often, last with: {failure}")]
+    TaskFailed { task: &'static str, failure: String },
+
+    /// A task panicked, or was cancelled outside of a shutdown.
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+
+    /// Invalid or unreadable settings, e.g. a malformed listen address or labels file.
+    #[error("{context}: {source}")]
+    Config {
+        context: Cow<'static, str>,
+        #[source]
+        source: BoxError,
+    },
+
+    /// Reading or writing local data, e.g. exports.
+    #[error("{context}: {source}")]
+    Storage {
+        context: Cow<'static, str>,
+        #[source]
+        source: BoxError,
+    },
+
+    /// The HTTP server failed to start or stopped.
+    #[error("{context}: {source}")]
+    Server {
+        context: Cow<'static, str>,
+        #[source]
+        source: BoxError,
+    },
+}
+
+impl AppError {
+    pub fn config(context: impl Into<Cow<'static, str>>, source: impl Into<BoxError>) -> Self {
+        AppError::Config { context: context.into(), source: source.into() }
+    }
+
+    pub fn storage(context: impl Into<Cow<'static, str>>, source: impl Into<BoxError>) -> Self {
+        AppError::Storage { context: context.into(), source: source.into() }
+    }
+
+    pub fn server(context: impl Into<Cow<'static, str>>, source: impl Into<BoxError>) -> Self {
+        AppError::Server { context: context.into(), source: source.into() }
+    }
+
+    /// The exit code the process should report for this error. An unreachable node exits with
+    /// [`exit_code::NODE_UNAVAILABLE`], so it can be retried, unlike a bad configuration.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::NodeError(e) if e.is_transient() => exit_code::NODE_UNAVAILABLE,
+            AppError::NodeError(NodeError::NotIndexed(_) | NodeError::IndexUnavailable) => {
+                exit_code::NODE_UNAVAILABLE
+            }
+            AppError::NodeError(_) => exit_code::NODE_PROTOCOL,
+            AppError::Io(_) | AppError::Storage { .. } => exit_code::STORAGE,
+            AppError::Usage(_) => exit_code::USAGE,
+            AppError::TaskFailed { .. } | AppError::Join(_) => exit_code::SOFTWARE,
+            AppError::Config { .. } => exit_code::CONFIG,
+            AppError::Server { .. } => exit_code::SERVER,
+        }
+    }
+}
+
+impl From<&AppError> for ExitCode {
+    fn from(err: &AppError) -> Self {
+        ExitCode::from(err.exit_code())
+    }
+}
+
+/// Attaches context to errors on their way into [`AppError`].
+pub trait Context<T> {
+    fn config_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError>;
+    fn storage_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError>;
+    fn server_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<BoxError>> Context<T> for Result<T, E> {
+    fn config_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError> {
+        self.map_err(|e| AppError::config(context, e))
+    }
+
+    fn storage_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError> {
+        self.map_err(|e| AppError::storage(context, e))
+    }
+
+    fn server_context(self, context: impl Into<Cow<'static, str>>) -> Result<T, AppError> {
+        self.map_err(|e| AppError::server(context, e))
+    }
 }
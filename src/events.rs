@@ -0,0 +1,238 @@
+//! Bounded broadcast channels for events fanned out to consumers of varying speed, e.g.
+//! webhooks.
+//!
+//! A channel retains at most `capacity` events. A consumer falling further behind either skips
+//! the oldest events and is told how many with [`RecvError::Lagged`], or is disconnected,
+//! according to the channel's [`LagPolicy`]. Dropped events are counted in [`ChannelMetrics`].
+//!
+//! Events carry a deterministic [`EventId`], the same every time an event is emitted again,
+//! e.g. after a restart, so that downstream consumers can deduplicate deliveries.
+
+use std::{
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{
+    hash::Blake2b256Hasher,
+    types::{Digest, HashDigest, Height},
+};
+
+/// Events retained by default for the slowest consumer.
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// What happens to a consumer falling more than the channel capacity behind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LagPolicy {
+    /// The oldest events are skipped and the consumer resumes with the oldest retained one.
+    #[default]
+    DropOldest,
+    /// The consumer is disconnected and receives no further events.
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum RecvError {
+    /// `n` events were skipped; the next call resumes with the oldest retained event.
+    #[error("Consumer lagged behind by {0} events")]
+    Lagged(u64),
+
+    #[error("Consumer disconnected for lagging behind")]
+    Disconnected,
+
+    #[error("Event channel closed")]
+    Closed,
+}
+
+/// Deterministic id of an emitted event, see [`event_id`].
+pub type EventId = Digest<32>;
+
+/// blake2b256 of the event type, its subject, usually a transaction id, its height and a
+/// sequence number telling apart events that would otherwise be equal.
+pub fn event_id(
+    kind: &str,
+    subject: Option<&HashDigest>,
+    height: Option<Height>,
+    seq: u32,
+) -> EventId {
+    let mut hasher = Blake2b256Hasher::new();
+    hasher.update(kind.as_bytes()).update(&[0]);
+    if let Some(subject) = subject {
+        hasher.update(&subject.0);
+    }
+    hasher
+        .update(&height.map_or(u32::MAX, |h| h.0).to_be_bytes())
+        .update(&seq.to_be_bytes());
+    Digest(hasher.finalize())
+}
+
+/// An event with a deterministic id.
+pub trait IdentifiedEvent {
+    fn event_id(&self) -> EventId;
+}
+
+/// An event serialized with its id as an additional `id` field, e.g. for webhook bodies.
+#[derive(Debug, Clone, Serialize)]
+pub struct WithId<'a, T> {
+    pub id: EventId,
+    #[serde(flatten)]
+    pub event: &'a T,
+}
+
+impl<'a, T: IdentifiedEvent> WithId<'a, T> {
+    pub fn new(event: &'a T) -> Self {
+        Self { id: event.event_id(), event }
+    }
+}
+
+/// Counters of a channel, shared by its sender and receivers.
+#[derive(Debug, Default)]
+pub struct ChannelMetrics {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    disconnected: AtomicU64,
+}
+
+impl ChannelMetrics {
+    /// Events sent, whether or not any consumer received them.
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    /// Events skipped by lagging consumers, counted once per consumer.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Consumers disconnected under [`LagPolicy::Disconnect`].
+    pub fn disconnected(&self) -> u64 {
+        self.disconnected.load(Ordering::Relaxed)
+    }
+}
+
+/// The counters of `channels` in the Prometheus text format, labelled with the channel name.
+pub fn to_prometheus<'a>(
+    channels: impl IntoIterator<Item = (&'a str, &'a ChannelMetrics)> + Clone,
+) -> String {
+    let mut out = String::new();
+    let mut family = |name: &str, help: &str, value: fn(&ChannelMetrics) -> u64| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} counter");
+        for (channel, metrics) in channels.clone() {
+            let _ = writeln!(out, "{name}{{channel=\"{channel}\"}} {}", value(metrics));
+        }
+    };
+    family("hergmes_events_sent_total", "Events sent to the channel.", ChannelMetrics::sent);
+    family(
+        "hergmes_events_dropped_total",
+        "Events skipped by lagging consumers.",
+        ChannelMetrics::dropped,
+    );
+    family(
+        "hergmes_event_consumers_disconnected_total",
+        "Consumers disconnected for lagging behind.",
+        ChannelMetrics::disconnected,
+    );
+    out
+}
+
+/// Sending half of a bounded event channel. Clones send to the same consumers.
+#[derive(Debug, Clone)]
+pub struct EventSender<T> {
+    inner: broadcast::Sender<T>,
+    policy: LagPolicy,
+    metrics: Arc<ChannelMetrics>,
+}
+
+impl<T: Clone> EventSender<T> {
+    /// A channel retaining at most `capacity` events for its slowest consumer.
+    pub fn new(capacity: usize, policy: LagPolicy) -> Self {
+        let (inner, _) = broadcast::channel(capacity.max(1));
+        Self { inner, policy, metrics: Arc::default() }
+    }
+
+    /// Sends to all current consumers, returning how many there are. Never waits: consumers
+    /// that are full lag behind instead.
+    pub fn send(&self, event: T) -> usize {
+        self.metrics.sent.fetch_add(1, Ordering::Relaxed);
+        self.inner.send(event).unwrap_or(0)
+    }
+
+    /// A consumer of the events sent from now on.
+    pub fn subscribe(&self) -> EventReceiver<T> {
+        EventReceiver {
+            inner: self.inner.subscribe(),
+            policy: self.policy,
+            metrics: self.metrics.clone(),
+            disconnected: false,
+        }
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.inner.receiver_count()
+    }
+
+    pub fn metrics(&self) -> &Arc<ChannelMetrics> {
+        &self.metrics
+    }
+}
+
+/// Receiving half of a bounded event channel.
+#[derive(Debug)]
+pub struct EventReceiver<T> {
+    inner: broadcast::Receiver<T>,
+    policy: LagPolicy,
+    metrics: Arc<ChannelMetrics>,
+    disconnected: bool,
+}
+
+impl<T: Clone> EventReceiver<T> {
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        if self.disconnected {
+            return Err(RecvError::Disconnected);
+        }
+        let result = self.inner.recv().await;
+        self.handle(result.map_err(|e| match e {
+            broadcast::error::RecvError::Lagged(n) => RecvError::Lagged(n),
+            broadcast::error::RecvError::Closed => RecvError::Closed,
+        }))
+    }
+
+    /// Like [`recv`](Self::recv), returning [`None`] instead of waiting when no event is
+    /// pending.
+    pub fn try_recv(&mut self) -> Option<Result<T, RecvError>> {
+        if self.disconnected {
+            return Some(Err(RecvError::Disconnected));
+        }
+        let result = match self.inner.try_recv() {
+            Ok(event) => Ok(event),
+            Err(broadcast::error::TryRecvError::Empty) => return None,
+            Err(broadcast::error::TryRecvError::Lagged(n)) => Err(RecvError::Lagged(n)),
+            Err(broadcast::error::TryRecvError::Closed) => Err(RecvError::Closed),
+        };
+        Some(self.handle(result))
+    }
+
+    /// Counts skipped events and applies the lag policy.
+    fn handle(&mut self, result: Result<T, RecvError>) -> Result<T, RecvError> {
+        let Err(RecvError::Lagged(n)) = result else {
+            return result;
+        };
+        self.metrics.dropped.fetch_add(n, Ordering::Relaxed);
+        match self.policy {
+            LagPolicy::DropOldest => Err(RecvError::Lagged(n)),
+            LagPolicy::Disconnect => {
+                self.disconnected = true;
+                self.metrics.disconnected.fetch_add(1, Ordering::Relaxed);
+                Err(RecvError::Disconnected)
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! C ABI for the address and base58 codecs, declared in `include/hergmes.h`. The build script
+//! generates the header from this module, see `build.rs`.
+//!
+//! The crate only builds as a Rust library. Build the shared library for C callers with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//!
+//! Every function returns a [`HergmesStatus`]. Outputs are written to caller-provided buffers:
+//! when a buffer is too small, the required length is still written to the `*_len` out
+//! parameter and `HERGMES_STATUS_BUFFER_TOO_SMALL` is returned, so callers can retry.
+
+use std::{
+    ffi::{CStr, c_char},
+    ptr, slice,
+};
+
+use crate::address::{self, AddressError, AddressType, ErgoAddress, NetworkPrefix};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HergmesStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    BufferTooSmall = 3,
+    /// Unknown network or address type passed by the caller.
+    InvalidArgument = 4,
+    InvalidBase58 = 10,
+    TooShort = 11,
+    ChecksumMismatch = 12,
+    UnknownNetwork = 13,
+    UnknownType = 14,
+    InvalidContent = 15,
+}
+
+impl From<AddressError> for HergmesStatus {
+    fn from(e: AddressError) -> Self {
+        match e {
+            AddressError::InvalidBase58 => Self::InvalidBase58,
+            AddressError::TooShort => Self::TooShort,
+            AddressError::ChecksumMismatch => Self::ChecksumMismatch,
+            AddressError::UnknownNetwork(_) => Self::UnknownNetwork,
+            AddressError::UnknownType(_) => Self::UnknownType,
+            AddressError::InvalidContent(_) => Self::InvalidContent,
+        }
+    }
+}
+
+macro_rules! tri {
+    ($e:expr) => {
+        match $e {
+            Ok(v) => v,
+            Err(status) => return HergmesStatus::from(status),
+        }
+    };
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, HergmesStatus> {
+    if s.is_null() {
+        return Err(HergmesStatus::NullPointer);
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|_| HergmesStatus::InvalidUtf8)
+}
+
+unsafe fn bytes_arg<'a>(bytes: *const u8, len: usize) -> Result<&'a [u8], HergmesStatus> {
+    match (bytes.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(HergmesStatus::NullPointer),
+        (false, _) => Ok(unsafe { slice::from_raw_parts(bytes, len) }),
+    }
+}
+
+fn network_arg(network: u8) -> Result<NetworkPrefix, HergmesStatus> {
+    match network {
+        0x00 => Ok(NetworkPrefix::Mainnet),
+        0x10 => Ok(NetworkPrefix::Testnet),
+        _ => Err(HergmesStatus::InvalidArgument),
+    }
+}
+
+/// Copies `bytes` to `out`, followed by a NUL byte when `nul` is set.
+unsafe fn write_out(
+    bytes: &[u8],
+    nul: bool,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> HergmesStatus {
+    if out_len.is_null() {
+        return HergmesStatus::NullPointer;
+    }
+    unsafe { *out_len = bytes.len() };
+    if out_cap < bytes.len() + nul as usize {
+        return HergmesStatus::BufferTooSmall;
+    }
+    if out.is_null() {
+        return HergmesStatus::NullPointer;
+    }
+    unsafe {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+        if nul {
+            *out.add(bytes.len()) = 0;
+        }
+    }
+    HergmesStatus::Ok
+}
+
+/// Checks that `address` is a well-formed Ergo address with a valid checksum.
+///
+/// # Safety
+///
+/// `address` must be a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_address_validate(address: *const c_char) -> HergmesStatus {
+    let address = tri!(unsafe { str_arg(address) });
+    tri!(address.parse::<ErgoAddress>());
+    HergmesStatus::Ok
+}
+
+/// Decodes `address` into its network prefix (0x00 mainnet, 0x10 testnet), type (1 P2PK,
+/// 2 P2SH, 3 P2S) and content: the public key, script hash or ErgoTree.
+///
+/// # Safety
+///
+/// `address` must be a NUL-terminated string, `network`, `address_type` and `content_len`
+/// valid pointers, and `content` valid for `content_cap` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_address_decode(
+    address: *const c_char,
+    network: *mut u8,
+    address_type: *mut u8,
+    content: *mut u8,
+    content_cap: usize,
+    content_len: *mut usize,
+) -> HergmesStatus {
+    let address = tri!(unsafe { str_arg(address) });
+    let address = tri!(address.parse::<ErgoAddress>());
+    if network.is_null() || address_type.is_null() {
+        return HergmesStatus::NullPointer;
+    }
+    unsafe {
+        *network = address.network() as u8;
+        *address_type = address.kind() as u8;
+        write_out(address.content(), false, content, content_cap, content_len)
+    }
+}
+
+/// Encodes an address from its parts into `out` as a NUL-terminated string. `out_len` receives
+/// the length without the terminator.
+///
+/// # Safety
+///
+/// `content` must be valid for `content_len` bytes, `out` for `out_cap` bytes and `out_len` a
+/// valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_address_encode(
+    network: u8,
+    address_type: u8,
+    content: *const u8,
+    content_len: usize,
+    out: *mut c_char,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> HergmesStatus {
+    let network = tri!(network_arg(network));
+    let kind = match address_type {
+        1 => AddressType::P2PK,
+        2 => AddressType::P2SH,
+        3 => AddressType::P2S,
+        _ => return HergmesStatus::InvalidArgument,
+    };
+    let content = tri!(unsafe { bytes_arg(content, content_len) });
+    let address = tri!(ErgoAddress::new(network, kind, content.to_vec()));
+    unsafe { write_out(address.encode().as_bytes(), true, out.cast(), out_cap, out_len) }
+}
+
+/// Encodes the address of an ErgoTree, P2PK for `ProveDlog` trees and P2S otherwise, into
+/// `out` as a NUL-terminated string.
+///
+/// # Safety
+///
+/// `tree` must be valid for `tree_len` bytes, `out` for `out_cap` bytes and `out_len` a valid
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_address_from_ergo_tree(
+    network: u8,
+    tree: *const u8,
+    tree_len: usize,
+    out: *mut c_char,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> HergmesStatus {
+    let network = tri!(network_arg(network));
+    let tree = tri!(unsafe { bytes_arg(tree, tree_len) });
+    if tree.is_empty() {
+        return HergmesStatus::InvalidArgument;
+    }
+    let address = ErgoAddress::from_ergo_tree(network, tree);
+    unsafe { write_out(address.encode().as_bytes(), true, out.cast(), out_cap, out_len) }
+}
+
+/// Base58-encodes `bytes` into `out` as a NUL-terminated string.
+///
+/// # Safety
+///
+/// `bytes` must be valid for `len` bytes, `out` for `out_cap` bytes and `out_len` a valid
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_base58_encode(
+    bytes: *const u8,
+    len: usize,
+    out: *mut c_char,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> HergmesStatus {
+    let bytes = tri!(unsafe { bytes_arg(bytes, len) });
+    let encoded = address::base58::encode(bytes);
+    unsafe { write_out(encoded.as_bytes(), true, out.cast(), out_cap, out_len) }
+}
+
+/// Decodes a base58 string into `out`.
+///
+/// # Safety
+///
+/// `encoded` must be a NUL-terminated string, `out` valid for `out_cap` bytes and `out_len` a
+/// valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hergmes_base58_decode(
+    encoded: *const c_char,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> HergmesStatus {
+    let encoded = tri!(unsafe { str_arg(encoded) });
+    let bytes = tri!(address::base58::decode(encoded));
+    unsafe { write_out(&bytes, false, out, out_cap, out_len) }
+}
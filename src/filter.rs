@@ -0,0 +1,283 @@
+//! Composable box filters shared by the watcher, the HTTP API and the CLI.
+//!
+//! Filters are built in code, `Filter::address(a).and(Filter::min_value(n)).or(Filter::token(id))`,
+//! or parsed from the equivalent text, `address:<a> and min-value:<n> or token:<id>`. Terms are
+//! `address:`, `addresses:` (comma-separated), `tree:` (hex ErgoTree), `token:`, `min-value:`
+//! and `max-value:` (nanoERG), combined with `not`, `and` and `or` in decreasing precedence, and grouped with parentheses.
+
+use std::{fmt, ops, str::FromStr};
+
+use crate::{
+    address::{AddressSet, AddressType, ErgoAddress},
+    hash::p2sh_hash,
+    types::{
+        HashDigest, HexBytes,
+        ergo::{UTxO, UnconfirmedTransaction},
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FilterError {
+    #[error("Empty filter")]
+    Empty,
+
+    #[error("Unexpected `{0}` in filter")]
+    Unexpected(String),
+
+    #[error("Unknown filter term `{0}`")]
+    UnknownTerm(String),
+
+    #[error("Invalid value `{value}` for filter term `{term}`")]
+    InvalidValue { term: &'static str, value: String },
+
+    #[error("Unclosed parenthesis in filter")]
+    Unclosed,
+}
+
+/// A predicate over boxes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Boxes sent to the address, including P2SH addresses by the hash of the box's tree.
+    Address(ErgoAddress),
+    /// Boxes sent to any of the addresses, tested with a single lookup whatever their number.
+    Addresses(AddressSet),
+    ErgoTree(HexBytes),
+    /// Boxes holding any amount of the token.
+    Token(HashDigest),
+    MinValue(u64),
+    MaxValue(u64),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn address(address: ErgoAddress) -> Self {
+        Filter::Address(address)
+    }
+
+    pub fn addresses(addresses: AddressSet) -> Self {
+        Filter::Addresses(addresses)
+    }
+
+    pub fn ergo_tree(tree: HexBytes) -> Self {
+        Filter::ErgoTree(tree)
+    }
+
+    pub fn token(id: HashDigest) -> Self {
+        Filter::Token(id)
+    }
+
+    pub fn min_value(nano_ergs: u64) -> Self {
+        Filter::MinValue(nano_ergs)
+    }
+
+    pub fn max_value(nano_ergs: u64) -> Self {
+        Filter::MaxValue(nano_ergs)
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, utxo: &UTxO) -> bool {
+        match self {
+            Filter::Address(address) => match address.kind() {
+                AddressType::P2SH => p2sh_hash(&utxo.ergo_tree.0)[..] == *address.content(),
+                _ => address
+                    .ergo_tree()
+                    .is_some_and(|tree| tree == utxo.ergo_tree),
+            },
+            Filter::Addresses(addresses) => addresses.matches(utxo),
+            Filter::ErgoTree(tree) => *tree == utxo.ergo_tree,
+            Filter::Token(id) => utxo.tokens.iter().any(|t| t.id == *id),
+            Filter::MinValue(min) => utxo.value >= *min,
+            Filter::MaxValue(max) => utxo.value <= *max,
+            Filter::And(a, b) => a.matches(utxo) && b.matches(utxo),
+            Filter::Or(a, b) => a.matches(utxo) || b.matches(utxo),
+            Filter::Not(filter) => !filter.matches(utxo),
+        }
+    }
+
+    /// Whether any output of the transaction matches.
+    pub fn matches_transaction(&self, tx: &UnconfirmedTransaction) -> bool {
+        tx.outputs.iter().any(|output| self.matches(output))
+    }
+
+    /// Binding strength in the text form, to parenthesize only where needed.
+    fn precedence(&self) -> u8 {
+        match self {
+            Filter::Or(..) => 0,
+            Filter::And(..) => 1,
+            _ => 2,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence { write!(f, "({self})") } else { write!(f, "{self}") }
+    }
+}
+
+impl ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Filter::Address(address) => write!(f, "address:{address}"),
+            Filter::Addresses(addresses) => {
+                f.write_str("addresses:")?;
+                for (i, address) in addresses.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{address}")?;
+                }
+                Ok(())
+            }
+            Filter::ErgoTree(tree) => write!(f, "tree:{tree}"),
+            Filter::Token(id) => write!(f, "token:{id}"),
+            Filter::MinValue(min) => write!(f, "min-value:{min}"),
+            Filter::MaxValue(max) => write!(f, "max-value:{max}"),
+            Filter::And(a, b) => {
+                a.fmt_operand(f, 1)?;
+                f.write_str(" and ")?;
+                b.fmt_operand(f, 2)
+            }
+            Filter::Or(a, b) => {
+                a.fmt_operand(f, 0)?;
+                f.write_str(" or ")?;
+                b.fmt_operand(f, 1)
+            }
+            Filter::Not(filter) => {
+                f.write_str("not ")?;
+                filter.fmt_operand(f, 2)
+            }
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { tokens: tokenize(s), position: 0 };
+        let filter = parser.or()?;
+        match parser.next() {
+            None => Ok(filter),
+            Some(token) => Err(FilterError::Unexpected(token.to_string())),
+        }
+    }
+}
+
+/// Splits on whitespace, with parentheses as tokens of their own.
+fn tokenize(s: &str) -> Vec<&str> {
+    s.split_whitespace()
+        .flat_map(|word| word.split_inclusive(['(', ')']))
+        .flat_map(|part| match part.strip_suffix(['(', ')']) {
+            Some(rest) if !rest.is_empty() => vec![rest, &part[rest.len()..]],
+            _ => vec![part],
+        })
+        .collect()
+}
+
+/// Recursive descent over `or := and ("or" and)*`, `and := not ("and" not)*` and
+/// `not := "not" not | "(" or ")" | term`.
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.and()?;
+        while self.peek() == Some("or") {
+            self.position += 1;
+            filter = filter.or(self.and()?);
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.not()?;
+        while self.peek() == Some("and") {
+            self.position += 1;
+            filter = filter.and(self.not()?);
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<Filter, FilterError> {
+        match self.next() {
+            None => Err(FilterError::Empty),
+            Some("not") => Ok(!self.not()?),
+            Some("(") => {
+                let filter = self.or()?;
+                match self.next() {
+                    Some(")") => Ok(filter),
+                    Some(token) => Err(FilterError::Unexpected(token.to_string())),
+                    None => Err(FilterError::Unclosed),
+                }
+            }
+            Some(token) => term(token),
+        }
+    }
+}
+
+fn term(token: &str) -> Result<Filter, FilterError> {
+    let Some((term, value)) = token.split_once(':') else {
+        return Err(match token {
+            ")" | "and" | "or" => FilterError::Unexpected(token.to_string()),
+            _ => FilterError::UnknownTerm(token.to_string()),
+        });
+    };
+    let invalid = |term: &'static str| FilterError::InvalidValue { term, value: value.to_string() };
+    match term {
+        "address" => value
+            .parse()
+            .map(Filter::Address)
+            .map_err(|_| invalid("address")),
+        "addresses" => value
+            .split(',')
+            .filter(|address| !address.is_empty())
+            .map(ErgoAddress::from_str)
+            .collect::<Result<AddressSet, _>>()
+            .map(Filter::Addresses)
+            .map_err(|_| invalid("addresses")),
+        "tree" => hex::decode(value)
+            .map(|tree| Filter::ErgoTree(HexBytes(tree)))
+            .map_err(|_| invalid("tree")),
+        "token" => value
+            .parse()
+            .map(Filter::Token)
+            .map_err(|_| invalid("token")),
+        "min-value" => value
+            .parse()
+            .map(Filter::MinValue)
+            .map_err(|_| invalid("min-value")),
+        "max-value" => value
+            .parse()
+            .map(Filter::MaxValue)
+            .map_err(|_| invalid("max-value")),
+        _ => Err(FilterError::UnknownTerm(term.to_string())),
+    }
+}
@@ -0,0 +1,91 @@
+//! Hash functions of the Ergo protocol: blake2b256 for ids, checksums and Merkle trees, keyed
+//! and variable-length blake2b, and sha256.
+
+use blake2::{
+    Blake2b256, Blake2bMac, Blake2bVarCore, Digest,
+    digest::{
+        Mac, Output,
+        block_api::{Buffer, UpdateCore, VariableOutputCore},
+        consts::U32,
+    },
+};
+use sha2::{Digest as _, Sha256};
+
+/// Length of the script hash of P2SH addresses.
+pub const P2SH_HASH_LEN: usize = 24;
+
+/// Largest blake2b output, and key, in bytes.
+pub const BLAKE2B_MAX_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum HashError {
+    #[error("blake2b output length must be 1 to 64 bytes, not {0}")]
+    OutputLength(usize),
+
+    #[error("blake2b key length must be 1 to 64 bytes, not {0}")]
+    KeyLength(usize),
+}
+
+pub fn blake2b256(data: &[u8]) -> [u8; 32] {
+    Blake2b256::digest(data).into()
+}
+
+/// Streaming blake2b256, for data hashed in parts without being concatenated first.
+#[derive(Debug, Clone, Default)]
+pub struct Blake2b256Hasher(Blake2b256);
+
+impl Blake2b256Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> &mut Self {
+        Digest::update(&mut self.0, data);
+        self
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        self.0.finalize().into()
+    }
+
+    /// Returns the hash and resets the hasher for reuse.
+    pub fn finalize_reset(&mut self) -> [u8; 32] {
+        std::mem::take(&mut self.0).finalize().into()
+    }
+}
+
+/// blake2b256 keyed with `key`, as a MAC.
+pub fn blake2b256_keyed(key: &[u8], data: &[u8]) -> Result<[u8; 32], HashError> {
+    if key.is_empty() || key.len() > BLAKE2B_MAX_LEN {
+        return Err(HashError::KeyLength(key.len()));
+    }
+    let mut mac = Blake2bMac::<U32>::new_with_salt_and_personal(Some(key), &[], &[])
+        .map_err(|_| HashError::KeyLength(key.len()))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().into())
+}
+
+/// blake2b with an output of `len` bytes. Unlike a truncated blake2b512, the length is part of
+/// the hash parameters, so e.g. `blake2b(32, data)` equals [`blake2b256`].
+pub fn blake2b(len: usize, data: &[u8]) -> Result<Vec<u8>, HashError> {
+    if len == 0 || len > BLAKE2B_MAX_LEN {
+        return Err(HashError::OutputLength(len));
+    }
+    let mut core = Blake2bVarCore::new(len).map_err(|_| HashError::OutputLength(len))?;
+    let mut buffer = Buffer::<Blake2bVarCore>::default();
+    buffer.digest_blocks(data, |blocks| core.update_blocks(blocks));
+    let mut out = Output::<Blake2bVarCore>::default();
+    core.finalize_variable_core(&mut buffer, &mut out);
+    Ok(out[..len].to_vec())
+}
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Script hash of a P2SH address: the first 192 bits of blake2b256 of the ErgoTree.
+pub fn p2sh_hash(ergo_tree: &[u8]) -> [u8; P2SH_HASH_LEN] {
+    let mut hash = [0; P2SH_HASH_LEN];
+    hash.copy_from_slice(&blake2b256(ergo_tree)[..P2SH_HASH_LEN]);
+    hash
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+const SHARDS: usize = 16;
+
+/// ErgoTree bytes with a precomputed hash, so hashing and unequal comparisons don't touch the
+/// bytes.
+pub struct ErgoTreeBytes {
+    hash: u64,
+    bytes: Box<[u8]>,
+}
+
+impl ErgoTreeBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    BuildHasherDefault::<DefaultHasher>::default().hash_one(bytes)
+}
+
+impl From<&[u8]> for ErgoTreeBytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self { hash: hash_bytes(bytes), bytes: bytes.into() }
+    }
+}
+
+impl Deref for ErgoTreeBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl PartialEq for ErgoTreeBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.bytes == other.bytes
+    }
+}
+
+impl Eq for ErgoTreeBytes {}
+
+impl Hash for ErgoTreeBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl fmt::Debug for ErgoTreeBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ErgoTreeBytes({})", hex::encode(&self.bytes))
+    }
+}
+
+/// Concurrent pool of ErgoTrees, so a contract guarding thousands of boxes is stored once.
+///
+/// The pool is split in shards by tree hash, keeping lock contention low when several tasks
+/// intern at once. Trees stay pooled until [`purge`](Self::purge) drops those no longer
+/// referenced elsewhere.
+#[derive(Default)]
+pub struct ErgoTreeInterner {
+    shards: [Mutex<HashMap<u64, Vec<Arc<ErgoTreeBytes>>>>; SHARDS],
+}
+
+impl ErgoTreeInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The pooled handle equal to `tree`, added to the pool if not present yet.
+    pub fn intern(&self, tree: &[u8]) -> Arc<ErgoTreeBytes> {
+        let hash = hash_bytes(tree);
+        let mut shard = self.shards[hash as usize % SHARDS].lock().unwrap();
+        let bucket = shard.entry(hash).or_default();
+        if let Some(pooled) = bucket.iter().find(|t| *t.bytes == *tree) {
+            return pooled.clone();
+        }
+
+        let interned = Arc::new(ErgoTreeBytes::from(tree));
+        bucket.push(interned.clone());
+        interned
+    }
+
+    /// Drops trees only referenced by the pool, returning how many were removed.
+    pub fn purge(&self) -> usize {
+        let mut removed = 0;
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap();
+            shard.retain(|_, bucket| {
+                let before = bucket.len();
+                bucket.retain(|t| Arc::strong_count(t) > 1);
+                removed += before - bucket.len();
+                !bucket.is_empty()
+            });
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().unwrap().values().map(Vec::len).sum::<usize>())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, path::Path};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address::{AddressError, AddressType, ErgoAddress, NetworkPrefix},
+    types::HexBytes,
+};
+
+/// Labels shipped with the crate.
+pub static BUILTIN: Lazy<LabelSet> = Lazy::new(|| {
+    LabelSet::from_json(include_str!("builtin.json")).expect("built-in labels are valid")
+});
+
+#[derive(Debug, thiserror::Error)]
+pub enum LabelError {
+    #[error("Failed to read labels: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid label file: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid labelled address `{0}`: {1}")]
+    Address(String, AddressError),
+
+    #[error("Label entry `{0}` must have exactly one of `address` or `ergoTree`")]
+    Target(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LabelCategory {
+    Exchange,
+    Mixer,
+    Bridge,
+    Dapp,
+    Miner,
+    Protocol,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Label {
+    pub name: String,
+    pub category: LabelCategory,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// An entry of a label file, keyed by either an address or an ErgoTree.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelEntry {
+    address: Option<String>,
+    ergo_tree: Option<HexBytes>,
+    #[serde(flatten)]
+    label: Label,
+}
+
+/// Labels for well-known addresses. Lookups ignore the network prefix, so one set serves
+/// both mainnet and testnet addresses.
+#[derive(Debug, Clone, Default)]
+pub struct LabelSet {
+    labels: HashMap<(AddressType, Vec<u8>), Label>,
+}
+
+impl LabelSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in labels with the entries of `path`, if any, taking precedence.
+    pub fn with_overrides(path: Option<&Path>) -> Result<Self, LabelError> {
+        let mut labels = BUILTIN.clone();
+        if let Some(path) = path {
+            labels.extend(Self::from_json(&std::fs::read_to_string(path)?)?);
+        }
+        Ok(labels)
+    }
+
+    /// Parses a JSON array of `{ "address" | "ergoTree", "name", "category", "url"? }`.
+    pub fn from_json(json: &str) -> Result<Self, LabelError> {
+        let entries: Vec<LabelEntry> = serde_json::from_str(json)?;
+        let mut labels = Self::new();
+        for entry in entries {
+            let address = match (entry.address, entry.ergo_tree) {
+                (Some(address), None) => address
+                    .parse()
+                    .map_err(|e| LabelError::Address(address.clone(), e))?,
+                (None, Some(tree)) => ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &tree.0),
+                _ => return Err(LabelError::Target(entry.label.name)),
+            };
+            labels.insert(&address, entry.label);
+        }
+        Ok(labels)
+    }
+
+    /// Adds or replaces the label of `address`.
+    pub fn insert(&mut self, address: &ErgoAddress, label: Label) {
+        self.labels
+            .insert((address.kind(), address.content().to_vec()), label);
+    }
+
+    /// Adds all labels of `other`, replacing existing ones for the same address.
+    pub fn extend(&mut self, other: LabelSet) {
+        self.labels.extend(other.labels);
+    }
+
+    pub fn get(&self, address: &ErgoAddress) -> Option<&Label> {
+        self.labels
+            .get(&(address.kind(), address.content().to_vec()))
+    }
+
+    pub fn get_by_tree(&self, ergo_tree: &[u8]) -> Option<&Label> {
+        self.get(&ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, ergo_tree))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
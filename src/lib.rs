@@ -1,6 +1,48 @@
+pub mod address;
+#[cfg(feature = "runtime")]
+pub mod alerts;
+#[cfg(feature = "runtime")]
+pub mod analytics;
+pub mod chain;
+#[cfg(feature = "runtime")]
 pub mod clients;
+#[cfg(feature = "runtime")]
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "runtime")]
+pub mod conformance;
+#[cfg(feature = "runtime")]
 pub mod env;
+#[cfg(feature = "runtime")]
 pub mod error;
+#[cfg(feature = "runtime")]
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod hash;
+pub mod intern;
+pub mod labels;
+#[cfg(feature = "p2p")]
+pub mod p2p;
+pub mod params;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "runtime")]
+pub mod shutdown;
+pub mod storage_rent;
+#[cfg(feature = "runtime")]
+pub mod supervisor;
+pub mod templates;
+#[cfg(feature = "runtime")]
+pub mod tokens;
+#[cfg(feature = "runtime")]
 pub mod trace;
 pub mod types;
+pub mod utxo;
+#[cfg(feature = "runtime")]
+pub mod wallet;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "runtime")]
 pub mod watcher;
@@ -1,28 +1,694 @@
-use std::time::Duration;
+use std::{process::ExitCode, sync::Arc, time::Duration};
 
+use arc_swap::ArcSwap;
 use dotenvy::dotenv;
 use hergmes::{
-    clients::node::NodeClient,
-    env::ERGO_NODE_URL,
-    error::AppError,
+    address::NetworkPrefix,
+    alerts::{self, AlertEngine, LogNotifier, Notifier, WebhookNotifier},
+    analytics::{
+        holders,
+        rolling::{self, DEFAULT_ROLLING_WINDOW, RollingReport, RollingWindow},
+    },
+    clients::node::{
+        DEFAULT_SWAP_DRAIN_TIMEOUT, IndexPolicy, NodeClient, NodeClientBuilder, NodeError,
+        ReplayTransport, ReqwestTransport,
+    },
+    env::{
+        self, ERGO_ALERT_RULES_FILE, ERGO_ALERT_WEBHOOK_URL, ERGO_NETWORK, ERGO_NODE_CA_CERT,
+        ERGO_NODE_INDEX_POLICY, ERGO_NODE_PROXY, ERGO_NODE_PUSH_URL, ERGO_TOKENS_FILE,
+    },
+    error::{AppError, Context},
+    params,
+    shutdown::{ShutdownCoordinator, Stage},
+    tokens::TokenRegistry,
     trace::{self, default_subscriber},
     watcher,
 };
+use tokio::{
+    runtime::{self, Runtime},
+    task::{JoinHandle, JoinSet},
+};
 
-#[tokio::main]
-async fn main() -> Result<(), AppError> {
+fn main() -> ExitCode {
     let _ = dotenv();
     trace::init(default_subscriber());
 
-    let http_client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .expect("Failed to build HTTP client");
+    let result = env::runtime_worker_threads()
+        .and_then(|threads| runtime("hergmes-worker", threads))
+        .and_then(|runtime| runtime.block_on(run()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!(code = e.exit_code(), "{e}");
+            ExitCode::from(&e)
+        }
+    }
+}
+
+/// A multi-threaded runtime with `worker_threads` workers, or one per core.
+fn runtime(name: &str, worker_threads: Option<usize>) -> Result<Runtime, AppError> {
+    let mut builder = runtime::Builder::new_multi_thread();
+    builder.enable_all().thread_name(name);
+    if let Some(threads) = worker_threads {
+        builder.worker_threads(threads);
+    }
+    if let Some(threads) = env::runtime_max_blocking_threads()? {
+        builder.max_blocking_threads(threads);
+    }
+    Ok(builder.build()?)
+}
+
+async fn run() -> Result<(), AppError> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let dry_run = args.first().is_some_and(|a| a == "--dry-run");
+    let recordings = if dry_run {
+        let dir = args.get(1).cloned().ok_or_else(|| {
+            AppError::Usage("Usage: hergmes --dry-run <recordings dir> [command]".to_string())
+        })?;
+        args.drain(..2);
+        Some(dir)
+    } else {
+        None
+    };
+    if let Some((command, args)) = args.split_first()
+        && let Some(result) = run_offline_command(command, args)
+    {
+        return result;
+    }
 
-    let node = NodeClient::new(http_client, &ERGO_NODE_URL);
+    let node = match recordings {
+        Some(dir) => NodeClient::with_transport(ReplayTransport::new(dir))
+            .with_index_policy(IndexPolicy::Degrade),
+        None => build_node(&env::ergo_node_url()?)?,
+    };
+    node.detect_capabilities().await?;
     node.check_node_index_status().await?;
 
-    let _mempool_snapshot = watcher::spawn(node.clone()).await?;
+    if let Some((command, args)) = args.split_first() {
+        return run_command(&node, command, args).await;
+    }
+
+    #[cfg(unix)]
+    if !dry_run {
+        spawn_node_reload(node.clone())?;
+    }
+
+    let shutdown = ShutdownCoordinator::new();
+    let alerts = load_alert_rules()?;
+    let (_network_params, params_task) = params::spawn(node.clone());
+    shutdown.abort(Stage::Intake, "network parameters", params_task.abort_handle());
+
+    #[cfg(feature = "server")]
+    if let Some(addr) = hergmes::env::SERVER_LISTEN_ADDR.as_deref() {
+        // The divergence report is only read by the server. Mirror nodes are live, so they
+        // are not compared in dry runs.
+        let divergence = if dry_run { None } else { spawn_divergence_tracker(&node)? };
+        return serve(node, addr, divergence, alerts, params_task, shutdown).await;
+    }
+
+    let watch = watcher::spawn_mempool(node.clone(), watcher::DEFAULT_HISTORY_CAPACITY);
+    shutdown.abort(Stage::Intake, "mempool watcher", watch.handle.abort_handle());
+    #[cfg(feature = "p2p")]
+    spawn_p2p_relay(&node, &watch, &shutdown)?;
+    #[cfg(feature = "proofs")]
+    watcher::spawn_proof_verifier(watch.snapshot.clone());
+    if let Some(engine) = alerts {
+        let rolling = engine.needs_rolling().then(|| {
+            rolling::spawn_rolling(
+                watch.snapshot.clone(),
+                RollingWindow::new(DEFAULT_ROLLING_WINDOW),
+            )
+        });
+        spawn_alerts(&node, &watch, rolling, engine, &shutdown)?;
+    }
+
+    until_stopped([watch.handle, params_task], shutdown).await
+}
+
+/// Waits on supervised tasks until one escalates a failure or the process is asked to
+/// terminate, then shuts the subsystems down in order so that queued events are delivered.
+async fn until_stopped(
+    tasks: impl IntoIterator<Item = JoinHandle<Result<(), AppError>>>,
+    shutdown: ShutdownCoordinator,
+) -> Result<(), AppError> {
+    let result = tokio::select! {
+        result = until_failure(tasks, &shutdown) => result,
+        () = terminated() => Ok(()),
+    };
+    shutdown.shutdown().await;
+    result
+}
+
+/// Resolves on SIGINT, or SIGTERM on unix.
+async fn terminated() {
+    #[cfg(unix)]
+    if let Ok(mut terminate) =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+    {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        return;
+    }
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Waits for all `tasks`, returning the first failure as soon as it happens. Panicked tasks
+/// fail too, as do cancelled ones unless `shutdown` began aborting them.
+async fn until_failure(
+    tasks: impl IntoIterator<Item = JoinHandle<Result<(), AppError>>>,
+    shutdown: &ShutdownCoordinator,
+) -> Result<(), AppError> {
+    let mut tasks: JoinSet<_> = tasks.into_iter().collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined.and_then(|task| task) {
+            Ok(result) => result?,
+            Err(e) if e.is_cancelled() && shutdown.is_started() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+fn build_node(url: &str) -> Result<NodeClient, AppError> {
+    let mut builder = node_builder(url)?;
+    if let Some(url) = ERGO_NODE_PUSH_URL.as_deref() {
+        builder = builder.push_url(url);
+    }
+    builder
+        .build()
+        .config_context("Invalid node client settings")
+}
+
+/// A client builder with the proxy, CA certificate and index policy shared by the main and
+/// mirror nodes.
+fn node_builder(url: &str) -> Result<NodeClientBuilder, AppError> {
+    let mut builder = NodeClient::builder(url).index_policy(index_policy());
+    if let Some(proxy) = ERGO_NODE_PROXY.as_deref() {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = ERGO_NODE_CA_CERT.as_deref() {
+        let pem = std::fs::read(path)
+            .config_context(format!("Failed to read CA certificate `{path}`"))?;
+        builder = builder.add_root_certificate_pem(pem);
+    }
+    Ok(builder)
+}
+
+/// Re-reads `.env` on SIGHUP and moves `node` to the `ERGO_NODE_URL` it sets, keeping the
+/// running watchers and their state.
+#[cfg(unix)]
+fn spawn_node_reload(node: NodeClient) -> Result<(), AppError> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            if let Err(e) = reload_node(&node).await {
+                tracing::error!("Node not switched: {e}");
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn reload_node(node: &NodeClient) -> Result<(), AppError> {
+    let _ = dotenvy::dotenv_override();
+    let url = std::env::var("ERGO_NODE_URL").config_context("Missing `ERGO_NODE_URL`")?;
+    tracing::info!(url, "Switching node...");
+    node.swap_node(&build_node(&url)?, DEFAULT_SWAP_DRAIN_TIMEOUT)
+        .await?;
+    tracing::info!(url, "Node switched.");
+    Ok(())
+}
+
+async fn run_command(node: &NodeClient, command: &str, args: &[String]) -> Result<(), AppError> {
+    match command {
+        "fees" => fees(node, args).await,
+        "holders" => holders(node, args).await,
+        "mempool" => mempool(node, args).await,
+        "vectors" => vectors(node, args).await,
+        "votes" => votes(node, args).await,
+        _ => Err(AppError::Usage(format!("Unknown command `{command}`"))),
+    }
+}
+
+/// Runs `command` if it does not need a node, so that it works offline.
+#[cfg_attr(not(feature = "qr"), allow(unused_variables))]
+fn run_offline_command(command: &str, args: &[String]) -> Option<Result<(), AppError>> {
+    match command {
+        #[cfg(feature = "qr")]
+        "qr" => Some(qr(args)),
+        _ => None,
+    }
+}
+
+/// `fees <count> <fee_per_byte> [--tx-size N] [--blocks N]`: simulates the next blocks after
+/// `count` transactions paying `fee_per_byte` nanoERG enter the mempool, and prints the fees a
+/// new transaction would need as JSON.
+async fn fees(node: &NodeClient, args: &[String]) -> Result<(), AppError> {
+    use hergmes::{
+        analytics::feemarket::{self, DEFAULT_TX_SIZE, FeeMarket},
+        intern::ErgoTreeInterner,
+        params::NetworkParameters,
+        types::TimestampMillis,
+        watcher::MempoolSnapshot,
+    };
+
+    const USAGE: &str = "Usage: hergmes fees <count> <fee_per_byte> [--tx-size N] [--blocks N]";
+    let usage = || AppError::Usage(USAGE.to_string());
+
+    let [count, fee_per_byte, rest @ ..] = args else {
+        return Err(usage());
+    };
+    let count: usize = count.parse().map_err(|_| usage())?;
+    let fee_per_byte: u64 = fee_per_byte.parse().map_err(|_| usage())?;
+    let (mut tx_size, mut blocks) = (DEFAULT_TX_SIZE, 3);
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        let value = rest.next().and_then(|v| v.parse().ok()).ok_or_else(usage)?;
+        match arg.as_str() {
+            "--tx-size" => tx_size = value,
+            "--blocks" => blocks = value,
+            _ => return Err(usage()),
+        }
+    }
+
+    let parameters = node
+        .get_info()
+        .await?
+        .parameters
+        .as_ref()
+        .map(NetworkParameters::from)
+        .unwrap_or_default();
+    let transactions = node.get_mempool_snapshot().await?;
+    let snapshot =
+        MempoolSnapshot::new(TimestampMillis::now(), transactions, &ErgoTreeInterner::new());
+    let mut market = FeeMarket::from_snapshot(&snapshot);
+    market.inject(count, tx_size, fee_per_byte.saturating_mul(tx_size as u64));
+
+    let what_if = feemarket::what_if(&market, blocks, parameters.max_block_size, tx_size);
+    let report = serde_json::to_string_pretty(&what_if).map_err(NodeError::from)?;
+    println!("{report}");
+    Ok(())
+}
+
+/// `holders <token_id> [--at-height N]`: prints the token's holders as CSV.
+async fn holders(node: &NodeClient, args: &[String]) -> Result<(), AppError> {
+    const USAGE: &str = "Usage: hergmes holders <token_id> [--at-height N]";
+    let usage = || AppError::Usage(USAGE.to_string());
+
+    let (token_id, mut at_height) = (args.first().ok_or_else(usage)?, None);
+    let token_id = token_id.parse().map_err(|_| usage())?;
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--at-height" => {
+                let height = rest.next().and_then(|h| h.parse().ok()).ok_or_else(usage)?;
+                at_height = Some(height);
+            }
+            _ => return Err(usage()),
+        }
+    }
+
+    let holders = holders::token_holders(node, &token_id, at_height).await?;
+    let tokens = load_tokens()?;
+    // Without metadata the balances are written as raw amounts.
+    if let Err(e) = tokens.resolve(node, &token_id).await {
+        tracing::warn!("Failed to read the token's decimals: {e}");
+    }
+    holders
+        .write_csv(network(), &tokens, std::io::stdout().lock())
+        .storage_context("Failed to write holders")?;
+    Ok(())
+}
+
+/// `mempool [filter]`: prints the ids of unconfirmed transactions with an output matching the
+/// filter expression, e.g. `mempool address:<address> and min-value:1000000`.
+async fn mempool(node: &NodeClient, args: &[String]) -> Result<(), AppError> {
+    use hergmes::filter::Filter;
 
+    let filter = match args {
+        [] => None,
+        _ => Some(
+            args.join(" ")
+                .parse::<Filter>()
+                .map_err(|e| AppError::Usage(format!("Usage: hergmes mempool [filter]: {e}")))?,
+        ),
+    };
+    for tx in node.get_mempool_snapshot().await? {
+        if filter.as_ref().is_none_or(|f| f.matches_transaction(&tx)) {
+            println!("{}", tx.id);
+        }
+    }
     Ok(())
 }
+
+/// `qr <address>`: prints the address as a QR code for the terminal.
+#[cfg(feature = "qr")]
+fn qr(args: &[String]) -> Result<(), AppError> {
+    use hergmes::address::{ErgoAddress, qr::AddressQr, typo};
+
+    let usage = || AppError::Usage("Usage: hergmes qr <address>".to_string());
+    let [address] = args else {
+        return Err(usage());
+    };
+    let address: ErgoAddress = address
+        .parse()
+        .map_err(|e| AppError::Usage(typo::describe_error(address, &e)))?;
+    let code = AddressQr::new(&address).map_err(|e| AppError::Usage(e.to_string()))?;
+    println!("{}", code.to_terminal());
+    Ok(())
+}
+
+/// `vectors [blocks]`: prints codec test vectors of the last blocks' transactions as JSON.
+async fn vectors(node: &NodeClient, args: &[String]) -> Result<(), AppError> {
+    use hergmes::chain::vectors::TestVectors;
+
+    let usage = || AppError::Usage("Usage: hergmes vectors [blocks]".to_string());
+    let blocks = match args {
+        [] => 1,
+        [n] => n.parse().map_err(|_| usage())?,
+        _ => return Err(usage()),
+    };
+
+    let mut vectors = TestVectors::new();
+    for header in node.get_last_n_headers(blocks).await? {
+        for tx in node.get_block_signed_transactions(&header.id).await? {
+            vectors.add_transaction(&tx).map_err(NodeError::from)?;
+        }
+    }
+    print!("{}", vectors.to_json());
+    Ok(())
+}
+
+/// `votes [epochs]`: prints the votes of the last epochs and the ongoing soft-fork as JSON.
+async fn votes(node: &NodeClient, args: &[String]) -> Result<(), AppError> {
+    use hergmes::analytics::voting::{DEFAULT_VOTING_EPOCHS, VotingSettings, VotingTracker};
+
+    let usage = || AppError::Usage("Usage: hergmes votes [epochs]".to_string());
+    let epochs = match args {
+        [] => DEFAULT_VOTING_EPOCHS,
+        [n] => n.parse().map_err(|_| usage())?,
+        _ => return Err(usage()),
+    };
+
+    let mut tracker =
+        VotingTracker::new(VotingSettings::for_network(network())).with_epochs(epochs);
+    tracker.poll(node).await?;
+    let report = serde_json::to_string_pretty(&tracker.report()).map_err(NodeError::from)?;
+    println!("{report}");
+    Ok(())
+}
+
+fn network() -> NetworkPrefix {
+    match ERGO_NETWORK.as_deref() {
+        Some("testnet") => NetworkPrefix::Testnet,
+        _ => NetworkPrefix::Mainnet,
+    }
+}
+
+fn index_policy() -> IndexPolicy {
+    match ERGO_NODE_INDEX_POLICY.as_deref() {
+        Some("degrade") => IndexPolicy::Degrade,
+        _ => IndexPolicy::Require,
+    }
+}
+
+#[cfg(feature = "server")]
+fn spawn_divergence_tracker(
+    node: &NodeClient,
+) -> Result<Option<Arc<ArcSwap<watcher::DivergenceReport>>>, AppError> {
+    use hergmes::env::ERGO_MIRROR_NODE_URLS;
+
+    if ERGO_MIRROR_NODE_URLS.is_empty() {
+        return Ok(None);
+    }
+
+    let mut nodes = vec![(env::ergo_node_url()?, node.clone())];
+    for url in ERGO_MIRROR_NODE_URLS.iter() {
+        let mirror = node_builder(url)?
+            .build()
+            .config_context(format!("Invalid settings for mirror node `{url}`"))?;
+        nodes.push((url.clone(), mirror));
+    }
+    let (report, _) = watcher::spawn_divergence(nodes, Duration::from_secs(5));
+
+    Ok(Some(report))
+}
+
+fn load_tokens() -> Result<TokenRegistry, AppError> {
+    TokenRegistry::with_overrides(ERGO_TOKENS_FILE.as_deref().map(std::path::Path::new))
+        .config_context("Failed to load token metadata")
+}
+
+fn load_alert_rules() -> Result<Option<AlertEngine>, AppError> {
+    let Some(path) = ERGO_ALERT_RULES_FILE.as_deref() else {
+        return Ok(None);
+    };
+    let json = std::fs::read_to_string(path)
+        .config_context(format!("Failed to read alert rules `{path}`"))?;
+    let engine =
+        AlertEngine::from_json(&json).config_context(format!("Invalid alert rules `{path}`"))?;
+    Ok(Some(engine.with_tokens(Arc::new(load_tokens()?))))
+}
+
+/// Evaluates the alert rules in the background, posting alerts to the webhook if one is set
+/// and logging them otherwise.
+fn spawn_alerts(
+    node: &NodeClient,
+    watch: &watcher::MempoolWatch,
+    rolling: Option<Arc<ArcSwap<RollingReport>>>,
+    engine: AlertEngine,
+    shutdown: &ShutdownCoordinator,
+) -> Result<(), AppError> {
+    let notifier: Arc<dyn Notifier> = match ERGO_ALERT_WEBHOOK_URL.as_deref() {
+        Some(url) => {
+            let (base, path) = url.rsplit_once('/').ok_or_else(|| {
+                AppError::config("Invalid alert webhook", format!("`{url}` is not a URL"))
+            })?;
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .config_context("Invalid alert webhook client")?;
+            Arc::new(WebhookNotifier::new(ReqwestTransport::new(client, base), path))
+        }
+        None => Arc::new(LogNotifier),
+    };
+    let signal = shutdown.register(Stage::Sinks, "alert rules");
+    alerts::spawn_alerts(node.clone(), watch.snapshot.clone(), rolling, engine, notifier, signal);
+    Ok(())
+}
+
+/// Adds the transactions relayed by the peers of `ERGO_P2P_PEERS` to the mempool snapshot
+/// between the node's updates.
+#[cfg(feature = "p2p")]
+fn spawn_p2p_relay(
+    node: &NodeClient,
+    watch: &watcher::MempoolWatch,
+    shutdown: &ShutdownCoordinator,
+) -> Result<(), AppError> {
+    use hergmes::{
+        env::ERGO_P2P_PEERS,
+        p2p::{self, P2pConfig},
+    };
+
+    if ERGO_P2P_PEERS.is_empty() {
+        return Ok(());
+    }
+
+    let peers = ERGO_P2P_PEERS
+        .iter()
+        .map(|p| {
+            p.parse()
+                .config_context(format!("Invalid peer address `{p}`"))
+        })
+        .collect::<Result<_, _>>()?;
+    let events = p2p::spawn(P2pConfig::new(peers));
+    let relay = watcher::spawn_relay(node.clone(), watch.snapshot.clone(), events);
+    shutdown.abort(Stage::Intake, "P2P relay", relay.abort_handle());
+    Ok(())
+}
+
+#[cfg(feature = "server")]
+fn spawn_order_book(
+    node: &NodeClient,
+    network: NetworkPrefix,
+) -> Result<Option<Arc<std::sync::RwLock<hergmes::analytics::orderbook::OrderBook>>>, AppError> {
+    use hergmes::{
+        analytics::orderbook::{self, OrderBook, SaleContract},
+        env::ERGO_SALE_CONTRACTS_FILE,
+    };
+
+    let Some(path) = ERGO_SALE_CONTRACTS_FILE.as_deref() else {
+        return Ok(None);
+    };
+    let json = std::fs::read_to_string(path)
+        .config_context(format!("Failed to read sale contracts `{path}`"))?;
+    let contracts: Vec<SaleContract> =
+        serde_json::from_str(&json).config_context(format!("Invalid sale contracts `{path}`"))?;
+
+    let mut book = OrderBook::new(network);
+    contracts.into_iter().for_each(|c| book.register(c));
+    Ok(Some(orderbook::spawn_order_book(node.clone(), book)))
+}
+
+/// Keeps the prices of the configured oracle and AMM pools up to date, if any.
+#[cfg(feature = "server")]
+fn spawn_prices(
+    node: &NodeClient,
+) -> Result<Option<Arc<ArcSwap<hergmes::analytics::portfolio::Prices>>>, AppError> {
+    use hergmes::{
+        analytics::portfolio::{self, PriceFeeds},
+        env::{ERGO_AMM_POOL_NFTS, ERGO_USD_ORACLE_POOL_NFT},
+    };
+
+    let parse = |nft: &str| {
+        nft.parse()
+            .config_context(format!("Invalid pool NFT `{nft}`"))
+    };
+    let feeds = PriceFeeds {
+        erg_usd_oracle: ERGO_USD_ORACLE_POOL_NFT.as_deref().map(parse).transpose()?,
+        amm_pools: ERGO_AMM_POOL_NFTS
+            .iter()
+            .map(|nft| parse(nft))
+            .collect::<Result<_, _>>()?,
+    };
+    if feeds.erg_usd_oracle.is_none() && feeds.amm_pools.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(portfolio::spawn_prices(node.clone(), feeds)))
+}
+
+#[cfg(feature = "server")]
+async fn serve(
+    node: NodeClient,
+    addr: &str,
+    divergence: Option<Arc<ArcSwap<watcher::DivergenceReport>>>,
+    alerts: Option<AlertEngine>,
+    params_task: JoinHandle<Result<(), AppError>>,
+    shutdown: ShutdownCoordinator,
+) -> Result<(), AppError> {
+    use hergmes::{
+        analytics::{
+            dapps::{self, DappCounters},
+            miners::{self, MinerTracker},
+            voting::{self, VotingSettings, VotingTracker},
+        },
+        env::{ERGO_LABELS_FILE, ERGO_TEMPLATES_FILE, SERVER_TENANTS_FILE},
+        labels::LabelSet,
+        server::{
+            self, ServerState,
+            tenants::{self, Tenants},
+        },
+        templates::TemplateRegistry,
+    };
+
+    let addr = addr
+        .parse()
+        .config_context(format!("Invalid listen address `{addr}`"))?;
+    let network = network();
+
+    let labels = LabelSet::with_overrides(ERGO_LABELS_FILE.as_deref().map(std::path::Path::new))
+        .config_context("Failed to load labels")?;
+    let templates = Arc::new(
+        TemplateRegistry::with_overrides(ERGO_TEMPLATES_FILE.as_deref().map(std::path::Path::new))
+            .config_context("Failed to load contract templates")?,
+    );
+
+    let watch = watcher::spawn_mempool(node.clone(), watcher::DEFAULT_HISTORY_CAPACITY);
+    shutdown.abort(Stage::Intake, "mempool watcher", watch.handle.abort_handle());
+    #[cfg(feature = "p2p")]
+    spawn_p2p_relay(&node, &watch, &shutdown)?;
+    #[cfg(feature = "proofs")]
+    watcher::spawn_proof_verifier(watch.snapshot.clone());
+    // Fee rules read the rolling aggregates, so they are kept even if not configured.
+    let rolling = env::ergo_rolling_window_secs()?
+        .map(|secs| Duration::from_secs(secs as u64))
+        .or_else(|| {
+            alerts
+                .as_ref()
+                .is_some_and(AlertEngine::needs_rolling)
+                .then_some(DEFAULT_ROLLING_WINDOW)
+        })
+        .map(|window| {
+            let window = RollingWindow::new(window).with_templates(templates.clone());
+            rolling::spawn_rolling(watch.snapshot.clone(), window)
+        });
+    if let Some(engine) = alerts {
+        spawn_alerts(&node, &watch, rolling.clone(), engine, &shutdown)?;
+    }
+    let tenants = match SERVER_TENANTS_FILE.as_deref() {
+        Some(path) => {
+            let json = std::fs::read_to_string(path)
+                .config_context(format!("Failed to read tenants `{path}`"))?;
+            let tenants =
+                Tenants::from_json(&json).config_context(format!("Invalid tenants `{path}`"))?;
+            Some(Arc::new(tenants))
+        }
+        None => None,
+    };
+    if let Some(tenants) = &tenants {
+        let client = tenants::webhook_client().config_context("Invalid tenant webhook client")?;
+        let connect =
+            move |base: &str| Arc::new(ReqwestTransport::new(client.clone(), base)) as Arc<_>;
+        tenants::spawn_webhooks(tenants.clone(), watch.snapshot.clone(), connect, &shutdown);
+    }
+    let state = ServerState {
+        node: node.clone(),
+        mempool: watch.snapshot.clone(),
+        network,
+        divergence,
+        labels: Arc::new(labels),
+        dapps: ERGO_TEMPLATES_FILE.is_some().then(|| {
+            dapps::spawn_dapp_counters(node.clone(), DappCounters::new(templates.clone()))
+        }),
+        templates,
+        tokens: Arc::new(load_tokens()?),
+        prices: spawn_prices(&node)?,
+        order_book: spawn_order_book(&node, network)?,
+        miners: env::ergo_miner_window()?.map(|window| {
+            let tracker = MinerTracker::new(network).with_window(window);
+            miners::spawn_miner_tracker(node.clone(), tracker)
+        }),
+        voting: env::ergo_voting_epochs()?.map(|epochs| {
+            let tracker =
+                VotingTracker::new(VotingSettings::for_network(network)).with_epochs(epochs);
+            voting::spawn_voting(node.clone(), tracker)
+        }),
+        rolling,
+        tenants,
+    };
+    let signal = shutdown.register(Stage::Intake, "server");
+    let server = async move {
+        server::serve(addr, state, signal)
+            .await
+            .server_context(format!("Server on `{addr}` stopped"))
+    };
+    // A dedicated runtime keeps request handling responsive while scanners are busy.
+    let server_task = match env::server_worker_threads()? {
+        Some(threads) => {
+            let runtime = runtime("hergmes-server", Some(threads))?;
+            let (done, stopped) = tokio::sync::oneshot::channel();
+            std::thread::Builder::new()
+                .name("hergmes-server".to_string())
+                .spawn(move || done.send(runtime.block_on(server)))?;
+            tokio::spawn(async move {
+                // The sender is only dropped without a result if the server thread panicked.
+                stopped.await.unwrap_or_else(|_| {
+                    Err(AppError::TaskFailed {
+                        task: "server",
+                        failure: "server thread panicked".to_string(),
+                    })
+                })
+            })
+        }
+        None => tokio::spawn(server),
+    };
+
+    until_stopped([watch.handle, params_task, server_task], shutdown).await
+}
@@ -0,0 +1,46 @@
+use super::P2pError;
+use crate::hash::blake2b256;
+
+/// Size of the `magic + code + length` prefix of every framed message.
+pub const HEADER_LEN: usize = 9;
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Encodes a message as `magic | code | length (BE i32) | checksum | body`.
+///
+/// The checksum (first 4 bytes of blake2b256 of the body) is omitted for empty bodies.
+pub fn encode_frame(magic: [u8; 4], code: u8, body: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + CHECKSUM_LEN + body.len());
+    frame.extend_from_slice(&magic);
+    frame.push(code);
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    if !body.is_empty() {
+        frame.extend_from_slice(&blake2b256(body)[..CHECKSUM_LEN]);
+        frame.extend_from_slice(body);
+    }
+    frame
+}
+
+/// Parses a frame header, returning the message code and body length.
+pub fn decode_header(
+    magic: [u8; 4],
+    header: &[u8; HEADER_LEN],
+    max_len: usize,
+) -> Result<(u8, usize), P2pError> {
+    if header[..4] != magic {
+        return Err(P2pError::InvalidMagic(header[..4].try_into().unwrap()));
+    }
+
+    let len = u32::from_be_bytes(header[5..9].try_into().unwrap()) as usize;
+    if len > max_len {
+        return Err(P2pError::MessageTooLarge(len));
+    }
+
+    Ok((header[4], len))
+}
+
+pub fn verify_checksum(checksum: &[u8; CHECKSUM_LEN], body: &[u8]) -> Result<(), P2pError> {
+    if blake2b256(body)[..CHECKSUM_LEN] != checksum[..] {
+        return Err(P2pError::ChecksumMismatch);
+    }
+    Ok(())
+}
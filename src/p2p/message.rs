@@ -0,0 +1,178 @@
+use std::net::{IpAddr, SocketAddr};
+
+use super::P2pError;
+use crate::{
+    codec::{CodecError, Reader, Writer},
+    types::{Digest, HashDigest, TimestampMillis},
+};
+
+pub const GET_PEERS: u8 = 1;
+pub const PEERS: u8 = 2;
+pub const REQUEST_MODIFIER: u8 = 22;
+pub const MODIFIER: u8 = 33;
+pub const INV: u8 = 55;
+pub const SYNC_INFO: u8 = 65;
+pub const HANDSHAKE: u8 = 75;
+
+/// Modifier type id of unconfirmed transactions in `Inv`/`Modifier` messages.
+pub const TRANSACTION_TYPE_ID: u8 = 2;
+
+const MODE_FEATURE_ID: u8 = 16;
+const DIGEST_STATE_TYPE: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version(pub u8, pub u8, pub u8);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handshake {
+    pub timestamp: TimestampMillis,
+    pub agent_name: String,
+    pub version: Version,
+    pub node_name: String,
+    pub declared_address: Option<SocketAddr>,
+    /// Raw `(feature id, payload)` pairs; only used to advertise our own mode.
+    pub features: Vec<(u8, Vec<u8>)>,
+}
+
+impl Handshake {
+    /// A handshake advertising a digest-state, non-verifying node that doesn't store blocks.
+    pub fn light(agent_name: &str, node_name: &str, timestamp: TimestampMillis) -> Self {
+        let mut mode = Writer::new();
+        mode.put_u8(DIGEST_STATE_TYPE)
+            .put_u8(0)
+            .put_option(None::<i32>, |_, _| {})
+            .put_int(-1);
+
+        Self {
+            timestamp,
+            agent_name: agent_name.to_string(),
+            version: Version(5, 0, 12),
+            node_name: node_name.to_string(),
+            declared_address: None,
+            features: vec![(MODE_FEATURE_ID, mode.into_bytes())],
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.put_ulong(self.timestamp.0)
+            .put_short_string(&self.agent_name)
+            .put_bytes(&[self.version.0, self.version.1, self.version.2])
+            .put_short_string(&self.node_name)
+            .put_option(self.declared_address, |w, addr| {
+                let ip = match addr.ip() {
+                    IpAddr::V4(ip) => ip.octets().to_vec(),
+                    IpAddr::V6(ip) => ip.octets().to_vec(),
+                };
+                w.put_u8(ip.len() as u8 + 4)
+                    .put_bytes(&ip)
+                    .put_uint(addr.port() as u32);
+            })
+            .put_u8(self.features.len() as u8);
+
+        for (id, payload) in &self.features {
+            w.put_u8(*id)
+                .put_uint(payload.len() as u32)
+                .put_bytes(payload);
+        }
+
+        w.into_bytes()
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, P2pError> {
+        let mut r = Reader::new(bytes);
+        let timestamp = TimestampMillis(r.get_ulong()?);
+        let agent_name = r.get_short_string()?;
+        let [major, minor, patch] = r.get_array()?;
+        let node_name = r.get_short_string()?;
+        let declared_address = r.get_option(|r| {
+            let len = r.get_u8()? as usize;
+            let ip: IpAddr = match len.checked_sub(4) {
+                Some(4) => <[u8; 4]>::try_from(r.get_bytes(4)?).unwrap().into(),
+                Some(16) => <[u8; 16]>::try_from(r.get_bytes(16)?).unwrap().into(),
+                _ => return Err(CodecError("invalid declared address length")),
+            };
+            let port = r.get_uint()? as u16;
+            Ok(SocketAddr::new(ip, port))
+        })?;
+
+        let mut features = Vec::new();
+        if r.remaining() > 0 {
+            for _ in 0..r.get_u8()? {
+                let id = r.get_u8()?;
+                let len = r.get_uint()? as usize;
+                features.push((id, r.get_bytes(len)?.to_vec()));
+            }
+        }
+
+        Ok(Self {
+            timestamp,
+            agent_name,
+            version: Version(major, minor, patch),
+            node_name,
+            declared_address,
+            features,
+        })
+    }
+}
+
+/// Body of `Inv` and `RequestModifier` messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvData {
+    pub type_id: u8,
+    pub ids: Vec<HashDigest>,
+}
+
+impl InvData {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.put_u8(self.type_id).put_uint(self.ids.len() as u32);
+        for id in &self.ids {
+            w.put_bytes(&id.0);
+        }
+        w.into_bytes()
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, P2pError> {
+        let mut r = Reader::new(bytes);
+        let type_id = r.get_u8()?;
+        let count = r.get_uint()? as usize;
+        let ids = (0..count)
+            .map(|_| r.get_array().map(Digest))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { type_id, ids })
+    }
+}
+
+/// Body of `Modifier` messages: serialized modifiers keyed by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModifiersData {
+    pub type_id: u8,
+    pub modifiers: Vec<(HashDigest, Vec<u8>)>,
+}
+
+impl ModifiersData {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.put_u8(self.type_id).put_uint(self.modifiers.len() as u32);
+        for (id, bytes) in &self.modifiers {
+            w.put_bytes(&id.0)
+                .put_uint(bytes.len() as u32)
+                .put_bytes(bytes);
+        }
+        w.into_bytes()
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, P2pError> {
+        let mut r = Reader::new(bytes);
+        let type_id = r.get_u8()?;
+        let count = r.get_uint()? as usize;
+        let mut modifiers = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let id = Digest(r.get_array()?);
+            let len = r.get_uint()? as usize;
+            modifiers.push((id, r.get_bytes(len)?.to_vec()));
+        }
+        Ok(Self { type_id, modifiers })
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{sync::mpsc, time::sleep};
+use tracing::{error, info};
+
+use crate::{codec::CodecError, types::HashDigest};
+pub use message::{Handshake, InvData, ModifiersData, Version};
+pub use origin::{OriginTracker, PeerTiming, TxPropagation};
+pub use peer::PeerConnection;
+
+pub mod codec;
+pub mod message;
+mod origin;
+mod peer;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum P2pError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid network magic {0:?}.")]
+    InvalidMagic([u8; 4]),
+
+    #[error("Message checksum mismatch.")]
+    ChecksumMismatch,
+
+    #[error("Message of {0} bytes exceeds the size limit.")]
+    MessageTooLarge(usize),
+
+    #[error("Unexpected message code {0}.")]
+    UnexpectedMessage(u8),
+
+    #[error("Failed to decode message: {0}")]
+    Codec(#[from] CodecError),
+
+    #[error("Peer timed out.")]
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn magic(&self) -> [u8; 4] {
+        match self {
+            Network::Mainnet => [1, 0, 2, 4],
+            Network::Testnet => [2, 0, 2, 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct P2pConfig {
+    pub peers: Vec<SocketAddr>,
+    pub network: Network,
+    pub agent_name: String,
+    pub node_name: String,
+    pub connect_timeout: Duration,
+    pub max_message_size: usize,
+}
+
+impl P2pConfig {
+    pub fn new(peers: Vec<SocketAddr>) -> Self {
+        Self {
+            peers,
+            network: Network::default(),
+            agent_name: "hergmes".to_string(),
+            node_name: "hergmes-listener".to_string(),
+            connect_timeout: Duration::from_secs(10),
+            max_message_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum P2pEvent {
+    /// `peer` announced the given transaction ids at `at`.
+    Announcement { peer: SocketAddr, ids: Vec<HashDigest>, at: SystemTime },
+    /// A serialized unconfirmed transaction received from `peer`.
+    Transaction { peer: SocketAddr, id: HashDigest, bytes: Vec<u8> },
+}
+
+/// Connects to every configured peer and streams the transactions they relay.
+///
+/// Each peer runs in its own task and is reconnected after failures. The same transaction is
+/// usually announced by several peers, so consumers should deduplicate by id.
+/// [`watcher::spawn_relay`](crate::watcher::spawn_relay) merges their transactions into the
+/// mempool snapshot.
+pub fn spawn(config: P2pConfig) -> mpsc::Receiver<P2pEvent> {
+    let (tx, rx) = mpsc::channel(1024);
+
+    for addr in config.peers.clone() {
+        let config = config.clone();
+        let events = tx.clone();
+        tokio::spawn(async move {
+            while !events.is_closed() {
+                match PeerConnection::connect(addr, &config).await {
+                    Ok(conn) => match conn.run(events.clone()).await {
+                        Ok(()) => return,
+                        Err(e) => error!(%addr, "Peer connection lost: {:?}", e),
+                    },
+                    Err(e) => error!(%addr, "Failed to connect to peer: {:?}", e),
+                }
+
+                info!(%addr, "Reconnecting in {:?}...", RECONNECT_DELAY);
+                sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    rx
+}
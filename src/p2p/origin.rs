@@ -0,0 +1,118 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+
+use super::P2pEvent;
+use crate::types::HashDigest;
+
+/// How a single transaction propagated to us across peers.
+#[derive(Debug, Clone)]
+pub struct TxPropagation {
+    pub first_peer: SocketAddr,
+    pub first_seen: SystemTime,
+    /// Later announcements with their delay relative to `first_seen`, in arrival order.
+    pub later: Vec<(SocketAddr, Duration)>,
+}
+
+/// Aggregated announcement timing for a single peer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PeerTiming {
+    /// Transactions this peer announced before any other peer.
+    pub first: u64,
+    /// Transactions this peer announced after another peer already did.
+    pub later: u64,
+    /// Sum of delays behind the first announcer, over `later` announcements.
+    pub total_delay: Duration,
+}
+
+impl PeerTiming {
+    pub fn average_delay(&self) -> Option<Duration> {
+        (self.later > 0).then(|| self.total_delay / self.later as u32)
+    }
+}
+
+/// Records which peer announced each transaction first and how long the others lagged behind.
+///
+/// Only the most recent `capacity` transactions are kept; older entries are evicted in
+/// first-seen order. Per-peer timings are cumulative.
+#[derive(Debug)]
+pub struct OriginTracker {
+    capacity: usize,
+    transactions: HashMap<HashDigest, TxPropagation>,
+    order: VecDeque<HashDigest>,
+    peers: HashMap<SocketAddr, PeerTiming>,
+}
+
+impl OriginTracker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            transactions: HashMap::new(),
+            order: VecDeque::new(),
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Records announcements carried by `event`; other events are ignored.
+    pub fn observe(&mut self, event: &P2pEvent) {
+        if let P2pEvent::Announcement { peer, ids, at } = event {
+            for id in ids {
+                self.record(*peer, id, *at);
+            }
+        }
+    }
+
+    /// Records that `peer` announced `id` at `at`. Returns `true` if it was the first announcer.
+    pub fn record(&mut self, peer: SocketAddr, id: &HashDigest, at: SystemTime) -> bool {
+        let timing = self.peers.entry(peer).or_default();
+
+        if let Some(propagation) = self.transactions.get_mut(id) {
+            if propagation.first_peer == peer || propagation.later.iter().any(|(p, _)| *p == peer) {
+                return false;
+            }
+
+            let delay = at
+                .duration_since(propagation.first_seen)
+                .unwrap_or_default();
+            propagation.later.push((peer, delay));
+            timing.later += 1;
+            timing.total_delay += delay;
+            return false;
+        }
+
+        timing.first += 1;
+        self.transactions
+            .insert(id.clone(), TxPropagation { first_peer: peer, first_seen: at, later: vec![] });
+        self.order.push_back(id.clone());
+
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.transactions.remove(&evicted);
+            }
+        }
+
+        true
+    }
+
+    pub fn get(&self, id: &HashDigest) -> Option<&TxPropagation> {
+        self.transactions.get(id)
+    }
+
+    pub fn first_peer(&self, id: &HashDigest) -> Option<SocketAddr> {
+        self.get(id).map(|p| p.first_peer)
+    }
+
+    pub fn peer_timings(&self) -> &HashMap<SocketAddr, PeerTiming> {
+        &self.peers
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::mpsc,
+    time::timeout,
+};
+use tracing::{debug, info, trace};
+
+use super::{
+    P2pConfig, P2pError, P2pEvent,
+    codec::{self, CHECKSUM_LEN, HEADER_LEN},
+    message::{self, Handshake, InvData, ModifiersData, TRANSACTION_TYPE_ID},
+};
+use crate::types::TimestampMillis;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A single established peer connection.
+pub struct PeerConnection {
+    addr: SocketAddr,
+    magic: [u8; 4],
+    max_message_size: usize,
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl PeerConnection {
+    #[tracing::instrument(skip(config))]
+    pub async fn connect(addr: SocketAddr, config: &P2pConfig) -> Result<Self, P2pError> {
+        let stream = timeout(config.connect_timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| P2pError::Timeout)??;
+        let (reader, writer) = stream.into_split();
+        let mut conn = Self {
+            addr,
+            magic: config.network.magic(),
+            max_message_size: config.max_message_size,
+            reader: BufReader::new(reader),
+            writer,
+        };
+
+        let ours = Handshake::light(&config.agent_name, &config.node_name, TimestampMillis::now());
+        conn.send(message::HANDSHAKE, &ours.serialize()).await?;
+
+        let (code, body) = timeout(HANDSHAKE_TIMEOUT, conn.receive())
+            .await
+            .map_err(|_| P2pError::Timeout)??;
+        if code != message::HANDSHAKE {
+            return Err(P2pError::UnexpectedMessage(code));
+        }
+
+        let theirs = Handshake::parse(&body)?;
+        info!(agent = %theirs.agent_name, version = ?theirs.version, "Handshake completed.");
+
+        Ok(conn)
+    }
+
+    /// Runs the message loop, requesting every announced transaction and forwarding it as an
+    /// event until the connection fails or the receiver is dropped.
+    #[tracing::instrument(skip(self, events), fields(peer = %self.addr))]
+    pub async fn run(mut self, events: mpsc::Sender<P2pEvent>) -> Result<(), P2pError> {
+        loop {
+            let (code, body) = self.receive().await?;
+            match code {
+                message::INV => {
+                    let at = SystemTime::now();
+                    let inv = InvData::parse(&body)?;
+                    if inv.type_id != TRANSACTION_TYPE_ID || inv.ids.is_empty() {
+                        continue;
+                    }
+
+                    trace!(count = inv.ids.len(), "Transactions announced, requesting.");
+                    self.send(message::REQUEST_MODIFIER, &inv.serialize())
+                        .await?;
+
+                    let event = P2pEvent::Announcement { peer: self.addr, ids: inv.ids, at };
+                    if events.send(event).await.is_err() {
+                        return Ok(());
+                    }
+                }
+                message::MODIFIER => {
+                    let modifiers = ModifiersData::parse(&body)?;
+                    if modifiers.type_id != TRANSACTION_TYPE_ID {
+                        continue;
+                    }
+
+                    for (id, bytes) in modifiers.modifiers {
+                        let event = P2pEvent::Transaction { peer: self.addr, id, bytes };
+                        if events.send(event).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                message::GET_PEERS => {
+                    // We don't relay peers; an empty list keeps the remote side satisfied.
+                    self.send(message::PEERS, &[0]).await?;
+                }
+                message::SYNC_INFO | message::PEERS => {}
+                other => debug!(code = other, "Ignoring unsupported message."),
+            }
+        }
+    }
+
+    async fn send(&mut self, code: u8, body: &[u8]) -> Result<(), P2pError> {
+        let frame = codec::encode_frame(self.magic, code, body);
+        self.writer.write_all(&frame).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<(u8, Vec<u8>), P2pError> {
+        let mut header = [0u8; HEADER_LEN];
+        self.reader.read_exact(&mut header).await?;
+        let (code, len) = codec::decode_header(self.magic, &header, self.max_message_size)?;
+        if len == 0 {
+            return Ok((code, Vec::new()));
+        }
+
+        let mut checksum = [0u8; CHECKSUM_LEN];
+        self.reader.read_exact(&mut checksum).await?;
+        let mut body = vec![0u8; len];
+        self.reader.read_exact(&mut body).await?;
+        codec::verify_checksum(&checksum, &body)?;
+
+        Ok((code, body))
+    }
+}
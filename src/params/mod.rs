@@ -0,0 +1,65 @@
+#[cfg(feature = "runtime")]
+pub use tracker::{spawn, start};
+
+use crate::{
+    chain::extension::{ParameterId, Parameters},
+    types::Height,
+};
+
+#[cfg(feature = "runtime")]
+mod tracker;
+
+/// Consensus parameters used by fee and dust calculations.
+///
+/// Defaults to the mainnet launch values until the tracker has fetched the current epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkParameters {
+    /// Height of the epoch-start block that declared these values.
+    pub epoch_height: Height,
+    /// Storage rent charged per byte of a box, in nanoERG.
+    pub storage_fee_factor: u64,
+    /// Minimum value per byte of a box, in nanoERG.
+    pub min_value_per_byte: u64,
+    pub max_block_size: u64,
+    pub max_block_cost: u64,
+}
+
+impl Default for NetworkParameters {
+    fn default() -> Self {
+        Self {
+            epoch_height: Height(0),
+            storage_fee_factor: 1_250_000,
+            min_value_per_byte: 360,
+            max_block_size: 524_288,
+            max_block_cost: 1_000_000,
+        }
+    }
+}
+
+impl NetworkParameters {
+    /// Applies the values declared in an epoch-start extension; missing ones are kept.
+    pub fn with_declared(mut self, epoch_height: Height, declared: &Parameters) -> Self {
+        let get = |id, current| declared.get(id).map(non_negative).unwrap_or(current);
+
+        self.epoch_height = epoch_height;
+        self.storage_fee_factor = get(ParameterId::StorageFeeFactor, self.storage_fee_factor);
+        self.min_value_per_byte = get(ParameterId::MinValuePerByte, self.min_value_per_byte);
+        self.max_block_size = get(ParameterId::MaxBlockSize, self.max_block_size);
+        self.max_block_cost = get(ParameterId::MaxBlockCost, self.max_block_cost);
+        self
+    }
+
+    /// Smallest value a box of `box_size` serialized bytes may hold.
+    pub fn min_box_value(&self, box_size: usize) -> u64 {
+        box_size as u64 * self.min_value_per_byte
+    }
+
+    /// Storage rent that may be claimed from a box of `box_size` bytes once per rent period.
+    pub fn storage_fee(&self, box_size: usize) -> u64 {
+        box_size as u64 * self.storage_fee_factor
+    }
+}
+
+fn non_negative(value: i32) -> u64 {
+    value.max(0) as u64
+}
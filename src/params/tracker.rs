@@ -0,0 +1,89 @@
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::info;
+
+use super::{NetworkParameters, non_negative};
+use crate::{
+    chain::extension,
+    clients::node::{InfoParameters, NodeClient, NodeError},
+    error::AppError,
+    supervisor::{RestartPolicy, supervise},
+    trace::ErrorLog,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+impl From<&InfoParameters> for NetworkParameters {
+    fn from(params: &InfoParameters) -> Self {
+        Self {
+            epoch_height: params.height,
+            storage_fee_factor: non_negative(params.storage_fee_factor),
+            min_value_per_byte: non_negative(params.min_value_per_byte),
+            max_block_size: non_negative(params.max_block_size),
+            max_block_cost: non_negative(params.max_block_cost),
+        }
+    }
+}
+
+/// Starts tracking network parameters in the background, restarting on panics, and returns
+/// the shared snapshot with the supervisor's handle.
+pub fn spawn(
+    node: NodeClient,
+) -> (Arc<ArcSwap<NetworkParameters>>, JoinHandle<Result<(), AppError>>) {
+    let params = Arc::new(ArcSwap::from_pointee(NetworkParameters::default()));
+    let cloned_params = params.clone();
+
+    let handle = supervise("network parameters", RestartPolicy::default(), move || {
+        let (node, params) = (node.clone(), cloned_params.clone());
+        async move { start(&node, params).await }
+    });
+
+    (params, handle)
+}
+
+#[tracing::instrument(skip(node, swap))]
+pub async fn start(
+    node: &NodeClient,
+    swap: Arc<ArcSwap<NetworkParameters>>,
+) -> Result<(), AppError> {
+    info!("Starting network parameters tracker...");
+
+    let mut errors = ErrorLog::new("network parameters tracker");
+    loop {
+        match fetch(node, &swap.load()).await {
+            Ok(Some(params)) => {
+                info!(?params, "Network parameters updated.");
+                swap.store(Arc::new(params));
+                errors.success();
+            }
+            Ok(None) => errors.success(),
+            Err(e) => errors.failure(&e),
+        }
+
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Reads parameters from `/info`, falling back to the epoch-start extension for nodes that
+/// don't report them. Returns `None` when nothing changed.
+async fn fetch(
+    node: &NodeClient,
+    current: &NetworkParameters,
+) -> Result<Option<NetworkParameters>, NodeError> {
+    let info = node.get_info().await?;
+    if let Some(params) = &info.parameters {
+        let params = NetworkParameters::from(params);
+        return Ok((params != *current).then_some(params));
+    }
+
+    let Some(height) = info.full_height else { return Ok(None) };
+    let epoch_height = extension::epoch_start(height);
+    if epoch_height == current.epoch_height {
+        return Ok(None);
+    }
+
+    let declared = node.get_epoch_parameters(height).await?;
+    Ok(Some(current.clone().with_declared(epoch_height, &declared)))
+}
@@ -0,0 +1,54 @@
+use axum::{Json, Router, response::Html, routing::get};
+use utoipa::OpenApi;
+
+/// Swagger UI loading the spec from `/docs/openapi.json`.
+const SWAGGER_UI: &str = r##"<!doctype html>
+<html>
+  <head>
+    <title>hergmes API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>SwaggerUIBundle({ url: "/docs/openapi.json", dom_id: "#swagger-ui" });</script>
+  </body>
+</html>
+"##;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "hergmes"),
+    paths(
+        super::health,
+        super::mempool,
+        super::mempool_treemap,
+        super::metrics,
+        super::miners,
+        super::portfolio,
+        super::rolling,
+        super::tenants::tenant_mempool,
+        super::tenants::watchlists,
+        super::tenants::set_watchlist,
+        super::tenants::remove_watchlist,
+        super::tenants::set_webhook,
+        super::tenants::remove_webhook
+    )
+)]
+struct ApiDoc;
+
+/// The OpenAPI spec of all endpoints enabled in this build.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut doc = ApiDoc::openapi();
+    #[cfg(feature = "graphql")]
+    doc.merge(super::graphql::ApiDoc::openapi());
+    doc
+}
+
+pub fn router() -> Router {
+    let spec = openapi();
+    Router::new()
+        .route("/docs", get(|| async { Html(SWAGGER_UI) }))
+        .route("/docs/openapi.json", get(move || async move { Json(spec) }))
+}
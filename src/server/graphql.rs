@@ -0,0 +1,386 @@
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Object, Result, Schema, SimpleObject,
+};
+use axum::{Json, Router, extract::State, routing::post};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    address::ErgoAddress,
+    analytics::orderbook::{Order, OrderKind},
+    clients::node::{BoxQuery, DEFAULT_BOX_QUERY_LIMIT},
+    filter::Filter,
+    server::ServerState,
+    types::{
+        HashDigest,
+        ergo::{IndexedBox, IndexedTransaction, TokenInfo, UTxO, UnconfirmedTransaction},
+    },
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// Most boxes a single query returns, matching the indexer's default page.
+const MAX_PAGE_SIZE: u32 = DEFAULT_BOX_QUERY_LIMIT;
+
+pub type ErgoSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema(state: ServerState) -> ErgoSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .finish()
+}
+
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/graphql", post(execute))
+        .with_state(schema(state))
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(execute))]
+pub(super) struct ApiDoc;
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlRequest {
+    query: String,
+    #[schema(value_type = Option<Object>)]
+    variables: Option<serde_json::Value>,
+    operation_name: Option<String>,
+}
+
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct GraphqlResponse {
+    #[schema(value_type = Object)]
+    data: serde_json::Value,
+    #[schema(value_type = Option<Vec<Object>>)]
+    errors: Option<Vec<serde_json::Value>>,
+}
+
+/// Executes a GraphQL query over boxes, transactions, tokens and the mempool.
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    request_body = GraphqlRequest,
+    responses((status = 200, body = GraphqlResponse))
+)]
+async fn execute(
+    State(schema): State<ErgoSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Unconfirmed transactions, optionally only those paying `address` and with an output
+    /// matching the `filter` expression.
+    async fn mempool(
+        &self,
+        ctx: &Context<'_>,
+        address: Option<String>,
+        filter: Option<String>,
+    ) -> Result<Mempool> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let snapshot = state.mempool.load();
+        let mut transactions = match address {
+            Some(address) => snapshot.transactions_for_address(&address.parse()?),
+            None => snapshot.transactions.iter().collect(),
+        };
+        if let Some(filter) = filter {
+            let filter: Filter = filter.parse()?;
+            transactions.retain(|tx| filter.matches_transaction(tx));
+        }
+        Ok(Mempool {
+            last_update: snapshot.last_update.0,
+            total_bytes: snapshot.total_bytes,
+            transactions: transactions
+                .into_iter()
+                .map(|tx| GqlTransaction::unconfirmed(tx, snapshot.size_of(&tx.id), state))
+                .collect(),
+        })
+    }
+
+    #[graphql(name = "box")]
+    async fn indexed_box(&self, ctx: &Context<'_>, box_id: String) -> Result<GqlBox> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let indexed = state.node.get_indexed_box(&box_id.parse()?).await?;
+        Ok(GqlBox::indexed(&indexed, state))
+    }
+
+    /// Boxes ever owned by `address`, at most [`MAX_PAGE_SIZE`] at once. `spent: false` pages
+    /// through the unspent boxes only; `spent: true` keeps the spent boxes of the page, so it
+    /// may hold fewer than `limit`, or none, while `hasMore` is still set.
+    async fn boxes(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        spent: Option<bool>,
+        offset: Option<u64>,
+        limit: Option<u32>,
+    ) -> Result<BoxPage> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let address: ErgoAddress = address.parse()?;
+        let offset = offset.unwrap_or(0);
+        let query = BoxQuery::new()
+            .offset(offset)
+            .limit(limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE));
+        let page = match spent {
+            Some(false) => {
+                state
+                    .node
+                    .get_unspent_boxes_by_address(&address, &query)
+                    .await?
+            }
+            _ => state.node.get_boxes_by_address(&address, &query).await?,
+        };
+        let next_offset = offset + page.items.len() as u64;
+        Ok(BoxPage {
+            items: page
+                .items
+                .iter()
+                .filter(|b| spent.is_none_or(|spent| b.spent_transaction_id.is_some() == spent))
+                .map(|b| GqlBox::indexed(b, state))
+                .collect(),
+            has_more: next_offset < page.total,
+            next_offset,
+        })
+    }
+
+    async fn transaction(
+        &self,
+        ctx: &Context<'_>,
+        transaction_id: String,
+    ) -> Result<GqlTransaction> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let tx = state
+            .node
+            .get_indexed_transaction(&transaction_id.parse()?)
+            .await?;
+        Ok(GqlTransaction::confirmed(&tx, state))
+    }
+
+    /// Open sale and auction orders for a token, cheapest first.
+    async fn orders(&self, ctx: &Context<'_>, token_id: String) -> Result<Vec<GqlOrder>> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let token_id = token_id.parse()?;
+        let Some(book) = &state.order_book else {
+            return Ok(Vec::new());
+        };
+        let book = book.read().unwrap();
+        Ok(book
+            .orders_for_token(&token_id)
+            .into_iter()
+            .map(GqlOrder::from)
+            .collect())
+    }
+
+    async fn token(&self, ctx: &Context<'_>, token_id: String) -> Result<Token> {
+        let state = ctx.data_unchecked::<ServerState>();
+        let token = state.node.get_token(&token_id.parse()?).await?;
+        Ok(Token::from(token))
+    }
+}
+
+/// A page of `boxes`.
+#[derive(SimpleObject)]
+pub struct BoxPage {
+    pub items: Vec<GqlBox>,
+    /// Whether the indexer has boxes past this page.
+    pub has_more: bool,
+    /// `offset` of the next page, past the boxes filtered out of this one.
+    pub next_offset: u64,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Order")]
+pub struct GqlOrder {
+    pub box_id: String,
+    pub contract: String,
+    /// `fixedPrice` or `auction`.
+    pub kind: String,
+    pub seller: String,
+    pub token_id: String,
+    pub token_amount: u64,
+    pub price: u64,
+    pub creation_height: u32,
+}
+
+impl From<&Order> for GqlOrder {
+    fn from(order: &Order) -> Self {
+        Self {
+            box_id: order.box_id.to_string(),
+            contract: order.contract.clone(),
+            kind: match order.kind {
+                OrderKind::FixedPrice => "fixedPrice",
+                OrderKind::Auction => "auction",
+            }
+            .to_string(),
+            seller: order.seller.to_string(),
+            token_id: order.token_id.to_string(),
+            token_amount: order.token_amount,
+            price: order.price,
+            creation_height: order.creation_height.0,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Mempool {
+    pub last_update: u64,
+    /// Serialized size of all unconfirmed transactions.
+    pub total_bytes: u64,
+    pub transactions: Vec<GqlTransaction>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Transaction")]
+pub struct GqlTransaction {
+    pub transaction_id: String,
+    /// `null` for unconfirmed transactions.
+    pub inclusion_height: Option<u32>,
+    /// Serialized size in bytes, for unconfirmed transactions.
+    pub size: Option<u64>,
+    pub inputs: Vec<GqlBox>,
+    pub outputs: Vec<GqlBox>,
+}
+
+impl GqlTransaction {
+    fn unconfirmed(tx: &UnconfirmedTransaction, size: Option<usize>, state: &ServerState) -> Self {
+        Self {
+            transaction_id: tx.id.to_string(),
+            inclusion_height: None,
+            size: size.map(|size| size as u64),
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|i| GqlBox::new(&i.utxo, None, state))
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|o| GqlBox::new(o, None, state))
+                .collect(),
+        }
+    }
+
+    fn confirmed(tx: &IndexedTransaction, state: &ServerState) -> Self {
+        Self {
+            transaction_id: tx.id.to_string(),
+            inclusion_height: Some(tx.inclusion_height.0),
+            size: None,
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|i| GqlBox::new(i, Some(&tx.id), state))
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .map(|o| GqlBox::new(o, None, state))
+                .collect(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Box")]
+pub struct GqlBox {
+    pub box_id: String,
+    pub transaction_id: String,
+    pub index: u16,
+    pub value: u64,
+    pub creation_height: u32,
+    pub ergo_tree: String,
+    pub address: String,
+    pub assets: Vec<Asset>,
+    pub additional_registers: Vec<Register>,
+    pub spent_transaction_id: Option<String>,
+    pub label: Option<AddressLabel>,
+}
+
+impl GqlBox {
+    fn new(utxo: &UTxO, spent_by: Option<&HashDigest>, state: &ServerState) -> Self {
+        let registers = utxo.registers.as_array();
+        Self {
+            box_id: utxo.id.to_string(),
+            transaction_id: utxo.transaction_id.to_string(),
+            index: utxo.index,
+            value: utxo.value,
+            creation_height: utxo.creation_height.0,
+            ergo_tree: utxo.ergo_tree.to_string(),
+            address: ErgoAddress::from_ergo_tree(state.network, &utxo.ergo_tree.0).to_string(),
+            assets: utxo
+                .tokens
+                .iter()
+                .map(|t| Asset { token_id: t.id.to_string(), amount: t.amount })
+                .collect(),
+            additional_registers: registers
+                .iter()
+                .zip(4..)
+                .filter_map(|(value, i)| {
+                    value.map(|v| Register { key: format!("R{i}"), value: v.to_string() })
+                })
+                .collect(),
+            spent_transaction_id: spent_by.map(HashDigest::to_string),
+            label: state
+                .labels
+                .get_by_tree(&utxo.ergo_tree.0)
+                .map(|l| AddressLabel {
+                    name: l.name.clone(),
+                    category: format!("{:?}", l.category).to_lowercase(),
+                    url: l.url.clone(),
+                }),
+        }
+    }
+
+    fn indexed(indexed: &IndexedBox, state: &ServerState) -> Self {
+        Self::new(&indexed.utxo, indexed.spent_transaction_id.as_ref(), state)
+    }
+}
+
+/// Known owner or purpose of a box's address.
+#[derive(SimpleObject)]
+#[graphql(name = "Label")]
+pub struct AddressLabel {
+    pub name: String,
+    pub category: String,
+    pub url: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Asset {
+    pub token_id: String,
+    pub amount: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct Register {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(SimpleObject)]
+pub struct Token {
+    pub token_id: String,
+    pub box_id: String,
+    pub emission_amount: u64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub decimals: Option<u32>,
+}
+
+impl From<TokenInfo> for Token {
+    fn from(token: TokenInfo) -> Self {
+        Self {
+            token_id: token.id.to_string(),
+            box_id: token.box_id.to_string(),
+            emission_amount: token.emission_amount,
+            name: token.name,
+            description: token.description,
+            decimals: token.decimals,
+        }
+    }
+}
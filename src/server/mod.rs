@@ -0,0 +1,258 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+};
+
+use arc_swap::ArcSwap;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::{HeaderName, StatusCode, header::CONTENT_TYPE},
+    routing::get,
+};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::{
+        dapps::DappCounters,
+        miners::{MinerStats, MinerTracker},
+        orderbook::OrderBook,
+        portfolio::{self, Prices, Valuation},
+        rolling::RollingReport,
+        treemap::{TreemapBuilder, TreemapNode},
+        voting::VotingReport,
+    },
+    clients::node::NodeClient,
+    filter::Filter,
+    labels::LabelSet,
+    server::tenants::Tenants,
+    shutdown::ShutdownSignal,
+    templates::TemplateRegistry,
+    tokens::TokenRegistry,
+    types::ergo::UnconfirmedTransaction,
+    watcher::{DivergenceReport, MempoolSnapshot},
+};
+
+pub mod docs;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod tenants;
+
+/// Data shared by all server endpoints.
+#[derive(Clone)]
+pub struct ServerState {
+    pub node: NodeClient,
+    pub mempool: Arc<ArcSwap<MempoolSnapshot>>,
+    pub network: NetworkPrefix,
+    /// Mempool divergence between watched nodes, when more than one node is configured.
+    pub divergence: Option<Arc<ArcSwap<DivergenceReport>>>,
+    pub labels: Arc<LabelSet>,
+    /// Contract templates attributing boxes to dApps.
+    pub templates: Arc<TemplateRegistry>,
+    /// Confirmed transactions and volume by dApp, when contract templates are configured.
+    pub dapps: Option<Arc<RwLock<DappCounters>>>,
+    /// Names and decimals of tokens.
+    pub tokens: Arc<TokenRegistry>,
+    /// Token prices and the ERG/USD rate, when price feeds are configured.
+    pub prices: Option<Arc<ArcSwap<Prices>>>,
+    /// Open sale orders, when sale contracts are configured.
+    pub order_book: Option<Arc<RwLock<OrderBook>>>,
+    /// Miners of the last blocks, when miner attribution is enabled.
+    pub miners: Option<Arc<RwLock<MinerTracker>>>,
+    /// Status of ongoing votes, when vote tallying is enabled.
+    pub voting: Option<Arc<ArcSwap<VotingReport>>>,
+    /// Aggregates of the transactions entering the mempool, when enabled.
+    pub rolling: Option<Arc<ArcSwap<RollingReport>>>,
+    /// API-key scoped namespaces under `/tenant`, when tenants are configured.
+    pub tenants: Option<Arc<Tenants>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    mempool_last_update: u64,
+    mempool_size: usize,
+    /// Serialized size of the unconfirmed transactions.
+    mempool_bytes: u64,
+}
+
+pub fn router(state: ServerState) -> Router {
+    let router = Router::new()
+        .route("/health", get(health))
+        .route("/mempool", get(mempool))
+        .route("/mempool/treemap", get(mempool_treemap))
+        .route("/metrics", get(metrics))
+        .route("/miners", get(miners))
+        .route("/portfolio/{address}", get(portfolio))
+        .route("/rolling", get(rolling))
+        .merge(tenants::router())
+        .with_state(state.clone())
+        .merge(docs::router());
+
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::router(state));
+
+    router
+}
+
+/// Serves until `shutdown` is requested, then waits for the requests in flight.
+pub async fn serve(
+    addr: SocketAddr,
+    state: ServerState,
+    shutdown: ShutdownSignal,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!(%addr, "Server listening.");
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown.requested())
+        .await
+}
+
+/// Status of the mempool indexer.
+#[utoipa::path(get, path = "/health", responses((status = 200, body = Health)))]
+async fn health(State(state): State<ServerState>) -> Json<Health> {
+    let mempool = state.mempool.load();
+    Json(Health {
+        mempool_last_update: mempool.last_update.0,
+        mempool_size: mempool.transactions.len(),
+        mempool_bytes: mempool.total_bytes,
+    })
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct MempoolParams {
+    /// Filter expression, e.g. `address:<address> and min-value:1000000 or token:<id>`.
+    filter: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct Mempool {
+    last_update: u64,
+    #[schema(value_type = Vec<Object>)]
+    transactions: Vec<UnconfirmedTransaction>,
+}
+
+/// Unconfirmed transactions, optionally only those with an output matching `filter`.
+#[utoipa::path(
+    get,
+    path = "/mempool",
+    params(MempoolParams),
+    responses(
+        (status = 200, body = Mempool),
+        (status = 400, description = "Invalid filter", body = String)
+    )
+)]
+async fn mempool(
+    State(state): State<ServerState>,
+    Query(params): Query<MempoolParams>,
+) -> Result<Json<Mempool>, (StatusCode, String)> {
+    let filter = params
+        .filter
+        .map(|f| f.parse::<Filter>())
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let snapshot = state.mempool.load();
+    let transactions = match &filter {
+        Some(filter) => snapshot.matching(filter).into_iter().cloned().collect(),
+        None => snapshot.transactions.to_vec(),
+    };
+    Ok(Json(Mempool { last_update: snapshot.last_update.0, transactions }))
+}
+
+/// Value of the mempool outputs grouped by dApp and contract, or by label, as a
+/// `{ name, value, children }` hierarchy for treemap rendering. Values are in nanoErgs.
+#[utoipa::path(
+    get,
+    path = "/mempool/treemap",
+    responses((status = 200, description = "Pending value by contract, largest first", body = Object))
+)]
+async fn mempool_treemap(State(state): State<ServerState>) -> Json<TreemapNode> {
+    let mut builder = TreemapBuilder::new(&state.templates, &state.labels);
+    builder.add_mempool(&state.mempool.load());
+    Json(builder.build("Mempool"))
+}
+
+/// Blocks, rewards and fees per miner over the last blocks.
+#[utoipa::path(
+    get,
+    path = "/miners",
+    responses(
+        (status = 200, description = "Miners of the window, most blocks first", body = Object),
+        (status = 404, description = "Miner attribution is not enabled")
+    )
+)]
+async fn miners(State(state): State<ServerState>) -> Result<Json<MinerStats>, StatusCode> {
+    let tracker = state.miners.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(tracker.read().unwrap().report()))
+}
+
+/// Confirmed holdings of an address valued in ERG, and in USD with an oracle rate. Tokens
+/// without a price feed are listed unvalued.
+#[utoipa::path(
+    get,
+    path = "/portfolio/{address}",
+    params(("address" = String, Path, description = "Address whose holdings are valued")),
+    responses(
+        (status = 200, description = "Holdings and their value", body = Object),
+        (status = 400, description = "Invalid address", body = String),
+        (status = 502, description = "The node failed to return the balance", body = String)
+    )
+)]
+async fn portfolio(
+    State(state): State<ServerState>,
+    Path(address): Path<String>,
+) -> Result<Json<Valuation>, (StatusCode, String)> {
+    let address: ErgoAddress = address
+        .parse()
+        .map_err(|e: crate::address::AddressError| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let prices = state
+        .prices
+        .as_ref()
+        .map(|prices| prices.load_full())
+        .unwrap_or_default();
+    let valuation = portfolio::value(&state.node, &state.tokens, &prices, &address)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    Ok(Json(valuation))
+}
+
+/// Transaction rate, fees and volume by token over the rolling window.
+#[utoipa::path(
+    get,
+    path = "/rolling",
+    responses(
+        (status = 200, description = "Aggregates of the transactions of the window", body = Object),
+        (status = 404, description = "Rolling aggregates are not enabled")
+    )
+)]
+async fn rolling(State(state): State<ServerState>) -> Result<Json<RollingReport>, StatusCode> {
+    let report = state.rolling.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(RollingReport::clone(&report.load())))
+}
+
+/// Prometheus metrics.
+#[utoipa::path(get, path = "/metrics", responses((status = 200, content_type = "text/plain", body = String)))]
+async fn metrics(State(state): State<ServerState>) -> ([(HeaderName, &'static str); 1], String) {
+    let mut body = state.node.metrics().to_prometheus();
+    if let Some(report) = &state.divergence {
+        body.push_str(&report.load().to_prometheus());
+    }
+    if let Some(report) = &state.voting {
+        body.push_str(&report.load().to_prometheus());
+    }
+    if let Some(report) = &state.rolling {
+        body.push_str(&report.load().to_prometheus());
+    }
+    if let Some(counters) = &state.dapps {
+        body.push_str(&counters.read().unwrap().to_prometheus());
+    }
+    if let Some(tenants) = &state.tenants {
+        body.push_str(&tenants.to_prometheus());
+    }
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
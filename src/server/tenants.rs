@@ -0,0 +1,773 @@
+//! API-key scoped namespaces for teams sharing one instance.
+//!
+//! Each tenant authenticates with the `x-api-key` header, registers its own watchlists and
+//! webhook under `/tenant`, and only sees the mempool transactions matching its watchlists.
+//! Requests are rate limited per tenant, and events wait for a slow webhook in a bounded
+//! queue.
+//!
+//! Webhooks may not point at loopback, private or link-local addresses, such as a cloud
+//! metadata endpoint, unless the operator allows their host in `webhookHosts`. URLs are
+//! checked once normalised, so `http://2130706433/` is `127.0.0.1`, and host names are resolved
+//! when the webhook is set. Webhooks are posted to without following redirects, see
+//! [`webhook_client`].
+//!
+//! Watchlists and webhooks are kept in memory only: tenants register them again after a
+//! restart.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use arc_swap::ArcSwap;
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, put},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{net::lookup_host, task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+use url::{Host, Url};
+use utoipa::ToSchema;
+
+use super::{Mempool, ServerState};
+use crate::{
+    clients::node::{HttpRequest, HttpTransport},
+    events::{self, EventSender, LagPolicy, RecvError},
+    filter::Filter,
+    shutdown::{ShutdownCoordinator, Stage},
+    trace::ErrorLog,
+    types::{HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::{MempoolSnapshot, SnapshotDiff},
+};
+
+/// Header carrying the tenant's API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+/// Events queued by default for a tenant's webhook before its lag policy applies.
+pub const DEFAULT_WEBHOOK_QUEUE: usize = 64;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TenantError {
+    #[error("Invalid tenants: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("API key of tenant `{0}` is already used by another tenant")]
+    DuplicateKey(String),
+
+    #[error("Tenant `{0}` is configured twice")]
+    DuplicateName(String),
+
+    #[error("Missing or unknown API key")]
+    Unauthorized,
+
+    #[error("Rate limit of tenant `{0}` exceeded")]
+    RateLimited(String),
+
+    #[error("Invalid webhook URL `{0}`")]
+    InvalidWebhook(String),
+
+    #[error("Webhook host `{0}` is not allowed")]
+    ForbiddenWebhook(String),
+}
+
+impl TenantError {
+    fn status(&self) -> StatusCode {
+        match self {
+            TenantError::Unauthorized => StatusCode::UNAUTHORIZED,
+            TenantError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A tenant as configured by the operator, e.g.
+/// `{ "name": "team-a", "apiKey": "...", "requestsPerMinute": 120, "lagPolicy": "disconnect",
+/// "webhookHosts": ["hooks.example.com"] }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantConfig {
+    pub name: String,
+    pub api_key: String,
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Events queued for the webhook while it is slow.
+    #[serde(default = "default_webhook_queue")]
+    pub webhook_queue: usize,
+    /// Whether a webhook falling behind the queue skips the oldest events or is removed.
+    #[serde(default)]
+    pub lag_policy: LagPolicy,
+    /// Hosts the webhook may point at, internal ones included. Any public host when empty.
+    #[serde(default)]
+    pub webhook_hosts: Vec<String>,
+}
+
+fn default_requests_per_minute() -> u32 {
+    DEFAULT_REQUESTS_PER_MINUTE
+}
+
+fn default_webhook_queue() -> usize {
+    DEFAULT_WEBHOOK_QUEUE
+}
+
+/// Token bucket allowing bursts of up to `per_minute` requests, refilled continuously.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        Self { per_minute, tokens: per_minute as f64, refilled: now }
+    }
+
+    /// Takes a token if one is left at `now`.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.per_minute as f64 / 60.0).min(self.per_minute as f64);
+        self.refilled = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// A tenant's namespace: its watchlists, webhook and rate limit.
+#[derive(Debug)]
+pub struct Tenant {
+    name: String,
+    watchlists: BTreeMap<String, Filter>,
+    webhook: Option<String>,
+    webhook_hosts: Vec<String>,
+    limiter: RateLimiter,
+}
+
+impl Tenant {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn watchlists(&self) -> &BTreeMap<String, Filter> {
+        &self.watchlists
+    }
+
+    /// Adds or replaces the watchlist `name`.
+    pub fn set_watchlist(&mut self, name: &str, filter: Filter) {
+        self.watchlists.insert(name.to_string(), filter);
+    }
+
+    pub fn remove_watchlist(&mut self, name: &str) -> bool {
+        self.watchlists.remove(name).is_some()
+    }
+
+    pub fn webhook(&self) -> Option<&str> {
+        self.webhook.as_deref()
+    }
+
+    /// Sets the URL the tenant's events are posted to, or stops posting them with `None`.
+    /// Internal hosts are rejected unless allowed by the operator, see the module docs; names
+    /// are not resolved here, but by [`resolve_webhook`] first.
+    pub fn set_webhook(&mut self, url: Option<String>) -> Result<(), TenantError> {
+        self.webhook = match url {
+            Some(url) => Some(self.check_webhook(&url)?.to_string()),
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// The normalised `url` if the tenant may post to its host.
+    fn check_webhook(&self, url: &str) -> Result<Url, TenantError> {
+        let url = parse_webhook(url)?;
+        let host = host_name(&url);
+        let allowed = match self.webhook_hosts.is_empty() {
+            true => !is_internal_host(&url),
+            false => self
+                .webhook_hosts
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(&host)),
+        };
+        match allowed {
+            true => Ok(url),
+            false => Err(TenantError::ForbiddenWebhook(host)),
+        }
+    }
+
+    /// Whether the host of `url` was allowed by the operator, so resolving it is not needed.
+    fn allows_host(&self, url: &Url) -> bool {
+        let host = host_name(url);
+        self.webhook_hosts
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case(&host))
+    }
+
+    /// Matches the outputs of any watchlist, `None` without watchlists.
+    pub fn filter(&self) -> Option<Filter> {
+        self.watchlists.values().cloned().reduce(Filter::or)
+    }
+}
+
+/// Mempool changes matching a tenant's watchlists, as posted to its webhook.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantEvent {
+    pub tenant: String,
+    pub last_update: TimestampMillis,
+    pub added: Vec<UnconfirmedTransaction>,
+    pub removed: Vec<HashDigest>,
+}
+
+/// An event and the webhook it is posted to.
+type WebhookEvent = (String, Arc<TenantEvent>);
+
+/// The tenants of the server, by API key.
+#[derive(Debug, Default)]
+pub struct Tenants {
+    tenants: HashMap<String, Mutex<Tenant>>,
+    /// Queues of the webhook events, by tenant name.
+    queues: BTreeMap<String, EventSender<WebhookEvent>>,
+}
+
+impl Tenants {
+    pub fn new(configs: Vec<TenantConfig>) -> Result<Self, TenantError> {
+        let now = Instant::now();
+        let mut tenants = HashMap::new();
+        let mut queues = BTreeMap::new();
+        for config in configs {
+            let queue = EventSender::new(config.webhook_queue, config.lag_policy);
+            if queues.insert(config.name.clone(), queue).is_some() {
+                return Err(TenantError::DuplicateName(config.name));
+            }
+            let tenant = Tenant {
+                name: config.name.clone(),
+                watchlists: BTreeMap::new(),
+                webhook: None,
+                webhook_hosts: config.webhook_hosts,
+                limiter: RateLimiter::new(config.requests_per_minute, now),
+            };
+            if tenants.insert(config.api_key, Mutex::new(tenant)).is_some() {
+                return Err(TenantError::DuplicateKey(config.name));
+            }
+        }
+        Ok(Self { tenants, queues })
+    }
+
+    /// Parses a JSON array of [`TenantConfig`]s.
+    pub fn from_json(json: &str) -> Result<Self, TenantError> {
+        Self::new(serde_json::from_str(json)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    /// The tenant holding `api_key`, without taking from its rate limit.
+    fn get(&self, api_key: &str) -> Option<MutexGuard<'_, Tenant>> {
+        self.tenants.get(api_key).map(|t| t.lock().unwrap())
+    }
+
+    /// The tenant holding `api_key`, once its rate limit allows another request at `now`.
+    pub fn authorize(
+        &self,
+        api_key: Option<&str>,
+        now: Instant,
+    ) -> Result<MutexGuard<'_, Tenant>, TenantError> {
+        let mut tenant = api_key
+            .and_then(|key| self.tenants.get(key))
+            .ok_or(TenantError::Unauthorized)?
+            .lock()
+            .unwrap();
+        if !tenant.limiter.try_acquire(now) {
+            return Err(TenantError::RateLimited(tenant.name.clone()));
+        }
+        Ok(tenant)
+    }
+
+    /// Counters of the webhook queues in the Prometheus text format.
+    pub fn to_prometheus(&self) -> String {
+        events::to_prometheus(
+            self.queues
+                .iter()
+                .map(|(name, queue)| (name.as_str(), &**queue.metrics())),
+        )
+    }
+
+    /// Removes the webhook of the tenant `name`.
+    fn remove_webhook(&self, name: &str) {
+        for tenant in self.tenants.values() {
+            let mut tenant = tenant.lock().unwrap();
+            if tenant.name == name {
+                tenant.webhook = None;
+            }
+        }
+    }
+
+    /// Events of the tenants with a webhook and watchlists between two snapshots, with the
+    /// webhook they go to. Tenants without matching changes are skipped.
+    pub fn events(
+        &self,
+        from: &MempoolSnapshot,
+        to: &MempoolSnapshot,
+    ) -> Vec<(String, TenantEvent)> {
+        let mut events = Vec::new();
+        for tenant in self.tenants.values() {
+            let tenant = tenant.lock().unwrap();
+            let (Some(webhook), Some(filter)) = (&tenant.webhook, tenant.filter()) else {
+                continue;
+            };
+            let diff = SnapshotDiff::between_matching(from, to, &filter);
+            if diff.is_empty() {
+                continue;
+            }
+            let added: HashSet<&HashDigest> = diff.added.iter().collect();
+            events.push((
+                webhook.clone(),
+                TenantEvent {
+                    tenant: tenant.name.clone(),
+                    last_update: to.last_update,
+                    added: to
+                        .transactions
+                        .iter()
+                        .filter(|tx| added.contains(&tx.id))
+                        .cloned()
+                        .collect(),
+                    removed: diff.removed,
+                },
+            ));
+        }
+        events
+    }
+}
+
+/// Splits `scheme://host[/path]` into the base URL and the path without its leading slash.
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    let scheme_len = ["https://", "http://"]
+        .iter()
+        .find(|scheme| url.starts_with(*scheme))?
+        .len();
+    let (base, path) = match url[scheme_len..].find('/') {
+        Some(i) => (&url[..scheme_len + i], &url[scheme_len + i + 1..]),
+        None => (url, ""),
+    };
+    (base.len() > scheme_len).then_some((base, path))
+}
+
+/// Parses an `http` or `https` webhook URL. User info and fragments are rejected, as they could
+/// make a client read another host than the one checked.
+fn parse_webhook(url: &str) -> Result<Url, TenantError> {
+    let invalid = || TenantError::InvalidWebhook(url.to_string());
+    let parsed = Url::parse(url).map_err(|_| invalid())?;
+    if !matches!(parsed.scheme(), "http" | "https")
+        || parsed.host().is_none()
+        || !parsed.username().is_empty()
+        || parsed.password().is_some()
+        || parsed.fragment().is_some()
+    {
+        return Err(invalid());
+    }
+    Ok(parsed)
+}
+
+/// Lowercase host of a parsed URL, without IPv6 brackets or a trailing dot.
+fn host_name(url: &Url) -> String {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain.trim_end_matches('.').to_ascii_lowercase(),
+        Some(Host::Ipv4(ip)) => ip.to_string(),
+        Some(Host::Ipv6(ip)) => ip.to_string(),
+        None => String::new(),
+    }
+}
+
+/// Whether the host of `url` is `localhost` or a loopback, private, link-local or unspecified
+/// address.
+fn is_internal_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(_)) => {
+            let host = host_name(url);
+            host == "localhost" || host.ends_with(".localhost")
+        }
+        Some(Host::Ipv4(ip)) => is_internal_ipv4(ip),
+        Some(Host::Ipv6(ip)) => is_internal_ip(IpAddr::V6(ip)),
+        None => true,
+    }
+}
+
+fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_internal_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal_ipv4(ip),
+            None => is_internal_ipv6(ip),
+        },
+    }
+}
+
+/// Resolves the host name of a webhook URL, failing if any of its addresses is internal, so
+/// that a public name pointing at a private network is rejected like the address itself.
+/// Addresses are not resolved again when posting, so names the tenant controls can still be
+/// pointed elsewhere later; operators needing a guarantee should set `webhookHosts`.
+pub async fn resolve_webhook(url: &str) -> Result<(), TenantError> {
+    let parsed = parse_webhook(url)?;
+    let Some(Host::Domain(domain)) = parsed.host() else {
+        return Ok(());
+    };
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addrs = lookup_host((domain, port))
+        .await
+        .map_err(|_| TenantError::InvalidWebhook(url.to_string()))?;
+    for addr in addrs {
+        if is_internal_ip(addr.ip()) {
+            return Err(TenantError::ForbiddenWebhook(host_name(&parsed)));
+        }
+    }
+    Ok(())
+}
+
+/// HTTP client posting to tenant webhooks. Redirects are not followed, as they could lead to
+/// an internal host.
+#[cfg(feature = "reqwest")]
+pub fn webhook_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+}
+
+fn is_internal_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // Shared address space of carrier-grade NATs, 100.64.0.0/10.
+        || (a == 100 && b & 0xc0 == 64)
+}
+
+fn is_internal_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local, fc00::/7, and link-local, fe80::/10, addresses.
+        || first & 0xfe00 == 0xfc00
+        || first & 0xffc0 == 0xfe80
+}
+
+/// Posts the events of every mempool update to the tenants' webhooks. `connect` opens a
+/// transport to the base URL of a webhook.
+///
+/// Each webhook is posted to by a task of its own, so a slow one only delays its own events.
+/// Once its queue is full, it skips the oldest events or, under [`LagPolicy::Disconnect`], is
+/// removed until the tenant sets it again.
+///
+/// Snapshots stop being read at the [`Stage::Intake`] of a shutdown, and each webhook posts
+/// the events already queued before stopping at [`Stage::Sinks`].
+pub fn spawn_webhooks(
+    tenants: Arc<Tenants>,
+    mempool: Arc<ArcSwap<MempoolSnapshot>>,
+    connect: impl Fn(&str) -> Arc<dyn HttpTransport> + Send + Sync + 'static,
+    shutdown: &ShutdownCoordinator,
+) -> JoinHandle<()> {
+    let connect = Arc::new(connect);
+    for name in tenants.queues.keys() {
+        let (tenants, connect, name) = (tenants.clone(), connect.clone(), name.clone());
+        let stop = shutdown.register(Stage::Sinks, format!("{name} webhook"));
+        tokio::spawn(async move {
+            let queue = &tenants.queues[&name];
+            let mut events = queue.subscribe();
+            let mut errors = ErrorLog::new(format!("{name} webhook"));
+            let stopping = stop.requested();
+            tokio::pin!(stopping);
+            let mut draining = false;
+            loop {
+                let received = if draining {
+                    match events.try_recv() {
+                        Some(received) => received,
+                        None => return,
+                    }
+                } else {
+                    tokio::select! {
+                        received = events.recv() => received,
+                        () = &mut stopping => {
+                            draining = true;
+                            continue;
+                        }
+                    }
+                };
+                let (webhook, event) = match received {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(tenant = name, skipped, "Webhook lagging, oldest events dropped.");
+                        continue;
+                    }
+                    Err(RecvError::Disconnected) => {
+                        warn!(tenant = name, "Webhook lagging, removed.");
+                        tenants.remove_webhook(&name);
+                        events = queue.subscribe();
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                // Webhooks are validated when set.
+                let Some((base, path)) = split_url(&webhook) else { continue };
+                let body = serde_json::to_vec(&*event).expect("events serialize");
+                match connect(base).send(HttpRequest::post(path, body)).await {
+                    Ok(resp) if (200..300).contains(&resp.status) => errors.success(),
+                    Ok(resp) => {
+                        errors.failure(&format!("Webhook responded with status {}", resp.status))
+                    }
+                    Err(e) => errors.failure(&e),
+                }
+            }
+        });
+    }
+
+    let events = tokio::spawn(async move {
+        info!(tenants = tenants.len(), "Starting tenant webhooks...");
+        let mut previous: Option<Arc<MempoolSnapshot>> = None;
+        loop {
+            let snapshot = mempool.load_full();
+            if snapshot.last_update > TimestampMillis(0) {
+                if let Some(previous) = &previous
+                    && snapshot.last_update > previous.last_update
+                {
+                    for (webhook, event) in tenants.events(previous, &snapshot) {
+                        tenants.queues[&event.tenant].send((webhook, Arc::new(event)));
+                    }
+                }
+                previous = Some(snapshot);
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+    shutdown.abort(Stage::Intake, "tenant events", events.abort_handle());
+    events
+}
+
+pub(super) fn router() -> Router<ServerState> {
+    Router::new()
+        .route("/tenant/mempool", get(tenant_mempool))
+        .route("/tenant/watchlists", get(watchlists))
+        .route("/tenant/watchlists/{name}", put(set_watchlist).delete(remove_watchlist))
+        .route("/tenant/webhook", put(set_webhook).delete(remove_webhook))
+}
+
+/// The tenant of the request, failing with 404 if tenants are not configured.
+fn authorize<'a>(
+    state: &'a ServerState,
+    headers: &HeaderMap,
+) -> Result<MutexGuard<'a, Tenant>, (StatusCode, String)> {
+    let tenants = state
+        .tenants
+        .as_ref()
+        .ok_or((StatusCode::NOT_FOUND, "Tenants are not enabled".to_string()))?;
+    let api_key = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+    tenants
+        .authorize(api_key, Instant::now())
+        .map_err(|e| (e.status(), e.to_string()))
+}
+
+/// The tenant of a request already [authorized](authorize), without taking from its rate limit
+/// again, e.g. after awaiting.
+fn reauthorize<'a>(
+    state: &'a ServerState,
+    headers: &HeaderMap,
+) -> Result<MutexGuard<'a, Tenant>, (StatusCode, String)> {
+    let api_key = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+    let unauthorized = TenantError::Unauthorized;
+    state
+        .tenants
+        .as_ref()
+        .zip(api_key)
+        .and_then(|(tenants, api_key)| tenants.get(api_key))
+        .ok_or((unauthorized.status(), unauthorized.to_string()))
+}
+
+/// Unconfirmed transactions with an output matching any of the tenant's watchlists.
+#[utoipa::path(
+    get,
+    path = "/tenant/mempool",
+    params(("x-api-key" = String, Header, description = "API key of the tenant")),
+    responses(
+        (status = 200, body = Mempool),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 404, description = "Tenants are not enabled", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn tenant_mempool(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Mempool>, (StatusCode, String)> {
+    let filter = authorize(&state, &headers)?.filter();
+    let snapshot = state.mempool.load();
+    let transactions = match &filter {
+        Some(filter) => snapshot.matching(filter).into_iter().cloned().collect(),
+        None => Vec::new(),
+    };
+    Ok(Json(Mempool { last_update: snapshot.last_update.0, transactions }))
+}
+
+/// The tenant's watchlists, as filter expressions by name.
+#[utoipa::path(
+    get,
+    path = "/tenant/watchlists",
+    params(("x-api-key" = String, Header, description = "API key of the tenant")),
+    responses(
+        (status = 200, body = BTreeMap<String, String>),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn watchlists(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+) -> Result<Json<BTreeMap<String, String>>, (StatusCode, String)> {
+    let tenant = authorize(&state, &headers)?;
+    Ok(Json(
+        tenant
+            .watchlists()
+            .iter()
+            .map(|(name, filter)| (name.clone(), filter.to_string()))
+            .collect(),
+    ))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(super) struct WatchlistBody {
+    /// Filter expression, e.g. `address:<address> or token:<id>`.
+    filter: String,
+}
+
+/// Adds or replaces a watchlist of the tenant.
+#[utoipa::path(
+    put,
+    path = "/tenant/watchlists/{name}",
+    params(
+        ("x-api-key" = String, Header, description = "API key of the tenant"),
+        ("name" = String, Path, description = "Name of the watchlist")
+    ),
+    request_body = WatchlistBody,
+    responses(
+        (status = 204),
+        (status = 400, description = "Invalid filter", body = String),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn set_watchlist(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(body): Json<WatchlistBody>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mut tenant = authorize(&state, &headers)?;
+    let filter = body
+        .filter
+        .parse()
+        .map_err(|e: crate::filter::FilterError| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    tenant.set_watchlist(&name, filter);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Removes a watchlist of the tenant.
+#[utoipa::path(
+    delete,
+    path = "/tenant/watchlists/{name}",
+    params(
+        ("x-api-key" = String, Header, description = "API key of the tenant"),
+        ("name" = String, Path, description = "Name of the watchlist")
+    ),
+    responses(
+        (status = 204),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 404, description = "No such watchlist", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn remove_watchlist(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mut tenant = authorize(&state, &headers)?;
+    if !tenant.remove_watchlist(&name) {
+        return Err((StatusCode::NOT_FOUND, format!("No watchlist `{name}`")));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub(super) struct WebhookBody {
+    /// URL the tenant's events are posted to as JSON.
+    url: String,
+}
+
+/// Posts the mempool changes matching the tenant's watchlists to a URL.
+#[utoipa::path(
+    put,
+    path = "/tenant/webhook",
+    params(("x-api-key" = String, Header, description = "API key of the tenant")),
+    request_body = WebhookBody,
+    responses(
+        (status = 204),
+        (status = 400, description = "Invalid URL", body = String),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn set_webhook(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+    Json(body): Json<WebhookBody>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let error = |e: TenantError| (e.status(), e.to_string());
+    let allowed = {
+        let tenant = authorize(&state, &headers)?;
+        let url = tenant.check_webhook(&body.url).map_err(error)?;
+        tenant.allows_host(&url)
+    };
+    if !allowed {
+        resolve_webhook(&body.url).await.map_err(error)?;
+    }
+    reauthorize(&state, &headers)?
+        .set_webhook(Some(body.url))
+        .map_err(error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stops posting the tenant's events.
+#[utoipa::path(
+    delete,
+    path = "/tenant/webhook",
+    params(("x-api-key" = String, Header, description = "API key of the tenant")),
+    responses(
+        (status = 204),
+        (status = 401, description = "Missing or unknown API key", body = String),
+        (status = 429, description = "Rate limit exceeded", body = String)
+    )
+)]
+pub(super) async fn remove_webhook(
+    State(state): State<ServerState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    authorize(&state, &headers)?
+        .set_webhook(None)
+        .map_err(|e| (e.status(), e.to_string()))?;
+    Ok(StatusCode::NO_CONTENT)
+}
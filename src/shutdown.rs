@@ -0,0 +1,181 @@
+//! Ordered shutdown of the running subsystems: intake stops first so nothing new is
+//! produced, then sinks deliver what they have buffered, then storage is flushed. Each stage
+//! is given a timeout, after which the next one starts regardless.
+
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use serde::Serialize;
+use tokio::{
+    sync::{oneshot, watch},
+    task::AbortHandle,
+    time::{Instant, timeout_at},
+};
+use tracing::{info, warn};
+
+/// Time each stage is given by default.
+pub const DEFAULT_STAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Stages of a shutdown, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    /// Watchers and the server: stop polling and accepting requests.
+    Intake,
+    /// Notifiers and webhooks: deliver the events already queued.
+    Sinks,
+    /// Cursors and stores: persist what the sinks delivered.
+    Storage,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Intake, Stage::Sinks, Stage::Storage];
+}
+
+/// Handed to a task taking part in a stage. The task is done with its stage once it calls
+/// [`done`](Self::done) or drops the signal, e.g. by returning.
+#[derive(Debug)]
+pub struct ShutdownSignal {
+    requested: watch::Receiver<bool>,
+    done: Option<oneshot::Sender<()>>,
+}
+
+impl ShutdownSignal {
+    /// Resolves once the task's stage begins, or the coordinator is dropped.
+    pub fn requested(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut requested = self.requested.clone();
+        async move {
+            let _ = requested.wait_for(|requested| *requested).await;
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    pub fn done(mut self) {
+        if let Some(done) = self.done.take() {
+            let _ = done.send(());
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Participant {
+    Task(oneshot::Receiver<()>),
+    /// Holds nothing worth finishing, so it is aborted.
+    Abort(AbortHandle),
+}
+
+#[derive(Debug)]
+struct StageState {
+    requested: watch::Sender<bool>,
+    timeout: Duration,
+    participants: Vec<(String, Participant)>,
+}
+
+/// Outcome of a stage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageReport {
+    pub stage: Stage,
+    pub completed: Vec<String>,
+    /// Participants still running at the stage timeout.
+    pub timed_out: Vec<String>,
+}
+
+/// Runs the stages of a shutdown on the participants registered to them.
+#[derive(Debug)]
+pub struct ShutdownCoordinator {
+    stages: Mutex<BTreeMap<Stage, StageState>>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        let stages = Stage::ALL
+            .into_iter()
+            .map(|stage| {
+                let state = StageState {
+                    requested: watch::Sender::new(false),
+                    timeout: DEFAULT_STAGE_TIMEOUT,
+                    participants: Vec::new(),
+                };
+                (stage, state)
+            })
+            .collect();
+        Self { stages: Mutex::new(stages) }
+    }
+
+    pub fn with_timeout(self, stage: Stage, timeout: Duration) -> Self {
+        self.stages.lock().unwrap().get_mut(&stage).unwrap().timeout = timeout;
+        self
+    }
+
+    /// Registers a task named `name` to `stage`.
+    pub fn register(&self, stage: Stage, name: impl Into<String>) -> ShutdownSignal {
+        let mut stages = self.stages.lock().unwrap();
+        let state = stages.get_mut(&stage).unwrap();
+        let (done, finished) = oneshot::channel();
+        state
+            .participants
+            .push((name.into(), Participant::Task(finished)));
+        ShutdownSignal { requested: state.requested.subscribe(), done: Some(done) }
+    }
+
+    /// Aborts the task of `handle` when `stage` begins, for tasks like pollers that have
+    /// nothing to finish.
+    pub fn abort(&self, stage: Stage, name: impl Into<String>, handle: AbortHandle) {
+        let mut stages = self.stages.lock().unwrap();
+        let state = stages.get_mut(&stage).unwrap();
+        state
+            .participants
+            .push((name.into(), Participant::Abort(handle)));
+    }
+
+    /// Whether [`shutdown`](Self::shutdown) began, so tasks it aborts may be ending.
+    pub fn is_started(&self) -> bool {
+        let stages = self.stages.lock().unwrap();
+        *stages[&Stage::ALL[0]].requested.borrow()
+    }
+
+    /// Runs every stage in order, each until its participants are done or its timeout.
+    /// Participants registered to a stage once it began are signalled but not waited on.
+    pub async fn shutdown(&self) -> Vec<StageReport> {
+        let mut reports = Vec::with_capacity(Stage::ALL.len());
+        for stage in Stage::ALL {
+            let (participants, timeout) = {
+                let mut stages = self.stages.lock().unwrap();
+                let state = stages.get_mut(&stage).unwrap();
+                state.requested.send_replace(true);
+                (std::mem::take(&mut state.participants), state.timeout)
+            };
+            info!(?stage, participants = participants.len(), "Shutting down...");
+
+            let deadline = Instant::now() + timeout;
+            let mut report = StageReport { stage, completed: Vec::new(), timed_out: Vec::new() };
+            for (name, participant) in participants {
+                match participant {
+                    Participant::Abort(handle) => {
+                        handle.abort();
+                        report.completed.push(name);
+                    }
+                    // A dropped sender is done all the same.
+                    Participant::Task(finished) => match timeout_at(deadline, finished).await {
+                        Ok(_) => report.completed.push(name),
+                        Err(_) => {
+                            warn!(?stage, participant = name, "Shutdown timed out.");
+                            report.timed_out.push(name);
+                        }
+                    },
+                }
+            }
+            reports.push(report);
+        }
+        reports
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{
+    chain::ergo_box,
+    codec::CodecError,
+    params::NetworkParameters,
+    types::{
+        HashDigest, Height,
+        ergo::{BoxCandidate, UTxO},
+    },
+};
+
+/// Blocks a box may stay in the UTXO set before storage rent can be collected (~4 years).
+pub const STORAGE_PERIOD: u32 = 1_051_200;
+
+/// Context extension variable pointing a rent-collecting input at its recreated output.
+pub const STORAGE_INDEX_VAR_ID: u8 = 127;
+
+/// A box eligible for storage rent collection.
+#[derive(Debug, Clone)]
+pub struct ClaimableBox<'a> {
+    pub utxo: &'a UTxO,
+    /// Serialized box size in bytes.
+    pub size: usize,
+    /// Amount the collector may take, in nanoERG.
+    pub claimable: u64,
+}
+
+impl ClaimableBox<'_> {
+    /// Whether the rent covers the whole box value, so the box is consumed without being recreated.
+    pub fn is_consumed(&self) -> bool {
+        self.claimable == self.utxo.value
+    }
+}
+
+/// An input spent by storage rent, with its context extension.
+#[derive(Debug, Clone)]
+pub struct RentInput {
+    pub box_id: HashDigest,
+    /// Index of the recreated output, stored under [`STORAGE_INDEX_VAR_ID`]. `None` when the
+    /// box is consumed.
+    pub recreated_index: Option<u16>,
+}
+
+/// Inputs and outputs of a rent-collection transaction. The collected value is left unassigned
+/// for the builder to pay out (usually to the miner fee or the collector's address).
+#[derive(Debug, Clone)]
+pub struct RentCollection {
+    pub inputs: Vec<RentInput>,
+    pub outputs: Vec<BoxCandidate>,
+    pub collected: u64,
+}
+
+/// Finds the boxes whose age is at least [`STORAGE_PERIOD`] at `current_height`.
+pub fn find_claimable<'a>(
+    boxes: &'a [UTxO],
+    current_height: Height,
+    params: &NetworkParameters,
+) -> Result<Vec<ClaimableBox<'a>>, CodecError> {
+    let mut claimable = Vec::new();
+    for utxo in boxes {
+        if current_height.blocks_since(utxo.creation_height) < STORAGE_PERIOD {
+            continue;
+        }
+
+        let size = ergo_box::serialize(utxo)?.len();
+        let fee = params.storage_fee(size);
+        claimable.push(ClaimableBox { utxo, size, claimable: fee.min(utxo.value) });
+    }
+    Ok(claimable)
+}
+
+/// Plans a transaction collecting rent from `claims`. Boxes worth more than the rent are
+/// recreated with identical contents, reduced value and `current_height` as creation height.
+pub fn build_collection(claims: &[ClaimableBox], current_height: Height) -> RentCollection {
+    let mut inputs = Vec::with_capacity(claims.len());
+    let mut outputs = Vec::new();
+    let mut collected = 0;
+
+    for claim in claims {
+        collected += claim.claimable;
+
+        let recreated_index = (!claim.is_consumed()).then(|| {
+            let utxo = claim.utxo;
+            outputs.push(BoxCandidate {
+                ergo_tree: utxo.ergo_tree.clone(),
+                creation_height: current_height,
+                value: utxo.value - claim.claimable,
+                tokens: utxo.tokens.clone(),
+                registers: utxo.registers.clone(),
+            });
+            (outputs.len() - 1) as u16
+        });
+        inputs.push(RentInput { box_id: claim.utxo.id.clone(), recreated_index });
+    }
+
+    RentCollection { inputs, outputs, collected }
+}
@@ -0,0 +1,106 @@
+use std::{any::Any, collections::VecDeque, future::Future, sync::Arc, time::Duration};
+
+use tokio::{
+    task::{AbortHandle, JoinHandle},
+    time::Instant,
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    clock::{self, Clock},
+    error::AppError,
+};
+
+/// How often a supervised task may be restarted before its supervisor gives up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Restarts allowed within `window`; one more fails the supervisor.
+    pub max_restarts: u32,
+    pub window: Duration,
+    /// Delay before each restart.
+    pub backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self { max_restarts: 5, window: Duration::from_secs(60), backoff: Duration::from_secs(1) }
+    }
+}
+
+/// Runs the task built by `start` in the background, restarting it when it panics or returns
+/// an error. A task returning `Ok` is done and not restarted.
+///
+/// When restarts exceed `policy`, the returned handle resolves to
+/// [`AppError::TaskFailed`], which callers should escalate, e.g. by exiting the process.
+/// Aborting the returned task also aborts the one it runs.
+pub fn supervise<F, Fut>(
+    task: &'static str,
+    policy: RestartPolicy,
+    start: F,
+) -> JoinHandle<Result<(), AppError>>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    supervise_with_clock(task, policy, clock::system(), start)
+}
+
+/// [`supervise`], timing the restart window and backoff by `clock`.
+pub fn supervise_with_clock<F, Fut>(
+    task: &'static str,
+    policy: RestartPolicy,
+    clock: Arc<dyn Clock>,
+    mut start: F,
+) -> JoinHandle<Result<(), AppError>>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut restarts: VecDeque<Instant> = VecDeque::new();
+        loop {
+            let running = tokio::spawn(start());
+            let _abort = AbortOnDrop(running.abort_handle());
+            let failure = match running.await {
+                Ok(Ok(())) => {
+                    info!(task, "Supervised task finished.");
+                    return Ok(());
+                }
+                Ok(Err(e)) => format!("{e:?}"),
+                Err(e) if e.is_panic() => format!("panicked: {}", panic_message(&*e.into_panic())),
+                // Cancelled along with the runtime.
+                Err(_) => return Ok(()),
+            };
+
+            let now = clock.now();
+            restarts.retain(|at| now.duration_since(*at) < policy.window);
+            if restarts.len() >= policy.max_restarts as usize {
+                error!(task, %failure, "Supervised task failed too often, giving up.");
+                return Err(AppError::TaskFailed { task, failure });
+            }
+            restarts.push_back(now);
+
+            warn!(task, %failure, attempt = restarts.len(), "Supervised task failed, restarting.");
+            clock.sleep(policy.backoff).await;
+        }
+    })
+}
+
+/// Aborts a task when the supervisor is dropped, e.g. aborted itself.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "non-string panic payload"
+    }
+}
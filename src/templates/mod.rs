@@ -0,0 +1,148 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chain::ergo_tree,
+    codec::CodecError,
+    types::{
+        HashDigest, HexBytes,
+        ergo::{UTxO, UnconfirmedTransaction},
+    },
+};
+
+/// Contract templates shipped with the crate.
+pub static BUILTIN: Lazy<TemplateRegistry> = Lazy::new(|| {
+    TemplateRegistry::from_json(include_str!("builtin.json")).expect("built-in templates are valid")
+});
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("Failed to read templates: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid template file: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid ErgoTree for template `{0}`: {1}")]
+    ErgoTree(String, CodecError),
+
+    #[error("Template entry `{0}` must have exactly one of `templateHash` or `ergoTree`")]
+    Target(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContractTemplate {
+    pub name: String,
+    /// Application the contract belongs to.
+    pub dapp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// An entry of a template file, keyed by either a template hash or an example ErgoTree.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TemplateEntry {
+    template_hash: Option<HashDigest>,
+    ergo_tree: Option<HexBytes>,
+    #[serde(flatten)]
+    template: ContractTemplate,
+}
+
+/// Known contracts by ErgoTree template hash, see [`ergo_tree::template_hash`].
+#[derive(Debug, Clone, Default)]
+pub struct TemplateRegistry {
+    templates: HashMap<HashDigest, ContractTemplate>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in templates with the entries of `path`, if any, taking precedence.
+    pub fn with_overrides(path: Option<&Path>) -> Result<Self, TemplateError> {
+        let mut templates = BUILTIN.clone();
+        if let Some(path) = path {
+            templates.extend(Self::from_json(&std::fs::read_to_string(path)?)?);
+        }
+        Ok(templates)
+    }
+
+    /// Parses a JSON array of `{ "templateHash" | "ergoTree", "name", "dapp", "description"? }`.
+    pub fn from_json(json: &str) -> Result<Self, TemplateError> {
+        let entries: Vec<TemplateEntry> = serde_json::from_str(json)?;
+        let mut registry = Self::new();
+        for entry in entries {
+            match (entry.template_hash, entry.ergo_tree) {
+                (Some(hash), None) => registry.register(hash, entry.template),
+                (None, Some(tree)) => {
+                    let name = entry.template.name.clone();
+                    registry
+                        .register_tree(&tree.0, entry.template)
+                        .map_err(|e| TemplateError::ErgoTree(name, e))?;
+                }
+                _ => return Err(TemplateError::Target(entry.template.name)),
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Adds or replaces the template with the given hash.
+    pub fn register(&mut self, template_hash: HashDigest, template: ContractTemplate) {
+        self.templates.insert(template_hash, template);
+    }
+
+    /// Registers the template of an instance of the contract.
+    pub fn register_tree(
+        &mut self,
+        ergo_tree: &[u8],
+        template: ContractTemplate,
+    ) -> Result<HashDigest, CodecError> {
+        let hash = ergo_tree::template_hash(ergo_tree)?;
+        self.register(hash.clone(), template);
+        Ok(hash)
+    }
+
+    /// Adds all templates of `other`, replacing existing ones with the same hash.
+    pub fn extend(&mut self, other: TemplateRegistry) {
+        self.templates.extend(other.templates);
+    }
+
+    pub fn get(&self, template_hash: &HashDigest) -> Option<&ContractTemplate> {
+        self.templates.get(template_hash)
+    }
+
+    /// Template of the contract guarding `ergo_tree`. Unparseable trees are unknown.
+    pub fn classify_tree(&self, ergo_tree: &[u8]) -> Option<&ContractTemplate> {
+        self.get(&ergo_tree::template_hash(ergo_tree).ok()?)
+    }
+
+    pub fn classify_box(&self, utxo: &UTxO) -> Option<&ContractTemplate> {
+        self.classify_tree(&utxo.ergo_tree.0)
+    }
+
+    /// dApps whose contracts are spent or created by the transaction.
+    pub fn classify_transaction(&self, tx: &UnconfirmedTransaction) -> BTreeSet<&str> {
+        tx.inputs
+            .iter()
+            .map(|i| &i.utxo)
+            .chain(&tx.outputs)
+            .filter_map(|utxo| self.classify_box(utxo))
+            .map(|t| t.dapp.as_str())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+}
@@ -0,0 +1,151 @@
+//! Display metadata of tokens, names and decimals, read from the node's blockchain indexer
+//! and cached, with configured overrides taking precedence.
+
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::node::{NodeClient, NodeError, TokenBalance},
+    types::{HashDigest, ergo::TokenInfo},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("Failed to read token metadata: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid token metadata file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub decimals: u32,
+}
+
+impl From<&TokenInfo> for TokenMetadata {
+    fn from(info: &TokenInfo) -> Self {
+        Self { name: info.name.clone(), decimals: info.decimals.unwrap_or(0) }
+    }
+}
+
+/// An entry of a token metadata file.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenEntry {
+    token_id: HashDigest,
+    #[serde(flatten)]
+    metadata: TokenMetadata,
+}
+
+/// Token metadata by id. Overrides are never replaced by what the node reports; tokens
+/// without any metadata are shown as raw amounts of their short id.
+#[derive(Debug, Default)]
+pub struct TokenRegistry {
+    overrides: HashMap<HashDigest, TokenMetadata>,
+    cache: RwLock<HashMap<HashDigest, TokenMetadata>>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the overrides of `path`, if any.
+    pub fn with_overrides(path: Option<&Path>) -> Result<Self, TokenError> {
+        match path {
+            Some(path) => Self::from_json(&std::fs::read_to_string(path)?),
+            None => Ok(Self::new()),
+        }
+    }
+
+    /// Parses a JSON array of `{ "tokenId", "name"?, "decimals"? }` overrides.
+    pub fn from_json(json: &str) -> Result<Self, TokenError> {
+        let entries: Vec<TokenEntry> = serde_json::from_str(json)?;
+        let mut registry = Self::new();
+        for entry in entries {
+            registry.insert(entry.token_id, entry.metadata);
+        }
+        Ok(registry)
+    }
+
+    /// Overrides the metadata of `token_id`.
+    pub fn insert(&mut self, token_id: HashDigest, metadata: TokenMetadata) {
+        self.overrides.insert(token_id, metadata);
+    }
+
+    pub fn get(&self, token_id: &HashDigest) -> Option<TokenMetadata> {
+        self.overrides
+            .get(token_id)
+            .or(self.cache.read().unwrap().get(token_id))
+            .cloned()
+    }
+
+    /// Caches metadata already at hand, e.g. from the node's balance responses.
+    pub fn remember(&self, token_id: &HashDigest, metadata: TokenMetadata) {
+        if !self.overrides.contains_key(token_id) {
+            let mut cache = self.cache.write().unwrap();
+            cache.entry(token_id.clone()).or_insert(metadata);
+        }
+    }
+
+    /// Caches the metadata of the tokens of a balance that carry their decimals.
+    pub fn remember_balances(&self, tokens: &[TokenBalance]) {
+        for token in tokens {
+            if let Some(decimals) = token.decimals {
+                let metadata = TokenMetadata { name: token.name.clone(), decimals };
+                self.remember(&token.token_id, metadata);
+            }
+        }
+    }
+
+    /// The metadata of `token_id`, fetched from the node and cached unless known.
+    pub async fn resolve(
+        &self,
+        node: &NodeClient,
+        token_id: &HashDigest,
+    ) -> Result<TokenMetadata, NodeError> {
+        if let Some(metadata) = self.get(token_id) {
+            return Ok(metadata);
+        }
+        let metadata = TokenMetadata::from(&node.get_token(token_id).await?);
+        self.remember(token_id, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// `raw` in whole units of the token, e.g. `12.50` for 1250 of a token with 2 decimals,
+    /// or `raw` itself when the decimals are unknown.
+    pub fn format_units(&self, token_id: &HashDigest, raw: u64) -> String {
+        let decimals = self.get(token_id).map_or(0, |m| m.decimals);
+        format_units(raw.into(), decimals)
+    }
+
+    /// `raw` in whole units followed by the token name, e.g. `12.50 SigUSD`. Unnamed tokens
+    /// are named by the first 8 hex digits of their id.
+    pub fn format_token_amount(&self, token_id: &HashDigest, raw: u64) -> String {
+        let metadata = self.get(token_id).unwrap_or_default();
+        let amount = format_units(raw.into(), metadata.decimals);
+        match metadata.name.filter(|name| !name.is_empty()) {
+            Some(name) => format!("{amount} {name}"),
+            None => format!("{amount} {}", &token_id.to_string()[..8]),
+        }
+    }
+}
+
+/// `amount` of the smallest unit as a decimal with `decimals` fraction digits.
+pub fn format_units(amount: i128, decimals: u32) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let digits = amount.unsigned_abs().to_string();
+    if decimals == 0 {
+        return format!("{sign}{digits}");
+    }
+    // Split the digits rather than divide, as decimals read from chain are unbounded.
+    let digits = format!("{digits:0>width$}", width = decimals as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+    format!("{sign}{whole}.{fraction}")
+}
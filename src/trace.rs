@@ -1,11 +1,18 @@
-use std::env;
+use std::{
+    env,
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
-use tracing::Subscriber;
 use tracing::subscriber::set_global_default;
+use tracing::{Subscriber, error, info, warn};
 use tracing_log::LogTracer;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry, fmt};
 
+/// Interval between "repeated N times" summaries of an [`ErrorLog`].
+pub const DEFAULT_SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
 pub fn default_subscriber() -> impl Subscriber + Send + Sync {
     let log_level = env::var("RUST_LOG").unwrap_or("info".into());
     Registry::default()
@@ -17,3 +24,102 @@ pub fn init(subscriber: impl Subscriber + Send + Sync) {
     LogTracer::init().expect("Failed to set logger");
     set_global_default(subscriber).expect("Failed to set subscriber");
 }
+
+/// Logs the errors of a polling loop without repeating them every poll.
+///
+/// The first failure is logged as the loop going down, and recovery as it coming back up with
+/// the number of failures in between. While failing, identical errors are counted and logged
+/// as "repeated N times" summaries at most once per summary interval; a different error is
+/// logged right away.
+#[derive(Debug)]
+pub struct ErrorLog {
+    source: String,
+    summary_interval: Duration,
+    outage: Option<Outage>,
+}
+
+#[derive(Debug)]
+struct Outage {
+    started: Instant,
+    failures: u64,
+    last_error: String,
+    /// Failures with `last_error` since it was last logged.
+    repeated: u64,
+    last_logged: Instant,
+}
+
+impl ErrorLog {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self { source: source.into(), summary_interval: DEFAULT_SUMMARY_INTERVAL, outage: None }
+    }
+
+    pub fn with_summary_interval(mut self, interval: Duration) -> Self {
+        self.summary_interval = interval;
+        self
+    }
+
+    pub fn is_failing(&self) -> bool {
+        self.outage.is_some()
+    }
+
+    /// Failures since the loop went down, 0 while it is up.
+    pub fn failures(&self) -> u64 {
+        self.outage.as_ref().map_or(0, |o| o.failures)
+    }
+
+    /// Repeats of the last error not logged yet.
+    pub fn suppressed(&self) -> u64 {
+        self.outage.as_ref().map_or(0, |o| o.repeated)
+    }
+
+    pub fn failure(&mut self, error: &impl Display) {
+        let message = error.to_string();
+        let now = Instant::now();
+        let source = self.source.as_str();
+        let Some(outage) = &mut self.outage else {
+            error!(source, error = %message, "Failing.");
+            self.outage = Some(Outage {
+                started: now,
+                failures: 1,
+                last_error: message,
+                repeated: 0,
+                last_logged: now,
+            });
+            return;
+        };
+
+        outage.failures += 1;
+        if outage.last_error != message {
+            outage.flush(source);
+            error!(source, error = %message, "Still failing with a different error.");
+            outage.last_error = message;
+            outage.last_logged = now;
+            return;
+        }
+        outage.repeated += 1;
+        if now.duration_since(outage.last_logged) >= self.summary_interval {
+            outage.flush(source);
+            outage.last_logged = now;
+        }
+    }
+
+    /// Marks a successful poll, logging the recovery if the loop was failing.
+    pub fn success(&mut self) {
+        if let Some(mut outage) = self.outage.take() {
+            let source = self.source.as_str();
+            outage.flush(source);
+            let down_secs = outage.started.elapsed().as_secs();
+            info!(source, failures = outage.failures, down_secs, "Recovered.");
+        }
+    }
+}
+
+impl Outage {
+    fn flush(&mut self, source: &str) {
+        if self.repeated > 0 {
+            let repeated = self.repeated;
+            warn!(source, error = %self.last_error, "Error repeated {repeated} times.");
+            self.repeated = 0;
+        }
+    }
+}
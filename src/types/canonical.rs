@@ -0,0 +1,165 @@
+//! JSON in the node's own formatting, for payloads that are hashed or compared byte for byte.
+//!
+//! [`Canonical`] serializes boxes and transactions with the fields in the order the node emits
+//! them, lowercase hex, plain integers and context extensions sorted by variable id. Written
+//! with [`to_string`], the output is compact like the node's.
+
+use std::collections::BTreeMap;
+
+use serde::{
+    Serialize, Serializer,
+    ser::{Error, SerializeStruct},
+};
+
+use crate::{
+    chain::transaction,
+    types::{
+        HashDigest, HexBytes,
+        ergo::{
+            BoxCandidate, MinimalInput, SignedInput, SignedTransaction, SpendingProof,
+            TransactionInput, UTxO, UnconfirmedTransaction,
+        },
+    },
+};
+
+/// Serializes the wrapped value in the node's formatting.
+#[derive(Debug, Clone, Copy)]
+pub struct Canonical<'a, T: ?Sized>(pub &'a T);
+
+/// Compact canonical JSON of `value`.
+pub fn to_string<T: ?Sized>(value: &T) -> Result<String, serde_json::Error>
+where
+    for<'a> Canonical<'a, T>: Serialize,
+{
+    serde_json::to_string(&Canonical(value))
+}
+
+/// A list of values, each in canonical form.
+struct Seq<'a, T>(&'a [T]);
+
+impl<T> Serialize for Seq<'_, T>
+where
+    for<'a> Canonical<'a, T>: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(Canonical))
+    }
+}
+
+impl Serialize for Canonical<'_, UTxO> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let utxo = self.0;
+        let mut s = serializer.serialize_struct("ErgoBox", 8)?;
+        s.serialize_field("boxId", &utxo.id)?;
+        s.serialize_field("value", &utxo.value)?;
+        s.serialize_field("ergoTree", &utxo.ergo_tree)?;
+        s.serialize_field("assets", &utxo.tokens)?;
+        s.serialize_field("creationHeight", &utxo.creation_height)?;
+        s.serialize_field("additionalRegisters", &utxo.registers)?;
+        s.serialize_field("transactionId", &utxo.transaction_id)?;
+        s.serialize_field("index", &utxo.index)?;
+        s.end()
+    }
+}
+
+impl Serialize for Canonical<'_, BoxCandidate> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let candidate = self.0;
+        let mut s = serializer.serialize_struct("ErgoBoxCandidate", 5)?;
+        s.serialize_field("value", &candidate.value)?;
+        s.serialize_field("ergoTree", &candidate.ergo_tree)?;
+        s.serialize_field("assets", &candidate.tokens)?;
+        s.serialize_field("creationHeight", &candidate.creation_height)?;
+        s.serialize_field("additionalRegisters", &candidate.registers)?;
+        s.end()
+    }
+}
+
+impl Serialize for Canonical<'_, SpendingProof> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let proof = self.0;
+        let extension = proof
+            .extension
+            .iter()
+            .map(|(id, value)| {
+                let id: u8 = id.parse().map_err(|_| {
+                    S::Error::custom(format!("invalid context extension variable id `{id}`"))
+                })?;
+                Ok((id, value))
+            })
+            .collect::<Result<BTreeMap<u8, &HexBytes>, S::Error>>()?;
+        let mut s = serializer.serialize_struct("SpendingProof", 2)?;
+        s.serialize_field("proofBytes", &proof.proof_bytes)?;
+        s.serialize_field("extension", &extension)?;
+        s.end()
+    }
+}
+
+/// An input as the node emits it: the box id and its proof.
+struct Input<'a> {
+    box_id: &'a HashDigest,
+    proof: &'a SpendingProof,
+}
+
+impl Serialize for Input<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Input", 2)?;
+        s.serialize_field("boxId", self.box_id)?;
+        s.serialize_field("spendingProof", &Canonical(self.proof))?;
+        s.end()
+    }
+}
+
+impl Serialize for Canonical<'_, SignedInput> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Input { box_id: &self.0.box_id, proof: &self.0.spending_proof }.serialize(serializer)
+    }
+}
+
+impl Serialize for Canonical<'_, TransactionInput> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Input { box_id: &self.0.utxo.id, proof: &self.0.spending_proof }.serialize(serializer)
+    }
+}
+
+impl Serialize for Canonical<'_, MinimalInput> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("DataInput", 1)?;
+        s.serialize_field("boxId", &self.0.id)?;
+        s.end()
+    }
+}
+
+/// A transaction as accepted by the node's submission endpoints. The id is computed when
+/// missing.
+impl Serialize for Canonical<'_, SignedTransaction> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tx = self.0;
+        let id = match &tx.id {
+            Some(id) => id.clone(),
+            None => transaction::transaction_id(tx).map_err(S::Error::custom)?,
+        };
+        let mut s = serializer.serialize_struct("ErgoTransaction", 4)?;
+        s.serialize_field("id", &id)?;
+        s.serialize_field("inputs", &Seq(&tx.inputs))?;
+        s.serialize_field("dataInputs", &Seq(&tx.data_inputs))?;
+        s.serialize_field("outputs", &Seq(&tx.outputs))?;
+        s.end()
+    }
+}
+
+/// A mempool transaction as emitted by `transactions/unconfirmed`: inputs without their boxes
+/// and the serialized size.
+impl Serialize for Canonical<'_, UnconfirmedTransaction> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tx = self.0;
+        let size = transaction::unconfirmed_size(tx).map_err(S::Error::custom)?;
+        let mut s = serializer.serialize_struct("ErgoTransaction", 5)?;
+        s.serialize_field("id", &tx.id)?;
+        s.serialize_field("inputs", &Seq(&tx.inputs))?;
+        s.serialize_field("dataInputs", &Seq(&tx.data_inputs))?;
+        s.serialize_field("outputs", &Seq(&tx.outputs))?;
+        s.serialize_field("size", &size)?;
+        s.end()
+    }
+}
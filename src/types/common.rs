@@ -1,4 +1,7 @@
-use std::fmt::{self, Debug, Display, Formatter};
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    str::FromStr,
+};
 
 use hex::{FromHex, ToHex};
 use serde::de::Error;
@@ -8,7 +11,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub type HashDigest = Digest<32>;
 
 /// A fixed-size byte array represented as a hex string in serialization.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Digest<const N: usize>(pub [u8; N]);
 
 impl<const N: usize> Display for Digest<N> {
@@ -29,6 +32,16 @@ impl<const N: usize> Debug for Digest<N> {
     }
 }
 
+impl<const N: usize> FromStr for Digest<N> {
+    type Err = hex::FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = [0u8; N];
+        hex::decode_to_slice(s, &mut bytes)?;
+        Ok(Digest(bytes))
+    }
+}
+
 impl<'de, const N: usize> Deserialize<'de> for Digest<N> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -55,7 +68,7 @@ impl<const N: usize> Serialize for Digest<N> {
 }
 
 /// A byte vector represented as a hex string in serialization.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
 pub struct HexBytes(pub Vec<u8>);
 
 impl Display for HexBytes {
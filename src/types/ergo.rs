@@ -1,15 +1,61 @@
-use std::{collections::HashMap, str};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::types::{HashDigest, HexBytes};
+use crate::{
+    chain::{
+        extension::Extension,
+        register::{self, Constant, RegisterError, RegisterId, SigmaDecode},
+    },
+    codec::CodecError,
+    types::{Digest, HashDigest, Height, HexBytes, TimestampMillis},
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BlockHeader {
     pub id: HashDigest,
-    #[serde(rename = "parentId")]
     pub parent_id: HashDigest,
-    pub height: u32,
+    pub height: Height,
+    pub version: u8,
+    #[serde(rename = "adProofsRoot")]
+    pub ad_proofs_root: HashDigest,
+    pub transactions_root: HashDigest,
+    /// AVL+ tree root digest followed by the tree height byte.
+    pub state_root: Digest<33>,
+    pub timestamp: TimestampMillis,
+    #[serde(rename = "extensionHash")]
+    pub extension_root: HashDigest,
+    #[serde(rename = "nBits")]
+    pub n_bits: u32,
+    pub votes: HexBytes,
+    #[serde(default)]
+    pub unparsed_bytes: HexBytes,
+    #[serde(rename = "powSolutions")]
+    pub pow_solution: PowSolution,
+
+    #[serde(default)]
+    pub extension_id: Option<HashDigest>,
+    #[serde(rename = "adProofsId", default)]
+    pub ad_proofs_id: Option<HashDigest>,
+    #[serde(default)]
+    pub transactions_id: Option<HashDigest>,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub size: Option<u32>,
+}
+
+/// Autolykos solution. `w` and `d` are only meaningful for version 1 headers.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PowSolution {
+    pub pk: Digest<33>,
+    pub w: Digest<33>,
+    pub n: Digest<8>,
+    pub d: serde_json::Number,
 }
 
 #[derive(Debug, Deserialize)]
@@ -17,6 +63,8 @@ pub struct Block {
     pub header: BlockHeader,
     #[serde(rename = "blockTransactions")]
     pub transactions: BlockTransactions,
+    #[serde(default)]
+    pub extension: Option<Extension>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,7 +74,7 @@ pub struct BlockTransactions {
     pub transactions: Vec<BlockTransaction>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MinimalInput {
     #[serde(rename = "boxId")]
     pub id: HashDigest,
@@ -45,17 +93,98 @@ pub struct Transaction {
     pub inputs: Vec<TransactionInput>,
     pub outputs: Vec<UTxO>,
     #[serde(rename = "inclusionHeight")]
-    pub height: u32,
+    pub height: Height,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A confirmed transaction as returned by the blockchain indexer.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransaction {
+    pub id: HashDigest,
+    pub inclusion_height: Height,
+    #[serde(default)]
+    pub timestamp: TimestampMillis,
+    pub inputs: Vec<UTxO>,
+    pub outputs: Vec<UTxO>,
+}
+
+/// A box as returned by the blockchain indexer, with its spending status.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexedBox {
+    #[serde(flatten)]
+    pub utxo: UTxO,
+    #[serde(rename = "inclusionHeight")]
+    pub inclusion_height: Height,
+    #[serde(rename = "spentTransactionId", default)]
+    pub spent_transaction_id: Option<HashDigest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UnconfirmedTransaction {
     pub id: HashDigest,
     pub inputs: Vec<TransactionInput>,
+    #[serde(rename = "dataInputs", default)]
+    pub data_inputs: Vec<MinimalInput>,
     pub outputs: Vec<UTxO>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A signed transaction in the format accepted by the node's submission endpoints.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTransaction {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<HashDigest>,
+    pub inputs: Vec<SignedInput>,
+    #[serde(default)]
+    pub data_inputs: Vec<MinimalInput>,
+    pub outputs: Vec<BoxCandidate>,
+}
+
+/// A transaction whose inputs aren't signed yet.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsignedTransaction {
+    pub inputs: Vec<UnsignedInput>,
+    #[serde(default)]
+    pub data_inputs: Vec<MinimalInput>,
+    pub outputs: Vec<BoxCandidate>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnsignedInput {
+    #[serde(rename = "boxId")]
+    pub box_id: HashDigest,
+    /// Context extension variables, as serialized constants keyed by variable id.
+    #[serde(default)]
+    pub extension: BTreeMap<u8, HexBytes>,
+}
+
+impl UnsignedInput {
+    pub fn new(box_id: HashDigest) -> Self {
+        Self { box_id, extension: BTreeMap::new() }
+    }
+
+    /// Sets context extension variable `id`.
+    pub fn with_extension_var(mut self, id: u8, value: impl Into<Constant>) -> Self {
+        self.extension.insert(id, value.into().encode());
+        self
+    }
+
+    /// Decodes context extension variable `id`, if set.
+    pub fn extension_var(&self, id: u8) -> Option<Result<Constant, CodecError>> {
+        self.extension.get(&id).map(|v| Constant::decode(&v.0))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignedInput {
+    #[serde(rename = "boxId")]
+    pub box_id: HashDigest,
+    #[serde(rename = "spendingProof")]
+    pub spending_proof: SpendingProof,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TransactionInput {
     #[serde(flatten)]
     pub utxo: UTxO,
@@ -63,14 +192,51 @@ pub struct TransactionInput {
     pub spending_proof: SpendingProof,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SpendingProof {
     #[serde(rename = "proofBytes")]
     pub proof_bytes: HexBytes,
+    #[serde(default)]
     pub extension: HashMap<String, HexBytes>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl SpendingProof {
+    pub fn new(proof_bytes: HexBytes) -> Self {
+        Self { proof_bytes, extension: HashMap::new() }
+    }
+
+    /// Sets context extension variable `id`.
+    pub fn with_extension_var(mut self, id: u8, value: impl Into<Constant>) -> Self {
+        self.set_extension_var(id, value);
+        self
+    }
+
+    pub fn set_extension_var(&mut self, id: u8, value: impl Into<Constant>) {
+        self.extension.insert(id.to_string(), value.into().encode());
+    }
+
+    /// Decodes context extension variable `id`, if set.
+    pub fn extension_var(&self, id: u8) -> Option<Result<Constant, CodecError>> {
+        self.extension
+            .get(&id.to_string())
+            .map(|v| Constant::decode(&v.0))
+    }
+
+    /// All context extension variables in id order. Fails on keys that aren't variable ids.
+    pub fn extension_vars(&self) -> Result<BTreeMap<u8, Constant>, CodecError> {
+        self.extension
+            .iter()
+            .map(|(id, v)| {
+                let id = id
+                    .parse()
+                    .map_err(|_| CodecError("invalid context extension variable id"))?;
+                Ok((id, Constant::decode(&v.0)?))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UTxO {
     #[serde(rename = "boxId")]
     pub id: HashDigest,
@@ -79,14 +245,14 @@ pub struct UTxO {
     pub ergo_tree: HexBytes,
 
     #[serde(rename = "creationHeight")]
-    pub creation_height: u32,
+    pub creation_height: Height,
 
     pub value: u64,
 
-    #[serde(rename = "assets")]
+    #[serde(rename = "assets", default)]
     pub tokens: Vec<Token>,
 
-    #[serde(rename = "additionalRegisters")]
+    #[serde(rename = "additionalRegisters", default)]
     pub registers: NonMandatoryRegisters,
 
     pub index: u16,
@@ -95,25 +261,88 @@ pub struct UTxO {
     pub transaction_id: HashDigest,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl UTxO {
+    /// Decodes register `id` as a `T`, e.g. `utxo.register::<i64>(RegisterId::R4)`.
+    pub fn register<T: SigmaDecode>(&self, id: RegisterId) -> Result<T, RegisterError> {
+        self.registers.decode(id)
+    }
+}
+
+/// An output that hasn't been included in a transaction yet, so it has no id.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BoxCandidate {
+    #[serde(rename = "ergoTree")]
+    pub ergo_tree: HexBytes,
+
+    #[serde(rename = "creationHeight")]
+    pub creation_height: Height,
+
+    pub value: u64,
+
+    #[serde(rename = "assets", default)]
+    pub tokens: Vec<Token>,
+
+    #[serde(rename = "additionalRegisters", default)]
+    pub registers: NonMandatoryRegisters,
+}
+
+impl BoxCandidate {
+    /// Decodes register `id` as a `T`, see [`UTxO::register`].
+    pub fn register<T: SigmaDecode>(&self, id: RegisterId) -> Result<T, RegisterError> {
+        self.registers.decode(id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Token {
     #[serde(rename = "tokenId")]
     pub id: HashDigest,
     pub amount: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Token metadata as returned by the blockchain indexer.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenInfo {
+    pub id: HashDigest,
+    /// Box that minted the token.
+    pub box_id: HashDigest,
+    pub emission_amount: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub decimals: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct NonMandatoryRegisters {
-    #[serde(rename = "R4")]
+    #[serde(rename = "R4", skip_serializing_if = "Option::is_none")]
     pub r4: Option<HexBytes>,
-    #[serde(rename = "R5")]
+    #[serde(rename = "R5", skip_serializing_if = "Option::is_none")]
     pub r5: Option<HexBytes>,
-    #[serde(rename = "R6")]
+    #[serde(rename = "R6", skip_serializing_if = "Option::is_none")]
     pub r6: Option<HexBytes>,
-    #[serde(rename = "R7")]
+    #[serde(rename = "R7", skip_serializing_if = "Option::is_none")]
     pub r7: Option<HexBytes>,
-    #[serde(rename = "R8")]
+    #[serde(rename = "R8", skip_serializing_if = "Option::is_none")]
     pub r8: Option<HexBytes>,
-    #[serde(rename = "R9")]
+    #[serde(rename = "R9", skip_serializing_if = "Option::is_none")]
     pub r9: Option<HexBytes>,
 }
+
+impl NonMandatoryRegisters {
+    /// Registers in order from R4 to R9.
+    pub fn as_array(&self) -> [Option<&HexBytes>; 6] {
+        [&self.r4, &self.r5, &self.r6, &self.r7, &self.r8, &self.r9].map(Option::as_ref)
+    }
+
+    pub fn get(&self, id: RegisterId) -> Option<&HexBytes> {
+        self.as_array()[(id.number() - 4) as usize]
+    }
+
+    pub fn decode<T: SigmaDecode>(&self, id: RegisterId) -> Result<T, RegisterError> {
+        register::decode_register(id, self.get(id))
+    }
+}
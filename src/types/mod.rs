@@ -1,4 +1,7 @@
 mod common;
 pub use common::*;
+mod time;
+pub use time::*;
 
+pub mod canonical;
 pub mod ergo;
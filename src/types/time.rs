@@ -0,0 +1,198 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    num::ParseIntError,
+    ops::{Add, AddAssign, Sub},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A block height.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Height(pub u32);
+
+impl Height {
+    pub const fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// Blocks from `earlier` to this height, 0 if `earlier` is above it.
+    pub const fn blocks_since(self, earlier: Height) -> u32 {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    pub fn checked_sub(self, blocks: u32) -> Option<Self> {
+        self.0.checked_sub(blocks).map(Self)
+    }
+
+    pub fn saturating_sub(self, blocks: u32) -> Self {
+        Self(self.0.saturating_sub(blocks))
+    }
+
+    /// Heights from this one up to and including `last`.
+    pub fn up_to(self, last: Height) -> impl DoubleEndedIterator<Item = Height> {
+        (self.0..=last.0).map(Height)
+    }
+}
+
+impl Add<u32> for Height {
+    type Output = Height;
+
+    fn add(self, blocks: u32) -> Height {
+        Height(self.0 + blocks)
+    }
+}
+
+impl AddAssign<u32> for Height {
+    fn add_assign(&mut self, blocks: u32) {
+        self.0 += blocks;
+    }
+}
+
+impl Sub<u32> for Height {
+    type Output = Height;
+
+    fn sub(self, blocks: u32) -> Height {
+        Height(self.0 - blocks)
+    }
+}
+
+impl From<u32> for Height {
+    fn from(height: u32) -> Self {
+        Self(height)
+    }
+}
+
+impl From<Height> for u32 {
+    fn from(height: Height) -> u32 {
+        height.0
+    }
+}
+
+impl PartialEq<u32> for Height {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Display for Height {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Debug for Height {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for Height {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+/// Milliseconds since the Unix epoch, as in block headers and the node's mempool timestamps.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TimestampMillis(pub u64);
+
+impl TimestampMillis {
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(secs * 1000)
+    }
+
+    pub const fn as_secs(self) -> u64 {
+        self.0 / 1000
+    }
+
+    /// Time from `earlier` to this timestamp, zero if `earlier` is later.
+    pub const fn since(self, earlier: TimestampMillis) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+        self.0.checked_sub(duration.as_millis() as u64).map(Self)
+    }
+
+    pub fn saturating_sub(self, duration: Duration) -> Self {
+        Self(self.0.saturating_sub(duration.as_millis() as u64))
+    }
+
+    pub fn to_system_time(self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.0)
+    }
+}
+
+impl Add<Duration> for TimestampMillis {
+    type Output = TimestampMillis;
+
+    fn add(self, duration: Duration) -> TimestampMillis {
+        TimestampMillis(self.0 + duration.as_millis() as u64)
+    }
+}
+
+impl Sub<Duration> for TimestampMillis {
+    type Output = TimestampMillis;
+
+    fn sub(self, duration: Duration) -> TimestampMillis {
+        TimestampMillis(self.0 - duration.as_millis() as u64)
+    }
+}
+
+/// Times before the Unix epoch map to the epoch.
+impl From<SystemTime> for TimestampMillis {
+    fn from(time: SystemTime) -> Self {
+        Self(
+            time.duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+        )
+    }
+}
+
+impl From<u64> for TimestampMillis {
+    fn from(millis: u64) -> Self {
+        Self(millis)
+    }
+}
+
+impl From<TimestampMillis> for u64 {
+    fn from(timestamp: TimestampMillis) -> u64 {
+        timestamp.0
+    }
+}
+
+impl PartialEq<u64> for TimestampMillis {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl Display for TimestampMillis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Debug for TimestampMillis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for TimestampMillis {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chain::{
+        avl::{self, AvlError},
+        ergo_box,
+    },
+    codec::CodecError,
+    types::{
+        HashDigest, Height,
+        ergo::{Block, BlockHeader, UTxO},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum UtxoError {
+    #[error(transparent)]
+    Avl(#[from] AvlError),
+
+    #[error("Malformed box in UTXO set snapshot: {0}")]
+    Codec(#[from] CodecError),
+
+    #[error("Block {actual} at height {height} doesn't extend the tracked tip {expected}.")]
+    NotNextBlock { expected: HashDigest, actual: HashDigest, height: Height },
+
+    #[error("Input {0} is not in the UTXO set.")]
+    MissingInput(HashDigest),
+}
+
+/// The set of unspent boxes at a block, kept up to date by applying the following blocks.
+///
+/// Instead of replaying the chain from genesis, the set is bootstrapped from a snapshot at a
+/// checkpoint header, verified against that header's state root.
+#[derive(Debug, Clone)]
+pub struct UtxoSet {
+    header_id: HashDigest,
+    height: Height,
+    boxes: HashMap<HashDigest, UTxO>,
+}
+
+impl UtxoSet {
+    /// Loads a UTXO set dump taken at `checkpoint`; see [`avl::verify_dump`] for its format.
+    pub fn from_snapshot(checkpoint: &BlockHeader, dump: &[u8]) -> Result<Self, UtxoError> {
+        let boxes = avl::verify_dump(&checkpoint.state_root, dump)?
+            .into_iter()
+            .map(|(id, bytes)| Ok((id, ergo_box::parse(&bytes)?)))
+            .collect::<Result<_, UtxoError>>()?;
+
+        Ok(Self { header_id: checkpoint.id.clone(), height: checkpoint.height, boxes })
+    }
+
+    /// Id of the last applied block.
+    pub fn header_id(&self) -> &HashDigest {
+        &self.header_id
+    }
+
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    pub fn get(&self, box_id: &HashDigest) -> Option<&UTxO> {
+        self.boxes.get(box_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UTxO> {
+        self.boxes.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// Spends the inputs and adds the outputs of the block following the tracked tip. The set
+    /// is left unchanged on error.
+    pub fn apply_block(&mut self, block: &Block) -> Result<(), UtxoError> {
+        let header = &block.header;
+        if header.parent_id != self.header_id {
+            return Err(UtxoError::NotNextBlock {
+                expected: self.header_id.clone(),
+                actual: header.id.clone(),
+                height: header.height,
+            });
+        }
+
+        let transactions = &block.transactions.transactions;
+        let mut created: HashMap<&HashDigest, &UTxO> = HashMap::new();
+        let mut spent: HashSet<&HashDigest> = HashSet::new();
+        for tx in transactions {
+            for input in &tx.inputs {
+                // Outputs may be spent later in the same block.
+                let unspent = created.remove(&input.id).is_some()
+                    || (self.boxes.contains_key(&input.id) && spent.insert(&input.id));
+                if !unspent {
+                    return Err(UtxoError::MissingInput(input.id.clone()));
+                }
+            }
+            created.extend(tx.outputs.iter().map(|o| (&o.id, o)));
+        }
+
+        for id in spent {
+            self.boxes.remove(id);
+        }
+        self.boxes.extend(
+            created
+                .into_iter()
+                .map(|(id, utxo)| (id.clone(), utxo.clone())),
+        );
+        self.header_id = header.id.clone();
+        self.height = header.height;
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use std::str::FromStr;
+
+use hmac::{Hmac, Mac};
+use k256::{
+    AffinePoint, ProjectivePoint, PublicKey, Scalar,
+    elliptic_curve::{PrimeField, sec1::ToEncodedPoint},
+};
+use sha2::Sha512;
+
+use crate::{
+    address::{ErgoAddress, NetworkPrefix},
+    hash::sha256,
+};
+
+/// Version bytes of mainnet and testnet extended public keys.
+const XPUB_VERSIONS: [[u8; 4]; 2] = [[0x04, 0x88, 0xb2, 0x1e], [0x04, 0x35, 0x87, 0xcf]];
+const XPUB_LEN: usize = 78;
+const HARDENED: u32 = 1 << 31;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HdError {
+    #[error("Invalid extended public key: {0}")]
+    InvalidKey(&'static str),
+
+    #[error("Cannot derive hardened child {0} from a public key.")]
+    Hardened(u32),
+
+    #[error("Child {0} is not a valid key; skip to the next index.")]
+    InvalidChild(u32),
+}
+
+/// A BIP32 extended public key, e.g. the account key at EIP-3 path `m/44'/429'/0'`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    pub depth: u8,
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    /// Compressed secp256k1 point.
+    pub public_key: [u8; 33],
+}
+
+impl ExtendedPublicKey {
+    /// Derives the non-hardened child at `index`.
+    pub fn derive_child(&self, index: u32) -> Result<Self, HdError> {
+        if index >= HARDENED {
+            return Err(HdError::Hardened(index));
+        }
+
+        let mut mac = Hmac::<Sha512>::new_from_slice(&self.chain_code).expect("any key length");
+        mac.update(&self.public_key);
+        mac.update(&index.to_be_bytes());
+        let i = mac.finalize().into_bytes();
+        let (il, ir) = i.split_at(32);
+
+        let il: [u8; 32] = il.try_into().unwrap();
+        let tweak: Option<Scalar> = Scalar::from_repr(il.into()).into();
+        let tweak = tweak.ok_or(HdError::InvalidChild(index))?;
+        let parent = PublicKey::from_sec1_bytes(&self.public_key)
+            .map_err(|_| HdError::InvalidKey("public key is not on the curve"))?;
+        let child = ProjectivePoint::GENERATOR * tweak + parent.to_projective();
+        if child == ProjectivePoint::IDENTITY {
+            return Err(HdError::InvalidChild(index));
+        }
+
+        let encoded = AffinePoint::from(child).to_encoded_point(true);
+        Ok(Self {
+            depth: self.depth.saturating_add(1),
+            child_number: index,
+            chain_code: ir.try_into().unwrap(),
+            public_key: encoded.as_bytes().try_into().unwrap(),
+        })
+    }
+
+    /// Derives a chain of non-hardened children, e.g. `[0, 5]` for `0/5`.
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, HdError> {
+        path.iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    pub fn address(&self, network: NetworkPrefix) -> ErgoAddress {
+        ErgoAddress::p2pk(network, &self.public_key)
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = HdError;
+
+    /// Parses a base58check-encoded `xpub`/`tpub`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = crate::address::base58::decode(s)
+            .map_err(|_| HdError::InvalidKey("not valid base58"))?;
+        if bytes.len() != XPUB_LEN + 4 {
+            return Err(HdError::InvalidKey("unexpected length"));
+        }
+
+        let (payload, checksum) = bytes.split_at(XPUB_LEN);
+        if sha256(&sha256(payload))[..4] != *checksum {
+            return Err(HdError::InvalidKey("checksum mismatch"));
+        }
+        if !XPUB_VERSIONS.iter().any(|v| payload[..4] == *v) {
+            return Err(HdError::InvalidKey("not an extended public key"));
+        }
+
+        let public_key: [u8; 33] = payload[45..78].try_into().unwrap();
+        PublicKey::from_sec1_bytes(&public_key)
+            .map_err(|_| HdError::InvalidKey("public key is not on the curve"))?;
+
+        Ok(Self {
+            depth: payload[4],
+            child_number: u32::from_be_bytes(payload[9..13].try_into().unwrap()),
+            chain_code: payload[13..45].try_into().unwrap(),
+            public_key,
+        })
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use crate::{
+    address::ErgoAddress,
+    chain::{fee::outputs_fee, value::ValueError},
+    tokens::format_units,
+    types::{HashDigest, Height, HexBytes, TimestampMillis, ergo::IndexedTransaction},
+};
+
+const ERG_DECIMALS: u32 = 9;
+const FEES_ACCOUNT: &str = "Expenses:Ergo:Fees";
+const PAYMENTS_ACCOUNT: &str = "Expenses:Ergo:Payments";
+const RECEIPTS_ACCOUNT: &str = "Income:Ergo:Receipts";
+/// Longest commodity symbol Beancount accepts.
+const MAX_SYMBOL_LEN: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Commodity {
+    Erg,
+    Token(HashDigest),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Posting {
+    pub account: String,
+    pub commodity: Commodity,
+    /// Signed amount in base units: nanoErgs or raw token units.
+    pub amount: i128,
+}
+
+/// A balanced journal entry: postings of each commodity sum to zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub tx_id: HashDigest,
+    pub height: Height,
+    pub timestamp: TimestampMillis,
+    pub postings: Vec<Posting>,
+}
+
+#[derive(Debug, Clone)]
+struct TokenFormat {
+    symbol: String,
+    decimals: u32,
+}
+
+/// Double-entry ledger of the transactions affecting a set of owned addresses.
+///
+/// Each owned address is an `Assets:Ergo:<address>` account. The miner fee of transactions
+/// spending owned boxes goes to `Expenses:Ergo:Fees`, and the net amounts exchanged with
+/// other addresses to `Expenses:Ergo:Payments` or `Income:Ergo:Receipts`.
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    owned: HashMap<HexBytes, String>,
+    tokens: HashMap<HashDigest, TokenFormat>,
+    entries: Vec<Entry>,
+}
+
+impl Ledger {
+    /// P2SH addresses are ignored, since their boxes can't be recognized by ErgoTree.
+    pub fn new(addresses: &[ErgoAddress]) -> Self {
+        let owned = addresses
+            .iter()
+            .filter_map(|a| Some((a.ergo_tree()?, format!("Assets:Ergo:{a}"))))
+            .collect();
+        Self { owned, tokens: HashMap::new(), entries: Vec::new() }
+    }
+
+    /// Commodity symbol and decimals used for a token. Unnamed tokens are written as `T`
+    /// followed by the first 8 hex digits of their id, without decimals.
+    ///
+    /// Token names are chosen by their minters, so the symbol is uppercased and stripped of
+    /// characters Beancount doesn't accept in commodities; a symbol left empty falls back to the
+    /// unnamed form.
+    pub fn token(mut self, token_id: HashDigest, symbol: &str, decimals: u32) -> Self {
+        let symbol = sanitize_symbol(symbol)
+            .unwrap_or_else(|| format!("T{}", &token_id.to_string()[..8]).to_uppercase());
+        self.tokens
+            .insert(token_id, TokenFormat { symbol, decimals });
+        self
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Records a transaction, returning its entry if it affects an owned address.
+    pub fn record(&mut self, tx: &IndexedTransaction) -> Result<Option<&Entry>, ValueError> {
+        let mut own: BTreeMap<(&str, Commodity), i128> = BTreeMap::new();
+        let mut spends_own = false;
+        for (utxo, sign) in tx
+            .inputs
+            .iter()
+            .map(|i| (i, -1))
+            .chain(tx.outputs.iter().map(|o| (o, 1)))
+        {
+            let Some(account) = self.owned.get(&utxo.ergo_tree) else {
+                continue;
+            };
+            spends_own |= sign < 0;
+            *own.entry((account, Commodity::Erg)).or_default() += sign * utxo.value as i128;
+            for token in &utxo.tokens {
+                let commodity = Commodity::Token(token.id.clone());
+                *own.entry((account, commodity)).or_default() += sign * token.amount as i128;
+            }
+        }
+        own.retain(|_, amount| *amount != 0);
+        if own.is_empty() {
+            return Ok(None);
+        }
+
+        let mut postings: Vec<Posting> = own
+            .iter()
+            .map(|((account, commodity), amount)| Posting {
+                account: account.to_string(),
+                commodity: commodity.clone(),
+                amount: *amount,
+            })
+            .collect();
+
+        let mut net: BTreeMap<Commodity, i128> = BTreeMap::new();
+        for ((_, commodity), amount) in own {
+            *net.entry(commodity).or_default() += amount;
+        }
+        let fee = i128::from(outputs_fee(&tx.outputs)?);
+        if spends_own && fee > 0 {
+            postings.push(posting(FEES_ACCOUNT, Commodity::Erg, fee));
+            *net.entry(Commodity::Erg).or_default() += fee;
+        }
+        for (commodity, amount) in net.into_iter().filter(|(_, amount)| *amount != 0) {
+            let account = if amount < 0 { PAYMENTS_ACCOUNT } else { RECEIPTS_ACCOUNT };
+            postings.push(posting(account, commodity, -amount));
+        }
+
+        self.entries.push(Entry {
+            tx_id: tx.id.clone(),
+            height: tx.inclusion_height,
+            timestamp: tx.timestamp,
+            postings,
+        });
+        Ok(self.entries.last())
+    }
+
+    /// Writes the entries in Beancount syntax, preceded by account openings dated on each
+    /// account's earliest entry.
+    pub fn write_beancount(&self, mut out: impl Write) -> io::Result<()> {
+        for (account, opened) in self.accounts() {
+            writeln!(out, "{} open {account}", date(opened, '-'))?;
+        }
+
+        for entry in &self.entries {
+            writeln!(out)?;
+            writeln!(out, "{} * \"Transaction {}\"", date(entry.timestamp, '-'), entry.tx_id)?;
+            writeln!(out, "  tx: \"{}\"", entry.tx_id)?;
+            writeln!(out, "  height: {}", entry.height)?;
+            self.write_postings(&mut out, entry, false)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the entries in ledger-cli syntax. Symbols with characters other than letters are
+    /// quoted, since ledger-cli would read digits and dots as part of the amount.
+    pub fn write_ledger(&self, mut out: impl Write) -> io::Result<()> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "{} Transaction {}", date(entry.timestamp, '/'), entry.tx_id)?;
+            writeln!(out, "    ; height: {}", entry.height)?;
+            self.write_postings(&mut out, entry, true)?;
+        }
+        Ok(())
+    }
+
+    fn write_postings(&self, out: &mut impl Write, entry: &Entry, quote: bool) -> io::Result<()> {
+        for posting in &entry.postings {
+            let (mut symbol, decimals) = self.format(&posting.commodity);
+            if quote && !symbol.chars().all(|c| c.is_ascii_alphabetic()) {
+                symbol = format!("\"{symbol}\"");
+            }
+            let amount = format_units(posting.amount, decimals);
+            writeln!(out, "  {:<60} {amount} {symbol}", posting.account)?;
+        }
+        Ok(())
+    }
+
+    /// Every posted account, with the timestamp of its earliest entry. Entries may be recorded
+    /// in any order, e.g. newest first as the indexer pages address history.
+    fn accounts(&self) -> BTreeMap<&str, TimestampMillis> {
+        let mut accounts: BTreeMap<&str, TimestampMillis> = BTreeMap::new();
+        for entry in &self.entries {
+            for posting in &entry.postings {
+                accounts
+                    .entry(posting.account.as_str())
+                    .and_modify(|opened| *opened = (*opened).min(entry.timestamp))
+                    .or_insert(entry.timestamp);
+            }
+        }
+        accounts
+    }
+
+    fn format(&self, commodity: &Commodity) -> (String, u32) {
+        match commodity {
+            Commodity::Erg => ("ERG".to_string(), ERG_DECIMALS),
+            Commodity::Token(id) => match self.tokens.get(id) {
+                Some(format) => (format.symbol.clone(), format.decimals),
+                None => (format!("T{}", &id.to_string()[..8]).to_uppercase(), 0),
+            },
+        }
+    }
+}
+
+fn posting(account: &str, commodity: Commodity, amount: i128) -> Posting {
+    Posting { account: account.to_string(), commodity, amount }
+}
+
+/// `symbol` as a Beancount commodity: an uppercase letter, then up to 23 uppercase letters,
+/// digits or `'._-`, ending with a letter or digit.
+fn sanitize_symbol(symbol: &str) -> Option<String> {
+    let mut sanitized: String = symbol
+        .chars()
+        .map(|c| c.to_ascii_uppercase())
+        .filter(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || "'._-".contains(*c))
+        .skip_while(|c| !c.is_ascii_alphanumeric())
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, 'T');
+    }
+    sanitized.truncate(MAX_SYMBOL_LEN);
+    let end = sanitized
+        .trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+        .len();
+    sanitized.truncate(end);
+    (!sanitized.is_empty()).then_some(sanitized)
+}
+
+/// UTC calendar date of a timestamp.
+fn date(timestamp: TimestampMillis, separator: char) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`.
+    let days = (timestamp.0 / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}{separator}{month:02}{separator}{day:02}")
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    address::ErgoAddress,
+    clients::node::{NodeClient, NodeError},
+    clock::{self, Clock},
+    types::{HashDigest, ergo::UTxO},
+    wallet::spendable::get_spendable_boxes,
+    watcher::MempoolSnapshot,
+};
+
+/// How long a kept reservation outlives its transaction's submission by default, enough for the
+/// transaction to show up in the next mempool snapshot.
+pub const DEFAULT_LOCK_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    #[error("Box {0} is reserved by another transaction")]
+    Reserved(HashDigest),
+}
+
+#[derive(Debug)]
+struct Lock {
+    owner: u64,
+    expires: Instant,
+}
+
+/// Reserves boxes for concurrent transaction-building tasks spending from the same wallet, so
+/// that they don't pick the same inputs. Clones share the reservations.
+#[derive(Debug, Clone)]
+pub struct BoxLockManager {
+    locks: Arc<Mutex<HashMap<HashDigest, Lock>>>,
+    next_owner: Arc<AtomicU64>,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for BoxLockManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCK_TTL)
+    }
+}
+
+impl BoxLockManager {
+    /// Reservations expire `ttl` after being taken, even if never released.
+    pub fn new(ttl: Duration) -> Self {
+        Self { locks: Arc::default(), next_owner: Arc::default(), ttl, clock: clock::system() }
+    }
+
+    /// Expires reservations by `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Reserves all of `ids`, or none if any is already reserved.
+    pub fn try_reserve(&self, ids: &[HashDigest]) -> Result<BoxReservation, LockError> {
+        let now = self.clock.now();
+        let mut locks = self.locks.lock().unwrap();
+        locks.retain(|_, lock| lock.expires > now);
+        if let Some(id) = ids.iter().find(|id| locks.contains_key(*id)) {
+            return Err(LockError::Reserved(id.clone()));
+        }
+
+        let owner = self.next_owner.fetch_add(1, Ordering::Relaxed);
+        for id in ids {
+            locks.insert(id.clone(), Lock { owner, expires: now + self.ttl });
+        }
+        Ok(BoxReservation { manager: self.clone(), owner, ids: ids.to_vec() })
+    }
+
+    pub fn is_reserved(&self, id: &HashDigest) -> bool {
+        let locks = self.locks.lock().unwrap();
+        locks
+            .get(id)
+            .is_some_and(|lock| lock.expires > self.clock.now())
+    }
+
+    /// Drops reserved boxes from `boxes`.
+    pub fn available(&self, boxes: Vec<UTxO>) -> Vec<UTxO> {
+        boxes
+            .into_iter()
+            .filter(|utxo| !self.is_reserved(&utxo.id))
+            .collect()
+    }
+
+    /// [`get_spendable_boxes`] without the boxes reserved by other tasks.
+    pub async fn spendable_boxes(
+        &self,
+        node: &NodeClient,
+        address: &ErgoAddress,
+        mempool: &MempoolSnapshot,
+    ) -> Result<Vec<UTxO>, NodeError> {
+        let boxes = get_spendable_boxes(node, address, mempool).await?;
+        Ok(self.available(boxes))
+    }
+
+    fn release(&self, owner: u64, ids: &[HashDigest]) {
+        let mut locks = self.locks.lock().unwrap();
+        for id in ids {
+            if locks.get(id).is_some_and(|lock| lock.owner == owner) {
+                locks.remove(id);
+            }
+        }
+    }
+}
+
+/// Boxes reserved by [`BoxLockManager::try_reserve`], released when dropped.
+#[derive(Debug)]
+pub struct BoxReservation {
+    manager: BoxLockManager,
+    owner: u64,
+    ids: Vec<HashDigest>,
+}
+
+impl BoxReservation {
+    pub fn ids(&self) -> &[HashDigest] {
+        &self.ids
+    }
+
+    /// Keeps the boxes reserved until the reservation expires, e.g. once the transaction
+    /// spending them is submitted but not yet in the mempool snapshot.
+    pub fn keep(mut self) {
+        self.ids.clear();
+    }
+}
+
+impl Drop for BoxReservation {
+    fn drop(&mut self) {
+        self.manager.release(self.owner, &self.ids);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod hd;
+pub mod ledger;
+pub mod locks;
+pub mod pending;
+pub mod spendable;
+pub mod sync;
@@ -0,0 +1,127 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    chain::transaction,
+    codec::CodecError,
+    types::{
+        HashDigest, HexBytes,
+        ergo::{SignedTransaction, UTxO},
+    },
+    watcher::MempoolSnapshot,
+};
+
+/// Syncs a submitted transaction may stay out of the mempool before it is considered dropped.
+pub const MAX_UNSEEN_SYNCS: u32 = 5;
+
+#[derive(Debug)]
+struct Pending {
+    inputs: Vec<HashDigest>,
+    outputs: Vec<UTxO>,
+    seen: bool,
+    unseen_syncs: u32,
+}
+
+/// Transactions submitted by this process and not yet confirmed, whose outputs, e.g. change,
+/// can be spent right away by the next transaction instead of waiting for a block.
+///
+/// Output ids are computed locally, so chained transactions can be built before the parent
+/// shows up in a mempool snapshot. When a parent leaves the mempool without being confirmed,
+/// [`sync`](Self::sync) drops it together with every transaction built on it.
+#[derive(Debug, Default)]
+pub struct PendingChain {
+    transactions: HashMap<HashDigest, Pending>,
+}
+
+impl PendingChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a submitted transaction, returning its outputs.
+    pub fn push(&mut self, tx: &SignedTransaction) -> Result<Vec<UTxO>, CodecError> {
+        let outputs = transaction::output_boxes(tx)?;
+        let Some(first) = outputs.first() else {
+            return Err(CodecError("transaction has no outputs"));
+        };
+        let id = first.transaction_id.clone();
+        let inputs = tx.inputs.iter().map(|i| i.box_id.clone()).collect();
+        let pending = Pending { inputs, outputs: outputs.clone(), seen: false, unseen_syncs: 0 };
+        self.transactions.insert(id, pending);
+        Ok(outputs)
+    }
+
+    pub fn contains(&self, tx_id: &HashDigest) -> bool {
+        self.transactions.contains_key(tx_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Boxes spent by pending transactions.
+    pub fn spent(&self) -> HashSet<&HashDigest> {
+        self.transactions
+            .values()
+            .flat_map(|tx| &tx.inputs)
+            .collect()
+    }
+
+    /// Outputs guarded by `ergo_tree` that no pending transaction spends yet.
+    pub fn spendable(&self, ergo_tree: &HexBytes) -> Vec<&UTxO> {
+        let spent = self.spent();
+        self.transactions
+            .values()
+            .flat_map(|tx| &tx.outputs)
+            .filter(|utxo| utxo.ergo_tree == *ergo_tree && !spent.contains(&utxo.id))
+            .collect()
+    }
+
+    /// Forgets transactions included in a block; their outputs are now confirmed boxes.
+    /// Call it for each new block before [`sync`](Self::sync), or confirmed transactions look
+    /// evicted.
+    pub fn confirm<'a>(&mut self, tx_ids: impl IntoIterator<Item = &'a HashDigest>) {
+        for id in tx_ids {
+            self.transactions.remove(id);
+        }
+    }
+
+    /// Checks pending transactions against a new mempool snapshot. Transactions that left the
+    /// mempool, or never entered it after [`MAX_UNSEEN_SYNCS`] syncs, are dropped along with
+    /// their descendants, whose ids are all returned.
+    pub fn sync(&mut self, mempool: &MempoolSnapshot) -> Vec<HashDigest> {
+        let in_mempool: HashSet<&HashDigest> =
+            mempool.transactions.iter().map(|tx| &tx.id).collect();
+
+        let mut dropped = Vec::new();
+        for (id, tx) in &mut self.transactions {
+            if in_mempool.contains(id) {
+                tx.seen = true;
+                continue;
+            }
+            tx.unseen_syncs += 1;
+            if tx.seen || tx.unseen_syncs > MAX_UNSEEN_SYNCS {
+                dropped.push(id.clone());
+            }
+        }
+
+        let mut invalidated = Vec::new();
+        while let Some(id) = dropped.pop() {
+            let Some(tx) = self.transactions.remove(&id) else {
+                continue;
+            };
+            let outputs: HashSet<&HashDigest> = tx.outputs.iter().map(|o| &o.id).collect();
+            dropped.extend(
+                self.transactions
+                    .iter()
+                    .filter(|(_, child)| child.inputs.iter().any(|i| outputs.contains(i)))
+                    .map(|(child_id, _)| child_id.clone()),
+            );
+            invalidated.push(id);
+        }
+        invalidated
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use crate::{
+    address::ErgoAddress,
+    clients::node::{BoxQuery, NodeClient, NodeError, SortOrder},
+    types::{HashDigest, ergo::UTxO},
+    watcher::MempoolSnapshot,
+};
+
+const PAGE_SIZE: u32 = 500;
+
+/// Boxes of `address` that can be spent right now: confirmed unspent boxes not already spent by
+/// a transaction in `mempool`, followed by mempool outputs to `address`, e.g. change, that no
+/// other mempool transaction spends.
+///
+/// Building transactions from the confirmed set alone picks inputs that a pending transaction
+/// already spent, which the node then rejects as double spends. Unconfirmed outputs of P2SH
+/// addresses are not found, as their tree is unknown.
+#[tracing::instrument(skip(node, mempool))]
+pub async fn get_spendable_boxes(
+    node: &NodeClient,
+    address: &ErgoAddress,
+    mempool: &MempoolSnapshot,
+) -> Result<Vec<UTxO>, NodeError> {
+    let spent: HashSet<&HashDigest> = mempool
+        .transactions
+        .iter()
+        .flat_map(|tx| &tx.inputs)
+        .map(|input| &input.utxo.id)
+        .collect();
+
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+    loop {
+        let query = BoxQuery::new()
+            .sort(SortOrder::Asc)
+            .offset(offset)
+            .limit(PAGE_SIZE);
+        let page = node.get_unspent_boxes_by_address(address, &query).await?;
+        if page.items.is_empty() {
+            break;
+        }
+        offset += page.items.len() as u64;
+        boxes.extend(
+            page.items
+                .into_iter()
+                .map(|indexed| indexed.utxo)
+                .filter(|utxo| !spent.contains(&utxo.id)),
+        );
+
+        if offset >= page.total {
+            break;
+        }
+    }
+
+    if let Some(tree) = address.ergo_tree() {
+        let confirmed: HashSet<HashDigest> = boxes.iter().map(|b| b.id.clone()).collect();
+        let unconfirmed = mempool
+            .transactions_for_tree(&tree.0)
+            .into_iter()
+            .flat_map(|tx| &tx.outputs)
+            .filter(|utxo| utxo.ergo_tree == tree)
+            .filter(|utxo| !spent.contains(&utxo.id) && !confirmed.contains(&utxo.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        boxes.extend(unconfirmed);
+    }
+
+    Ok(boxes)
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use tokio::{sync::mpsc, time::sleep};
+use tracing::info;
+
+use crate::{
+    address::{AddressSet, ErgoAddress, NetworkPrefix},
+    analytics::assets::asset_deltas,
+    chain::value,
+    clients::node::{NodeClient, NodeError},
+    trace::ErrorLog,
+    types::{
+        HashDigest, Height, HexBytes,
+        ergo::{IndexedTransaction, UTxO},
+    },
+    wallet::hd::{ExtendedPublicKey, HdError},
+};
+
+const DEFAULT_GAP_LIMIT: u32 = 20;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+const PAGE_SIZE: u32 = 100;
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Chain of receiving addresses below the account key (`m/44'/429'/0'/0`).
+const EXTERNAL_CHAIN: u32 = 0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletError {
+    #[error(transparent)]
+    Node(#[from] NodeError),
+
+    #[error(transparent)]
+    Derivation(#[from] HdError),
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletAddress {
+    pub index: u32,
+    pub address: ErgoAddress,
+    pub ergo_tree: HexBytes,
+    /// Number of indexed transactions involving the address at the last sync.
+    pub tx_count: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Balance {
+    pub nano_ergs: u64,
+    pub tokens: BTreeMap<HashDigest, u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletEvent {
+    /// A confirmed transaction changed the wallet balance. Deltas are net of change outputs.
+    Payment {
+        tx_id: HashDigest,
+        inclusion_height: Height,
+        nano_ergs: i64,
+        tokens: BTreeMap<HashDigest, i64>,
+    },
+}
+
+/// Handles to a running [`WalletSync`].
+pub struct WalletHandle {
+    pub balance: Arc<ArcSwap<Balance>>,
+    pub events: mpsc::Receiver<WalletEvent>,
+}
+
+/// Watch-only wallet backed by the node's blockchain indexer.
+///
+/// Derives receiving addresses from an account xpub until `gap_limit` consecutive addresses
+/// have no history, backfills their transactions and keeps the balance up to date.
+pub struct WalletSync {
+    node: NodeClient,
+    account: ExtendedPublicKey,
+    network: NetworkPrefix,
+    gap_limit: u32,
+    poll_interval: Duration,
+    addresses: Vec<WalletAddress>,
+    owned: AddressSet,
+    seen: HashSet<HashDigest>,
+    balance: Balance,
+}
+
+impl WalletSync {
+    pub fn new(node: NodeClient, account: ExtendedPublicKey, network: NetworkPrefix) -> Self {
+        Self {
+            node,
+            account,
+            network,
+            gap_limit: DEFAULT_GAP_LIMIT,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            addresses: Vec::new(),
+            owned: AddressSet::new(),
+            seen: HashSet::new(),
+            balance: Balance::default(),
+        }
+    }
+
+    pub fn gap_limit(mut self, gap_limit: u32) -> Self {
+        self.gap_limit = gap_limit.max(1);
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn addresses(&self) -> &[WalletAddress] {
+        &self.addresses
+    }
+
+    pub fn balance(&self) -> &Balance {
+        &self.balance
+    }
+
+    /// Fetches new history of all derived addresses, extending the derivation window to keep
+    /// `gap_limit` unused addresses, and applies it. Returns the resulting events in
+    /// inclusion order.
+    #[tracing::instrument(skip(self))]
+    pub async fn sync(&mut self) -> Result<Vec<WalletEvent>, WalletError> {
+        let mut fetched = Vec::new();
+
+        let mut i = 0;
+        while i < self.addresses.len() || self.unused_tail() < self.gap_limit {
+            if i == self.addresses.len() {
+                self.derive_next()?;
+            }
+            if let Some(txs) = self.fetch_new(i).await? {
+                fetched.extend(txs);
+            }
+            i += 1;
+        }
+
+        // Apply once every address is known, so transactions between wallet addresses net out.
+        fetched.sort_by_key(|tx| tx.inclusion_height);
+        Ok(fetched.iter().filter_map(|tx| self.apply(tx)).collect())
+    }
+
+    /// Applies a transaction to the balance, returning a payment event if it affects the wallet.
+    /// Transactions already applied are ignored.
+    pub fn apply(&mut self, tx: &IndexedTransaction) -> Option<WalletEvent> {
+        if !self.seen.insert(tx.id.clone()) {
+            return None;
+        }
+
+        let own = |utxo: &&UTxO| self.owned.matches(utxo);
+        let (inputs, outputs) = (tx.inputs.iter().filter(own), tx.outputs.iter().filter(own));
+
+        // Values and token amounts span the whole u64 range, so deltas are clamped rather than
+        // wrapped.
+        let nano_ergs = clamp_i64(value::value_delta(inputs.clone(), outputs.clone()));
+        let tokens: BTreeMap<HashDigest, i64> = asset_deltas(inputs, outputs)
+            .into_iter()
+            .map(|(id, delta)| (id, clamp_i64(delta)))
+            .collect();
+
+        if nano_ergs == 0 && tokens.is_empty() {
+            return None;
+        }
+
+        self.balance.nano_ergs = self.balance.nano_ergs.saturating_add_signed(nano_ergs);
+        for (id, delta) in &tokens {
+            let amount = self.balance.tokens.entry(id.clone()).or_default();
+            *amount = amount.saturating_add_signed(*delta);
+        }
+        self.balance.tokens.retain(|_, amount| *amount > 0);
+
+        Some(WalletEvent::Payment {
+            tx_id: tx.id.clone(),
+            inclusion_height: tx.inclusion_height,
+            nano_ergs,
+            tokens,
+        })
+    }
+
+    /// Backfills the wallet, then polls for new payments in the background. Backfilled
+    /// history is applied to the balance without emitting events.
+    pub async fn spawn(mut self) -> Result<WalletHandle, WalletError> {
+        let history = self.sync().await?;
+        info!(addresses = self.addresses.len(), transactions = history.len(), "Wallet backfilled.");
+
+        let balance = Arc::new(ArcSwap::from_pointee(self.balance.clone()));
+        let (tx, events) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+        let cloned_balance = balance.clone();
+
+        tokio::spawn(async move {
+            let mut errors = ErrorLog::new("wallet sync");
+            loop {
+                sleep(self.poll_interval).await;
+                match self.sync().await {
+                    Ok(new_events) if !new_events.is_empty() => {
+                        errors.success();
+                        cloned_balance.store(Arc::new(self.balance.clone()));
+                        for event in new_events {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Ok(_) => errors.success(),
+                    Err(e) => errors.failure(&e),
+                }
+            }
+        });
+
+        Ok(WalletHandle { balance, events })
+    }
+
+    fn derive_next(&mut self) -> Result<(), HdError> {
+        let mut index = self.addresses.last().map_or(0, |a| a.index + 1);
+        let key = loop {
+            match self.account.derive_path(&[EXTERNAL_CHAIN, index]) {
+                Err(HdError::InvalidChild(_)) => index += 1,
+                other => break other?,
+            }
+        };
+
+        let address = key.address(self.network);
+        let ergo_tree = address.ergo_tree().expect("P2PK addresses have a tree");
+        self.owned.insert(&address);
+        self.addresses
+            .push(WalletAddress { index, address, ergo_tree, tx_count: 0 });
+        Ok(())
+    }
+
+    fn unused_tail(&self) -> u32 {
+        self.addresses
+            .iter()
+            .rev()
+            .take_while(|a| a.tx_count == 0)
+            .count() as u32
+    }
+
+    /// Fetches the full history of the address at `position` if its transaction count changed.
+    async fn fetch_new(
+        &mut self,
+        position: usize,
+    ) -> Result<Option<Vec<IndexedTransaction>>, NodeError> {
+        let address = &self.addresses[position];
+        let first = self
+            .node
+            .get_transactions_by_address(&address.address, 0, PAGE_SIZE)
+            .await?;
+        if first.total == address.tx_count {
+            return Ok(None);
+        }
+
+        let total = first.total;
+        let mut txs = first.items;
+        while (txs.len() as u64) < total {
+            let page = self
+                .node
+                .get_transactions_by_address(&address.address, txs.len() as u64, PAGE_SIZE)
+                .await?;
+            if page.items.is_empty() {
+                break;
+            }
+            txs.extend(page.items);
+        }
+
+        self.addresses[position].tx_count = total;
+        let mut unique: HashMap<HashDigest, IndexedTransaction> = HashMap::new();
+        for tx in txs.into_iter().filter(|tx| !self.seen.contains(&tx.id)) {
+            unique.entry(tx.id.clone()).or_insert(tx);
+        }
+        Ok(Some(unique.into_values().collect()))
+    }
+}
+
+fn clamp_i64(delta: i128) -> i64 {
+    delta.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
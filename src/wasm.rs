@@ -0,0 +1,172 @@
+//! `wasm-bindgen` bindings, so web frontends run the same codecs as the backend.
+//!
+//! Build them without the `runtime` feature, so the bundle only carries the codecs:
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown --no-default-features
+//! --features wasm --crate-type cdylib`, then run `wasm-bindgen` on the output.
+//!
+//! Byte arrays are exchanged as `Uint8Array`s, 64-bit integers as `BigInt`s, and errors are
+//! thrown as JS `Error`s carrying the Rust error message.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    address::{self, AddressType, NetworkPrefix},
+    chain::{candidate, register, transaction},
+    hash::blake2b256,
+    types::{Height, HexBytes, ergo::UnsignedTransaction},
+};
+
+fn network(mainnet: bool) -> NetworkPrefix {
+    if mainnet { NetworkPrefix::Mainnet } else { NetworkPrefix::Testnet }
+}
+
+#[wasm_bindgen]
+pub struct ErgoAddress(address::ErgoAddress);
+
+#[wasm_bindgen]
+impl ErgoAddress {
+    pub fn decode(encoded: &str) -> Result<ErgoAddress, JsError> {
+        Ok(Self(encoded.parse()?))
+    }
+
+    /// Whether `encoded` is a well-formed address with a valid checksum.
+    pub fn validate(encoded: &str) -> bool {
+        encoded.parse::<address::ErgoAddress>().is_ok()
+    }
+
+    #[wasm_bindgen(js_name = fromPublicKey)]
+    pub fn from_public_key(public_key: &[u8], mainnet: bool) -> Result<ErgoAddress, JsError> {
+        let public_key = public_key
+            .try_into()
+            .map_err(|_| JsError::new("Public key must be 33 bytes."))?;
+        Ok(Self(address::ErgoAddress::p2pk(network(mainnet), public_key)))
+    }
+
+    #[wasm_bindgen(js_name = fromErgoTree)]
+    pub fn from_ergo_tree(tree: &[u8], mainnet: bool) -> ErgoAddress {
+        Self(address::ErgoAddress::from_ergo_tree(network(mainnet), tree))
+    }
+
+    pub fn encode(&self) -> String {
+        self.0.encode()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mainnet(&self) -> bool {
+        self.0.network() == NetworkPrefix::Mainnet
+    }
+
+    /// 1 for P2PK, 2 for P2SH and 3 for P2S.
+    #[wasm_bindgen(getter, js_name = addressType)]
+    pub fn address_type(&self) -> u8 {
+        self.0.kind() as u8
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn content(&self) -> Vec<u8> {
+        self.0.content().to_vec()
+    }
+
+    /// `undefined` for P2SH addresses.
+    #[wasm_bindgen(getter, js_name = ergoTree)]
+    pub fn ergo_tree(&self) -> Option<Vec<u8>> {
+        self.0.ergo_tree().map(|t| t.0)
+    }
+
+    #[wasm_bindgen(js_name = isP2PK)]
+    pub fn is_p2pk(&self) -> bool {
+        self.0.kind() == AddressType::P2PK
+    }
+}
+
+#[wasm_bindgen(js_name = base58Encode)]
+pub fn base58_encode(bytes: &[u8]) -> String {
+    address::base58::encode(bytes)
+}
+
+#[wasm_bindgen(js_name = base58Decode)]
+pub fn base58_decode(encoded: &str) -> Result<Vec<u8>, JsError> {
+    Ok(address::base58::decode(encoded)?)
+}
+
+#[wasm_bindgen(js_name = decodeInt)]
+pub fn decode_int(register: &[u8]) -> Result<i32, JsError> {
+    Ok(register::decode_int(register)?)
+}
+
+#[wasm_bindgen(js_name = decodeLong)]
+pub fn decode_long(register: &[u8]) -> Result<i64, JsError> {
+    Ok(register::decode_long(register)?)
+}
+
+#[wasm_bindgen(js_name = decodeCollByte)]
+pub fn decode_coll_byte(register: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(register::decode_coll_byte(register)?)
+}
+
+/// Items of a `Coll[Coll[Byte]]`, hex encoded.
+#[wasm_bindgen(js_name = decodeCollCollByte)]
+pub fn decode_coll_coll_byte(register: &[u8]) -> Result<Vec<String>, JsError> {
+    let items = register::decode_coll_coll_byte(register)?;
+    Ok(items.iter().map(hex::encode).collect())
+}
+
+#[wasm_bindgen(js_name = decodeCollLong)]
+pub fn decode_coll_long(register: &[u8]) -> Result<Vec<i64>, JsError> {
+    Ok(register::decode_coll_long(register)?)
+}
+
+#[wasm_bindgen(js_name = decodeProveDlog)]
+pub fn decode_prove_dlog(register: &[u8]) -> Result<Vec<u8>, JsError> {
+    Ok(register::decode_prove_dlog(register)?.to_vec())
+}
+
+/// Message signed by every input of an unsigned transaction given in the node's JSON format.
+#[wasm_bindgen(js_name = bytesToSign)]
+pub fn bytes_to_sign(unsigned_tx: &str) -> Result<Vec<u8>, JsError> {
+    let tx: UnsignedTransaction = serde_json::from_str(unsigned_tx)?;
+    Ok(transaction::bytes_to_sign(&tx)?)
+}
+
+/// Id the transaction will have once signed, hex encoded.
+#[wasm_bindgen(js_name = transactionId)]
+pub fn transaction_id(unsigned_tx: &str) -> Result<String, JsError> {
+    Ok(hex::encode(blake2b256(&bytes_to_sign(unsigned_tx)?)))
+}
+
+/// Builds an output checked against the protocol rules the node enforces, see
+/// [`candidate::BoxCandidateBuilder`]. Each setter consumes the builder and returns it.
+#[wasm_bindgen]
+pub struct BoxCandidateBuilder(candidate::BoxCandidateBuilder);
+
+#[wasm_bindgen]
+impl BoxCandidateBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(value: u64, creation_height: u32) -> BoxCandidateBuilder {
+        Self(candidate::BoxCandidateBuilder::new(value, Height(creation_height)))
+    }
+
+    #[wasm_bindgen(js_name = ergoTree)]
+    pub fn ergo_tree(self, tree: &[u8]) -> BoxCandidateBuilder {
+        Self(self.0.ergo_tree(HexBytes(tree.to_vec())))
+    }
+
+    pub fn address(self, address: &ErgoAddress) -> BoxCandidateBuilder {
+        Self(self.0.address(&address.0))
+    }
+
+    /// Adds `amount` of the token with the hex encoded `id`.
+    pub fn token(self, id: &str, amount: u64) -> Result<BoxCandidateBuilder, JsError> {
+        Ok(Self(self.0.token(id.parse()?, amount)))
+    }
+
+    /// Sets register `R{id}` to a serialized constant.
+    pub fn register(self, id: u8, value: &[u8]) -> BoxCandidateBuilder {
+        Self(self.0.register(id, HexBytes(value.to_vec())))
+    }
+
+    /// The candidate in the node's JSON format, ready to be added to an unsigned transaction.
+    pub fn build(self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.0.build()?)?)
+    }
+}
@@ -1 +1,535 @@
+//! Block events of the best chain, sequenced so that reorganizations roll back what they
+//! undo, and a block is only final once buried under a configurable number of blocks.
+//!
+//! A sequencer resumed far behind the tip first catches up on the finalized part of the
+//! chain, fetching blocks concurrently and reporting progress, then announces that it is
+//! [live](ChainEvent::Live) before sequencing the blocks near the tip.
+//!
+//! Sinks record the last event they processed in [`SinkCursors`] to resume from their
+//! finalized block after a restart; events emitted again keep their [`EventId`].
 
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{error, info};
+
+use crate::{
+    clients::node::{NodeClient, NodeError, PushTopic},
+    events::{
+        EventId, EventReceiver, EventSender, IdentifiedEvent, LagPolicy, RecvError, event_id,
+    },
+    trace::ErrorLog,
+    types::{HashDigest, Height, ergo::BlockHeader},
+};
+
+/// Confirmations after which a block is final by default.
+pub const DEFAULT_FINALITY_DEPTH: u32 = 10;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Blocks fetched per catch-up step, after which progress is reported.
+pub const CATCH_UP_CHUNK: u32 = 100;
+/// Blocks fetched concurrently while catching up.
+pub const CATCH_UP_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockRef {
+    pub id: HashDigest,
+    pub height: Height,
+}
+
+impl From<&BlockHeader> for BlockRef {
+    fn from(header: &BlockHeader) -> Self {
+        Self { id: header.id.clone(), height: header.height }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChainEvent {
+    /// A block joined the best chain. Its transactions are provisional: the block may still
+    /// be rolled back.
+    ///
+    /// `seq` counts the earlier rollbacks at the block's height, telling apart a block applied
+    /// again after a reorganization back to it.
+    Applied { block: BlockRef, transactions: Vec<HashDigest>, seq: u32 },
+    /// A block applied earlier left the best chain. Rollbacks come newest first. `seq` is that
+    /// of the rolled back [`Applied`](Self::Applied) event.
+    RolledBack { block: BlockRef, transactions: Vec<HashDigest>, seq: u32 },
+    /// A block reached the finality depth and is no longer rolled back.
+    Finalized { block: BlockRef, transactions: Vec<HashDigest> },
+    /// Catching up after downtime: blocks up to `height` were sequenced, out of `tip`.
+    CatchingUp { height: Height, tip: Height },
+    /// Caught up: blocks after `height` are sequenced as they are mined. Sent once, also when
+    /// there was nothing to catch up on.
+    Live { height: Height },
+}
+
+impl ChainEvent {
+    /// The block of a block event, `None` for progress events.
+    pub fn block(&self) -> Option<&BlockRef> {
+        match self {
+            Self::Applied { block, .. }
+            | Self::RolledBack { block, .. }
+            | Self::Finalized { block, .. } => Some(block),
+            Self::CatchingUp { .. } | Self::Live { .. } => None,
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Finalized { .. })
+    }
+
+    pub fn is_progress(&self) -> bool {
+        matches!(self, Self::CatchingUp { .. } | Self::Live { .. })
+    }
+}
+
+/// Block events are identified by their block id, height and `seq`; progress events by their
+/// heights.
+impl IdentifiedEvent for ChainEvent {
+    fn event_id(&self) -> EventId {
+        match self {
+            Self::Applied { block, seq, .. } => {
+                event_id("applied", Some(&block.id), Some(block.height), *seq)
+            }
+            Self::RolledBack { block, seq, .. } => {
+                event_id("rolledBack", Some(&block.id), Some(block.height), *seq)
+            }
+            Self::Finalized { block, .. } => {
+                event_id("finalized", Some(&block.id), Some(block.height), 0)
+            }
+            Self::CatchingUp { height, tip } => event_id("catchingUp", None, Some(*height), tip.0),
+            Self::Live { height } => event_id("live", None, Some(*height), 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChainError {
+    #[error("Reorganization below the finalized block at height {0}")]
+    DeepReorg(Height),
+
+    #[error("Block at height {0} doesn't extend the tip")]
+    Disconnected(Height),
+}
+
+/// Sequences the blocks of the best chain into [`ChainEvent`]s.
+///
+/// Blocks less than `finality_depth` confirmations deep are pending: a reorganization rolls
+/// them back, newest first, before the blocks of the new branch are applied. A block with
+/// `finality_depth` confirmations, the tip counting as one, is finalized and forgotten.
+#[derive(Debug, Clone)]
+pub struct ChainSequencer {
+    finality_depth: u32,
+    pending: VecDeque<(BlockRef, Vec<HashDigest>)>,
+    finalized: Option<BlockRef>,
+    /// Rollbacks by height above the finalized block, the `seq` of the events at the height.
+    rollbacks: BTreeMap<Height, u32>,
+}
+
+impl Default for ChainSequencer {
+    fn default() -> Self {
+        Self::new(DEFAULT_FINALITY_DEPTH)
+    }
+}
+
+impl ChainSequencer {
+    pub fn new(finality_depth: u32) -> Self {
+        Self {
+            finality_depth: finality_depth.max(1),
+            pending: VecDeque::new(),
+            finalized: None,
+            rollbacks: BTreeMap::new(),
+        }
+    }
+
+    /// A sequencer resuming after `finalized`, e.g. the last finalized block a consumer
+    /// processed before a restart.
+    pub fn resume_after(finality_depth: u32, finalized: BlockRef) -> Self {
+        Self { finalized: Some(finalized), ..Self::new(finality_depth) }
+    }
+
+    pub fn finality_depth(&self) -> u32 {
+        self.finality_depth
+    }
+
+    /// The last block applied.
+    pub fn tip(&self) -> Option<&BlockRef> {
+        self.pending
+            .back()
+            .map(|(block, _)| block)
+            .or(self.finalized.as_ref())
+    }
+
+    pub fn finalized(&self) -> Option<&BlockRef> {
+        self.finalized.as_ref()
+    }
+
+    /// Height from which the best chain must be read to detect a reorganization: the
+    /// finalized block, or the oldest pending one.
+    pub fn sync_from(&self) -> Option<Height> {
+        self.finalized
+            .as_ref()
+            .or(self.pending.front().map(|(block, _)| block))
+            .map(|block| block.height)
+    }
+
+    /// Rolls back the pending blocks that are not in `headers`, a contiguous slice of the
+    /// best chain, lowest first. Afterwards the tip is the common ancestor with `headers`.
+    pub fn rollback_to(&mut self, headers: &[BlockHeader]) -> Result<Vec<ChainEvent>, ChainError> {
+        let on_chain = |block: &BlockRef| match headers.first() {
+            Some(first) if block.height >= first.height => headers
+                .get(block.height.blocks_since(first.height) as usize)
+                .is_some_and(|h| h.id == block.id),
+            // Below the slice: assumed unchanged.
+            _ => true,
+        };
+
+        let mut events = Vec::new();
+        while let Some((block, _)) = self.pending.back() {
+            if on_chain(block) {
+                return Ok(events);
+            }
+            let (block, transactions) = self.pending.pop_back().unwrap();
+            let rollbacks = self.rollbacks.entry(block.height).or_default();
+            let seq = *rollbacks;
+            *rollbacks += 1;
+            events.push(ChainEvent::RolledBack { block, transactions, seq });
+        }
+        match &self.finalized {
+            Some(block) if !on_chain(block) => Err(ChainError::DeepReorg(block.height)),
+            _ => Ok(events),
+        }
+    }
+
+    /// Applies a block extending the tip, finalizing the blocks it buries deep enough.
+    pub fn apply(
+        &mut self,
+        header: &BlockHeader,
+        transactions: Vec<HashDigest>,
+    ) -> Result<Vec<ChainEvent>, ChainError> {
+        if self
+            .tip()
+            .is_some_and(|tip| tip.id != header.parent_id || tip.height.next() != header.height)
+        {
+            return Err(ChainError::Disconnected(header.height));
+        }
+
+        let block = BlockRef::from(header);
+        let seq = self.rollbacks.get(&block.height).copied().unwrap_or(0);
+        let mut events = vec![ChainEvent::Applied {
+            block: block.clone(),
+            transactions: transactions.clone(),
+            seq,
+        }];
+        self.pending.push_back((block, transactions));
+        while let Some((oldest, _)) = self.pending.front() {
+            if header.height.blocks_since(oldest.height) + 1 < self.finality_depth {
+                break;
+            }
+            let (block, transactions) = self.pending.pop_front().unwrap();
+            self.rollbacks = self.rollbacks.split_off(&block.height.next());
+            self.finalized = Some(block.clone());
+            events.push(ChainEvent::Finalized { block, transactions });
+        }
+        Ok(events)
+    }
+}
+
+/// Which chain events a consumer acts on. Progress events are received in both modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkMode {
+    /// Applied blocks as soon as they are seen, and their rollbacks.
+    Provisional,
+    /// Finalized blocks only, which are never rolled back.
+    Finalized,
+}
+
+/// Chain events fanned out to consumers, see [`spawn_chain_events`].
+#[derive(Debug, Clone)]
+pub struct ChainEvents {
+    sender: EventSender<ChainEvent>,
+}
+
+impl ChainEvents {
+    pub fn new(capacity: usize, policy: LagPolicy) -> Self {
+        Self { sender: EventSender::new(capacity, policy) }
+    }
+
+    pub fn sender(&self) -> &EventSender<ChainEvent> {
+        &self.sender
+    }
+
+    /// A consumer of the events of `mode` sent from now on.
+    pub fn subscribe(&self, mode: SinkMode) -> ChainSubscription {
+        ChainSubscription { inner: self.sender.subscribe(), mode }
+    }
+}
+
+#[derive(Debug)]
+pub struct ChainSubscription {
+    inner: EventReceiver<ChainEvent>,
+    mode: SinkMode,
+}
+
+impl ChainSubscription {
+    pub fn mode(&self) -> SinkMode {
+        self.mode
+    }
+
+    pub async fn recv(&mut self) -> Result<ChainEvent, RecvError> {
+        loop {
+            let event = self.inner.recv().await?;
+            if event.is_progress() || event.is_final() == (self.mode == SinkMode::Finalized) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+/// Where a sink stands: the last event it processed, and the last finalized block, from which
+/// it resumes.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SinkCursor {
+    pub last_event: EventId,
+    pub finalized: Option<BlockRef>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CursorError {
+    #[error("Failed to access the sink cursors: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid sink cursors: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The [`SinkCursor`]s of named sinks, persisted as a JSON object in a file.
+#[derive(Debug)]
+pub struct SinkCursors {
+    path: PathBuf,
+    cursors: BTreeMap<String, SinkCursor>,
+}
+
+impl SinkCursors {
+    /// Loads the cursors of `path`, none if the file doesn't exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CursorError> {
+        let path = path.into();
+        let cursors = match std::fs::read(&path) {
+            Ok(json) => serde_json::from_slice(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, cursors })
+    }
+
+    pub fn get(&self, sink: &str) -> Option<&SinkCursor> {
+        self.cursors.get(sink)
+    }
+
+    /// Whether `sink` already processed `event`, being the last event it recorded.
+    pub fn is_last(&self, sink: &str, event: &ChainEvent) -> bool {
+        self.get(sink)
+            .is_some_and(|cursor| cursor.last_event == event.event_id())
+    }
+
+    /// A sequencer resuming after the finalized block of `sink`, from the tip if there is none.
+    pub fn sequencer(&self, sink: &str, finality_depth: u32) -> ChainSequencer {
+        match self.get(sink).and_then(|cursor| cursor.finalized.clone()) {
+            Some(block) => ChainSequencer::resume_after(finality_depth, block),
+            None => ChainSequencer::new(finality_depth),
+        }
+    }
+
+    /// Records `event` as processed by `sink`, and persists the cursors. The file is replaced
+    /// atomically, so a crash leaves either the previous cursors or the new ones.
+    pub fn record(&mut self, sink: &str, event: &ChainEvent) -> Result<(), CursorError> {
+        let finalized = match event {
+            ChainEvent::Finalized { block, .. } => Some(block.clone()),
+            _ => self.get(sink).and_then(|cursor| cursor.finalized.clone()),
+        };
+        let cursor = SinkCursor { last_event: event.event_id(), finalized };
+        self.cursors.insert(sink.to_owned(), cursor);
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.cursors)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Follows the best chain, sending chain events for every new block: from the tip for a new
+/// sequencer, after catching up for a resumed one.
+///
+/// A reorganization below the finalized block can't be undone by consumers; it is logged
+/// and sequencing restarts from the new tip.
+pub fn spawn_chain_events(node: NodeClient, mut sequencer: ChainSequencer) -> ChainEvents {
+    let events = ChainEvents::new(EVENT_CHANNEL_CAPACITY, LagPolicy::DropOldest);
+    let sender = events.sender.clone();
+
+    tokio::spawn(async move {
+        info!(depth = sequencer.finality_depth(), "Starting chain event sequencing...");
+        let mut errors = ErrorLog::new("chain events");
+        let mut live = false;
+        let mut wakeup = node.wakeup(PushTopic::Blocks, DEFAULT_POLL_INTERVAL);
+        loop {
+            let synced = async {
+                if !live {
+                    catch_up(&node, &mut sequencer, &sender).await?;
+                    live = true;
+                }
+                sync(&node, &mut sequencer, &sender).await
+            };
+            match synced.await {
+                Ok(()) => errors.success(),
+                Err(SyncError::Chain(e @ ChainError::DeepReorg(_))) => {
+                    error!("{e}, restarting from the tip.");
+                    sequencer = ChainSequencer::new(sequencer.finality_depth());
+                }
+                Err(e) => errors.failure(&e),
+            }
+            wakeup.wait().await;
+        }
+    });
+
+    events
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    #[error(transparent)]
+    Node(#[from] NodeError),
+
+    #[error(transparent)]
+    Chain(#[from] ChainError),
+}
+
+/// Sequences the blocks that are already final on the node, [`CATCH_UP_CHUNK`] at a time,
+/// then sends [`ChainEvent::Live`]. Does nothing but the latter for a sequencer without a
+/// tip, which starts from the node's.
+pub async fn catch_up(
+    node: &NodeClient,
+    sequencer: &mut ChainSequencer,
+    sender: &EventSender<ChainEvent>,
+) -> Result<(), SyncError> {
+    if let Some(start) = sequencer.tip().map(|tip| tip.height) {
+        let Some(tip) = node.get_last_n_headers(1).await?.pop() else {
+            return Ok(());
+        };
+        // Blocks within the finality depth may still be reorganized: they are left to `sync`.
+        let last = tip.height.saturating_sub(sequencer.finality_depth());
+        if last > start {
+            info!(from = %start, to = %last, "Catching up on blocks...");
+        }
+        let mut from = start;
+        while from < last {
+            // The slice starts at the tip, so a resume point orphaned meanwhile is detected.
+            let to = (from + CATCH_UP_CHUNK).min(last);
+            let headers = node.get_chain_slice(from, to).await?;
+            for event in sequencer.rollback_to(&headers)? {
+                sender.send(event);
+            }
+            let headers = &headers[headers.len().min(1)..];
+            let Some(height) = headers.last().map(|h| h.height) else {
+                break;
+            };
+            for (header, transactions) in
+                headers.iter().zip(fetch_transactions(node, headers).await?)
+            {
+                for event in sequencer.apply(header, transactions)? {
+                    sender.send(event);
+                }
+            }
+            sender.send(ChainEvent::CatchingUp { height, tip: tip.height });
+            from = height;
+        }
+    }
+
+    let height = sequencer.tip().map(|tip| tip.height);
+    let height = match height {
+        Some(height) => height,
+        None => match node.get_last_n_headers(1).await?.pop() {
+            Some(tip) => tip.height,
+            None => return Ok(()),
+        },
+    };
+    sender.send(ChainEvent::Live { height });
+    Ok(())
+}
+
+/// Transaction ids of the blocks of `headers`, in order, with at most
+/// [`CATCH_UP_CONCURRENCY`] blocks fetched at once.
+async fn fetch_transactions(
+    node: &NodeClient,
+    headers: &[BlockHeader],
+) -> Result<Vec<Vec<HashDigest>>, NodeError> {
+    let permits = Arc::new(Semaphore::new(CATCH_UP_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for (i, header) in headers.iter().enumerate() {
+        let (node, permits, id) = (node.clone(), permits.clone(), header.id.to_string());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (i, node.get_block(&id).await)
+        });
+    }
+
+    let mut transactions = vec![Vec::new(); headers.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (i, block) = joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+        transactions[i] = block?
+            .transactions
+            .transactions
+            .iter()
+            .map(|tx| tx.id.clone())
+            .collect();
+    }
+    Ok(transactions)
+}
+
+/// Brings the sequencer to the node's tip, sending the events on the way.
+async fn sync(
+    node: &NodeClient,
+    sequencer: &mut ChainSequencer,
+    sender: &EventSender<ChainEvent>,
+) -> Result<(), SyncError> {
+    let Some(tip) = node.get_last_n_headers(1).await?.pop() else {
+        return Ok(());
+    };
+    let headers = match sequencer.sync_from() {
+        Some(from) => node.get_chain_slice(from, tip.height).await?,
+        None => vec![tip],
+    };
+
+    for event in sequencer.rollback_to(&headers)? {
+        sender.send(event);
+    }
+    let next = sequencer.tip().map(|tip| tip.height.next());
+    for header in headers
+        .iter()
+        .filter(|h| next.is_none_or(|next| h.height >= next))
+    {
+        let block = node.get_block(&header.id.to_string()).await?;
+        let transactions = block
+            .transactions
+            .transactions
+            .iter()
+            .map(|tx| tx.id.clone())
+            .collect();
+        for event in sequencer.apply(header, transactions)? {
+            sender.send(event);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::{
+    clients::node::{NodeClient, NodeError},
+    types::{Height, ergo::Block},
+};
+
+/// Walks the best chain one block at a time, starting from the tip at the first poll.
+/// Reorganizations are not followed: blocks are visited once, by height.
+#[derive(Debug, Clone, Default)]
+pub struct BlockFollower {
+    height: Option<Height>,
+}
+
+impl BlockFollower {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follower that visits blocks above `height` on the first poll.
+    pub fn after(height: Height) -> Self {
+        Self { height: Some(height) }
+    }
+
+    /// Height of the last visited block.
+    pub fn height(&self) -> Option<Height> {
+        self.height
+    }
+
+    /// Calls `visit` with each block up to the current tip. On error, blocks visited so far
+    /// are not revisited on the next poll.
+    pub async fn poll(
+        &mut self,
+        node: &NodeClient,
+        mut visit: impl FnMut(&Block),
+    ) -> Result<(), NodeError> {
+        let Some(tip) = node.get_last_n_headers(1).await?.pop() else {
+            return Ok(());
+        };
+        let from = self.height.map_or(tip.height, Height::next);
+
+        for height in from.up_to(tip.height) {
+            let Some(id) = node
+                .get_header_ids_at_height(height)
+                .await?
+                .into_iter()
+                .next()
+            else {
+                break;
+            };
+            visit(&node.get_block(&id.to_string()).await?);
+            self.height = Some(height);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use tokio::{sync::mpsc, time::sleep};
+use tracing::info;
+
+use crate::{
+    address::{ErgoAddress, NetworkPrefix},
+    chain::register,
+    clients::node::{BoxQuery, NodeClient, NodeError},
+    events::{EventId, IdentifiedEvent, event_id},
+    trace::ErrorLog,
+    types::{
+        HashDigest, Height, HexBytes, TimestampMillis,
+        ergo::{Block, Token, UTxO, UnconfirmedTransaction},
+    },
+    watcher::{BlockFollower, MempoolSnapshot},
+};
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const PAGE_SIZE: u32 = 100;
+
+/// Destination of a lock request, read from the lock box's R4:
+/// `Coll[Coll[Byte]]` of `[toChain, toAddress, networkFee, bridgeFee, fromAddress]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockRequest {
+    pub to_chain: String,
+    pub to_address: String,
+    pub network_fee: u64,
+    pub bridge_fee: u64,
+    pub from_address: String,
+}
+
+impl LockRequest {
+    pub fn decode(r4: &[u8]) -> Option<Self> {
+        let fields = register::decode_coll_coll_byte(r4).ok()?;
+        let [to_chain, to_address, network_fee, bridge_fee, from_address] = fields.as_slice()
+        else {
+            return None;
+        };
+        let text = |b: &Vec<u8>| String::from_utf8(b.clone()).ok();
+        let amount = |b: &Vec<u8>| text(b)?.parse().ok();
+
+        Some(Self {
+            to_chain: text(to_chain)?,
+            to_address: text(to_address)?,
+            network_fee: amount(network_fee)?,
+            bridge_fee: amount(bridge_fee)?,
+            from_address: text(from_address)?,
+        })
+    }
+}
+
+/// A box released from the lock address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payout {
+    pub address: ErgoAddress,
+    pub nano_ergs: u64,
+    pub tokens: Vec<(HashDigest, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeEvent {
+    /// Assets sent to the lock address to be bridged to another chain.
+    Lock {
+        tx_id: HashDigest,
+        box_id: HashDigest,
+        /// `None` while the transaction is in the mempool.
+        height: Option<Height>,
+        request: LockRequest,
+        nano_ergs: u64,
+        tokens: Vec<(HashDigest, u64)>,
+    },
+    /// Lock boxes spent to pay out assets bridged from another chain.
+    Release {
+        tx_id: HashDigest,
+        height: Option<Height>,
+        spent: Vec<HashDigest>,
+        payouts: Vec<Payout>,
+    },
+}
+
+impl BridgeEvent {
+    pub fn tx_id(&self) -> &HashDigest {
+        match self {
+            Self::Lock { tx_id, .. } | Self::Release { tx_id, .. } => tx_id,
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        match self {
+            Self::Lock { height, .. } | Self::Release { height, .. } => height.is_some(),
+        }
+    }
+}
+
+/// Releases are identified by their transaction and height, locks by their lock box, as a
+/// transaction may create several. Unconfirmed events differ from their confirmed ones.
+impl IdentifiedEvent for BridgeEvent {
+    fn event_id(&self) -> EventId {
+        match self {
+            Self::Lock { box_id, height, .. } => event_id("lock", Some(box_id), *height, 0),
+            Self::Release { tx_id, height, .. } => event_id("release", Some(tx_id), *height, 0),
+        }
+    }
+}
+
+/// Detects Rosen bridge lock and release transactions by the bridge's lock address.
+///
+/// Block transactions only carry input ids, so releases in blocks are recognized by the lock
+/// boxes this scanner has seen created, or registered with [`RosenScanner::track_box`].
+///
+/// The scanner is only exposed as a library: `serve` does not run it, as bridge events have no
+/// endpoint or sink yet. Run it with [`spawn_rosen`].
+pub struct RosenScanner {
+    network: NetworkPrefix,
+    lock_tree: HexBytes,
+    lock_boxes: HashSet<HashDigest>,
+}
+
+impl RosenScanner {
+    pub fn new(lock_address: &ErgoAddress) -> Option<Self> {
+        Some(Self {
+            network: lock_address.network(),
+            lock_tree: lock_address.ergo_tree()?,
+            lock_boxes: HashSet::new(),
+        })
+    }
+
+    /// Registers an existing box at the lock address, e.g. from an initial unspent box query.
+    pub fn track_box(&mut self, box_id: HashDigest) {
+        self.lock_boxes.insert(box_id);
+    }
+
+    pub fn lock_tree(&self) -> &HexBytes {
+        &self.lock_tree
+    }
+
+    pub fn tracked_boxes(&self) -> usize {
+        self.lock_boxes.len()
+    }
+
+    pub fn scan_mempool_transaction(&mut self, tx: &UnconfirmedTransaction) -> Vec<BridgeEvent> {
+        let spent = tx
+            .inputs
+            .iter()
+            .filter(|i| i.utxo.ergo_tree == self.lock_tree)
+            .map(|i| i.utxo.id.clone())
+            .collect();
+        self.scan(&tx.id, spent, &tx.outputs, None)
+    }
+
+    pub fn scan_block(&mut self, block: &Block) -> Vec<BridgeEvent> {
+        let height = Some(block.header.height);
+        let mut events = Vec::new();
+        for tx in &block.transactions.transactions {
+            let spent = tx
+                .inputs
+                .iter()
+                .filter(|i| self.lock_boxes.remove(&i.id))
+                .map(|i| i.id.clone())
+                .collect();
+            events.extend(self.scan(&tx.id, spent, &tx.outputs, height));
+        }
+        events
+    }
+
+    fn scan(
+        &mut self,
+        tx_id: &HashDigest,
+        spent: Vec<HashDigest>,
+        outputs: &[UTxO],
+        height: Option<Height>,
+    ) -> Vec<BridgeEvent> {
+        let mut events = Vec::new();
+        let (locked, other): (Vec<&UTxO>, Vec<&UTxO>) =
+            outputs.iter().partition(|o| o.ergo_tree == self.lock_tree);
+
+        if height.is_some() {
+            self.lock_boxes.extend(locked.iter().map(|o| o.id.clone()));
+        }
+
+        if !spent.is_empty() {
+            let payouts = other
+                .iter()
+                .map(|o| Payout {
+                    address: ErgoAddress::from_ergo_tree(self.network, &o.ergo_tree.0),
+                    nano_ergs: o.value,
+                    tokens: tokens(&o.tokens),
+                })
+                .collect();
+            events.push(BridgeEvent::Release { tx_id: tx_id.clone(), height, spent, payouts });
+            // Change returned to the lock address is not a new lock request.
+            return events;
+        }
+
+        for output in locked {
+            let Some(request) = output
+                .registers
+                .r4
+                .as_ref()
+                .and_then(|r4| LockRequest::decode(&r4.0))
+            else {
+                continue;
+            };
+            events.push(BridgeEvent::Lock {
+                tx_id: tx_id.clone(),
+                box_id: output.id.clone(),
+                height,
+                request,
+                nano_ergs: output.value,
+                tokens: tokens(&output.tokens),
+            });
+        }
+        events
+    }
+}
+
+fn tokens(tokens: &[Token]) -> Vec<(HashDigest, u64)> {
+    tokens.iter().map(|t| (t.id.clone(), t.amount)).collect()
+}
+
+/// Fetches the ids of all unspent boxes guarded by `lock_tree` from the indexer.
+pub async fn fetch_lock_boxes(
+    node: &NodeClient,
+    lock_tree: &HexBytes,
+) -> Result<Vec<HashDigest>, NodeError> {
+    let mut ids = Vec::new();
+    let mut offset = 0;
+    loop {
+        let query = BoxQuery::new().offset(offset).limit(PAGE_SIZE);
+        let page = node
+            .get_unspent_boxes_by_ergo_tree(lock_tree, &query)
+            .await?;
+        if page.items.is_empty() {
+            break;
+        }
+        offset += page.items.len() as u64;
+        ids.extend(page.items.into_iter().map(|indexed| indexed.utxo.id));
+        if offset >= page.total {
+            break;
+        }
+    }
+    Ok(ids)
+}
+
+/// Emits bridge events from the mempool snapshot and from new blocks. Each transaction is
+/// reported once unconfirmed, if seen in the mempool, and once confirmed.
+///
+/// The scanner first tracks the unspent boxes at the lock address, so releases spending boxes
+/// locked before startup are recognized, then follows blocks from the tip it was seeded at.
+pub fn spawn_rosen(
+    node: NodeClient,
+    mut scanner: RosenScanner,
+    mempool: Arc<ArcSwap<MempoolSnapshot>>,
+) -> mpsc::Receiver<BridgeEvent> {
+    let (tx, rx) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        info!("Starting Rosen bridge watcher...");
+        let mut errors = ErrorLog::new("Rosen bridge watcher");
+        let mut blocks = loop {
+            match seed(&node, &mut scanner).await {
+                Ok(blocks) => break blocks,
+                Err(e) => errors.failure(&e),
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        };
+        info!(boxes = scanner.tracked_boxes(), "Rosen lock boxes loaded.");
+        let mut last_update = TimestampMillis(0);
+        let mut reported: HashSet<HashDigest> = HashSet::new();
+
+        loop {
+            let snapshot = mempool.load_full();
+            let mut events = Vec::new();
+            if snapshot.last_update > last_update {
+                last_update = snapshot.last_update;
+                for mempool_tx in snapshot.transactions.iter() {
+                    if reported.insert(mempool_tx.id.clone()) {
+                        events.extend(scanner.scan_mempool_transaction(mempool_tx));
+                    }
+                }
+                let ids: HashSet<&HashDigest> =
+                    snapshot.transactions.iter().map(|t| &t.id).collect();
+                reported.retain(|id| ids.contains(id));
+            }
+
+            let scanned = blocks.poll(&node, |block| events.extend(scanner.scan_block(block)));
+            match scanned.await {
+                Ok(_) => errors.success(),
+                Err(e) => errors.failure(&e),
+            }
+
+            for event in events {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    rx
+}
+
+async fn seed(node: &NodeClient, scanner: &mut RosenScanner) -> Result<BlockFollower, NodeError> {
+    let blocks = match node.get_last_n_headers(1).await?.pop() {
+        Some(tip) => BlockFollower::after(tip.height),
+        None => BlockFollower::new(),
+    };
+    for id in fetch_lock_boxes(node, scanner.lock_tree()).await? {
+        scanner.track_box(id);
+    }
+    Ok(blocks)
+}
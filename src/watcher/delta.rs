@@ -0,0 +1,189 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    intern::ErgoTreeInterner,
+    types::{HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+/// Number of frames between keyframes used by [`DeltaEncoder::new`].
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeltaError {
+    #[error("Delta frame received before any keyframe")]
+    MissingKeyframe,
+
+    #[error("Delta frame based on snapshot {expected}, but the current one is {actual}")]
+    BaseMismatch { expected: TimestampMillis, actual: TimestampMillis },
+}
+
+/// A persisted or published mempool snapshot, either in full or relative to the previous one.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SnapshotFrame {
+    /// Carries the first-seen times ordering the transactions, so readers rebuild the same
+    /// order.
+    #[serde(rename_all = "camelCase")]
+    Keyframe {
+        last_update: TimestampMillis,
+        transactions: Vec<UnconfirmedTransaction>,
+        #[serde(default)]
+        first_seen: BTreeMap<HashDigest, TimestampMillis>,
+    },
+
+    /// Changes since the snapshot updated at `base`. Only new transactions carry bodies.
+    #[serde(rename_all = "camelCase")]
+    Delta {
+        base: TimestampMillis,
+        last_update: TimestampMillis,
+        removed: Vec<HashDigest>,
+        added: Vec<UnconfirmedTransaction>,
+    },
+}
+
+impl SnapshotFrame {
+    pub fn last_update(&self) -> TimestampMillis {
+        match self {
+            Self::Keyframe { last_update, .. } | Self::Delta { last_update, .. } => *last_update,
+        }
+    }
+
+    pub fn is_keyframe(&self) -> bool {
+        matches!(self, Self::Keyframe { .. })
+    }
+}
+
+/// Turns consecutive snapshots into frames, emitting a keyframe every `keyframe_interval`
+/// frames so readers can start from, or recover at, a recent point.
+pub struct DeltaEncoder {
+    keyframe_interval: u32,
+    since_keyframe: u32,
+    previous: Option<Arc<MempoolSnapshot>>,
+}
+
+impl Default for DeltaEncoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYFRAME_INTERVAL)
+    }
+}
+
+impl DeltaEncoder {
+    pub fn new(keyframe_interval: u32) -> Self {
+        Self { keyframe_interval: keyframe_interval.max(1), since_keyframe: 0, previous: None }
+    }
+
+    /// Forces the next frame to be a keyframe, e.g. when a new subscriber joins.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    pub fn encode(&mut self, snapshot: Arc<MempoolSnapshot>) -> SnapshotFrame {
+        let frame = match self.previous.as_deref() {
+            Some(previous)
+                if self.since_keyframe < self.keyframe_interval
+                    && previous.content_hash() == snapshot.content_hash() =>
+            {
+                self.since_keyframe += 1;
+                SnapshotFrame::Delta {
+                    base: previous.last_update,
+                    last_update: snapshot.last_update,
+                    removed: Vec::new(),
+                    added: Vec::new(),
+                }
+            }
+            Some(previous) if self.since_keyframe < self.keyframe_interval => {
+                self.since_keyframe += 1;
+                let diff = previous.diff(&snapshot);
+                let added_ids: HashSet<&HashDigest> = diff.added.iter().collect();
+                let added = snapshot
+                    .transactions
+                    .iter()
+                    .filter(|tx| added_ids.contains(&tx.id))
+                    .cloned()
+                    .collect();
+
+                SnapshotFrame::Delta {
+                    base: previous.last_update,
+                    last_update: snapshot.last_update,
+                    removed: diff.removed,
+                    added,
+                }
+            }
+            _ => {
+                self.since_keyframe = 1;
+                SnapshotFrame::Keyframe {
+                    last_update: snapshot.last_update,
+                    transactions: snapshot.transactions.to_vec(),
+                    first_seen: snapshot
+                        .first_seen
+                        .iter()
+                        .map(|(id, seen)| (id.clone(), *seen))
+                        .collect(),
+                }
+            }
+        };
+
+        self.previous = Some(snapshot);
+        frame
+    }
+}
+
+/// Rebuilds snapshots from a sequence of frames.
+#[derive(Default)]
+pub struct DeltaDecoder {
+    current: Option<Arc<MempoolSnapshot>>,
+    interner: ErgoTreeInterner,
+}
+
+impl DeltaDecoder {
+    pub fn current(&self) -> Option<&Arc<MempoolSnapshot>> {
+        self.current.as_ref()
+    }
+
+    /// Applies a frame, returning the resulting snapshot. A failed delta leaves the decoder
+    /// unchanged; the caller should skip ahead to the next keyframe.
+    pub fn apply(&mut self, frame: SnapshotFrame) -> Result<Arc<MempoolSnapshot>, DeltaError> {
+        let snapshot = match frame {
+            SnapshotFrame::Keyframe { last_update, transactions, first_seen } => {
+                let first_seen = first_seen.into_iter().collect();
+                MempoolSnapshot::with_first_seen(
+                    last_update,
+                    transactions,
+                    first_seen,
+                    &self.interner,
+                )
+            }
+            SnapshotFrame::Delta { base, last_update, removed, added } => {
+                let current = self.current.as_ref().ok_or(DeltaError::MissingKeyframe)?;
+                if current.last_update != base {
+                    return Err(DeltaError::BaseMismatch {
+                        expected: base,
+                        actual: current.last_update,
+                    });
+                }
+
+                let removed: HashSet<HashDigest> = removed.into_iter().collect();
+                let transactions = current
+                    .transactions
+                    .iter()
+                    .filter(|tx| !removed.contains(&tx.id))
+                    .cloned()
+                    .chain(added)
+                    .collect();
+
+                current.next(last_update, transactions, &self.interner)
+            }
+        };
+
+        let snapshot = Arc::new(snapshot);
+        self.current = Some(snapshot.clone());
+        self.interner.purge();
+        Ok(snapshot)
+    }
+}
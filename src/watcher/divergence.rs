@@ -0,0 +1,248 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::info;
+
+use crate::{
+    clients::node::NodeClient,
+    trace::ErrorLog,
+    types::{HashDigest, TimestampMillis},
+};
+
+/// A transaction known to some of the watched nodes but not all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergentTransaction {
+    pub id: HashDigest,
+    pub present_on: Vec<String>,
+    pub missing_on: Vec<String>,
+    /// When the divergence was first observed.
+    pub since: TimestampMillis,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DivergenceReport {
+    pub observed_at: TimestampMillis,
+    /// Nodes that answered the last poll. Unreachable nodes are left out of the comparison.
+    pub nodes: Vec<String>,
+    pub transactions: Vec<DivergentTransaction>,
+    /// Number of divergences that ended since the tracker started.
+    pub resolved: u64,
+    pub resolved_total_ms: u64,
+    pub resolved_max_ms: u64,
+}
+
+impl DivergenceReport {
+    /// Divergent transaction count per node that is missing them.
+    pub fn missing_by_node(&self) -> BTreeMap<&str, usize> {
+        let mut missing: BTreeMap<&str, usize> =
+            self.nodes.iter().map(|n| (n.as_str(), 0)).collect();
+        for tx in &self.transactions {
+            for node in &tx.missing_on {
+                *missing.entry(node.as_str()).or_default() += 1;
+            }
+        }
+        missing
+    }
+
+    /// Age of the oldest ongoing divergence in ms.
+    pub fn oldest_age(&self) -> u64 {
+        self.transactions
+            .iter()
+            .map(|tx| self.observed_at.since(tx.since).as_millis() as u64)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Renders the report in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut gauge = |name: &str, help: &str, kind: &str, samples: &[(String, u64)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+
+        gauge(
+            "hergmes_mempool_divergent_transactions",
+            "Transactions present on some watched nodes but not others.",
+            "gauge",
+            &[(String::new(), self.transactions.len() as u64)],
+        );
+        let missing: Vec<_> = self
+            .missing_by_node()
+            .into_iter()
+            .map(|(node, count)| (format!("{{node=\"{}\"}}", escape_label(node)), count as u64))
+            .collect();
+        gauge(
+            "hergmes_mempool_missing_transactions",
+            "Divergent transactions missing from a node's mempool.",
+            "gauge",
+            &missing,
+        );
+        gauge(
+            "hergmes_mempool_divergence_oldest_seconds",
+            "Age of the oldest ongoing divergence.",
+            "gauge",
+            &[(String::new(), self.oldest_age() / 1000)],
+        );
+        gauge(
+            "hergmes_mempool_divergence_resolved_total",
+            "Divergences that ended.",
+            "counter",
+            &[(String::new(), self.resolved)],
+        );
+        gauge(
+            "hergmes_mempool_divergence_resolved_seconds_total",
+            "Summed duration of ended divergences.",
+            "counter",
+            &[(String::new(), self.resolved_total_ms / 1000)],
+        );
+        gauge(
+            "hergmes_mempool_divergence_resolved_max_seconds",
+            "Longest ended divergence.",
+            "gauge",
+            &[(String::new(), self.resolved_max_ms / 1000)],
+        );
+
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Compares the mempools of several nodes over time.
+#[derive(Default)]
+pub struct DivergenceTracker {
+    since: HashMap<HashDigest, TimestampMillis>,
+    resolved: u64,
+    resolved_total_ms: u64,
+    resolved_max_ms: u64,
+}
+
+impl DivergenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the transaction ids each node reported at `now` and returns the
+    /// resulting report. A divergence ends when every node agrees, whether the transaction
+    /// propagated or was dropped everywhere.
+    pub fn observe(
+        &mut self,
+        now: TimestampMillis,
+        views: &BTreeMap<String, Vec<HashDigest>>,
+    ) -> DivergenceReport {
+        let sets: Vec<(&String, HashSet<&HashDigest>)> = views
+            .iter()
+            .map(|(node, ids)| (node, ids.iter().collect()))
+            .collect();
+        let all: HashSet<&HashDigest> = sets
+            .iter()
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect();
+
+        let mut transactions = Vec::new();
+        for id in all {
+            let (present, missing): (Vec<_>, Vec<_>) =
+                sets.iter().partition(|(_, ids)| ids.contains(id));
+            if missing.is_empty() {
+                continue;
+            }
+
+            let since = *self.since.entry(id.clone()).or_insert(now);
+            transactions.push(DivergentTransaction {
+                id: id.clone(),
+                present_on: present.iter().map(|(node, _)| (*node).clone()).collect(),
+                missing_on: missing.iter().map(|(node, _)| (*node).clone()).collect(),
+                since,
+            });
+        }
+        transactions.sort_by(|a, b| a.since.cmp(&b.since).then_with(|| a.id.cmp(&b.id)));
+
+        let ongoing: HashSet<&HashDigest> = transactions.iter().map(|tx| &tx.id).collect();
+        let ended: Vec<HashDigest> = self
+            .since
+            .keys()
+            .filter(|id| !ongoing.contains(id))
+            .cloned()
+            .collect();
+        for id in ended {
+            let duration = now.since(self.since.remove(&id).unwrap_or(now)).as_millis() as u64;
+            self.resolved += 1;
+            self.resolved_total_ms += duration;
+            self.resolved_max_ms = self.resolved_max_ms.max(duration);
+        }
+
+        DivergenceReport {
+            observed_at: now,
+            nodes: views.keys().cloned().collect(),
+            transactions,
+            resolved: self.resolved,
+            resolved_total_ms: self.resolved_total_ms,
+            resolved_max_ms: self.resolved_max_ms,
+        }
+    }
+}
+
+/// Polls the unconfirmed transaction ids of every named node each `interval` and keeps the
+/// latest divergence report.
+pub fn spawn_divergence(
+    nodes: Vec<(String, NodeClient)>,
+    interval: Duration,
+) -> (Arc<ArcSwap<DivergenceReport>>, JoinHandle<()>) {
+    let report = Arc::new(ArcSwap::from_pointee(DivergenceReport::default()));
+    let cloned_report = report.clone();
+
+    let handle = tokio::spawn(async move {
+        info!(nodes = nodes.len(), "Starting mempool divergence tracker...");
+        let mut tracker = DivergenceTracker::new();
+        let mut errors: Vec<ErrorLog> = nodes
+            .iter()
+            .map(|(name, _)| ErrorLog::new(format!("divergence tracker of `{name}`")))
+            .collect();
+        loop {
+            let mut views = BTreeMap::new();
+            for ((name, node), errors) in nodes.iter().zip(&mut errors) {
+                match node.get_unconfirmed_transaction_ids().await {
+                    Ok(ids) => {
+                        views.insert(name.clone(), ids);
+                        errors.success();
+                    }
+                    Err(e) => errors.failure(&e),
+                }
+            }
+
+            if views.len() > 1 {
+                let next = tracker.observe(TimestampMillis::now(), &views);
+                if !next.transactions.is_empty() {
+                    info!(
+                        divergent = next.transactions.len(),
+                        oldest_ms = next.oldest_age(),
+                        "Node mempools diverge."
+                    );
+                }
+                cloned_report.store(Arc::new(next));
+            }
+
+            sleep(interval).await;
+        }
+    });
+
+    (report, handle)
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filter::Filter,
+    types::{HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+/// Transaction ids that entered and left the mempool between two snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    /// In the order of the newer snapshot.
+    pub added: Vec<HashDigest>,
+    /// In the order of the older snapshot.
+    pub removed: Vec<HashDigest>,
+    /// Transactions present in both snapshots.
+    pub unchanged_count: usize,
+}
+
+impl SnapshotDiff {
+    pub fn between(from: &MempoolSnapshot, to: &MempoolSnapshot) -> Self {
+        Self::of(
+            &from.transactions.iter().collect::<Vec<_>>(),
+            &to.transactions.iter().collect::<Vec<_>>(),
+        )
+    }
+
+    /// Changes restricted to transactions matching `filter`, e.g. to notify a subscriber only
+    /// of the transactions it watches.
+    pub fn between_matching(from: &MempoolSnapshot, to: &MempoolSnapshot, filter: &Filter) -> Self {
+        Self::of(&from.matching(filter), &to.matching(filter))
+    }
+
+    fn of(from: &[&UnconfirmedTransaction], to: &[&UnconfirmedTransaction]) -> Self {
+        let before: HashSet<&HashDigest> = from.iter().map(|tx| &tx.id).collect();
+        let after: HashSet<&HashDigest> = to.iter().map(|tx| &tx.id).collect();
+
+        let added: Vec<HashDigest> = to
+            .iter()
+            .filter(|tx| !before.contains(&tx.id))
+            .map(|tx| tx.id.clone())
+            .collect();
+        Self {
+            unchanged_count: to.len() - added.len(),
+            added,
+            removed: from
+                .iter()
+                .filter(|tx| !after.contains(&tx.id))
+                .map(|tx| tx.id.clone())
+                .collect(),
+        }
+    }
+
+    /// Whether the two snapshots hold the same transactions.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Ring buffer of the last `capacity` mempool snapshots, ordered by update time.
+pub struct SnapshotHistory {
+    capacity: usize,
+    snapshots: VecDeque<Arc<MempoolSnapshot>>,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self { capacity, snapshots: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Appends a snapshot, evicting the oldest one when full. Snapshots older than the latest
+    /// retained one are ignored.
+    pub fn push(&mut self, snapshot: Arc<MempoolSnapshot>) {
+        if self
+            .latest()
+            .is_some_and(|latest| latest.last_update > snapshot.last_update)
+        {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn latest(&self) -> Option<&Arc<MempoolSnapshot>> {
+        self.snapshots.back()
+    }
+
+    pub fn oldest(&self) -> Option<&Arc<MempoolSnapshot>> {
+        self.snapshots.front()
+    }
+
+    /// The snapshot in effect at `timestamp`: the latest one updated at or before it.
+    /// `None` if `timestamp` predates the retained window.
+    pub fn snapshot_at(&self, timestamp: TimestampMillis) -> Option<&Arc<MempoolSnapshot>> {
+        let after = self
+            .snapshots
+            .partition_point(|s| s.last_update <= timestamp);
+        after.checked_sub(1).map(|i| &self.snapshots[i])
+    }
+
+    /// Mempool churn between two points in time within the retained window.
+    pub fn diff(&self, from: TimestampMillis, to: TimestampMillis) -> Option<SnapshotDiff> {
+        Some(SnapshotDiff::between(self.snapshot_at(from)?, self.snapshot_at(to)?))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<MempoolSnapshot>> {
+        self.snapshots.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
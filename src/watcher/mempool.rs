@@ -1,36 +1,329 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use arc_swap::ArcSwap;
-use tokio::time::sleep;
-use tracing::{error, info};
+use tracing::{debug, info};
 
-use crate::{clients::node::NodeClient, error::AppError, types::ergo::UnconfirmedTransaction};
+use crate::{
+    address::{AddressSet, ErgoAddress},
+    chain::{fee, transaction},
+    clients::node::{MempoolPoll, NodeClient, PushTopic},
+    clock::Clock,
+    error::AppError,
+    filter::Filter,
+    hash::blake2b256,
+    intern::{ErgoTreeBytes, ErgoTreeInterner},
+    trace::ErrorLog,
+    types::{Digest, HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::{SnapshotDiff, SnapshotHistory},
+};
 
-#[derive(Default)]
+/// Time between mempool polls, unless woken up by pushed events.
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Position of an output in a snapshot: transaction index, then output index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxRef {
+    pub tx: usize,
+    pub output: usize,
+}
+
+/// The transactions and indexes of a snapshot are shared, so snapshots differing only in
+/// `last_update` cost no copies, see [`refreshed`](MempoolSnapshot::refreshed).
 pub struct MempoolSnapshot {
-    pub last_update: u64,
-    pub transactions: Vec<UnconfirmedTransaction>,
+    pub last_update: TimestampMillis,
+    /// Ordered by first-seen time, then id, regardless of the order the node returned them in.
+    pub transactions: Arc<Vec<UnconfirmedTransaction>>,
+    /// `last_update` of the snapshot each transaction first appeared in.
+    pub first_seen: Arc<HashMap<HashDigest, TimestampMillis>>,
+    /// Outputs by ErgoTree, in transaction order. Trees are pooled, so a tree is shared by every
+    /// snapshot it appears in.
+    pub outputs_by_tree: Arc<HashMap<Arc<ErgoTreeBytes>, Vec<TxRef>>>,
+    /// Serialized size of each transaction, in bytes. Transactions the binary serializer
+    /// rejects are missing.
+    pub sizes: Arc<HashMap<HashDigest, usize>>,
+    /// Sum of `sizes`.
+    pub total_bytes: u64,
+    /// Transactions relayed by peers that the node hadn't returned yet, see
+    /// [`with_relayed`](Self::with_relayed).
+    pub relayed: Arc<HashSet<HashDigest>>,
+    content_hash: HashDigest,
+}
+
+impl Default for MempoolSnapshot {
+    fn default() -> Self {
+        Self {
+            last_update: TimestampMillis(0),
+            transactions: Arc::default(),
+            first_seen: Arc::default(),
+            outputs_by_tree: Arc::default(),
+            sizes: Arc::default(),
+            total_bytes: 0,
+            relayed: Arc::default(),
+            content_hash: content_hash(&[]),
+        }
+    }
+}
+
+impl MempoolSnapshot {
+    /// A snapshot with no history: every transaction is first seen at `last_update`.
+    pub fn new(
+        last_update: TimestampMillis,
+        transactions: Vec<UnconfirmedTransaction>,
+        interner: &ErgoTreeInterner,
+    ) -> Self {
+        Self::with_first_seen(last_update, transactions, HashMap::new(), interner)
+    }
+
+    /// The snapshot following this one, keeping the first-seen times of its transactions and
+    /// the sizes of those already serialized.
+    pub fn next(
+        &self,
+        last_update: TimestampMillis,
+        transactions: Vec<UnconfirmedTransaction>,
+        interner: &ErgoTreeInterner,
+    ) -> Self {
+        let first_seen = (*self.first_seen).clone();
+        Self::build(last_update, transactions, first_seen, &self.sizes, interner)
+    }
+
+    /// Transactions missing from `first_seen` are first seen at `last_update`; entries of
+    /// other transactions are dropped.
+    pub fn with_first_seen(
+        last_update: TimestampMillis,
+        transactions: Vec<UnconfirmedTransaction>,
+        first_seen: HashMap<HashDigest, TimestampMillis>,
+        interner: &ErgoTreeInterner,
+    ) -> Self {
+        Self::build(last_update, transactions, first_seen, &HashMap::new(), interner)
+    }
+
+    fn build(
+        last_update: TimestampMillis,
+        mut transactions: Vec<UnconfirmedTransaction>,
+        first_seen: HashMap<HashDigest, TimestampMillis>,
+        known_sizes: &HashMap<HashDigest, usize>,
+        interner: &ErgoTreeInterner,
+    ) -> Self {
+        let first_seen: HashMap<HashDigest, TimestampMillis> = transactions
+            .iter()
+            .map(|tx| (tx.id.clone(), first_seen.get(&tx.id).copied().unwrap_or(last_update)))
+            .collect();
+        transactions.sort_by(|a, b| (first_seen[&a.id], &a.id).cmp(&(first_seen[&b.id], &b.id)));
+
+        let mut outputs_by_tree: HashMap<Arc<ErgoTreeBytes>, Vec<TxRef>> = HashMap::new();
+        for (tx, transaction) in transactions.iter().enumerate() {
+            for (output, utxo) in transaction.outputs.iter().enumerate() {
+                let tree = interner.intern(&utxo.ergo_tree.0);
+                outputs_by_tree
+                    .entry(tree)
+                    .or_default()
+                    .push(TxRef { tx, output });
+            }
+        }
+        let sizes: HashMap<HashDigest, usize> = transactions
+            .iter()
+            .filter_map(|tx| {
+                let size = match known_sizes.get(&tx.id) {
+                    Some(size) => *size,
+                    None => transaction::unconfirmed_size(tx)
+                        .inspect_err(
+                            |e| debug!(id = %tx.id, %e, "Failed to serialize transaction."),
+                        )
+                        .ok()?,
+                };
+                Some((tx.id.clone(), size))
+            })
+            .collect();
+        let total_bytes = sizes.values().map(|size| *size as u64).sum();
+
+        let content_hash = content_hash(&transactions);
+        Self {
+            last_update,
+            transactions: Arc::new(transactions),
+            first_seen: Arc::new(first_seen),
+            outputs_by_tree: Arc::new(outputs_by_tree),
+            sizes: Arc::new(sizes),
+            total_bytes,
+            relayed: Arc::default(),
+            content_hash,
+        }
+    }
+
+    /// This snapshot with `tx`, relayed by a peer, first seen at `seen`. `last_update` is
+    /// kept, as the node's mempool didn't change; its next update replaces the transactions,
+    /// keeping `seen` as the first-seen time of `tx` if the node has it by then.
+    pub fn with_relayed(
+        &self,
+        tx: UnconfirmedTransaction,
+        seen: TimestampMillis,
+        interner: &ErgoTreeInterner,
+    ) -> Self {
+        let mut relayed = (*self.relayed).clone();
+        relayed.insert(tx.id.clone());
+        let mut first_seen = (*self.first_seen).clone();
+        first_seen.insert(tx.id.clone(), seen);
+        let mut transactions = (*self.transactions).clone();
+        transactions.push(tx);
+        let snapshot =
+            Self::build(self.last_update, transactions, first_seen, &self.sizes, interner);
+        Self { relayed: Arc::new(relayed), ..snapshot }
+    }
+
+    /// The same transactions as of a later node update at `last_update`, for polls where the
+    /// node reported an update but returned an identical mempool. Shares the transactions and
+    /// indexes of this snapshot.
+    pub fn refreshed(&self, last_update: TimestampMillis) -> Self {
+        Self {
+            last_update,
+            transactions: self.transactions.clone(),
+            first_seen: self.first_seen.clone(),
+            outputs_by_tree: self.outputs_by_tree.clone(),
+            sizes: self.sizes.clone(),
+            total_bytes: self.total_bytes,
+            relayed: self.relayed.clone(),
+            content_hash: self.content_hash.clone(),
+        }
+    }
+
+    /// Time since the node last updated its mempool as of this snapshot.
+    pub fn age(&self, clock: &dyn Clock) -> Duration {
+        clock.timestamp().since(self.last_update)
+    }
+
+    /// Whether the snapshot is older than `max_age`, e.g. because the node stopped updating
+    /// its mempool or the watcher is failing to poll it.
+    pub fn is_stale(&self, clock: &dyn Clock, max_age: Duration) -> bool {
+        self.age(clock) > max_age
+    }
+
+    /// Serialized size of a transaction of the snapshot, in bytes.
+    pub fn size_of(&self, id: &HashDigest) -> Option<usize> {
+        self.sizes.get(id).copied()
+    }
+
+    /// Miner fee per serialized byte of a transaction of the snapshot, in nanoERG.
+    pub fn fee_per_byte(&self, tx: &UnconfirmedTransaction) -> Option<f64> {
+        let size = self.size_of(&tx.id)?;
+        let fee = fee::outputs_fee(&tx.outputs).ok()?;
+        Some(fee as f64 / size as f64)
+    }
+
+    /// Hash of the sorted transaction ids: equal for snapshots holding the same transactions,
+    /// whatever their `last_update`.
+    pub fn content_hash(&self) -> &HashDigest {
+        &self.content_hash
+    }
+
+    /// Changes from this snapshot to `newer`.
+    pub fn diff(&self, newer: &MempoolSnapshot) -> SnapshotDiff {
+        SnapshotDiff::between(self, newer)
+    }
+
+    pub fn outputs_for_tree(&self, tree: &[u8]) -> &[TxRef] {
+        self.outputs_by_tree
+            .get(&ErgoTreeBytes::from(tree))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Transactions with at least one output guarded by `tree`.
+    pub fn transactions_for_tree(&self, tree: &[u8]) -> Vec<&UnconfirmedTransaction> {
+        self.outputs_for_tree(tree)
+            .chunk_by(|a, b| a.tx == b.tx)
+            .map(|refs| &self.transactions[refs[0].tx])
+            .collect()
+    }
+
+    /// Transactions paying `address`. Always empty for P2SH addresses, whose tree is unknown.
+    pub fn transactions_for_address(&self, address: &ErgoAddress) -> Vec<&UnconfirmedTransaction> {
+        match address.ergo_tree() {
+            Some(tree) => self.transactions_for_tree(&tree.0),
+            None => Vec::new(),
+        }
+    }
+
+    /// Transactions paying any of `addresses`, P2SH ones included, in snapshot order. Each
+    /// distinct tree of the snapshot is hashed once, whatever the number of addresses.
+    pub fn transactions_for_addresses(
+        &self,
+        addresses: &AddressSet,
+    ) -> Vec<&UnconfirmedTransaction> {
+        let mut txs: Vec<usize> = self
+            .outputs_by_tree
+            .iter()
+            .filter(|(tree, _)| addresses.contains_tree(tree.as_bytes()))
+            .flat_map(|(_, refs)| refs.iter().map(|r| r.tx))
+            .collect();
+        txs.sort_unstable();
+        txs.dedup();
+        txs.into_iter().map(|tx| &self.transactions[tx]).collect()
+    }
+
+    /// Transactions with at least one output matching `filter`, in snapshot order.
+    pub fn matching(&self, filter: &Filter) -> Vec<&UnconfirmedTransaction> {
+        self.transactions
+            .iter()
+            .filter(|tx| filter.matches_transaction(tx))
+            .collect()
+    }
+}
+
+fn content_hash(transactions: &[UnconfirmedTransaction]) -> HashDigest {
+    let mut ids: Vec<&HashDigest> = transactions.iter().map(|tx| &tx.id).collect();
+    ids.sort_unstable();
+    let bytes: Vec<u8> = ids.iter().flat_map(|id| id.0).collect();
+    Digest(blake2b256(&bytes))
 }
 
-#[tracing::instrument(skip(node, swap))]
-pub async fn start(node: &NodeClient, swap: Arc<ArcSwap<MempoolSnapshot>>) -> Result<(), AppError> {
+#[tracing::instrument(skip(node, swap, history))]
+pub async fn start(
+    node: &NodeClient,
+    swap: Arc<ArcSwap<MempoolSnapshot>>,
+    history: Arc<RwLock<SnapshotHistory>>,
+) -> Result<(), AppError> {
     info!("Starting mempool indexer...");
 
-    let mut last_update = 0u64;
+    let interner = ErgoTreeInterner::new();
+    let mut errors = ErrorLog::new("mempool watcher");
+    let mut last_update = TimestampMillis(0);
+    let mut body_hash = None;
+    let mut wakeup = node.wakeup(PushTopic::Mempool, MEMPOOL_POLL_INTERVAL);
     loop {
         match node.get_last_mempool_update_timestamp().await {
-            Ok(updated) if updated > last_update => match node.get_mempool_snapshot().await {
-                Ok(transactions) => {
-                    last_update = updated;
-                    info!(count = ?transactions.len(), ?last_update, "Mempool updated, storing new snapshot");
-                    swap.store(Arc::new(MempoolSnapshot { last_update, transactions }));
+            Ok(updated) if updated > last_update => {
+                match node
+                    .get_mempool_snapshot_if_changed(body_hash.as_ref())
+                    .await
+                {
+                    Ok(MempoolPoll::Changed { body_hash: hash, transactions }) => {
+                        last_update = updated;
+                        body_hash = Some(hash);
+                        info!(count = ?transactions.len(), ?last_update, "Mempool updated, storing new snapshot");
+                        let snapshot = swap.load().next(last_update, transactions, &interner);
+                        let snapshot = Arc::new(snapshot);
+                        history.write().unwrap().push(snapshot.clone());
+                        swap.store(snapshot);
+                        // Trees of snapshots evicted from the history.
+                        interner.purge();
+                        errors.success();
+                    }
+                    Ok(MempoolPoll::Unchanged) => {
+                        last_update = updated;
+                        // Keeps `age` and `is_stale` in line with the node, without a
+                        // history entry as nothing changed.
+                        swap.store(Arc::new(swap.load().refreshed(last_update)));
+                        errors.success();
+                    }
+                    Err(e) => errors.failure(&e),
                 }
-                Err(e) => error!("Error fetching mempool snapshot: {:?}", e),
-            },
-            Err(e) => error!("Error fetching mempool update timestamp: {:?}", e),
-            _ => {}
+            }
+            Err(e) => errors.failure(&e),
+            _ => errors.success(),
         }
 
-        sleep(Duration::from_secs(1)).await;
+        wakeup.wait().await;
     }
 }
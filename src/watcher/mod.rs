@@ -1,17 +1,70 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use arc_swap::ArcSwap;
-pub use mempool::MempoolSnapshot;
+pub use blockchain::{
+    BlockRef, CATCH_UP_CHUNK, CATCH_UP_CONCURRENCY, ChainError, ChainEvent, ChainEvents,
+    ChainSequencer, ChainSubscription, CursorError, DEFAULT_FINALITY_DEPTH, SinkCursor,
+    SinkCursors, SinkMode, SyncError, catch_up, spawn_chain_events,
+};
+pub use blocks::BlockFollower;
+pub use bridge::{BridgeEvent, LockRequest, Payout, RosenScanner, fetch_lock_boxes, spawn_rosen};
+pub use delta::{DEFAULT_KEYFRAME_INTERVAL, DeltaDecoder, DeltaEncoder, DeltaError, SnapshotFrame};
+pub use divergence::{DivergenceReport, DivergenceTracker, DivergentTransaction, spawn_divergence};
+pub use history::{SnapshotDiff, SnapshotHistory};
+pub use mempool::{MempoolSnapshot, TxRef};
+#[cfg(feature = "proofs")]
+pub use proofs::{InvalidTransaction, ProofReport, ProofVerifier, spawn_proof_verifier};
+#[cfg(feature = "p2p")]
+pub use relay::{RelayError, spawn_relay};
+use tokio::task::JoinHandle;
 
-use crate::{clients::node::NodeClient, error::AppError};
+use crate::{
+    clients::node::NodeClient,
+    error::AppError,
+    supervisor::{RestartPolicy, supervise},
+};
 
+mod blockchain;
+mod blocks;
+mod bridge;
+mod delta;
+mod divergence;
+mod history;
 mod mempool;
+#[cfg(feature = "proofs")]
+mod proofs;
+#[cfg(feature = "p2p")]
+mod relay;
+
+/// Number of mempool snapshots retained by [`spawn`].
+pub const DEFAULT_HISTORY_CAPACITY: usize = 60;
+
+/// Handles to a running mempool indexer.
+pub struct MempoolWatch {
+    pub snapshot: Arc<ArcSwap<MempoolSnapshot>>,
+    pub history: Arc<RwLock<SnapshotHistory>>,
+    pub handle: JoinHandle<Result<(), AppError>>,
+}
 
 pub async fn spawn(node: NodeClient) -> Result<Arc<ArcSwap<MempoolSnapshot>>, AppError> {
-    let mempool_snapshot = Arc::new(ArcSwap::from_pointee(MempoolSnapshot::default()));
-    let cloned_mempool_snapshot = mempool_snapshot.clone();
+    let watch = spawn_mempool(node, DEFAULT_HISTORY_CAPACITY);
+    watch.handle.await??;
+
+    Ok(watch.snapshot)
+}
+
+/// Starts the mempool indexer without waiting on it, retaining the last `history_capacity`
+/// snapshots. The indexer is restarted on panics under the default [`RestartPolicy`].
+pub fn spawn_mempool(node: NodeClient, history_capacity: usize) -> MempoolWatch {
+    let snapshot = Arc::new(ArcSwap::from_pointee(MempoolSnapshot::default()));
+    let history = Arc::new(RwLock::new(SnapshotHistory::new(history_capacity)));
+    let (cloned_snapshot, cloned_history) = (snapshot.clone(), history.clone());
 
-    let _ = tokio::spawn(async move { mempool::start(&node, cloned_mempool_snapshot).await }).await;
+    let handle = supervise("mempool watcher", RestartPolicy::default(), move || {
+        let (node, snapshot, history) =
+            (node.clone(), cloned_snapshot.clone(), cloned_history.clone());
+        async move { mempool::start(&node, snapshot, history).await }
+    });
 
-    Ok(mempool_snapshot)
+    MempoolWatch { snapshot, history, handle }
 }
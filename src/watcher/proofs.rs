@@ -0,0 +1,179 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    sync::Arc,
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    chain::schnorr::{self, ProofCheck},
+    types::{HashDigest, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Proof checks of a transaction's inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TxProofs {
+    valid: usize,
+    invalid: usize,
+    unsupported: usize,
+}
+
+/// A mempool transaction with at least one input whose P2PK proof doesn't verify.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidTransaction {
+    pub id: HashDigest,
+    pub invalid_inputs: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofReport {
+    pub last_update: TimestampMillis,
+    pub invalid: Vec<InvalidTransaction>,
+    /// Inputs of the snapshot with a valid P2PK proof.
+    pub valid_inputs: usize,
+    /// Inputs of the snapshot that are not P2PK, whose proofs are not checked.
+    pub unsupported_inputs: usize,
+}
+
+impl ProofReport {
+    /// Renders the report in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (name, help, value) in [
+            (
+                "hergmes_mempool_invalid_proof_transactions",
+                "Mempool transactions with an input whose P2PK proof doesn't verify.",
+                self.invalid.len(),
+            ),
+            (
+                "hergmes_mempool_unverified_inputs",
+                "Mempool inputs whose proofs are not checked, not being P2PK.",
+                self.unsupported_inputs,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {value}");
+        }
+        out
+    }
+}
+
+/// Checks the P2PK spending proofs of mempool snapshots. Transactions are checked once, when
+/// they enter the mempool; the new transactions of a snapshot are checked as one batch,
+/// spread over the rayon thread pool with the `rayon` feature.
+#[derive(Debug, Default)]
+pub struct ProofVerifier {
+    checked: HashMap<HashDigest, TxProofs>,
+}
+
+impl ProofVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the transactions of `snapshot` not seen in previous ones, forgetting those that
+    /// left the mempool.
+    pub fn verify_snapshot(&mut self, snapshot: &MempoolSnapshot) -> ProofReport {
+        let new: Vec<&UnconfirmedTransaction> = snapshot
+            .transactions
+            .iter()
+            .filter(|tx| !self.checked.contains_key(&tx.id))
+            .collect();
+        let mut checked: HashMap<HashDigest, TxProofs> = check_batch(&new)
+            .into_iter()
+            .zip(&new)
+            .map(|(proofs, tx)| (tx.id.clone(), proofs))
+            .collect();
+        for tx in snapshot.transactions.iter() {
+            if let Some(proofs) = self.checked.remove(&tx.id) {
+                checked.insert(tx.id.clone(), proofs);
+            }
+        }
+        self.checked = checked;
+
+        let mut report = ProofReport { last_update: snapshot.last_update, ..Default::default() };
+        for tx in snapshot.transactions.iter() {
+            let proofs = self.checked[&tx.id];
+            report.valid_inputs += proofs.valid;
+            report.unsupported_inputs += proofs.unsupported;
+            if proofs.invalid > 0 {
+                report
+                    .invalid
+                    .push(InvalidTransaction { id: tx.id.clone(), invalid_inputs: proofs.invalid });
+            }
+        }
+        report
+    }
+}
+
+fn check_batch(transactions: &[&UnconfirmedTransaction]) -> Vec<TxProofs> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        transactions.par_iter().map(|tx| check(tx)).collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        transactions.iter().map(|tx| check(tx)).collect()
+    }
+}
+
+/// Counts the proof checks of a transaction's inputs. A transaction that can't be serialized
+/// has no message to check against, so none of its proofs hold.
+fn check(tx: &UnconfirmedTransaction) -> TxProofs {
+    let Ok(checks) = schnorr::check_transaction(tx) else {
+        return TxProofs { invalid: tx.inputs.len(), ..Default::default() };
+    };
+    let mut proofs = TxProofs::default();
+    for check in checks {
+        match check {
+            ProofCheck::Valid => proofs.valid += 1,
+            ProofCheck::Invalid => proofs.invalid += 1,
+            ProofCheck::Unsupported => proofs.unsupported += 1,
+        }
+    }
+    proofs
+}
+
+/// Checks the proofs of every new mempool snapshot, logging the transactions that fail once.
+pub fn spawn_proof_verifier(mempool: Arc<ArcSwap<MempoolSnapshot>>) -> Arc<ArcSwap<ProofReport>> {
+    let report = Arc::new(ArcSwap::from_pointee(ProofReport::default()));
+    let cloned_report = report.clone();
+
+    tokio::spawn(async move {
+        info!("Starting mempool proof verification...");
+        let mut verifier = ProofVerifier::new();
+        let mut last_update = TimestampMillis(0);
+        let mut flagged: HashSet<HashDigest> = HashSet::new();
+        loop {
+            let snapshot = mempool.load_full();
+            if snapshot.last_update > last_update {
+                last_update = snapshot.last_update;
+                let next = verifier.verify_snapshot(&snapshot);
+                for tx in &next.invalid {
+                    if flagged.insert(tx.id.clone()) {
+                        warn!(id = %tx.id, inputs = tx.invalid_inputs, "Invalid spending proof in mempool.");
+                    }
+                }
+                flagged.retain(|id| next.invalid.iter().any(|tx| tx.id == *id));
+                cloned_report.store(Arc::new(next));
+            }
+            sleep(DEFAULT_POLL_INTERVAL).await;
+        }
+    });
+
+    report
+}
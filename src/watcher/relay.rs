@@ -0,0 +1,111 @@
+use std::{collections::HashMap, sync::Arc};
+
+use arc_swap::ArcSwap;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{debug, info};
+
+use crate::{
+    chain::transaction,
+    clients::node::{NodeClient, NodeError},
+    codec::CodecError,
+    intern::ErgoTreeInterner,
+    p2p::P2pEvent,
+    trace::ErrorLog,
+    types::{
+        HashDigest,
+        ergo::{TransactionInput, UTxO, UnconfirmedTransaction},
+    },
+    watcher::MempoolSnapshot,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    #[error("Failed to decode relayed transaction: {0}")]
+    Codec(#[from] CodecError),
+
+    #[error("Failed to fetch input box {0}: {1}")]
+    Input(HashDigest, NodeError),
+}
+
+/// Merges the transactions relayed by peers into the mempool snapshot as they arrive, rather
+/// than on the node's next mempool update.
+///
+/// Inputs are looked up among the outputs of the snapshot, then on the blockchain indexer.
+/// Transactions already in the snapshot, e.g. relayed by another peer, are skipped.
+pub fn spawn_relay(
+    node: NodeClient,
+    snapshot: Arc<ArcSwap<MempoolSnapshot>>,
+    mut events: mpsc::Receiver<P2pEvent>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Starting P2P mempool relay...");
+        let interner = ErgoTreeInterner::new();
+        let mut errors = ErrorLog::new("P2P relay");
+        while let Some(event) = events.recv().await {
+            let P2pEvent::Transaction { peer, id, bytes } = event else {
+                continue;
+            };
+            if snapshot.load().first_seen.contains_key(&id) {
+                continue;
+            }
+            match unconfirmed(&node, &snapshot.load(), &bytes).await {
+                Ok(tx) => {
+                    debug!(%peer, %id, "Relaying transaction.");
+                    let seen = node.clock().timestamp();
+                    snapshot.rcu(|current| match current.first_seen.contains_key(&tx.id) {
+                        true => current.clone(),
+                        false => Arc::new(current.with_relayed(tx.clone(), seen, &interner)),
+                    });
+                    interner.purge();
+                    errors.success();
+                }
+                Err(e) => errors.failure(&e),
+            }
+        }
+    })
+}
+
+/// Decodes a relayed transaction and looks up the boxes it spends.
+pub async fn unconfirmed(
+    node: &NodeClient,
+    snapshot: &MempoolSnapshot,
+    bytes: &[u8],
+) -> Result<UnconfirmedTransaction, RelayError> {
+    let tx = transaction::parse(bytes)?;
+    let id = tx.id.clone().expect("parsed transactions have an id");
+    let outputs = transaction::output_boxes(&tx)?;
+
+    // Chained transactions spend boxes only the mempool knows of.
+    let unconfirmed: HashMap<&HashDigest, &UTxO> = snapshot
+        .transactions
+        .iter()
+        .flat_map(|tx| &tx.outputs)
+        .map(|utxo| (&utxo.id, utxo))
+        .collect();
+    let missing: Vec<HashDigest> = tx
+        .inputs
+        .iter()
+        .map(|input| input.box_id.clone())
+        .filter(|id| !unconfirmed.contains_key(id))
+        .collect();
+    let mut fetched = node.get_boxes_by_ids(&missing).await;
+
+    let inputs = tx
+        .inputs
+        .into_iter()
+        .map(|input| {
+            let utxo = match unconfirmed.get(&input.box_id) {
+                Some(utxo) => (*utxo).clone(),
+                None => match fetched.remove(&input.box_id) {
+                    Some(Ok(indexed)) => indexed.utxo,
+                    Some(Err(e)) => return Err(RelayError::Input(input.box_id, e)),
+                    // The box was taken by an earlier input of the transaction.
+                    None => return Err(RelayError::Codec(CodecError("input spent twice"))),
+                },
+            };
+            Ok(TransactionInput { utxo, spending_proof: input.spending_proof })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(UnconfirmedTransaction { id, inputs, data_inputs: tx.data_inputs, outputs })
+}
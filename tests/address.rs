@@ -0,0 +1,165 @@
+use hergmes::address::{
+    AddressError, AddressSet, AddressType, EncodedAddress, ErgoAddress, NetworkPrefix,
+    typo::{self, TypoFix},
+};
+
+fn addresses(n: u8) -> Vec<String> {
+    (0..n)
+        .map(|i| {
+            let mut key = [i; 33];
+            key[0] = 0x02;
+            ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key).encode()
+        })
+        .collect()
+}
+
+#[test]
+fn decodes_batches() {
+    let mut encoded = addresses(64);
+    let typo = if &encoded[7][10..11] == "z" { "y" } else { "z" };
+    encoded[7].replace_range(10..11, typo);
+    encoded[9] = "0OIl".to_string();
+
+    let decoded = ErgoAddress::decode_batch(&encoded);
+    assert_eq!(decoded.len(), encoded.len());
+    for (i, (result, s)) in decoded.iter().zip(&encoded).enumerate() {
+        match i {
+            7 => assert!(matches!(result, Err(AddressError::ChecksumMismatch))),
+            9 => assert!(matches!(result, Err(AddressError::InvalidBase58))),
+            _ => assert_eq!(result.as_ref().unwrap(), &s.parse::<ErgoAddress>().unwrap()),
+        }
+    }
+}
+
+#[test]
+fn round_trips_every_kind() {
+    let encoded = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+    let address: ErgoAddress = encoded.parse().unwrap();
+    assert_eq!(address.content().len(), 33);
+    assert_eq!(address.encode(), encoded);
+
+    let tree = hex::decode("100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a70173007301").unwrap();
+    for network in [NetworkPrefix::Mainnet, NetworkPrefix::Testnet] {
+        let p2s = ErgoAddress::from_ergo_tree(network, &tree);
+        let decoded: ErgoAddress = p2s.encode().parse().unwrap();
+        assert_eq!(decoded, p2s);
+        assert_eq!(decoded.ergo_tree().unwrap().0, tree);
+    }
+}
+
+#[test]
+fn encodes_into_reused_buffers() {
+    let long_tree = [0x10; 300];
+    let addresses = [
+        ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33]),
+        ErgoAddress::from_ergo_tree(NetworkPrefix::Testnet, &long_tree),
+    ];
+
+    let mut encoded = String::from("prefix:");
+    for address in &addresses {
+        encoded.truncate("prefix:".len());
+        address.encode_into(&mut encoded);
+        assert_eq!(&encoded["prefix:".len()..], address.encode());
+        assert!(address.encode().len() <= address.encoded_len());
+        assert_eq!(address.encode().parse::<ErgoAddress>().unwrap(), *address);
+    }
+}
+
+#[test]
+fn caches_encoded_form() {
+    let address = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x03; 33]);
+    let encoded = EncodedAddress::from(address.clone());
+    assert_eq!(encoded.as_str(), address.encode());
+    assert_eq!(encoded.to_string(), address.to_string());
+    assert_eq!(encoded, address);
+    assert_eq!(encoded.kind(), address.kind());
+
+    let json = serde_json::to_string(&encoded).unwrap();
+    assert_eq!(json, format!("\"{address}\""));
+    let parsed: EncodedAddress = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, encoded);
+    assert_eq!(parsed.into_inner(), address);
+
+    assert!(serde_json::from_str::<EncodedAddress>("\"0OIl\"").is_err());
+}
+
+#[test]
+fn normalizes_and_compares_across_networks() {
+    let key = [0x02; 33];
+    let p2pk = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key);
+    let tree = p2pk.ergo_tree().unwrap().0;
+    let p2s = ErgoAddress::new(NetworkPrefix::Testnet, AddressType::P2S, tree.clone()).unwrap();
+
+    assert_ne!(p2s, p2pk);
+    assert!(p2s.eq_ignoring_network(&p2pk));
+    assert_eq!(p2s.normalize(), ErgoAddress::p2pk(NetworkPrefix::Testnet, &key));
+    assert_eq!(p2pk.normalize(), p2pk);
+    assert!(!p2pk.eq_ignoring_network(&ErgoAddress::p2sh(NetworkPrefix::Mainnet, &tree)));
+
+    let mut sorted = [
+        ErgoAddress::p2pk(NetworkPrefix::Testnet, &[0x02; 33]),
+        ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x03; 33]),
+        ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33]),
+    ];
+    sorted.sort();
+    let encoded: Vec<String> = sorted.iter().map(ErgoAddress::encode).collect();
+    assert_eq!(sorted[0], p2pk);
+    assert!(encoded[0] < encoded[1]);
+    assert_eq!(sorted[2].network(), NetworkPrefix::Testnet);
+
+    // P2S content of different lengths is ordered by bytes, not by encoded length.
+    let short = ErgoAddress::new(NetworkPrefix::Mainnet, AddressType::P2S, vec![0xff]).unwrap();
+    let long =
+        ErgoAddress::new(NetworkPrefix::Mainnet, AddressType::P2S, vec![0x00, 0x01]).unwrap();
+    assert!(long < short);
+    assert!(short.encode() < long.encode());
+}
+
+#[test]
+fn tests_set_membership_by_tree() {
+    let p2pk = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33]);
+    let script = hex::decode("100104000e01abd17300").unwrap();
+    let p2sh = ErgoAddress::p2sh(NetworkPrefix::Mainnet, &script);
+    let mut set: AddressSet = [p2pk.clone(), p2sh.clone()].into_iter().collect();
+
+    assert!(!set.insert(&ErgoAddress::p2pk(NetworkPrefix::Testnet, &[0x02; 33])));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&p2pk) && set.contains(&p2sh));
+    assert!(set.contains_tree(&p2pk.ergo_tree().unwrap().0));
+    assert!(set.contains_tree(&script));
+    assert!(!set.contains(&ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &script)));
+    assert!(!set.contains_tree(&[0x10; 20]));
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![&p2pk, &p2sh]);
+}
+
+#[test]
+fn suggests_single_typo_fixes() {
+    let valid = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+    let address: ErgoAddress = valid.parse().unwrap();
+    assert_eq!(typo::suggest(valid), None);
+
+    let substituted = valid.replacen("Tcdb", "Tcdc", 1);
+    let suggestion = typo::suggest(&substituted).unwrap();
+    assert_eq!(suggestion.address, address);
+    assert_eq!(suggestion.position, 13);
+    assert_eq!(suggestion.fix, TypoFix::Substitution { found: 'c', expected: 'b' });
+
+    let swapped = valid.replacen("RAW", "ARW", 1);
+    let suggestion = typo::suggest(&swapped).unwrap();
+    assert_eq!((suggestion.position, suggestion.fix), (2, TypoFix::Transposition));
+    assert_eq!(
+        typo::describe_error(&swapped, &AddressError::ChecksumMismatch),
+        format!(
+            "Address checksum mismatch. Did you mean {valid}? (characters 3 and 4 are swapped)"
+        )
+    );
+
+    // `0` is not base58.
+    let mistyped = valid.replacen("a65", "a05", 1);
+    assert_eq!(typo::suggest(&mistyped).unwrap().address, address);
+
+    // Two typos are beyond a single fix.
+    let twice = substituted.replacen("RAW", "ARW", 1);
+    assert_eq!(typo::suggest(&twice), None);
+    assert_eq!(typo::suggest("0OIl"), None);
+}
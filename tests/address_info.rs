@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::address::resolve_address_info,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    types::{Height, TimestampMillis},
+};
+use serde_json::{Value, json};
+
+/// Serves canned responses keyed by path and query, or by path alone.
+#[derive(Debug, Default)]
+struct IndexerTransport {
+    responses: HashMap<String, Value>,
+}
+
+impl IndexerTransport {
+    fn respond(mut self, key: &str, body: Value) -> Self {
+        self.responses.insert(key.to_string(), body);
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for IndexerTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let body = self
+            .responses
+            .get(&request.path_and_query())
+            .or_else(|| self.responses.get(&request.path))
+            .unwrap_or_else(|| panic!("unexpected request {}", request.path_and_query()));
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(body).unwrap().into() })
+    }
+}
+
+fn transactions(total: u64, id: u8, height: u32) -> Value {
+    let tx = json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inclusionHeight": height,
+        "timestamp": height as u64 * 120_000,
+        "inputs": [],
+        "outputs": [],
+    });
+    json!({ "items": [tx], "total": total })
+}
+
+fn boxes(total: u64) -> Value {
+    json!({ "items": [], "total": total })
+}
+
+fn address() -> ErgoAddress {
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33])
+}
+
+#[tokio::test]
+async fn resolves_balance_boxes_and_activity() {
+    let token = "aa".repeat(32);
+    let transport = IndexerTransport::default()
+        .respond(
+            "blockchain/balance",
+            json!({
+                "confirmed": {
+                    "nanoErgs": 5_000_000_000u64,
+                    "tokens": [{ "tokenId": token, "amount": 10, "decimals": 2, "name": "AA" }],
+                },
+                "unconfirmed": { "nanoErgs": 1_000_000, "tokens": [] },
+            }),
+        )
+        .respond("blockchain/box/byAddress", boxes(12))
+        .respond("blockchain/box/unspent/byAddress", boxes(3))
+        .respond("blockchain/transaction/byAddress?offset=0&limit=1", transactions(7, 0x07, 900))
+        .respond("blockchain/transaction/byAddress?offset=6&limit=1", transactions(7, 0x01, 100));
+    let node = NodeClient::with_transport(transport);
+
+    let info = resolve_address_info(&node, &address()).await.unwrap();
+    assert_eq!(info.address, address());
+    assert_eq!(info.balance, 5_000_000_000);
+    assert_eq!(info.unconfirmed_balance, 1_000_000);
+    assert_eq!(info.tokens.len(), 1);
+    assert_eq!(info.tokens[0].token_id.to_string(), token);
+    assert_eq!(info.tokens[0].decimals, Some(2));
+    assert_eq!((info.box_count, info.unspent_box_count, info.transaction_count), (12, 3, 7));
+
+    let first = info.first_activity.unwrap();
+    assert_eq!((first.height, first.timestamp), (Height(100), TimestampMillis(12_000_000)));
+    assert_eq!(first.transaction_id.to_string(), "01".repeat(32));
+    assert_eq!(info.last_activity.unwrap().height, 900);
+}
+
+#[tokio::test]
+async fn resolves_unused_addresses() {
+    let transport = IndexerTransport::default()
+        .respond("blockchain/balance", json!({ "confirmed": { "nanoErgs": 0 } }))
+        .respond("blockchain/box/byAddress", boxes(0))
+        .respond("blockchain/box/unspent/byAddress", boxes(0))
+        .respond("blockchain/transaction/byAddress", json!({ "items": [], "total": 0 }));
+    let node = NodeClient::with_transport(transport);
+
+    let info = resolve_address_info(&node, &address()).await.unwrap();
+    assert_eq!(info.balance, 0);
+    assert!(info.tokens.is_empty());
+    assert_eq!(info.transaction_count, 0);
+    assert!(info.first_activity.is_none() && info.last_activity.is_none());
+}
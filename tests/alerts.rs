@@ -0,0 +1,233 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    alerts::{
+        Alert, AlertEngine, AlertError, AlertState, Notifier, NotifyError, Observations,
+        WebhookNotifier,
+    },
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    tokens::{TokenMetadata, TokenRegistry},
+    types::{HashDigest, TimestampMillis},
+    watcher::MempoolSnapshot,
+};
+use serde_json::{Value, json};
+
+/// Serves the balance and oracle pool box of the current step, recording requests.
+#[derive(Debug, Default)]
+struct StepTransport {
+    step: Mutex<usize>,
+    balances: Vec<u64>,
+    rates: Vec<&'static str>,
+    status: u16,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl StepTransport {
+    fn advance(&self) {
+        *self.step.lock().unwrap() += 1;
+    }
+}
+
+#[async_trait]
+impl HttpTransport for StepTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let step = *self.step.lock().unwrap();
+        let body = if request.path == "blockchain/balance" {
+            json!({ "confirmed": { "nanoErgs": self.balances[step] } })
+        } else if request
+            .path
+            .starts_with("blockchain/box/unspent/byTokenId/")
+        {
+            json!({ "items": [pool_box(self.rates[step])], "total": 1 })
+        } else {
+            Value::Null
+        };
+        self.requests.lock().unwrap().push(request);
+        let status = if self.status == 0 { 200 } else { self.status };
+        Ok(HttpResponse { status, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+fn pool_box(rate: &str) -> Value {
+    json!({
+        "boxId": "11".repeat(32),
+        "ergoTree": "0008cd",
+        "creationHeight": 1_000,
+        "value": 1_000_000,
+        "assets": [{ "tokenId": pool_nft().to_string(), "amount": 1 }],
+        "additionalRegisters": { "R4": rate },
+        "index": 0,
+        "transactionId": "22".repeat(32),
+        "inclusionHeight": 1_000,
+    })
+}
+
+fn pool_nft() -> HashDigest {
+    "aa".repeat(32).parse().unwrap()
+}
+
+fn address() -> ErgoAddress {
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33])
+}
+
+#[test]
+fn parses_rules_and_rejects_invalid_addresses() {
+    let engine = AlertEngine::from_json(
+        r#"[
+            { "name": "busy", "kind": "mempoolSize", "max": 5000 },
+            { "name": "fees", "kind": "feeP90", "max": 2000000 }
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(engine.rules().count(), 2);
+    assert!(engine.needs_rolling());
+
+    let invalid = AlertEngine::from_json(
+        r#"[{ "name": "treasury", "kind": "balanceChange", "address": "nope", "max": 1 }]"#,
+    );
+    assert!(matches!(invalid, Err(AlertError::Address(address, _)) if address == "nope"));
+}
+
+#[test]
+fn fires_once_and_resolves_on_recovery() {
+    let mut engine =
+        AlertEngine::from_json(r#"[{ "name": "busy", "kind": "mempoolSize", "max": 2 }]"#).unwrap();
+    let at = |mempool_size, ms| Observations {
+        at: TimestampMillis(ms),
+        mempool_size,
+        ..Default::default()
+    };
+
+    assert!(engine.evaluate(&at(2, 1)).is_empty());
+    let fired = engine.evaluate(&at(3, 2));
+    assert_eq!(fired.len(), 1);
+    assert_eq!(
+        (fired[0].state, fired[0].value, fired[0].threshold),
+        (AlertState::Firing, 3.0, 2.0)
+    );
+    assert_eq!(fired[0].message, "busy: mempool size is 3, above 2");
+    assert!(engine.evaluate(&at(4, 3)).is_empty());
+    assert_eq!(engine.firing().count(), 1);
+
+    let resolved = engine.evaluate(&at(1, 4));
+    assert_eq!(resolved.len(), 1);
+    assert_eq!((resolved[0].state, resolved[0].at), (AlertState::Resolved, TimestampMillis(4)));
+    assert_eq!(engine.firing().count(), 0);
+}
+
+#[tokio::test]
+async fn compares_balances_and_oracle_rates_between_checks() {
+    let rules = json!([
+        { "name": "treasury", "kind": "balanceChange", "address": address().to_string(), "max": 500 },
+        { "name": "oracle", "kind": "oraclePriceDeviation", "poolNft": pool_nft(), "maxPercent": 5.0 },
+    ]);
+    let mut engine = AlertEngine::from_json(&rules.to_string()).unwrap();
+    // Rates are `Long` registers: 100, 110 and 111.
+    let transport = std::sync::Arc::new(StepTransport {
+        balances: vec![10_000, 10_200, 11_000],
+        rates: vec!["05c801", "05dc01", "05de01"],
+        ..Default::default()
+    });
+    let node = NodeClient::with_transport(SharedTransport(transport.clone()));
+    let mempool = MempoolSnapshot::default();
+
+    let first = engine.observe(&node, &mempool, None).await.unwrap();
+    assert_eq!(first.balances[&address()], 10_000);
+    assert_eq!(first.oracle_rates[&pool_nft()], 100);
+    assert!(engine.evaluate(&first).is_empty());
+
+    transport.advance();
+    let second = engine.observe(&node, &mempool, None).await.unwrap();
+    let alerts: Vec<_> = engine
+        .evaluate(&second)
+        .into_iter()
+        .map(|a| (a.rule, a.state, a.value))
+        .collect();
+    assert_eq!(alerts, [("oracle".to_string(), AlertState::Firing, 10.0)]);
+
+    transport.advance();
+    let third = engine.observe(&node, &mempool, None).await.unwrap();
+    let alerts: Vec<_> = engine
+        .evaluate(&third)
+        .into_iter()
+        .map(|a| (a.rule, a.state))
+        .collect();
+    assert_eq!(
+        alerts,
+        [
+            ("treasury".to_string(), AlertState::Firing),
+            ("oracle".to_string(), AlertState::Resolved),
+        ]
+    );
+}
+
+#[test]
+fn formats_token_balance_changes_with_decimals() {
+    let rules = json!([{
+        "name": "reserve",
+        "kind": "tokenBalanceChange",
+        "address": address().to_string(),
+        "tokenId": pool_nft(),
+        "max": 1_000,
+    }]);
+    let mut tokens = TokenRegistry::new();
+    tokens.insert(pool_nft(), TokenMetadata { name: Some("SigUSD".into()), decimals: 2 });
+    let mut engine = AlertEngine::from_json(&rules.to_string())
+        .unwrap()
+        .with_tokens(std::sync::Arc::new(tokens));
+    let at = |balance| Observations {
+        token_balances: [((address(), pool_nft()), balance)].into(),
+        ..Default::default()
+    };
+
+    assert!(engine.evaluate(&at(5_000)).is_empty());
+    let fired = engine.evaluate(&at(3_750));
+    assert_eq!(
+        fired[0].message,
+        format!(
+            "reserve: token balance change of {} is 12.50 SigUSD, above 10.00 SigUSD",
+            address()
+        )
+    );
+    assert_eq!(fired[0].value, 1_250.0);
+}
+
+#[derive(Debug)]
+struct SharedTransport(std::sync::Arc<StepTransport>);
+
+#[async_trait]
+impl HttpTransport for SharedTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        self.0.send(request).await
+    }
+}
+
+fn alert() -> Alert {
+    Alert {
+        rule: "busy".to_string(),
+        state: AlertState::Firing,
+        value: 3.0,
+        threshold: 2.0,
+        message: "busy: mempool size is 3, above 2".to_string(),
+        at: TimestampMillis(1_730_000_000_000),
+    }
+}
+
+#[tokio::test]
+async fn webhook_posts_alerts_as_json() {
+    let transport = std::sync::Arc::new(StepTransport::default());
+    let notifier = WebhookNotifier::new(SharedTransport(transport.clone()), "/hooks/alerts");
+    notifier.notify(&alert()).await.unwrap();
+
+    let request = transport.requests.lock().unwrap().remove(0);
+    assert_eq!(request.path, "hooks/alerts");
+    let body: Value = serde_json::from_slice(request.body.as_ref().unwrap()).unwrap();
+    assert_eq!(body["rule"], "busy");
+    assert_eq!(body["state"], "firing");
+    assert_eq!(body["at"], 1_730_000_000_000u64);
+
+    let failing = WebhookNotifier::new(StepTransport { status: 500, ..Default::default() }, "x");
+    assert!(matches!(failing.notify(&alert()).await, Err(NotifyError::Status(500))));
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use hergmes::{
+    analytics::assets::{
+        AssetError, aggregate_assets, aggregate_assets_batch, asset_deltas, checked_u64,
+        merge_assets,
+    },
+    types::{HashDigest, ergo::UTxO},
+};
+use serde_json::json;
+
+fn token(id: u8) -> HashDigest {
+    format!("{id:02x}").repeat(32).parse().unwrap()
+}
+
+fn utxo(tokens: &[(u8, u64)]) -> UTxO {
+    let assets: Vec<_> = tokens
+        .iter()
+        .map(|(id, amount)| json!({ "tokenId": token(*id), "amount": amount }))
+        .collect();
+    serde_json::from_value(json!({
+        "boxId": "01".repeat(32),
+        "ergoTree": "0008cd",
+        "creationHeight": 1,
+        "value": 1_000_000,
+        "assets": assets,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn sums_beyond_u64() {
+    let boxes = [utxo(&[(1, u64::MAX), (2, 5)]), utxo(&[(1, u64::MAX)]), utxo(&[])];
+    let totals = aggregate_assets(&boxes);
+
+    assert_eq!(totals[&token(1)], 2 * u128::from(u64::MAX));
+    assert_eq!(totals[&token(2)], 5);
+    assert_eq!(checked_u64(totals), Err(AssetError::Overflow(token(1))));
+
+    let fits = checked_u64(aggregate_assets(&boxes[..1])).unwrap();
+    assert_eq!(fits, HashMap::from([(token(1), u64::MAX), (token(2), 5)]));
+}
+
+#[test]
+fn batches_match_sequential_sums() {
+    let boxes: Vec<UTxO> = (0..5000u64)
+        .map(|i| utxo(&[((i % 7) as u8, i), (9, u64::MAX)]))
+        .collect();
+    assert_eq!(aggregate_assets_batch(&boxes), aggregate_assets(&boxes));
+
+    let mut merged = aggregate_assets(&boxes[..10]);
+    merge_assets(&mut merged, aggregate_assets(&boxes[10..]));
+    assert_eq!(merged, aggregate_assets(&boxes));
+}
+
+#[test]
+fn diffs_inputs_and_outputs() {
+    let inputs = [utxo(&[(1, 100), (2, 7)]), utxo(&[(3, u64::MAX)])];
+    let outputs = [utxo(&[(1, 60)]), utxo(&[(1, 40), (4, 1)]), utxo(&[(3, 1)])];
+    let deltas = asset_deltas(&inputs, &outputs);
+
+    // Token 1 is only moved, 2 burned, 3 mostly burned and 4 minted.
+    let expected =
+        HashMap::from([(token(2), -7), (token(3), 1 - i128::from(u64::MAX)), (token(4), 1)]);
+    assert_eq!(deltas, expected);
+}
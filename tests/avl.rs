@@ -0,0 +1,83 @@
+use blake2::{Blake2b, Digest as _, digest::consts::U32};
+use hergmes::{
+    chain::avl::{self, AvlError},
+    types::Digest,
+};
+
+const NEGATIVE_INFINITY: [u8; 32] = [0; 32];
+const POSITIVE_INFINITY: [u8; 32] = [0xff; 32];
+
+fn blake2b256(data: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::digest(data).into()
+}
+
+fn leaf_label(key: &[u8; 32], value: &[u8], next_key: &[u8; 32]) -> [u8; 32] {
+    blake2b256(&[&[0u8][..], key, value, next_key].concat())
+}
+
+/// Packs a two-leaf tree (sentinel leaf + one box) and direction bits for the given lookups.
+/// Returns the proof and the 33-byte state root digest.
+fn two_leaf_proof(box_bytes: &[u8], go_left: &[bool]) -> (Vec<u8>, Digest<33>) {
+    let box_id = blake2b256(box_bytes);
+
+    let mut proof = vec![2];
+    proof.extend_from_slice(&NEGATIVE_INFINITY);
+    proof.extend_from_slice(&box_id);
+    proof.extend_from_slice(&0u32.to_be_bytes());
+    proof.push(2);
+    proof.extend_from_slice(&POSITIVE_INFINITY);
+    proof.extend_from_slice(&(box_bytes.len() as u32).to_be_bytes());
+    proof.extend_from_slice(box_bytes);
+    proof.push(0);
+    proof.push(4);
+
+    let mut directions = vec![0u8; go_left.len().div_ceil(8)];
+    for (i, left) in go_left.iter().enumerate() {
+        if *left {
+            directions[i >> 3] |= 1 << (i & 7);
+        }
+    }
+    proof.extend_from_slice(&directions);
+
+    let left = leaf_label(&NEGATIVE_INFINITY, &[], &box_id);
+    let right = leaf_label(&box_id, box_bytes, &POSITIVE_INFINITY);
+    let root = blake2b256(&[&[1u8, 0][..], &left, &right].concat());
+    let mut digest = [1u8; 33];
+    digest[..32].copy_from_slice(&root);
+
+    (proof, Digest(digest))
+}
+
+#[test]
+fn proves_membership_and_absence() {
+    let box_bytes = b"serialized box".to_vec();
+    let box_id = Digest(blake2b256(&box_bytes));
+    let mut absent = [0u8; 32];
+    absent[31] = 1;
+    assert!(absent < box_id.0);
+
+    let (proof, root) = two_leaf_proof(&box_bytes, &[false, true]);
+    let values = avl::verify_lookups(&root, &proof, &[box_id, Digest(absent)]).unwrap();
+
+    assert_eq!(values, vec![Some(box_bytes), None]);
+}
+
+#[test]
+fn rejects_wrong_root_and_inconsistent_paths() {
+    let box_bytes = b"serialized box".to_vec();
+    let box_id = Digest(blake2b256(&box_bytes));
+
+    let (proof, mut root) = two_leaf_proof(&box_bytes, &[false]);
+    root.0[0] ^= 1;
+    assert!(matches!(
+        avl::verify_lookups(&root, &proof, std::slice::from_ref(&box_id)),
+        Err(AvlError::RootMismatch)
+    ));
+
+    let (proof, root) = two_leaf_proof(&box_bytes, &[true]);
+    assert!(matches!(avl::verify_lookups(&root, &proof, &[box_id]), Err(AvlError::Malformed(_))));
+
+    let (proof, root) = two_leaf_proof(&box_bytes, &[]);
+    assert!(matches!(avl::verify_lookups(&root, &proof[..10], &[]), Err(AvlError::Malformed(_))));
+    assert!(avl::verify_lookups(&root, &proof, &[]).unwrap().is_empty());
+}
@@ -0,0 +1,53 @@
+use hergmes::address::{AddressError, base58};
+
+/// Payloads of every length up to 80 bytes, with and without leading zeros.
+fn payloads() -> Vec<Vec<u8>> {
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as u8
+    };
+    let mut payloads = vec![vec![], vec![0], vec![0; 5], vec![0xff; 40]];
+    for len in 1..=80 {
+        let bytes: Vec<u8> = (0..len).map(|_| next()).collect();
+        payloads.push([&[0, 0][..], &bytes].concat());
+        payloads.push(bytes);
+    }
+    payloads
+}
+
+#[test]
+fn matches_bs58() {
+    for payload in payloads() {
+        let encoded = base58::encode(&payload);
+        assert_eq!(encoded, bs58::encode(&payload).into_string());
+        assert!(encoded.len() <= base58::max_encoded_len(payload.len()));
+
+        assert_eq!(base58::decode(&encoded).unwrap(), payload);
+        assert_eq!(bs58::decode(&encoded).into_vec().unwrap(), payload);
+        assert_eq!(unsafe { base58::decode_unsafe(&encoded) }, payload);
+    }
+}
+
+#[test]
+fn rejects_what_bs58_rejects() {
+    for invalid in ["0", "9hO", "3WvsI", "l1", "9é", "abc "] {
+        assert!(bs58::decode(invalid).into_vec().is_err());
+        assert!(matches!(base58::decode(invalid), Err(AddressError::InvalidBase58)));
+    }
+}
+
+#[test]
+fn appends_to_buffers() {
+    let mut out = vec![0xaa];
+    base58::decode_into("1112", &mut out).unwrap();
+    assert_eq!(out, [0xaa, 0, 0, 0, 1]);
+    assert!(base58::decode_into("11O", &mut out).is_err());
+    assert_eq!(out, [0xaa, 0, 0, 0, 1]);
+
+    let mut encoded = "prefix:".to_string();
+    base58::encode_into(&[&[0, 0], &[0x01, 0x02]], &mut encoded);
+    assert_eq!(encoded, format!("prefix:{}", bs58::encode([0, 0, 1, 2]).into_string()));
+}
@@ -0,0 +1,83 @@
+use hergmes::{
+    chain::{block, header, transaction},
+    codec::Writer,
+    types::{
+        HexBytes,
+        ergo::{BlockHeader, SignedTransaction},
+    },
+};
+use serde_json::json;
+
+fn fixture_header() -> BlockHeader {
+    let path = format!("{}/tests/fixtures/node-6.0/last_headers.json", env!("CARGO_MANIFEST_DIR"));
+    let headers: Vec<BlockHeader> =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    headers.into_iter().next().unwrap()
+}
+
+fn tx() -> SignedTransaction {
+    serde_json::from_value(json!({
+        "inputs": [{ "boxId": "aa".repeat(32), "spendingProof": { "proofBytes": "", "extension": {} } }],
+        "outputs": [{
+            "ergoTree": format!("0008cd02{}", "01".repeat(32)),
+            "creationHeight": 1_400_000,
+            "value": 1_000_000,
+            "assets": [{ "tokenId": "cc".repeat(32), "amount": 5 }],
+        }],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn header_round_trip() {
+    let header = fixture_header();
+    let bytes = header::serialize(&header).unwrap();
+    let parsed = header::parse(&bytes).unwrap();
+
+    assert_eq!(parsed.id, header::compute_id(&header).unwrap());
+    assert_eq!(parsed.size, Some(bytes.len() as u32));
+    assert_eq!(parsed.pow_solution.w, header.pow_solution.w);
+    assert_eq!(header::serialize(&parsed).unwrap(), bytes);
+}
+
+#[test]
+fn assembles_block_from_sections() {
+    let fields = vec![
+        (HexBytes(vec![0x00, 0x01]), HexBytes(1_250_000i32.to_be_bytes().to_vec())),
+        (HexBytes(vec![0x01, 0x00]), HexBytes([&[1u8][..], &[0x22; 32]].concat())),
+        (HexBytes(vec![0x01, 0x01]), HexBytes([&[3u8][..], &[0x33; 32]].concat())),
+    ];
+    let mut header = fixture_header();
+    header.extension_root = block::extension_digest(&fields);
+    let header_bytes = header::serialize(&header).unwrap();
+    let id = header::compute_id(&header).unwrap();
+
+    let mut w = Writer::new();
+    w.put_bytes(&id.0).put_uint(10_000_000 + 3).put_uint(1);
+    w.put_bytes(&transaction::serialize(&tx()).unwrap());
+    let transactions = w.into_bytes();
+
+    let mut w = Writer::new();
+    w.put_bytes(&id.0).put_uint(fields.len() as u32);
+    for (key, value) in &fields {
+        w.put_bytes(&key.0)
+            .put_u8(value.0.len() as u8)
+            .put_bytes(&value.0);
+    }
+    let extension = w.into_bytes();
+
+    let block = block::parse(&header_bytes, &transactions, Some(&extension)).unwrap();
+    assert_eq!(block.header.id, id);
+    let parsed = &block.transactions.transactions[0];
+    assert_eq!(parsed.id, transaction::transaction_id(&tx()).unwrap());
+    assert_eq!(parsed.outputs[0].transaction_id, parsed.id);
+    assert_eq!(parsed.outputs[0].tokens[0].amount, 5);
+    let extension = block.extension.unwrap();
+    assert_eq!(extension.parameters().unwrap().storage_fee_factor(), Some(1_250_000));
+    assert_eq!(extension.interlinks().unwrap().len(), 4);
+
+    // Sections of another block are rejected.
+    let mut other = transactions.clone();
+    other[0] ^= 1;
+    assert!(block::parse(&header_bytes, &other, None).is_err());
+}
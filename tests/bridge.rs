@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    chain::register,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    events::IdentifiedEvent,
+    types::{
+        Height,
+        ergo::{Block, UnconfirmedTransaction},
+    },
+    watcher::{self, BridgeEvent, LockRequest, RosenScanner},
+};
+use serde_json::{Value, json};
+
+const LOCK_TREE: &str = "100104000e01abd17300";
+const USER_TREE: &str = "0008cd02aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn lock_r4(fields: &[&str]) -> String {
+    let mut bytes = vec![0x1a, fields.len() as u8];
+    for field in fields {
+        bytes.push(field.len() as u8);
+        bytes.extend(field.as_bytes());
+    }
+    hex::encode(bytes)
+}
+
+fn output(id: u8, tree: &str, r4: Option<String>) -> Value {
+    let mut output = json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1_000_000,
+        "value": 2_000_000_000u64,
+        "assets": [{ "tokenId": "cc".repeat(32), "amount": 10 }],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    });
+    if let Some(r4) = r4 {
+        output["additionalRegisters"] = json!({ "R4": r4 });
+    }
+    output
+}
+
+fn scanner() -> RosenScanner {
+    let lock_address =
+        ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &hex::decode(LOCK_TREE).unwrap());
+    RosenScanner::new(&lock_address).unwrap()
+}
+
+#[test]
+fn decodes_lock_requests() {
+    let r4 = lock_r4(&["cardano", "addr1xyz", "1000000", "250", "9fRAWhdx"]);
+    let request = LockRequest::decode(&hex::decode(&r4).unwrap()).unwrap();
+
+    assert_eq!(request.to_chain, "cardano");
+    assert_eq!(request.to_address, "addr1xyz");
+    assert_eq!((request.network_fee, request.bridge_fee), (1_000_000, 250));
+    assert_eq!(request.from_address, "9fRAWhdx");
+
+    let short = lock_r4(&["cardano", "addr1xyz"]);
+    assert!(LockRequest::decode(&hex::decode(short).unwrap()).is_none());
+}
+
+fn block(height: u32, txs: Value) -> Block {
+    serde_json::from_value(json!({
+        "header": serde_json::from_slice::<Value>(&std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        )).unwrap()).unwrap()[0].clone(),
+        "blockTransactions": { "headerId": "dd".repeat(32), "transactions": txs },
+    }))
+    .map(|mut b: Block| {
+        b.header.height = Height(height);
+        b
+    })
+    .unwrap()
+}
+
+#[test]
+fn detects_mempool_locks_and_block_releases() {
+    let mut scanner = scanner();
+    let r4 = lock_r4(&["cardano", "addr1xyz", "1000000", "250", "9fRAWhdx"]);
+
+    let mut input = output(0x01, USER_TREE, None);
+    input["spendingProof"] = json!({ "proofBytes": "" });
+    let lock: UnconfirmedTransaction = serde_json::from_value(json!({
+        "id": "a1".repeat(32),
+        "inputs": [input],
+        "outputs": [output(0x02, LOCK_TREE, Some(r4.clone())), output(0x03, USER_TREE, None)],
+    }))
+    .unwrap();
+
+    let events = scanner.scan_mempool_transaction(&lock);
+    let [BridgeEvent::Lock { box_id, height: None, request, nano_ergs, tokens, .. }] = &events[..]
+    else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(box_id, &lock.outputs[0].id);
+    assert_eq!(request.to_chain, "cardano");
+    assert_eq!(*nano_ergs, 2_000_000_000);
+    assert_eq!(tokens[0].1, 10);
+
+    let confirmed = scanner.scan_block(&block(
+        10,
+        json!([{
+            "id": "a1".repeat(32),
+            "inputs": [{ "boxId": "01".repeat(32) }],
+            "outputs": [output(0x02, LOCK_TREE, Some(r4))],
+        }]),
+    ));
+    assert!(matches!(&confirmed[..], [BridgeEvent::Lock { height: Some(Height(10)), .. }]));
+    assert_ne!(confirmed[0].event_id(), events[0].event_id());
+
+    let released = scanner.scan_block(&block(
+        11,
+        json!([{
+            "id": "b2".repeat(32),
+            "inputs": [{ "boxId": "02".repeat(32) }],
+            "outputs": [output(0x04, USER_TREE, None), output(0x05, LOCK_TREE, None)],
+        }]),
+    ));
+    let [BridgeEvent::Release { spent, payouts, .. }] = &released[..] else {
+        panic!("unexpected events {released:?}");
+    };
+    assert_eq!(spent, &[lock.outputs[0].id.clone()]);
+    assert_eq!(payouts.len(), 1);
+    assert_eq!(payouts[0].address.ergo_tree().unwrap().to_string(), USER_TREE);
+}
+
+/// Holds two boxes at the lock address, locked before the scanner started.
+#[derive(Debug)]
+struct LockedBoxes;
+
+#[async_trait]
+impl HttpTransport for LockedBoxes {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        assert_eq!(request.path, "blockchain/box/unspent/byErgoTree");
+        assert_eq!(request.body.as_deref(), Some(LOCK_TREE.as_bytes()));
+        let items: Vec<Value> = [0x06, 0x07]
+            .into_iter()
+            .map(|id| {
+                let mut item = output(id, LOCK_TREE, None);
+                item["inclusionHeight"] = json!(900_000);
+                item
+            })
+            .collect();
+        let body = json!({ "items": items, "total": 2 });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn recognizes_releases_of_boxes_locked_before_startup() {
+    let node = NodeClient::with_transport(LockedBoxes);
+    let mut scanner = scanner();
+    for id in watcher::fetch_lock_boxes(&node, scanner.lock_tree())
+        .await
+        .unwrap()
+    {
+        scanner.track_box(id);
+    }
+    assert_eq!(scanner.tracked_boxes(), 2);
+
+    let released = scanner.scan_block(&block(
+        10,
+        json!([{
+            "id": "b2".repeat(32),
+            "inputs": [{ "boxId": "07".repeat(32) }],
+            "outputs": [output(0x08, USER_TREE, None)],
+        }]),
+    ));
+    let [BridgeEvent::Release { spent, .. }] = &released[..] else {
+        panic!("unexpected events {released:?}");
+    };
+    assert_eq!(spent[0].to_string(), "07".repeat(32));
+    assert_eq!(scanner.tracked_boxes(), 1);
+}
+
+#[test]
+fn decodes_register_constants() {
+    assert_eq!(register::decode_int(&[0x04, 0x07]).unwrap(), -4);
+    assert_eq!(register::decode_long(&[0x05, 0xa0, 0x1f]).unwrap(), 2000);
+    assert_eq!(register::decode_coll_byte(&[0x0e, 0x02, 0xab, 0xcd]).unwrap(), [0xab, 0xcd]);
+    assert_eq!(register::decode_coll_long(&[0x11, 0x02, 0x02, 0x03]).unwrap(), [1, -2]);
+    assert!(register::decode_long(&[0x04, 0x02]).is_err());
+    assert!(register::decode_coll_byte(&[0x0e, 0x01, 0xab, 0xcd]).is_err());
+}
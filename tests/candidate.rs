@@ -0,0 +1,78 @@
+use hergmes::{
+    address::ErgoAddress,
+    chain::candidate::{BoxCandidateBuilder, CandidateError},
+    types::{HashDigest, Height, HexBytes},
+};
+use serde_json::json;
+
+const ADDRESS: &str = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+
+fn token_id(byte: u8) -> HashDigest {
+    format!("{byte:02x}").repeat(32).parse().unwrap()
+}
+
+fn constant(hex: &str) -> HexBytes {
+    HexBytes(hex::decode(hex).unwrap())
+}
+
+#[test]
+fn builds_node_json() {
+    let address: ErgoAddress = ADDRESS.parse().unwrap();
+    let candidate = BoxCandidateBuilder::new(1_000_000, Height(1_200_000))
+        .address(&address)
+        .token(token_id(1), 10)
+        .token(token_id(2), 5)
+        .token(token_id(1), 15)
+        .register(5, constant("0e0101"))
+        .register(4, constant("0402"))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&candidate).unwrap(),
+        json!({
+            "ergoTree": address.ergo_tree().unwrap().to_string(),
+            "creationHeight": 1_200_000,
+            "value": 1_000_000,
+            "assets": [
+                {"tokenId": "01".repeat(32), "amount": 25},
+                {"tokenId": "02".repeat(32), "amount": 5},
+            ],
+            "additionalRegisters": {"R4": "0402", "R5": "0e0101"},
+        })
+    );
+}
+
+#[test]
+fn rejects_invalid_candidates() {
+    let address: ErgoAddress = ADDRESS.parse().unwrap();
+    let builder = BoxCandidateBuilder::new(1_000_000, Height(1)).address(&address);
+
+    assert!(matches!(
+        BoxCandidateBuilder::new(1_000_000, Height(1)).build(),
+        Err(CandidateError::MissingErgoTree)
+    ));
+    assert!(matches!(
+        builder.clone().value(1000).build(),
+        Err(CandidateError::ValueTooLow { value: 1000, .. })
+    ));
+    assert!(matches!(
+        builder
+            .clone()
+            .register(4, constant("0402"))
+            .register(6, constant("0404"))
+            .build(),
+        Err(CandidateError::RegisterGap(6, 5))
+    ));
+    assert!(matches!(
+        builder.clone().register(3, constant("0402")).build(),
+        Err(CandidateError::InvalidRegister(3))
+    ));
+    assert!(matches!(
+        builder.clone().token(token_id(1), 0).build(),
+        Err(CandidateError::ZeroTokenAmount(_))
+    ));
+
+    let many = (0..=122u8).fold(builder.value(u64::MAX), |b, i| b.token(token_id(i), 1));
+    assert!(matches!(many.build(), Err(CandidateError::TooManyTokens(123))));
+}
@@ -0,0 +1,97 @@
+use hergmes::{
+    chain::transaction,
+    types::{
+        HexBytes,
+        canonical::{self, Canonical},
+        ergo::{SignedTransaction, SpendingProof, UTxO, UnconfirmedTransaction},
+    },
+};
+use serde_json::json;
+
+fn utxo() -> UTxO {
+    serde_json::from_value(json!({
+        "transactionId": "E1".repeat(32),
+        "index": 1,
+        "additionalRegisters": { "R5": "0402", "R4": "0e0568657267" },
+        "assets": [{ "amount": 200, "tokenId": "7C".repeat(32) }],
+        "value": 1_000_000_000u64,
+        "creationHeight": 1_400_001,
+        "ergoTree": "0008CD".to_owned() + &"03".repeat(33),
+        "boxId": "B1".repeat(32),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn box_in_node_field_order() {
+    let expected = format!(
+        concat!(
+            r#"{{"boxId":"{}","value":1000000000,"ergoTree":"0008cd{}","#,
+            r#""assets":[{{"tokenId":"{}","amount":200}}],"creationHeight":1400001,"#,
+            r#""additionalRegisters":{{"R4":"0e0568657267","R5":"0402"}},"#,
+            r#""transactionId":"{}","index":1}}"#
+        ),
+        "b1".repeat(32),
+        "03".repeat(33),
+        "7c".repeat(32),
+        "e1".repeat(32),
+    );
+    assert_eq!(canonical::to_string(&utxo()).unwrap(), expected);
+}
+
+#[test]
+fn signed_transaction_sorts_extension_and_fills_id() {
+    let tx: SignedTransaction = serde_json::from_value(json!({
+        "inputs": [{
+            "boxId": "aa".repeat(32),
+            "spendingProof": {
+                "proofBytes": "",
+                "extension": { "10": "0402", "2": "0e0101", "0": "0101" },
+            },
+        }],
+        "outputs": [{ "ergoTree": "0008cd".to_owned() + &"02".repeat(33), "creationHeight": 1, "value": 1 }],
+    }))
+    .unwrap();
+
+    let json = canonical::to_string(&tx).unwrap();
+    let id = transaction::transaction_id(&tx).unwrap();
+    assert!(json.starts_with(&format!(r#"{{"id":"{id}","inputs":"#)));
+    assert!(json.contains(r#""extension":{"0":"0101","2":"0e0101","10":"0402"}"#));
+    assert!(json.contains(r#""dataInputs":[],"outputs":[{"value":1,"ergoTree":"#));
+
+    // Serializing is deterministic and parses back to the same transaction.
+    let parsed: SignedTransaction = serde_json::from_str(&json).unwrap();
+    assert_eq!(canonical::to_string(&parsed).unwrap(), json);
+}
+
+#[test]
+fn unconfirmed_transaction_lists_inputs_by_id_with_size() {
+    let tx = UnconfirmedTransaction {
+        id: "d1".repeat(32).parse().unwrap(),
+        inputs: vec![hergmes::types::ergo::TransactionInput {
+            utxo: utxo(),
+            spending_proof: SpendingProof::new(HexBytes(vec![0xab; 56])),
+        }],
+        data_inputs: vec![],
+        outputs: vec![utxo()],
+    };
+    let value: serde_json::Value =
+        serde_json::from_str(&canonical::to_string(&tx).unwrap()).unwrap();
+
+    assert_eq!(
+        value["inputs"],
+        json!([{
+            "boxId": "b1".repeat(32),
+            "spendingProof": { "proofBytes": "ab".repeat(56), "extension": {} },
+        }])
+    );
+    assert_eq!(value["size"], json!(transaction::unconfirmed_size(&tx).unwrap()));
+    assert_eq!(value["outputs"][0], serde_json::to_value(Canonical(&utxo())).unwrap());
+}
+
+#[test]
+fn rejects_invalid_extension_ids() {
+    let mut proof = SpendingProof::new(HexBytes(vec![]));
+    proof.extension.insert("R4".into(), HexBytes(vec![1]));
+    assert!(canonical::to_string(&proof).is_err());
+}
@@ -0,0 +1,316 @@
+use async_trait::async_trait;
+use hergmes::{
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    events::{IdentifiedEvent, LagPolicy, WithId},
+    types::{HashDigest, Height, ergo::BlockHeader},
+    watcher::{
+        self, BlockRef, ChainError, ChainEvent, ChainEvents, ChainSequencer, SinkCursors, SinkMode,
+    },
+};
+use serde_json::{Value, json};
+
+/// Block `fork:height`, whose parent is `parent_fork:height - 1`.
+fn header(height: u32, fork: u8, parent_fork: u8) -> BlockHeader {
+    serde_json::from_value(header_json(height, fork, parent_fork)).unwrap()
+}
+
+fn header_json(height: u32, fork: u8, parent_fork: u8) -> Value {
+    let mut header = serde_json::from_slice::<Value>(
+        &std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap()[0]
+        .clone();
+    header["id"] = json!(id(height, fork).to_string());
+    header["parentId"] = json!(id(height - 1, parent_fork).to_string());
+    header["height"] = json!(height);
+    header
+}
+
+fn id(height: u32, fork: u8) -> HashDigest {
+    format!("{fork:02x}{height:062x}").parse().unwrap()
+}
+
+fn tx(height: u32, fork: u8) -> Vec<HashDigest> {
+    vec![id(height, fork + 0x10)]
+}
+
+fn chain(from: u32, to: u32, fork: u8) -> Vec<BlockHeader> {
+    (from..=to).map(|h| header(h, fork, fork)).collect()
+}
+
+fn summary(events: &[ChainEvent]) -> Vec<(&'static str, u32)> {
+    events
+        .iter()
+        .map(|e| {
+            let kind = match e {
+                ChainEvent::Applied { .. } => "applied",
+                ChainEvent::RolledBack { .. } => "rolledBack",
+                ChainEvent::Finalized { .. } => "finalized",
+                ChainEvent::CatchingUp { height, .. } => return ("catchingUp", height.0),
+                ChainEvent::Live { height } => return ("live", height.0),
+            };
+            (kind, e.block().unwrap().height.0)
+        })
+        .collect()
+}
+
+#[test]
+fn finalizes_after_depth_confirmations() {
+    let mut sequencer = ChainSequencer::new(3);
+    let mut events = Vec::new();
+    for header in chain(100, 103, 0) {
+        events.extend(sequencer.apply(&header, tx(header.height.0, 0)).unwrap());
+    }
+    assert_eq!(
+        summary(&events),
+        [
+            ("applied", 100),
+            ("applied", 101),
+            ("applied", 102),
+            ("finalized", 100),
+            ("applied", 103),
+            ("finalized", 101),
+        ]
+    );
+    assert_eq!(
+        events[3],
+        ChainEvent::Finalized {
+            block: BlockRef { id: id(100, 0), height: Height(100) },
+            transactions: tx(100, 0),
+        }
+    );
+    assert_eq!(sequencer.finalized().unwrap().height, Height(101));
+    assert_eq!(sequencer.sync_from(), Some(Height(101)));
+    assert_eq!(
+        sequencer.apply(&header(105, 0, 0), vec![]),
+        Err(ChainError::Disconnected(Height(105)))
+    );
+}
+
+#[test]
+fn rolls_back_pending_blocks_on_reorg() {
+    let mut sequencer = ChainSequencer::new(3);
+    for header in chain(100, 103, 0) {
+        sequencer.apply(&header, tx(header.height.0, 0)).unwrap();
+    }
+
+    // Fork 1 branches off block 102 and is longer.
+    let mut best = chain(101, 102, 0);
+    best.push(header(103, 1, 0));
+    best.push(header(104, 1, 1));
+    let rolled_back = sequencer.rollback_to(&best).unwrap();
+    assert_eq!(
+        rolled_back,
+        [ChainEvent::RolledBack {
+            block: BlockRef { id: id(103, 0), height: Height(103) },
+            transactions: tx(103, 0),
+            seq: 0,
+        }]
+    );
+    assert_eq!(sequencer.tip().unwrap().id, id(102, 0));
+
+    let mut events = Vec::new();
+    for header in &best[2..] {
+        events.extend(sequencer.apply(header, tx(header.height.0, 1)).unwrap());
+    }
+    assert_eq!(summary(&events), [("applied", 103), ("applied", 104), ("finalized", 102)]);
+    assert_eq!(events[1].block().unwrap().id, id(104, 1));
+}
+
+#[test]
+fn rejects_reorgs_below_the_finalized_block() {
+    let mut sequencer = ChainSequencer::new(2);
+    for header in chain(100, 102, 0) {
+        sequencer.apply(&header, vec![]).unwrap();
+    }
+    assert_eq!(sequencer.finalized().unwrap().height, Height(101));
+
+    let other = chain(101, 103, 1);
+    assert_eq!(sequencer.rollback_to(&other), Err(ChainError::DeepReorg(Height(101))));
+}
+
+#[tokio::test]
+async fn subscriptions_only_see_events_of_their_mode() {
+    let events = ChainEvents::new(16, LagPolicy::DropOldest);
+    let mut provisional = events.subscribe(SinkMode::Provisional);
+    let mut finalized = events.subscribe(SinkMode::Finalized);
+
+    let mut sequencer = ChainSequencer::new(2);
+    for header in chain(100, 101, 0) {
+        for event in sequencer.apply(&header, vec![]).unwrap() {
+            events.sender().send(event);
+        }
+    }
+
+    assert_eq!(provisional.recv().await.unwrap().block().unwrap().height, Height(100));
+    assert_eq!(provisional.recv().await.unwrap().block().unwrap().height, Height(101));
+    let event = finalized.recv().await.unwrap();
+    assert!(event.is_final());
+    assert_eq!(event.block().unwrap().height, Height(100));
+    assert_eq!(serde_json::to_value(&event).unwrap()["type"], json!("finalized"));
+}
+
+/// Node whose best chain is fork 0 up to `tip`, each block holding one transaction.
+#[derive(Debug)]
+struct ChainMock {
+    tip: u32,
+}
+
+#[async_trait]
+impl HttpTransport for ChainMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let query = |key: &str| -> u32 {
+            let (_, value) = request.query.iter().find(|(k, _)| k == key).unwrap();
+            value.parse().unwrap()
+        };
+        let body = if request.path.starts_with("blocks/lastHeaders/") {
+            json!([header_json(self.tip, 0, 0)])
+        } else if request.path == "blocks/chainSlice" {
+            let to = query("toHeight").min(self.tip);
+            json!(
+                (query("fromHeight")..=to)
+                    .map(|h| header_json(h, 0, 0))
+                    .collect::<Vec<_>>()
+            )
+        } else {
+            let id = request.path.strip_prefix("blocks/").unwrap();
+            let height = u32::from_str_radix(&id[2..], 16).unwrap();
+            json!({
+                "header": header_json(height, 0, 0),
+                "blockTransactions": {
+                    "headerId": id,
+                    "transactions": [{ "id": tx(height, 0)[0], "inputs": [], "outputs": [] }],
+                },
+            })
+        };
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn catches_up_on_final_blocks_before_going_live() {
+    let node = NodeClient::with_transport(ChainMock { tip: 350 });
+    let events = ChainEvents::new(1024, LagPolicy::DropOldest);
+    let mut finalized = events.subscribe(SinkMode::Finalized);
+    let resumed = BlockRef { id: id(100, 0), height: Height(100) };
+    let mut sequencer = ChainSequencer::resume_after(3, resumed);
+
+    watcher::catch_up(&node, &mut sequencer, events.sender())
+        .await
+        .unwrap();
+    // Blocks within the finality depth of the node's tip are left to live sequencing.
+    assert_eq!(sequencer.tip().unwrap().height, Height(347));
+    assert_eq!(sequencer.finalized().unwrap().height, Height(345));
+
+    let mut received = Vec::new();
+    loop {
+        let event = finalized.recv().await.unwrap();
+        let live = matches!(event, ChainEvent::Live { .. });
+        received.push(event);
+        if live {
+            break;
+        }
+    }
+    assert_eq!(received.len(), 245 + 4);
+    assert_eq!(
+        received[0],
+        ChainEvent::Finalized {
+            block: BlockRef { id: id(101, 0), height: Height(101) },
+            transactions: tx(101, 0),
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(&received[98]).unwrap(),
+        json!({ "type": "catchingUp", "height": 200, "tip": 350 })
+    );
+    let progress: Vec<ChainEvent> = received.into_iter().filter(|e| e.is_progress()).collect();
+    assert_eq!(
+        summary(&progress),
+        [("catchingUp", 200), ("catchingUp", 300), ("catchingUp", 347), ("live", 347)]
+    );
+}
+
+#[tokio::test]
+async fn rejects_an_orphaned_resume_point() {
+    let node = NodeClient::with_transport(ChainMock { tip: 350 });
+    let events = ChainEvents::new(16, LagPolicy::DropOldest);
+    let orphaned = BlockRef { id: id(100, 1), height: Height(100) };
+    let mut sequencer = ChainSequencer::resume_after(3, orphaned);
+
+    let result = watcher::catch_up(&node, &mut sequencer, events.sender()).await;
+    assert!(matches!(result, Err(watcher::SyncError::Chain(ChainError::DeepReorg(Height(100))))));
+}
+
+#[test]
+fn event_ids_are_stable_and_tell_reapplications_apart() {
+    let mut sequencer = ChainSequencer::new(5);
+    let mut again = ChainSequencer::new(5);
+    let (mut events, mut emitted_again) = (Vec::new(), Vec::new());
+    for header in chain(100, 101, 0) {
+        events.extend(sequencer.apply(&header, vec![]).unwrap());
+        emitted_again.extend(again.apply(&header, vec![]).unwrap());
+    }
+    let applied = events[1].clone();
+    assert_eq!(applied.event_id(), emitted_again[1].event_id());
+
+    // A reorganization to fork 1 and back to block 0:101.
+    events.extend(
+        sequencer
+            .rollback_to(&[header(100, 0, 0), header(101, 1, 0)])
+            .unwrap(),
+    );
+    events.extend(sequencer.apply(&header(101, 1, 0), vec![]).unwrap());
+    events.extend(sequencer.rollback_to(&chain(100, 101, 0)).unwrap());
+    events.extend(sequencer.apply(&header(101, 0, 0), vec![]).unwrap());
+    assert_eq!(
+        summary(&events),
+        [
+            ("applied", 100),
+            ("applied", 101),
+            ("rolledBack", 101),
+            ("applied", 101),
+            ("rolledBack", 101),
+            ("applied", 101),
+        ]
+    );
+    let reapplied = events.last().unwrap();
+    assert_eq!(reapplied.block(), applied.block());
+    assert_ne!(reapplied.event_id(), applied.event_id());
+    let ids: std::collections::HashSet<_> = events.iter().map(|e| e.event_id()).collect();
+    assert_eq!(ids.len(), events.len());
+
+    let json = serde_json::to_value(WithId::new(reapplied)).unwrap();
+    assert_eq!(json["id"], json!(reapplied.event_id().to_string()));
+    assert_eq!((json["type"].clone(), json["seq"].clone()), (json!("applied"), json!(2)));
+}
+
+#[test]
+fn sink_cursors_persist_the_resume_point() {
+    let path = std::env::temp_dir().join(format!("hergmes-cursors-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut sequencer = ChainSequencer::new(2);
+    let mut events = Vec::new();
+    for header in chain(100, 102, 0) {
+        events.extend(sequencer.apply(&header, vec![]).unwrap());
+    }
+    let mut cursors = SinkCursors::open(&path).unwrap();
+    assert!(cursors.get("webhook").is_none());
+    for event in &events {
+        cursors.record("webhook", event).unwrap();
+    }
+    cursors.record("kafka", &events[0]).unwrap();
+
+    let cursors = SinkCursors::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let cursor = cursors.get("webhook").unwrap();
+    assert_eq!(cursor.finalized.as_ref().unwrap().height, Height(101));
+    assert!(cursors.is_last("webhook", events.last().unwrap()));
+    assert!(!cursors.is_last("kafka", events.last().unwrap()));
+    assert_eq!(cursors.sequencer("webhook", 2).tip().unwrap().height, Height(101));
+    assert!(cursors.sequencer("kafka", 2).tip().is_none());
+}
@@ -0,0 +1,69 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use hergmes::{
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError, PushTopic},
+    clock::{Clock, ManualClock, SystemClock},
+    intern::ErgoTreeInterner,
+    types::TimestampMillis,
+    watcher::MempoolSnapshot,
+};
+
+#[tokio::test]
+async fn manual_clock_moves_only_when_told() {
+    let clock = ManualClock::new(TimestampMillis::from_secs(1_000));
+    let (started, shared) = (clock.now(), clock.clone());
+    assert_eq!(clock.now(), started);
+
+    shared.advance(Duration::from_millis(1_500));
+    clock.sleep(Duration::from_secs(2)).await;
+    assert_eq!(clock.now() - started, Duration::from_millis(3_500));
+    assert_eq!(clock.timestamp(), TimestampMillis(1_003_500));
+    assert_eq!(shared.elapsed(), Duration::from_millis(3_500));
+
+    let system = SystemClock;
+    assert!(system.timestamp() > TimestampMillis::from_secs(1_700_000_000));
+}
+
+#[test]
+fn snapshots_go_stale_on_the_clock() {
+    let clock = ManualClock::new(TimestampMillis::from_secs(1_000));
+    let snapshot = MempoolSnapshot::new(clock.timestamp(), vec![], &ErgoTreeInterner::new());
+    let max_age = Duration::from_secs(30);
+
+    clock.advance(max_age);
+    assert_eq!(snapshot.age(&clock), max_age);
+    assert!(!snapshot.is_stale(&clock, max_age));
+    clock.advance(Duration::from_millis(1));
+    assert!(snapshot.is_stale(&clock, max_age));
+
+    // A poll returning the same mempool after a node update refreshes the snapshot's age.
+    let refreshed = snapshot.refreshed(clock.timestamp());
+    assert!(!refreshed.is_stale(&clock, max_age));
+    assert_eq!(refreshed.content_hash(), snapshot.content_hash());
+    assert!(Arc::ptr_eq(&refreshed.transactions, &snapshot.transactions));
+    assert!(Arc::ptr_eq(&refreshed.outputs_by_tree, &snapshot.outputs_by_tree));
+}
+
+#[derive(Debug)]
+struct NoNode;
+
+#[async_trait]
+impl HttpTransport for NoNode {
+    async fn send(&self, _: HttpRequest) -> Result<HttpResponse, NodeError> {
+        Err(NodeError::NotFound("no node".into()))
+    }
+}
+
+#[tokio::test]
+async fn wakeups_sleep_on_the_node_clock() {
+    let clock = ManualClock::new(TimestampMillis::from_secs(1_000));
+    let node = NodeClient::with_transport(NoNode).with_clock(Arc::new(clock.clone()));
+    let mut wakeup = node.wakeup(PushTopic::Mempool, Duration::from_secs(3_600));
+
+    let started = std::time::Instant::now();
+    wakeup.wait().await;
+    wakeup.wait().await;
+    assert_eq!(clock.elapsed(), Duration::from_secs(7_200));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
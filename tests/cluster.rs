@@ -0,0 +1,46 @@
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::cluster::AddressClusters,
+    types::ergo::UTxO,
+};
+use serde_json::json;
+
+fn p2pk(key: u8) -> String {
+    format!("0008cd02{}", format!("{key:02x}").repeat(32))
+}
+
+fn input(tree: &str) -> UTxO {
+    serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1,
+        "value": 1_000_000,
+        "index": 0,
+        "transactionId": "00".repeat(32),
+    }))
+    .unwrap()
+}
+
+fn address(tree: &str) -> ErgoAddress {
+    ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &hex::decode(tree).unwrap())
+}
+
+#[test]
+fn clusters_common_inputs_transitively() {
+    let pool = "1004040004000e20";
+    let mut clusters = AddressClusters::new(NetworkPrefix::Mainnet);
+
+    clusters.observe_inputs(&[input(&p2pk(1)), input(&p2pk(2))]);
+    clusters.observe_inputs(&[input(&p2pk(3)), input(&p2pk(2))]);
+    clusters.observe_inputs(&[input(&p2pk(4)), input(pool)]);
+    clusters.observe_inputs(&[input(&p2pk(5)), input(pool)]);
+
+    assert_eq!(clusters.len(), 5);
+    assert!(clusters.same_owner(&address(&p2pk(1)), &address(&p2pk(3))));
+    assert!(!clusters.same_owner(&address(&p2pk(4)), &address(&p2pk(5))));
+    assert_eq!(clusters.cluster_id(&address(pool)), None);
+
+    let mut sizes: Vec<usize> = clusters.clusters().values().map(Vec::len).collect();
+    sizes.sort();
+    assert_eq!(sizes, vec![1, 1, 3]);
+}
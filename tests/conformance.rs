@@ -0,0 +1,174 @@
+//! Differential tests against a reference node.
+//!
+//! `ERGO_NODE_URL=<url> cargo test --test conformance -- [--blocks N] [--seed S]` checks
+//! random blocks of the node and prints the mismatches, failing if any. Without a node URL, the
+//! checker itself is tested on a synthetic block.
+
+use std::{fs, path::Path};
+
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    chain::transaction,
+    clients::node::{NodeClient, ReplayTransport},
+    conformance::ConformanceChecker,
+    types::{Height, ergo::SignedTransaction},
+};
+use serde_json::{Value, json};
+
+const HEADER_ID: &str = "dddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddddd";
+const HEIGHT: u32 = 1_200_000;
+
+fn main() {
+    match std::env::var("ERGO_NODE_URL") {
+        Ok(url) => against_node(&url),
+        Err(_) => {
+            checks_synthetic_block();
+            println!("test result: ok. Set ERGO_NODE_URL to check a reference node.");
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn against_node(url: &str) {
+    let mut args = std::env::args().skip(1);
+    let (mut blocks, mut seed) = (10, None);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().and_then(|v| v.parse().ok());
+        match arg.as_str() {
+            "--blocks" => blocks = value().expect("--blocks takes a number") as u32,
+            "--seed" => seed = Some(value().expect("--seed takes a number")),
+            _ => {}
+        }
+    }
+    let mut seed = seed.unwrap_or_else(|| {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+        now.unwrap().as_nanos() as u64 | 1
+    });
+    println!("Checking {blocks} random blocks of {url} with seed {seed}.");
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let report = runtime.block_on(async {
+        let node = NodeClient::builder(url).build().unwrap();
+        let tip = node
+            .get_info()
+            .await
+            .unwrap()
+            .full_height
+            .expect("node has no blocks");
+        let mut checker = ConformanceChecker::new(&node);
+        for _ in 0..blocks {
+            // xorshift64
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let height = 1 + (seed % tip.0 as u64) as u32;
+            checker.check_height(Height(height)).await.unwrap();
+        }
+        checker.into_report()
+    });
+
+    print!("{report}");
+    if !report.is_ok() {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "reqwest"))]
+fn against_node(_url: &str) {
+    panic!("checking a node needs the `reqwest` feature");
+}
+
+fn signed_transaction() -> SignedTransaction {
+    serde_json::from_value(json!({
+        "inputs": [{
+            "boxId": "aa".repeat(32),
+            "spendingProof": { "proofBytes": "ab".repeat(56), "extension": {} },
+        }],
+        "outputs": [
+            {
+                "ergoTree": format!("0008cd02{}", "01".repeat(32)),
+                "creationHeight": HEIGHT,
+                "value": 1_000_000_000u64,
+                "assets": [{ "tokenId": "cc".repeat(32), "amount": 5 }],
+            },
+            {
+                "ergoTree": "1806010402d17300",
+                "creationHeight": HEIGHT,
+                "value": 2_000_000,
+            },
+        ],
+    }))
+    .unwrap()
+}
+
+/// Records the node's answers for a block holding `tx`, as computed locally, then lets
+/// `corrupt` alter the node's view of the block.
+fn record(dir: &Path, tx: &SignedTransaction, corrupt: impl FnOnce(&mut Value)) {
+    let write = |path: &str, value: &Value| {
+        let path = dir.join(format!("{path}.json"));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_vec(value).unwrap()).unwrap();
+    };
+    let headers: Value = serde_json::from_slice(
+        &fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/node-6.0/last_headers.json"
+        ))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let id = transaction::transaction_id(tx).unwrap();
+    let outputs = transaction::output_boxes(tx).unwrap();
+    let mut block = json!({
+        "header": headers[0],
+        "blockTransactions": {
+            "headerId": HEADER_ID,
+            "transactions": [{ "id": id, "inputs": [{ "boxId": "aa".repeat(32) }], "outputs": outputs }],
+        },
+    });
+    corrupt(&mut block);
+
+    write(&format!("blocks/at/{HEIGHT}"), &json!([HEADER_ID]));
+    write(&format!("blocks/{HEADER_ID}"), &block);
+    let signed = json!({ "headerId": HEADER_ID, "transactions": [tx] });
+    write(&format!("blocks/{HEADER_ID}/transactions"), &signed);
+    for output in &tx.outputs {
+        let address = ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &output.ergo_tree.0);
+        let path = format!("utils/ergoTreeToAddress/{}", output.ergo_tree);
+        write(&path, &json!({ "address": address.encode() }));
+    }
+}
+
+fn checks_synthetic_block() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let dir = std::env::temp_dir().join(format!("hergmes-conformance-{}", std::process::id()));
+    let tx = signed_transaction();
+    let check = |dir: &Path| {
+        let node = NodeClient::with_transport(ReplayTransport::new(dir));
+        runtime.block_on(async {
+            let mut checker = ConformanceChecker::new(&node);
+            checker.check_height(Height(HEIGHT)).await.unwrap();
+            checker.into_report()
+        })
+    };
+
+    record(&dir.join("agreeing"), &tx, |_| {});
+    let report = check(&dir.join("agreeing"));
+    assert!(report.is_ok(), "{report}");
+    let counts = (report.blocks, report.transactions, report.boxes, report.addresses);
+    assert_eq!(counts, (1, 1, 2, 2));
+
+    record(&dir.join("diverging"), &tx, |block| {
+        block["blockTransactions"]["transactions"][0]["outputs"][1]["value"] = json!(3_000_000);
+    });
+    let report = check(&dir.join("diverging"));
+    let checks: Vec<_> = report.mismatches.iter().map(|m| m.check).collect();
+    // The node box no longer hashes to the id the node reports for it.
+    assert_eq!(checks, ["box id of node box"], "{report}");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,95 @@
+use hergmes::{
+    chain::register::{Constant, RegisterError, RegisterId},
+    types::{
+        HashDigest, HexBytes,
+        ergo::{SpendingProof, UTxO, UnsignedInput},
+    },
+};
+use serde_json::json;
+
+#[test]
+fn constants_round_trip() {
+    let constants = [
+        Constant::Boolean(true),
+        Constant::Int(-1),
+        Constant::Long(1_000_000_000),
+        Constant::CollByte(vec![1, 2, 3]),
+        Constant::CollCollByte(vec![vec![], vec![0xff; 40]]),
+        Constant::CollLong(vec![i64::MIN, 0, i64::MAX]),
+        Constant::ProveDlog([2; 33]),
+    ];
+    for constant in constants {
+        assert_eq!(Constant::decode(&constant.encode().0).unwrap(), constant);
+    }
+
+    assert_eq!(Constant::Int(-1).encode().to_string(), "0401");
+    assert_eq!(Constant::Long(100).encode().to_string(), "05c801");
+    assert_eq!(Constant::CollByte(vec![0xab]).encode().to_string(), "0e01ab");
+    assert!(Constant::decode(&[0x04, 0x02, 0x00]).is_err());
+    assert!(Constant::decode(&[0x63]).is_err());
+}
+
+#[test]
+fn typed_extension_variables() {
+    let proof = SpendingProof::new(HexBytes(vec![]))
+        .with_extension_var(0, 5i32)
+        .with_extension_var(1, vec![0xcau8, 0xfe]);
+    assert_eq!(proof.extension["0"].to_string(), "040a");
+    assert_eq!(proof.extension_var(0).unwrap().unwrap(), Constant::Int(5));
+    assert!(proof.extension_var(2).is_none());
+
+    let json = serde_json::to_value(&proof).unwrap();
+    let proof: SpendingProof = serde_json::from_value(json).unwrap();
+    let vars = proof.extension_vars().unwrap();
+    assert_eq!(vars.keys().copied().collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(vars[&1], Constant::CollByte(vec![0xca, 0xfe]));
+
+    let input = UnsignedInput::new("aa".repeat(32).parse().unwrap()).with_extension_var(127, 2i32);
+    assert_eq!(input.extension_var(127).unwrap().unwrap(), Constant::Int(2));
+}
+
+#[test]
+fn typed_register_accessors() {
+    let token: HashDigest = "aa".repeat(32).parse().unwrap();
+    let utxo: UTxO = serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": "1004",
+        "creationHeight": 1,
+        "value": 1_000_000,
+        "additionalRegisters": {
+            "R4": Constant::Long(-42).encode(),
+            "R5": Constant::CollByte(token.0.to_vec()).encode(),
+            "R6": Constant::ProveDlog([2; 33]).encode(),
+            "R7": "0e05ab",
+        },
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap();
+
+    assert_eq!(utxo.register::<i64>(RegisterId::R4), Ok(-42));
+    assert_eq!(utxo.register::<HashDigest>(RegisterId::R5), Ok(token));
+    assert_eq!(utxo.register::<[u8; 33]>(RegisterId::R6), Ok([2; 33]));
+    assert_eq!(utxo.register::<Constant>(RegisterId::R4), Ok(Constant::Long(-42)));
+
+    assert_eq!(utxo.register::<i64>(RegisterId::R9), Err(RegisterError::Missing(RegisterId::R9)));
+    let err = utxo.register::<i32>(RegisterId::R4).unwrap_err();
+    assert_eq!(err.to_string(), "Register R4 holds a value of type Long, expected Int");
+    assert!(matches!(
+        utxo.register::<HashDigest>(RegisterId::R6),
+        Err(RegisterError::WrongType { expected: "Coll[Byte]", found: "SigmaProp", .. })
+    ));
+    // A `Coll[Byte]` of the wrong length, and one that is cut short.
+    assert!(matches!(
+        utxo.register::<HashDigest>(RegisterId::R7),
+        Err(RegisterError::Invalid { register: RegisterId::R7, .. })
+    ));
+    assert!(matches!(
+        utxo.register::<Vec<u8>>(RegisterId::R7),
+        Err(RegisterError::Invalid { expected: "Coll[Byte]", .. })
+    ));
+
+    assert_eq!(RegisterId::new(9), Some(RegisterId::R9));
+    assert_eq!(RegisterId::new(3), None);
+    assert_eq!(RegisterId::R7.to_string(), "R7");
+}
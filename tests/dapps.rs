@@ -0,0 +1,103 @@
+use std::{sync::Arc, time::Duration};
+
+use hergmes::{
+    analytics::{dapps::DappCounters, rolling::RollingWindow},
+    chain::fee::FEE_ERGO_TREE,
+    templates::{ContractTemplate, TemplateRegistry},
+    types::{HexBytes, TimestampMillis, ergo::Block},
+};
+use serde_json::{Value, json};
+
+const DEX_TREE: [u8; 3] = [0x10, 0x00, 0x7f];
+const TOKEN: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn output(tree: &[u8], value: u64, assets: Value) -> Value {
+    json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": HexBytes(tree.to_vec()),
+        "creationHeight": 1_000_000,
+        "value": value,
+        "assets": assets,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn templates() -> Arc<TemplateRegistry> {
+    let mut templates = hergmes::templates::BUILTIN.clone();
+    let dex = ContractTemplate { name: "Pool".into(), dapp: "Dex".into(), description: None };
+    templates.register_tree(&DEX_TREE, dex).unwrap();
+    Arc::new(templates)
+}
+
+/// A swap through the dex pool when `swap`, a plain payment otherwise, both paying a fee.
+fn outputs(swap: bool) -> Value {
+    let tree: &[u8] = if swap { &DEX_TREE } else { &[0x00, 0x08, 0xcd] };
+    json!([
+        output(tree, 5_000, json!([{ "tokenId": TOKEN, "amount": 10 }])),
+        output(&FEE_ERGO_TREE, 1_000, json!([])),
+    ])
+}
+
+#[test]
+fn attributes_mempool_transactions_to_dapps() {
+    let mut window = RollingWindow::new(Duration::from_secs(60)).with_templates(templates());
+    for (id, swap) in [(1u8, true), (2, true), (3, false)] {
+        let tx = serde_json::from_value(json!({
+            "id": format!("{id:02x}").repeat(32),
+            "inputs": [],
+            "outputs": outputs(swap),
+        }))
+        .unwrap();
+        window.record(TimestampMillis::from_secs(1_000), &tx);
+    }
+
+    let report = window.report(TimestampMillis::from_secs(1_000));
+    let dapps: Vec<(&str, u64, u64)> = report
+        .dapps
+        .iter()
+        .map(|d| (d.dapp.as_str(), d.transactions, d.nano_ergs))
+        .collect();
+    assert_eq!(dapps, [("Ergo", 3, 18_000), ("Dex", 2, 12_000)]);
+    assert_eq!(report.dapps[1].tokens[0].amount, 20);
+
+    let metrics = report.to_prometheus();
+    assert!(metrics.contains("hergmes_rolling_dapp_transactions_per_second{dapp=\"Dex\"} 0.0333"));
+    assert!(metrics.contains("hergmes_rolling_dapp_erg_volume{dapp=\"Ergo\"} 18000"));
+}
+
+#[test]
+fn counts_confirmed_transactions_by_dapp() {
+    let header = serde_json::from_slice::<Value>(
+        &std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap()[0]
+        .clone();
+    let block: Block = serde_json::from_value(json!({
+        "header": header,
+        "blockTransactions": {
+            "headerId": "dd".repeat(32),
+            "transactions": [
+                { "id": "a1".repeat(32), "inputs": [{ "boxId": "01".repeat(32) }], "outputs": outputs(true) },
+                { "id": "a2".repeat(32), "inputs": [{ "boxId": "02".repeat(32) }], "outputs": outputs(false) },
+            ],
+        },
+    }))
+    .unwrap();
+
+    let mut counters = DappCounters::new(templates());
+    counters.apply_block(&block);
+    counters.apply_block(&block);
+    assert_eq!(counters.height(), Some(block.header.height));
+
+    let report = counters.report();
+    assert_eq!(report[0].dapp, "Ergo");
+    assert_eq!((report[1].dapp.as_str(), report[1].transactions), ("Dex", 2));
+    let metrics = counters.to_prometheus();
+    assert!(metrics.contains("# TYPE hergmes_dapp_confirmed_transactions_total counter"));
+    assert!(metrics.contains("hergmes_dapp_confirmed_erg_volume_total{dapp=\"Dex\"} 12000"));
+}
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use hergmes::{
+    analytics::distribution::{self, Asset, Bucket, DistributionBuilder},
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    types::ergo::UTxO,
+};
+use serde_json::{Value, json};
+
+fn utxo(owner: u8, value: u64, tokens: u64) -> UTxO {
+    serde_json::from_value(box_json(owner, value, tokens)).unwrap()
+}
+
+fn box_json(owner: u8, value: u64, tokens: u64) -> Value {
+    json!({
+        "boxId": "01".repeat(32),
+        "ergoTree": format!("0008cd02{}", format!("{owner:02x}").repeat(32)),
+        "creationHeight": 1,
+        "value": value,
+        "assets": [{ "tokenId": "aa".repeat(32), "amount": tokens }],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+        "inclusionHeight": 1,
+    })
+}
+
+#[test]
+fn equal_balances_have_zero_gini() {
+    let mut builder = DistributionBuilder::new(Asset::Erg);
+    for owner in 0..4 {
+        builder.add(&utxo(owner, 1_000, 0));
+    }
+    let distribution = builder.build(10);
+
+    assert_eq!(distribution.holders, 4);
+    assert_eq!(distribution.total, 4_000);
+    assert!(distribution.gini.abs() < 1e-9);
+}
+
+#[test]
+fn reports_top_holders_and_histogram() {
+    let token = Asset::Token("aa".repeat(32).parse().unwrap());
+    let mut builder = DistributionBuilder::new(token);
+    builder.add(&utxo(1, 1, 5));
+    builder.add(&utxo(2, 1, 40));
+    builder.add(&utxo(2, 1, 60));
+    builder.add(&utxo(3, 1, 900));
+    builder.add(&utxo(4, 1, 0));
+    let distribution = builder.build(2);
+
+    assert_eq!(distribution.holders, 3);
+    assert_eq!(distribution.total, 1_005);
+    let top: Vec<u64> = distribution.top.iter().map(|(_, amount)| *amount).collect();
+    assert_eq!(top, [900, 100]);
+
+    // Sorted balances 5, 100, 900: (2 * (5 + 200 + 2700)) / (3 * 1005) - 4 / 3.
+    assert!((distribution.gini - 0.5937).abs() < 1e-3);
+
+    assert_eq!(
+        distribution.histogram,
+        [
+            Bucket { min: 1, max: Some(10), holders: 1, total: 5 },
+            Bucket { min: 10, max: Some(100), holders: 0, total: 0 },
+            Bucket { min: 100, max: None, holders: 2, total: 1_000 },
+        ]
+    );
+}
+
+/// Serves three unspent boxes of a contract template, two per page.
+#[derive(Debug)]
+struct TemplateBoxes;
+
+#[async_trait]
+impl HttpTransport for TemplateBoxes {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        assert_eq!(
+            request.path,
+            format!("blockchain/box/unspent/byTemplateHash/{}", "dd".repeat(32))
+        );
+        let offset = request
+            .query
+            .iter()
+            .find(|(key, _)| key == "offset")
+            .map_or(0, |(_, value)| value.parse().unwrap());
+        let boxes = [box_json(1, 3_000, 0), box_json(2, 1_000, 7), box_json(1, 2_000, 0)];
+        let items: Vec<Value> = boxes.into_iter().skip(offset).take(2).collect();
+        let body = json!({ "items": items, "total": 3 });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn tallies_erg_over_contract_instances() {
+    let node = NodeClient::with_transport(TemplateBoxes);
+    let template_hash = "dd".repeat(32).parse().unwrap();
+    let distribution = distribution::erg_distribution(&node, &template_hash, 1)
+        .await
+        .unwrap();
+
+    assert_eq!(distribution.asset, Asset::Erg);
+    assert_eq!(distribution.holders, 2);
+    assert_eq!(distribution.total, 6_000);
+    let top: Vec<u64> = distribution.top.iter().map(|(_, amount)| *amount).collect();
+    assert_eq!(top, [5_000]);
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use hergmes::{
+    types::{HashDigest, TimestampMillis},
+    watcher::DivergenceTracker,
+};
+
+fn id(n: u8) -> HashDigest {
+    format!("{n:02x}").repeat(32).parse().unwrap()
+}
+
+fn views(nodes: &[(&str, &[u8])]) -> BTreeMap<String, Vec<HashDigest>> {
+    nodes
+        .iter()
+        .map(|(name, ids)| (name.to_string(), ids.iter().copied().map(id).collect()))
+        .collect()
+}
+
+#[test]
+fn tracks_divergence_until_nodes_agree() {
+    let mut tracker = DivergenceTracker::new();
+
+    let report = tracker.observe(TimestampMillis(1_000), &views(&[("a", &[1, 2]), ("b", &[1])]));
+    assert_eq!(report.transactions.len(), 1);
+    let divergent = &report.transactions[0];
+    assert_eq!(divergent.id, id(2));
+    assert_eq!(divergent.present_on, ["a"]);
+    assert_eq!(divergent.missing_on, ["b"]);
+    assert_eq!(divergent.since, 1_000);
+    assert_eq!(report.missing_by_node().get("b"), Some(&1));
+
+    let report = tracker.observe(TimestampMillis(4_000), &views(&[("a", &[1, 2]), ("b", &[1, 3])]));
+    assert_eq!(report.transactions.len(), 2);
+    assert_eq!(report.oldest_age(), 3_000);
+
+    let report = tracker.observe(TimestampMillis(6_000), &views(&[("a", &[2, 3]), ("b", &[2, 3])]));
+    assert!(report.transactions.is_empty());
+    assert_eq!(report.resolved, 2);
+    assert_eq!(report.resolved_max_ms, 5_000);
+    assert_eq!(report.resolved_total_ms, 7_000);
+}
+
+#[test]
+fn renders_prometheus_metrics() {
+    let mut tracker = DivergenceTracker::new();
+    let report =
+        tracker.observe(TimestampMillis(1_000), &views(&[("http://a", &[1]), ("http://b", &[])]));
+    let text = report.to_prometheus();
+
+    assert!(text.contains("# TYPE hergmes_mempool_divergent_transactions gauge\n"));
+    assert!(text.contains("hergmes_mempool_divergent_transactions 1\n"));
+    assert!(text.contains("hergmes_mempool_missing_transactions{node=\"http://a\"} 0\n"));
+    assert!(text.contains("hergmes_mempool_missing_transactions{node=\"http://b\"} 1\n"));
+}
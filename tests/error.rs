@@ -0,0 +1,60 @@
+use std::net::SocketAddr;
+
+use hergmes::{
+    clients::node::NodeError,
+    env,
+    error::{AppError, Context, exit_code},
+};
+
+#[test]
+fn context_prefixes_the_source() {
+    let err = "not-an-address"
+        .parse::<SocketAddr>()
+        .config_context("Invalid listen address `not-an-address`")
+        .unwrap_err();
+
+    assert!(matches!(err, AppError::Config { .. }));
+    assert!(
+        err.to_string()
+            .starts_with("Invalid listen address `not-an-address`: ")
+    );
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn malformed_numbers_exit_as_config_errors() {
+    // SAFETY: no other test reads this variable.
+    unsafe { std::env::set_var("ERGO_MINER_WINDOW", "ten") };
+    let err = env::ergo_miner_window().unwrap_err();
+
+    assert_eq!(err.exit_code(), exit_code::CONFIG);
+    assert!(
+        err.to_string()
+            .starts_with("Environment variable `ERGO_MINER_WINDOW` must be a number: ")
+    );
+}
+
+#[test]
+fn exit_codes_distinguish_failures() {
+    let io = || std::io::Error::other("disk full");
+    let unreachable = AppError::from(NodeError::transport(io()));
+    let not_found = AppError::from(NodeError::NotFound("box".to_string()));
+
+    assert_eq!(unreachable.exit_code(), exit_code::NODE_UNAVAILABLE);
+    assert_eq!(not_found.exit_code(), exit_code::NODE_PROTOCOL);
+    assert_eq!(AppError::config("bad", io()).exit_code(), exit_code::CONFIG);
+    assert_eq!(AppError::storage("bad", io()).exit_code(), exit_code::STORAGE);
+    assert_eq!(AppError::server("bad", io()).exit_code(), exit_code::SERVER);
+    assert_eq!(AppError::Usage("usage".to_string()).exit_code(), exit_code::USAGE);
+
+    let failed = AppError::TaskFailed { task: "watcher", failure: "boom".to_string() };
+    assert_eq!(failed.exit_code(), exit_code::SOFTWARE);
+}
+
+#[tokio::test]
+async fn panicked_tasks_exit_as_software_errors() {
+    let panicked = tokio::spawn(async { panic!("boom") }).await.unwrap_err();
+    let error = AppError::from(panicked);
+    assert_eq!(error.exit_code(), exit_code::SOFTWARE);
+    assert!(error.to_string().contains("panicked"));
+}
@@ -0,0 +1,60 @@
+use hergmes::events::{self, EventSender, LagPolicy, RecvError};
+
+#[tokio::test]
+async fn lagging_consumers_skip_the_oldest_events() {
+    let sender = EventSender::new(2, LagPolicy::DropOldest);
+    let mut fast = sender.subscribe();
+    let mut slow = sender.subscribe();
+
+    assert_eq!(sender.send(1), 2);
+    assert_eq!(fast.recv().await, Ok(1));
+    for event in 2..=4 {
+        sender.send(event);
+    }
+
+    assert_eq!(slow.recv().await, Err(RecvError::Lagged(2)));
+    assert_eq!(slow.recv().await, Ok(3));
+    assert_eq!(slow.recv().await, Ok(4));
+    assert_eq!(slow.try_recv(), None);
+    assert_eq!(fast.recv().await, Err(RecvError::Lagged(1)));
+
+    let metrics = sender.metrics();
+    assert_eq!((metrics.sent(), metrics.dropped(), metrics.disconnected()), (4, 3, 0));
+}
+
+#[tokio::test]
+async fn disconnects_lagging_consumers_under_disconnect_policy() {
+    let sender = EventSender::new(1, LagPolicy::Disconnect);
+    let mut events = sender.subscribe();
+    sender.send("a");
+    sender.send("b");
+
+    assert_eq!(events.recv().await, Err(RecvError::Disconnected));
+    sender.send("c");
+    assert_eq!(events.try_recv(), Some(Err(RecvError::Disconnected)));
+    assert_eq!(sender.metrics().dropped(), 1);
+    assert_eq!(sender.metrics().disconnected(), 1);
+
+    let mut resubscribed = sender.subscribe();
+    sender.send("d");
+    assert_eq!(resubscribed.recv().await, Ok("d"));
+    drop(sender);
+    assert_eq!(resubscribed.recv().await, Err(RecvError::Closed));
+}
+
+#[test]
+fn renders_channel_metrics() {
+    let a = EventSender::new(1, LagPolicy::default());
+    let b = EventSender::<()>::new(1, LagPolicy::default());
+    a.send(());
+    let text = events::to_prometheus([("a", &**a.metrics()), ("b", &**b.metrics())]);
+
+    assert_eq!(
+        text.matches("# TYPE hergmes_events_dropped_total counter")
+            .count(),
+        1
+    );
+    assert!(text.contains("hergmes_events_sent_total{channel=\"a\"} 1\n"));
+    assert!(text.contains("hergmes_events_sent_total{channel=\"b\"} 0\n"));
+    assert!(text.contains("hergmes_event_consumers_disconnected_total{channel=\"b\"} 0\n"));
+}
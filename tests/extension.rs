@@ -0,0 +1,75 @@
+use hergmes::{
+    chain::extension::{self, Extension, ParameterId, Vote},
+    types::{Digest, Height, HexBytes},
+};
+
+fn extension(fields: Vec<(Vec<u8>, Vec<u8>)>) -> Extension {
+    Extension {
+        header_id: Digest([0; 32]),
+        digest: Digest([0; 32]),
+        fields: fields
+            .into_iter()
+            .map(|(k, v)| (HexBytes(k), HexBytes(v)))
+            .collect(),
+    }
+}
+
+fn interlink(qty: u8, id: u8) -> Vec<u8> {
+    let mut value = vec![qty];
+    value.extend_from_slice(&[id; 32]);
+    value
+}
+
+#[test]
+fn parses_parameters_and_skips_other_fields() {
+    let ext = extension(vec![
+        (vec![0, 1], 1_250_000i32.to_be_bytes().to_vec()),
+        (vec![0, 2], 360i32.to_be_bytes().to_vec()),
+        (vec![0, 4], 8_001_091i32.to_be_bytes().to_vec()),
+        (vec![0, 123], 4i32.to_be_bytes().to_vec()),
+        (vec![0, 124], vec![0, 1, 2]),
+        (vec![2, 0], vec![0xff]),
+        (vec![1, 0], interlink(1, 9)),
+    ]);
+
+    let params = ext.parameters().unwrap();
+    assert_eq!(params.storage_fee_factor(), Some(1_250_000));
+    assert_eq!(params.min_value_per_byte(), Some(360));
+    assert_eq!(params.max_block_cost(), Some(8_001_091));
+    assert_eq!(params.max_block_size(), None);
+    assert_eq!(params.block_version(), Some(4));
+    assert_eq!(params.iter().count(), 4);
+
+    let bad = extension(vec![(vec![0, 3], vec![0, 1])]);
+    assert!(bad.parameters().is_err());
+}
+
+#[test]
+fn unpacks_interlinks_in_index_order() {
+    let ext = extension(vec![
+        (vec![1, 1], interlink(2, 2)),
+        (vec![0, 1], 1i32.to_be_bytes().to_vec()),
+        (vec![1, 0], interlink(1, 1)),
+    ]);
+
+    assert_eq!(ext.interlinks().unwrap(), vec![Digest([1; 32]), Digest([2; 32]), Digest([2; 32])]);
+
+    let bad = extension(vec![(vec![1, 0], vec![1, 2, 3])]);
+    assert!(bad.interlinks().is_err());
+}
+
+#[test]
+fn parses_votes_and_epochs() {
+    assert_eq!(
+        extension::parse_votes(&[4, (-2i8) as u8, 0]),
+        vec![
+            Vote::Increase(ParameterId::MaxBlockCost),
+            Vote::Decrease(ParameterId::MinValuePerByte)
+        ]
+    );
+    assert_eq!(extension::parse_votes(&[120, 0, 50]), vec![Vote::SoftFork, Vote::Unknown(50)]);
+    assert!(extension::parse_votes(&[0, 0, 0]).is_empty());
+
+    assert_eq!(extension::epoch_start(Height(1_024)), 1_024);
+    assert_eq!(extension::epoch_start(Height(1_400_000)), 1_399_808);
+}
@@ -0,0 +1,64 @@
+use hergmes::{
+    analytics::feemarket::{self, FeeMarket, PendingTransaction},
+    types::HashDigest,
+};
+
+fn id(n: u8) -> HashDigest {
+    format!("{n:02x}").repeat(32).parse().unwrap()
+}
+
+fn pending(n: u8, size: usize, fee: u64, parents: &[u8]) -> PendingTransaction {
+    PendingTransaction { id: id(n), size, fee, parents: parents.iter().map(|p| id(*p)).collect() }
+}
+
+#[test]
+fn packs_blocks_by_fee_per_byte() {
+    // 1 pays 10/byte, 2 pays 20/byte, and 3 pays 5/byte but its child 4 pays 50/byte.
+    let market = FeeMarket::new(vec![
+        pending(1, 100, 1_000, &[]),
+        pending(2, 100, 2_000, &[]),
+        pending(3, 100, 500, &[9]),
+        pending(4, 100, 5_000, &[3]),
+    ]);
+
+    let simulation = market.simulate(3, 200);
+    let summary: Vec<_> = simulation
+        .blocks
+        .iter()
+        .map(|b| (b.transactions, b.fees, b.min_fee_per_byte))
+        .collect();
+    // The child waits for its parent, which is outbid in the first block.
+    assert_eq!(summary, [(2, 3_000, Some(10.0)), (2, 5_500, Some(5.0)), (0, 0, None)]);
+    assert_eq!(simulation.remaining, 0);
+
+    // A parent and its child fit in the same block once the parent gets in.
+    let simulation = market.simulate(1, 400);
+    assert_eq!(simulation.blocks[0].transactions, 4);
+    assert_eq!(simulation.blocks[0].bytes, 400);
+}
+
+#[test]
+fn quotes_the_fee_to_outbid_injected_transactions() {
+    let mut market = FeeMarket::new(vec![pending(1, 100, 500, &[])]);
+    let quiet = market.clone();
+    assert_eq!(quiet.required_fee(100, 1, 1_000), Some(0));
+
+    // 25 transactions of 100 bytes at 20/byte fill the next two blocks and half the third.
+    market.inject(25, 100, 2_000);
+    let what_if = feemarket::what_if(&market, 3, 1_000, 100);
+    let injected: Vec<_> = what_if
+        .simulation
+        .blocks
+        .iter()
+        .map(|b| b.injected)
+        .collect();
+    assert_eq!(injected, [10, 10, 5]);
+    assert_eq!(what_if.simulation.blocks[2].transactions, 6);
+    assert_eq!((what_if.simulation.remaining, what_if.simulation.remaining_injected), (0, 0));
+
+    let fees: Vec<_> = what_if.quotes.iter().map(|q| q.fee).collect();
+    // Ties lose to the transactions already in the mempool.
+    assert_eq!(fees, [Some(2_001), Some(2_001), Some(0)]);
+    assert_eq!(what_if.quotes[0].fee_per_byte, Some(20.01));
+    assert_eq!(market.required_fee(2_000, 1, 1_000), None);
+}
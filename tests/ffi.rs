@@ -0,0 +1,82 @@
+use std::ffi::{CStr, CString, c_char};
+
+use hergmes::ffi::{
+    HergmesStatus, hergmes_address_decode, hergmes_address_encode, hergmes_address_validate,
+    hergmes_base58_decode, hergmes_base58_encode,
+};
+
+const ADDRESS: &str = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+
+#[test]
+fn round_trips_addresses() {
+    let address = CString::new(ADDRESS).unwrap();
+    assert_eq!(unsafe { hergmes_address_validate(address.as_ptr()) }, HergmesStatus::Ok);
+
+    let (mut network, mut kind, mut content, mut len) = (0xff, 0, [0u8; 64], 0);
+    let status = unsafe {
+        hergmes_address_decode(
+            address.as_ptr(),
+            &mut network,
+            &mut kind,
+            content.as_mut_ptr(),
+            content.len(),
+            &mut len,
+        )
+    };
+    assert_eq!(status, HergmesStatus::Ok);
+    assert_eq!((network, kind, len), (0x00, 1, 33));
+
+    // Too small: the required length is reported for a retry.
+    let mut out = [0 as c_char; 52];
+    let mut out_len = 0;
+    let encode = |cap: usize, out: &mut [c_char], out_len: &mut usize| unsafe {
+        hergmes_address_encode(network, kind, content.as_ptr(), len, out.as_mut_ptr(), cap, out_len)
+    };
+    assert_eq!(encode(8, &mut out, &mut out_len), HergmesStatus::BufferTooSmall);
+    assert_eq!(out_len, ADDRESS.len());
+
+    let status = encode(out.len(), &mut out, &mut out_len);
+    assert_eq!(status, HergmesStatus::Ok);
+    assert_eq!(unsafe { CStr::from_ptr(out.as_ptr()) }.to_str().unwrap(), ADDRESS);
+}
+
+#[test]
+fn reports_error_codes() {
+    let mut corrupted = ADDRESS.to_string();
+    corrupted.replace_range(10..11, "z");
+    let corrupted = CString::new(corrupted).unwrap();
+    let status = unsafe { hergmes_address_validate(corrupted.as_ptr()) };
+    assert_eq!(status, HergmesStatus::ChecksumMismatch);
+
+    let invalid = CString::new("0OIl").unwrap();
+    assert_eq!(unsafe { hergmes_address_validate(invalid.as_ptr()) }, HergmesStatus::InvalidBase58);
+    assert_eq!(unsafe { hergmes_address_validate(std::ptr::null()) }, HergmesStatus::NullPointer);
+
+    let mut out = [0 as c_char; 8];
+    let mut out_len = 0;
+    let status = unsafe {
+        hergmes_address_encode(0x20, 1, [2u8; 33].as_ptr(), 33, out.as_mut_ptr(), 8, &mut out_len)
+    };
+    assert_eq!(status, HergmesStatus::InvalidArgument);
+    let status = unsafe {
+        hergmes_address_encode(0x00, 1, [2u8; 32].as_ptr(), 32, out.as_mut_ptr(), 8, &mut out_len)
+    };
+    assert_eq!(status, HergmesStatus::InvalidContent);
+}
+
+#[test]
+fn round_trips_base58() {
+    let bytes = [0u8, 0, 1, 2, 3, 255];
+    let mut encoded = [0 as c_char; 16];
+    let mut len = 0;
+    let status = unsafe {
+        hergmes_base58_encode(bytes.as_ptr(), bytes.len(), encoded.as_mut_ptr(), 16, &mut len)
+    };
+    assert_eq!(status, HergmesStatus::Ok);
+
+    let mut decoded = [0u8; 16];
+    let status =
+        unsafe { hergmes_base58_decode(encoded.as_ptr(), decoded.as_mut_ptr(), 16, &mut len) };
+    assert_eq!(status, HergmesStatus::Ok);
+    assert_eq!(&decoded[..len], &bytes);
+}
@@ -0,0 +1,173 @@
+use hergmes::{
+    address::{AddressSet, ErgoAddress, NetworkPrefix},
+    filter::{Filter, FilterError},
+    intern::ErgoTreeInterner,
+    types::{
+        HashDigest, HexBytes, TimestampMillis,
+        ergo::{UTxO, UnconfirmedTransaction},
+    },
+    watcher::{MempoolSnapshot, SnapshotDiff},
+};
+use serde_json::json;
+
+const P2S_TREE: &str = "100104000e01abd17300";
+
+fn token(id: u8) -> HashDigest {
+    format!("{id:02x}").repeat(32).parse().unwrap()
+}
+
+fn owner(key: u8) -> ErgoAddress {
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[key; 33])
+}
+
+fn output(tree: &HexBytes, value: u64, tokens: &[u8]) -> serde_json::Value {
+    let assets: Vec<_> = tokens
+        .iter()
+        .map(|id| json!({ "tokenId": token(*id), "amount": 1 }))
+        .collect();
+    json!({
+        "boxId": "01".repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1,
+        "value": value,
+        "assets": assets,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn utxo(tree: &HexBytes, value: u64, tokens: &[u8]) -> UTxO {
+    serde_json::from_value(output(tree, value, tokens)).unwrap()
+}
+
+fn tx(id: u8, outputs: &[serde_json::Value]) -> UnconfirmedTransaction {
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inputs": [],
+        "outputs": outputs,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn combines_predicates() {
+    let tree = owner(2).ergo_tree().unwrap();
+    let filter = Filter::address(owner(2))
+        .and(Filter::min_value(1_000))
+        .or(Filter::token(token(7)));
+
+    assert!(filter.matches(&utxo(&tree, 1_000, &[])));
+    assert!(!filter.matches(&utxo(&tree, 999, &[])));
+    assert!(filter.matches(&utxo(&owner(3).ergo_tree().unwrap(), 1, &[7])));
+    assert!(!(!filter.clone()).matches(&utxo(&tree, 1_000, &[])));
+    assert!(Filter::max_value(5).matches(&utxo(&tree, 5, &[])));
+    assert!(Filter::ergo_tree(tree.clone()).matches(&utxo(&tree, 5, &[])));
+}
+
+#[test]
+fn matches_p2sh_addresses_by_script_hash() {
+    let tree = HexBytes(hex::decode(P2S_TREE).unwrap());
+    let p2sh = Filter::address(ErgoAddress::p2sh(NetworkPrefix::Mainnet, &tree.0));
+
+    assert!(p2sh.matches(&utxo(&tree, 1, &[])));
+    assert!(!p2sh.matches(&utxo(&owner(2).ergo_tree().unwrap(), 1, &[])));
+}
+
+#[test]
+fn parses_and_prints_expressions() {
+    let text = format!("address:{} and min-value:1000 or token:{}", owner(2), token(7));
+    let filter: Filter = text.parse().unwrap();
+    let built = Filter::address(owner(2))
+        .and(Filter::min_value(1_000))
+        .or(Filter::token(token(7)));
+    assert_eq!(filter, built);
+    assert_eq!(filter.to_string(), text);
+
+    let grouped: Filter = "not (min-value:5 or max-value:1) and(tree:0008cd)"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        grouped,
+        (!Filter::min_value(5).or(Filter::max_value(1)))
+            .and(Filter::ergo_tree(HexBytes(vec![0x00, 0x08, 0xcd])))
+    );
+    assert_eq!(grouped.to_string(), "not (min-value:5 or max-value:1) and tree:0008cd");
+
+    let nested = Filter::min_value(1).and(Filter::min_value(2).and(Filter::min_value(3)));
+    assert_eq!(nested.to_string().parse::<Filter>().unwrap(), nested);
+}
+
+#[test]
+fn matches_address_sets() {
+    let script = HexBytes(hex::decode(P2S_TREE).unwrap());
+    let p2sh = ErgoAddress::p2sh(NetworkPrefix::Mainnet, &script.0);
+    let filter = Filter::addresses([owner(2), p2sh.clone()].into_iter().collect());
+
+    assert!(filter.matches(&utxo(&owner(2).ergo_tree().unwrap(), 1, &[])));
+    assert!(filter.matches(&utxo(&script, 1, &[])));
+    assert!(!filter.matches(&utxo(&owner(3).ergo_tree().unwrap(), 1, &[])));
+
+    let text = format!("addresses:{},{p2sh} and min-value:5", owner(2));
+    let parsed: Filter = text.parse().unwrap();
+    assert_eq!(parsed, filter.and(Filter::min_value(5)));
+    assert_eq!(parsed.to_string(), text);
+    assert_eq!("addresses:".parse::<Filter>().unwrap(), Filter::addresses(AddressSet::new()));
+}
+
+#[test]
+fn rejects_malformed_expressions() {
+    let parse = |s: &str| s.parse::<Filter>().unwrap_err();
+
+    assert_eq!(parse(""), FilterError::Empty);
+    assert_eq!(parse("min-value:1 and"), FilterError::Empty);
+    assert_eq!(parse("(min-value:1"), FilterError::Unclosed);
+    assert_eq!(parse("min-value:1)"), FilterError::Unexpected(")".to_string()));
+    assert_eq!(parse("or min-value:1"), FilterError::Unexpected("or".to_string()));
+    assert_eq!(parse("height:5"), FilterError::UnknownTerm("height".to_string()));
+    assert_eq!(
+        parse("min-value:-1"),
+        FilterError::InvalidValue { term: "min-value", value: "-1".to_string() }
+    );
+    assert!(matches!(parse("address:nope"), FilterError::InvalidValue { term: "address", .. }));
+    assert!(matches!(
+        parse(&format!("addresses:{},nope", owner(2))),
+        FilterError::InvalidValue { term: "addresses", .. }
+    ));
+}
+
+#[test]
+fn filters_snapshots_and_diffs() {
+    let (mine, other) = (owner(2).ergo_tree().unwrap(), owner(3).ergo_tree().unwrap());
+    let interner = ErgoTreeInterner::new();
+    let older = MempoolSnapshot::new(
+        TimestampMillis(1),
+        vec![tx(1, &[output(&mine, 10, &[])]), tx(2, &[output(&other, 10, &[])])],
+        &interner,
+    );
+    let newer = older.next(
+        TimestampMillis(2),
+        vec![
+            tx(2, &[output(&other, 10, &[])]),
+            tx(3, &[output(&other, 1, &[]), output(&mine, 20, &[])]),
+            tx(4, &[output(&other, 30, &[])]),
+        ],
+        &interner,
+    );
+
+    let filter = Filter::address(owner(2));
+    let ids = |txs: Vec<&UnconfirmedTransaction>| -> Vec<HashDigest> {
+        txs.iter().map(|tx| tx.id.clone()).collect()
+    };
+    assert_eq!(ids(newer.matching(&filter)), vec![tx(3, &[]).id]);
+    let owners: AddressSet = [owner(2), owner(3)].into_iter().collect();
+    assert_eq!(
+        ids(newer.transactions_for_addresses(&owners)),
+        ids(newer.transactions.iter().collect())
+    );
+
+    let diff = SnapshotDiff::between_matching(&older, &newer, &filter);
+    assert_eq!(diff.added, vec![tx(3, &[]).id]);
+    assert_eq!(diff.removed, vec![tx(1, &[]).id]);
+    assert_eq!(diff.unchanged_count, 0);
+    assert_eq!(SnapshotDiff::between(&older, &newer).unchanged_count, 1);
+}
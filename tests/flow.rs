@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use hergmes::{
+    analytics::flow::{self, FlowDirection},
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    types::Digest,
+};
+use serde_json::{Value, json};
+
+/// Serves indexer boxes and transactions keyed by request path.
+#[derive(Debug, Default)]
+struct IndexerMock {
+    responses: HashMap<String, Value>,
+}
+
+impl IndexerMock {
+    fn with_box(mut self, id: u8, tx: u8, spent_by: Option<u8>) -> Self {
+        let mut indexed = utxo(id, tx);
+        indexed["inclusionHeight"] = 100.into();
+        indexed["spentTransactionId"] = spent_by.map(hex_id).into();
+        self.responses
+            .insert(format!("blockchain/box/byId/{}", hex_id(id)), indexed);
+        self
+    }
+
+    fn with_tx(mut self, id: u8, inputs: &[(u8, u8)], outputs: &[u8]) -> Self {
+        let tx = json!({
+            "id": hex_id(id),
+            "inclusionHeight": 100,
+            "inputs": inputs.iter().map(|(b, t)| utxo(*b, *t)).collect::<Vec<_>>(),
+            "outputs": outputs.iter().map(|b| utxo(*b, id)).collect::<Vec<_>>(),
+        });
+        self.responses
+            .insert(format!("blockchain/transaction/byId/{}", hex_id(id)), tx);
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for IndexerMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let body = serde_json::to_vec(&self.responses[&request.path]).unwrap();
+        Ok(HttpResponse { status: 200, body: body.into() })
+    }
+}
+
+fn hex_id(id: u8) -> String {
+    format!("{id:02x}").repeat(32)
+}
+
+fn utxo(id: u8, tx: u8) -> Value {
+    json!({
+        "boxId": hex_id(id),
+        "ergoTree": "0008cd",
+        "creationHeight": 1,
+        "value": 1_000_000,
+        "index": 0,
+        "transactionId": hex_id(tx),
+    })
+}
+
+/// Box 0xa0 is spent by 0x01 into 0xb0 and 0xc0; 0xb0 is spent by 0x02 into 0xd0.
+fn node() -> NodeClient {
+    let mock = IndexerMock::default()
+        .with_box(0xa0, 0x00, Some(0x01))
+        .with_box(0xb0, 0x01, Some(0x02))
+        .with_box(0xc0, 0x01, None)
+        .with_box(0xd0, 0x02, None)
+        .with_tx(0x01, &[(0xa0, 0x00)], &[0xb0, 0xc0])
+        .with_tx(0x02, &[(0xb0, 0x01)], &[0xd0]);
+    NodeClient::with_transport(mock)
+}
+
+#[tokio::test]
+async fn traces_spends_forward_up_to_depth() {
+    let node = node();
+    let root = Digest([0xa0; 32]);
+
+    let graph = flow::trace_funds(&node, &root, FlowDirection::Forward, 2)
+        .await
+        .unwrap();
+    assert_eq!(graph.boxes.len(), 4);
+    assert_eq!(graph.transactions.len(), 2);
+    assert_eq!(graph.boxes[&Digest([0xd0; 32])].depth, 2);
+
+    let shallow = flow::trace_funds(&node, &root, FlowDirection::Forward, 1)
+        .await
+        .unwrap();
+    assert_eq!(shallow.boxes.len(), 3);
+    assert_eq!(shallow.transactions[&Digest([0x01; 32])].outputs.len(), 2);
+}
+
+#[tokio::test]
+async fn traces_origins_backward() {
+    let graph = flow::trace_funds(&node(), &Digest([0xd0; 32]), FlowDirection::Backward, 1)
+        .await
+        .unwrap();
+
+    assert_eq!(graph.transactions.len(), 1);
+    assert!(graph.boxes.contains_key(&Digest([0xb0; 32])));
+    assert!(!graph.boxes.contains_key(&Digest([0xa0; 32])));
+}
@@ -0,0 +1,145 @@
+//! Golden tests of the node response deserializers.
+//!
+//! Fixtures in `tests/fixtures/responses` are gzipped node responses; each one is decoded
+//! into its type and the `{:#?}` rendering compared with `tests/golden/<name>.txt`. Run with
+//! `UPDATE_GOLDEN=1` to accept intended changes, and
+//! `ERGO_NODE_URL=<url> cargo test --test golden -- --ignored` to re-record the fixtures from
+//! a mainnet node with the blockchain indexer enabled.
+//!
+//! The committed fixtures are still hand-written in the node's response format, with
+//! placeholder ids and addresses, so they pin the deserializers but can't reveal drift of the
+//! node API. Replace them with a recording, then accept the goldens with `UPDATE_GOLDEN=1`.
+
+use std::{fmt::Debug, fs, io::Read, path::PathBuf};
+
+use flate2::read::GzDecoder;
+use hergmes::{
+    clients::node::{ItemsResponse, SchemaMode},
+    types::{
+        TimestampMillis,
+        ergo::{Block, IndexedBox, IndexedTransaction, TokenInfo, UnconfirmedTransaction},
+    },
+};
+use serde::de::DeserializeOwned;
+
+fn path(dir: &str, file: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", dir, file]
+        .iter()
+        .collect()
+}
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = path("fixtures/responses", &format!("{name}.json.gz"));
+    let compressed = fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {path:?}: {e}"));
+    let mut json = Vec::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_end(&mut json)
+        .unwrap();
+    json
+}
+
+/// Decodes the fixture leniently, as the node client does, and checks it against its golden
+/// rendering.
+fn golden<T: DeserializeOwned + Debug>(name: &str) -> T {
+    let value: T = SchemaMode::Lenient
+        .decode(&fixture(name))
+        .unwrap_or_else(|e| panic!("Failed to decode fixture `{name}`: {e}"));
+    let rendered = format!("{value:#?}\n");
+
+    let path = path("golden", &format!("{name}.txt"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &rendered).unwrap();
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {path:?}: {e}; run with UPDATE_GOLDEN=1"));
+    assert!(rendered == expected, "`{name}` no longer decodes to {path:?}");
+    value
+}
+
+#[test]
+fn unconfirmed_transactions() {
+    let txs: Vec<UnconfirmedTransaction> = golden("unconfirmed_transactions");
+    assert_eq!(txs.len(), 2);
+}
+
+#[test]
+fn block() {
+    let block: Block = golden("block");
+    assert_eq!(block.transactions.header_id, block.header.id);
+}
+
+#[test]
+fn boxes() {
+    let page: ItemsResponse<IndexedBox> = golden("boxes_by_token_id");
+    assert_eq!(page.items.len() as u64, page.total);
+    golden::<IndexedBox>("box");
+}
+
+#[test]
+fn indexed_transaction() {
+    let tx: IndexedTransaction = golden("indexed_transaction");
+    assert!(tx.timestamp > TimestampMillis(0));
+}
+
+#[test]
+fn token() {
+    let token: TokenInfo = golden("token");
+    assert_eq!(token.decimals, Some(2));
+}
+
+#[cfg(feature = "reqwest")]
+#[tokio::test]
+#[ignore = "needs a node at ERGO_NODE_URL"]
+async fn record_fixtures() {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+    use serde_json::Value;
+
+    let url = std::env::var("ERGO_NODE_URL").expect("ERGO_NODE_URL is not set");
+    let client = reqwest::Client::new();
+    let get = async |endpoint: String| -> Value {
+        let response = client
+            .get(format!("{url}/{endpoint}"))
+            .send()
+            .await
+            .unwrap();
+        response.error_for_status().unwrap().json().await.unwrap()
+    };
+    let record = |name: &str, value: &Value| {
+        let path = path("fixtures/responses", &format!("{name}.json.gz"));
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        serde_json::to_writer_pretty(&mut encoder, value).unwrap();
+        encoder.write_all(b"\n").unwrap();
+        fs::write(path, encoder.finish().unwrap()).unwrap();
+    };
+
+    let info = get("info".into()).await;
+    assert_eq!(info["network"], "mainnet", "fixtures are recorded from mainnet");
+
+    let mempool = get("transactions/unconfirmed?offset=0&limit=5".into()).await;
+    record("unconfirmed_transactions", &mempool);
+
+    let header = &get("blocks/lastHeaders/1".into()).await[0];
+    let block = get(format!("blocks/{}", header["id"].as_str().unwrap())).await;
+    record("block", &block);
+
+    let tx = &block["blockTransactions"]["transactions"][0];
+    let tx = get(format!("blockchain/transaction/byId/{}", tx["id"].as_str().unwrap())).await;
+    record("indexed_transaction", &tx);
+
+    let output = &tx["outputs"][0];
+    let indexed = get(format!("blockchain/box/byId/{}", output["boxId"].as_str().unwrap())).await;
+    record("box", &indexed);
+
+    let token_id = block["blockTransactions"]["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|tx| tx["outputs"].as_array().unwrap())
+        .find_map(|o| o["assets"][0]["tokenId"].as_str())
+        .expect("no token in the last block");
+    record("token", &get(format!("blockchain/token/byId/{token_id}")).await);
+    let boxes = get(format!("blockchain/box/byTokenId/{token_id}?offset=0&limit=5")).await;
+    record("boxes_by_token_id", &boxes);
+}
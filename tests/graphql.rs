@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use hergmes::{
+    address::NetworkPrefix,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    intern::ErgoTreeInterner,
+    server::{ServerState, graphql},
+    types::TimestampMillis,
+    watcher::MempoolSnapshot,
+};
+use serde_json::json;
+
+/// Answers every indexer token request with the same token.
+#[derive(Debug)]
+struct TokenMock;
+
+#[async_trait]
+impl HttpTransport for TokenMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let id = request.path.rsplit('/').next().unwrap();
+        let body = json!({
+            "id": id,
+            "boxId": "bb".repeat(32),
+            "emissionAmount": 1_000_000,
+            "name": "Test",
+            "decimals": 2,
+        });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+/// Records box requests and answers them with one unspent box.
+#[derive(Debug, Default)]
+struct BoxesMock {
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+}
+
+#[async_trait]
+impl HttpTransport for BoxesMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        self.requests.lock().unwrap().push(request);
+        let item = json!({
+            "boxId": "bb".repeat(32),
+            "ergoTree": "0008cd".to_string() + &"02".repeat(33),
+            "creationHeight": 1_000,
+            "value": 1_000_000,
+            "assets": [],
+            "additionalRegisters": {},
+            "index": 0,
+            "transactionId": "ee".repeat(32),
+            "inclusionHeight": 1_000,
+        });
+        let body = json!({ "items": [item], "total": 20 });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+fn state(node: NodeClient, mempool: MempoolSnapshot) -> ServerState {
+    ServerState {
+        node,
+        mempool: Arc::new(ArcSwap::from_pointee(mempool)),
+        network: NetworkPrefix::Mainnet,
+        divergence: None,
+        labels: Arc::new(hergmes::labels::BUILTIN.clone()),
+        templates: Arc::new(hergmes::templates::BUILTIN.clone()),
+        dapps: None,
+        tokens: Arc::default(),
+        prices: None,
+        order_book: None,
+        miners: None,
+        voting: None,
+        rolling: None,
+        tenants: None,
+    }
+}
+
+#[tokio::test]
+async fn serves_mempool_and_tokens() {
+    let path =
+        format!("{}/tests/fixtures/node-5.0/unconfirmed_by_ids.json", env!("CARGO_MANIFEST_DIR"));
+    let transactions = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+    let mempool = MempoolSnapshot::new(TimestampMillis(42), transactions, &ErgoTreeInterner::new());
+    let state = state(NodeClient::with_transport(TokenMock), mempool);
+
+    let query = format!(
+        r#"{{
+            mempool {{ lastUpdate transactions {{ outputs {{ value address additionalRegisters {{ key }} }} }} }}
+            token(tokenId: "{}") {{ name decimals emissionAmount }}
+        }}"#,
+        "cc".repeat(32)
+    );
+    let cloned_state = state.clone();
+    let response = graphql::schema(state).execute(query.as_str()).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["mempool"]["lastUpdate"], 42);
+    let output = &data["mempool"]["transactions"][0]["outputs"][0];
+    assert_eq!(output["value"], 1_000_000);
+    assert!(output["address"].as_str().unwrap().starts_with('9'));
+    assert_eq!(output["additionalRegisters"][0]["key"], "R4");
+    assert_eq!(
+        data["token"],
+        json!({ "name": "Test", "decimals": 2, "emissionAmount": 1_000_000 })
+    );
+
+    let invalid = graphql::schema(cloned_state)
+        .execute(r#"{ token(tokenId: "zz") { name } }"#)
+        .await;
+    assert_eq!(invalid.errors.len(), 1);
+}
+
+#[tokio::test]
+async fn pages_unspent_boxes_on_the_indexer() {
+    let mock = BoxesMock::default();
+    let requests = mock.requests.clone();
+    let state = state(NodeClient::with_transport(mock), MempoolSnapshot::default());
+    let address = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+
+    let query = format!(
+        r#"{{ boxes(address: "{address}", spent: false, offset: 10, limit: 100000) {{
+            items {{ value }} hasMore nextOffset
+        }} }}"#
+    );
+    let response = graphql::schema(state).execute(query.as_str()).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let boxes = &response.data.into_json().unwrap()["boxes"];
+    assert_eq!(boxes["items"][0]["value"], 1_000_000);
+    assert_eq!((&boxes["hasMore"], &boxes["nextOffset"]), (&json!(true), &json!(11)));
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].path, "blockchain/box/unspent/byAddress");
+    let param = |key: &str| {
+        let (_, value) = requests[0].query.iter().find(|(k, _)| k == key).unwrap();
+        value.clone()
+    };
+    assert_eq!((param("offset").as_str(), param("limit").as_str()), ("10", "100"));
+}
+
+#[tokio::test]
+async fn reports_invalid_addresses_without_suggestions() {
+    let mock = BoxesMock::default();
+    let requests = mock.requests.clone();
+    let state = state(NodeClient::with_transport(mock), MempoolSnapshot::default());
+    let mistyped = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA".replacen("RAW", "ARW", 1);
+
+    let query = format!(r#"{{ boxes(address: "{mistyped}") {{ items {{ value }} }} }}"#);
+    let response = graphql::schema(state).execute(query.as_str()).await;
+    assert_eq!(response.errors[0].message, "Address checksum mismatch.");
+    assert!(requests.lock().unwrap().is_empty());
+}
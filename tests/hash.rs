@@ -0,0 +1,57 @@
+use hergmes::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    hash::{Blake2b256Hasher, HashError, blake2b, blake2b256, blake2b256_keyed, p2sh_hash, sha256},
+};
+
+#[test]
+fn matches_reference_vectors() {
+    assert_eq!(
+        hex::encode(blake2b256(b"")),
+        "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+    );
+    assert_eq!(
+        hex::encode(blake2b(64, b"abc").unwrap()),
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+         7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+    );
+    assert_eq!(
+        hex::encode(sha256(b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn variants_agree() {
+    let data = [7u8; 300];
+    assert_eq!(blake2b(32, &data).unwrap(), blake2b256(&data));
+    assert_eq!(blake2b(20, &data).unwrap().len(), 20);
+    assert_ne!(blake2b(20, &data).unwrap()[..], blake2b256(&data)[..20]);
+    assert_eq!(blake2b(0, &data), Err(HashError::OutputLength(0)));
+    assert_eq!(blake2b(65, &data), Err(HashError::OutputLength(65)));
+
+    let mut hasher = Blake2b256Hasher::new();
+    hasher.update(&data[..100]).update(&data[100..]);
+    assert_eq!(hasher.finalize_reset(), blake2b256(&data));
+    assert_eq!(hasher.finalize(), blake2b256(b""));
+}
+
+#[test]
+fn keyed_hashes_depend_on_the_key() {
+    let a = blake2b256_keyed(b"key", b"data").unwrap();
+    assert_eq!(a, blake2b256_keyed(b"key", b"data").unwrap());
+    assert_ne!(a, blake2b256_keyed(b"other key", b"data").unwrap());
+    assert_ne!(a, blake2b256(b"data"));
+    assert_eq!(blake2b256_keyed(b"", b"data"), Err(HashError::KeyLength(0)));
+    assert_eq!(blake2b256_keyed(&[1; 65], b"data"), Err(HashError::KeyLength(65)));
+}
+
+#[test]
+fn p2sh_addresses_hash_the_tree() {
+    let tree = hex::decode("100104000e01abd17300").unwrap();
+    let address = ErgoAddress::p2sh(NetworkPrefix::Mainnet, &tree);
+
+    assert_eq!(address.kind(), AddressType::P2SH);
+    assert_eq!(address.content(), p2sh_hash(&tree));
+    assert_eq!(address.encode().parse::<ErgoAddress>().unwrap(), address);
+    assert_eq!(address.ergo_tree(), None);
+}
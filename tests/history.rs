@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use hergmes::{
+    intern::ErgoTreeInterner,
+    types::{TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::{
+        DeltaDecoder, DeltaEncoder, DeltaError, MempoolSnapshot, SnapshotDiff, SnapshotFrame,
+        SnapshotHistory,
+    },
+};
+use serde_json::json;
+
+fn tx(id: u8) -> UnconfirmedTransaction {
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inputs": [],
+        "outputs": [],
+    }))
+    .unwrap()
+}
+
+fn snapshot(last_update: u64, ids: &[u8]) -> Arc<MempoolSnapshot> {
+    let transactions = ids.iter().copied().map(tx).collect();
+    Arc::new(MempoolSnapshot::new(
+        TimestampMillis(last_update),
+        transactions,
+        &ErgoTreeInterner::new(),
+    ))
+}
+
+#[test]
+fn evicts_oldest_when_full() {
+    let mut history = SnapshotHistory::new(2);
+    history.push(snapshot(100, &[1]));
+    history.push(snapshot(200, &[2]));
+    history.push(snapshot(300, &[3]));
+
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.oldest().unwrap().last_update, 200);
+    assert_eq!(history.latest().unwrap().last_update, 300);
+
+    history.push(snapshot(250, &[4]));
+    assert_eq!(history.latest().unwrap().last_update, 300);
+}
+
+#[test]
+fn snapshot_at_returns_snapshot_in_effect() {
+    let mut history = SnapshotHistory::new(4);
+    history.push(snapshot(100, &[1]));
+    history.push(snapshot(200, &[2]));
+
+    assert!(history.snapshot_at(TimestampMillis(99)).is_none());
+    assert_eq!(
+        history
+            .snapshot_at(TimestampMillis(100))
+            .unwrap()
+            .last_update,
+        100
+    );
+    assert_eq!(
+        history
+            .snapshot_at(TimestampMillis(199))
+            .unwrap()
+            .last_update,
+        100
+    );
+    assert_eq!(
+        history
+            .snapshot_at(TimestampMillis(200))
+            .unwrap()
+            .last_update,
+        200
+    );
+    assert_eq!(
+        history
+            .snapshot_at(TimestampMillis(u64::MAX))
+            .unwrap()
+            .last_update,
+        200
+    );
+}
+
+#[test]
+fn diff_between_times() {
+    let mut history = SnapshotHistory::new(4);
+    history.push(snapshot(100, &[1, 2]));
+    history.push(snapshot(200, &[2, 3]));
+
+    let diff = history
+        .diff(TimestampMillis(150), TimestampMillis(250))
+        .unwrap();
+    assert_eq!(diff.added, vec![tx(3).id]);
+    assert_eq!(diff.removed, vec![tx(1).id]);
+
+    assert!(
+        history
+            .diff(TimestampMillis(200), TimestampMillis(200))
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(history.diff(TimestampMillis(50), TimestampMillis(200)), None::<SnapshotDiff>);
+}
+
+#[test]
+fn snapshot_diff_serializes() {
+    let diff = snapshot(100, &[1, 2, 3]).diff(&snapshot(200, &[2, 3, 4, 5]));
+    assert_eq!(diff.added, vec![tx(4).id, tx(5).id]);
+    assert_eq!(diff.removed, vec![tx(1).id]);
+    assert_eq!(diff.unchanged_count, 2);
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["unchangedCount"], 2);
+    assert_eq!(json["removed"], json!(["01".repeat(32)]));
+    assert_eq!(serde_json::from_value::<SnapshotDiff>(json).unwrap(), diff);
+}
+
+#[test]
+fn delta_frames_round_trip() {
+    let snapshots = [
+        snapshot(100, &[1, 2]),
+        snapshot(200, &[2, 3]),
+        snapshot(300, &[3, 4, 5]),
+        snapshot(400, &[5]),
+    ];
+    let mut encoder = DeltaEncoder::new(3);
+    let mut decoder = DeltaDecoder::default();
+
+    let frames: Vec<SnapshotFrame> = snapshots
+        .iter()
+        .map(|s| encoder.encode(s.clone()))
+        .collect();
+    let keyframes: Vec<bool> = frames.iter().map(SnapshotFrame::is_keyframe).collect();
+    assert_eq!(keyframes, [true, false, false, true]);
+
+    let SnapshotFrame::Delta { added, removed, .. } = &frames[1] else { unreachable!() };
+    assert_eq!(added.iter().map(|tx| &tx.id).collect::<Vec<_>>(), [&tx(3).id]);
+    assert_eq!(removed, &[tx(1).id]);
+
+    for (frame, expected) in frames.into_iter().zip(&snapshots) {
+        let json = serde_json::to_string(&frame).unwrap();
+        let decoded = decoder.apply(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(decoded.last_update, expected.last_update);
+        let ids = |s: &MempoolSnapshot| {
+            s.transactions
+                .iter()
+                .map(|tx| tx.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&decoded), ids(expected));
+    }
+}
+
+#[test]
+fn delta_requires_matching_base() {
+    let mut encoder = DeltaEncoder::default();
+    encoder.encode(snapshot(100, &[1]));
+    let delta = encoder.encode(snapshot(200, &[2]));
+
+    let mut decoder = DeltaDecoder::default();
+    assert!(matches!(decoder.apply(delta.clone()), Err(DeltaError::MissingKeyframe)));
+
+    decoder
+        .apply(SnapshotFrame::Keyframe {
+            last_update: TimestampMillis(150),
+            transactions: vec![],
+            first_seen: Default::default(),
+        })
+        .unwrap();
+    assert!(matches!(
+        decoder.apply(delta),
+        Err(DeltaError::BaseMismatch {
+            expected: TimestampMillis(100),
+            actual: TimestampMillis(150)
+        })
+    ));
+    assert_eq!(decoder.current().unwrap().last_update, 150);
+}
+
+#[test]
+fn orders_transactions_by_first_seen_then_id() {
+    let interner = ErgoTreeInterner::new();
+    let first = MempoolSnapshot::new(TimestampMillis(100), vec![tx(5), tx(2)], &interner);
+    let second = first.next(TimestampMillis(200), vec![tx(1), tx(5), tx(3), tx(2)], &interner);
+    let ids = |s: &MempoolSnapshot| {
+        s.transactions
+            .iter()
+            .map(|tx| tx.id.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&first), [tx(2).id, tx(5).id]);
+    assert_eq!(ids(&second), [tx(2).id, tx(5).id, tx(1).id, tx(3).id]);
+    assert_eq!(second.first_seen[&tx(3).id], 200);
+
+    let same = first.next(TimestampMillis(300), vec![tx(2), tx(5)], &interner);
+    assert_eq!(same.content_hash(), first.content_hash());
+    assert_ne!(second.content_hash(), first.content_hash());
+
+    // Keyframes carry first-seen times, so readers rebuild the same order.
+    let mut decoder = DeltaDecoder::default();
+    let frame = DeltaEncoder::default().encode(Arc::new(second));
+    let json = serde_json::to_string(&frame).unwrap();
+    let decoded = decoder.apply(serde_json::from_str(&json).unwrap()).unwrap();
+    assert_eq!(ids(&decoded), [tx(2).id, tx(5).id, tx(1).id, tx(3).id]);
+}
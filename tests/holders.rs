@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use hergmes::{
+    address::NetworkPrefix,
+    analytics::holders,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    tokens::{TokenMetadata, TokenRegistry},
+    types::{HashDigest, Height, HexBytes},
+};
+use serde_json::{Value, json};
+
+const TOKEN: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn tree(owner: u8) -> String {
+    format!("0008cd02{}", format!("{owner:02x}").repeat(32))
+}
+
+/// `(box, owner, amount, included at, spent at)`
+const BOXES: [(u8, u8, u64, u32, Option<u32>); 4] = [
+    (0x01, 0x10, 1_000, 100, Some(150)),
+    (0x02, 0x20, 600, 150, None),
+    (0x03, 0x10, 400, 150, None),
+    (0x04, 0x30, 5, 200, None),
+];
+
+/// Serves the boxes above two per page, and spending transactions by id.
+#[derive(Debug)]
+struct TokenIndexMock;
+
+fn indexed_box((id, owner, amount, included, spent): (u8, u8, u64, u32, Option<u32>)) -> Value {
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": tree(owner),
+        "creationHeight": included,
+        "value": 1_000_000,
+        "assets": [
+            { "tokenId": "bb".repeat(32), "amount": 7 },
+            { "tokenId": TOKEN, "amount": amount },
+        ],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+        "inclusionHeight": included,
+        "spentTransactionId": spent.map(|h| format!("{:064x}", h)),
+    })
+}
+
+#[async_trait]
+impl HttpTransport for TokenIndexMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let query = |key: &str| -> usize {
+            request
+                .query
+                .iter()
+                .find(|(k, _)| k == key)
+                .unwrap()
+                .1
+                .parse()
+                .unwrap()
+        };
+        let body = if let Some(tx_id) = request.path.strip_prefix("blockchain/transaction/byId/") {
+            let height = u32::from_str_radix(tx_id, 16).unwrap();
+            json!({ "id": tx_id, "inclusionHeight": height, "inputs": [], "outputs": [] })
+        } else {
+            let unspent = request
+                .path
+                .starts_with("blockchain/box/unspent/byTokenId/");
+            let boxes: Vec<Value> = BOXES
+                .into_iter()
+                .filter(|b| !unspent || b.4.is_none())
+                .map(indexed_box)
+                .collect();
+            let offset = query("offset");
+            let page: Vec<Value> = boxes.iter().skip(offset).take(2).cloned().collect();
+            json!({ "items": page, "total": boxes.len() })
+        };
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+fn balance(holders: &holders::TokenHolders, owner: u8) -> Option<u64> {
+    let tree = HexBytes(hex::decode(tree(owner)).unwrap());
+    holders.balances.get(&tree).copied()
+}
+
+#[tokio::test]
+async fn computes_current_holders() {
+    let node = NodeClient::with_transport(TokenIndexMock);
+    let token: HashDigest = TOKEN.parse().unwrap();
+    let holders = holders::token_holders(&node, &token, None).await.unwrap();
+
+    assert_eq!(holders.total_supply(), 1_005);
+    assert_eq!(balance(&holders, 0x10), Some(400));
+
+    let mut tokens = TokenRegistry::new();
+    tokens.insert(token, TokenMetadata { name: Some("Test".into()), decimals: 2 });
+    let mut csv = Vec::new();
+    holders
+        .write_csv(NetworkPrefix::Mainnet, &tokens, &mut csv)
+        .unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "address,amount,balance");
+    assert!(rows[1].ends_with(",600,6.00"));
+    assert!(rows[3].ends_with(",5,0.05"));
+}
+
+#[tokio::test]
+async fn computes_holders_at_height() {
+    let node = NodeClient::with_transport(TokenIndexMock);
+    let token: HashDigest = TOKEN.parse().unwrap();
+
+    let holders = holders::token_holders(&node, &token, Some(Height(149)))
+        .await
+        .unwrap();
+    assert_eq!(holders.balances.len(), 1);
+    assert_eq!(balance(&holders, 0x10), Some(1_000));
+
+    let holders = holders::token_holders(&node, &token, Some(Height(150)))
+        .await
+        .unwrap();
+    assert_eq!(balance(&holders, 0x10), Some(400));
+    assert_eq!(balance(&holders, 0x20), Some(600));
+    assert_eq!(balance(&holders, 0x30), None);
+}
@@ -0,0 +1,126 @@
+use std::{sync::Arc, thread};
+
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    intern::ErgoTreeInterner,
+    types::{TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::{MempoolSnapshot, TxRef},
+};
+use serde_json::json;
+
+#[test]
+fn shares_equal_trees() {
+    let interner = ErgoTreeInterner::new();
+    let a = interner.intern(&[0x10, 0x01]);
+    let b = interner.intern(&[0x10, 0x01]);
+    let c = interner.intern(&[0x10, 0x02]);
+
+    assert!(Arc::ptr_eq(&a, &b));
+    assert_ne!(a, c);
+    assert_eq!(a.as_bytes(), [0x10, 0x01]);
+    assert_eq!(interner.len(), 2);
+
+    drop(c);
+    assert_eq!(interner.purge(), 1);
+    assert_eq!(interner.len(), 1);
+}
+
+#[test]
+fn interns_concurrently() {
+    let interner = Arc::new(ErgoTreeInterner::new());
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let interner = interner.clone();
+            thread::spawn(move || {
+                (0..100u8)
+                    .map(|i| interner.intern(&[i; 40]))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(interner.len(), 100);
+    for trees in &results[1..] {
+        assert!(
+            trees
+                .iter()
+                .zip(&results[0])
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+        );
+    }
+}
+
+fn tx(id: u8, trees: &[&str]) -> UnconfirmedTransaction {
+    let outputs: Vec<_> = trees
+        .iter()
+        .enumerate()
+        .map(|(i, tree)| {
+            json!({
+                "boxId": format!("{:02x}", i).repeat(32),
+                "ergoTree": tree,
+                "creationHeight": 1,
+                "value": 1_000_000,
+                "index": i,
+                "transactionId": format!("{id:02x}").repeat(32),
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inputs": [],
+        "outputs": outputs,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn snapshots_share_output_trees() {
+    let interner = ErgoTreeInterner::new();
+    let first =
+        MempoolSnapshot::new(TimestampMillis(1), vec![tx(1, &["1001", "1002", "1001"])], &interner);
+    let second = MempoolSnapshot::new(
+        TimestampMillis(2),
+        vec![tx(2, &["1002"]), tx(3, &["1003"])],
+        &interner,
+    );
+
+    assert_eq!(first.outputs_by_tree.len(), 2);
+    let shared = |s: &MempoolSnapshot| {
+        s.outputs_by_tree
+            .keys()
+            .find(|t| t.as_bytes() == [0x10, 0x02])
+            .unwrap()
+            .clone()
+    };
+    assert!(Arc::ptr_eq(&shared(&first), &shared(&second)));
+    assert_eq!(interner.len(), 3);
+
+    drop(first);
+    assert_eq!(interner.purge(), 1);
+}
+
+#[test]
+fn indexes_outputs_by_tree() {
+    let interner = ErgoTreeInterner::new();
+    let p2pk = format!("0008cd{}", "02".repeat(33));
+    let snapshot = MempoolSnapshot::new(
+        TimestampMillis(1),
+        vec![tx(1, &["1001", &p2pk, &p2pk]), tx(2, &["1001"]), tx(3, &[&p2pk])],
+        &interner,
+    );
+
+    assert_eq!(
+        snapshot.outputs_for_tree(&[0x10, 0x01]),
+        [TxRef { tx: 0, output: 0 }, TxRef { tx: 1, output: 0 }]
+    );
+    assert!(snapshot.outputs_for_tree(&[0x10, 0x09]).is_empty());
+
+    let address = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33]);
+    let ids: Vec<_> = snapshot
+        .transactions_for_address(&address)
+        .iter()
+        .map(|tx| tx.id.to_string())
+        .collect();
+    assert_eq!(ids, ["01".repeat(32), "03".repeat(32)]);
+}
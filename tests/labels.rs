@@ -0,0 +1,77 @@
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::cluster::AddressClusters,
+    chain::fee::FEE_ERGO_TREE,
+    labels::{BUILTIN, Label, LabelCategory, LabelError, LabelSet},
+    types::ergo::UTxO,
+};
+use serde_json::json;
+
+const ADDRESS: &str = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+
+fn p2pk_input(key: u8) -> UTxO {
+    serde_json::from_value(json!({
+        "boxId": format!("{key:02x}").repeat(32),
+        "ergoTree": format!("0008cd02{}", format!("{key:02x}").repeat(32)),
+        "creationHeight": 1_000_000,
+        "value": 1_000_000,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn builtin_labels_the_fee_contract() {
+    let label = BUILTIN.get_by_tree(&FEE_ERGO_TREE).unwrap();
+    assert_eq!(label.category, LabelCategory::Protocol);
+}
+
+#[test]
+fn overrides_replace_builtin_labels() {
+    let fee_tree = hex::encode(&*FEE_ERGO_TREE);
+    let overrides = LabelSet::from_json(
+        &json!([
+            { "ergoTree": fee_tree, "name": "Fees", "category": "miner" },
+            { "address": ADDRESS, "name": "Some exchange", "category": "exchange", "url": "https://example.com" },
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    let mut labels = BUILTIN.clone();
+    labels.extend(overrides);
+
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels.get_by_tree(&FEE_ERGO_TREE).unwrap().name, "Fees");
+
+    // Labels apply regardless of the address network.
+    let mainnet: ErgoAddress = ADDRESS.parse().unwrap();
+    let tree = mainnet.ergo_tree().unwrap();
+    let testnet = ErgoAddress::from_ergo_tree(NetworkPrefix::Testnet, &tree.0);
+    assert_eq!(labels.get(&testnet).unwrap().category, LabelCategory::Exchange);
+}
+
+#[test]
+fn rejects_entries_without_a_single_target() {
+    let json = json!([{ "name": "Nowhere", "category": "other" }]).to_string();
+    assert!(
+        matches!(LabelSet::from_json(&json), Err(LabelError::Target(name)) if name == "Nowhere")
+    );
+}
+
+#[test]
+fn annotates_address_clusters() {
+    let mut clusters = AddressClusters::new(NetworkPrefix::Mainnet);
+    clusters.observe_inputs(&[p2pk_input(1), p2pk_input(2)]);
+    clusters.observe_inputs(&[p2pk_input(3)]);
+
+    let labelled = ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &p2pk_input(2).ergo_tree.0);
+    let mut labels = LabelSet::new();
+    let label = Label { name: "Mixer".into(), category: LabelCategory::Mixer, url: None };
+    labels.insert(&labelled, label.clone());
+
+    let annotated = clusters.labels(&labels);
+    assert_eq!(annotated.len(), 1);
+    assert_eq!(annotated[&clusters.cluster_id(&labelled).unwrap()], [&label]);
+}
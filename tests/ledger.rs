@@ -0,0 +1,180 @@
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    types::{HashDigest, ergo::IndexedTransaction},
+    wallet::ledger::{Commodity, Ledger},
+};
+use serde_json::{Value, json};
+
+const TOKEN: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const FEE_TREE: &str = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+
+fn key(owner: u8) -> [u8; 33] {
+    let mut key = [owner; 33];
+    key[0] = 0x02;
+    key
+}
+
+fn utxo(tree: String, value: u64, tokens: u64) -> Value {
+    let assets: Vec<Value> = (tokens > 0)
+        .then(|| json!({ "tokenId": TOKEN, "amount": tokens }))
+        .into_iter()
+        .collect();
+    json!({
+        "boxId": "01".repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 100,
+        "value": value,
+        "assets": assets,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn p2pk(owner: u8) -> String {
+    format!("0008cd{}", hex::encode(key(owner)))
+}
+
+fn transaction(
+    id: u8,
+    timestamp: u64,
+    inputs: Vec<Value>,
+    outputs: Vec<Value>,
+) -> IndexedTransaction {
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inclusionHeight": 1_000 + id as u32,
+        "timestamp": timestamp,
+        "inputs": inputs,
+        "outputs": outputs,
+    }))
+    .unwrap()
+}
+
+fn balanced(ledger: &Ledger) -> bool {
+    ledger.entries().iter().all(|entry| {
+        let mut sums = std::collections::HashMap::<&Commodity, i128>::new();
+        for p in &entry.postings {
+            *sums.entry(&p.commodity).or_default() += p.amount;
+        }
+        sums.values().all(|s| *s == 0)
+    })
+}
+
+fn ledger() -> (Ledger, ErgoAddress) {
+    let own = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key(0x11));
+    let token: HashDigest = TOKEN.parse().unwrap();
+    let mut ledger = Ledger::new(std::slice::from_ref(&own)).token(token, "TKN", 2);
+
+    // Receives 5 ERG and 12.34 TKN on 2024-02-29.
+    ledger
+        .record(&transaction(
+            1,
+            1_709_164_800_000,
+            vec![utxo(p2pk(0x22), 6_000_000_000, 1_234)],
+            vec![
+                utxo(p2pk(0x11), 5_000_000_000, 1_234),
+                utxo(p2pk(0x22), 999_000_000, 0),
+                utxo(FEE_TREE.to_string(), 1_000_000, 0),
+            ],
+        ))
+        .unwrap();
+    // Sends 2 ERG and pays the fee, keeping the change.
+    ledger
+        .record(&transaction(
+            2,
+            1_709_251_200_000,
+            vec![utxo(p2pk(0x11), 5_000_000_000, 1_234)],
+            vec![
+                utxo(p2pk(0x33), 2_000_000_000, 0),
+                utxo(p2pk(0x11), 2_998_900_000, 1_234),
+                utxo(FEE_TREE.to_string(), 1_100_000, 0),
+            ],
+        ))
+        .unwrap();
+    (ledger, own)
+}
+
+#[test]
+fn records_balanced_entries() {
+    let (mut ledger, own) = ledger();
+    assert_eq!(ledger.entries().len(), 2);
+    assert!(balanced(&ledger));
+
+    let received = &ledger.entries()[0];
+    assert_eq!(received.postings.len(), 4);
+    assert!(!received.postings.iter().any(|p| p.account.contains("Fees")));
+
+    let sent = &ledger.entries()[1];
+    let amount = |account: &str| {
+        sent.postings
+            .iter()
+            .find(|p| p.account == account)
+            .map(|p| p.amount)
+    };
+    assert_eq!(amount(&format!("Assets:Ergo:{own}")), Some(-2_001_100_000));
+    assert_eq!(amount("Expenses:Ergo:Fees"), Some(1_100_000));
+    assert_eq!(amount("Expenses:Ergo:Payments"), Some(2_000_000_000));
+
+    // Transactions between third parties are ignored.
+    let unrelated =
+        transaction(3, 0, vec![utxo(p2pk(0x22), 1_000, 0)], vec![utxo(p2pk(0x33), 1_000, 0)]);
+    assert!(ledger.record(&unrelated).unwrap().is_none());
+}
+
+#[test]
+fn exports_beancount_and_ledger_cli() {
+    let (ledger, own) = ledger();
+
+    let mut beancount = Vec::new();
+    ledger.write_beancount(&mut beancount).unwrap();
+    let beancount = String::from_utf8(beancount).unwrap();
+    assert!(beancount.contains(&format!("2024-02-29 open Assets:Ergo:{own}")));
+    assert!(beancount.contains("2024-03-01 * \"Transaction 0202"));
+    assert!(beancount.contains(" 12.34 TKN"));
+    assert!(beancount.contains(" -2.001100000 ERG"));
+
+    let mut ledger_cli = Vec::new();
+    ledger.write_ledger(&mut ledger_cli).unwrap();
+    let ledger_cli = String::from_utf8(ledger_cli).unwrap();
+    assert!(ledger_cli.starts_with("2024/02/29 Transaction 0101"));
+    assert!(ledger_cli.contains(" 0.001100000 ERG"));
+}
+
+#[test]
+fn opens_accounts_on_their_first_entry_and_sanitizes_symbols() {
+    let own = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key(0x11));
+    let token: HashDigest = TOKEN.parse().unwrap();
+    let mut ledger = Ledger::new(std::slice::from_ref(&own)).token(token, "2x Sig USD!\n", 2);
+
+    // Recorded newest first: a payment on 2024-03-01, then the receipt on 2024-02-29.
+    ledger
+        .record(&transaction(
+            2,
+            1_709_251_200_000,
+            vec![utxo(p2pk(0x11), 5_000_000_000, 1_234)],
+            vec![utxo(p2pk(0x33), 4_999_000_000, 1_234), utxo(FEE_TREE.to_string(), 1_000_000, 0)],
+        ))
+        .unwrap();
+    ledger
+        .record(&transaction(
+            1,
+            1_709_164_800_000,
+            vec![utxo(p2pk(0x22), 5_001_000_000, 1_234)],
+            vec![utxo(p2pk(0x11), 5_000_000_000, 1_234), utxo(FEE_TREE.to_string(), 1_000_000, 0)],
+        ))
+        .unwrap();
+
+    let mut beancount = Vec::new();
+    ledger.write_beancount(&mut beancount).unwrap();
+    let beancount = String::from_utf8(beancount).unwrap();
+    assert!(beancount.contains(&format!("2024-02-29 open Assets:Ergo:{own}")));
+    assert!(beancount.contains("2024-02-29 open Income:Ergo:Receipts"));
+    assert!(beancount.contains("2024-03-01 open Expenses:Ergo:Payments"));
+    assert!(beancount.contains(" 12.34 T2XSIGUSD\n"));
+
+    let mut ledger_cli = Vec::new();
+    ledger.write_ledger(&mut ledger_cli).unwrap();
+    let ledger_cli = String::from_utf8(ledger_cli).unwrap();
+    assert!(ledger_cli.contains(" 12.34 \"T2XSIGUSD\"\n"));
+    assert!(ledger_cli.contains(" 0.001000000 ERG\n"));
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use hergmes::{
+    analytics::lineage::{self, RegisterChange, RegisterValue, SpendingTransaction},
+    chain::register::Constant,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    intern::ErgoTreeInterner,
+    types::{HashDigest, TimestampMillis, ergo::NonMandatoryRegisters},
+    watcher::MempoolSnapshot,
+};
+use serde_json::{Value, json};
+
+const NFT: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn id(n: u8) -> String {
+    format!("{n:02x}").repeat(32)
+}
+
+/// Pool box `n` holds the NFT and rate `n * 100` in R4, and is spent by transaction `0x10 + n`
+/// recreating it as box `n + 1`, next to a change box. Box 3 is unspent.
+fn pool_box(n: u8) -> Value {
+    json!({
+        "boxId": id(n),
+        "ergoTree": "1004",
+        "creationHeight": 100 + n as u32,
+        "value": 1_000_000,
+        "assets": [{ "tokenId": NFT, "amount": 1 }],
+        "additionalRegisters": {
+            "R4": Constant::Long(n as i64 * 100).encode(),
+            "R5": Constant::Int(7).encode(),
+        },
+        "index": 1,
+        "transactionId": id(0x10 + n - 1),
+        "inclusionHeight": 100 + n as u32,
+        "spentTransactionId": (n < 3).then(|| id(0x10 + n)),
+    })
+}
+
+#[derive(Debug)]
+struct PoolMock;
+
+#[async_trait]
+impl HttpTransport for PoolMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let body = if let Some(box_id) = request.path.strip_prefix("blockchain/box/byId/") {
+            pool_box(u8::from_str_radix(&box_id[..2], 16).unwrap())
+        } else {
+            let tx_id = request
+                .path
+                .strip_prefix("blockchain/transaction/byId/")
+                .unwrap();
+            let n = u8::from_str_radix(&tx_id[..2], 16).unwrap() - 0x10;
+            let mut change = pool_box(0x40);
+            change["ergoTree"] = json!(format!("0008cd02{}", "11".repeat(32)));
+            change["assets"] = json!([]);
+            json!({
+                "id": tx_id,
+                "inclusionHeight": 101 + n as u32,
+                "inputs": [pool_box(n)],
+                "outputs": [change, pool_box(n + 1)],
+            })
+        };
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn follows_state_box_by_nft() {
+    let node = NodeClient::with_transport(PoolMock);
+    let start: HashDigest = id(1).parse().unwrap();
+
+    let boxes = lineage::follow_lineage(&node, &start, 10).await.unwrap();
+    let ids: Vec<String> = boxes.iter().map(|b| b.id.to_string()).collect();
+    assert_eq!(ids, [id(1), id(2), id(3)]);
+
+    let boxes = lineage::follow_lineage(&node, &start, 1).await.unwrap();
+    assert_eq!(boxes.len(), 2);
+
+    let changes = lineage::diff_registers(&boxes[0].registers, &boxes[1].registers);
+    assert_eq!(
+        changes,
+        [RegisterChange::Changed {
+            register: 4,
+            from: RegisterValue::Decoded(Constant::Long(100)),
+            to: RegisterValue::Decoded(Constant::Long(200)),
+        }]
+    );
+    assert_eq!(changes[0].delta(), Some(100));
+}
+
+#[tokio::test]
+async fn streams_lineage_into_mempool() {
+    let node = NodeClient::with_transport(PoolMock);
+    let start: HashDigest = id(1).parse().unwrap();
+
+    let confirmed: Vec<_> = lineage::lineage(&node, &start, None).collect().await;
+    let pairs: Vec<(String, String)> = confirmed
+        .into_iter()
+        .map(|item| {
+            let (utxo, tx) = item.unwrap();
+            assert!(matches!(tx, SpendingTransaction::Confirmed(_)));
+            (utxo.id.to_string(), tx.id().to_string())
+        })
+        .collect();
+    assert_eq!(pairs, [(id(1), id(0x11)), (id(2), id(0x12))]);
+
+    // Box 3 is spent in the mempool, recreating the contract as box 4 then box 5.
+    let mut box3 = pool_box(3);
+    box3["spendingProof"] = json!({ "proofBytes": "", "extension": {} });
+    let mut box4 = pool_box(4);
+    let tx = |n: u8, input: Value, output: Value| {
+        serde_json::from_value(
+            json!({ "id": id(0x10 + n), "inputs": [input], "outputs": [output] }),
+        )
+        .unwrap()
+    };
+    let first = tx(3, box3, box4.clone());
+    box4["spendingProof"] = json!({ "proofBytes": "", "extension": {} });
+    let second = tx(4, box4, pool_box(5));
+    let mempool =
+        MempoolSnapshot::new(TimestampMillis(1), vec![second, first], &ErgoTreeInterner::new());
+
+    let ids: Vec<String> = lineage::lineage(&node, &start, Some(&mempool))
+        .map(|item| item.unwrap().0.id.to_string())
+        .collect()
+        .await;
+    assert_eq!(ids, [id(1), id(2), id(3), id(4)]);
+}
+
+#[test]
+fn diffs_added_removed_and_undecodable_registers() {
+    let registers =
+        |value: Value| -> NonMandatoryRegisters { serde_json::from_value(value).unwrap() };
+    let prev = registers(json!({ "R4": "0402", "R6": "ff01" }));
+    let next = registers(json!({ "R5": "0101", "R6": "ff02" }));
+
+    let changes = lineage::diff_registers(&prev, &next);
+    assert_eq!(
+        changes
+            .iter()
+            .map(RegisterChange::register)
+            .collect::<Vec<_>>(),
+        [4, 5, 6]
+    );
+    assert_eq!(
+        changes[0],
+        RegisterChange::Removed { register: 4, value: RegisterValue::Decoded(Constant::Int(1)) }
+    );
+    assert_eq!(
+        changes[1],
+        RegisterChange::Added {
+            register: 5,
+            value: RegisterValue::Decoded(Constant::Boolean(true))
+        }
+    );
+    assert!(matches!(&changes[2], RegisterChange::Changed { from: RegisterValue::Raw(_), .. }));
+    assert_eq!(changes[2].delta(), None);
+    assert!(lineage::diff_registers(&next, &next).is_empty());
+}
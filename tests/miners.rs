@@ -0,0 +1,110 @@
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::miners::{BlockReward, MinerTracker},
+    chain::fee::{self, FEE_ERGO_TREE},
+    types::{Height, HexBytes, ergo::Block},
+};
+use serde_json::{Value, json};
+
+const EMISSION_TREE: &str = "101004020e36";
+
+fn output(id: u8, tree: &[u8], value: u64) -> Value {
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": HexBytes(tree.to_vec()),
+        "creationHeight": 1_000_000,
+        "value": value,
+        "assets": [],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+/// A block by the miner of `key` at `height`, paying `fee` in its single user transaction.
+/// Blocks with no `key` pay nothing to the reward script.
+fn block(height: u32, key: Option<[u8; 33]>, fee: u64) -> Block {
+    let mut header = serde_json::from_slice::<Value>(
+        &std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap()[0]
+        .clone();
+    header["height"] = json!(height);
+
+    let emission = hex::decode(EMISSION_TREE).unwrap();
+    let mut coinbase = vec![output(1, &emission, 1_000_000_000_000)];
+    if let Some(key) = key {
+        coinbase.push(output(2, &fee::miner_reward_tree(&key), 6_000_000_000));
+    }
+    serde_json::from_value(json!({
+        "header": header,
+        "blockTransactions": {
+            "headerId": "dd".repeat(32),
+            "transactions": [
+                { "id": "a1".repeat(32), "inputs": [{ "boxId": "01".repeat(32) }], "outputs": coinbase },
+                {
+                    "id": "a2".repeat(32),
+                    "inputs": [{ "boxId": "03".repeat(32) }],
+                    "outputs": [output(4, &[0x10, 0x00, 0x7f], 5), output(5, &FEE_ERGO_TREE, fee)],
+                },
+            ],
+        },
+    }))
+    .unwrap()
+}
+
+fn miner(key: [u8; 33]) -> ErgoAddress {
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key)
+}
+
+#[test]
+fn reads_miner_keys_from_reward_scripts() {
+    let key = [0x02; 33];
+    assert_eq!(fee::miner_reward_key(&fee::miner_reward_tree(&key)), Some(key));
+    // The reward script embedded in the fee contract, with another delay constant.
+    let delayed =
+        [&[0x10, 0x02, 0x04, 0xc0, 0x0c, 0x08, 0xcd][..], &fee::miner_reward_tree(&key)[7..]];
+    assert_eq!(fee::miner_reward_key(&delayed.concat()), Some(key));
+    assert_eq!(fee::miner_reward_key(&FEE_ERGO_TREE), None);
+    assert_eq!(fee::miner_reward_key(&[0x00, 0x08, 0xcd]), None);
+}
+
+#[test]
+fn attributes_rewards_and_fees() {
+    let reward = BlockReward::of(NetworkPrefix::Mainnet, &block(100, Some([0x02; 33]), 1_100_000));
+    assert_eq!(reward.height, 100);
+    assert_eq!(reward.miner, miner([0x02; 33]));
+    assert_eq!((reward.reward, reward.fees), (6_000_000_000, 1_100_000));
+
+    // Without a reward output, the block goes to the key of its PoW solution.
+    let block = block(101, None, 0);
+    let reward = BlockReward::of(NetworkPrefix::Mainnet, &block);
+    assert_eq!(reward.miner, miner(block.header.pow_solution.pk.0));
+    assert_eq!(reward.reward, 0);
+}
+
+#[test]
+fn reports_miners_over_a_window() {
+    let mut tracker = MinerTracker::new(NetworkPrefix::Mainnet).with_window(3);
+    assert_eq!(tracker.report().from_height, None);
+
+    tracker.apply_block(&block(1, Some([0x09; 33]), 10));
+    tracker.apply_block(&block(2, Some([0x02; 33]), 20));
+    tracker.apply_block(&block(3, Some([0x03; 33]), 30));
+    tracker.apply_block(&block(4, Some([0x02; 33]), 40));
+
+    let stats = tracker.report();
+    assert_eq!(
+        (stats.blocks, stats.from_height, stats.to_height),
+        (3, Some(Height(2)), Some(Height(4)))
+    );
+    assert_eq!(stats.miners.len(), 2);
+    let top = &stats.miners[0];
+    assert_eq!(top.miner, miner([0x02; 33]));
+    assert_eq!((top.blocks, top.rewards, top.fees), (2, 12_000_000_000, 60));
+    assert!((top.share - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(stats.miners[1].miner, miner([0x03; 33]));
+}
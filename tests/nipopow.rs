@@ -0,0 +1,153 @@
+use hergmes::{
+    chain::{
+        header,
+        nipopow::{NipopowError, NipopowProof, PoPowHeader},
+        pow,
+    },
+    types::{
+        Digest, Height, HexBytes, TimestampMillis,
+        ergo::{BlockHeader, PowSolution},
+    },
+};
+use num_bigint::BigUint;
+
+/// Compact encoding of difficulty 1, so every header passes the PoW check.
+const MIN_DIFFICULTY_BITS: u32 = 0x0101_0000;
+
+fn mine(parent: &Digest<32>, height: u32, nonce: u64) -> BlockHeader {
+    let mut header = BlockHeader {
+        id: Digest([0; 32]),
+        parent_id: parent.clone(),
+        height: Height(height),
+        version: 3,
+        ad_proofs_root: Digest([1; 32]),
+        transactions_root: Digest([2; 32]),
+        state_root: Digest([3; 33]),
+        timestamp: TimestampMillis(1_730_000_000_000 + height as u64 * 120_000),
+        extension_root: Digest([4; 32]),
+        n_bits: MIN_DIFFICULTY_BITS,
+        votes: HexBytes(vec![0, 0, 0]),
+        unparsed_bytes: HexBytes(vec![]),
+        pow_solution: PowSolution {
+            pk: Digest([2; 33]),
+            w: Digest([2; 33]),
+            n: Digest(nonce.to_be_bytes()),
+            d: 0.into(),
+        },
+        extension_id: None,
+        ad_proofs_id: None,
+        transactions_id: None,
+        difficulty: None,
+        size: None,
+    };
+    header.id = header::compute_id(&header).unwrap();
+    header
+}
+
+fn chain(from_height: u32, len: u32, parent: &Digest<32>, nonce: u64) -> Vec<BlockHeader> {
+    let mut headers: Vec<BlockHeader> = Vec::new();
+    for height in from_height..from_height + len {
+        let parent = headers
+            .last()
+            .map(|h| h.id.clone())
+            .unwrap_or(parent.clone());
+        headers.push(mine(&parent, height, nonce));
+    }
+    headers
+}
+
+fn popow(header: &BlockHeader, interlinks: Vec<Digest<32>>) -> PoPowHeader {
+    PoPowHeader { header: header.clone(), interlinks, interlinks_proof: serde_json::Value::Null }
+}
+
+fn proof(headers: &[BlockHeader], k: usize) -> NipopowProof {
+    let (prefix, suffix) = headers.split_at(headers.len() - k);
+    let prefix: Vec<PoPowHeader> = prefix
+        .iter()
+        .enumerate()
+        .map(|(i, h)| popow(h, prefix[..i].iter().map(|p| p.id.clone()).collect()))
+        .collect();
+
+    NipopowProof {
+        m: 2,
+        k: k as u32,
+        prefix,
+        suffix_head: popow(&suffix[0], vec![headers[0].id.clone()]),
+        suffix_tail: suffix[1..].to_vec(),
+        continuous: true,
+    }
+}
+
+#[test]
+fn compact_bits_and_table_size() {
+    assert_eq!(pow::decode_compact_bits(0x1d00_ffff), BigUint::from(0xffffu32) << (8 * 26));
+    assert_eq!(pow::decode_compact_bits(MIN_DIFFICULTY_BITS), BigUint::from(1u32));
+    assert_eq!(pow::decode_compact_bits(0x0480_0001), BigUint::ZERO);
+
+    assert_eq!(pow::calc_n(Height(500_000)), 67_108_864);
+    assert_eq!(pow::calc_n(Height(614_400)), 70_464_240);
+    assert_eq!(pow::calc_n(Height(4_198_400)), pow::calc_n(Height(10_000_000)));
+}
+
+#[test]
+fn header_serialization_covers_pow_solution() {
+    let header = mine(&Digest([0; 32]), 1_000_000, 7);
+    let without_pow = header::serialize_without_pow(&header).unwrap();
+    let full = header::serialize(&header).unwrap();
+
+    assert_eq!(full.len(), without_pow.len() + 33 + 8);
+    assert_ne!(header.id, mine(&Digest([0; 32]), 1_000_000, 8).id);
+
+    let mut v1 = header.clone();
+    v1.version = 1;
+    assert!(header::compute_id(&v1).is_err());
+    assert!(pow::pow_hit(&v1).is_err());
+}
+
+#[test]
+fn verifies_well_formed_proof() {
+    let headers = chain(1_000_000, 6, &Digest([0; 32]), 1);
+    let proof = proof(&headers, 3);
+    proof.verify().unwrap();
+    assert_eq!(proof.tip().height, 1_000_005);
+}
+
+#[test]
+fn rejects_tampered_proofs() {
+    let headers = chain(1_000_000, 6, &Digest([0; 32]), 1);
+
+    let mut tampered = proof(&headers, 3);
+    tampered.suffix_tail[0].timestamp.0 += 1;
+    assert!(matches!(tampered.verify(), Err(NipopowError::IdMismatch { .. })));
+
+    let mut unlinked = proof(&headers, 3);
+    unlinked.suffix_tail.swap(0, 1);
+    assert!(matches!(unlinked.verify(), Err(NipopowError::BrokenConnection { .. })));
+
+    let mut short = proof(&headers, 3);
+    short.suffix_tail.pop();
+    assert!(matches!(short.verify(), Err(NipopowError::InvalidSuffixLength { .. })));
+
+    let mut hard = mine(&headers[5].id, 1_000_006, 1);
+    hard.n_bits = 0x2100_ffff;
+    hard.id = header::compute_id(&hard).unwrap();
+    let mut hard_proof = proof(&headers, 3);
+    hard_proof.suffix_tail.push(hard);
+    hard_proof.k += 1;
+    assert!(matches!(hard_proof.verify(), Err(NipopowError::InvalidPow(_))));
+}
+
+#[test]
+fn longer_fork_wins_comparison() {
+    let common = chain(1_000_000, 3, &Digest([0; 32]), 1);
+    let tip = common.last().unwrap().id.clone();
+
+    let mut short = common.clone();
+    short.extend(chain(1_000_003, 3, &tip, 2));
+    let mut long = common.clone();
+    long.extend(chain(1_000_003, 12, &tip, 3));
+
+    let (short, long) = (proof(&short, 3), proof(&long, 3));
+    assert!(long.is_better_than(&short).unwrap());
+    assert!(!short.is_better_than(&long).unwrap());
+}
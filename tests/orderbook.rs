@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::orderbook::{self, OrderBook, OrderKind, SaleContract, SellerEncoding},
+    chain::ergo_tree,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    types::ergo::{Block, UTxO},
+};
+use serde_json::{Value, json};
+
+const SALE_TREE: &str = "100104000e01abd17300";
+const SELLER_KEY: [u8; 33] = [0x02; 33];
+
+fn sale_box(id: u8, token: u8, price: u64) -> Value {
+    // Long price in R4 (ZigZag VLQ) and the seller's ProveDlog in R5.
+    let mut r4 = vec![0x05];
+    let mut v = price << 1;
+    while v >= 0x80 {
+        r4.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    r4.push(v as u8);
+    let r5 = [&[0x08, 0xcd][..], &SELLER_KEY].concat();
+
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": SALE_TREE,
+        "creationHeight": 1_000_000 + id as u32,
+        "value": 1_000_000,
+        "assets": [{ "tokenId": format!("{token:02x}").repeat(32), "amount": 1 }],
+        "additionalRegisters": { "R4": hex::encode(r4), "R5": hex::encode(r5) },
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn book() -> OrderBook {
+    let mut book = OrderBook::new(NetworkPrefix::Mainnet);
+    book.register(SaleContract {
+        name: "Test sale".into(),
+        kind: OrderKind::FixedPrice,
+        template_hash: ergo_tree::template_hash(&hex::decode(SALE_TREE).unwrap()).unwrap(),
+        price_register: 4,
+        seller_register: 5,
+        seller_encoding: SellerEncoding::ProveDlog,
+    });
+    book
+}
+
+#[test]
+fn decodes_sale_boxes() {
+    let book = book();
+    let utxo: UTxO = serde_json::from_value(sale_box(1, 0xaa, 5_000_000_000)).unwrap();
+    let order = book.decode(&utxo).unwrap();
+
+    assert_eq!(order.price, 5_000_000_000);
+    assert_eq!(order.seller, ErgoAddress::p2pk(NetworkPrefix::Mainnet, &SELLER_KEY));
+    assert_eq!(order.token_id, utxo.tokens[0].id);
+
+    let mut other: UTxO = serde_json::from_value(sale_box(2, 0xaa, 1)).unwrap();
+    other.ergo_tree.0.push(0x00);
+    assert!(book.decode(&other).is_none());
+}
+
+#[test]
+fn tracks_orders_across_blocks() {
+    let mut book = book();
+    let token = "aa".repeat(32).parse().unwrap();
+    let boxes: Vec<UTxO> = [sale_box(1, 0xaa, 300), sale_box(2, 0xaa, 100), sale_box(3, 0xbb, 50)]
+        .into_iter()
+        .map(|b| serde_json::from_value(b).unwrap())
+        .collect();
+    book.insert_boxes(&boxes);
+
+    let prices: Vec<u64> = book
+        .orders_for_token(&token)
+        .iter()
+        .map(|o| o.price)
+        .collect();
+    assert_eq!(prices, [100, 300]);
+    assert_eq!(book.best_offer(&token).unwrap().price, 100);
+
+    let header = serde_json::from_slice::<Value>(
+        &std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap()[0]
+        .clone();
+    let block: Block = serde_json::from_value(json!({
+        "header": header,
+        "blockTransactions": {
+            "headerId": "dd".repeat(32),
+            "transactions": [{
+                "id": "a1".repeat(32),
+                "inputs": [{ "boxId": "02".repeat(32) }],
+                "outputs": [sale_box(4, 0xaa, 200)],
+            }],
+        },
+    }))
+    .unwrap();
+    book.apply_block(&block);
+
+    let prices: Vec<u64> = book
+        .orders_for_token(&token)
+        .iter()
+        .map(|o| o.price)
+        .collect();
+    assert_eq!(prices, [200, 300]);
+    assert_eq!(book.len(), 3);
+    let seller = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &SELLER_KEY);
+    assert_eq!(book.orders_by_seller(&seller).len(), 3);
+}
+
+/// Serves three unspent sale boxes, two per page.
+#[derive(Debug)]
+struct UnspentSales;
+
+#[async_trait]
+impl HttpTransport for UnspentSales {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let hash = ergo_tree::template_hash(&hex::decode(SALE_TREE).unwrap()).unwrap();
+        assert_eq!(request.path, format!("blockchain/box/unspent/byTemplateHash/{hash}"));
+        let offset: usize = request
+            .query
+            .iter()
+            .find(|(k, _)| k == "offset")
+            .map_or(0, |(_, v)| v.parse().unwrap());
+        let items: Vec<Value> =
+            [sale_box(1, 0xaa, 300), sale_box(2, 0xaa, 100), sale_box(3, 0xbb, 50)]
+                .into_iter()
+                .skip(offset)
+                .take(2)
+                .map(|mut b| {
+                    b["inclusionHeight"] = json!(1_000_000);
+                    b
+                })
+                .collect();
+        let body = json!({ "items": items, "total": 3 });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn backfills_unspent_orders() {
+    let node = NodeClient::with_transport(UnspentSales);
+    let mut book = book();
+    let hash = book.template_hashes().next().unwrap().clone();
+
+    let boxes = orderbook::fetch_unspent(&node, &hash).await.unwrap();
+    assert_eq!(boxes.len(), 3);
+    book.insert_boxes(&boxes);
+    assert_eq!(book.len(), 3);
+}
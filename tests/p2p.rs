@@ -0,0 +1,249 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use hergmes::{
+    chain::transaction,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    clock::ManualClock,
+    intern::ErgoTreeInterner,
+    p2p::{
+        self, Handshake, InvData, ModifiersData, Network, OriginTracker, P2pConfig, P2pEvent,
+        codec::{self, CHECKSUM_LEN, HEADER_LEN},
+        message,
+    },
+    types::{
+        Digest, TimestampMillis,
+        ergo::{SignedTransaction, UnconfirmedTransaction},
+    },
+    watcher::{self, MempoolSnapshot},
+};
+use serde_json::json;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+const MAGIC: [u8; 4] = [1, 0, 2, 4];
+
+async fn read_message(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut header = [0u8; HEADER_LEN];
+    stream.read_exact(&mut header).await.unwrap();
+    let (code, len) = codec::decode_header(MAGIC, &header, usize::MAX).unwrap();
+    let mut checksum = [0u8; CHECKSUM_LEN];
+    stream.read_exact(&mut checksum).await.unwrap();
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await.unwrap();
+    codec::verify_checksum(&checksum, &body).unwrap();
+    (code, body)
+}
+
+async fn write_message(stream: &mut TcpStream, code: u8, body: &[u8]) {
+    stream
+        .write_all(&codec::encode_frame(MAGIC, code, body))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn frame_checksum_is_verified() {
+    let mut frame = codec::encode_frame(MAGIC, message::INV, b"payload");
+    let header: [u8; HEADER_LEN] = frame[..HEADER_LEN].try_into().unwrap();
+    assert_eq!(codec::decode_header(MAGIC, &header, 1024).unwrap(), (message::INV, 7));
+    assert!(codec::decode_header(Network::Testnet.magic(), &header, 1024).is_err());
+    assert!(codec::decode_header(MAGIC, &header, 6).is_err());
+
+    let checksum = frame[HEADER_LEN..HEADER_LEN + CHECKSUM_LEN]
+        .try_into()
+        .unwrap();
+    let last = frame.len() - 1;
+    frame[last] ^= 1;
+    assert!(codec::verify_checksum(&checksum, &frame[HEADER_LEN + CHECKSUM_LEN..]).is_err());
+}
+
+#[test]
+fn messages_round_trip() {
+    let handshake = Handshake::light("hergmes", "test", TimestampMillis(1730000000000));
+    assert_eq!(Handshake::parse(&handshake.serialize()).unwrap(), handshake);
+
+    let inv = InvData { type_id: 2, ids: vec![Digest([1; 32]), Digest([2; 32])] };
+    assert_eq!(InvData::parse(&inv.serialize()).unwrap(), inv);
+
+    let modifiers = ModifiersData { type_id: 2, modifiers: vec![(Digest([1; 32]), vec![7; 300])] };
+    assert_eq!(ModifiersData::parse(&modifiers.serialize()).unwrap(), modifiers);
+}
+
+#[tokio::test]
+async fn listener_requests_and_forwards_announced_transactions() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tx_id = Digest([9; 32]);
+
+    let peer = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let (code, body) = read_message(&mut stream).await;
+        assert_eq!(code, message::HANDSHAKE);
+        assert_eq!(Handshake::parse(&body).unwrap().agent_name, "hergmes");
+
+        let ours = Handshake::light("ergoref", "peer", TimestampMillis(1730000000000));
+        write_message(&mut stream, message::HANDSHAKE, &ours.serialize()).await;
+
+        let inv = InvData { type_id: message::TRANSACTION_TYPE_ID, ids: vec![tx_id.clone()] };
+        write_message(&mut stream, message::INV, &inv.serialize()).await;
+
+        let (code, body) = read_message(&mut stream).await;
+        assert_eq!(code, message::REQUEST_MODIFIER);
+        assert_eq!(InvData::parse(&body).unwrap(), inv);
+
+        let modifiers = ModifiersData {
+            type_id: message::TRANSACTION_TYPE_ID,
+            modifiers: vec![(tx_id, vec![1, 2, 3])],
+        };
+        write_message(&mut stream, message::MODIFIER, &modifiers.serialize()).await;
+        stream
+    });
+
+    let mut events = p2p::spawn(P2pConfig::new(vec![addr]));
+    let Some(P2pEvent::Announcement { peer: from, ids, .. }) = events.recv().await else {
+        panic!("expected an announcement");
+    };
+    assert_eq!(from, addr);
+    assert_eq!(ids, vec![Digest([9; 32])]);
+
+    let Some(P2pEvent::Transaction { peer: from, id, bytes }) = events.recv().await else {
+        panic!("expected a transaction");
+    };
+    assert_eq!(from, addr);
+    assert_eq!(id, Digest([9; 32]));
+    assert_eq!(bytes, vec![1, 2, 3]);
+
+    drop(peer.await.unwrap());
+}
+
+#[test]
+fn origin_tracker_records_first_announcer_and_delays() {
+    let (a, b, c) = (
+        "10.0.0.1:9030".parse().unwrap(),
+        "10.0.0.2:9030".parse().unwrap(),
+        "10.0.0.3:9030".parse().unwrap(),
+    );
+    let t0 = std::time::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut tracker = OriginTracker::new(2);
+
+    assert!(tracker.record(a, &Digest([1; 32]), t0));
+    assert!(!tracker.record(b, &Digest([1; 32]), t0 + Duration::from_millis(300)));
+    assert!(!tracker.record(b, &Digest([1; 32]), t0 + Duration::from_millis(900)));
+    assert!(tracker.record(b, &Digest([2; 32]), t0));
+    assert!(!tracker.record(a, &Digest([2; 32]), t0 + Duration::from_millis(100)));
+
+    let propagation = tracker.get(&Digest([1; 32])).unwrap();
+    assert_eq!(propagation.first_peer, a);
+    assert_eq!(propagation.later, vec![(b, Duration::from_millis(300))]);
+
+    let timings = tracker.peer_timings();
+    assert_eq!((timings[&a].first, timings[&a].later), (1, 1));
+    assert_eq!(timings[&b].average_delay(), Some(Duration::from_millis(300)));
+
+    tracker.record(c, &Digest([3; 32]), t0);
+    assert!(tracker.get(&Digest([1; 32])).is_none());
+    assert_eq!(tracker.len(), 2);
+}
+
+fn p2pk_tree(key: u8) -> String {
+    format!("0008cd02{}", format!("{key:02x}").repeat(32))
+}
+
+/// Serves the confirmed boxes of the indexer, recording the requested paths.
+#[derive(Debug, Default)]
+struct IndexedBoxes {
+    paths: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl HttpTransport for IndexedBoxes {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        self.paths.lock().unwrap().push(request.path.clone());
+        let id = request.path.rsplit('/').next().unwrap();
+        let body = json!({
+            "boxId": id,
+            "ergoTree": p2pk_tree(7),
+            "creationHeight": 1_000,
+            "value": 3_000_000_000u64,
+            "assets": [],
+            "additionalRegisters": {},
+            "index": 0,
+            "transactionId": "ee".repeat(32),
+            "inclusionHeight": 1_000,
+        });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn relays_peer_transactions_into_the_snapshot() {
+    // A mempool transaction creating the box `ab..` spent by the relayed one.
+    let parent: UnconfirmedTransaction = serde_json::from_value(json!({
+        "id": "dd".repeat(32),
+        "inputs": [],
+        "outputs": [{
+            "boxId": "ab".repeat(32),
+            "ergoTree": p2pk_tree(8),
+            "creationHeight": 1_000,
+            "value": 1_000_000,
+            "assets": [],
+            "index": 0,
+            "transactionId": "dd".repeat(32),
+        }],
+    }))
+    .unwrap();
+    let snapshot =
+        MempoolSnapshot::new(TimestampMillis(42), vec![parent], &ErgoTreeInterner::new());
+    let snapshot = Arc::new(ArcSwap::from_pointee(snapshot));
+
+    let relayed: SignedTransaction = serde_json::from_value(json!({
+        "inputs": [
+            { "boxId": "aa".repeat(32), "spendingProof": { "proofBytes": "", "extension": {} } },
+            { "boxId": "ab".repeat(32), "spendingProof": { "proofBytes": "", "extension": {} } },
+        ],
+        "outputs": [
+            { "ergoTree": p2pk_tree(1), "creationHeight": 1_200_000, "value": 3_001_000_000u64 },
+        ],
+    }))
+    .unwrap();
+    let bytes = transaction::serialize(&relayed).unwrap();
+    let id = transaction::transaction_id(&relayed).unwrap();
+
+    let mock = IndexedBoxes::default();
+    let paths = mock.paths.clone();
+    let clock = ManualClock::new(TimestampMillis(1_000));
+    let node = NodeClient::with_transport(mock).with_clock(Arc::new(clock));
+    let (events, received) = mpsc::channel(4);
+    watcher::spawn_relay(node, snapshot.clone(), received);
+
+    let peer = "10.0.0.1:9030".parse().unwrap();
+    for _ in 0..2 {
+        let event = P2pEvent::Transaction { peer, id: id.clone(), bytes: bytes.clone() };
+        events.send(event).await.unwrap();
+    }
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while !snapshot.load().relayed.contains(&id) {
+        assert!(std::time::Instant::now() < deadline, "transaction not relayed");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let current = snapshot.load();
+    assert_eq!(current.last_update, TimestampMillis(42));
+    assert_eq!(current.first_seen[&id], TimestampMillis(1_000));
+    let tx = &current.transactions[1];
+    assert_eq!(tx.id, id);
+    assert_eq!(tx.inputs[0].utxo.value, 3_000_000_000);
+    assert_eq!(tx.inputs[1].utxo.value, 1_000_000);
+    assert_eq!(tx.outputs[0].transaction_id, id);
+    // The chained input is taken from the snapshot, not the indexer.
+    assert_eq!(*paths.lock().unwrap(), [format!("blockchain/box/byId/{}", "aa".repeat(32))]);
+    assert_eq!(current.transactions.len(), 2);
+}
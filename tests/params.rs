@@ -0,0 +1,39 @@
+use hergmes::{
+    chain::extension::Extension,
+    clients::node::{InfoResponse, SchemaMode},
+    params::NetworkParameters,
+    types::{Digest, Height, HexBytes},
+};
+
+#[test]
+fn reads_parameters_from_info() {
+    let path = format!("{}/tests/fixtures/node-6.0/info.json", env!("CARGO_MANIFEST_DIR"));
+    let info: InfoResponse = SchemaMode::Lenient
+        .decode(&std::fs::read(path).unwrap())
+        .unwrap();
+    let params = NetworkParameters::from(info.parameters.as_ref().unwrap());
+
+    assert_eq!(params.epoch_height, 1_399_808);
+    assert_eq!(params.max_block_cost, 8_001_091);
+    assert_eq!(params.min_box_value(200), 72_000);
+    assert_eq!(params.storage_fee(200), 250_000_000);
+}
+
+#[test]
+fn applies_declared_extension_parameters() {
+    let extension = Extension {
+        header_id: Digest([0; 32]),
+        digest: Digest([0; 32]),
+        fields: vec![
+            (HexBytes(vec![0, 2]), HexBytes(400i32.to_be_bytes().to_vec())),
+            (HexBytes(vec![0, 4]), HexBytes(9_000_000i32.to_be_bytes().to_vec())),
+        ],
+    };
+    let declared = extension.parameters().unwrap();
+    let params = NetworkParameters::default().with_declared(Height(1_024), &declared);
+
+    assert_eq!(params.epoch_height, 1_024);
+    assert_eq!(params.min_value_per_byte, 400);
+    assert_eq!(params.max_block_cost, 9_000_000);
+    assert_eq!(params.storage_fee_factor, NetworkParameters::default().storage_fee_factor);
+}
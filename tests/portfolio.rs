@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::portfolio::{self, AmmPool, PriceFeeds},
+    chain::register::Constant,
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    tokens::TokenRegistry,
+    types::HashDigest,
+};
+use serde_json::{Value, json};
+
+fn id(n: u8) -> HashDigest {
+    format!("{n:02x}").repeat(32).parse().unwrap()
+}
+
+const ORACLE_NFT: u8 = 0x01;
+const POOL_NFT: u8 = 0x02;
+const LP: u8 = 0x03;
+const SIGUSD: u8 = 0x04;
+const UNPRICED: u8 = 0x05;
+
+fn pool_box(nft: u8, value: u64, assets: Value, registers: Value) -> Value {
+    json!({
+        "boxId": id(nft + 0x10).to_string(),
+        "ergoTree": "0008cd",
+        "creationHeight": 1_000,
+        "value": value,
+        "assets": assets,
+        "additionalRegisters": registers,
+        "index": 0,
+        "transactionId": id(0xee).to_string(),
+        "inclusionHeight": 1_000,
+    })
+}
+
+/// An oracle at 2 ERG per USD and a pool of 100 ERG against 50.00 SigUSD.
+#[derive(Debug)]
+struct MarketMock;
+
+#[async_trait]
+impl HttpTransport for MarketMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let body = if request.path == "blockchain/balance" {
+            json!({ "confirmed": {
+                "nanoErgs": 3_000_000_000u64,
+                "tokens": [
+                    { "tokenId": id(UNPRICED), "amount": 7 },
+                    { "tokenId": id(SIGUSD), "amount": 1_000, "decimals": 2, "name": "SigUSD" },
+                ],
+            } })
+        } else {
+            let nft = request
+                .path
+                .strip_prefix("blockchain/box/unspent/byTokenId/")
+                .unwrap();
+            let item = if nft == id(ORACLE_NFT).to_string() {
+                let rate = Constant::Long(2_000_000_000).encode();
+                pool_box(
+                    ORACLE_NFT,
+                    1_000_000,
+                    json!([{ "tokenId": nft, "amount": 1 }]),
+                    json!({ "R4": rate }),
+                )
+            } else {
+                let assets = json!([
+                    { "tokenId": nft, "amount": 1 },
+                    { "tokenId": id(LP), "amount": 1_000_000 },
+                    { "tokenId": id(SIGUSD), "amount": 5_000 },
+                ]);
+                pool_box(POOL_NFT, 100_000_000_000, assets, json!({}))
+            };
+            json!({ "items": [item], "total": 1 })
+        };
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn values_holdings_in_erg_and_usd() {
+    let node = NodeClient::with_transport(MarketMock);
+    let feeds = PriceFeeds { erg_usd_oracle: Some(id(ORACLE_NFT)), amm_pools: vec![id(POOL_NFT)] };
+    let prices = portfolio::fetch_prices(&node, &feeds).await.unwrap();
+    assert_eq!(prices.nano_ergs_per_usd, Some(2_000_000_000));
+    assert_eq!(prices.tokens[&id(SIGUSD)], 20_000_000.0);
+
+    let tokens = TokenRegistry::new();
+    let address = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &[0x02; 33]);
+    let valuation = portfolio::value(&node, &tokens, &prices, &address)
+        .await
+        .unwrap();
+
+    // 10.00 SigUSD at 0.2 ERG per cent is worth 20 ERG, or 10 USD.
+    let sigusd = &valuation.assets[0];
+    assert_eq!(
+        (sigusd.formatted.as_str(), sigusd.nano_ergs),
+        ("10.00 SigUSD", Some(20_000_000_000))
+    );
+    assert_eq!(sigusd.usd, Some(10.0));
+    let unpriced = &valuation.assets[1];
+    assert_eq!((unpriced.nano_ergs, unpriced.usd), (None, None));
+    assert_eq!(valuation.total_nano_ergs, 23_000_000_000);
+    assert_eq!(valuation.total_usd, Some(11.5));
+
+    let json = serde_json::to_value(&valuation).unwrap();
+    assert_eq!(json["assets"][0]["name"], "SigUSD");
+    assert_eq!(json["totalUsd"], 11.5);
+}
+
+#[test]
+fn decodes_amm_pools_by_token_layout() {
+    let with_assets = |assets: Value| {
+        serde_json::from_value(pool_box(POOL_NFT, 1_000, assets, json!({}))).unwrap()
+    };
+    let pool = AmmPool::decode(&with_assets(json!([
+        { "tokenId": id(POOL_NFT), "amount": 1 },
+        { "tokenId": id(LP), "amount": 10 },
+        { "tokenId": id(SIGUSD), "amount": 4 },
+    ])))
+    .unwrap();
+    assert_eq!((&pool.nft, &pool.token_id), (&id(POOL_NFT), &id(SIGUSD)));
+    assert_eq!(pool.price(), 250.0);
+
+    assert!(
+        AmmPool::decode(&with_assets(json!([{ "tokenId": id(POOL_NFT), "amount": 1 }]))).is_none()
+    );
+}
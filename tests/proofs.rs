@@ -0,0 +1,108 @@
+use std::io::Read;
+
+use hergmes::{
+    chain::{
+        schnorr::{self, ProofCheck},
+        transaction,
+    },
+    intern::ErgoTreeInterner,
+    types::{HexBytes, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::{MempoolSnapshot, ProofVerifier},
+};
+use k256::{
+    AffinePoint, ProjectivePoint, Scalar,
+    elliptic_curve::{PrimeField, sec1::ToEncodedPoint},
+};
+
+fn transactions() -> Vec<UnconfirmedTransaction> {
+    let path = format!(
+        "{}/tests/fixtures/responses/unconfirmed_transactions.json.gz",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let mut body = Vec::new();
+    flate2::read::GzDecoder::new(&std::fs::read(path).unwrap()[..])
+        .read_to_end(&mut body)
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn scalar(value: u64) -> Scalar {
+    Scalar::from(value)
+}
+
+fn compressed(point: ProjectivePoint) -> [u8; 33] {
+    AffinePoint::from(point)
+        .to_encoded_point(true)
+        .as_bytes()
+        .try_into()
+        .unwrap()
+}
+
+/// Signs the P2PK input of the first fixture transaction with the secret `secret`.
+fn signed_transaction(secret: u64) -> UnconfirmedTransaction {
+    let mut tx = transactions().remove(0);
+    let public_key = compressed(ProjectivePoint::GENERATOR * scalar(secret));
+    tx.inputs[0].utxo.ergo_tree = HexBytes([&[0x00, 0x08, 0xcd][..], &public_key].concat());
+
+    let message = transaction::unconfirmed_bytes_to_sign(&tx).unwrap();
+    let nonce = scalar(0x5eed);
+    let commitment = compressed(ProjectivePoint::GENERATOR * nonce);
+    let e = schnorr::challenge(&public_key, &commitment, &message);
+    let mut e_repr = [0; 32];
+    e_repr[8..].copy_from_slice(&e);
+    let z = nonce + Scalar::from_repr(e_repr.into()).unwrap() * scalar(secret);
+    tx.inputs[0].spending_proof.proof_bytes = HexBytes([&e[..], &z.to_bytes()[..]].concat());
+    tx
+}
+
+#[test]
+fn verifies_p2pk_proofs() {
+    let tx = signed_transaction(42);
+    assert_eq!(schnorr::check_transaction(&tx).unwrap(), vec![ProofCheck::Valid]);
+
+    let mut tampered = tx.clone();
+    tampered.outputs[0].value += 1;
+    assert_eq!(schnorr::check_transaction(&tampered).unwrap(), vec![ProofCheck::Invalid]);
+
+    let mut forged = tx.clone();
+    forged.inputs[0].spending_proof.proof_bytes.0[30] ^= 1;
+    assert_eq!(schnorr::check_transaction(&forged).unwrap(), vec![ProofCheck::Invalid]);
+
+    let mut truncated = tx;
+    truncated.inputs[0].spending_proof.proof_bytes.0.pop();
+    assert_eq!(schnorr::check_transaction(&truncated).unwrap(), vec![ProofCheck::Invalid]);
+
+    let script = &transactions()[1];
+    assert_eq!(schnorr::check_transaction(script).unwrap(), vec![ProofCheck::Unsupported]);
+}
+
+#[test]
+fn flags_invalid_transactions_of_snapshots() {
+    let interner = ErgoTreeInterner::new();
+    let valid = signed_transaction(7);
+    let mut invalid = signed_transaction(8);
+    invalid.id = "e1".repeat(32).parse().unwrap();
+    invalid.inputs[0].spending_proof.proof_bytes.0[0] ^= 1;
+    let script = transactions().remove(1);
+
+    let mut verifier = ProofVerifier::new();
+    let first = MempoolSnapshot::new(
+        TimestampMillis(1),
+        vec![valid.clone(), invalid.clone(), script],
+        &interner,
+    );
+    let report = verifier.verify_snapshot(&first);
+    assert_eq!(report.invalid.len(), 1);
+    assert_eq!(report.invalid[0].id, invalid.id);
+    assert_eq!((report.valid_inputs, report.unsupported_inputs), (1, 1));
+    assert!(
+        report
+            .to_prometheus()
+            .contains("hergmes_mempool_invalid_proof_transactions 1\n")
+    );
+
+    let next = first.next(TimestampMillis(2), vec![valid], &interner);
+    let report = verifier.verify_snapshot(&next);
+    assert!(report.invalid.is_empty());
+    assert_eq!((report.valid_inputs, report.unsupported_inputs), (1, 0));
+}
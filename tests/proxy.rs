@@ -0,0 +1,147 @@
+use hergmes::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    analytics::proxy::{ProxyContract, ProxyError, ProxyEvent, ProxyOutcome, ProxyTracker},
+    chain::ergo_tree::{self, ErgoTreeParts},
+    types::{
+        HexBytes,
+        ergo::{IndexedTransaction, UTxO},
+    },
+};
+use serde_json::{Value, json};
+
+const BUYER: [u8; 33] = [0x02; 33];
+const OTHER_BUYER: [u8; 33] = [0x03; 33];
+
+/// A proxy contract refundable after a relative height of 720 blocks: an `Int` constant, then
+/// the buyer's key, then a body referring to both.
+fn contract_tree(key: &[u8; 33], has_size: bool) -> Vec<u8> {
+    let constants = [&[0x02, 0x04, 0xa0, 0x0b, 0x08, 0xcd][..], key].concat();
+    let body = [&constants[..], &[0xea, 0x02, 0x73, 0x00, 0x73, 0x01]].concat();
+    if has_size {
+        [&[0x18, body.len() as u8][..], &body].concat()
+    } else {
+        [&[0x10][..], &body].concat()
+    }
+}
+
+fn contract() -> ProxyContract {
+    ProxyContract {
+        name: "Test proxy".into(),
+        ergo_tree: HexBytes(contract_tree(&[0x09; 33], false)),
+        refund_key_constant: 1,
+    }
+}
+
+fn p2pk_tree(key: &[u8; 33]) -> HexBytes {
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, key)
+        .ergo_tree()
+        .unwrap()
+}
+
+fn utxo(id: u8, tree: &HexBytes, value: u64) -> Value {
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1_000_000 + id as u32,
+        "value": value,
+        "assets": [],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn transaction(id: u8, inputs: Vec<Value>, outputs: Vec<Value>) -> IndexedTransaction {
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inclusionHeight": 1_000_100,
+        "inputs": inputs,
+        "outputs": outputs,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn builds_instances_sharing_the_template() {
+    let contract = contract();
+    let tree = contract.build(&BUYER).unwrap();
+    assert_eq!(tree.0, contract_tree(&BUYER, false));
+    assert_eq!(ergo_tree::template_hash(&tree.0).unwrap(), contract.validate().unwrap());
+    assert_eq!(contract.refund_key(&tree.0), Some(BUYER));
+
+    let address = contract.address(NetworkPrefix::Mainnet, &BUYER).unwrap();
+    assert_eq!(address.kind(), AddressType::P2S);
+    assert_eq!(address.ergo_tree(), Some(tree));
+
+    // Trees with a size keep a correct one.
+    let sized = contract_tree(&[0x09; 33], true);
+    let sized = ErgoTreeParts::parse(&sized).unwrap();
+    let rebuilt = sized
+        .with_constant(1, &[[0x08, 0xcd].as_slice(), &BUYER].concat())
+        .unwrap();
+    assert_eq!(rebuilt, contract_tree(&BUYER, true));
+    assert!(sized.with_constant(2, &[0x04, 0x00]).is_err());
+}
+
+#[test]
+fn rejects_contracts_without_a_key_constant() {
+    let contract = ProxyContract { refund_key_constant: 0, ..contract() };
+    assert!(matches!(contract.validate(), Err(ProxyError::RefundKey { index: 0, .. })));
+    let contract = ProxyContract { refund_key_constant: 5, ..self::contract() };
+    assert!(matches!(contract.validate(), Err(ProxyError::RefundKey { index: 5, .. })));
+}
+
+#[test]
+fn tracks_orders_until_executed_or_refunded() {
+    let mut tracker = ProxyTracker::new(NetworkPrefix::Mainnet);
+    tracker.register(contract()).unwrap();
+    let proxy = |key| HexBytes(contract_tree(key, false));
+    let (buyer, other, service) =
+        (p2pk_tree(&BUYER), p2pk_tree(&OTHER_BUYER), HexBytes(vec![0x10, 0x00, 0x7f]));
+
+    let paid = tracker.apply(&transaction(
+        1,
+        vec![utxo(0x10, &buyer, 2_000_000_000), utxo(0x11, &other, 1_000_000_000)],
+        vec![
+            utxo(0x20, &proxy(&BUYER), 1_500_000_000),
+            utxo(0x21, &proxy(&OTHER_BUYER), 900_000_000),
+        ],
+    ));
+    assert_eq!(paid.len(), 2);
+    let buyer_address = ErgoAddress::p2pk(NetworkPrefix::Mainnet, &BUYER);
+    let orders = tracker.orders_by_buyer(&buyer_address);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].value, 1_500_000_000);
+    assert_eq!(orders[0].contract, "Test proxy");
+
+    // The service executes the first order against its own box.
+    let executed = tracker.apply(&transaction(
+        2,
+        vec![utxo(0x20, &proxy(&BUYER), 1_500_000_000), utxo(0x30, &service, 10)],
+        vec![utxo(0x31, &service, 1_400_000_010), utxo(0x32, &buyer, 100_000_000)],
+    ));
+    assert!(matches!(&executed[..], [ProxyEvent::Closed { outcome: ProxyOutcome::Executed, .. }]));
+
+    // The other buyer takes their payment back.
+    let refunded = tracker.apply(&transaction(
+        3,
+        vec![utxo(0x21, &proxy(&OTHER_BUYER), 900_000_000)],
+        vec![utxo(0x40, &other, 899_000_000)],
+    ));
+    match &refunded[..] {
+        [ProxyEvent::Closed { order, outcome, transaction_id }] => {
+            assert_eq!(*outcome, ProxyOutcome::Refunded);
+            assert_eq!(order.buyer, ErgoAddress::p2pk(NetworkPrefix::Mainnet, &OTHER_BUYER));
+            assert_eq!(transaction_id.to_string(), "03".repeat(32));
+        }
+        other => panic!("expected a refund, got {other:?}"),
+    }
+    assert!(tracker.is_empty());
+}
+
+#[test]
+fn ignores_other_contracts() {
+    let mut tracker = ProxyTracker::new(NetworkPrefix::Mainnet);
+    tracker.register(contract()).unwrap();
+    let payment: UTxO = serde_json::from_value(utxo(1, &p2pk_tree(&BUYER), 1)).unwrap();
+    assert!(tracker.recognize(&payment).is_none());
+}
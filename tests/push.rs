@@ -0,0 +1,140 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::{StreamExt, stream};
+use hergmes::{
+    clients::node::{
+        HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError, PushEvent, PushStream,
+        PushTopic, PushTransport, SseDecoder, SseMessage,
+    },
+    types::Height,
+};
+use tokio::time::{Instant, timeout};
+
+#[derive(Debug)]
+struct NoNode;
+
+#[async_trait]
+impl HttpTransport for NoNode {
+    async fn send(&self, _: HttpRequest) -> Result<HttpResponse, NodeError> {
+        Err(NodeError::NotFound("no node".into()))
+    }
+}
+
+/// Hands out the queued subscriptions in order, then refuses to subscribe.
+#[derive(Debug, Default)]
+struct MockPush {
+    subscriptions: Mutex<Vec<Vec<PushEvent>>>,
+    /// Whether a subscription stays open after its events.
+    open: bool,
+    subscribed: Mutex<usize>,
+}
+
+#[async_trait]
+impl PushTransport for MockPush {
+    async fn subscribe(&self) -> Result<PushStream, NodeError> {
+        *self.subscribed.lock().unwrap() += 1;
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.is_empty() {
+            return Err(NodeError::NotFound("push".into()));
+        }
+        let events = stream::iter(subscriptions.remove(0).into_iter().map(Ok));
+        Ok(match self.open {
+            true => Box::pin(events.chain(stream::pending())),
+            false => Box::pin(events),
+        })
+    }
+}
+
+fn message(event: &str, data: &str) -> SseMessage {
+    SseMessage { event: event.into(), data: data.into() }
+}
+
+#[test]
+fn decodes_event_streams_across_chunks() {
+    let mut decoder = SseDecoder::new();
+    assert!(decoder.decode(b": keep-alive\n\nevent: mem").is_empty());
+    assert_eq!(
+        decoder.decode(b"pool\r\ndata: {}\r\n\r\ndata: a\ndata:b\n"),
+        vec![message("mempool", "{}")]
+    );
+    assert_eq!(decoder.decode(b"\n"), vec![message("message", "a\nb")]);
+    // An event without data is not dispatched.
+    assert!(decoder.decode(b"event: block\n\n").is_empty());
+}
+
+#[test]
+fn maps_messages_to_events() {
+    assert_eq!(PushEvent::from_sse(&message("mempool", "")), Some(PushEvent::MempoolChanged));
+    assert_eq!(
+        PushEvent::from_sse(&message("block", r#"{"height": 1500000}"#)),
+        Some(PushEvent::NewBlock { height: Some(Height(1_500_000)) })
+    );
+    let block = PushEvent::from_sse(&message("block", "abc")).unwrap();
+    assert_eq!(block, PushEvent::NewBlock { height: None });
+    assert_eq!(block.topic(), PushTopic::Blocks);
+    assert_eq!(PushEvent::from_sse(&message("message", "hi")), None);
+}
+
+#[tokio::test]
+async fn polls_on_a_timer_without_push() {
+    let node = NodeClient::with_transport(NoNode);
+    let mut wakeup = node.wakeup(PushTopic::Mempool, Duration::from_millis(20));
+    let started = Instant::now();
+    wakeup.wait().await;
+    assert!(started.elapsed() >= Duration::from_millis(20));
+    assert!(!wakeup.is_subscribed());
+}
+
+#[tokio::test]
+async fn wakes_up_on_events_of_its_topic() {
+    let push = Arc::new(MockPush {
+        subscriptions: Mutex::new(vec![vec![
+            PushEvent::NewBlock { height: None },
+            PushEvent::MempoolChanged,
+            PushEvent::MempoolChanged,
+        ]]),
+        open: true,
+        ..Default::default()
+    });
+    let node = NodeClient::with_transport(NoNode).with_push(push.clone());
+    let mut wakeup = node.wakeup(PushTopic::Mempool, Duration::from_secs(60));
+
+    for _ in 0..2 {
+        timeout(Duration::from_secs(5), wakeup.wait())
+            .await
+            .expect("woken up by the pushed event");
+        assert!(wakeup.is_subscribed());
+    }
+    assert_eq!(*push.subscribed.lock().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn falls_back_to_polling_when_the_subscription_drops() {
+    let push = Arc::new(MockPush {
+        subscriptions: Mutex::new(vec![vec![PushEvent::NewBlock { height: None }]]),
+        ..Default::default()
+    });
+    let node = NodeClient::with_transport(NoNode).with_push(push.clone());
+    let interval = Duration::from_millis(20);
+    let mut wakeup = node.wakeup(PushTopic::Blocks, interval);
+
+    timeout(Duration::from_secs(5), wakeup.wait())
+        .await
+        .unwrap();
+    assert!(wakeup.is_subscribed());
+
+    // The stream ended: polls right away, then on the timer until resubscribing.
+    let started = Instant::now();
+    wakeup.wait().await;
+    assert!(!wakeup.is_subscribed());
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let started = Instant::now();
+    wakeup.wait().await;
+    assert!(started.elapsed() >= interval);
+    assert_eq!(*push.subscribed.lock().unwrap(), 1);
+}
@@ -0,0 +1,46 @@
+use hergmes::address::{
+    AddressType, ErgoAddress, NetworkPrefix,
+    qr::{AddressQr, QrError},
+};
+
+fn address() -> ErgoAddress {
+    let mut key = [7; 33];
+    key[0] = 0x02;
+    ErgoAddress::p2pk(NetworkPrefix::Mainnet, &key)
+}
+
+#[test]
+fn renders_address() {
+    let qr = AddressQr::new(&address()).unwrap();
+
+    assert!(qr.to_svg().contains("<svg"));
+    assert!(qr.to_png().unwrap().starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    // Two modules per line plus the quiet zone of 4 on each side.
+    let terminal = qr.to_terminal();
+    let lines: Vec<&str> = terminal.lines().collect();
+    assert_eq!(lines.len(), (qr.width() + 8).div_ceil(2));
+    assert!(lines.iter().all(|l| l.chars().count() == qr.width() + 8));
+}
+
+#[test]
+fn rejects_oversized_scripts() {
+    let script = ErgoAddress::new(NetworkPrefix::Mainnet, AddressType::P2S, vec![1; 4096]).unwrap();
+
+    assert!(matches!(AddressQr::new(&script), Err(QrError::Encode(_))));
+}
+
+#[cfg(feature = "reqwest")]
+#[test]
+fn cli_renders_without_a_node() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hergmes"))
+        .args(["qr", &address().to_string()])
+        // Away from any `.env` naming a node.
+        .current_dir(std::env::temp_dir())
+        .env_remove("ERGO_NODE_URL")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+    assert!(!output.stdout.is_empty());
+}
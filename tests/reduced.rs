@@ -0,0 +1,67 @@
+use hergmes::{
+    chain::{
+        reduced::{ReducedInput, ReducedTransaction},
+        transaction,
+    },
+    types::{HexBytes, ergo::UnsignedTransaction},
+};
+use serde_json::json;
+
+fn unsigned_tx() -> UnsignedTransaction {
+    let output = |value: u64| {
+        json!({
+            "ergoTree": "0008cd",
+            "creationHeight": 1,
+            "value": value,
+            "assets": [{ "tokenId": "cc".repeat(32), "amount": 5 }],
+        })
+    };
+    serde_json::from_value(json!({
+        "inputs": [{ "boxId": "aa".repeat(32), "extension": { "1": "0402" } }],
+        "dataInputs": [{ "boxId": "bb".repeat(32) }],
+        "outputs": [output(1), output(2)],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn serializes_bytes_to_sign() {
+    let bytes = transaction::bytes_to_sign(&unsigned_tx()).unwrap();
+
+    let mut expected = vec![1];
+    expected.extend([0xaa; 32]);
+    expected.extend([0, 1, 1, 0x04, 0x02]);
+    expected.push(1);
+    expected.extend([0xbb; 32]);
+    expected.push(1);
+    expected.extend([0xcc; 32]);
+    expected.push(2);
+    for value in [1, 2] {
+        expected.extend([value, 0x00, 0x08, 0xcd, 1, 1, 0, 5, 0]);
+    }
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn serializes_reduced_transaction() {
+    let unsigned_tx = unsigned_tx();
+    let message = transaction::bytes_to_sign(&unsigned_tx).unwrap();
+    let mut sigma_prop = vec![0xcd];
+    sigma_prop.extend([2; 33]);
+
+    let mut reduced = ReducedTransaction {
+        unsigned_tx,
+        reduced_inputs: vec![ReducedInput { sigma_prop: HexBytes(sigma_prop.clone()), cost: 300 }],
+        cost: 1000,
+    };
+    let bytes = reduced.serialize().unwrap();
+
+    assert_eq!(bytes[0] as usize, message.len());
+    assert_eq!(&bytes[1..=message.len()], &message[..]);
+    let rest = &bytes[message.len() + 1..];
+    assert_eq!(&rest[..34], &sigma_prop[..]);
+    assert_eq!(&rest[34..], &[0xac, 0x02, 0xe8, 0x07]);
+
+    reduced.reduced_inputs.clear();
+    assert!(reduced.serialize().is_err());
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use hergmes::{
+    analytics::rolling::RollingWindow,
+    chain::fee::FEE_ERGO_TREE,
+    intern::ErgoTreeInterner,
+    types::{HexBytes, TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+use serde_json::{Value, json};
+
+const TOKEN_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const TOKEN_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+fn output(tree: &[u8], value: u64, assets: Value) -> Value {
+    json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": HexBytes(tree.to_vec()),
+        "creationHeight": 1_000_000,
+        "value": value,
+        "assets": assets,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+/// A transaction paying `fee`, with each of `tokens` in two outputs.
+fn tx(id: u8, fee: u64, tokens: &[(&str, u64)]) -> UnconfirmedTransaction {
+    let assets: Vec<Value> = tokens
+        .iter()
+        .map(|(id, amount)| json!({ "tokenId": id, "amount": amount }))
+        .collect();
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inputs": [],
+        "outputs": [
+            output(&[0x10, 0x00, 0x7f], 1_000, json!(assets)),
+            output(&[0x10, 0x00, 0x7e], 2_000, json!(assets)),
+            output(&FEE_ERGO_TREE, fee, json!([])),
+        ],
+    }))
+    .unwrap()
+}
+
+fn at(secs: u64) -> TimestampMillis {
+    TimestampMillis::from_secs(secs)
+}
+
+#[test]
+fn aggregates_the_window() {
+    let mut window = RollingWindow::new(Duration::from_secs(60)).with_buckets(6);
+    window.record(at(1_000), &tx(1, 1_000_000, &[(TOKEN_A, 5)]));
+    window.record(at(1_005), &tx(2, 2_000_000, &[(TOKEN_A, 1), (TOKEN_B, 7)]));
+    window.record(at(1_030), &tx(3, 4_000_000, &[]));
+    window.record(at(1_059), &tx(4, 1_000_000, &[(TOKEN_B, 1)]));
+
+    let report = window.report(at(1_059));
+    assert_eq!(report.transactions, 4);
+    assert_eq!(report.transactions_per_sec, 4.0 / 60.0);
+    assert_eq!(report.average_fee, Some(2_000_000.0));
+    assert_eq!(report.median_fee, Some(1_000_000));
+    assert_eq!(report.p99_fee, Some(4_000_000));
+    assert_eq!(report.nano_ergs, 4 * 3_000 + 8_000_000);
+
+    let tokens: Vec<_> = report
+        .tokens
+        .iter()
+        .map(|t| (t.token_id.to_string(), t.amount, t.transactions))
+        .collect();
+    assert_eq!(
+        tokens,
+        [(TOKEN_A.to_string(), 12, 2), (TOKEN_B.to_string(), 16, 2)],
+        "each output is counted, each transaction once"
+    );
+    assert_eq!(window.fee_percentile(at(1_059), 0.0), Some(1_000_000));
+    assert_eq!(window.fee_percentile(at(1_059), 0.75), Some(2_000_000));
+}
+
+#[test]
+fn slides_and_evicts_old_buckets() {
+    let mut window = RollingWindow::new(Duration::from_secs(60)).with_buckets(6);
+    window.record(at(1_000), &tx(1, 1_000_000, &[(TOKEN_A, 5)]));
+    window.record(at(1_030), &tx(2, 3_000_000, &[]));
+
+    let report = window.report(at(1_075));
+    assert_eq!(report.transactions, 1, "the first bucket left the window");
+    assert_eq!(report.median_fee, Some(3_000_000));
+    assert!(report.tokens.is_empty());
+
+    window.record(at(1_095), &tx(3, 2_000_000, &[]));
+    window.record(at(1_020), &tx(4, 9_000_000, &[]));
+    assert_eq!(window.report(at(1_095)).transactions, 2, "late transactions are dropped");
+    assert_eq!(window.report(at(2_000)).transactions, 0);
+    assert_eq!(window.report(at(2_000)).average_fee, None);
+}
+
+#[test]
+fn records_transactions_entering_the_mempool() {
+    let interner = ErgoTreeInterner::new();
+    let first = MempoolSnapshot::new(at(1_000), vec![tx(1, 1_000_000, &[])], &interner);
+    let second = first.next(
+        at(1_010),
+        vec![tx(1, 1_000_000, &[]), tx(2, 2_000_000, &[]), tx(3, 3_000_000, &[])],
+        &interner,
+    );
+
+    let mut window = RollingWindow::new(Duration::from_secs(60));
+    window.apply_snapshot(&first, &second);
+    let report = window.report(at(1_010));
+    assert_eq!(report.transactions, 2);
+    assert_eq!(report.median_fee, Some(2_000_000));
+
+    let metrics = report.to_prometheus();
+    assert!(metrics.contains("hergmes_rolling_fee{quantile=\"0.5\"} 2000000\n"));
+    assert!(metrics.contains("hergmes_rolling_average_fee 2500000\n"));
+}
@@ -0,0 +1,235 @@
+use std::time::{Duration, Instant};
+
+use hergmes::{
+    intern::ErgoTreeInterner,
+    server::{
+        docs,
+        tenants::{self, TenantError, Tenants},
+    },
+    types::{TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+
+#[test]
+fn openapi_documents_enabled_endpoints() {
+    let spec = serde_json::to_value(docs::openapi()).unwrap();
+    let paths = spec["paths"].as_object().unwrap();
+
+    assert!(paths["/health"]["get"]["responses"]["200"].is_object());
+    assert!(paths["/metrics"]["get"].is_object());
+    assert!(paths["/miners"]["get"]["responses"]["404"].is_object());
+    assert!(paths["/rolling"]["get"]["responses"]["404"].is_object());
+    assert!(paths["/portfolio/{address}"]["get"]["responses"]["502"].is_object());
+    assert_eq!(paths["/mempool"]["get"]["parameters"][0]["name"], "filter");
+    assert!(paths["/mempool/treemap"]["get"].is_object());
+    assert!(paths["/tenant/watchlists/{name}"]["put"]["responses"]["429"].is_object());
+    assert!(paths["/tenant/webhook"]["delete"].is_object());
+    assert!(spec["components"]["schemas"]["Health"]["properties"]["mempoolSize"].is_object());
+    assert_eq!(paths.contains_key("/graphql"), cfg!(feature = "graphql"));
+}
+
+fn snapshot(last_update: u64, transactions: Vec<UnconfirmedTransaction>) -> MempoolSnapshot {
+    MempoolSnapshot::new(TimestampMillis(last_update), transactions, &ErgoTreeInterner::new())
+}
+
+fn fixture_transactions() -> Vec<UnconfirmedTransaction> {
+    let path =
+        format!("{}/tests/fixtures/node-5.0/unconfirmed_by_ids.json", env!("CARGO_MANIFEST_DIR"));
+    serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn tenants_are_scoped_by_api_key_and_rate_limited() {
+    let tenants = Tenants::from_json(
+        r#"[
+            { "name": "team-a", "apiKey": "key-a", "requestsPerMinute": 2, "lagPolicy": "disconnect" },
+            { "name": "team-b", "apiKey": "key-b" }
+        ]"#,
+    )
+    .unwrap();
+    let now = Instant::now();
+
+    assert!(matches!(tenants.authorize(None, now), Err(TenantError::Unauthorized)));
+    assert!(matches!(tenants.authorize(Some("nope"), now), Err(TenantError::Unauthorized)));
+    assert_eq!(tenants.authorize(Some("key-a"), now).unwrap().name(), "team-a");
+    tenants
+        .authorize(Some("key-a"), now)
+        .unwrap()
+        .set_watchlist("tokens", format!("token:{}", "cc".repeat(32)).parse().unwrap());
+    assert!(matches!(
+        tenants.authorize(Some("key-a"), now),
+        Err(TenantError::RateLimited(name)) if name == "team-a"
+    ));
+    // One request per 30 seconds is refilled.
+    let later = now + Duration::from_secs(30);
+    assert_eq!(
+        tenants
+            .authorize(Some("key-a"), later)
+            .unwrap()
+            .watchlists()
+            .len(),
+        1
+    );
+
+    let team_b = tenants.authorize(Some("key-b"), now).unwrap();
+    assert!(team_b.watchlists().is_empty());
+    assert!(team_b.filter().is_none());
+
+    let duplicate = r#"[{ "name": "a", "apiKey": "k" }, { "name": "a", "apiKey": "l" }]"#;
+    assert!(
+        matches!(Tenants::from_json(duplicate), Err(TenantError::DuplicateName(name)) if name == "a")
+    );
+    let duplicate = r#"[{ "name": "a", "apiKey": "k" }, { "name": "b", "apiKey": "k" }]"#;
+    assert!(
+        matches!(Tenants::from_json(duplicate), Err(TenantError::DuplicateKey(name)) if name == "b")
+    );
+}
+
+#[test]
+fn tenant_webhooks_reject_internal_hosts() {
+    let tenants = Tenants::from_json(
+        r#"[
+            { "name": "team-a", "apiKey": "key-a" },
+            { "name": "team-b", "apiKey": "key-b", "webhookHosts": ["hooks.internal"] }
+        ]"#,
+    )
+    .unwrap();
+    let now = Instant::now();
+    let mut team_a = tenants.authorize(Some("key-a"), now).unwrap();
+    for url in [
+        "http://169.254.169.254/latest/meta-data",
+        "http://127.0.0.1:9000",
+        "http://localhost/hook",
+        "https://10.0.0.7/hook",
+        "http://192.168.1.1",
+        "http://[::1]:8080/hook",
+        "http://[::ffff:169.254.169.254]/",
+        "http://[fd00::1]/hook",
+        // Decimal, short, hex and octal forms of 127.0.0.1 and 169.254.169.254.
+        "http://2130706433/",
+        "http://127.1/",
+        "http://0x7f000001/",
+        "http://0251.0376.0251.0376/",
+    ] {
+        assert!(
+            matches!(
+                team_a.set_webhook(Some(url.to_string())),
+                Err(TenantError::ForbiddenWebhook(_))
+            ),
+            "{url}"
+        );
+    }
+    // User info or fragments could make the client connect to another host than checked.
+    for url in ["http://hooks.example.com@127.0.0.1/", "http://127.0.0.1#@hooks.example.com"] {
+        assert!(matches!(
+            team_a.set_webhook(Some(url.to_string())),
+            Err(TenantError::InvalidWebhook(_))
+        ));
+    }
+    assert!(team_a.webhook().is_none());
+    team_a
+        .set_webhook(Some("https://Hooks.Example.com:8443/team-a".to_string()))
+        .unwrap();
+    assert_eq!(team_a.webhook(), Some("https://hooks.example.com:8443/team-a"));
+    drop(team_a);
+
+    let mut team_b = tenants.authorize(Some("key-b"), now).unwrap();
+    team_b
+        .set_webhook(Some("http://hooks.internal/team-b".to_string()))
+        .unwrap();
+    assert!(matches!(
+        team_b.set_webhook(Some("https://hooks.example.com".to_string())),
+        Err(TenantError::ForbiddenWebhook(host)) if host == "hooks.example.com"
+    ));
+}
+
+#[test]
+fn tenant_events_only_hold_watched_transactions() {
+    let tenants = Tenants::from_json(
+        r#"[
+            { "name": "team-a", "apiKey": "key-a" },
+            { "name": "team-b", "apiKey": "key-b", "webhookHosts": ["127.0.0.1"] }
+        ]"#,
+    )
+    .unwrap();
+    let now = Instant::now();
+    {
+        let mut team_a = tenants.authorize(Some("key-a"), now).unwrap();
+        assert!(matches!(
+            team_a.set_webhook(Some("hooks.example.com".to_string())),
+            Err(TenantError::InvalidWebhook(_))
+        ));
+        team_a
+            .set_webhook(Some("https://hooks.example.com/team-a".to_string()))
+            .unwrap();
+        team_a.set_watchlist("tokens", format!("token:{}", "cc".repeat(32)).parse().unwrap());
+    }
+    {
+        let mut team_b = tenants.authorize(Some("key-b"), now).unwrap();
+        team_b
+            .set_webhook(Some("http://127.0.0.1:9000".to_string()))
+            .unwrap();
+        team_b.set_watchlist("whales", "min-value:1000000000000".parse().unwrap());
+    }
+
+    let empty = snapshot(1, Vec::new());
+    let full = snapshot(2, fixture_transactions());
+    let events = tenants.events(&empty, &full);
+    assert_eq!(events.len(), 1);
+    let (webhook, event) = &events[0];
+    assert_eq!(webhook, "https://hooks.example.com/team-a");
+    assert_eq!(event.tenant, "team-a");
+    assert_eq!(event.last_update, TimestampMillis(2));
+    assert_eq!(event.added.len(), 1);
+    assert!(event.removed.is_empty());
+
+    let events = tenants.events(&full, &snapshot(3, Vec::new()));
+    assert_eq!(events[0].1.removed, vec![full.transactions[0].id.clone()]);
+    assert!(tenants.events(&full, &full).is_empty());
+    assert!(
+        tenants
+            .to_prometheus()
+            .contains("hergmes_events_dropped_total{channel=\"team-a\"} 0\n")
+    );
+}
+
+#[tokio::test]
+async fn resolves_webhook_names_to_reject_internal_addresses() {
+    assert!(matches!(
+        tenants::resolve_webhook("http://localhost:9000/hook").await,
+        Err(TenantError::ForbiddenWebhook(host)) if host == "localhost"
+    ));
+    tenants::resolve_webhook("http://203.0.113.7/hook")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "reqwest")]
+#[tokio::test]
+async fn webhooks_do_not_follow_redirects() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use axum::{Router, http::StatusCode, routing::post};
+    use hergmes::clients::node::{HttpRequest, HttpTransport, ReqwestTransport};
+
+    let redirected = Arc::new(AtomicBool::new(false));
+    let target = redirected.clone();
+    let app = Router::new()
+        .route("/hook", post(|| async { (StatusCode::FOUND, [("location", "/internal")]) }))
+        .route("/internal", post(move || async move { target.store(true, Ordering::SeqCst) }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = tenants::webhook_client().unwrap();
+    let transport = ReqwestTransport::new(client, &format!("http://{addr}"));
+    let resp = transport
+        .send(HttpRequest::post("hook", b"{}".to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(resp.status, 302);
+    assert!(!redirected.load(Ordering::SeqCst));
+}
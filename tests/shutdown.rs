@@ -0,0 +1,86 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hergmes::{
+    shutdown::{ShutdownCoordinator, Stage, StageReport},
+    supervisor::{RestartPolicy, supervise},
+};
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn runs_stages_in_order() {
+    let shutdown = ShutdownCoordinator::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for (stage, name) in
+        [(Stage::Storage, "cursors"), (Stage::Sinks, "webhook"), (Stage::Intake, "server")]
+    {
+        let signal = shutdown.register(stage, name);
+        let log = log.clone();
+        tokio::spawn(async move {
+            signal.requested().await;
+            // Later stages wait for this one to finish.
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            log.lock().unwrap().push(name);
+            signal.done();
+        });
+    }
+    let poller = tokio::spawn(std::future::pending::<()>());
+    shutdown.abort(Stage::Intake, "watcher", poller.abort_handle());
+
+    assert!(!shutdown.is_started());
+    let reports = shutdown.shutdown().await;
+    assert!(shutdown.is_started());
+    assert_eq!(*log.lock().unwrap(), ["server", "webhook", "cursors"]);
+    assert_eq!(
+        reports[0],
+        StageReport {
+            stage: Stage::Intake,
+            completed: vec!["server".to_string(), "watcher".to_string()],
+            timed_out: vec![],
+        }
+    );
+    assert!(poller.await.unwrap_err().is_cancelled());
+}
+
+#[tokio::test]
+async fn moves_on_after_a_stage_timeout() {
+    let shutdown = ShutdownCoordinator::new().with_timeout(Stage::Sinks, Duration::from_millis(20));
+    let stuck = shutdown.register(Stage::Sinks, "stuck");
+    // Tasks that are already gone are done.
+    drop(shutdown.register(Stage::Sinks, "gone"));
+    let storage = shutdown.register(Stage::Storage, "store");
+    let flushed = tokio::spawn(async move {
+        storage.requested().await;
+        storage.done();
+    });
+
+    let reports = shutdown.shutdown().await;
+    assert_eq!(reports[1].completed, ["gone"]);
+    assert_eq!(reports[1].timed_out, ["stuck"]);
+    assert_eq!(reports[2].completed, ["store"]);
+    assert!(stuck.is_requested());
+    flushed.await.unwrap();
+}
+
+#[tokio::test]
+async fn aborting_a_supervisor_stops_its_task() {
+    let (started, running) = oneshot::channel();
+    let started = Arc::new(Mutex::new(Some(started)));
+    let (held, stopped) = oneshot::channel::<()>();
+    let held = Arc::new(Mutex::new(Some(held)));
+    let handle = supervise("poller", RestartPolicy::default(), move || {
+        let (started, held) = (started.lock().unwrap().take(), held.lock().unwrap().take());
+        async move {
+            let _held = held;
+            started.unwrap().send(()).unwrap();
+            std::future::pending().await
+        }
+    });
+
+    running.await.unwrap();
+    handle.abort();
+    // The task's end drops what it held.
+    assert!(stopped.await.is_err());
+}
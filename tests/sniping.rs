@@ -0,0 +1,97 @@
+use hergmes::{
+    analytics::sniping::{self, ConflictKind, PlannedTransaction, RiskLevel},
+    chain::fee::FEE_ERGO_TREE,
+    types::{
+        Height, HexBytes,
+        ergo::{BoxCandidate, UTxO, UnconfirmedTransaction},
+    },
+};
+use serde_json::json;
+
+const POOL_TREE: &str = "101f0400";
+
+fn p2pk() -> String {
+    format!("0008cd02{}", "ab".repeat(32))
+}
+
+fn utxo_json(id: u8, tree: &str) -> serde_json::Value {
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1_400_000,
+        "value": 1_000_000_000u64,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    })
+}
+
+fn mempool_tx(id: u8, inputs: &[(u8, &str)], fee: u64) -> UnconfirmedTransaction {
+    let inputs: Vec<_> = inputs
+        .iter()
+        .map(|(box_id, tree)| {
+            let mut input = utxo_json(*box_id, tree);
+            input["spendingProof"] = json!({ "proofBytes": "" });
+            input
+        })
+        .collect();
+    let mut fee_output = utxo_json(0xfe, &hex::encode(&*FEE_ERGO_TREE));
+    fee_output["value"] = fee.into();
+
+    serde_json::from_value(json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inputs": inputs,
+        "outputs": [fee_output],
+    }))
+    .unwrap()
+}
+
+fn planned(fee: u64) -> PlannedTransaction {
+    let input = |id, tree: &str| -> UTxO { serde_json::from_value(utxo_json(id, tree)).unwrap() };
+    PlannedTransaction {
+        inputs: vec![input(1, POOL_TREE), input(2, &p2pk())],
+        outputs: vec![BoxCandidate {
+            ergo_tree: HexBytes(FEE_ERGO_TREE.clone()),
+            creation_height: Height(1_400_000),
+            value: fee,
+            tokens: vec![],
+            registers: Default::default(),
+        }],
+    }
+}
+
+#[test]
+fn reports_double_spends_and_contract_contention() {
+    let planned = planned(2_000_000);
+    let mempool = vec![
+        mempool_tx(0xa1, &[(2, &p2pk())], 1_000_000),
+        mempool_tx(0xa2, &[(3, POOL_TREE)], 1_000_000),
+        mempool_tx(0xa3, &[(4, &p2pk())], 9_000_000),
+    ];
+
+    let report = sniping::analyze(&planned, &mempool).unwrap();
+    assert_eq!(report.planned_fee, 2_000_000);
+    assert_eq!(report.conflicts.len(), 2);
+    assert!(matches!(report.conflicts[0].kind, ConflictKind::DoubleSpend { .. }));
+    assert!(matches!(report.conflicts[1].kind, ConflictKind::SharedContract { .. }));
+    assert_eq!(report.level(), RiskLevel::High);
+    assert_eq!(report.highest_competing_fee(), Some(1_000_000));
+}
+
+#[test]
+fn ranks_contract_contention_by_fee() {
+    let mempool = vec![mempool_tx(0xa2, &[(3, POOL_TREE)], 1_000_000)];
+
+    assert_eq!(
+        sniping::analyze(&planned(2_000_000), &mempool)
+            .unwrap()
+            .level(),
+        RiskLevel::Low
+    );
+    assert_eq!(
+        sniping::analyze(&planned(1_000_000), &mempool)
+            .unwrap()
+            .level(),
+        RiskLevel::Medium
+    );
+    assert_eq!(sniping::analyze(&planned(1_000_000), &[]).unwrap().level(), RiskLevel::None);
+}
@@ -0,0 +1,60 @@
+use hergmes::{
+    chain::ergo_box,
+    params::NetworkParameters,
+    storage_rent::{self, STORAGE_PERIOD},
+    types::{Height, ergo::UTxO},
+};
+use serde_json::json;
+
+fn utxo(value: u64, creation_height: u32, registers: serde_json::Value) -> UTxO {
+    serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": format!("0008cd02{}", "ab".repeat(32)),
+        "creationHeight": creation_height,
+        "value": value,
+        "assets": [{ "tokenId": "11".repeat(32), "amount": 1000 }],
+        "additionalRegisters": registers,
+        "index": 1,
+        "transactionId": "22".repeat(32),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn serializes_boxes() {
+    let utxo = utxo(1_000_000_000, 500_000, json!({ "R4": "0e0101", "R5": "0402" }));
+    let bytes = ergo_box::serialize(&utxo).unwrap();
+
+    // value(5) + tree(36) + height(3) + tokens(1 + 32 + 2) + registers(1 + 3 + 2) + tx id(32) + index(1)
+    assert_eq!(bytes.len(), 118);
+    assert_eq!(&bytes[..5], &[0x80, 0x94, 0xeb, 0xdc, 0x03]);
+    assert_ne!(ergo_box::compute_id(&utxo).unwrap(), utxo.id);
+
+    let sparse = self::utxo(1, 1, json!({ "R5": "0402" }));
+    assert!(ergo_box::serialize(&sparse).is_err());
+}
+
+#[test]
+fn finds_and_collects_rent() {
+    let params = NetworkParameters::default();
+    let height = 2_000_000;
+    let boxes = vec![
+        utxo(1_000_000_000, height - STORAGE_PERIOD, json!({})),
+        utxo(1_000_000, height - STORAGE_PERIOD - 10, json!({})),
+        utxo(1_000_000_000, height - STORAGE_PERIOD + 1, json!({})),
+    ];
+
+    let claims = storage_rent::find_claimable(&boxes, Height(height), &params).unwrap();
+    assert_eq!(claims.len(), 2);
+    assert_eq!(claims[0].claimable, params.storage_fee(claims[0].size));
+    assert!(!claims[0].is_consumed());
+    assert!(claims[1].is_consumed());
+
+    let collection = storage_rent::build_collection(&claims, Height(height));
+    assert_eq!(collection.collected, claims[0].claimable + 1_000_000);
+    assert_eq!(collection.outputs.len(), 1);
+    assert_eq!(collection.outputs[0].creation_height, height);
+    assert_eq!(collection.outputs[0].value, 1_000_000_000 - claims[0].claimable);
+    assert_eq!(collection.inputs[0].recreated_index, Some(0));
+    assert_eq!(collection.inputs[1].recreated_index, None);
+}
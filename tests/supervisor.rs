@@ -0,0 +1,84 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use hergmes::{
+    clock::ManualClock,
+    error::AppError,
+    supervisor::{RestartPolicy, supervise, supervise_with_clock},
+    types::TimestampMillis,
+};
+
+const POLICY: RestartPolicy = RestartPolicy {
+    max_restarts: 2,
+    window: Duration::from_secs(60),
+    backoff: Duration::from_millis(1),
+};
+
+#[tokio::test]
+async fn restarts_panicking_tasks() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let counter = runs.clone();
+    let handle = supervise("flaky", POLICY, move || {
+        let runs = counter.clone();
+        async move {
+            if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                panic!("boom");
+            }
+            Ok(())
+        }
+    });
+
+    handle.await.unwrap().unwrap();
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_too_many_restarts() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let counter = runs.clone();
+    let handle = supervise("broken", POLICY, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        async { Err(AppError::Usage("bad input".to_string())) }
+    });
+
+    let result = handle.await.unwrap();
+    assert!(matches!(
+        result,
+        Err(AppError::TaskFailed { task: "broken", failure }) if failure.contains("bad input")
+    ));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn backs_off_on_the_supervisor_clock() {
+    let clock = ManualClock::new(TimestampMillis(0));
+    let policy = RestartPolicy { backoff: Duration::from_secs(20), ..POLICY };
+    let handle = supervise_with_clock("slow", policy, Arc::new(clock.clone()), || async {
+        Err(AppError::Usage("bad input".to_string()))
+    });
+
+    // Two restarts 20s apart fit in the window, so the third failure gives up.
+    assert!(handle.await.unwrap().is_err());
+    assert_eq!(clock.elapsed(), Duration::from_secs(40));
+
+    // Failures spread beyond the window are restarted indefinitely.
+    let policy = RestartPolicy { backoff: Duration::from_secs(61), ..POLICY };
+    let runs = Arc::new(AtomicU32::new(0));
+    let counter = runs.clone();
+    let handle = supervise_with_clock("sparse", policy, Arc::new(clock.clone()), move || {
+        let runs = counter.clone();
+        async move {
+            match runs.fetch_add(1, Ordering::SeqCst) {
+                0..10 => Err(AppError::Usage("bad input".to_string())),
+                _ => Ok(()),
+            }
+        }
+    });
+    handle.await.unwrap().unwrap();
+    assert_eq!(runs.load(Ordering::SeqCst), 11);
+}
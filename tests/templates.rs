@@ -0,0 +1,101 @@
+use hergmes::{
+    chain::{
+        ergo_tree::{self, ErgoTreeParts},
+        fee::FEE_ERGO_TREE,
+    },
+    templates::{BUILTIN, ContractTemplate, TemplateError, TemplateRegistry},
+    types::ergo::UnconfirmedTransaction,
+};
+use serde_json::json;
+
+/// A segregated tree with an Int, a SigmaProp, a Coll[Byte] and a (Long, Coll[Int]) constant.
+fn tree(int: u8, key: u8) -> Vec<u8> {
+    let mut tree = vec![0x10, 0x04];
+    tree.extend([0x04, int]);
+    tree.extend([0x08, 0xcd, 0x02]);
+    tree.extend([key; 32]);
+    tree.extend([0x0e, 0x02, 0xab, 0xcd]);
+    tree.extend([0x3c + 5, 0x10, 0x0a, 0x02, 0x02, 0x04]);
+    tree.extend(hex::decode("d1937300730173027303").unwrap());
+    tree
+}
+
+fn template(name: &str) -> ContractTemplate {
+    ContractTemplate { name: name.into(), dapp: "Test".into(), description: None }
+}
+
+#[test]
+fn splits_segregated_constants() {
+    let tree = tree(0x04, 0xaa);
+    let parts = ErgoTreeParts::parse(&tree).unwrap();
+
+    assert!(parts.header.constant_segregation);
+    assert_eq!(parts.constants_count, 4);
+    assert_eq!(hex::encode(parts.template), "d1937300730173027303");
+
+    let fee = ErgoTreeParts::parse(&FEE_ERGO_TREE).unwrap();
+    assert_eq!(fee.constants_count, 5);
+    assert!(hex::encode(fee.template).starts_with("d19683030193"));
+}
+
+#[test]
+fn template_hash_ignores_constants() {
+    let hash = ergo_tree::template_hash(&tree(0x04, 0xaa)).unwrap();
+    assert_eq!(hash, ergo_tree::template_hash(&tree(0x06, 0xbb)).unwrap());
+
+    let mut sized = tree(0x04, 0xaa);
+    let size = sized.len() as u8 - 1;
+    sized[0] |= 0x08;
+    sized.insert(1, size);
+    assert_eq!(ergo_tree::template_hash(&sized).unwrap(), hash);
+
+    sized[1] += 1;
+    assert!(ergo_tree::template_hash(&sized).is_err());
+}
+
+#[test]
+fn classifies_boxes_and_transactions() {
+    let mut registry = BUILTIN.clone();
+    registry
+        .register_tree(&tree(0x04, 0xaa), template("Pool"))
+        .unwrap();
+
+    let utxo = |tree: &[u8], id: u8| {
+        json!({
+            "boxId": format!("{id:02x}").repeat(32),
+            "ergoTree": hex::encode(tree),
+            "creationHeight": 1_000_000,
+            "value": 1_000_000,
+            "index": 0,
+            "transactionId": "ee".repeat(32),
+        })
+    };
+    let mut input = utxo(&tree(0x08, 0xcc), 1);
+    input["spendingProof"] = json!({ "proofBytes": "" });
+    let tx: UnconfirmedTransaction = serde_json::from_value(json!({
+        "id": "ff".repeat(32),
+        "inputs": [input],
+        "outputs": [utxo(&FEE_ERGO_TREE, 2)],
+    }))
+    .unwrap();
+
+    assert_eq!(registry.classify_box(&tx.inputs[0].utxo).unwrap().name, "Pool");
+    assert_eq!(
+        registry
+            .classify_transaction(&tx)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        ["Ergo", "Test"]
+    );
+}
+
+#[test]
+fn loads_templates_from_json() {
+    let hash = ergo_tree::template_hash(&tree(0x04, 0xaa)).unwrap();
+    let json = json!([{ "templateHash": hash, "name": "Pool", "dapp": "Test" }]).to_string();
+    let registry = TemplateRegistry::from_json(&json).unwrap();
+    assert_eq!(registry.get(&hash), Some(&template("Pool")));
+
+    let json = json!([{ "ergoTree": "10", "name": "Broken", "dapp": "Test" }]).to_string();
+    assert!(matches!(TemplateRegistry::from_json(&json), Err(TemplateError::ErgoTree(..))));
+}
@@ -0,0 +1,45 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use hergmes::types::{Height, TimestampMillis};
+
+#[test]
+fn serializes_as_plain_numbers() {
+    let json = serde_json::json!([1_400_000, 1_730_000_000_123u64]);
+    let (height, timestamp): (Height, TimestampMillis) =
+        serde_json::from_value(json.clone()).unwrap();
+
+    assert_eq!((height, timestamp), (Height(1_400_000), TimestampMillis(1_730_000_000_123)));
+    assert_eq!(serde_json::to_value((height, timestamp)).unwrap(), json);
+    assert_eq!(format!("{height} {height:?}"), "1400000 1400000");
+    assert_eq!("1400000".parse::<Height>().unwrap(), height);
+}
+
+#[test]
+fn heights_count_blocks() {
+    let height = Height(100);
+
+    assert_eq!(height.next(), Height(101));
+    assert_eq!(height + 20, Height(120));
+    assert_eq!(height - 20, Height(80));
+    assert_eq!(height.blocks_since(Height(40)), 60);
+    assert_eq!(height.blocks_since(Height(140)), 0);
+    assert_eq!(height.checked_sub(101), None);
+    assert_eq!(height.saturating_sub(101), Height(0));
+
+    let heights: Vec<u32> = Height(3).up_to(Height(5)).map(u32::from).collect();
+    assert_eq!(heights, [3, 4, 5]);
+    assert_eq!(Height(5).up_to(Height(4)).count(), 0);
+}
+
+#[test]
+fn timestamps_measure_durations() {
+    let timestamp = TimestampMillis::from_secs(1_730_000_000);
+
+    assert_eq!(timestamp.as_secs(), 1_730_000_000);
+    assert_eq!(timestamp + Duration::from_millis(1_500), TimestampMillis(1_730_000_001_500));
+    assert_eq!((timestamp + Duration::from_secs(90)).since(timestamp), Duration::from_secs(90));
+    assert_eq!(timestamp.since(timestamp + Duration::from_secs(1)), Duration::ZERO);
+    assert_eq!(TimestampMillis(10).checked_sub(Duration::from_millis(11)), None);
+    assert_eq!(TimestampMillis::from(timestamp.to_system_time()), timestamp);
+    assert_eq!(TimestampMillis::from(UNIX_EPOCH - Duration::from_secs(1)), TimestampMillis(0));
+}
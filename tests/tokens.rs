@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use hergmes::{
+    clients::node::{
+        HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError, TokenBalance,
+    },
+    tokens::{self, TokenMetadata, TokenRegistry},
+    types::HashDigest,
+};
+use serde_json::json;
+
+fn token(n: u8) -> HashDigest {
+    format!("{n:02x}").repeat(32).parse().unwrap()
+}
+
+/// Serves the indexer's metadata of every token.
+#[derive(Debug)]
+struct TokenMock;
+
+#[async_trait]
+impl HttpTransport for TokenMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let id = request.path.strip_prefix("blockchain/token/byId/").unwrap();
+        let body = json!({
+            "id": id,
+            "boxId": "ff".repeat(32),
+            "emissionAmount": 1_000_000,
+            "name": "Node name",
+            "decimals": 3,
+        });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[test]
+fn formats_units_with_decimals() {
+    assert_eq!(tokens::format_units(1_250, 2), "12.50");
+    assert_eq!(tokens::format_units(5, 3), "0.005");
+    assert_eq!(tokens::format_units(-1_000_000_001, 9), "-1.000000001");
+    assert_eq!(tokens::format_units(42, 0), "42");
+    assert_eq!(tokens::format_units(7, 60), format!("0.{}7", "0".repeat(59)));
+}
+
+#[test]
+fn falls_back_to_raw_amounts_of_short_ids() {
+    let registry = TokenRegistry::from_json(&format!(
+        r#"[{{ "tokenId": "{}", "name": "SigUSD", "decimals": 2 }}, {{ "tokenId": "{}", "decimals": 4 }}]"#,
+        token(1),
+        token(2),
+    ))
+    .unwrap();
+
+    assert_eq!(registry.format_token_amount(&token(1), 1_250), "12.50 SigUSD");
+    assert_eq!(registry.format_token_amount(&token(2), 1_250), "0.1250 02020202");
+    assert_eq!(registry.format_token_amount(&token(3), 1_250), "1250 03030303");
+    assert_eq!(registry.format_units(&token(3), 1_250), "1250");
+}
+
+#[tokio::test]
+async fn caches_node_metadata_below_overrides() {
+    let node = NodeClient::with_transport(TokenMock);
+    let mut registry = TokenRegistry::new();
+    registry.insert(token(1), TokenMetadata { name: Some("Override".into()), decimals: 1 });
+
+    assert_eq!(registry.resolve(&node, &token(1)).await.unwrap().decimals, 1);
+    let resolved = registry.resolve(&node, &token(2)).await.unwrap();
+    assert_eq!(resolved, TokenMetadata { name: Some("Node name".into()), decimals: 3 });
+    registry.resolve(&node, &token(2)).await.unwrap();
+    assert_eq!(registry.format_token_amount(&token(2), 1_500), "1.500 Node name");
+
+    registry.remember_balances(&[
+        TokenBalance { token_id: token(1), amount: 1, decimals: Some(6), name: None },
+        TokenBalance { token_id: token(4), amount: 1, decimals: Some(6), name: None },
+    ]);
+    assert_eq!(registry.get(&token(1)).unwrap().decimals, 1);
+    assert_eq!(registry.get(&token(4)).unwrap().decimals, 6);
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use hergmes::trace::ErrorLog;
+
+#[test]
+fn counts_repeated_errors_until_recovery() {
+    let mut errors = ErrorLog::new("poller");
+    assert!(!errors.is_failing());
+
+    errors.failure(&"connection refused");
+    assert!(errors.is_failing());
+    assert_eq!(errors.suppressed(), 0);
+
+    errors.failure(&"connection refused");
+    errors.failure(&"connection refused");
+    assert_eq!(errors.failures(), 3);
+    assert_eq!(errors.suppressed(), 2);
+
+    // A different error is logged right away, and a new count starts.
+    errors.failure(&"timed out");
+    assert_eq!(errors.suppressed(), 0);
+    errors.failure(&"timed out");
+    assert_eq!(errors.suppressed(), 1);
+
+    errors.success();
+    assert!(!errors.is_failing());
+    assert_eq!(errors.failures(), 0);
+    assert_eq!(errors.suppressed(), 0);
+}
+
+#[test]
+fn summarizes_once_per_interval() {
+    let mut errors = ErrorLog::new("poller").with_summary_interval(Duration::ZERO);
+    errors.failure(&"connection refused");
+    errors.failure(&"connection refused");
+    assert_eq!(errors.suppressed(), 0);
+    assert_eq!(errors.failures(), 2);
+}
@@ -0,0 +1,210 @@
+use hergmes::{
+    chain::{
+        ergo_box,
+        fee::{FEE_ERGO_TREE, fee_per_byte},
+        transaction,
+    },
+    intern::ErgoTreeInterner,
+    types::{
+        TimestampMillis,
+        ergo::{SignedTransaction, UTxO, UnconfirmedTransaction, UnsignedTransaction},
+    },
+    watcher::MempoolSnapshot,
+};
+use serde_json::json;
+
+fn p2pk_tree(key: u8) -> String {
+    format!("0008cd02{}", format!("{key:02x}").repeat(32))
+}
+
+fn signed_tx() -> SignedTransaction {
+    serde_json::from_value(json!({
+        "inputs": [
+            {
+                "boxId": "aa".repeat(32),
+                "spendingProof": { "proofBytes": "ab".repeat(56), "extension": { "1": "0402", "0": "0e0101" } },
+            },
+            { "boxId": "ab".repeat(32), "spendingProof": { "proofBytes": "", "extension": {} } },
+        ],
+        "dataInputs": [{ "boxId": "bb".repeat(32) }],
+        "outputs": [
+            {
+                "ergoTree": p2pk_tree(1),
+                "creationHeight": 1_200_000,
+                "value": 1_000_000_000u64,
+                "assets": [{ "tokenId": "cc".repeat(32), "amount": 5 }],
+                "additionalRegisters": { "R4": "0580897a", "R5": "1a020101020203" },
+            },
+            {
+                // Sized tree with a segregated constant
+                "ergoTree": "1806010402d17300",
+                "creationHeight": 1_200_000,
+                "value": 2_000_000,
+                "assets": [
+                    { "tokenId": "dd".repeat(32), "amount": 1 },
+                    { "tokenId": "cc".repeat(32), "amount": 7 },
+                ],
+            },
+            {
+                "ergoTree": hex::encode(&*FEE_ERGO_TREE),
+                "creationHeight": 1_200_000,
+                "value": 1_100_000,
+            },
+        ],
+    }))
+    .unwrap()
+}
+
+#[test]
+fn binary_round_trip() {
+    let tx = signed_tx();
+    let bytes = transaction::serialize(&tx).unwrap();
+    let parsed = transaction::parse(&bytes).unwrap();
+
+    assert_eq!(transaction::serialize(&parsed).unwrap(), bytes);
+    let mut expected = serde_json::to_value(&tx).unwrap();
+    expected["id"] = json!(transaction::transaction_id(&tx).unwrap().to_string());
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), expected);
+
+    assert!(transaction::parse(&bytes[..bytes.len() - 1]).is_err());
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(transaction::parse(&trailing).is_err());
+}
+
+#[test]
+fn id_ignores_proofs() {
+    let tx = signed_tx();
+    let unsigned: UnsignedTransaction = serde_json::from_value(json!({
+        "inputs": [
+            { "boxId": "aa".repeat(32), "extension": { "0": "0e0101", "1": "0402" } },
+            { "boxId": "ab".repeat(32) },
+        ],
+        "dataInputs": tx.data_inputs,
+        "outputs": tx.outputs,
+    }))
+    .unwrap();
+    let message = transaction::bytes_to_sign(&unsigned).unwrap();
+
+    let id = transaction::transaction_id(&tx).unwrap();
+    assert_eq!(id.0, blake2b256(&message));
+}
+
+#[test]
+fn fee_per_byte_uses_serialized_size() {
+    let tx = signed_tx();
+    let size = transaction::serialize(&tx).unwrap().len();
+    assert_eq!(fee_per_byte(&tx).unwrap(), 1_100_000.0 / size as f64);
+}
+
+/// `signed_tx` as the node's mempool returns it, with its inputs resolved to boxes.
+fn unconfirmed_tx() -> UnconfirmedTransaction {
+    let tx = signed_tx();
+    let inputs: Vec<_> = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            json!({
+                "boxId": input.box_id,
+                "ergoTree": p2pk_tree(9),
+                "creationHeight": 1_100_000,
+                "value": 600_000_000,
+                "index": 0,
+                "transactionId": "ee".repeat(32),
+                "spendingProof": input.spending_proof,
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "id": transaction::transaction_id(&tx).unwrap(),
+        "inputs": inputs,
+        "dataInputs": tx.data_inputs,
+        "outputs": transaction::output_boxes(&tx).unwrap(),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn sizes_mempool_transactions_as_serialized() {
+    let unconfirmed = unconfirmed_tx();
+    let size = transaction::serialize(&signed_tx()).unwrap().len();
+    assert_eq!(transaction::unconfirmed_size(&unconfirmed).unwrap(), size);
+
+    let interner = ErgoTreeInterner::new();
+    let snapshot = MempoolSnapshot::new(TimestampMillis(1), vec![unconfirmed.clone()], &interner);
+    assert_eq!(snapshot.size_of(&unconfirmed.id), Some(size));
+    assert_eq!(snapshot.total_bytes, size as u64);
+    assert_eq!(snapshot.fee_per_byte(&unconfirmed), Some(1_100_000.0 / size as f64));
+
+    // Sizes carry over to the next snapshot.
+    let next = snapshot.next(TimestampMillis(2), vec![unconfirmed.clone()], &interner);
+    assert_eq!(next.total_bytes, size as u64);
+    assert_eq!(MempoolSnapshot::default().total_bytes, 0);
+}
+
+#[test]
+fn box_round_trip() {
+    let utxo: UTxO = serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": p2pk_tree(2),
+        "creationHeight": 1_000,
+        "value": 1_000_000,
+        "assets": [{ "tokenId": "cc".repeat(32), "amount": 1 }],
+        "additionalRegisters": { "R4": "0402" },
+        "index": 300,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap();
+
+    let bytes = ergo_box::serialize(&utxo).unwrap();
+    let parsed = ergo_box::parse(&bytes).unwrap();
+    assert_eq!(parsed.id, ergo_box::compute_id(&utxo).unwrap());
+    assert_eq!(parsed.index, 300);
+    assert_eq!(ergo_box::serialize(&parsed).unwrap(), bytes);
+}
+
+#[test]
+fn walks_unsized_tree_bodies() {
+    let trees = [
+        // sigmaProp(HEIGHT > 100) && pk, with segregated constants
+        format!("100204c80108cd02{}ea02d191a373007301", "03".repeat(32)),
+        // OUTPUTS.exists { (b: Box) => b.propositionBytes == script }
+        "10010e02abcdd1aea5d901016393c272017300".to_owned(),
+        // { val h = HEIGHT; sigmaProp(h >= 10 && true) }, without segregated constants
+        "00d801d601a3d1ed92720104147f".to_owned(),
+    ];
+    for tree in trees {
+        let utxo: UTxO = serde_json::from_value(json!({
+            "boxId": "00".repeat(32),
+            "ergoTree": tree,
+            "creationHeight": 1_000,
+            "value": 1_000_000,
+            "additionalRegisters": { "R4": "0402" },
+            "index": 0,
+            "transactionId": "ee".repeat(32),
+        }))
+        .unwrap();
+
+        let bytes = ergo_box::serialize(&utxo).unwrap();
+        let parsed = ergo_box::parse(&bytes).unwrap();
+        assert_eq!(ergo_box::serialize(&parsed).unwrap(), bytes, "{tree}");
+    }
+
+    // Option constructors cannot appear in serialized trees.
+    let utxo: UTxO = serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": "00d1df",
+        "creationHeight": 1_000,
+        "value": 1_000_000,
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap();
+    let err = ergo_box::parse(&ergo_box::serialize(&utxo).unwrap()).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported ErgoTree opcode");
+}
+
+fn blake2b256(data: &[u8]) -> [u8; 32] {
+    use blake2::{Blake2b, Digest, digest::consts::U32};
+    Blake2b::<U32>::digest(data).into()
+}
@@ -0,0 +1,590 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use hergmes::{
+    clients::node::{
+        BoxQuery, CacheConfig, Capability, HttpRequest, HttpResponse, HttpTransport, IndexPolicy,
+        MempoolPoll, NodeClient, NodeError, NodeVersion, ReplayTransport, ResponseLimits,
+        SortOrder, TransactionCheck,
+    },
+    clock::ManualClock,
+    types::{HashDigest, TimestampMillis, ergo::SignedTransaction},
+};
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read fixture `{path}`: {e}"))
+}
+
+fn gzip_fixture(name: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    flate2::read::GzDecoder::new(&fixture(name)[..])
+        .read_to_end(&mut body)
+        .unwrap();
+    body
+}
+
+/// Serves canned responses keyed by request path.
+#[derive(Debug, Default)]
+struct MockTransport {
+    responses: HashMap<String, (u16, Vec<u8>)>,
+}
+
+impl MockTransport {
+    fn respond(self, path: &str, body: Vec<u8>) -> Self {
+        self.respond_with_status(path, 200, body)
+    }
+
+    fn respond_with_status(mut self, path: &str, status: u16, body: Vec<u8>) -> Self {
+        self.responses.insert(path.to_string(), (status, body));
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let (status, body) = self
+            .responses
+            .get(&request.path)
+            .cloned()
+            .unwrap_or((200, Vec::new()));
+        Ok(HttpResponse { status, body: body.into() })
+    }
+}
+
+/// Fails the first request of each listed path with a transport error.
+#[derive(Debug)]
+struct FlakyTransport {
+    inner: MockTransport,
+    failing: Mutex<Vec<String>>,
+    requests: Arc<Mutex<usize>>,
+}
+
+#[async_trait]
+impl HttpTransport for FlakyTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        *self.requests.lock().unwrap() += 1;
+        let failed = {
+            let mut failing = self.failing.lock().unwrap();
+            let index = failing.iter().position(|p| *p == request.path);
+            index.map(|i| failing.swap_remove(i)).is_some()
+        };
+        if failed {
+            let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+            return Err(NodeError::transport(reset));
+        }
+        self.inner.send(request).await
+    }
+}
+
+#[tokio::test]
+async fn node_client_uses_injected_transport() {
+    let transport = MockTransport::default()
+        .respond("info", fixture("node-5.0/info.json"))
+        .respond("blockchain/indexedHeight", fixture("node-6.0/indexed_height.json"));
+    let node = NodeClient::with_transport(transport);
+
+    assert_eq!(node.get_last_mempool_update_timestamp().await.unwrap(), 1730000000000);
+    assert!(matches!(node.check_node_index_status().await, Err(NodeError::NotIndexed(_))));
+}
+
+#[tokio::test]
+async fn degrades_without_blockchain_index() {
+    let box_id = "ab".repeat(32);
+    let utxo = format!(
+        r#"{{"boxId": "{box_id}", "ergoTree": "0008cd", "creationHeight": 1, "value": 1000000,
+            "index": 0, "transactionId": "{}"}}"#,
+        "cd".repeat(32)
+    );
+    let transport = || {
+        MockTransport::default()
+            .respond_with_status("blockchain/indexedHeight", 404, br#"{"error": 404}"#.to_vec())
+            .respond(&format!("utxo/byId/{box_id}"), utxo.clone().into_bytes())
+    };
+
+    let strict = NodeClient::with_transport(transport());
+    assert!(matches!(strict.check_node_index_status().await, Err(NodeError::IndexUnavailable)));
+
+    let node = NodeClient::with_transport(transport()).with_index_policy(IndexPolicy::Degrade);
+    node.check_node_index_status().await.unwrap();
+    assert!(node.index_unavailable());
+    let token = node.get_token(&"ef".repeat(32).parse().unwrap()).await;
+    assert!(matches!(token, Err(NodeError::IndexUnavailable)));
+    let found = node.get_box(&box_id.parse().unwrap()).await.unwrap();
+    assert_eq!(found.value, 1000000);
+}
+
+#[tokio::test]
+async fn rejects_oversized_responses() {
+    let transport = MockTransport::default()
+        .respond("info", fixture("node-5.0/info.json"))
+        .respond("transactions/unconfirmed", b"[]".to_vec());
+    let limits = ResponseLimits { default: 16, mempool: 1024 };
+    let node = NodeClient::with_transport(transport).with_response_limits(limits);
+
+    let info = node.get_last_mempool_update_timestamp().await;
+    assert!(matches!(info, Err(NodeError::ResponseTooLarge { limit: 16 })));
+    assert!(node.get_mempool_snapshot().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn records_latency_by_endpoint() {
+    let body = gzip_fixture("responses/token.json.gz");
+    let ids = ["aa", "bb"].map(|b| b.repeat(32).parse::<HashDigest>().unwrap());
+    let transport = ids.iter().fold(MockTransport::default(), |mock, id| {
+        mock.respond(&format!("blockchain/token/byId/{id}"), body.clone())
+    });
+    let node = NodeClient::with_transport(transport);
+    for id in &ids {
+        node.get_token(id).await.unwrap();
+    }
+    node.get_info().await.unwrap_err();
+
+    let tokens = node
+        .metrics()
+        .histogram("blockchain/token/byId/{id}")
+        .unwrap();
+    assert_eq!(tokens.count, 2);
+    assert_eq!(tokens.buckets.iter().sum::<u64>(), 2);
+    assert_eq!(node.metrics().histogram("info").unwrap().count, 1);
+    let text = node.metrics().to_prometheus();
+    assert!(text.contains(
+        "hergmes_node_request_duration_seconds_count{endpoint=\"blockchain/token/byId/{id}\"} 2"
+    ));
+}
+
+/// Answers every request with an empty page, recording paths and queries.
+#[derive(Debug, Default)]
+struct RecordingTransport {
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl HttpTransport for RecordingTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        self.requests.lock().unwrap().push(request.path_and_query());
+        Ok(HttpResponse { status: 200, body: br#"{"items": [], "total": 0}"#.to_vec().into() })
+    }
+}
+
+#[tokio::test]
+async fn box_queries_send_typed_params() {
+    let transport = RecordingTransport::default();
+    let requests = transport.requests.clone();
+    let node = NodeClient::with_transport(transport);
+    let token: HashDigest = "aa".repeat(32).parse().unwrap();
+
+    let query = BoxQuery::new()
+        .sort(SortOrder::Asc)
+        .exclude_mempool_spent(true)
+        .offset(20)
+        .limit(10);
+    node.get_unspent_boxes_by_token_id(&token, &query)
+        .await
+        .unwrap();
+    node.get_boxes_by_token_id(&token, &query).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0],
+        format!(
+            "blockchain/box/unspent/byTokenId/{token}?offset=20&limit=10&sortDirection=asc\
+             &includeUnconfirmed=false&excludeMempoolSpent=true"
+        )
+    );
+    // Queries over spent boxes only page.
+    assert_eq!(requests[1], format!("blockchain/box/byTokenId/{token}?offset=20&limit=10"));
+}
+
+#[tokio::test]
+async fn replays_recorded_responses() {
+    let dir = std::env::temp_dir().join(format!("hergmes-replay-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("transactions")).unwrap();
+    std::fs::write(dir.join("info.json"), fixture("node-5.0/info.json")).unwrap();
+    std::fs::write(dir.join("transactions/unconfirmed.json"), b"[]").unwrap();
+    let mempool = gzip_fixture("responses/unconfirmed_transactions.json.gz");
+    std::fs::write(dir.join("transactions/unconfirmed.1.json"), mempool).unwrap();
+
+    let node = NodeClient::with_transport(ReplayTransport::new(&dir));
+    assert_eq!(node.get_last_mempool_update_timestamp().await.unwrap(), 1730000000000);
+    // Recordings are served in turn, the last one repeating.
+    assert!(node.get_mempool_snapshot().await.unwrap().is_empty());
+    assert_eq!(node.get_mempool_snapshot().await.unwrap().len(), 2);
+    assert_eq!(node.get_mempool_snapshot().await.unwrap().len(), 2);
+    // Without a recording of the indexer, the node looks unindexed.
+    assert!(matches!(node.get_indexed_height().await, Err(NodeError::IndexUnavailable)));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn check_transaction_reports_validity() {
+    let tx: SignedTransaction = serde_json::from_str(&format!(
+        r#"{{"inputs": [{{"boxId": "{}", "spendingProof": {{"proofBytes": "", "extension": {{}}}}}}],
+            "outputs": [{{"ergoTree": "0008cd", "creationHeight": 1, "value": 1000000}}]}}"#,
+        "aa".repeat(32)
+    ))
+    .unwrap();
+    let id = format!("\"{}\"", "dd".repeat(32));
+
+    let valid = MockTransport::default().respond("transactions/check", id.into_bytes());
+    let check = NodeClient::with_transport(valid)
+        .check_transaction(&tx)
+        .await
+        .unwrap();
+    assert!(matches!(check, TransactionCheck::Valid { cost: None, .. }));
+
+    let with_cost = format!(r#"{{"id": "{}", "cost": 12345}}"#, "dd".repeat(32));
+    let valid = MockTransport::default().respond("transactions/check", with_cost.into_bytes());
+    let check = NodeClient::with_transport(valid)
+        .check_transaction(&tx)
+        .await
+        .unwrap();
+    assert!(matches!(check, TransactionCheck::Valid { cost: Some(12345), .. }));
+
+    let error = br#"{"error": 400, "reason": "bad.request", "detail": "Script reduced to false"}"#;
+    let invalid =
+        MockTransport::default().respond_with_status("transactions/check", 400, error.to_vec());
+    match NodeClient::with_transport(invalid)
+        .check_transaction(&tx)
+        .await
+        .unwrap()
+    {
+        TransactionCheck::Invalid(error) => {
+            assert_eq!(error.detail.as_deref(), Some("Script reduced to false"))
+        }
+        other => panic!("expected Invalid, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn get_boxes_by_ids_retries_transient_failures() {
+    let indexed_box = gzip_fixture("responses/box.json.gz");
+    let ids: Vec<HashDigest> = (1..=20u8)
+        .map(|i| format!("{i:02x}").repeat(32).parse().unwrap())
+        .collect();
+    let path = |id: &HashDigest| format!("blockchain/box/byId/{id}");
+
+    // Every box but the last exists; a few lookups fail once before succeeding.
+    let inner = ids[..19]
+        .iter()
+        .fold(MockTransport::default(), |mock, id| mock.respond(&path(id), indexed_box.clone()));
+    let failing = ids[..3].iter().map(path).collect();
+    let requests = Arc::new(Mutex::new(0));
+    let transport =
+        FlakyTransport { inner, failing: Mutex::new(failing), requests: requests.clone() };
+    let node = NodeClient::with_transport(transport);
+
+    let mut duplicated = ids.clone();
+    duplicated.push(ids[0].clone());
+    let boxes = node.get_boxes_by_ids(&duplicated).await;
+
+    assert_eq!(boxes.len(), ids.len());
+    assert!(ids[..19].iter().all(|id| boxes[id].is_ok()));
+    assert!(matches!(boxes[&ids[19]], Err(NodeError::Decode(_))));
+    assert_eq!(*requests.lock().unwrap(), ids.len() + 3);
+}
+
+#[tokio::test]
+async fn mempool_poll_skips_identical_bodies() {
+    let path = "transactions/unconfirmed";
+    let body = gzip_fixture("responses/unconfirmed_transactions.json.gz");
+    let node = NodeClient::with_transport(MockTransport::default().respond(path, body.clone()));
+
+    let MempoolPoll::Changed { body_hash, transactions } =
+        node.get_mempool_snapshot_if_changed(None).await.unwrap()
+    else {
+        panic!("first poll must decode");
+    };
+    assert!(!transactions.is_empty());
+    let poll = node.get_mempool_snapshot_if_changed(Some(&body_hash)).await;
+    assert!(matches!(poll, Ok(MempoolPoll::Unchanged)));
+
+    let node = NodeClient::with_transport(MockTransport::default().respond(path, b"[]".to_vec()));
+    let poll = node.get_mempool_snapshot_if_changed(Some(&body_hash)).await;
+    assert!(
+        matches!(poll, Ok(MempoolPoll::Changed { transactions, .. }) if transactions.is_empty())
+    );
+}
+
+/// Serves `transactions/unconfirmed` by offset and limit, recording the requested offsets.
+/// With `first_leaves`, the first transaction leaves the mempool after the first page.
+#[derive(Debug)]
+struct PagedMempool {
+    transactions: Vec<serde_json::Value>,
+    offsets: Arc<Mutex<Vec<usize>>>,
+    first_leaves: bool,
+}
+
+#[async_trait]
+impl HttpTransport for PagedMempool {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let param = |key: &str| -> usize {
+            let (_, value) = request.query.iter().find(|(k, _)| k == key).unwrap();
+            value.parse().unwrap()
+        };
+        let (offset, limit) = (param("offset"), param("limit"));
+        let mut offsets = self.offsets.lock().unwrap();
+        offsets.push(offset);
+        let left = usize::from(self.first_leaves && offsets.len() > 1);
+        let page = self
+            .transactions
+            .iter()
+            .skip(left + offset)
+            .take(limit)
+            .collect::<Vec<_>>();
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&page).unwrap().into() })
+    }
+}
+
+fn paged_transactions(count: u32) -> Vec<serde_json::Value> {
+    let body = gzip_fixture("responses/unconfirmed_transactions.json.gz");
+    let template = serde_json::from_slice::<Vec<serde_json::Value>>(&body).unwrap()[0].clone();
+    (0..count)
+        .map(|i| {
+            let mut tx = template.clone();
+            tx["id"] = format!("{i:064x}").into();
+            tx
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn mempool_is_fetched_in_pages() {
+    let transactions = paged_transactions(2_500);
+    let offsets = Arc::new(Mutex::new(Vec::new()));
+    let node = NodeClient::with_transport(PagedMempool {
+        transactions,
+        offsets: offsets.clone(),
+        first_leaves: false,
+    });
+
+    let snapshot = node.get_mempool_snapshot().await.unwrap();
+    assert_eq!(snapshot.len(), 2_500);
+    // A second pass confirms the mempool did not change while it was paged.
+    assert_eq!(*offsets.lock().unwrap(), [0, 1_000, 2_000, 0, 1_000, 2_000]);
+
+    let pages = node.mempool_pages().collect::<Vec<_>>().await;
+    let sizes = pages
+        .into_iter()
+        .map(|p| p.unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [1_000, 1_000, 500]);
+}
+
+#[tokio::test]
+async fn mempool_passes_catch_transactions_skipped_by_paging() {
+    let transactions = paged_transactions(1_500);
+    let skipped = transactions[1_000]["id"].clone();
+    let offsets = Arc::new(Mutex::new(Vec::new()));
+    let node = NodeClient::with_transport(PagedMempool {
+        transactions,
+        offsets: offsets.clone(),
+        first_leaves: true,
+    });
+
+    // The first pass skips the transaction moving from the second page to the first, and the
+    // second one no longer sees the transaction that left.
+    let snapshot = node.get_mempool_snapshot().await.unwrap();
+    assert!(snapshot.iter().any(|tx| tx.id.to_string() == skipped));
+    assert_eq!(snapshot.len(), 1_500);
+    assert_eq!(*offsets.lock().unwrap(), [0, 1_000, 0, 1_000]);
+}
+
+#[tokio::test]
+async fn caches_immutable_responses() {
+    let token: HashDigest = "aa".repeat(32).parse().unwrap();
+    let other: HashDigest = "bb".repeat(32).parse().unwrap();
+    let body = gzip_fixture("responses/token.json.gz");
+    let inner = MockTransport::default()
+        .respond(&format!("blockchain/token/byId/{token}"), body.clone())
+        .respond(&format!("blockchain/token/byId/{other}"), body);
+    let requests = Arc::new(Mutex::new(0));
+    let transport = FlakyTransport { inner, failing: Mutex::default(), requests: requests.clone() };
+    let config = CacheConfig { ttl: Duration::from_millis(200), capacity: 1 };
+    let clock = ManualClock::new(TimestampMillis(0));
+    let node = NodeClient::with_transport(transport)
+        .with_cache(config)
+        .with_clock(Arc::new(clock.clone()));
+
+    node.get_token(&token).await.unwrap();
+    node.clone().get_token(&token).await.unwrap();
+    assert_eq!(*requests.lock().unwrap(), 1);
+
+    // Evicts the oldest entry when full.
+    node.get_token(&other).await.unwrap();
+    node.get_token(&token).await.unwrap();
+    assert_eq!(*requests.lock().unwrap(), 3);
+
+    clock.advance(Duration::from_millis(199));
+    node.get_token(&token).await.unwrap();
+    assert_eq!(*requests.lock().unwrap(), 3);
+    clock.advance(Duration::from_millis(1));
+    node.get_token(&token).await.unwrap();
+    assert_eq!(*requests.lock().unwrap(), 4);
+
+    // Mutable data is never cached.
+    node.get_info().await.ok();
+    node.get_info().await.ok();
+    assert_eq!(*requests.lock().unwrap(), 6);
+}
+
+#[cfg(feature = "unix-socket")]
+#[tokio::test]
+async fn unix_socket_transport_round_trip() {
+    use hergmes::clients::node::UnixSocketTransport;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixListener,
+    };
+
+    let dir = std::env::temp_dir().join(format!("hergmes-transport-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let socket_path = dir.join("node.sock");
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path).unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0u8; 1024];
+        let n = stream.read(&mut buf).await.unwrap();
+        let request_line = String::from_utf8_lossy(&buf[..n])
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+
+        let body = fixture("node-5.0/info.json");
+        let head = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n", body.len());
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(&body).await.unwrap();
+        request_line
+    });
+
+    let node = NodeClient::with_transport(UnixSocketTransport::new(&socket_path));
+    assert_eq!(node.get_last_mempool_update_timestamp().await.unwrap(), 1730000000000);
+    assert_eq!(server.await.unwrap(), "GET /info HTTP/1.1");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn detects_capabilities() {
+    let html = b"<html><body>Not Found</body></html>".to_vec();
+    let node = NodeClient::with_transport(
+        MockTransport::default()
+            .respond("info", fixture("node-6.0/info.json"))
+            .respond_with_status("wallet/status", 404, html),
+    );
+    assert!(node.capabilities().is_none());
+
+    let capabilities = node.detect_capabilities().await.unwrap();
+    assert_eq!(capabilities.version, Some(NodeVersion::new(6, 0, 0)));
+    assert!(!capabilities.extra_index && !capabilities.wallet);
+    assert_eq!(node.capabilities(), Some(capabilities));
+
+    assert!(node.index_unavailable());
+    let token = node.get_token(&"ef".repeat(32).parse().unwrap()).await;
+    assert!(matches!(token, Err(NodeError::IndexUnavailable)));
+    assert!(matches!(
+        node.require(Capability::Wallet),
+        Err(NodeError::Unsupported { capability: Capability::Wallet, version: Some(_) })
+    ));
+}
+
+#[tokio::test]
+async fn probes_apis_not_reported_by_old_nodes() {
+    let node = NodeClient::with_transport(
+        MockTransport::default()
+            .respond("info", fixture("node-5.0/info.json"))
+            .respond("blockchain/indexedHeight", fixture("node-5.0/indexed_height.json")),
+    );
+
+    let capabilities = node.detect_capabilities().await.unwrap();
+    assert_eq!(capabilities.version, None);
+    assert!(capabilities.extra_index && capabilities.wallet);
+    assert!(!node.index_unavailable());
+    node.require(Capability::ExtraIndex).unwrap();
+}
+
+#[tokio::test]
+async fn reports_html_not_found_pages_by_endpoint() {
+    let html = || b"<html><body>Not Found</body></html>".to_vec();
+    let node = NodeClient::with_transport(
+        MockTransport::default()
+            .respond_with_status(&format!("blockchain/token/byId/{}", "ef".repeat(32)), 404, html())
+            .respond_with_status("blocks/lastHeaders/1", 404, html()),
+    );
+
+    let token = node.get_token(&"ef".repeat(32).parse().unwrap()).await;
+    assert!(matches!(token, Err(NodeError::IndexUnavailable)));
+    let headers = node.get_last_n_headers(1).await;
+    assert!(matches!(headers, Err(NodeError::NotFound(path)) if path == "blocks/lastHeaders/1"));
+}
+
+/// Delays every response of the inner transport.
+#[derive(Debug)]
+struct SlowTransport {
+    inner: MockTransport,
+    delay: Duration,
+}
+
+#[async_trait]
+impl HttpTransport for SlowTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        tokio::time::sleep(self.delay).await;
+        self.inner.send(request).await
+    }
+}
+
+#[tokio::test]
+async fn swaps_node_for_all_clones_after_draining() {
+    let old = SlowTransport {
+        inner: MockTransport::default().respond("info", fixture("node-5.0/info.json")),
+        delay: Duration::from_millis(100),
+    };
+    let node = NodeClient::with_transport(old);
+    let watcher = node.clone();
+    let in_flight = tokio::spawn(async move { watcher.get_last_mempool_update_timestamp().await });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let broken = NodeClient::with_transport(MockTransport::default());
+    assert!(
+        node.swap_node(&broken, Duration::from_secs(5))
+            .await
+            .is_err()
+    );
+    assert!(node.capabilities().is_none());
+
+    let new = NodeClient::with_transport(
+        MockTransport::default()
+            .respond("info", fixture("node-6.0/info.json"))
+            .respond_with_status("wallet/status", 404, Vec::new()),
+    );
+    let capabilities = node.swap_node(&new, Duration::from_secs(5)).await.unwrap();
+    assert!(in_flight.is_finished());
+    assert_eq!(in_flight.await.unwrap().unwrap(), 1730000000000);
+
+    assert_eq!(capabilities.version, Some(NodeVersion::new(6, 0, 0)));
+    assert!(node.index_unavailable());
+    let clone = node.clone();
+    assert_eq!(clone.capabilities(), Some(capabilities));
+    assert!(matches!(clone.require(Capability::Wallet), Err(NodeError::Unsupported { .. })));
+}
+
+#[test]
+fn parses_node_versions() {
+    assert_eq!("5.0.22".parse(), Ok(NodeVersion::new(5, 0, 22)));
+    assert_eq!("v6.0.0-RC1".parse(), Ok(NodeVersion::new(6, 0, 0)));
+    assert_eq!("4.0".parse(), Ok(NodeVersion::new(4, 0, 0)));
+    assert!("5.x.1".parse::<NodeVersion>().is_err());
+    assert!(NodeVersion::new(5, 0, 22) < NodeVersion::new(6, 0, 0));
+    assert_eq!(NodeVersion::new(6, 0, 1).to_string(), "6.0.1");
+}
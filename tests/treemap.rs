@@ -0,0 +1,85 @@
+use hergmes::{
+    address::{ErgoAddress, NetworkPrefix},
+    analytics::treemap::{TreemapBuilder, UNLABELLED},
+    chain::fee::FEE_ERGO_TREE,
+    intern::ErgoTreeInterner,
+    labels::{Label, LabelCategory, LabelSet},
+    templates,
+    types::{TimestampMillis, ergo::UnconfirmedTransaction},
+    watcher::MempoolSnapshot,
+};
+use serde_json::json;
+
+fn p2pk_tree(key: u8) -> String {
+    format!("0008cd02{}", format!("{key:02x}").repeat(32))
+}
+
+fn tx(id: u8, outputs: &[(String, u64)]) -> UnconfirmedTransaction {
+    let outputs: Vec<_> = outputs
+        .iter()
+        .enumerate()
+        .map(|(index, (tree, value))| {
+            json!({
+                "boxId": format!("{:02x}", id * 16 + index as u8).repeat(32),
+                "ergoTree": tree,
+                "creationHeight": 1,
+                "value": value,
+                "index": index,
+                "transactionId": format!("{id:02x}").repeat(32),
+            })
+        })
+        .collect();
+    serde_json::from_value(
+        json!({ "id": format!("{id:02x}").repeat(32), "inputs": [], "outputs": outputs }),
+    )
+    .unwrap()
+}
+
+#[test]
+fn groups_pending_value_by_contract() {
+    let mut labels = LabelSet::new();
+    let exchange = hex::decode(p2pk_tree(1)).unwrap();
+    labels.insert(
+        &ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &exchange),
+        Label { name: "Exchange A".into(), category: LabelCategory::Exchange, url: None },
+    );
+    let fee = hex::encode(&*FEE_ERGO_TREE);
+    let snapshot = MempoolSnapshot::new(
+        TimestampMillis(1),
+        vec![
+            tx(1, &[(p2pk_tree(1), 5_000), (fee.clone(), 100)]),
+            tx(2, &[(p2pk_tree(2), 700), (p2pk_tree(3), 300), (fee, 100)]),
+            tx(3, &[("1004".into(), 50)]),
+        ],
+        &ErgoTreeInterner::new(),
+    );
+
+    let mut builder = TreemapBuilder::new(&templates::BUILTIN, &labels);
+    builder.add_mempool(&snapshot);
+    let root = serde_json::to_value(builder.build("Mempool")).unwrap();
+
+    assert_eq!(
+        root,
+        json!({
+            "name": "Mempool",
+            "value": 6_250,
+            "children": [
+                { "name": "Exchanges", "value": 5_000, "children": [{ "name": "Exchange A", "value": 5_000 }] },
+                {
+                    "name": UNLABELLED,
+                    "value": 1_050,
+                    "children": [{ "name": "P2PK", "value": 1_000 }, { "name": "P2S", "value": 50 }],
+                },
+                { "name": "Ergo", "value": 200, "children": [{ "name": "Miner fee", "value": 200 }] },
+            ],
+        })
+    );
+}
+
+#[test]
+fn empty_mempool_has_no_children() {
+    let labels = LabelSet::new();
+    let root = TreemapBuilder::new(&templates::BUILTIN, &labels).build("Mempool");
+    assert_eq!(root.value, 0);
+    assert!(root.children.is_empty());
+}
@@ -0,0 +1,134 @@
+use blake2::{Blake2b, Digest as _, digest::consts::U32};
+use hergmes::{
+    chain::{avl::AvlError, ergo_box},
+    types::{
+        Digest, HashDigest,
+        ergo::{Block, BlockHeader, BlockTransaction, BlockTransactions, MinimalInput, UTxO},
+    },
+    utxo::{UtxoError, UtxoSet},
+};
+use serde_json::json;
+
+const LEAF: u8 = 2;
+const END_OF_TREE: u8 = 4;
+
+fn hash(parts: &[&[u8]]) -> [u8; 32] {
+    Blake2b::<U32>::digest(parts.concat()).into()
+}
+
+fn utxo(tx: u8, value: u64) -> UTxO {
+    let mut utxo: UTxO = serde_json::from_value(json!({
+        "boxId": "00".repeat(32),
+        "ergoTree": format!("0008cd02{}", "01".repeat(32)),
+        "creationHeight": 1_000,
+        "value": value,
+        "index": 0,
+        "transactionId": format!("{tx:02x}").repeat(32),
+    }))
+    .unwrap();
+    utxo.id = ergo_box::compute_id(&utxo).unwrap();
+    utxo
+}
+
+/// Full tree over the sentinel leaf and `boxes`, as a packaged dump and its root label.
+fn dump(boxes: &[UTxO]) -> (Vec<u8>, [u8; 32]) {
+    let mut leaves: Vec<([u8; 32], Vec<u8>)> = boxes
+        .iter()
+        .map(|b| (b.id.0, ergo_box::serialize(b).unwrap()))
+        .collect();
+    leaves.sort();
+    leaves.insert(0, ([0; 32], Vec::new()));
+
+    // Left-leaning tree: ((sentinel, a), b)
+    let mut bytes = vec![LEAF];
+    bytes.extend(leaves[0].0);
+    let mut labels = Vec::new();
+    for (i, (key, value)) in leaves.iter().enumerate() {
+        let next_key = leaves.get(i + 1).map_or([0xff; 32], |(k, _)| *k);
+        if i > 0 {
+            bytes.push(LEAF);
+        }
+        bytes.extend(next_key);
+        bytes.extend((value.len() as u32).to_be_bytes());
+        bytes.extend(value);
+        labels.push(hash(&[&[0], key, value, &next_key]));
+        if i == 1 {
+            bytes.push(0);
+        }
+    }
+    bytes.push(0xff);
+    bytes.push(END_OF_TREE);
+
+    let left = hash(&[&[1, 0], &labels[0], &labels[1]]);
+    (bytes, hash(&[&[1, 0xff], &left, &labels[2]]))
+}
+
+fn checkpoint(root: [u8; 32]) -> BlockHeader {
+    let path = format!("{}/tests/fixtures/node-6.0/last_headers.json", env!("CARGO_MANIFEST_DIR"));
+    let headers: Vec<BlockHeader> =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let mut header = headers.into_iter().next().unwrap();
+    let mut state_root = root.to_vec();
+    state_root.push(2);
+    header.state_root = Digest(state_root.try_into().unwrap());
+    header
+}
+
+fn next_block(parent: &BlockHeader, spent: &HashDigest, created: UTxO) -> Block {
+    let mut header = parent.clone();
+    header.parent_id = parent.id.clone();
+    header.id = Digest([0x77; 32]);
+    header.height += 1;
+    Block {
+        header,
+        transactions: BlockTransactions {
+            header_id: Digest([0x77; 32]),
+            transactions: vec![BlockTransaction {
+                id: created.transaction_id.clone(),
+                inputs: vec![MinimalInput { id: spent.clone() }],
+                outputs: vec![created],
+            }],
+        },
+        extension: None,
+    }
+}
+
+#[test]
+fn bootstraps_from_verified_snapshot() {
+    let boxes = [utxo(1, 1_000_000), utxo(2, 2_000_000)];
+    let (bytes, root) = dump(&boxes);
+    let header = checkpoint(root);
+
+    let mut set = UtxoSet::from_snapshot(&header, &bytes).unwrap();
+    assert_eq!((set.len(), set.height()), (2, header.height));
+    assert_eq!(set.get(&boxes[1].id).unwrap().value, 2_000_000);
+
+    let created = utxo(3, 3_000_000);
+    set.apply_block(&next_block(&header, &boxes[0].id, created.clone()))
+        .unwrap();
+    assert!(set.get(&boxes[0].id).is_none());
+    assert!(set.get(&created.id).is_some());
+    assert_eq!(set.height(), header.height + 1);
+
+    // Not a child of the new tip
+    let stale = next_block(&header, &boxes[1].id, utxo(4, 1));
+    assert!(matches!(set.apply_block(&stale), Err(UtxoError::NotNextBlock { .. })));
+}
+
+#[test]
+fn rejects_tampered_snapshots() {
+    let boxes = [utxo(1, 1_000_000), utxo(2, 2_000_000)];
+    let (mut bytes, root) = dump(&boxes);
+    let header = checkpoint(root);
+
+    let wrong_root = checkpoint([0x42; 32]);
+    assert!(matches!(
+        UtxoSet::from_snapshot(&wrong_root, &bytes),
+        Err(UtxoError::Avl(AvlError::RootMismatch))
+    ));
+
+    // Changes the value of a box: the label no longer matches.
+    let value_at = bytes.len() - 20;
+    bytes[value_at] ^= 1;
+    assert!(UtxoSet::from_snapshot(&header, &bytes).is_err());
+}
@@ -0,0 +1,55 @@
+use hergmes::{
+    chain::{
+        fee::{FEE_ERGO_TREE, outputs_fee},
+        value::{self, ValueError},
+    },
+    types::ergo::UTxO,
+};
+use serde_json::json;
+
+fn utxo(ergo_tree: &str, value: u64) -> UTxO {
+    serde_json::from_value(json!({
+        "boxId": "01".repeat(32),
+        "ergoTree": ergo_tree,
+        "creationHeight": 1,
+        "value": value,
+        "assets": [],
+        "index": 0,
+        "transactionId": "ee".repeat(32),
+    }))
+    .unwrap()
+}
+
+#[test]
+fn sums_beyond_u64() {
+    let values = [u64::MAX, u64::MAX, 2];
+    assert_eq!(value::total(values), 2 * u128::from(u64::MAX) + 2);
+    assert_eq!(
+        value::checked_total(values),
+        Err(ValueError::Overflow(2 * u128::from(u64::MAX) + 2))
+    );
+    assert_eq!(value::checked_total([u64::MAX - 1, 1]), Ok(u64::MAX));
+    assert_eq!(value::checked_total([]), Ok(0));
+}
+
+#[test]
+fn deltas_are_signed() {
+    let inputs = [utxo("0008cd", u64::MAX), utxo("0008cd", u64::MAX)];
+    let outputs = [utxo("0008cd", 1)];
+
+    let delta = value::value_delta(&inputs, &outputs);
+    assert_eq!(delta, 1 - 2 * i128::from(u64::MAX));
+    assert_eq!(value::value_delta(&outputs, &inputs), -delta);
+    assert_eq!(value::to_i64(delta), Err(ValueError::OutOfRange(delta)));
+    assert_eq!(value::to_i64(-5), Ok(-5));
+}
+
+#[test]
+fn overflowing_fees_are_errors() {
+    let fee_tree = hex::encode(&*FEE_ERGO_TREE);
+    let outputs = [utxo(&fee_tree, u64::MAX), utxo(&fee_tree, 1), utxo("0008cd", u64::MAX)];
+
+    assert_eq!(outputs_fee(&outputs[..1]), Ok(u64::MAX));
+    assert_eq!(outputs_fee(&outputs[1..]), Ok(1));
+    assert_eq!(outputs_fee(&outputs), Err(ValueError::Overflow(u128::from(u64::MAX) + 1)));
+}
@@ -0,0 +1,85 @@
+//! Checks the codecs against `tests/fixtures/vectors.json`, the corpus shared with reference
+//! implementations. Run with `UPDATE_VECTORS=1` to regenerate it after adding vectors.
+
+use std::{fs, path::PathBuf};
+
+use hergmes::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    chain::vectors::{TestVectors, VectorError},
+    types::ergo::SignedTransaction,
+};
+use serde_json::json;
+
+fn corpus_path() -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", "vectors.json"]
+        .iter()
+        .collect()
+}
+
+fn transaction() -> SignedTransaction {
+    serde_json::from_value(json!({
+        "inputs": [
+            {
+                "boxId": "aa".repeat(32),
+                "spendingProof": { "proofBytes": "ab".repeat(56), "extension": { "0": "0e0101" } },
+            },
+            { "boxId": "ab".repeat(32), "spendingProof": { "proofBytes": "", "extension": {} } },
+        ],
+        "dataInputs": [{ "boxId": "bb".repeat(32) }],
+        "outputs": [
+            {
+                "ergoTree": format!("0008cd02{}", "01".repeat(32)),
+                "creationHeight": 1_200_000,
+                "value": 1_000_000_000u64,
+                "assets": [{ "tokenId": "cc".repeat(32), "amount": 5 }],
+                "additionalRegisters": { "R4": "0580897a" },
+            },
+            {
+                "ergoTree": "1806010402d17300",
+                "creationHeight": 1_200_000,
+                "value": 2_000_000,
+            },
+        ],
+    }))
+    .unwrap()
+}
+
+fn generate() -> TestVectors {
+    let mut vectors = TestVectors::new();
+    vectors.add_transaction(&transaction()).unwrap();
+    for network in [NetworkPrefix::Mainnet, NetworkPrefix::Testnet] {
+        vectors.add_address(&ErgoAddress::new(network, AddressType::P2SH, vec![9; 24]).unwrap());
+    }
+    vectors
+}
+
+#[test]
+fn corpus_matches_codecs() {
+    let generated = generate().to_json();
+    if std::env::var_os("UPDATE_VECTORS").is_some() {
+        fs::write(corpus_path(), &generated).unwrap();
+    }
+
+    let corpus = TestVectors::load(&corpus_path()).unwrap();
+    assert_eq!(corpus.verify(), []);
+    assert!(corpus.to_json() == generated, "corpus is out of date; run with UPDATE_VECTORS=1");
+    assert_eq!((corpus.addresses.len(), corpus.trees.len(), corpus.transactions.len()), (4, 2, 1));
+}
+
+#[test]
+fn reports_mismatches() {
+    let mut vectors = generate();
+    vectors.transactions[0].bytes.0.push(0);
+    vectors.addresses[3].network = 0x00;
+
+    let mismatches = vectors.verify();
+    let failed: Vec<_> = mismatches.iter().map(|m| m.vector.as_str()).collect();
+    assert_eq!(failed, ["addresses[3]", "transactions[0]"]);
+    assert_eq!(mismatches[1].reason, "serialization mismatch");
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let json = r#"{ "version": 2, "addresses": [] }"#;
+    assert!(matches!(TestVectors::from_json(json), Err(VectorError::UnsupportedVersion(2))));
+}
@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use hergmes::{
+    address::NetworkPrefix,
+    analytics::voting::{SoftForkPhase, VotingSettings, VotingTracker},
+    chain::extension::{ParameterId, Parameters},
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    types::{Height, ergo::BlockHeader},
+};
+use serde_json::{Value, json};
+
+/// Epochs of 10 blocks: changes need 6 votes and soft-forks 19 votes over 2 epochs.
+const SETTINGS: VotingSettings =
+    VotingSettings { epoch_length: 10, soft_fork_epochs: 2, activation_epochs: 3 };
+
+fn header_json(height: u32, votes: [u8; 3]) -> Value {
+    let mut header = serde_json::from_slice::<Value>(
+        &std::fs::read(format!(
+            "{}/tests/fixtures/node-6.0/last_headers.json",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap(),
+    )
+    .unwrap()[0]
+        .clone();
+    header["height"] = json!(height);
+    header["votes"] = json!(hex::encode(votes));
+    header
+}
+
+fn header(height: u32, votes: [u8; 3]) -> BlockHeader {
+    serde_json::from_value(header_json(height, votes)).unwrap()
+}
+
+/// Votes for a bigger block cost (4) and a smaller storage fee (-1 as `0xff`) or a soft-fork.
+fn votes_at(height: u32) -> [u8; 3] {
+    match height % 10 {
+        0..=6 => [4, 0xff, 120],
+        _ => [4, 0, 120],
+    }
+}
+
+fn soft_fork_parameters(start: u32, collected: u32) -> Parameters {
+    let field = |id: ParameterId, value: u32| {
+        json!([hex::encode([0, id as u8]), hex::encode((value as i32).to_be_bytes())])
+    };
+    let extension = json!({
+        "headerId": "11".repeat(32),
+        "digest": "22".repeat(32),
+        "fields": [
+            field(ParameterId::SoftForkStartingHeight, start),
+            field(ParameterId::SoftForkVotesCollected, collected),
+        ],
+    });
+    serde_json::from_value::<hergmes::chain::extension::Extension>(extension)
+        .unwrap()
+        .parameters()
+        .unwrap()
+}
+
+#[test]
+fn tallies_votes_by_epoch() {
+    let mut tracker = VotingTracker::new(SETTINGS).with_epochs(2);
+    for height in 5..=24 {
+        tracker.apply_header(&header(height, votes_at(height)));
+    }
+    // Replayed headers are ignored.
+    tracker.apply_header(&header(24, votes_at(24)));
+
+    let report = tracker.report();
+    assert_eq!(report.height, Some(Height(24)));
+    let starts: Vec<u32> = report.epochs.iter().map(|e| e.start_height.0).collect();
+    assert_eq!(starts, [10, 20]);
+
+    let full = &report.epochs[0];
+    assert_eq!((full.blocks, full.soft_fork_votes), (10, 10));
+    assert_eq!(full.changes[0].parameter, "maxBlockCost");
+    assert!(full.changes[0].increase && full.changes[0].approved);
+    assert_eq!(full.changes[0].activation_height, 20);
+    let fee = &full.changes[1];
+    assert_eq!((fee.parameter.as_str(), fee.increase, fee.votes), ("storageFeeFactor", false, 7));
+    assert_eq!(fee.threshold, 6);
+    assert!(fee.approved);
+
+    let current = report.current_epoch().unwrap();
+    assert_eq!(current.blocks, 5);
+    assert_eq!((current.changes[0].votes, current.changes[0].projected_votes), (5, 10));
+    assert!(!current.changes[0].approved);
+    assert!((current.changes[0].progress - 5.0 / 6.0).abs() < 1e-9);
+
+    // Votes without a declared start propose a soft-fork starting with the next epoch.
+    let soft_fork = report.soft_fork.unwrap();
+    assert_eq!(soft_fork.phase, SoftForkPhase::Proposed);
+    assert_eq!((soft_fork.starting_height, soft_fork.threshold), (Height(30), 19));
+    assert_eq!(
+        (soft_fork.voting_end_height, soft_fork.activation_height),
+        (Height(50), Height(80))
+    );
+}
+
+#[test]
+fn follows_soft_fork_phases() {
+    let mut tracker = VotingTracker::new(SETTINGS);
+    for height in 20..=23 {
+        tracker.apply_header(&header(height, votes_at(height)));
+    }
+    tracker.set_declared(Height(20), soft_fork_parameters(10, 10));
+    let soft_fork = tracker.report().soft_fork.unwrap();
+    assert_eq!(soft_fork.phase, SoftForkPhase::Voting);
+    assert_eq!((soft_fork.starting_height, soft_fork.votes), (Height(10), 14));
+    assert_eq!(
+        (soft_fork.voting_end_height, soft_fork.activation_height),
+        (Height(30), Height(60))
+    );
+
+    tracker.apply_header(&header(30, [0; 3]));
+    tracker.set_declared(Height(30), soft_fork_parameters(10, 19));
+    assert_eq!(tracker.report().soft_fork.unwrap().phase, SoftForkPhase::Activating);
+    tracker.set_declared(Height(30), soft_fork_parameters(10, 18));
+    assert_eq!(tracker.report().soft_fork.unwrap().phase, SoftForkPhase::Rejected);
+
+    // Parameters of an earlier epoch are not applied to the current one.
+    tracker.apply_header(&header(40, [0; 3]));
+    assert!(tracker.report().soft_fork.is_none());
+}
+
+#[test]
+fn renders_prometheus_metrics() {
+    let mut tracker = VotingTracker::new(SETTINGS);
+    assert_eq!(tracker.report().to_prometheus(), "");
+    tracker.apply_header(&header(20, votes_at(20)));
+    tracker.set_declared(Height(20), soft_fork_parameters(20, 0));
+
+    let metrics = tracker.report().to_prometheus();
+    assert!(metrics.contains("hergmes_voting_epoch_blocks 1\n"));
+    assert!(metrics.contains(
+        "hergmes_voting_parameter_votes{parameter=\"maxBlockCost\",direction=\"increase\"} 1\n"
+    ));
+    assert!(metrics.contains("hergmes_voting_soft_fork_votes 1\n"));
+    assert!(metrics.contains("hergmes_voting_soft_fork_activation_height 70\n"));
+}
+
+/// Serves a chain of headers up to `tip` and an epoch-start extension.
+#[derive(Debug)]
+struct ChainTransport {
+    tip: u32,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl HttpTransport for ChainTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        self.requests.lock().unwrap().push(request.path_and_query());
+        let query = |key: &str| -> u32 {
+            let (_, value) = request.query.iter().find(|(k, _)| k == key).unwrap();
+            value.parse().unwrap()
+        };
+        let body = match request.path.as_str() {
+            "blocks/lastHeaders/1" => json!([header_json(self.tip, votes_at(self.tip))]),
+            "blocks/chainSlice" => (query("fromHeight")..=query("toHeight"))
+                .map(|h| header_json(h, votes_at(h)))
+                .collect(),
+            "blocks/at/20" => json!(["33".repeat(32)]),
+            path if path.ends_with("/extension") => {
+                json!({ "headerId": "33".repeat(32), "digest": "22".repeat(32), "fields": [] })
+            }
+            path => panic!("unexpected request {path}"),
+        };
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+#[tokio::test]
+async fn polls_headers_of_the_tracked_epochs() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let node = NodeClient::with_transport(ChainTransport { tip: 23, requests: requests.clone() });
+    let mut tracker = VotingTracker::new(SETTINGS).with_epochs(2);
+
+    tracker.poll(&node).await.unwrap();
+    assert_eq!(tracker.height(), Some(Height(23)));
+    let report = tracker.report();
+    assert_eq!(report.epochs.len(), 2);
+    assert_eq!(report.epochs[0].start_height, 10);
+    assert_eq!(report.soft_fork.unwrap().phase, SoftForkPhase::Proposed);
+
+    // Nothing new: only the tip is requested.
+    requests.lock().unwrap().clear();
+    tracker.poll(&node).await.unwrap();
+    assert_eq!(*requests.lock().unwrap(), ["blocks/lastHeaders/1"]);
+}
+
+#[test]
+fn picks_settings_by_network() {
+    let testnet = VotingSettings::for_network(NetworkPrefix::Testnet);
+    assert_eq!(testnet.epoch_length, 128);
+    assert_eq!(testnet.change_threshold(), 65);
+    assert_eq!(testnet.epoch_start(Height(1_000)), Height(896));
+    assert_eq!(VotingSettings::for_network(NetworkPrefix::Mainnet), VotingSettings::MAINNET);
+}
@@ -0,0 +1,288 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use hergmes::{
+    address::{AddressType, ErgoAddress, NetworkPrefix},
+    clients::node::{HttpRequest, HttpResponse, HttpTransport, NodeClient, NodeError},
+    clock::ManualClock,
+    intern::ErgoTreeInterner,
+    types::{
+        TimestampMillis,
+        ergo::{SignedTransaction, UnconfirmedTransaction},
+    },
+    wallet::{
+        hd::{ExtendedPublicKey, HdError},
+        locks::{BoxLockManager, LockError},
+        pending::PendingChain,
+        spendable::get_spendable_boxes,
+        sync::{WalletEvent, WalletSync},
+    },
+    watcher::MempoolSnapshot,
+};
+use serde_json::json;
+
+/// BIP32 test vector 2: `m` and `m/0`.
+const MASTER_XPUB: &str = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB";
+const CHILD_XPUB: &str = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH";
+
+#[test]
+fn derives_bip32_public_children() {
+    let master: ExtendedPublicKey = MASTER_XPUB.parse().unwrap();
+    let child: ExtendedPublicKey = CHILD_XPUB.parse().unwrap();
+
+    assert_eq!(master.derive_child(0).unwrap(), child);
+    assert_eq!(master.derive_path(&[0]).unwrap(), child);
+    assert!(matches!(master.derive_child(1 << 31), Err(HdError::Hardened(_))));
+    assert!(
+        MASTER_XPUB
+            .replace('W', "X")
+            .parse::<ExtendedPublicKey>()
+            .is_err()
+    );
+}
+
+#[test]
+fn encodes_and_decodes_addresses() {
+    let encoded = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA";
+    let address: ErgoAddress = encoded.parse().unwrap();
+
+    assert_eq!(address.network(), NetworkPrefix::Mainnet);
+    assert_eq!(address.kind(), AddressType::P2PK);
+    assert_eq!(address.to_string(), encoded);
+
+    let tree = address.ergo_tree().unwrap();
+    assert_eq!(ErgoAddress::from_ergo_tree(NetworkPrefix::Mainnet, &tree.0), address);
+
+    let p2s = ErgoAddress::from_ergo_tree(NetworkPrefix::Testnet, &[0x10, 0x01, 0x04, 0x00]);
+    assert_eq!(p2s.kind(), AddressType::P2S);
+    assert_eq!(p2s.to_string().parse::<ErgoAddress>().unwrap(), p2s);
+
+    assert!(
+        "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vB"
+            .parse::<ErgoAddress>()
+            .is_err()
+    );
+}
+
+/// Serves indexer transaction history keyed by the requested address.
+#[derive(Debug, Default)]
+struct IndexerMock {
+    history: HashMap<String, Vec<serde_json::Value>>,
+}
+
+#[async_trait]
+impl HttpTransport for IndexerMock {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, NodeError> {
+        let address = String::from_utf8(request.body.unwrap_or_default()).unwrap();
+        let items = self.history.get(&address).cloned().unwrap_or_default();
+        let body = json!({ "total": items.len(), "items": items });
+        Ok(HttpResponse { status: 200, body: serde_json::to_vec(&body).unwrap().into() })
+    }
+}
+
+fn utxo(id: u8, tree: &str, value: u64) -> serde_json::Value {
+    json!({
+        "boxId": format!("{id:02x}").repeat(32),
+        "ergoTree": tree,
+        "creationHeight": 1,
+        "value": value,
+        "index": 0,
+        "transactionId": "00".repeat(32),
+    })
+}
+
+fn tx(
+    id: u8,
+    height: u32,
+    inputs: Vec<serde_json::Value>,
+    outputs: Vec<serde_json::Value>,
+) -> serde_json::Value {
+    json!({
+        "id": format!("{id:02x}").repeat(32),
+        "inclusionHeight": height,
+        "inputs": inputs,
+        "outputs": outputs,
+    })
+}
+
+#[tokio::test]
+async fn syncs_watch_only_wallet() {
+    let account: ExtendedPublicKey = MASTER_XPUB.parse().unwrap();
+    let address = |i| {
+        account
+            .derive_path(&[0, i])
+            .unwrap()
+            .address(NetworkPrefix::Mainnet)
+    };
+    let tree = |i| address(i).ergo_tree().unwrap().to_string();
+    let external = "0008cd02".to_string() + &"ee".repeat(33);
+
+    let receive = tx(
+        1,
+        100,
+        vec![utxo(1, &external, 5_000_000_000)],
+        vec![utxo(2, &tree(0), 1_000_000_000), utxo(3, &external, 4_000_000_000)],
+    );
+    let internal = tx(
+        2,
+        101,
+        vec![utxo(2, &tree(0), 1_000_000_000)],
+        vec![utxo(4, &tree(1), 999_000_000), utxo(5, &external, 1_000_000)],
+    );
+
+    let mut mock = IndexerMock::default();
+    mock.history
+        .insert(address(0).to_string(), vec![receive.clone(), internal.clone()]);
+    mock.history.insert(address(1).to_string(), vec![internal]);
+
+    let mut wallet =
+        WalletSync::new(NodeClient::with_transport(mock), account, NetworkPrefix::Mainnet)
+            .gap_limit(2);
+    let events = wallet.sync().await.unwrap();
+
+    assert_eq!(wallet.addresses().len(), 4);
+    assert_eq!(wallet.balance().nano_ergs, 999_000_000);
+    let deltas: Vec<i64> = events
+        .iter()
+        .map(|e| match e {
+            WalletEvent::Payment { nano_ergs, .. } => *nano_ergs,
+        })
+        .collect();
+    assert_eq!(deltas, vec![1_000_000_000, -1_000_000]);
+
+    assert!(wallet.sync().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn spendable_boxes_account_for_mempool() {
+    let address: ErgoAddress = "9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA"
+        .parse()
+        .unwrap();
+    let tree = address.ergo_tree().unwrap().to_string();
+    let external = "0008cd02".to_string() + &"ee".repeat(33);
+    let confirmed = |id| {
+        let mut utxo = utxo(id, &tree, 1_000_000);
+        utxo["inclusionHeight"] = json!(100);
+        utxo
+    };
+    let input = |utxo: serde_json::Value| {
+        let mut input = utxo;
+        input["spendingProof"] = json!({ "proofBytes": "", "extension": {} });
+        input
+    };
+    let pending = |id: u8, inputs: Vec<serde_json::Value>, outputs| -> UnconfirmedTransaction {
+        serde_json::from_value(json!({
+            "id": format!("{id:02x}").repeat(32),
+            "inputs": inputs.into_iter().map(input).collect::<Vec<_>>(),
+            "outputs": outputs,
+        }))
+        .unwrap()
+    };
+
+    let mut mock = IndexerMock::default();
+    mock.history
+        .insert(address.to_string(), vec![confirmed(0x10), confirmed(0x11)]);
+    let node = NodeClient::with_transport(mock);
+
+    // 0x10 is spent into change 0x12; change 0x13 is already spent again by 0x22.
+    let mempool = MempoolSnapshot::new(
+        TimestampMillis(1),
+        vec![
+            pending(
+                0x20,
+                vec![utxo(0x10, &tree, 1_000_000)],
+                vec![utxo(0x12, &tree, 900_000), utxo(0x14, &external, 100_000)],
+            ),
+            pending(
+                0x21,
+                vec![utxo(0x15, &external, 1_000_000)],
+                vec![utxo(0x13, &tree, 1_000_000)],
+            ),
+            pending(
+                0x22,
+                vec![utxo(0x13, &tree, 1_000_000)],
+                vec![utxo(0x16, &external, 1_000_000)],
+            ),
+        ],
+        &ErgoTreeInterner::new(),
+    );
+
+    let spendable = get_spendable_boxes(&node, &address, &mempool)
+        .await
+        .unwrap();
+    let ids: Vec<_> = spendable.iter().map(|b| b.id.0[0]).collect();
+    assert_eq!(ids, [0x11, 0x12]);
+}
+
+#[tokio::test]
+async fn reservations_exclude_boxes_until_released_or_expired() {
+    let ids = [0x10u8, 0x11, 0x12].map(|b| format!("{b:02x}").repeat(32).parse().unwrap());
+    let clock = ManualClock::new(TimestampMillis(0));
+    let locks = BoxLockManager::new(Duration::from_millis(50)).with_clock(Arc::new(clock.clone()));
+
+    let first = locks.try_reserve(&ids[..2]).unwrap();
+    assert!(matches!(locks.try_reserve(&ids[1..]), Err(LockError::Reserved(id)) if id == ids[1]));
+    // A failed reservation takes nothing.
+    assert!(!locks.is_reserved(&ids[2]));
+
+    drop(first);
+    let second = locks.try_reserve(&ids[1..]).unwrap();
+    assert!(locks.is_reserved(&ids[2]));
+    second.keep();
+    assert!(locks.is_reserved(&ids[2]));
+
+    clock.advance(Duration::from_millis(49));
+    assert!(locks.is_reserved(&ids[2]));
+    clock.advance(Duration::from_millis(1));
+    assert!(!locks.is_reserved(&ids[2]));
+    locks.try_reserve(&ids).unwrap();
+}
+
+#[test]
+fn chains_unconfirmed_change_and_drops_evicted_parents() {
+    let tree = "0008cd02".to_string() + &"11".repeat(33);
+    let spend = |input: String, value: u64| -> SignedTransaction {
+        serde_json::from_value(json!({
+            "inputs": [{ "boxId": input, "spendingProof": { "proofBytes": "", "extension": {} } }],
+            "outputs": [{ "ergoTree": tree, "creationHeight": 1, "value": value }],
+        }))
+        .unwrap()
+    };
+    let mut chain = PendingChain::new();
+
+    let change = chain
+        .push(&spend("aa".repeat(32), 3_000_000))
+        .unwrap()
+        .remove(0);
+    let child = chain
+        .push(&spend(change.id.to_string(), 2_000_000))
+        .unwrap()
+        .remove(0);
+    let tree = change.ergo_tree.clone();
+    let spendable = chain.spendable(&tree);
+    assert_eq!(spendable.len(), 1);
+    assert_eq!(spendable[0].id, child.id);
+
+    let seen = |ids: &[&_]| {
+        let transactions = ids
+            .iter()
+            .map(|id| {
+                serde_json::from_value(json!({ "id": id, "inputs": [], "outputs": [] })).unwrap()
+            })
+            .collect::<Vec<UnconfirmedTransaction>>();
+        MempoolSnapshot::new(TimestampMillis(1), transactions, &ErgoTreeInterner::new())
+    };
+    assert!(
+        chain
+            .sync(&seen(&[&change.transaction_id, &child.transaction_id]))
+            .is_empty()
+    );
+
+    // The parent is evicted: the child spends a box that no longer exists.
+    let mut dropped = chain.sync(&seen(&[&child.transaction_id]));
+    dropped.sort();
+    let mut expected = vec![change.transaction_id.clone(), child.transaction_id.clone()];
+    expected.sort();
+    assert_eq!(dropped, expected);
+    assert!(chain.is_empty());
+}